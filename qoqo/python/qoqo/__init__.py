@@ -26,6 +26,7 @@ qoqo is the HQS python package to represent quantum circuits.
     measurements
     devices
     noise_models
+    transpiler
 """
 
 from .qoqo import *
@@ -33,6 +34,7 @@ from .operations import *  # type: ignore
 from .measurements import *  # type: ignore
 from .noise_models import *  # type: ignore
 from .devices import *  # type: ignore
+from .transpiler import *  # type: ignore
 
 __license__ = (
     "Apache-2.0 for linked dependencies see qoqo/LICENSE_FOR_BINARY_DISTRIBUTION"
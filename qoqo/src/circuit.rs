@@ -19,12 +19,16 @@
 //!
 
 use crate::{QoqoError, QOQO_VERSION};
+use num_complex::Complex64;
+use numpy::{PyArray2, ToPyArray};
 use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyByteArray;
 use roqoqo::prelude::*;
+use roqoqo::qasm::QasmVersion;
 use roqoqo::{Circuit, OperationIterator, ROQOQO_VERSION};
 use std::collections::HashSet;
+use std::str::FromStr;
 
 use crate::operations::{convert_operation_to_pyobject, convert_pyany_to_operation};
 
@@ -663,6 +667,92 @@ impl CircuitWrapper {
     fn number_of_qubits(&self) -> usize {
         self.internal.number_of_qubits()
     }
+
+    /// Return the unitary matrix of the Circuit.
+    ///
+    /// Qubit i corresponds to bit i of the row and column index (qubit 0 is the least significant qubit).
+    /// PRAGMAs that only structure the Circuit (PragmaStopParallelBlock, PragmaSleep and decomposition blocks) are ignored.
+    ///
+    /// Returns:
+    ///     np.ndarray: The unitary matrix of the Circuit.
+    ///
+    /// Raises:
+    ///     ValueError: The Circuit contains non-unitary operations or symbolic parameters.
+    pub fn unitary_matrix(&self) -> PyResult<Py<PyArray2<Complex64>>> {
+        let matrix = self.internal.unitary_matrix().map_err(|err| {
+            PyValueError::new_err(format!(
+                "Could not compute unitary matrix of Circuit: {err:?}"
+            ))
+        })?;
+        Python::attach(|py| -> PyResult<Py<PyArray2<Complex64>>> {
+            Ok(matrix.to_pyarray(py).unbind())
+        })
+    }
+
+    /// Check whether the Circuit implements the same unitary transformation as another Circuit.
+    ///
+    /// Args:
+    ///     other (Circuit): The Circuit that is compared to this Circuit.
+    ///     tolerance (float): The largest absolute difference of matrix elements for which the Circuits are considered equivalent.
+    ///     up_to_global_phase (bool): Whether Circuits that only differ by a global phase are considered equivalent.
+    ///
+    /// Returns:
+    ///     bool: Whether the Circuits are equivalent.
+    ///
+    /// Raises:
+    ///     TypeError: Other cannot be converted to Circuit.
+    ///     ValueError: One of the Circuits contains non-unitary operations or symbolic parameters.
+    #[pyo3(signature = (other, tolerance = 1e-10, up_to_global_phase = true))]
+    pub fn is_equivalent(
+        &self,
+        other: &Bound<PyAny>,
+        tolerance: f64,
+        up_to_global_phase: bool,
+    ) -> PyResult<bool> {
+        let other = convert_into_circuit(other).map_err(|err| {
+            PyTypeError::new_err(format!("Other cannot be converted to Circuit {err:?}"))
+        })?;
+        self.internal
+            .is_equivalent(&other, tolerance, up_to_global_phase)
+            .map_err(|err| PyValueError::new_err(format!("Could not compare Circuits: {err:?}")))
+    }
+
+    /// Convert the Circuit to an OpenQASM program.
+    ///
+    /// Args:
+    ///     version (str): The OpenQASM version of the output, "2.0" or "3.0".
+    ///
+    /// Returns:
+    ///     str: The OpenQASM program.
+    ///
+    /// Raises:
+    ///     ValueError: Unknown version or the Circuit contains operations without equivalent in OpenQASM.
+    #[pyo3(signature = (version = "3.0"))]
+    pub fn to_qasm(&self, version: &str) -> PyResult<String> {
+        let version = QasmVersion::from_str(version)
+            .map_err(|err| PyValueError::new_err(format!("{err}")))?;
+        self.internal.to_qasm(version).map_err(|err| {
+            PyValueError::new_err(format!("Could not convert Circuit to OpenQASM: {err}"))
+        })
+    }
+
+    /// Create a Circuit from an OpenQASM 2.0 or 3.0 program.
+    ///
+    /// Args:
+    ///     input (str): The OpenQASM program.
+    ///
+    /// Returns:
+    ///     Circuit: The Circuit of the program.
+    ///
+    /// Raises:
+    ///     ValueError: The program cannot be parsed, the message contains the line and column of the problem.
+    #[staticmethod]
+    pub fn from_qasm(input: &str) -> PyResult<Self> {
+        Ok(Self {
+            internal: Circuit::from_qasm(input)
+                .map_err(|err| PyValueError::new_err(format!("{err}")))?,
+        })
+    }
 }
 
 /// Convert generic python object to [roqoqo::Circuit].
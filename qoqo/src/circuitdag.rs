@@ -111,6 +111,31 @@ impl CircuitDagWrapper {
         })
     }
 
+    /// Create a CircuitDag from a given Circuit using gate-level commutation rules.
+    ///
+    /// Operations acting on the same qubits are only connected when they do not commute,
+    /// so that the CircuitDag exposes the partial order of the circuit.
+    /// Diagonal gates, Pauli gates and rotations about commuting Pauli strings, diagonal gates on the
+    /// control qubits of controlled gates and gates with commuting numeric unitary matrices commute.
+    ///
+    /// Args:
+    ///     circuit (Circuit): The Circuit to build the new CircuitDag from.
+    ///
+    /// Returns:
+    ///     self: The new CircuitDag.
+    ///
+    /// Raises:
+    ///     TypeError: The Python Object cannot be converted to Circuit.
+    #[pyo3(text_signature = "(circuit)")]
+    pub fn from_circuit_with_commutation(&self, circuit: &Bound<PyAny>) -> PyResult<Self> {
+        let circuit = crate::convert_into_circuit(circuit).map_err(|x| {
+            PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+        })?;
+        Ok(Self {
+            internal: CircuitDag::from_circuit_with_commutation(circuit),
+        })
+    }
+
     /// Transforms the CircuitDag into a Circuit.
     ///
     #[pyo3(text_signature = "($self)")]
@@ -40,6 +40,9 @@ pub use quantum_program::{convert_into_quantum_program, QuantumProgramWrapper};
 
 pub mod noise_models;
 
+#[cfg(feature = "circuitdag")]
+pub mod transpiler;
+
 #[cfg(feature = "circuitdag")]
 mod circuitdag;
 #[cfg(feature = "circuitdag")]
@@ -160,6 +163,7 @@ pub(crate) fn get_operator<'py>(
 ///     measurements
 ///     devices
 ///     noise_models
+///     transpiler
 ///     available_gates_hqslang
 ///
 #[pymodule]
@@ -198,6 +202,8 @@ fn qoqo(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
     module.add_wrapped(wrapper3)?;
     let wrapper4 = wrap_pymodule!(noise_models::noise_models);
     module.add_wrapped(wrapper4)?;
+    #[cfg(feature = "circuitdag")]
+    module.add_wrapped(wrap_pymodule!(transpiler::transpiler))?;
     // Adding nice imports corresponding to maturin example
     let system = PyModule::import(_py, "sys")?;
     let binding = system.getattr("modules")?;
@@ -206,6 +212,8 @@ fn qoqo(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
     system_modules.set_item("qoqo.measurements", module.getattr("measurements")?)?;
     system_modules.set_item("qoqo.devices", module.getattr("devices")?)?;
     system_modules.set_item("qoqo.noise_models", module.getattr("noise_models")?)?;
+    #[cfg(feature = "circuitdag")]
+    system_modules.set_item("qoqo.transpiler", module.getattr("transpiler")?)?;
 
     Ok(())
 }
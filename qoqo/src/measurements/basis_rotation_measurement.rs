@@ -13,17 +13,90 @@
 //! Qoqo PauliZ product measurement.
 
 use super::PauliZProductInputWrapper;
+use crate::noise_models::ImperfectReadoutModelWrapper;
 use crate::CircuitWrapper;
 use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyByteArray;
-use roqoqo::measurements::PauliZProduct;
+use roqoqo::measurements::{PauliZProduct, ReadoutErrorMitigation};
+use roqoqo::noise_models::NoiseModel;
 use roqoqo::prelude::*;
 use roqoqo::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
 use roqoqo::Circuit;
 #[cfg(feature = "json_schema")]
 use roqoqo::ROQOQO_VERSION;
 use std::collections::HashMap;
+use struqture_py::spins::{PauliHamiltonianWrapper, PauliOperatorWrapper};
+
+/// Converts the optional constant circuit argument of the measurement constructors.
+pub(crate) fn constant_circuit_from_pyany(
+    constant_circuit: Option<&Bound<PyAny>>,
+) -> PyResult<Option<Circuit>> {
+    constant_circuit
+        .map(|c| {
+            CircuitWrapper::from_pyany(c).map_err(|err| {
+                PyTypeError::new_err(format!(
+                    "`constant_circuit` argument is not None or a qoqo Circuit: {err}"
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Expectation values, standard errors and covariances returned by `evaluate_with_errors`.
+pub(crate) type EvaluatedWithErrors = (
+    HashMap<String, f64>,
+    HashMap<String, f64>,
+    HashMap<(String, String), f64>,
+);
+
+/// Converts bit registers given as booleans or integers.
+pub(crate) fn bit_registers_from_pyany(
+    input_bit_registers: &Bound<PyAny>,
+) -> PyResult<HashMap<String, BitOutputRegister>> {
+    let mut bit_registers: HashMap<String, BitOutputRegister> = HashMap::new();
+    let bit_registers_bool: PyResult<HashMap<String, Vec<Vec<bool>>>> =
+        input_bit_registers.extract::<HashMap<String, BitOutputRegister>>();
+    if let Ok(try_downcast) = bit_registers_bool {
+        bit_registers = try_downcast
+    } else {
+        let tmp_bit_registers =
+            input_bit_registers.extract::<HashMap<String, Vec<Vec<usize>>>>()?;
+        for (name, output_reg) in tmp_bit_registers {
+            let mut tmp_output_reg: Vec<Vec<bool>> = Vec::with_capacity(output_reg.len());
+            for reg in output_reg {
+                tmp_output_reg.push(reg.into_iter().map(|x| !matches!(x, 0)).collect());
+            }
+            bit_registers.insert(name, tmp_output_reg);
+        }
+    }
+    Ok(bit_registers)
+}
+
+/// Converts the readout error arguments of the evaluation with readout error mitigation.
+fn readout_error_mitigation_from_arguments(
+    readout_fidelities: Option<HashMap<usize, f64>>,
+    imperfect_readout_model: Option<&Bound<PyAny>>,
+    confusion_matrix_inversion: bool,
+) -> PyResult<ReadoutErrorMitigation> {
+    match (readout_fidelities, imperfect_readout_model) {
+        (Some(fidelities), None) => Ok(ReadoutErrorMitigation::Fidelities(fidelities)),
+        (None, Some(model)) => match ImperfectReadoutModelWrapper::from_pyany(model) {
+            Ok(NoiseModel::ImperfectReadoutModel(model)) => {
+                Ok(ReadoutErrorMitigation::ImperfectReadoutModel {
+                    model,
+                    confusion_matrix_inversion,
+                })
+            }
+            _ => Err(PyTypeError::new_err(
+                "`imperfect_readout_model` argument cannot be converted to ImperfectReadoutModel",
+            )),
+        },
+        _ => Err(PyValueError::new_err(
+            "Exactly one of `readout_fidelities` and `imperfect_readout_model` has to be given",
+        )),
+    }
+}
 
 #[pyclass(from_py_object, name = "PauliZProduct", module = "qoqo.measurements")]
 #[derive(Clone, Debug)]
@@ -95,6 +168,104 @@ impl PauliZProductWrapper {
         })
     }
 
+    /// Create a PauliZProduct measurement of the expectation value of a Hamiltonian.
+    ///
+    /// The terms of the Hamiltonian are grouped into sets of qubit-wise commuting Pauli products.
+    /// For each set a basis rotation circuit is created that is read out with a PragmaRepeatedMeasurement
+    /// into the bit register `ro_{index of set}`.
+    ///
+    /// Args:
+    ///     hamiltonian (struqture_py.spins.PauliHamiltonian): The Hamiltonian whose expectation value is measured.
+    ///     name (str): The name of the measured expectation value.
+    ///     number_qubits (int): The number of qubits in the measured circuits.
+    ///     number_measurements (int): The number of projective measurements for each basis rotation circuit.
+    ///     constant_circuit (Optional[Circuit]): The constant Circuit that is executed before each basis rotation circuit.
+    ///
+    /// Returns:
+    ///     PauliZProduct: The measurement of the Hamiltonian expectation value.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to PauliHamiltonian or Circuit.
+    ///     ValueError: The Hamiltonian has symbolic coefficients or acts on more than number_qubits qubits.
+    #[staticmethod]
+    #[pyo3(signature=(hamiltonian, name, number_qubits, number_measurements, constant_circuit=None))]
+    pub fn from_pauli_hamiltonian(
+        hamiltonian: &Bound<PyAny>,
+        name: String,
+        number_qubits: usize,
+        number_measurements: usize,
+        constant_circuit: Option<&Bound<PyAny>>,
+    ) -> PyResult<Self> {
+        let hamiltonian = match PauliHamiltonianWrapper::from_pyany(hamiltonian) {
+            Ok(x) => x,
+            Err(_) => {
+                PauliHamiltonianWrapper::from_pyany_struqture_1(hamiltonian).map_err(|err| {
+                    PyTypeError::new_err(format!(
+                        "`hamiltonian` argument is not a struqture PauliHamiltonian: {err:?}"
+                    ))
+                })?
+            }
+        };
+        let constant_circuit = constant_circuit_from_pyany(constant_circuit)?;
+        Ok(Self {
+            internal: PauliZProduct::from_pauli_hamiltonian(
+                &hamiltonian,
+                name,
+                number_qubits,
+                number_measurements,
+                constant_circuit,
+            )
+            .map_err(|err| PyValueError::new_err(format!("{err:?}")))?,
+        })
+    }
+
+    /// Create a PauliZProduct measurement of the expectation value of a hermitian PauliOperator.
+    ///
+    /// See `from_pauli_hamiltonian` for the construction of the measurement.
+    ///
+    /// Args:
+    ///     operator (struqture_py.spins.PauliOperator): The hermitian operator whose expectation value is measured.
+    ///     name (str): The name of the measured expectation value.
+    ///     number_qubits (int): The number of qubits in the measured circuits.
+    ///     number_measurements (int): The number of projective measurements for each basis rotation circuit.
+    ///     constant_circuit (Optional[Circuit]): The constant Circuit that is executed before each basis rotation circuit.
+    ///
+    /// Returns:
+    ///     PauliZProduct: The measurement of the operator expectation value.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to PauliOperator or Circuit.
+    ///     ValueError: The operator is not hermitian, has symbolic coefficients or acts on more than number_qubits qubits.
+    #[staticmethod]
+    #[pyo3(signature=(operator, name, number_qubits, number_measurements, constant_circuit=None))]
+    pub fn from_pauli_operator(
+        operator: &Bound<PyAny>,
+        name: String,
+        number_qubits: usize,
+        number_measurements: usize,
+        constant_circuit: Option<&Bound<PyAny>>,
+    ) -> PyResult<Self> {
+        let operator = match PauliOperatorWrapper::from_pyany(operator) {
+            Ok(x) => x,
+            Err(_) => PauliOperatorWrapper::from_pyany_struqture_1(operator).map_err(|err| {
+                PyTypeError::new_err(format!(
+                    "`operator` argument is not a struqture PauliOperator: {err:?}"
+                ))
+            })?,
+        };
+        let constant_circuit = constant_circuit_from_pyany(constant_circuit)?;
+        Ok(Self {
+            internal: PauliZProduct::from_pauli_operator(
+                &operator,
+                name,
+                number_qubits,
+                number_measurements,
+                constant_circuit,
+            )
+            .map_err(|err| PyValueError::new_err(format!("{err:?}")))?,
+        })
+    }
+
     /// Execute the PauliZ product measurement.
     ///
     /// Args:
@@ -114,27 +285,7 @@ impl PauliZProductWrapper {
         float_registers: HashMap<String, FloatOutputRegister>,
         complex_registers: HashMap<String, ComplexOutputRegister>,
     ) -> PyResult<Option<HashMap<String, f64>>> {
-        let mut bit_registers: HashMap<String, BitOutputRegister> = HashMap::new();
-        let bit_registers_bool: PyResult<HashMap<String, Vec<Vec<bool>>>> =
-            input_bit_registers.extract::<HashMap<String, BitOutputRegister>>();
-        if let Ok(try_downcast) = bit_registers_bool {
-            bit_registers = try_downcast
-        } else {
-            let tmp_bit_registers =
-                input_bit_registers.extract::<HashMap<String, Vec<Vec<usize>>>>()?;
-            for (name, output_reg) in tmp_bit_registers {
-                let mut tmp_output_reg: Vec<Vec<bool>> = Vec::with_capacity(output_reg.len());
-                for reg in output_reg {
-                    tmp_output_reg.push(reg.into_iter().map(|x| !matches!(x, 0)).collect());
-                }
-                bit_registers.insert(name, tmp_output_reg);
-                // if t.is_some() {
-                //     return Err(PyRuntimeError::new_err(
-                //         "Unexpected repetition of key in bit_registers",
-                //     ));
-                // }
-            }
-        }
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
         self.internal
             .evaluate(bit_registers, float_registers, complex_registers)
             .map_err(|x| {
@@ -144,6 +295,160 @@ impl PauliZProductWrapper {
             })
     }
 
+    /// Execute the PauliZ product measurement and estimate the statistical errors from the single shots.
+    ///
+    /// Args:
+    ///     input_bit_registers (Dict[str, Union[List[List[int]], List[List[bool]]]]): The classical bit registers with the register name as key
+    ///     float_registers (Dict[str, List[List[float]]]): The classical float registers as a dictionary with the register name as key
+    ///     complex_registers (Dict[str, List[List[complex]]]): The classical complex registers as a dictionary with the register name as key
+    ///
+    /// Returns:
+    ///     Optional[Tuple[Dict[str, float], Dict[str, float], Dict[Tuple[str, str], float]]]: The evaluated expectation values, their standard errors and the covariances between all pairs of expectation values.
+    ///
+    /// Raises:
+    ///     RuntimeError: Error evaluating PauliZ product measurement.
+    pub fn evaluate_with_errors(
+        &mut self,
+        input_bit_registers: &Bound<PyAny>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> PyResult<Option<EvaluatedWithErrors>> {
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
+        let evaluated = self
+            .internal
+            .evaluate_with_errors(bit_registers, float_registers, complex_registers)
+            .map_err(|x| {
+                PyRuntimeError::new_err(format!(
+                    "Error evaluating PauliZ product measurement {x:?}"
+                ))
+            })?;
+        Ok(evaluated.map(|evaluated| {
+            (
+                evaluated.values,
+                evaluated.standard_errors,
+                evaluated.covariances,
+            )
+        }))
+    }
+
+    /// Execute the PauliZ product measurement and mitigate readout errors.
+    ///
+    /// The readout errors are given either by per-qubit measurement fidelities or by an ImperfectReadoutModel.
+    /// With fidelities, the expectation values of Pauli products are divided by the product of the fidelities
+    /// of the involved qubits. Qubits without a fidelity are measured perfectly.
+    ///
+    /// Args:
+    ///     input_bit_registers (Dict[str, Union[List[List[int]], List[List[bool]]]]): The classical bit registers with the register name as key
+    ///     readout_fidelities (Optional[Dict[int, float]]): The measurement fidelities 1 - p(0->1) - p(1->0) of the qubits.
+    ///     imperfect_readout_model (Optional[ImperfectReadoutModel]): The readout error probabilities of the qubits.
+    ///     confusion_matrix_inversion (bool): Whether the confusion matrix of each qubit of the ImperfectReadoutModel
+    ///                                        is inverted for every shot instead of only rescaling the expectation values.
+    ///
+    /// Returns:
+    ///     Optional[Dict[str, float]]: The evaluated measurement.
+    ///
+    /// Raises:
+    ///     TypeError: imperfect_readout_model is not an ImperfectReadoutModel.
+    ///     ValueError: Not exactly one of readout_fidelities and imperfect_readout_model is given.
+    ///     RuntimeError: Error evaluating PauliZ product measurement.
+    #[pyo3(signature = (input_bit_registers, readout_fidelities = None, imperfect_readout_model = None, confusion_matrix_inversion = false))]
+    pub fn evaluate_with_readout_error_mitigation(
+        &mut self,
+        input_bit_registers: &Bound<PyAny>,
+        readout_fidelities: Option<HashMap<usize, f64>>,
+        imperfect_readout_model: Option<&Bound<PyAny>>,
+        confusion_matrix_inversion: bool,
+    ) -> PyResult<Option<HashMap<String, f64>>> {
+        let mitigation = readout_error_mitigation_from_arguments(
+            readout_fidelities,
+            imperfect_readout_model,
+            confusion_matrix_inversion,
+        )?;
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
+        self.internal
+            .evaluate_with_readout_error_mitigation(bit_registers, &mitigation)
+            .map_err(|x| {
+                PyRuntimeError::new_err(format!(
+                    "Error evaluating PauliZ product measurement {x:?}"
+                ))
+            })
+    }
+
+    /// Execute the PauliZ product measurement, mitigate readout errors and estimate the statistical errors.
+    ///
+    /// Args:
+    ///     input_bit_registers (Dict[str, Union[List[List[int]], List[List[bool]]]]): The classical bit registers with the register name as key
+    ///     readout_fidelities (Optional[Dict[int, float]]): The measurement fidelities 1 - p(0->1) - p(1->0) of the qubits.
+    ///     imperfect_readout_model (Optional[ImperfectReadoutModel]): The readout error probabilities of the qubits.
+    ///     confusion_matrix_inversion (bool): Whether the confusion matrix of each qubit of the ImperfectReadoutModel
+    ///                                        is inverted for every shot instead of only rescaling the expectation values.
+    ///
+    /// Returns:
+    ///     Optional[Tuple[Dict[str, float], Dict[str, float], Dict[Tuple[str, str], float]]]: The evaluated expectation values, their standard errors and the covariances between all pairs of expectation values.
+    ///
+    /// Raises:
+    ///     TypeError: imperfect_readout_model is not an ImperfectReadoutModel.
+    ///     ValueError: Not exactly one of readout_fidelities and imperfect_readout_model is given.
+    ///     RuntimeError: Error evaluating PauliZ product measurement.
+    #[pyo3(signature = (input_bit_registers, readout_fidelities = None, imperfect_readout_model = None, confusion_matrix_inversion = false))]
+    pub fn evaluate_with_errors_and_readout_error_mitigation(
+        &mut self,
+        input_bit_registers: &Bound<PyAny>,
+        readout_fidelities: Option<HashMap<usize, f64>>,
+        imperfect_readout_model: Option<&Bound<PyAny>>,
+        confusion_matrix_inversion: bool,
+    ) -> PyResult<Option<EvaluatedWithErrors>> {
+        let mitigation = readout_error_mitigation_from_arguments(
+            readout_fidelities,
+            imperfect_readout_model,
+            confusion_matrix_inversion,
+        )?;
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
+        let evaluated = self
+            .internal
+            .evaluate_with_errors_and_readout_error_mitigation(bit_registers, &mitigation)
+            .map_err(|x| {
+                PyRuntimeError::new_err(format!(
+                    "Error evaluating PauliZ product measurement {x:?}"
+                ))
+            })?;
+        Ok(evaluated.map(|evaluated| {
+            (
+                evaluated.values,
+                evaluated.standard_errors,
+                evaluated.covariances,
+            )
+        }))
+    }
+
+    /// Return a copy of the measurement with a total shot budget distributed over the circuits.
+    ///
+    /// The numbers of measurements are chosen proportional to the sum of the absolute coefficients of the
+    /// (non-identity) Pauli products measured by each circuit, minimising the variance of the linear expectation value.
+    /// Every measured circuit keeps at least one measurement.
+    ///
+    /// Args:
+    ///     expectation_value (str): The name of the linear expectation value whose variance is minimised.
+    ///     total_number_measurements (int): The total number of measurements distributed over all circuits.
+    ///
+    /// Returns:
+    ///     PauliZProduct: The measurement with the new numbers of measurements.
+    ///
+    /// Raises:
+    ///     ValueError: The expectation value is not linear, does not exist or the budget is smaller than the number of measured circuits.
+    pub fn allocate_shots(
+        &self,
+        expectation_value: &str,
+        total_number_measurements: usize,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            internal: self
+                .internal
+                .allocate_shots(expectation_value, total_number_measurements)
+                .map_err(|err| PyValueError::new_err(format!("{err:?}")))?,
+        })
+    }
+
     /// Return the collection of quantum circuits for the separate basis rotations.
     ///
     /// Returns:
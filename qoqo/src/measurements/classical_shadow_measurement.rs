@@ -0,0 +1,454 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Qoqo classical shadow measurement.
+
+use super::basis_rotation_measurement::{
+    bit_registers_from_pyany, constant_circuit_from_pyany, EvaluatedWithErrors,
+};
+use super::ClassicalShadowInputWrapper;
+use crate::CircuitWrapper;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyByteArray;
+use roqoqo::measurements::ClassicalShadow;
+use roqoqo::prelude::*;
+use roqoqo::registers::{ComplexOutputRegister, FloatOutputRegister};
+use roqoqo::Circuit;
+#[cfg(feature = "json_schema")]
+use roqoqo::ROQOQO_VERSION;
+use std::collections::HashMap;
+
+#[pyclass(from_py_object, name = "ClassicalShadow", module = "qoqo.measurements")]
+#[derive(Clone, Debug)]
+/// Collected information for executing a classical shadow measurement.
+///
+/// Every circuit measures all qubits in a (random) product basis. Expectation values of Pauli observables
+/// and fidelities with target states are estimated from the single-shot snapshots with a median-of-means estimator.
+///
+/// Args:
+///     constant_circuit (Optional[Circuit]): The constant Circuit that is executed before each Circuit in circuits.
+///     circuits (List[Circuit]): The collection of quantum circuits for the separate basis rotations.
+///     input (ClassicalShadowInput): The additional input information required for measurement.
+///
+/// Returns:
+///     ClassicalShadow: The new classical shadow measurement.
+pub struct ClassicalShadowWrapper {
+    /// Internal storage of [roqoqo::ClassicalShadow].
+    pub internal: ClassicalShadow,
+}
+
+#[pymethods]
+impl ClassicalShadowWrapper {
+    /// Create a new ClassicalShadow measurement.
+    ///
+    /// Args:
+    ///     constant_circuit (Optional[Circuit]): The constant Circuit that is executed before each Circuit in circuits.
+    ///     circuits (List[Circuit]): The collection of quantum circuits for the separate basis rotations.
+    ///     input (ClassicalShadowInput): The additional input information required for measurement.
+    ///
+    /// Returns:
+    ///     ClassicalShadow: The new classical shadow measurement.
+    #[new]
+    #[pyo3(signature=(constant_circuit, circuits, input))]
+    pub fn new(
+        constant_circuit: Option<&Bound<PyAny>>,
+        circuits: Vec<Py<PyAny>>,
+        input: &Bound<PyAny>,
+    ) -> PyResult<Self> {
+        let input = ClassicalShadowInputWrapper::from_pyany(input).map_err(|err| {
+            PyTypeError::new_err(format!(
+                "`input` argument is not a qoqo ClassicalShadowInput: {err}"
+            ))
+        })?;
+        let constant_circuit = constant_circuit_from_pyany(constant_circuit)?;
+        let circuits = Python::attach(|py| -> PyResult<Vec<Circuit>> {
+            circuits
+                .iter()
+                .map(|c| {
+                    CircuitWrapper::from_pyany(c.bind(py)).map_err(|err| {
+                        PyTypeError::new_err(format!(
+                            "`circuits` argument is not a list of qoqo Circuits: {err}"
+                        ))
+                    })
+                })
+                .collect()
+        })?;
+        Ok(Self {
+            internal: ClassicalShadow {
+                constant_circuit,
+                circuits,
+                input,
+            },
+        })
+    }
+
+    /// Create a ClassicalShadow measurement with randomly drawn measurement bases.
+    ///
+    /// Circuit `i` rotates every qubit into its random basis (Hadamard for X, RotateX(pi/2) for Y)
+    /// and reads out all qubits with a PragmaRepeatedMeasurement into the bit register `ro_i`.
+    /// The drawn bases replace the measurement bases of the input.
+    ///
+    /// Args:
+    ///     input (ClassicalShadowInput): The input defining the estimated observables and fidelities.
+    ///     number_circuits (int): The number of random basis rotation circuits.
+    ///     number_measurements (int): The number of projective measurements for each basis rotation circuit.
+    ///     constant_circuit (Optional[Circuit]): The constant Circuit that is executed before each basis rotation circuit.
+    ///     seed (Optional[int]): The seed of the random number generator drawing the bases.
+    ///
+    /// Returns:
+    ///     ClassicalShadow: The new classical shadow measurement.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to ClassicalShadowInput or Circuit.
+    #[staticmethod]
+    #[pyo3(signature=(input, number_circuits, number_measurements, constant_circuit=None, seed=None))]
+    pub fn new_random(
+        input: &Bound<PyAny>,
+        number_circuits: usize,
+        number_measurements: usize,
+        constant_circuit: Option<&Bound<PyAny>>,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let input = ClassicalShadowInputWrapper::from_pyany(input).map_err(|err| {
+            PyTypeError::new_err(format!(
+                "`input` argument is not a qoqo ClassicalShadowInput: {err}"
+            ))
+        })?;
+        let constant_circuit = constant_circuit_from_pyany(constant_circuit)?;
+        Ok(Self {
+            internal: ClassicalShadow::new_random(
+                constant_circuit,
+                input,
+                number_circuits,
+                number_measurements,
+                seed,
+            ),
+        })
+    }
+
+    /// Execute the classical shadow measurement.
+    ///
+    /// Args:
+    ///     input_bit_registers (Dict[str, Union[List[List[int]], List[List[bool]]]]): The classical bit registers with the register name as key
+    ///     float_registers (Dict[str, List[List[float]]]): The classical float registers as a dictionary with the register name as key
+    ///     complex_registers (Dict[str, List[List[complex]]]): The classical complex registers as a dictionary with the register name as key
+    ///
+    /// Returns:
+    ///     Optional[Dict[str, float]]: The evaluated measurement.
+    ///
+    /// Raises:
+    ///     RuntimeError: Error evaluating classical shadow measurement.
+    pub fn evaluate(
+        &mut self,
+        input_bit_registers: &Bound<PyAny>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> PyResult<Option<HashMap<String, f64>>> {
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
+        self.internal
+            .evaluate(bit_registers, float_registers, complex_registers)
+            .map_err(|x| {
+                PyRuntimeError::new_err(format!(
+                    "Error evaluating classical shadow measurement {x:?}"
+                ))
+            })
+    }
+
+    /// Execute the classical shadow measurement and estimate the statistical errors from the snapshots.
+    ///
+    /// Args:
+    ///     input_bit_registers (Dict[str, Union[List[List[int]], List[List[bool]]]]): The classical bit registers with the register name as key
+    ///     float_registers (Dict[str, List[List[float]]]): The classical float registers as a dictionary with the register name as key
+    ///     complex_registers (Dict[str, List[List[complex]]]): The classical complex registers as a dictionary with the register name as key
+    ///
+    /// Returns:
+    ///     Optional[Tuple[Dict[str, float], Dict[str, float], Dict[Tuple[str, str], float]]]: The evaluated expectation values, their standard errors and the covariances between all pairs of expectation values.
+    ///
+    /// Raises:
+    ///     RuntimeError: Error evaluating classical shadow measurement.
+    pub fn evaluate_with_errors(
+        &mut self,
+        input_bit_registers: &Bound<PyAny>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> PyResult<Option<EvaluatedWithErrors>> {
+        let bit_registers = bit_registers_from_pyany(input_bit_registers)?;
+        let evaluated = self
+            .internal
+            .evaluate_with_errors(bit_registers, float_registers, complex_registers)
+            .map_err(|x| {
+                PyRuntimeError::new_err(format!(
+                    "Error evaluating classical shadow measurement {x:?}"
+                ))
+            })?;
+        Ok(evaluated.map(|evaluated| {
+            (
+                evaluated.values,
+                evaluated.standard_errors,
+                evaluated.covariances,
+            )
+        }))
+    }
+
+    /// Return the collection of quantum circuits for the separate basis rotations.
+    ///
+    /// Returns:
+    ///     List[Circuit]: The quantum circuits.
+    pub fn circuits(&self) -> Vec<CircuitWrapper> {
+        self.internal
+            .circuits()
+            .map(|c| CircuitWrapper {
+                internal: c.clone(),
+            })
+            .collect()
+    }
+
+    /// Return constant circuit that is executed before any Circuit in circuits.
+    ///
+    /// Returns:
+    ///     Optional[Circuit]: The constant Circuit (None if not defined).
+    pub fn constant_circuit(&self) -> Option<CircuitWrapper> {
+        self.internal
+            .constant_circuit()
+            .clone()
+            .map(|c| CircuitWrapper { internal: c })
+    }
+
+    /// Returns the measurement input data defining how to construct expectation values from measurements.
+    ///
+    /// Returns:
+    ///     ClassicalShadowInput: The measurment input of ClassicalShadow.
+    pub fn input(&self) -> ClassicalShadowInputWrapper {
+        ClassicalShadowInputWrapper {
+            internal: self.internal.input.clone(),
+        }
+    }
+
+    /// Returns the type of the measurement in string form.
+    ///
+    /// Returns:
+    ///    str: The type of the measurement.
+    pub fn measurement_type(&self) -> &'static str {
+        "ClassicalShadow"
+    }
+
+    /// Return copy of Measurement with symbolic parameters replaced.
+    ///
+    /// Args:
+    ///     substituted_parameters (Dict[str, float]): The dictionary containing the substitutions to use in the Circuit.
+    ///
+    /// Raises:
+    ///     RuntimeError: Error substituting symbolic parameters.
+    pub fn substitute_parameters(
+        &self,
+        substituted_parameters: HashMap<String, f64>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            internal: self
+                .internal
+                .substitute_parameters(substituted_parameters)
+                .map_err(|x| {
+                    PyRuntimeError::new_err(format!("Error substituting symbolic parameters {x:?}"))
+                })?,
+        })
+    }
+
+    /// Return the name of the measurement and the bincode representation of the Measurement using the [bincode] crate.
+    ///
+    /// Returns:
+    ///     (str, ByteArray): Name and serialized measurement (in [bincode] form).
+    ///
+    /// Raises:
+    ///     ValueError: Cannot serialize Measurement to bytes.
+    pub fn _internal_to_bincode(&self) -> PyResult<(&'static str, Py<PyByteArray>)> {
+        let serialized =
+            bincode::serde::encode_to_vec(&self.internal, bincode::config::legacy())
+                .map_err(|_| PyValueError::new_err("Cannot serialize ClassicalShadow to bytes"))?;
+        let b: Py<PyByteArray> = Python::attach(|py| -> Py<PyByteArray> {
+            PyByteArray::new(py, &serialized[..]).into()
+        });
+        Ok(("ClassicalShadow", b))
+    }
+
+    /// Return the bincode representation of the ClassicalShadow using the [bincode] crate.
+    ///
+    /// Returns:
+    ///     ByteArray: The serialized ClassicalShadow (in [bincode] form).
+    ///
+    /// Raises:
+    ///     ValueError: Cannot serialize ClassicalShadow to bytes.
+    pub fn to_bincode(&self) -> PyResult<Py<PyByteArray>> {
+        let serialized =
+            bincode::serde::encode_to_vec(&self.internal, bincode::config::legacy())
+                .map_err(|_| PyValueError::new_err("Cannot serialize ClassicalShadow to bytes"))?;
+        let b: Py<PyByteArray> = Python::attach(|py| -> Py<PyByteArray> {
+            PyByteArray::new(py, &serialized[..]).into()
+        });
+        Ok(b)
+    }
+
+    /// Convert the bincode representation of the ClassicalShadow to a ClassicalShadow using the [bincode] crate.
+    ///
+    /// Args:
+    ///     input (ByteArray): The serialized ClassicalShadow (in [bincode] form).
+    ///
+    /// Returns:
+    ///     ClassicalShadow: The deserialized ClassicalShadow.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to byte array.
+    ///     ValueError: Input cannot be deserialized to ClassicalShadow.
+    #[staticmethod]
+    pub fn from_bincode(input: &Bound<PyAny>) -> PyResult<Self> {
+        let bytes = input
+            .extract::<Vec<u8>>()
+            .map_err(|_| PyTypeError::new_err("Input cannot be converted to byte array"))?;
+
+        Ok(Self {
+            internal: bincode::serde::decode_from_slice(&bytes[..], bincode::config::legacy())
+                .map_err(|_| {
+                    PyValueError::new_err("Input cannot be deserialized to ClassicalShadow")
+                })?
+                .0,
+        })
+    }
+
+    /// Serialize the ClassicalShadow measurement to json form.
+    ///
+    /// Returns:
+    ///     str: The serialized ClassicalShadow measurement.
+    ///
+    /// Raises:
+    ///     RuntimeError: Unexpected error serializing ClassicalShadow.
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.internal)
+            .map_err(|_| PyRuntimeError::new_err("Unexpected error serializing ClassicalShadow"))
+    }
+
+    /// Deserialize the ClassicalShadow measurement from json form.
+    ///
+    /// Returns:
+    ///     ClassicalShadow: The deserialized ClassicalShadow Measurement.
+    ///
+    /// Raises:
+    ///     ValueError: Cannot deserialize string to ClassicalShadow.
+    #[staticmethod]
+    pub fn from_json(json_string: &str) -> PyResult<Self> {
+        Ok(Self {
+            internal: serde_json::from_str(json_string).map_err(|_| {
+                PyValueError::new_err("Cannot deserialize string to ClassicalShadow")
+            })?,
+        })
+    }
+
+    /// Implement __repr__ magic method
+    pub fn __repr__(&self) -> String {
+        format!("{:?}", self.internal)
+    }
+
+    /// Return a copy of the Object (copy here produces a deepcopy).
+    pub fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Return a deep copy of the Object.
+    pub fn __deepcopy__(&self, _memodict: &Bound<PyAny>) -> Self {
+        self.clone()
+    }
+
+    /// Return the __richcmp__ magic method to perform rich comparison operations on ClassicalShadow.
+    ///
+    /// Args:
+    ///     other: The object to compare self to.
+    ///     op: Type of comparison.
+    ///
+    /// Returns:
+    ///     Whether the two operations compared evaluated to True or False
+    ///
+    /// Raises:
+    ///     NotImplementedError: Other comparison not implemented
+    fn __richcmp__(
+        &self,
+        other: ClassicalShadowWrapper,
+        op: pyo3::class::basic::CompareOp,
+    ) -> PyResult<bool> {
+        match op {
+            pyo3::class::basic::CompareOp::Eq => Ok(self.internal == other.internal),
+            pyo3::class::basic::CompareOp::Ne => Ok(self.internal != other.internal),
+            _ => Err(pyo3::exceptions::PyNotImplementedError::new_err(
+                "Other comparison not implemented",
+            )),
+        }
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Return the JsonSchema for the json serialisation of the class.
+    ///
+    /// Returns:
+    ///     str: The json schema serialized to json
+    #[staticmethod]
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(ClassicalShadow);
+        serde_json::to_string_pretty(&schema).expect("Unexpected failure to serialize schema")
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Returns the current version of the qoqo library .
+    ///
+    /// Returns:
+    ///     str: The current version of the library.
+    #[staticmethod]
+    pub fn current_version() -> String {
+        ROQOQO_VERSION.to_string()
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Return the minimum version of qoqo that supports this object.
+    ///
+    /// Returns:
+    ///     str: The minimum version of the qoqo library to deserialize this object.
+    pub fn min_supported_version(&self) -> String {
+        let min_version: (u32, u32, u32) =
+            ClassicalShadow::minimum_supported_roqoqo_version(&self.internal);
+        format!("{}.{}.{}", min_version.0, min_version.1, min_version.2)
+    }
+}
+
+impl ClassicalShadowWrapper {
+    /// Extracts a ClassicalShadow from a ClassicalShadowWrapper python object.
+    ///
+    /// When working with qoqo and other rust based python packages compiled separately
+    /// a downcast will not detect that two ClassicalShadowWrapper objects are compatible.
+    /// Provides a custom function to convert qoqo ClassicalShadows between different Python packages.
+    ///
+    /// # Arguments:
+    ///
+    /// `input` - The Python object that should be casted to a [roqoqo::ClassicalShadow]
+    pub fn from_pyany(input: &Bound<PyAny>) -> PyResult<ClassicalShadow> {
+        if let Ok(try_downcast) = input.extract::<ClassicalShadowWrapper>() {
+            Ok(try_downcast.internal)
+        } else {
+            let get_bytes = input.call_method0("to_bincode").map_err(|_| {
+                PyTypeError::new_err("Python object cannot be converted to qoqo ClassicalShadow: Cast to binary representation failed".to_string())
+            })?;
+            let bytes = get_bytes.extract::<Vec<u8>>().map_err(|_| {
+                PyTypeError::new_err("Python object cannot be converted to qoqo ClassicalShadow: Cast to binary representation failed".to_string())
+            })?;
+            bincode::serde::decode_from_slice(&bytes[..], bincode::config::legacy()).map_err(|err| {
+                    PyTypeError::new_err(format!(
+                    "Python object cannot be converted to qoqo ClassicalShadow: Deserialization failed: {err}"
+                ))
+                }).map(|(deserialized, _)| deserialized)
+        }
+    }
+}
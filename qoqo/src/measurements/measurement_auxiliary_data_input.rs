@@ -12,16 +12,20 @@
 
 //! Qoqo measurement inputs
 
+use crate::noise_models::ImperfectReadoutModelWrapper;
 use num_complex::Complex64;
 use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyByteArray;
 use roqoqo::measurements::{
-    CheatedInput, CheatedPauliZProductInput, PauliProductMask, PauliZProductInput,
+    CheatedInput, CheatedPauliZProductInput, ClassicalShadowInput, PauliProductMask,
+    PauliZProductInput, ReadoutErrorMitigation,
 };
+use roqoqo::noise_models::NoiseModel;
 #[cfg(feature = "json_schema")]
 use roqoqo::{operations::SupportedVersion, ROQOQO_VERSION};
 use std::collections::HashMap;
+use struqture_py::spins::PauliHamiltonianWrapper;
 
 #[pyclass(
     from_py_object,
@@ -140,6 +144,61 @@ impl PauliZProductInputWrapper {
             })
     }
 
+    /// Set per-qubit measurement fidelities used to mitigate readout errors.
+    ///
+    /// The expectation values of Pauli products are divided by the product of the fidelities
+    /// of the involved qubits. Qubits without a fidelity are measured perfectly.
+    ///
+    /// Args:
+    ///     fidelities (Dict[int, float]): The measurement fidelities 1 - p(0->1) - p(1->0) of the qubits.
+    ///
+    /// Raises:
+    ///     ValueError: A fidelity is not in the range (0, 1].
+    pub fn set_readout_fidelities(&mut self, fidelities: HashMap<usize, f64>) -> PyResult<()> {
+        self.internal
+            .set_readout_error_mitigation(Some(ReadoutErrorMitigation::Fidelities(fidelities)))
+            .map_err(|x| PyValueError::new_err(format!("Failed to set readout fidelities {x:?}")))
+    }
+
+    /// Set an ImperfectReadoutModel used to mitigate readout errors.
+    ///
+    /// Args:
+    ///     model (ImperfectReadoutModel): The readout error probabilities of the qubits.
+    ///     confusion_matrix_inversion (bool): Whether the confusion matrix of each qubit is inverted for every shot
+    ///                                        instead of only rescaling the expectation values.
+    ///
+    /// Raises:
+    ///     TypeError: Model is not an ImperfectReadoutModel.
+    ///     ValueError: The readout errors of a qubit can not be mitigated.
+    #[pyo3(signature = (model, confusion_matrix_inversion = false))]
+    pub fn set_imperfect_readout_model(
+        &mut self,
+        model: &Bound<PyAny>,
+        confusion_matrix_inversion: bool,
+    ) -> PyResult<()> {
+        let model = match ImperfectReadoutModelWrapper::from_pyany(model) {
+            Ok(NoiseModel::ImperfectReadoutModel(model)) => model,
+            _ => {
+                return Err(PyTypeError::new_err(
+                    "Model cannot be converted to ImperfectReadoutModel",
+                ))
+            }
+        };
+        self.internal
+            .set_readout_error_mitigation(Some(ReadoutErrorMitigation::ImperfectReadoutModel {
+                model,
+                confusion_matrix_inversion,
+            }))
+            .map_err(|x| {
+                PyValueError::new_err(format!("Failed to set imperfect readout model {x:?}"))
+            })
+    }
+
+    /// Remove the mitigation of readout errors.
+    pub fn remove_readout_error_mitigation(&mut self) {
+        self.internal.readout_error_mitigation = None;
+    }
+
     /// Serialize the PauliZProductInput to json form.
     ///
     /// Returns:
@@ -799,3 +858,262 @@ impl CheatedInputWrapper {
         }
     }
 }
+
+#[pyclass(
+    from_py_object,
+    name = "ClassicalShadowInput",
+    module = "qoqo.measurements"
+)]
+#[derive(Clone, Debug)]
+/// Provides Necessary Information to run a [roqoqo::measurements::ClassicalShadow] measurement.
+///
+/// The ClassicalShadowInput stores the number of measured qubits, the number of groups used in the
+/// median-of-means estimation, the measurement basis of every circuit and the observables and target
+/// states whose expectation values and fidelities are estimated.
+///
+/// Args:
+///     number_qubits (int): The number of qubits in the ClassicalShadow measurement.
+///     number_groups (int): The number of groups of snapshots in the median-of-means estimation.
+///
+/// Returns:
+///     ClassicalShadowInput: The new instance of ClassicalShadowInput without measurement bases, observables and target states.
+pub struct ClassicalShadowInputWrapper {
+    /// Internal storage of [roqoqo::ClassicalShadowInput].
+    pub internal: ClassicalShadowInput,
+}
+
+#[pymethods]
+impl ClassicalShadowInputWrapper {
+    /// Create a new ClassicalShadowInput.
+    ///
+    /// Args:
+    ///     number_qubits (int): The number of qubits in the ClassicalShadow measurement.
+    ///     number_groups (int): The number of groups of snapshots in the median-of-means estimation.
+    ///
+    /// Returns:
+    ///     ClassicalShadowInput: The new instance of ClassicalShadowInput without measurement bases, observables and target states.
+    #[new]
+    pub fn new(number_qubits: usize, number_groups: usize) -> Self {
+        Self {
+            internal: ClassicalShadowInput::new(number_qubits, number_groups),
+        }
+    }
+
+    /// Add a Pauli observable whose expectation value is estimated.
+    ///
+    /// Args:
+    ///     name (str): The name of the expectation value.
+    ///     observable (struqture_py.spins.PauliHamiltonian): The observable.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to PauliHamiltonian.
+    ///     RuntimeError: Failed to add observable.
+    pub fn add_observable(&mut self, name: String, observable: &Bound<PyAny>) -> PyResult<()> {
+        let observable = match PauliHamiltonianWrapper::from_pyany(observable) {
+            Ok(x) => x,
+            Err(_) => {
+                PauliHamiltonianWrapper::from_pyany_struqture_1(observable).map_err(|err| {
+                    PyTypeError::new_err(format!(
+                        "`observable` argument is not a struqture PauliHamiltonian: {err:?}"
+                    ))
+                })?
+            }
+        };
+        self.internal
+            .add_observable(name, observable)
+            .map_err(|x| PyRuntimeError::new_err(format!("Failed to add observable {x:?}")))
+    }
+
+    /// Add a target state whose fidelity with the measured state is estimated.
+    ///
+    /// The target state is normalized before it is stored.
+    ///
+    /// Args:
+    ///     name (str): The name of the fidelity.
+    ///     target_state (List[complex]): The statevector of the target state.
+    ///
+    /// Raises:
+    ///     RuntimeError: Failed to add fidelity.
+    pub fn add_fidelity(&mut self, name: String, target_state: Vec<Complex64>) -> PyResult<()> {
+        self.internal
+            .add_fidelity(name, target_state)
+            .map_err(|x| PyRuntimeError::new_err(format!("Failed to add fidelity {x:?}")))
+    }
+
+    /// Return the measurement bases of the circuits.
+    ///
+    /// Qubits not contained in a basis are measured in the Z-basis.
+    ///
+    /// Returns:
+    ///     List[str]: The measurement bases in PauliProduct string form.
+    pub fn measurement_bases(&self) -> Vec<String> {
+        self.internal
+            .measurement_bases
+            .iter()
+            .map(|basis| basis.to_string())
+            .collect()
+    }
+
+    /// Serialize the ClassicalShadowInput to json form.
+    ///
+    /// Returns:
+    ///     str: The serialized ClassicalShadowInput.
+    ///
+    /// Raises:
+    ///     PyRuntimeError: Unexpected error serializing ClassicalShadowInput.
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.internal).map_err(|_| {
+            PyRuntimeError::new_err("Unexpected error serializing ClassicalShadowInput")
+        })
+    }
+
+    /// Deserialize the ClassicalShadowInput from json form.
+    ///
+    /// Returns:
+    ///     ClassicalShadowInput: The deserialized ClassicalShadowInput.
+    ///
+    /// Raises:
+    ///     PyRuntimeError: Cannot deserialize string to ClassicalShadowInput.
+    #[staticmethod]
+    pub fn from_json(json_string: &str) -> PyResult<Self> {
+        Ok(Self {
+            internal: serde_json::from_str(json_string).map_err(|_| {
+                PyValueError::new_err("Cannot deserialize string to ClassicalShadowInput")
+            })?,
+        })
+    }
+
+    /// Return the bincode representation of the ClassicalShadowInput using the [bincode] crate.
+    ///
+    /// Returns:
+    ///     ByteArray: The serialized ClassicalShadowInput (in [bincode] form).
+    ///
+    /// Raises:
+    ///     ValueError: Cannot serialize ClassicalShadowInput to bytes.
+    pub fn to_bincode(&self) -> PyResult<Py<PyByteArray>> {
+        let serialized = bincode::serde::encode_to_vec(&self.internal, bincode::config::legacy())
+            .map_err(|_| {
+            PyValueError::new_err("Cannot serialize ClassicalShadowInput to bytes")
+        })?;
+        let b: Py<PyByteArray> = Python::attach(|py| -> Py<PyByteArray> {
+            PyByteArray::new(py, &serialized[..]).into()
+        });
+        Ok(b)
+    }
+
+    /// Convert the bincode representation of the ClassicalShadowInput to a ClassicalShadowInput using the [bincode] crate.
+    ///
+    /// Args:
+    ///     input (ByteArray): The serialized ClassicalShadowInput (in [bincode] form).
+    ///
+    /// Returns:
+    ///     ClassicalShadowInput: The deserialized ClassicalShadowInput.
+    ///
+    /// Raises:
+    ///     TypeError: Input cannot be converted to byte array.
+    ///     ValueError: Input cannot be deserialized to ClassicalShadowInput.
+    #[staticmethod]
+    pub fn from_bincode(input: &Bound<PyAny>) -> PyResult<Self> {
+        let bytes = input
+            .extract::<Vec<u8>>()
+            .map_err(|_| PyTypeError::new_err("Input cannot be converted to byte array"))?;
+
+        Ok(Self {
+            internal: bincode::serde::decode_from_slice(&bytes[..], bincode::config::legacy())
+                .map_err(|_| {
+                    PyValueError::new_err("Input cannot be deserialized to ClassicalShadowInput")
+                })?
+                .0,
+        })
+    }
+
+    /// Implement __repr__ magic method
+    pub fn __repr__(&self) -> String {
+        format!("{:?}", self.internal)
+    }
+
+    /// Return a copy of the Object (copy here produces a deepcopy).
+    pub fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Return a deep copy of the Object.
+    pub fn __deepcopy__(&self, _memodict: &Bound<PyAny>) -> Self {
+        self.clone()
+    }
+
+    fn __richcmp__(
+        &self,
+        other: ClassicalShadowInputWrapper,
+        op: pyo3::class::basic::CompareOp,
+    ) -> PyResult<bool> {
+        match op {
+            pyo3::class::basic::CompareOp::Eq => Ok(self.internal == other.internal),
+            pyo3::class::basic::CompareOp::Ne => Ok(self.internal != other.internal),
+            _ => Err(pyo3::exceptions::PyNotImplementedError::new_err(
+                "Other comparison not implemented",
+            )),
+        }
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Return the JsonSchema for the json serialisation of the class.
+    ///
+    /// Returns:
+    ///     str: The json schema serialized to json
+    #[staticmethod]
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(ClassicalShadowInput);
+        serde_json::to_string_pretty(&schema).expect("Unexpected failure to serialize schema")
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Returns the current version of the qoqo library .
+    ///
+    /// Returns:
+    ///     str: The current version of the library.
+    #[staticmethod]
+    pub fn current_version() -> String {
+        ROQOQO_VERSION.to_string()
+    }
+
+    #[cfg(feature = "json_schema")]
+    /// Return the minimum version of qoqo that supports this object.
+    ///
+    /// Returns:
+    ///     str: The minimum version of the qoqo library to deserialize this object.
+    pub fn min_supported_version(&self) -> String {
+        let min_version: (u32, u32, u32) =
+            ClassicalShadowInput::minimum_supported_roqoqo_version(&self.internal);
+        format!("{}.{}.{}", min_version.0, min_version.1, min_version.2)
+    }
+}
+
+impl ClassicalShadowInputWrapper {
+    /// Extracts a ClassicalShadowInput from a ClassicalShadowInputWrapper python object.
+    ///
+    /// When working with qoqo and other rust based python packages compiled separately
+    /// a downcast will not detect that two ClassicalShadowInputWrapper objects are compatible.
+    /// Provides a custom function to convert qoqo ClassicalShadowInputs between different Python packages.
+    ///
+    /// # Arguments:
+    ///
+    /// `input` - The Python object that should be casted to a [roqoqo::ClassicalShadowInput]
+    pub fn from_pyany(input: &Bound<PyAny>) -> PyResult<ClassicalShadowInput> {
+        if let Ok(try_downcast) = input.extract::<ClassicalShadowInputWrapper>() {
+            Ok(try_downcast.internal)
+        } else {
+            let get_bytes = input.call_method0("to_bincode").map_err(|_| {
+                PyTypeError::new_err("Python object cannot be converted to qoqo ClassicalShadowInput: Cast to binary representation failed".to_string())
+            })?;
+            let bytes = get_bytes.extract::<Vec<u8>>().map_err(|_| {
+                PyTypeError::new_err("Python object cannot be converted to qoqo ClassicalShadowInput: Cast to binary representation failed".to_string())
+            })?;
+            bincode::serde::decode_from_slice(&bytes[..], bincode::config::legacy()).map_err(|err| {
+                    PyTypeError::new_err(format!(
+                    "Python object cannot be converted to qoqo ClassicalShadowInput: Deserialization failed: {err}"
+                ))
+                }).map(|(deserialized, _)| deserialized)
+        }
+    }
+}
@@ -15,7 +15,8 @@
 use pyo3::prelude::*;
 mod measurement_auxiliary_data_input;
 pub use measurement_auxiliary_data_input::{
-    CheatedInputWrapper, CheatedPauliZProductInputWrapper, PauliZProductInputWrapper,
+    CheatedInputWrapper, CheatedPauliZProductInputWrapper, ClassicalShadowInputWrapper,
+    PauliZProductInputWrapper,
 };
 mod basis_rotation_measurement;
 pub use basis_rotation_measurement::PauliZProductWrapper;
@@ -25,6 +26,8 @@ mod cheated_measurement;
 pub use cheated_measurement::CheatedWrapper;
 mod classical_register_measurement;
 pub use classical_register_measurement::ClassicalRegisterWrapper;
+mod classical_shadow_measurement;
+pub use classical_shadow_measurement::ClassicalShadowWrapper;
 
 /// Measurements
 ///     
@@ -39,6 +42,8 @@ pub use classical_register_measurement::ClassicalRegisterWrapper;
 ///     DecoherenceOnIdleModel
 ///     Cheated
 ///     ClassicalRegister
+///     ClassicalShadowInput
+///     ClassicalShadow
 #[pymodule]
 pub fn measurements(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<PauliZProductInputWrapper>()?;
@@ -48,6 +53,8 @@ pub fn measurements(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<CheatedPauliZProductWrapper>()?;
     m.add_class::<CheatedWrapper>()?;
     m.add_class::<ClassicalRegisterWrapper>()?;
+    m.add_class::<ClassicalShadowInputWrapper>()?;
+    m.add_class::<ClassicalShadowWrapper>()?;
 
     Ok(())
 }
@@ -13,17 +13,26 @@
 use std::collections::HashMap;
 
 use crate::measurements::{
-    CheatedPauliZProductWrapper, CheatedWrapper, ClassicalRegisterWrapper, PauliZProductWrapper,
+    CheatedPauliZProductWrapper, CheatedWrapper, ClassicalRegisterWrapper, ClassicalShadowWrapper,
+    PauliZProductWrapper,
 };
-use crate::{QoqoError, QOQO_VERSION};
+use crate::{CircuitWrapper, QoqoError, QOQO_VERSION};
+use ndarray::Array2;
+use numpy::{PyArray2, PyReadonlyArray2, ToPyArray};
 use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyByteArray;
-use roqoqo::measurements::Measure;
+use roqoqo::backends::{EvaluatingBackend, RegisterResult};
+use roqoqo::measurements::{ClassicalRegister, Measure, MeasureExpectationValues};
+use roqoqo::operations::Operation;
 #[cfg(feature = "json_schema")]
 use roqoqo::operations::SupportedVersion;
-use roqoqo::QuantumProgram;
+use roqoqo::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister, Registers};
 use roqoqo::ROQOQO_VERSION;
+use roqoqo::{Circuit, QuantumProgram, RoqoqoBackendError};
+
+/// Jacobian returned by `run_gradient` and the names of the expectation values in order of its rows.
+type Jacobian = (Py<PyArray2<f64>>, Vec<String>);
 
 /// Represents a quantum program evaluating measurements based on a one or more free float parameters.
 ///
@@ -110,6 +119,16 @@ impl QuantumProgramWrapper {
                 },
             });
         }
+        // The bincode form of a ClassicalRegister is a prefix of the ClassicalShadow form,
+        // so the ClassicalShadow is tried first
+        if let Ok(try_downcast) = ClassicalShadowWrapper::from_pyany(measurement) {
+            return Ok(Self {
+                internal: QuantumProgram::ClassicalShadow {
+                    measurement: try_downcast,
+                    input_parameter_names,
+                },
+            });
+        }
         if let Ok(try_downcast) = ClassicalRegisterWrapper::from_pyany(measurement) {
             return Ok(Self {
                 internal: QuantumProgram::ClassicalRegister {
@@ -128,7 +147,7 @@ impl QuantumProgramWrapper {
     ///
     /// Returns:
     ///     PyObject corresponding to the qoqo measurement type of the QuantumProgram,
-    ///     i.e. PauliZProduct, CheatedPauliZProduct, Cheated or ClassicalRegister.
+    ///     i.e. PauliZProduct, CheatedPauliZProduct, Cheated, ClassicalRegister or ClassicalShadow.
     pub fn measurement<'py>(&'py self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
         match self.internal.clone() {
             QuantumProgram::PauliZProduct {
@@ -191,6 +210,21 @@ impl QuantumProgramWrapper {
                     .into_pyobject(py)
                     .map(|bound| bound.as_any().to_owned())
             }
+            QuantumProgram::ClassicalShadow {
+                measurement,
+                input_parameter_names: _,
+            } => {
+                let pyref: Py<ClassicalShadowWrapper> = Py::new(
+                    py,
+                    ClassicalShadowWrapper {
+                        internal: measurement.clone(),
+                    },
+                )
+                .unwrap();
+                pyref
+                    .into_pyobject(py)
+                    .map(|bound| bound.as_any().to_owned())
+            }
             _ => panic!("Unknown type of QuantumProgram"),
         }
         .map_err(|_| PyValueError::new_err("Couldn't convert the measurement to a pyobject."))
@@ -218,6 +252,10 @@ impl QuantumProgramWrapper {
                 measurement: _,
                 input_parameter_names,
             } => input_parameter_names,
+            QuantumProgram::ClassicalShadow {
+                measurement: _,
+                input_parameter_names,
+            } => input_parameter_names,
             _ => panic!("Unknown type of QuantumProgram"),
         }
     }
@@ -258,10 +296,133 @@ impl QuantumProgramWrapper {
                 ).map_err(|err| PyRuntimeError::new_err(format!("Applying parameters failed {err:?}")))?;
                     backend.call_method1("run_measurement", (CheatedWrapper{internal: substituted_measurement}, )).map(|bound| bound.into())
               }
+            QuantumProgram::ClassicalShadow{measurement, input_parameter_names } => {
+                if parameters.len() != input_parameter_names.len() { return Err(PyValueError::new_err( format!("Wrong number of parameters {} parameters expected {} parameters given", input_parameter_names.len(), parameters.len())))};
+                let substituted_parameters: HashMap<String, f64> = input_parameter_names.iter().zip(parameters.iter()).map(|(key, value)| (key.clone(), *value)).collect();
+                let substituted_measurement = measurement.substitute_parameters(
+                    substituted_parameters
+                ).map_err(|err| PyRuntimeError::new_err(format!("Applying parameters failed {err:?}")))?;
+                // The snapshots are evaluated locally so that any backend able to run a ClassicalRegister can be used
+                let registers = ClassicalRegister{constant_circuit: substituted_measurement.constant_circuit.clone(), circuits: substituted_measurement.circuits.clone()};
+                let (bit_registers, float_registers, complex_registers): (HashMap<String, BitOutputRegister>, HashMap<String, FloatOutputRegister>, HashMap<String, ComplexOutputRegister>) = backend.call_method1("run_measurement_registers", (ClassicalRegisterWrapper{internal: registers}, ))?.extract()?;
+                let evaluated = substituted_measurement.evaluate(bit_registers, float_registers, complex_registers).map_err(|err| PyRuntimeError::new_err(format!("Error evaluating classical shadow measurement {err:?}")))?;
+                evaluated.into_pyobject(backend.py()).map(|bound| bound.into_any().unbind())
+              }
             _ => Err(PyTypeError::new_err("A quantum programm returning classical registeres cannot be executed by `run` use `run_registers` instead".to_string()))
         }
     }
 
+    /// Runs the QuantumProgram and returns the Jacobian of the expectation values.
+    ///
+    /// The derivatives are evaluated with the parameter-shift rule. For every rotation angle depending on the
+    /// input parameters the program is run twice with the angle shifted by +pi/2 and -pi/2, the derivatives
+    /// with respect to the input parameters follow with the chain rule.
+    /// The input parameters may only enter the rotation angle theta of RotateX, RotateY, RotateZ, RotateXY,
+    /// RotateAroundSphericalAxis, PhaseShiftState0, PhaseShiftState1, ControlledPhaseShift,
+    /// ControlledControlledPhaseShift, PhaseShiftedControlledPhase, VariableMSXX, MultiQubitMS and MultiQubitZZ
+    /// gates, as an affine function of every input parameter.
+    ///
+    /// Args:
+    ///     backend (Backend): The backend the program is executed on.
+    ///     parameters (Optional[List[float]]): List of float  parameters of the function call in order of `input_parameter_names`
+    ///
+    /// Returns:
+    ///     Optional[Tuple[np.ndarray, List[str]]]: The Jacobian with one row per expectation value and one column per input parameter in order of `input_parameter_names`, and the names of the expectation values in order of the rows.
+    ///
+    /// Raises:
+    ///     ValueError: The gradient cannot be evaluated for the QuantumProgram.
+    #[pyo3(signature = (backend, parameters=None))]
+    pub fn run_gradient(
+        &self,
+        backend: &Bound<PyAny>,
+        parameters: Option<Vec<f64>>,
+    ) -> PyResult<Option<Jacobian>> {
+        let py = backend.py();
+        let shifted = self
+            .internal
+            .parameter_shift_programs(&parameters.unwrap_or_default())
+            .map_err(|err| PyValueError::new_err(format!("{err:?}")))?;
+        let mut results: Vec<HashMap<String, f64>> = Vec::with_capacity(shifted.programs.len());
+        for program in shifted.programs.iter() {
+            let result = QuantumProgramWrapper {
+                internal: program.clone(),
+            }
+            .run(backend, None)?;
+            match result.extract::<Option<HashMap<String, f64>>>(py)? {
+                Some(result) => results.push(result),
+                None => return Ok(None),
+            }
+        }
+        let jacobian = shifted
+            .jacobian(&results)
+            .map_err(|err| PyRuntimeError::new_err(format!("{err:?}")))?;
+        let mut names: Vec<String> = jacobian.keys().cloned().collect();
+        names.sort();
+        let number_parameters = shifted.weights.first().map_or(0, |weights| weights.len());
+        let matrix = Array2::from_shape_fn((names.len(), number_parameters), |(row, column)| {
+            jacobian[&names[row]][column]
+        });
+        Ok(Some((matrix.to_pyarray(py).unbind(), names)))
+    }
+
+    /// Runs the QuantumProgram for a batch of parameter sets and returns the expectation values.
+    ///
+    /// Every row of `parameters` is one set of parameters passed in the same order as the parameters
+    /// listed in `input_parameter_names`. The parameters are substituted once per set and the circuits of
+    /// all sets are submitted together to the `run_circuit_batch` method of the backend, taking a list of
+    /// circuits and returning a list of output registers. The measurements are evaluated with the combined
+    /// output registers. Backends without `run_circuit_batch` run every parameter set in the same way as `run`,
+    /// backends without `run_circuit_batch` and `run_measurement` run every circuit with `run_circuit`.
+    ///
+    /// Args:
+    ///     backend (Backend): The backend the program is executed on.
+    ///     parameters (Union[np.ndarray, List[List[float]]]): Two-dimensional array of float parameters, one row per function call.
+    ///
+    /// Returns:
+    ///     List[Optional[Dict[str, float]]]: The expectation values for every parameter set.
+    ///
+    /// Raises:
+    ///     TypeError: Parameters could not be converted to a two-dimensional float array.
+    ///     RuntimeError: Wrong number of parameters in one of the parameter sets or running the circuits failed.
+    pub fn run_batch(
+        &self,
+        backend: &Bound<PyAny>,
+        parameters: &Bound<PyAny>,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let parameters: Vec<Vec<f64>> = match parameters.extract::<PyReadonlyArray2<f64>>() {
+            Ok(array) => array
+                .as_array()
+                .outer_iter()
+                .map(|row| row.to_vec())
+                .collect(),
+            Err(_) => parameters.extract().map_err(|_| {
+                PyTypeError::new_err(
+                    "Parameters could not be converted to a two-dimensional float array",
+                )
+            })?,
+        };
+        if !backend.hasattr("run_circuit_batch").unwrap_or(false)
+            && backend.hasattr("run_measurement").unwrap_or(false)
+        {
+            return parameters
+                .into_iter()
+                .map(|row| self.run(backend, Some(row)))
+                .collect();
+        }
+        let results = self
+            .internal
+            .run_batch(PythonBackend { backend }, &parameters)
+            .map_err(|err| PyRuntimeError::new_err(format!("{err}")))?;
+        results
+            .into_iter()
+            .map(|result| {
+                result
+                    .into_pyobject(backend.py())
+                    .map(|bound| bound.into_any().unbind())
+            })
+            .collect()
+    }
+
     /// Runs the QuantumProgram and returns the classical registers of the quantum program.
     ///
     /// Runs the quantum programm for a given set of parameters passed in the same order as the parameters
@@ -293,6 +454,77 @@ impl QuantumProgramWrapper {
         }
     }
 
+    /// Runs the QuantumProgram with named parameters and returns expectation values.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Args:
+    ///     backend (Backend): The backend the program is executed on.
+    ///     parameters (Dict[str, float]): The float value of every input parameter by name.
+    ///
+    /// Raises:
+    ///     ValueError: A name in parameters is unknown or an input parameter has no value.
+    pub fn run_named(
+        &self,
+        backend: &Bound<PyAny>,
+        parameters: HashMap<String, f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let parameters = self
+            .internal
+            .ordered_parameters(&parameters)
+            .map_err(|err| PyValueError::new_err(format!("{err}")))?;
+        self.run(backend, Some(parameters))
+    }
+
+    /// Runs the QuantumProgram with named parameters and returns the classical registers of the quantum program.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Args:
+    ///     backend (Backend): The backend the program is executed on.
+    ///     parameters (Dict[str, float]): The float value of every input parameter by name.
+    ///
+    /// Raises:
+    ///     ValueError: A name in parameters is unknown or an input parameter has no value.
+    pub fn run_registers_named(
+        &self,
+        backend: &Bound<PyAny>,
+        parameters: HashMap<String, f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let parameters = self
+            .internal
+            .ordered_parameters(&parameters)
+            .map_err(|err| PyValueError::new_err(format!("{err}")))?;
+        self.run_registers(backend, Some(parameters))
+    }
+
+    /// Binds a subset of the input parameters to fixed values.
+    ///
+    /// The bound parameters are added as InputSymbolic definitions to every circuit of the measurement
+    /// and removed from `input_parameter_names`. The remaining input parameters keep their order.
+    ///
+    /// Args:
+    ///     parameters (Dict[str, float]): The float values of the input parameters that are bound by name.
+    ///
+    /// Returns:
+    ///     QuantumProgram: The QuantumProgram with the reduced list of input parameters.
+    ///
+    /// Raises:
+    ///     ValueError: A name in parameters is not an input parameter of the QuantumProgram.
+    pub fn bind_parameters(
+        &self,
+        parameters: HashMap<String, f64>,
+    ) -> PyResult<QuantumProgramWrapper> {
+        Ok(QuantumProgramWrapper {
+            internal: self
+                .internal
+                .bind_parameters(&parameters)
+                .map_err(|err| PyValueError::new_err(format!("{err}")))?,
+        })
+    }
+
     /// Return a copy of the QuantumProgram (copy here produces a deepcopy).
     ///
     /// Returns:
@@ -469,6 +701,51 @@ impl QuantumProgramWrapper {
     }
 }
 
+/// Runs circuits with the `run_circuit` and `run_circuit_batch` methods of a python backend.
+struct PythonBackend<'a, 'py> {
+    /// The python backend.
+    backend: &'a Bound<'py, PyAny>,
+}
+
+impl EvaluatingBackend for PythonBackend<'_, '_> {
+    fn run_circuit_iterator<'b>(
+        &self,
+        circuit: impl Iterator<Item = &'b Operation>,
+    ) -> RegisterResult {
+        let circuit: Circuit = circuit.cloned().collect();
+        self.backend
+            .call_method1("run_circuit", (CircuitWrapper { internal: circuit },))
+            .and_then(|registers| registers.extract::<Registers>())
+            .map_err(|err| RoqoqoBackendError::GenericError {
+                msg: format!("Running circuit with python backend failed: {err}"),
+            })
+    }
+
+    fn run_circuit_batch(
+        &self,
+        circuits: &[Circuit],
+    ) -> Result<Vec<Registers>, RoqoqoBackendError> {
+        if !self.backend.hasattr("run_circuit_batch").unwrap_or(false) {
+            return circuits
+                .iter()
+                .map(|circuit| self.run_circuit(circuit))
+                .collect();
+        }
+        let circuits: Vec<CircuitWrapper> = circuits
+            .iter()
+            .map(|circuit| CircuitWrapper {
+                internal: circuit.clone(),
+            })
+            .collect();
+        self.backend
+            .call_method1("run_circuit_batch", (circuits,))
+            .and_then(|registers| registers.extract::<Vec<Registers>>())
+            .map_err(|err| RoqoqoBackendError::GenericError {
+                msg: format!("Running circuit batch with python backend failed: {err}"),
+            })
+    }
+}
+
 /// Convert generic python object to [roqoqo::QuantumProgram].
 ///
 /// Fallible conversion of generic python object to [roqoqo::QuantumProgram].
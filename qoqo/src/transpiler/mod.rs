@@ -0,0 +1,32 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transformations of circuits for the execution on a device.
+//!
+//! Collection of pyo3 wrappers around the roqoqo transpiler passes.
+
+mod routing;
+use pyo3::prelude::*;
+pub use routing::route_to_device;
+
+/// Transformations of circuits for the execution on a device.
+///
+/// .. autosummary::
+///     :toctree: generated/
+///
+///     route_to_device
+///
+#[pymodule]
+pub fn transpiler(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(route_to_device, module)?)?;
+    Ok(())
+}
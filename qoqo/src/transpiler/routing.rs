@@ -0,0 +1,56 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::devices::GenericDeviceWrapper;
+use crate::{convert_into_circuit, CircuitWrapper};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Routes a circuit on the connectivity of a device by inserting SWAP operations.
+///
+/// Whenever no operation of the front layer of the circuit can be executed, because its two qubits
+/// are not connected in the device, the SWAP minimising the distance between the qubits of the
+/// blocked two-qubit gates is inserted (SABRE heuristic).
+/// Qubit i of the circuit starts on qubit i of the device. The operations between a
+/// PragmaStartDecompositionBlock and a PragmaStopDecompositionBlock are kept together,
+/// no SWAP acts on the qubits of the block before the block is stopped.
+///
+/// Args:
+///     circuit (Circuit): The circuit that is routed.
+///     device (Device): The device providing the connectivity.
+///
+/// Returns:
+///     Tuple[Circuit, Dict[int, int]]: The routed circuit and the final qubit permutation, mapping every qubit of the circuit to the qubit of the device holding its state at the end of the routed circuit.
+///
+/// Raises:
+///     TypeError: Circuit or device argument cannot be converted.
+///     ValueError: The circuit cannot be routed on the device.
+#[pyfunction]
+pub fn route_to_device(
+    circuit: &Bound<PyAny>,
+    device: &Bound<PyAny>,
+) -> PyResult<(CircuitWrapper, HashMap<usize, usize>)> {
+    let circuit = convert_into_circuit(circuit).map_err(|err| {
+        PyTypeError::new_err(format!(
+            "Circuit argument cannot be converted to qoqo Circuit: {err}"
+        ))
+    })?;
+    let device = GenericDeviceWrapper::from_pyany(device).map_err(|err| {
+        PyTypeError::new_err(format!(
+            "Device argument cannot be converted to qoqo Device: {err}"
+        ))
+    })?;
+    let (routed, permutation) = roqoqo::transpiler::route_to_device(&circuit, &device)
+        .map_err(|err| PyValueError::new_err(format!("{err}")))?;
+    Ok((CircuitWrapper { internal: routed }, permutation))
+}
@@ -10,7 +10,7 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
-use pyo3::exceptions::PyIndexError;
+use pyo3::exceptions::{PyIndexError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use qoqo::measurements::{PauliZProductInputWrapper, PauliZProductWrapper};
 use qoqo::operations::{convert_operation_to_pyobject, RotateXWrapper};
@@ -18,7 +18,6 @@ use qoqo::{CircuitWrapper, OperationIteratorWrapper, QOQO_VERSION};
 use qoqo_calculator::CalculatorFloat;
 use roqoqo::operations::Operation;
 use roqoqo::operations::*;
-#[cfg(feature = "json_schema")]
 use roqoqo::Circuit;
 use roqoqo::ROQOQO_VERSION;
 use std::collections::{HashMap, HashSet};
@@ -1112,6 +1111,103 @@ fn test_number_of_qubits() {
     })
 }
 
+/// Test is_equivalent and the errors of unitary_matrix
+#[test]
+fn test_is_equivalent_unitary_matrix() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut cnot = Circuit::new();
+        cnot += CNOT::new(0, 1);
+        let mut decomposed = Circuit::new();
+        decomposed += Hadamard::new(1);
+        decomposed += ControlledPauliZ::new(0, 1);
+        decomposed += Hadamard::new(1);
+        let mut reversed = Circuit::new();
+        reversed += CNOT::new(1, 0);
+        let cnot = Bound::new(py, CircuitWrapper { internal: cnot }).unwrap();
+        let decomposed = Bound::new(
+            py,
+            CircuitWrapper {
+                internal: decomposed,
+            },
+        )
+        .unwrap();
+        let reversed = Bound::new(py, CircuitWrapper { internal: reversed }).unwrap();
+
+        let equivalent: bool = cnot
+            .call_method1("is_equivalent", (&decomposed,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(equivalent);
+        let equivalent: bool = cnot
+            .call_method1("is_equivalent", (&decomposed, 1e-8, false))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(equivalent);
+        let equivalent: bool = cnot
+            .call_method1("is_equivalent", (&reversed,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(!equivalent);
+
+        let error = cnot.call_method1("is_equivalent", (vec!["fails"],));
+        assert!(error.unwrap_err().is_instance_of::<PyTypeError>(py));
+
+        let mut measured = Circuit::new();
+        measured += MeasureQubit::new(0, "ro".to_string(), 0);
+        let measured = Bound::new(py, CircuitWrapper { internal: measured }).unwrap();
+        let error = measured.call_method0("unitary_matrix");
+        assert!(error.unwrap_err().is_instance_of::<PyValueError>(py));
+        let error = cnot.call_method1("is_equivalent", (&measured,));
+        assert!(error.unwrap_err().is_instance_of::<PyValueError>(py));
+    })
+}
+
+/// Test to_qasm and from_qasm of Circuit
+#[test]
+fn test_qasm() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut circuit = Circuit::new();
+        circuit += Hadamard::new(0);
+        circuit += CNOT::new(0, 1);
+        let wrapped = Bound::new(
+            py,
+            CircuitWrapper {
+                internal: circuit.clone(),
+            },
+        )
+        .unwrap();
+        let program: String = wrapped.call_method0("to_qasm").unwrap().extract().unwrap();
+        assert!(program.starts_with("OPENQASM 3.0;"));
+        let program_2: String = wrapped
+            .call_method1("to_qasm", ("2.0",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(program_2.starts_with("OPENQASM 2.0;"));
+        let error = wrapped.call_method1("to_qasm", ("4.0",));
+        assert!(error.unwrap_err().is_instance_of::<PyValueError>(py));
+
+        let circuit_type = py.get_type::<CircuitWrapper>();
+        for program in [program, program_2] {
+            let imported: CircuitWrapper = circuit_type
+                .call_method1("from_qasm", (program,))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(imported.internal, circuit);
+        }
+        let error = circuit_type.call_method1("from_qasm", ("qubit q;\nfoo q;",));
+        let error = error.unwrap_err();
+        assert!(error.is_instance_of::<PyValueError>(py));
+        assert!(error.to_string().contains("line 2, column 1"));
+    })
+}
+
 /// Test function overrotate() for Circuit
 #[test]
 #[cfg(feature = "overrotate")]
@@ -707,6 +707,35 @@ fn test_getters_commuting_operations() {
     })
 }
 
+/// Test from_circuit_with_commutation
+#[test]
+fn test_from_circuit_with_commutation() {
+    Python::initialize();
+    Python::attach(|py| {
+        let cnot_01 = convert_operation_to_pyobject(Operation::from(CNOT::new(0, 1)), py).unwrap();
+        let rotatez_0 =
+            convert_operation_to_pyobject(Operation::from(RotateZ::new(0, "theta".into())), py)
+                .unwrap();
+        let circuit = new_circuit(py);
+        circuit.call_method1("add", (cnot_01,)).unwrap();
+        circuit.call_method1("add", (rotatez_0,)).unwrap();
+
+        let dag = new_circuitdag(py);
+        let commuting = dag
+            .call_method1("from_circuit_with_commutation", (circuit.clone(),))
+            .unwrap();
+        let fpb = commuting.call_method0("first_parallel_block").unwrap();
+        assert_eq!(fpb.len().unwrap(), 2);
+
+        let ordered = dag.call_method1("from_circuit", (circuit,)).unwrap();
+        let fpb = ordered.call_method0("first_parallel_block").unwrap();
+        assert_eq!(fpb.len().unwrap(), 1);
+
+        let result = dag.call_method1("from_circuit_with_commutation", (vec![0],));
+        assert!(result.is_err());
+    })
+}
+
 #[test]
 fn test_getters_parallel_blocks() {
     Python::initialize();
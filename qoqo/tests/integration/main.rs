@@ -31,3 +31,7 @@ mod measurements;
 
 #[cfg(test)]
 mod noise_models;
+
+#[cfg(test)]
+#[cfg(feature = "circuitdag")]
+mod transpiler;
@@ -13,8 +13,10 @@
 //! Integration test for public API of Basis rotation measurement
 
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::Python;
 use qoqo::measurements::{PauliZProductInputWrapper, PauliZProductWrapper};
+use qoqo::noise_models::ImperfectReadoutModelWrapper;
 use qoqo::CircuitWrapper;
 use roqoqo::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
 #[cfg(feature = "json_schema")]
@@ -24,6 +26,9 @@ use roqoqo::{
     Circuit,
 };
 use std::collections::HashMap;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliOperator, PauliProduct};
+use struqture_py::spins::{PauliHamiltonianWrapper, PauliOperatorWrapper};
 use test_case::test_case;
 
 #[test]
@@ -384,6 +389,185 @@ fn test_evaluate_symbolic(register: Vec<Vec<bool>>, constant: f64) {
     })
 }
 
+/// Test evaluate with readout error mitigation
+#[test]
+fn test_evaluate_readout_error_mitigation() {
+    Python::initialize();
+    Python::attach(|py| {
+        let input_type = py.get_type::<PauliZProductInputWrapper>();
+        let binding = input_type.call1((1, false)).unwrap();
+        let input = binding.cast::<PauliZProductInputWrapper>().unwrap();
+        let _ = input
+            .call_method1("add_pauliz_product", ("ro", vec![0]))
+            .unwrap();
+        let linear: HashMap<usize, f64> = HashMap::from([(0, 1.0)]);
+        let _ = input
+            .call_method1("add_linear_exp_val", ("z".to_string(), linear))
+            .unwrap();
+
+        let br_type = py.get_type::<PauliZProductWrapper>();
+        let circs: Vec<CircuitWrapper> = vec![CircuitWrapper::new()];
+        let binding = br_type
+            .call1((None::<CircuitWrapper>, circs, input))
+            .unwrap();
+        let br = binding.cast::<PauliZProductWrapper>().unwrap();
+        let mut measured_registers: HashMap<String, BitOutputRegister> = HashMap::new();
+        let _ = measured_registers.insert(
+            "ro".to_string(),
+            vec![vec![false], vec![false], vec![false], vec![true]],
+        );
+        let fidelities: HashMap<usize, f64> = HashMap::from([(0, 0.5)]);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("readout_fidelities", fidelities).unwrap();
+        let result = br
+            .call_method(
+                "evaluate_with_readout_error_mitigation",
+                (measured_registers.clone(),),
+                Some(&kwargs),
+            )
+            .unwrap();
+        let z = f64::extract(result.get_item("z").unwrap().as_borrowed()).unwrap();
+        assert!((z - 1.0).abs() < 1e-12);
+        let result = br
+            .call_method(
+                "evaluate_with_errors_and_readout_error_mitigation",
+                (measured_registers.clone(),),
+                Some(&kwargs),
+            )
+            .unwrap();
+        let z = f64::extract(
+            result
+                .get_item(0)
+                .unwrap()
+                .get_item("z")
+                .unwrap()
+                .as_borrowed(),
+        )
+        .unwrap();
+        assert!((z - 1.0).abs() < 1e-12);
+
+        // Detecting 0 as 1 with probability 0.25 and without errors for 1
+        let model_type = py.get_type::<ImperfectReadoutModelWrapper>();
+        let model = model_type
+            .call_method1("new_with_uniform_error", (1, 0.25, 0.0))
+            .unwrap();
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("imperfect_readout_model", model).unwrap();
+        kwargs.set_item("confusion_matrix_inversion", true).unwrap();
+        let result = br
+            .call_method(
+                "evaluate_with_readout_error_mitigation",
+                (measured_registers.clone(),),
+                Some(&kwargs),
+            )
+            .unwrap();
+        let z = f64::extract(result.get_item("z").unwrap().as_borrowed()).unwrap();
+        assert!((z - 1.0).abs() < 1e-12);
+
+        let invalid: HashMap<usize, f64> = HashMap::from([(0, 0.0)]);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("readout_fidelities", invalid).unwrap();
+        let error = br.call_method(
+            "evaluate_with_readout_error_mitigation",
+            (measured_registers.clone(),),
+            Some(&kwargs),
+        );
+        assert!(error.is_err());
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("imperfect_readout_model", 0.1).unwrap();
+        let error = br.call_method(
+            "evaluate_with_readout_error_mitigation",
+            (measured_registers.clone(),),
+            Some(&kwargs),
+        );
+        assert!(error.is_err());
+        let error = br.call_method1(
+            "evaluate_with_readout_error_mitigation",
+            (measured_registers,),
+        );
+        assert!(error.is_err());
+    })
+}
+
+/// Test evaluate with readout error mitigation stored in the input
+#[test]
+fn test_evaluate_stored_readout_error_mitigation() {
+    Python::initialize();
+    Python::attach(|py| {
+        let input_type = py.get_type::<PauliZProductInputWrapper>();
+        let binding = input_type.call1((1, false)).unwrap();
+        let input = binding.cast::<PauliZProductInputWrapper>().unwrap();
+        let _ = input
+            .call_method1("add_pauliz_product", ("ro", vec![0]))
+            .unwrap();
+        let linear: HashMap<usize, f64> = HashMap::from([(0, 1.0)]);
+        let _ = input
+            .call_method1("add_linear_exp_val", ("z".to_string(), linear))
+            .unwrap();
+        let fidelities: HashMap<usize, f64> = HashMap::from([(0, 0.5)]);
+        input
+            .call_method1("set_readout_fidelities", (fidelities,))
+            .unwrap();
+        let invalid: HashMap<usize, f64> = HashMap::from([(0, 0.0)]);
+        assert!(input
+            .call_method1("set_readout_fidelities", (invalid,))
+            .is_err());
+        assert!(input
+            .call_method1("set_imperfect_readout_model", (0.1,))
+            .is_err());
+
+        let br_type = py.get_type::<PauliZProductWrapper>();
+        let circs: Vec<CircuitWrapper> = vec![CircuitWrapper::new()];
+        let binding = br_type
+            .call1((None::<CircuitWrapper>, circs, input))
+            .unwrap();
+        let br = binding.cast::<PauliZProductWrapper>().unwrap();
+        let measured_registers: HashMap<String, BitOutputRegister> = HashMap::from([(
+            "ro".to_string(),
+            vec![vec![false], vec![false], vec![false], vec![true]],
+        )]);
+        let result = br
+            .call_method1(
+                "evaluate",
+                (
+                    measured_registers.clone(),
+                    HashMap::<String, FloatOutputRegister>::new(),
+                    HashMap::<String, ComplexOutputRegister>::new(),
+                ),
+            )
+            .unwrap();
+        let z = f64::extract(result.get_item("z").unwrap().as_borrowed()).unwrap();
+        assert!((z - 1.0).abs() < 1e-12);
+
+        // Detecting 0 as 1 with probability 0.25 and without errors for 1
+        let model_type = py.get_type::<ImperfectReadoutModelWrapper>();
+        let model = model_type
+            .call_method1("new_with_uniform_error", (1, 0.25, 0.0))
+            .unwrap();
+        input
+            .call_method1("set_imperfect_readout_model", (model, true))
+            .unwrap();
+        input
+            .call_method0("remove_readout_error_mitigation")
+            .unwrap();
+        let binding = br_type
+            .call1((None::<CircuitWrapper>, vec![CircuitWrapper::new()], input))
+            .unwrap();
+        let result = binding
+            .call_method1(
+                "evaluate",
+                (
+                    measured_registers,
+                    HashMap::<String, FloatOutputRegister>::new(),
+                    HashMap::<String, ComplexOutputRegister>::new(),
+                ),
+            )
+            .unwrap();
+        let z = f64::extract(result.get_item("z").unwrap().as_borrowed()).unwrap();
+        assert!((z - 0.5).abs() < 1e-12);
+    })
+}
+
 /// Test evaluate failure
 #[test]
 fn test_py03_evaluate_error0() {
@@ -513,10 +697,10 @@ fn test_pyo3_debug() {
         let br_clone = br_wrapper.clone();
         assert_eq!(format!("{:?}", br_wrapper), format!("{:?}", br_clone));
 
-        let debug_string = "PauliZProductWrapper { internal: PauliZProduct { constant_circuit: Some(Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }), circuits: [Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }], input: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false } } }";
+        let debug_string = "PauliZProductWrapper { internal: PauliZProduct { constant_circuit: Some(Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }), circuits: [Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }], input: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false, readout_error_mitigation: None } } }";
         assert_eq!(format!("{:?}", br.borrow()), debug_string);
 
-        let debug_input_string = "PauliZProductInputWrapper { internal: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false } }";
+        let debug_input_string = "PauliZProductInputWrapper { internal: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false, readout_error_mitigation: None } }";
         assert_eq!(format!("{:?}", input.borrow()), debug_input_string);
 
         let debug_input = input;
@@ -815,7 +999,7 @@ fn test_return_input() {
 fn test_pyo3_format_repr() {
     Python::initialize();
     Python::attach(|py| {
-        let format_repr = "PauliZProduct { constant_circuit: Some(Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }), circuits: [Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }], input: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false } }";
+        let format_repr = "PauliZProduct { constant_circuit: Some(Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }), circuits: [Circuit { definitions: [], operations: [], _roqoqo_version: RoqoqoVersion }], input: PauliZProductInput { pauli_product_qubit_masks: {\"ro\": {0: []}}, number_qubits: 3, number_pauli_products: 1, measured_exp_vals: {}, use_flipped_measurement: false, readout_error_mitigation: None } }";
         let input_type = py.get_type::<PauliZProductInputWrapper>();
         let binding = input_type.call1((3, false)).unwrap();
         let input = binding.cast::<PauliZProductInputWrapper>().unwrap();
@@ -992,3 +1176,108 @@ fn test_pyo3_json_schema() {
         assert_eq!(minimum_supported_version_string_input, "1.0.0");
     });
 }
+
+/// Test from_pauli_hamiltonian and from_pauli_operator of PauliZProduct
+#[test]
+fn test_pyo3_from_pauli_hamiltonian() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut hamiltonian = PauliHamiltonian::new();
+        hamiltonian
+            .add_operator_product(PauliProduct::new().x(0).x(1), 1.0.into())
+            .unwrap();
+        hamiltonian
+            .add_operator_product(PauliProduct::new().z(0), 2.0.into())
+            .unwrap();
+        let mut constant_circuit = Circuit::new();
+        constant_circuit += roqoqo::operations::Hadamard::new(0);
+        let comparison = PauliZProduct::from_pauli_hamiltonian(
+            &hamiltonian,
+            "energy".to_string(),
+            2,
+            10,
+            Some(constant_circuit.clone()),
+        )
+        .unwrap();
+
+        let measurement_type = py.get_type::<PauliZProductWrapper>();
+        let py_hamiltonian = Bound::new(
+            py,
+            PauliHamiltonianWrapper {
+                internal: hamiltonian.clone(),
+            },
+        )
+        .unwrap();
+        let py_circuit = Bound::new(
+            py,
+            CircuitWrapper {
+                internal: constant_circuit.clone(),
+            },
+        )
+        .unwrap();
+        let binding = measurement_type
+            .call_method1(
+                "from_pauli_hamiltonian",
+                (py_hamiltonian.clone(), "energy", 2, 10, py_circuit.clone()),
+            )
+            .unwrap();
+        let measurement = binding.cast::<PauliZProductWrapper>().unwrap();
+        assert_eq!(measurement.borrow().internal, comparison);
+
+        let py_operator = Bound::new(
+            py,
+            PauliOperatorWrapper {
+                internal: PauliOperator::from(hamiltonian),
+            },
+        )
+        .unwrap();
+        let binding = measurement_type
+            .call_method1(
+                "from_pauli_operator",
+                (py_operator, "energy", 2, 10, py_circuit),
+            )
+            .unwrap();
+        let measurement = binding.cast::<PauliZProductWrapper>().unwrap();
+        assert_eq!(measurement.borrow().internal, comparison);
+
+        let error = measurement_type
+            .call_method1("from_pauli_hamiltonian", (py_hamiltonian, "energy", 1, 10));
+        assert!(error.is_err());
+        let error = measurement_type.call_method1("from_pauli_hamiltonian", (3, "energy", 2, 10));
+        assert!(error.is_err());
+    })
+}
+
+/// Test allocate_shots function of PauliZProduct
+#[test]
+fn test_pyo3_allocate_shots() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut hamiltonian = PauliHamiltonian::new();
+        hamiltonian
+            .add_operator_product(PauliProduct::new().x(0), 3.0.into())
+            .unwrap();
+        hamiltonian
+            .add_operator_product(PauliProduct::new().z(0), 1.0.into())
+            .unwrap();
+        let measurement =
+            PauliZProduct::from_pauli_hamiltonian(&hamiltonian, "energy".to_string(), 1, 10, None)
+                .unwrap();
+        let br = Bound::new(
+            py,
+            PauliZProductWrapper {
+                internal: measurement.clone(),
+            },
+        )
+        .unwrap();
+        let binding = br.call_method1("allocate_shots", ("energy", 102)).unwrap();
+        let allocated = binding.cast::<PauliZProductWrapper>().unwrap();
+        assert_eq!(
+            allocated.borrow().internal,
+            measurement.allocate_shots("energy", 102).unwrap()
+        );
+
+        assert!(br.call_method1("allocate_shots", ("energy", 1)).is_err());
+        assert!(br.call_method1("allocate_shots", ("missing", 100)).is_err());
+    })
+}
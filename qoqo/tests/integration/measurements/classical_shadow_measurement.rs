@@ -0,0 +1,365 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration test for public API of classical shadow measurement
+
+use num_complex::Complex64;
+use pyo3::prelude::*;
+use pyo3::Python;
+use qoqo::measurements::{ClassicalShadowInputWrapper, ClassicalShadowWrapper};
+use qoqo::CircuitWrapper;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::measurements::{ClassicalShadow, ClassicalShadowInput};
+#[cfg(feature = "json_schema")]
+use roqoqo::ROQOQO_VERSION;
+use roqoqo::{operations, Circuit};
+use std::collections::HashMap;
+use std::str::FromStr;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliProduct};
+use struqture_py::spins::PauliHamiltonianWrapper;
+
+fn observable(product: &str) -> PauliHamiltonian {
+    let mut hamiltonian = PauliHamiltonian::new();
+    hamiltonian
+        .add_operator_product(
+            PauliProduct::from_str(product).unwrap(),
+            CalculatorFloat::from(1.0),
+        )
+        .unwrap();
+    hamiltonian
+}
+
+fn create_input(py: Python) -> Bound<ClassicalShadowInputWrapper> {
+    let input_type = py.get_type::<ClassicalShadowInputWrapper>();
+    let input = input_type
+        .call1((2, 1))
+        .unwrap()
+        .cast::<ClassicalShadowInputWrapper>()
+        .unwrap()
+        .to_owned();
+    let py_observable = Bound::new(
+        py,
+        PauliHamiltonianWrapper {
+            internal: observable("0X"),
+        },
+    )
+    .unwrap();
+    input
+        .call_method1("add_observable", ("x0", py_observable))
+        .unwrap();
+    input
+        .call_method1(
+            "add_fidelity",
+            (
+                "zero",
+                vec![
+                    Complex64::new(1.0, 0.0),
+                    Complex64::new(0.0, 0.0),
+                    Complex64::new(0.0, 0.0),
+                    Complex64::new(0.0, 0.0),
+                ],
+            ),
+        )
+        .unwrap();
+    input
+}
+
+#[test]
+fn test_input() {
+    Python::initialize();
+    Python::attach(|py| {
+        let input = create_input(py);
+        let mut expected = ClassicalShadowInput::new(2, 1);
+        expected
+            .add_observable("x0".to_string(), observable("0X"))
+            .unwrap();
+        expected
+            .add_fidelity(
+                "zero".to_string(),
+                vec![1.0.into(), 0.0.into(), 0.0.into(), 0.0.into()],
+            )
+            .unwrap();
+        assert_eq!(
+            input
+                .extract::<ClassicalShadowInputWrapper>()
+                .unwrap()
+                .internal,
+            expected
+        );
+
+        let py_observable = Bound::new(
+            py,
+            PauliHamiltonianWrapper {
+                internal: observable("2Z"),
+            },
+        )
+        .unwrap();
+        assert!(input
+            .call_method1("add_observable", ("z2", py_observable))
+            .is_err());
+        assert!(input.call_method1("add_observable", ("z2", 1.0)).is_err());
+        assert!(input
+            .call_method1("add_fidelity", ("x0", vec![Complex64::new(1.0, 0.0); 4]))
+            .is_err());
+        assert!(input
+            .call_method1("add_fidelity", ("other", vec![Complex64::new(1.0, 0.0); 2]))
+            .is_err());
+
+        let json: String = input.call_method0("to_json").unwrap().extract().unwrap();
+        let from_json = input.call_method1("from_json", (json,)).unwrap();
+        assert!(input.eq(&from_json).unwrap());
+        let bincode = input.call_method0("to_bincode").unwrap();
+        let from_bincode = input.call_method1("from_bincode", (bincode,)).unwrap();
+        assert!(input.eq(&from_bincode).unwrap());
+        assert!(input.call_method1("from_json", ("error",)).is_err());
+        assert!(input.call_method1("from_bincode", (vec![0u8],)).is_err());
+    })
+}
+
+#[test]
+fn test_new_random_and_evaluate() {
+    Python::initialize();
+    Python::attach(|py| {
+        let input = create_input(py);
+        let measurement_type = py.get_type::<ClassicalShadowWrapper>();
+        let binding = measurement_type
+            .call_method1(
+                "new_random",
+                (input.clone(), 4, 3, None::<CircuitWrapper>, 7),
+            )
+            .unwrap();
+        let measurement = binding.cast::<ClassicalShadowWrapper>().unwrap();
+        let expected = ClassicalShadow::new_random(
+            None,
+            input
+                .extract::<ClassicalShadowInputWrapper>()
+                .unwrap()
+                .internal,
+            4,
+            3,
+            Some(7),
+        );
+        assert_eq!(
+            measurement
+                .extract::<ClassicalShadowWrapper>()
+                .unwrap()
+                .internal,
+            expected
+        );
+        let bases: Vec<String> = measurement
+            .call_method0("input")
+            .unwrap()
+            .call_method0("measurement_bases")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(bases.len(), 4);
+        let circuits: Vec<CircuitWrapper> = measurement
+            .call_method0("circuits")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(circuits.len(), 4);
+        let measurement_type_string: String = measurement
+            .call_method0("measurement_type")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(measurement_type_string, "ClassicalShadow");
+
+        // Hand-crafted bases: qubit 0 measured in X and Y
+        let mut input_internal = input
+            .extract::<ClassicalShadowInputWrapper>()
+            .unwrap()
+            .internal;
+        input_internal.measurement_bases = vec![
+            PauliProduct::from_str("0X").unwrap(),
+            PauliProduct::from_str("0Y").unwrap(),
+        ];
+        let py_input = Bound::new(
+            py,
+            ClassicalShadowInputWrapper {
+                internal: input_internal,
+            },
+        )
+        .unwrap();
+        let binding = measurement_type
+            .call1((
+                None::<CircuitWrapper>,
+                vec![CircuitWrapper::new(), CircuitWrapper::new()],
+                py_input,
+            ))
+            .unwrap();
+        let measurement = binding.cast::<ClassicalShadowWrapper>().unwrap();
+        let mut bit_registers: HashMap<String, Vec<Vec<usize>>> = HashMap::new();
+        bit_registers.insert("ro_0".to_string(), vec![vec![0, 0], vec![0, 0]]);
+        bit_registers.insert("ro_1".to_string(), vec![vec![1, 0], vec![0, 0]]);
+        let float_registers: HashMap<String, Vec<Vec<f64>>> = HashMap::new();
+        let complex_registers: HashMap<String, Vec<Vec<Complex64>>> = HashMap::new();
+        let result: HashMap<String, f64> = measurement
+            .call_method1(
+                "evaluate",
+                (
+                    bit_registers.clone(),
+                    float_registers.clone(),
+                    complex_registers.clone(),
+                ),
+            )
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!((result["x0"] - 1.5).abs() < 1e-12);
+        // <00| (3|+><+| - 1) x (3|0><0| - 1) |00> = 0.5 * 2
+        assert!((result["zero"] - 1.0).abs() < 1e-12);
+
+        let with_errors = measurement
+            .call_method1(
+                "evaluate_with_errors",
+                (
+                    bit_registers,
+                    float_registers.clone(),
+                    complex_registers.clone(),
+                ),
+            )
+            .unwrap();
+        let values: HashMap<String, f64> = with_errors.get_item(0).unwrap().extract().unwrap();
+        let standard_errors: HashMap<String, f64> =
+            with_errors.get_item(1).unwrap().extract().unwrap();
+        assert_eq!(values, result);
+        // Snapshot estimates [3, 3, 0, 0] have sample variance 3
+        assert!((standard_errors["x0"] - (3.0_f64 / 4.0).sqrt()).abs() < 1e-12);
+
+        let mut missing_registers: HashMap<String, Vec<Vec<bool>>> = HashMap::new();
+        missing_registers.insert("ro_0".to_string(), vec![vec![false, false]]);
+        assert!(measurement
+            .call_method1(
+                "evaluate",
+                (missing_registers, float_registers, complex_registers)
+            )
+            .is_err());
+    })
+}
+
+#[test]
+fn test_substitute_parameters_and_serialization() {
+    Python::initialize();
+    Python::attach(|py| {
+        let input = create_input(py);
+        let mut circuit = CircuitWrapper::new();
+        circuit.internal += operations::RotateX::new(0, "theta".into());
+        let measurement_type = py.get_type::<ClassicalShadowWrapper>();
+        let binding = measurement_type
+            .call1((Some(circuit.clone()), vec![circuit.clone()], input))
+            .unwrap();
+        let measurement = binding.cast::<ClassicalShadowWrapper>().unwrap();
+
+        let mut substitution: HashMap<String, f64> = HashMap::new();
+        substitution.insert("theta".to_string(), 0.5);
+        let substituted = measurement
+            .call_method1("substitute_parameters", (substitution,))
+            .unwrap();
+        let mut expected = Circuit::new();
+        expected += operations::RotateX::new(0, 0.5.into());
+        let constant_circuit: CircuitWrapper = substituted
+            .call_method0("constant_circuit")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(constant_circuit.internal, expected);
+        assert!(measurement
+            .call_method1("substitute_parameters", (HashMap::<String, f64>::new(),))
+            .is_err());
+
+        let json: String = measurement
+            .call_method0("to_json")
+            .unwrap()
+            .extract()
+            .unwrap();
+        let from_json = measurement.call_method1("from_json", (json,)).unwrap();
+        assert!(measurement.eq(&from_json).unwrap());
+        let bincode = measurement.call_method0("to_bincode").unwrap();
+        let from_bincode = measurement
+            .call_method1("from_bincode", (bincode,))
+            .unwrap();
+        assert!(measurement.eq(&from_bincode).unwrap());
+        let (name, _): (String, Py<PyAny>) = measurement
+            .call_method0("_internal_to_bincode")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(name, "ClassicalShadow");
+        assert!(measurement.ne(&substituted).unwrap());
+        let copied = measurement.call_method0("__copy__").unwrap();
+        assert!(measurement.eq(&copied).unwrap());
+        let wrapper = measurement.extract::<ClassicalShadowWrapper>().unwrap();
+        assert_eq!(
+            ClassicalShadowWrapper::from_pyany(measurement.as_any()).unwrap(),
+            wrapper.internal
+        );
+    })
+}
+
+/// Test json_schema function
+#[cfg(feature = "json_schema")]
+#[test]
+fn test_pyo3_json_schema() {
+    let rust_schema =
+        serde_json::to_string_pretty(&schemars::schema_for!(ClassicalShadow)).unwrap();
+    let rust_input_schema =
+        serde_json::to_string_pretty(&schemars::schema_for!(ClassicalShadowInput)).unwrap();
+    Python::initialize();
+    pyo3::Python::attach(|py| {
+        let input = create_input(py);
+        let measurement_type = py.get_type::<ClassicalShadowWrapper>();
+        let binding = measurement_type
+            .call1((
+                None::<CircuitWrapper>,
+                Vec::<CircuitWrapper>::new(),
+                input.clone(),
+            ))
+            .unwrap();
+        let measurement = binding.cast::<ClassicalShadowWrapper>().unwrap();
+
+        let schema: String = measurement
+            .call_method0("json_schema")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(schema, rust_schema);
+        let schema: String = input
+            .call_method0("json_schema")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(schema, rust_input_schema);
+
+        let current_version_string: String = measurement
+            .call_method0("current_version")
+            .unwrap()
+            .extract()
+            .unwrap();
+        let minimum_supported_version_string: String = measurement
+            .call_method0("min_supported_version")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(current_version_string, ROQOQO_VERSION);
+        assert_eq!(minimum_supported_version_string, "1.23.0");
+        let minimum_supported_version_string: String = input
+            .call_method0("min_supported_version")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(minimum_supported_version_string, "1.23.0");
+    });
+}
@@ -11,10 +11,12 @@
 // limitations under the License.
 
 use num_complex::Complex64;
+use numpy::PyReadonlyArray2;
 use pyo3::prelude::*;
 use qoqo::measurements::{
     CheatedInputWrapper, CheatedPauliZProductInputWrapper, CheatedPauliZProductWrapper,
-    CheatedWrapper, ClassicalRegisterWrapper, PauliZProductInputWrapper, PauliZProductWrapper,
+    CheatedWrapper, ClassicalRegisterWrapper, ClassicalShadowWrapper, PauliZProductInputWrapper,
+    PauliZProductWrapper,
 };
 use qoqo::operations::convert_operation_to_pyobject;
 use qoqo::{
@@ -22,11 +24,15 @@ use qoqo::{
 };
 use roqoqo::measurements::{
     Cheated, CheatedInput, CheatedPauliZProduct, CheatedPauliZProductInput, ClassicalRegister,
-    PauliZProduct, PauliZProductInput,
+    ClassicalShadow, ClassicalShadowInput, PauliZProduct, PauliZProductInput,
 };
 use roqoqo::operations::Operation;
 use roqoqo::operations::*;
 use roqoqo::{Circuit, QuantumProgram, ROQOQO_VERSION};
+use std::collections::HashMap;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliProduct};
+use test_case::test_case;
 
 #[pyclass(from_py_object, name = "TestBackend", module = "qoqo")]
 #[derive(Debug, Clone, Copy)]
@@ -43,6 +49,95 @@ impl TestBackend {
     }
 }
 
+type Registers = (
+    HashMap<String, Vec<Vec<bool>>>,
+    HashMap<String, Vec<Vec<f64>>>,
+    HashMap<String, Vec<Vec<Complex64>>>,
+);
+
+#[pyclass(from_py_object, name = "TestRegisterBackend", module = "qoqo")]
+#[derive(Debug, Clone, Copy)]
+struct TestRegisterBackend;
+
+#[pymethods]
+impl TestRegisterBackend {
+    fn run_measurement_registers(&self, measurement: Py<PyAny>) -> PyResult<Registers> {
+        Python::attach(|py| {
+            let measurement = ClassicalRegisterWrapper::from_pyany(measurement.bind(py))?;
+            let bit_registers = (0..measurement.circuits.len())
+                .map(|index| (format!("ro_{index}"), vec![vec![false]; 2]))
+                .collect();
+            Ok((bit_registers, HashMap::new(), HashMap::new()))
+        })
+    }
+}
+
+#[pyclass(from_py_object, name = "TestGradientBackend", module = "qoqo")]
+#[derive(Debug, Clone, Copy)]
+struct TestGradientBackend;
+
+#[pymethods]
+impl TestGradientBackend {
+    /// Returns cos(theta) of the RotateX gates in the constant circuit as expectation value `z`.
+    fn run_measurement(&self, measurement: Py<PyAny>) -> PyResult<Option<HashMap<String, f64>>> {
+        Python::attach(|py| {
+            let measurement = CheatedPauliZProductWrapper::from_pyany(measurement.bind(py))?;
+            let mut value = 1.0;
+            for operation in measurement.constant_circuit.unwrap().iter() {
+                if let Operation::RotateX(op) = operation {
+                    value *= op.theta().float().unwrap().cos();
+                }
+            }
+            Ok(Some(HashMap::from([("z".to_string(), value)])))
+        })
+    }
+}
+
+/// Returns cos(theta) of the RotateX gates of a circuit in the float register `ro`.
+fn cos_registers(circuit: &Circuit) -> Registers {
+    let mut value = 1.0;
+    for operation in circuit.iter() {
+        if let Operation::RotateX(op) = operation {
+            value *= op.theta().float().unwrap().cos();
+        }
+    }
+    let float_registers = HashMap::from([("ro".to_string(), vec![vec![value]])]);
+    (HashMap::new(), float_registers, HashMap::new())
+}
+
+#[pyclass(from_py_object, name = "TestCircuitBackend", module = "qoqo")]
+#[derive(Debug, Clone, Copy)]
+struct TestCircuitBackend;
+
+#[pymethods]
+impl TestCircuitBackend {
+    fn run_circuit(&self, circuit: Py<PyAny>) -> PyResult<Registers> {
+        Python::attach(|py| {
+            let circuit = CircuitWrapper::from_pyany(circuit.bind(py))?;
+            Ok(cos_registers(&circuit))
+        })
+    }
+}
+
+#[pyclass(from_py_object, name = "TestCircuitBatchBackend", module = "qoqo")]
+#[derive(Debug, Clone, Copy)]
+struct TestCircuitBatchBackend;
+
+#[pymethods]
+impl TestCircuitBatchBackend {
+    fn run_circuit_batch(&self, circuits: Vec<Py<PyAny>>) -> PyResult<Vec<Registers>> {
+        Python::attach(|py| {
+            circuits
+                .iter()
+                .map(|circuit| {
+                    let circuit = CircuitWrapper::from_pyany(circuit.bind(py))?;
+                    Ok(cos_registers(&circuit))
+                })
+                .collect()
+        })
+    }
+}
+
 fn create_measurement(py: Python) -> Bound<CheatedPauliZProductWrapper> {
     let input_type = py.get_type::<CheatedPauliZProductInputWrapper>();
     let binding = input_type.call0().unwrap();
@@ -314,6 +409,291 @@ fn test_new_run_classical_register() {
     })
 }
 
+/// Test new and run functions of QuantumProgram with ClassicalShadow measurement input
+#[test]
+fn test_new_run_classical_shadow() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut input = ClassicalShadowInput::new(1, 1);
+        let mut observable = PauliHamiltonian::new();
+        observable
+            .add_operator_product(PauliProduct::new().z(0), 1.0.into())
+            .unwrap();
+        input.add_observable("z0".to_string(), observable).unwrap();
+        input.measurement_bases = vec![PauliProduct::new().z(0), PauliProduct::new().x(0)];
+        let mut circuit = Circuit::new();
+        circuit += RotateX::new(0, "test".into());
+        let measurement = ClassicalShadow {
+            constant_circuit: Some(circuit),
+            circuits: vec![Circuit::new(), Circuit::new()],
+            input,
+        };
+        let py_measurement = Bound::new(
+            py,
+            ClassicalShadowWrapper {
+                internal: measurement.clone(),
+            },
+        )
+        .unwrap();
+
+        let program_type = py.get_type::<QuantumProgramWrapper>();
+        let binding = program_type
+            .call1((py_measurement, vec!["test".to_string()]))
+            .unwrap();
+        let program = binding.cast::<QuantumProgramWrapper>().unwrap();
+        assert_eq!(
+            program.extract::<QuantumProgramWrapper>().unwrap(),
+            QuantumProgramWrapper {
+                internal: QuantumProgram::ClassicalShadow {
+                    measurement: measurement.clone(),
+                    input_parameter_names: vec!["test".to_string()]
+                }
+            }
+        );
+        let returned = program.call_method0("measurement").unwrap();
+        assert_eq!(
+            returned
+                .extract::<ClassicalShadowWrapper>()
+                .unwrap()
+                .internal,
+            measurement
+        );
+        let params_returned: Vec<String> = program
+            .call_method0("input_parameter_names")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(params_returned, vec!["test".to_string()]);
+
+        // Snapshot estimates [3, 3, 0, 0]
+        let result: HashMap<String, f64> = program
+            .call_method1("run", (TestRegisterBackend, Some(vec![0.0])))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!((result["z0"] - 1.5).abs() < 1e-12);
+        assert!(program
+            .call_method1("run", (TestRegisterBackend, Some(vec![0.0, 1.0])))
+            .is_err());
+        assert!(program
+            .call_method1("run", (TestBackend, Some(vec![0.0])))
+            .is_err());
+        assert!(program
+            .call_method1("run_registers", (TestRegisterBackend, Some(vec![0.0])))
+            .is_err());
+    })
+}
+
+/// Test run_gradient function of QuantumProgram
+#[test]
+fn test_run_gradient() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut constant_circuit = Circuit::new();
+        constant_circuit += RotateX::new(0, "2 * a".into());
+        let measurement = CheatedPauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![Circuit::new()],
+            input: CheatedPauliZProductInput::new(),
+        };
+        let program = Bound::new(
+            py,
+            QuantumProgramWrapper {
+                internal: QuantumProgram::CheatedPauliZProduct {
+                    measurement,
+                    input_parameter_names: vec!["a".to_string()],
+                },
+            },
+        )
+        .unwrap();
+        let result = program
+            .call_method1("run_gradient", (TestGradientBackend, Some(vec![0.3])))
+            .unwrap();
+        let (jacobian, names): (PyReadonlyArray2<f64>, Vec<String>) = result.extract().unwrap();
+        assert_eq!(names, vec!["z".to_string()]);
+        assert_eq!(jacobian.as_array().shape(), &[1, 1]);
+        assert!((jacobian.as_array()[[0, 0]] + 2.0 * 0.6_f64.sin()).abs() < 1e-12);
+    })
+}
+
+/// Test run_gradient function of QuantumProgram with unsupported input
+#[test]
+fn test_run_gradient_error() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut constant_circuit = Circuit::new();
+        constant_circuit += RotateX::new(0, "sin(a)".into());
+        let measurement = CheatedPauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![Circuit::new()],
+            input: CheatedPauliZProductInput::new(),
+        };
+        let program = Bound::new(
+            py,
+            QuantumProgramWrapper {
+                internal: QuantumProgram::CheatedPauliZProduct {
+                    measurement,
+                    input_parameter_names: vec!["a".to_string()],
+                },
+            },
+        )
+        .unwrap();
+        assert!(program
+            .call_method1("run_gradient", (TestGradientBackend, Some(vec![0.3])))
+            .is_err());
+        assert!(program
+            .call_method1("run_gradient", (TestGradientBackend, Some(vec![0.3, 0.1])))
+            .is_err());
+    })
+}
+
+/// Test run_batch function of QuantumProgram
+#[test_case(true; "batch backend")]
+#[test_case(false; "circuit backend")]
+fn test_run_batch(batch: bool) {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut constant_circuit = Circuit::new();
+        constant_circuit += RotateX::new(0, "2 * a - b".into());
+        let mut input = CheatedPauliZProductInput::new();
+        let index = input.add_pauliz_product("ro".to_string());
+        input
+            .add_linear_exp_val("z".to_string(), HashMap::from([(index, 1.0)]))
+            .unwrap();
+        let measurement = CheatedPauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![Circuit::new()],
+            input,
+        };
+        let program = Bound::new(
+            py,
+            QuantumProgramWrapper {
+                internal: QuantumProgram::CheatedPauliZProduct {
+                    measurement,
+                    input_parameter_names: vec!["a".to_string(), "b".to_string()],
+                },
+            },
+        )
+        .unwrap();
+        let backend = if batch {
+            Bound::new(py, TestCircuitBatchBackend).unwrap().into_any()
+        } else {
+            Bound::new(py, TestCircuitBackend).unwrap().into_any()
+        };
+        let parameters = vec![vec![0.3, 0.1], vec![1.0, -0.5]];
+        let results: Vec<HashMap<String, f64>> = program
+            .call_method1("run_batch", (&backend, parameters))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!((results[0]["z"] - 0.5_f64.cos()).abs() < 1e-12);
+        assert!((results[1]["z"] - 2.5_f64.cos()).abs() < 1e-12);
+
+        let results: Vec<HashMap<String, f64>> = program
+            .call_method1("run_batch", (&backend, Vec::<Vec<f64>>::new()))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(program
+            .call_method1("run_batch", (&backend, vec![vec![0.3]]))
+            .is_err());
+        assert!(program
+            .call_method1("run_batch", (&backend, "parameters"))
+            .is_err());
+        // Backends without batch execution run every parameter set with run_measurement like `run`
+        let results: Vec<HashMap<String, f64>> = program
+            .call_method1("run_batch", (TestGradientBackend, vec![vec![0.3, 0.1]]))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!((results[0]["z"] - 0.5_f64.cos()).abs() < 1e-12);
+        assert!(program
+            .call_method1("run_batch", (TestGradientBackend, vec![vec![0.3]]))
+            .is_err());
+    })
+}
+
+/// Test run_named and bind_parameters functions of QuantumProgram
+#[test]
+fn test_run_named_bind_parameters() {
+    Python::initialize();
+    Python::attach(|py| {
+        let mut constant_circuit = Circuit::new();
+        constant_circuit += RotateX::new(0, "2 * a - b".into());
+        let measurement = CheatedPauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![Circuit::new()],
+            input: CheatedPauliZProductInput::new(),
+        };
+        let program = Bound::new(
+            py,
+            QuantumProgramWrapper {
+                internal: QuantumProgram::CheatedPauliZProduct {
+                    measurement,
+                    input_parameter_names: vec!["a".to_string(), "b".to_string()],
+                },
+            },
+        )
+        .unwrap();
+        let parameters = HashMap::from([("b".to_string(), 0.1), ("a".to_string(), 0.3)]);
+        let result: HashMap<String, f64> = program
+            .call_method1("run_named", (TestGradientBackend, parameters.clone()))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!((result["z"] - 0.5_f64.cos()).abs() < 1e-12);
+        assert!(program
+            .call_method1(
+                "run_named",
+                (TestGradientBackend, HashMap::from([("a".to_string(), 0.3)]))
+            )
+            .is_err());
+        assert!(program
+            .call_method1(
+                "run_named",
+                (
+                    TestGradientBackend,
+                    HashMap::from([
+                        ("a".to_string(), 0.3),
+                        ("b".to_string(), 0.1),
+                        ("c".to_string(), 0.1)
+                    ])
+                )
+            )
+            .is_err());
+        assert!(program
+            .call_method1("run_registers_named", (TestGradientBackend, parameters))
+            .is_err());
+
+        let bound = program
+            .call_method1(
+                "bind_parameters",
+                (HashMap::from([("a".to_string(), 0.3)]),),
+            )
+            .unwrap();
+        let names: Vec<String> = bound
+            .call_method0("input_parameter_names")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(names, vec!["b".to_string()]);
+        let result: HashMap<String, f64> = bound
+            .call_method1("run", (TestGradientBackend, Some(vec![0.1])))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!((result["z"] - 0.5_f64.cos()).abs() < 1e-12);
+        assert!(bound
+            .call_method1(
+                "bind_parameters",
+                (HashMap::from([("a".to_string(), 0.3)]),)
+            )
+            .is_err());
+    })
+}
+
 /// Test new function of QuantumProgram first error
 #[test]
 fn test_new_error_1() {
@@ -0,0 +1,77 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use qoqo::devices::SquareLatticeDeviceWrapper;
+use qoqo::transpiler::route_to_device;
+use qoqo::CircuitWrapper;
+use roqoqo::operations::*;
+use roqoqo::Circuit;
+use std::collections::HashMap;
+
+fn new_line_device(py: Python) -> Bound<PyAny> {
+    let arguments: (usize, usize, [String; 0], [String; 1], f64) =
+        (1, 3, [], ["CNOT".to_string()], 1.0);
+    py.get_type::<SquareLatticeDeviceWrapper>()
+        .call1(arguments)
+        .unwrap()
+}
+
+fn new_circuit(py: Python, circuit: Circuit) -> Bound<PyAny> {
+    Bound::new(py, CircuitWrapper { internal: circuit })
+        .unwrap()
+        .into_any()
+}
+
+#[test]
+fn test_route_to_device() {
+    Python::initialize();
+    Python::attach(|py| {
+        let device = new_line_device(py);
+        let mut circuit = Circuit::new();
+        circuit += CNOT::new(0, 2);
+        circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+        let (routed, permutation) =
+            route_to_device(&new_circuit(py, circuit.clone()), &device).unwrap();
+        let (expected, expected_permutation) = roqoqo::transpiler::route_to_device(
+            &circuit,
+            &roqoqo::devices::SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0),
+        )
+        .unwrap();
+        assert_eq!(routed.internal, expected);
+        assert_eq!(permutation, expected_permutation);
+        assert_eq!(permutation, HashMap::from([(0, 1), (1, 0), (2, 2)]));
+
+        let result = qoqo::transpiler::transpiler(py, &PyModule::new(py, "transpiler").unwrap());
+        assert!(result.is_ok());
+    })
+}
+
+#[test]
+fn test_route_to_device_errors() {
+    Python::initialize();
+    Python::attach(|py| {
+        let device = new_line_device(py);
+        let mut circuit = Circuit::new();
+        circuit += Toffoli::new(0, 1, 2);
+        let result = route_to_device(&new_circuit(py, circuit.clone()), &device);
+        assert!(result.unwrap_err().is_instance_of::<PyValueError>(py));
+
+        let result = route_to_device(PyString::new(py, "circuit").as_any(), &device);
+        assert!(result.unwrap_err().is_instance_of::<PyTypeError>(py));
+
+        let result = route_to_device(&new_circuit(py, Circuit::new()), &new_circuit(py, circuit));
+        assert!(result.unwrap_err().is_instance_of::<PyTypeError>(py));
+    })
+}
@@ -409,6 +409,9 @@ impl<'ast> Visit<'ast> for Visitor {
                 if trait_name.as_str() == "OperateSingleQubitGate" {
                     self.single_qubit_gate_operations.push(id.clone());
                 }
+                if trait_name.as_str() == "OperateConstantGate" {
+                    self.constant_gate_operations.push(id.clone());
+                }
                 if trait_name.as_str() == "OperateGate" {
                     self.gate_operations.push(id.clone());
                     push_available_gate(id.to_string());
@@ -773,7 +776,7 @@ fn main() {
         }
 
         /// Enum of all Operations implementing [OperateConstantGate]
-        #[derive(Debug, Clone, PartialEq, Eq,InvolveQubits, Operate, OperateTryFromEnum, Substitute, OperateGate, OperateConstantGate,  SupportedVersion)]
+        #[derive(Debug, Clone, PartialEq, InvolveQubits, Operate, OperateTryFromEnum, Substitute, OperateGate, OperateConstantGate,  SupportedVersion)]
         #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
         #[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
         #[non_exhaustive]
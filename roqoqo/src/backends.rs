@@ -32,7 +32,6 @@ use std::collections::HashMap;
 
 use crate::operations::Operation;
 use crate::registers::Registers;
-use crate::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
 use crate::Circuit;
 use crate::{
     measurements::{Measure, MeasureExpectationValues},
@@ -91,6 +90,30 @@ pub trait EvaluatingBackend: Sized {
         circuit: impl Iterator<Item = &'a Operation>,
     ) -> RegisterResult;
 
+    /// Runs a batch of independent circuits with the backend.
+    ///
+    /// Used by [crate::QuantumProgram::run_batch] to submit the circuits of all parameter points together.
+    /// Backends that can execute several circuits in one job should override this function,
+    /// the default implementation runs the circuits one after another.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuits` - The circuits that are run on the backend.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Vec<Registers>)` - The output registers written by every circuit, in the order of `circuits`.
+    /// `Err(RoqoqoBackendError)` - Running one of the circuits failed.
+    fn run_circuit_batch(
+        &self,
+        circuits: &[Circuit],
+    ) -> Result<Vec<Registers>, RoqoqoBackendError> {
+        circuits
+            .iter()
+            .map(|circuit| self.run_circuit(circuit))
+            .collect()
+    }
+
     /// Runs all circuits corresponding to one measurement with the backend.
     ///
     /// An expectation value measurement in general involves several circuits.
@@ -114,40 +137,15 @@ pub trait EvaluatingBackend: Sized {
     where
         T: Measure,
     {
-        let mut bit_registers: HashMap<String, BitOutputRegister> = HashMap::new();
-        let mut float_registers: HashMap<String, FloatOutputRegister> = HashMap::new();
-        let mut complex_registers: HashMap<String, ComplexOutputRegister> = HashMap::new();
-
+        let mut registers: Registers = (HashMap::new(), HashMap::new(), HashMap::new());
         for circuit in measurement.circuits() {
-            let (tmp_bit_reg, tmp_float_reg, tmp_complex_reg) = match measurement.constant_circuit()
-            {
+            let circuit_registers = match measurement.constant_circuit() {
                 Some(x) => self.run_circuit_iterator(x.iter().chain(circuit.iter()))?,
                 None => self.run_circuit_iterator(circuit.iter())?,
             };
-
-            for (key, mut val) in tmp_bit_reg.into_iter() {
-                if let Some(x) = bit_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = bit_registers.insert(key, val);
-                }
-            }
-            for (key, mut val) in tmp_float_reg.into_iter() {
-                if let Some(x) = float_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = float_registers.insert(key, val);
-                }
-            }
-            for (key, mut val) in tmp_complex_reg.into_iter() {
-                if let Some(x) = complex_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = complex_registers.insert(key, val);
-                }
-            }
+            append_registers(&mut registers, circuit_registers);
         }
-        Ok((bit_registers, float_registers, complex_registers))
+        Ok(registers)
     }
     /// Evaluates expectation values of a measurement with the backend.
     ///
@@ -170,6 +168,62 @@ pub trait EvaluatingBackend: Sized {
             self.run_measurement_registers(measurement)?;
         Ok(measurement.evaluate(bit_registers, float_registers, complex_registers)?)
     }
+
+    /// Evaluates expectation values of several measurements with the backend.
+    ///
+    /// Used by [crate::QuantumProgram::run_batch] to run the measurements of all parameter points.
+    /// The default implementation submits the circuits of all measurements together with
+    /// [EvaluatingBackend::run_circuit_batch], combines the output registers of every measurement
+    /// in the same way as [EvaluatingBackend::run_measurement_registers] and evaluates the measurement.
+    /// Backends overriding [EvaluatingBackend::run_measurement] or [EvaluatingBackend::run_measurement_registers]
+    /// should override this function as well, so that batches are run in the same way as single measurements.
+    ///
+    /// # Arguments
+    ///
+    /// * `measurements` - The measurements that are run on the backend.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Vec<Option<HashMap<String, f64>>>)` - The measurement results, in the order of `measurements`.
+    /// `Err(RoqoqoBackendError)` - The measurement run failed.
+    fn run_measurement_batch<T>(
+        &self,
+        measurements: &[T],
+    ) -> Result<Vec<Option<HashMap<String, f64>>>, RoqoqoBackendError>
+    where
+        T: MeasureExpectationValues,
+    {
+        let mut circuits: Vec<Circuit> = Vec::new();
+        for measurement in measurements.iter() {
+            for circuit in measurement.circuits() {
+                circuits.push(match measurement.constant_circuit() {
+                    Some(constant_circuit) => constant_circuit.clone() + circuit,
+                    None => circuit.clone(),
+                });
+            }
+        }
+        let mut results = self.run_circuit_batch(&circuits)?.into_iter();
+        if results.len() != circuits.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Backend returned {} results for a batch of {} circuits",
+                    results.len(),
+                    circuits.len()
+                ),
+            });
+        }
+        measurements
+            .iter()
+            .map(|measurement| {
+                let mut registers: Registers = (HashMap::new(), HashMap::new(), HashMap::new());
+                for result in results.by_ref().take(measurement.circuits().count()) {
+                    append_registers(&mut registers, result);
+                }
+                let (bit_registers, float_registers, complex_registers) = registers;
+                Ok(measurement.evaluate(bit_registers, float_registers, complex_registers)?)
+            })
+            .collect()
+    }
 }
 
 #[cfg(feature = "async")]
@@ -247,10 +301,6 @@ pub trait AsyncEvaluatingBackend: Sized {
         T: Measure,
         T: std::marker::Sync,
     {
-        let mut bit_registers: HashMap<String, BitOutputRegister> = HashMap::new();
-        let mut float_registers: HashMap<String, FloatOutputRegister> = HashMap::new();
-        let mut complex_registers: HashMap<String, ComplexOutputRegister> = HashMap::new();
-
         let mut circuit_futures = Vec::new();
         for circuit in measurement.circuits() {
             let circuit_future = match measurement.constant_circuit() {
@@ -261,30 +311,11 @@ pub trait AsyncEvaluatingBackend: Sized {
         }
         let circuit_results = futures::future::try_join_all(circuit_futures).await?;
 
-        for (tmp_bit_reg, tmp_float_reg, tmp_complex_reg) in circuit_results {
-            for (key, mut val) in tmp_bit_reg.into_iter() {
-                if let Some(x) = bit_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = bit_registers.insert(key, val);
-                }
-            }
-            for (key, mut val) in tmp_float_reg.into_iter() {
-                if let Some(x) = float_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = float_registers.insert(key, val);
-                }
-            }
-            for (key, mut val) in tmp_complex_reg.into_iter() {
-                if let Some(x) = complex_registers.get_mut(&key) {
-                    x.append(&mut val);
-                } else {
-                    let _ = complex_registers.insert(key, val);
-                }
-            }
+        let mut registers: Registers = (HashMap::new(), HashMap::new(), HashMap::new());
+        for circuit_registers in circuit_results {
+            append_registers(&mut registers, circuit_registers);
         }
-        Ok((bit_registers, float_registers, complex_registers))
+        Ok(registers)
     }
     /// Evaluates expectation values of a measurement with the backend.
     ///
@@ -310,3 +341,18 @@ pub trait AsyncEvaluatingBackend: Sized {
         Ok(measurement.evaluate(bit_registers, float_registers, complex_registers)?)
     }
 }
+
+/// Appends the output registers of one circuit run to the combined output registers of a measurement.
+pub(crate) fn append_registers(registers: &mut Registers, new_registers: Registers) {
+    let (bit_registers, float_registers, complex_registers) = registers;
+    let (tmp_bit_reg, tmp_float_reg, tmp_complex_reg) = new_registers;
+    for (key, mut val) in tmp_bit_reg.into_iter() {
+        bit_registers.entry(key).or_default().append(&mut val);
+    }
+    for (key, mut val) in tmp_float_reg.into_iter() {
+        float_registers.entry(key).or_default().append(&mut val);
+    }
+    for (key, mut val) in tmp_complex_reg.into_iter() {
+        complex_registers.entry(key).or_default().append(&mut val);
+    }
+}
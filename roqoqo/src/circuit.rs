@@ -11,17 +11,20 @@
 // limitations under the License.
 
 use crate::operations::{
-    Define, InvolveQubits, InvolvedQubits, Operate, Operation, Substitute, SupportedVersion,
+    gate_qubits, subspace_indices, Define, GateOperation, InvolveQubits, InvolvedQubits, Operate,
+    OperateGate, Operation, Substitute, SupportedVersion,
 };
 #[cfg(feature = "overrotate")]
 use crate::operations::{Rotate, Rotation};
+use crate::qasm::{circuit_from_qasm, circuit_to_qasm, QasmVersion};
 use crate::RoqoqoError;
 use crate::RoqoqoVersion;
 #[cfg(feature = "serialize")]
 use crate::RoqoqoVersionSerializable;
+use ndarray::Array2;
+use num_complex::Complex64;
 use qoqo_calculator::Calculator;
 use std::collections::{HashMap, HashSet};
-#[cfg(feature = "overrotate")]
 use std::convert::TryFrom;
 use std::ops;
 use std::{
@@ -497,6 +500,226 @@ impl Circuit {
             .max()
             .unwrap_or_default()
     }
+
+    /// Returns the unitary matrix of the Circuit.
+    ///
+    /// The matrix acts on the qubits `0..number_of_qubits()`, where qubit `i` corresponds to bit `i`
+    /// of the row and column index (qubit 0 is the least significant qubit).
+    /// Note that this differs from [crate::operations::OperateGate::unitary_matrix] of a single gate,
+    /// where the first qubit of the gate is the most significant one.
+    ///
+    /// Gate operations and `PragmaGlobalPhase` contribute to the unitary matrix.
+    /// PRAGMAs that only structure the Circuit (`PragmaStopParallelBlock`, `PragmaSleep`,
+    /// `PragmaStartDecompositionBlock`, `PragmaStopDecompositionBlock`) and definitions are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Array2<Complex64>)` - The unitary matrix of the Circuit.
+    /// * `Err(RoqoqoError::GenericError)` - The Circuit contains an operation that is not unitary, such as a measurement or a noise PRAGMA.
+    /// * `Err(RoqoqoError::CalculatorError)` - The Circuit contains symbolic parameters.
+    /// * `Err(RoqoqoError::GenericError)` - The unitary matrix of the qubits of the Circuit is too large to be represented.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roqoqo::Circuit;
+    /// use roqoqo::operations::{CNOT, PauliX};
+    ///
+    /// let mut circuit = Circuit::new();
+    /// circuit += PauliX::new(0);
+    /// circuit += CNOT::new(0, 1);
+    /// let unitary = circuit.unitary_matrix().unwrap();
+    /// // |00> is mapped to |11>
+    /// assert_eq!(unitary[(3, 0)].re, 1.0);
+    /// ```
+    pub fn unitary_matrix(&self) -> Result<Array2<Complex64>, RoqoqoError> {
+        self.unitary_matrix_of_size(self.number_of_qubits())
+    }
+
+    /// Checks whether the Circuit implements the same unitary transformation as another Circuit.
+    ///
+    /// Both unitary matrices are computed on the qubits used in either Circuit and compared element-wise.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The Circuit that is compared to this Circuit.
+    /// * `tolerance` - The largest absolute difference of matrix elements for which the Circuits are considered equivalent.
+    /// * `up_to_global_phase` - Whether Circuits that only differ by a global phase are considered equivalent.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - Whether the Circuits are equivalent.
+    /// * `Err(RoqoqoError)` - The unitary matrix of one of the Circuits could not be computed, see [Circuit::unitary_matrix].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roqoqo::Circuit;
+    /// use roqoqo::operations::{OperateThreeQubitGate, Toffoli};
+    ///
+    /// let toffoli = Toffoli::new(0, 1, 2);
+    /// let mut circuit = Circuit::new();
+    /// circuit += toffoli.clone();
+    /// assert!(circuit.is_equivalent(&toffoli.circuit(), 1e-10, true).unwrap());
+    /// ```
+    pub fn is_equivalent(
+        &self,
+        other: &Circuit,
+        tolerance: f64,
+        up_to_global_phase: bool,
+    ) -> Result<bool, RoqoqoError> {
+        let number_qubits = self.number_of_qubits().max(other.number_of_qubits());
+        let unitary = self.unitary_matrix_of_size(number_qubits)?;
+        let other_unitary = other.unitary_matrix_of_size(number_qubits)?;
+        let phase = if up_to_global_phase {
+            // The phase of the overlap Tr(U^† V) aligns the global phase of both matrices
+            let overlap: Complex64 = unitary
+                .iter()
+                .zip(other_unitary.iter())
+                .map(|(value, other_value)| value.conj() * other_value)
+                .sum();
+            if overlap.norm() == 0.0 {
+                return Ok(false);
+            }
+            overlap / overlap.norm()
+        } else {
+            Complex64::new(1.0, 0.0)
+        };
+        Ok(unitary
+            .iter()
+            .zip(other_unitary.iter())
+            .all(|(value, other_value)| (value * phase - other_value).norm() <= tolerance))
+    }
+
+    /// Converts the Circuit to an OpenQASM program.
+    ///
+    /// See [crate::qasm::circuit_to_qasm] for the supported operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The OpenQASM version of the output.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The OpenQASM program.
+    /// * `Err(RoqoqoError)` - The Circuit contains operations that have no equivalent in the OpenQASM version.
+    ///
+    /// # Example
+    /// ```
+    /// use roqoqo::Circuit;
+    /// use roqoqo::operations::{CNOT, Hadamard};
+    /// use roqoqo::qasm::QasmVersion;
+    ///
+    /// let mut circuit = Circuit::new();
+    /// circuit += Hadamard::new(0);
+    /// circuit += CNOT::new(0, 1);
+    /// let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    /// assert!(program.ends_with("qubit[2] q;\nh q[0];\ncx q[0], q[1];\n"));
+    /// ```
+    pub fn to_qasm(&self, version: QasmVersion) -> Result<String, RoqoqoError> {
+        circuit_to_qasm(self, version)
+    }
+
+    /// Creates a Circuit from an OpenQASM 2.0 or 3.0 program.
+    ///
+    /// See [crate::qasm::circuit_from_qasm] for the supported statements.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The OpenQASM program.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Circuit)` - The Circuit of the program.
+    /// * `Err(RoqoqoError::QasmParsingError)` - The program can not be parsed.
+    ///
+    /// # Example
+    /// ```
+    /// use roqoqo::Circuit;
+    /// use roqoqo::operations::{CNOT, Hadamard};
+    ///
+    /// let circuit = Circuit::from_qasm("OPENQASM 3.0;\nqubit[2] q;\nh q[0];\ncx q[0], q[1];").unwrap();
+    /// let mut expected = Circuit::new();
+    /// expected += Hadamard::new(0);
+    /// expected += CNOT::new(0, 1);
+    /// assert_eq!(circuit, expected);
+    /// ```
+    pub fn from_qasm(input: &str) -> Result<Circuit, RoqoqoError> {
+        circuit_from_qasm(input)
+    }
+
+    /// Returns the unitary matrix of the Circuit acting on a given number of qubits.
+    fn unitary_matrix_of_size(
+        &self,
+        number_qubits: usize,
+    ) -> Result<Array2<Complex64>, RoqoqoError> {
+        let dimension = u32::try_from(number_qubits)
+            .ok()
+            .and_then(|number_qubits| 1_usize.checked_shl(number_qubits))
+            .filter(|dimension| {
+                dimension
+                    .checked_mul(*dimension)
+                    .and_then(|elements| elements.checked_mul(std::mem::size_of::<Complex64>()))
+                    .is_some_and(|bytes| bytes <= isize::MAX as usize)
+            })
+            .ok_or_else(|| RoqoqoError::GenericError {
+                msg: format!(
+                    "The unitary matrix of {number_qubits} qubits is too large to be represented"
+                ),
+            })?;
+        let mut unitary = Array2::<Complex64>::eye(dimension);
+        for operation in self.operations.iter() {
+            apply_unitary_operation(&mut unitary, operation)?;
+        }
+        Ok(unitary)
+    }
+}
+
+/// Multiplies a unitary matrix from the left with the unitary matrix of an operation.
+fn apply_unitary_operation(
+    unitary: &mut Array2<Complex64>,
+    operation: &Operation,
+) -> Result<(), RoqoqoError> {
+    if let Some(qubits) = gate_qubits(operation) {
+        let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+        let mask: usize = qubits.iter().map(|qubit| 1 << qubit).sum();
+        let mut local = vec![Complex64::new(0.0, 0.0); 1 << qubits.len()];
+        for mut column in unitary.columns_mut() {
+            for base in (0..column.len()).filter(|index| index & mask == 0) {
+                let indices = subspace_indices(base, &qubits);
+                for (value, index) in local.iter_mut().zip(indices.iter()) {
+                    *value = column[*index];
+                }
+                for (row, index) in indices.iter().enumerate() {
+                    column[*index] = matrix
+                        .row(row)
+                        .iter()
+                        .zip(local.iter())
+                        .map(|(element, value)| element * value)
+                        .sum();
+                }
+            }
+        }
+        return Ok(());
+    }
+    match operation {
+        Operation::PragmaGlobalPhase(op) => {
+            let phase = Complex64::from_polar(1.0, f64::try_from(op.phase().clone())?);
+            unitary.mapv_inplace(|value| value * phase);
+            Ok(())
+        }
+        Operation::PragmaAnnotatedOp(op) => apply_unitary_operation(unitary, &op.operation),
+        Operation::PragmaStopParallelBlock(_)
+        | Operation::PragmaSleep(_)
+        | Operation::PragmaStartDecompositionBlock(_)
+        | Operation::PragmaStopDecompositionBlock(_) => Ok(()),
+        _ => Err(RoqoqoError::GenericError {
+            msg: format!(
+                "Operation {} is not unitary, the unitary matrix of the Circuit cannot be computed",
+                operation.hqslang()
+            ),
+        }),
+    }
 }
 
 /// Implements Index Access for Circuit.
@@ -33,6 +33,7 @@ use petgraph::Direction::{Incoming, Outgoing};
 ///
 /// For CircuitDag, the following functions are defined:
 /// * `with_capacity(node_number, edge_number)`: creates an empty CircuitDag with estimated capacity
+/// * `from_circuit_with_commutation(circuit)`: creates a CircuitDag from a Circuit, only connecting operations that do not commute
 /// * `add_to_back(operation)`: adds an Operation to the back of the CircuitDag
 /// * `add_to_front(operation)`: adds an Operation to the front of the CircuitDag
 /// * `execution_blocked(already_executed_indices, index)`: returns the blocking elements of the execution of an Operation, the scope is just the whole graph
@@ -64,6 +65,11 @@ pub struct CircuitDag {
     pub(crate) last_operation_involving_qubit: HashMap<usize, NodeIndex<usize>>,
     pub(crate) first_operation_involving_classical: HashMap<(String, usize), NodeIndex<usize>>,
     pub(crate) last_operation_involving_classical: HashMap<(String, usize), NodeIndex<usize>>,
+    // All nodes without a predecessor (successor) acting on a qubit, for the qubits with several
+    // commuting first (last) operations. Only CircuitDags created with commutation rules have entries.
+    pub(crate) commuting_first_operations_involving_qubit:
+        HashMap<usize, HashSet<NodeIndex<usize>>>,
+    pub(crate) commuting_last_operations_involving_qubit: HashMap<usize, HashSet<NodeIndex<usize>>>,
     _roqoqo_version: RoqoqoVersion,
 }
 
@@ -90,7 +96,7 @@ struct CircuitDagSerializable {
 impl TryFrom<CircuitDagSerializable> for CircuitDag {
     type Error = RoqoqoError;
     fn try_from(value: CircuitDagSerializable) -> Result<Self, Self::Error> {
-        Ok(CircuitDag {
+        let mut dag = CircuitDag {
             _roqoqo_version: RoqoqoVersion,
             graph: value.graph,
             commuting_operations: value.commuting_operations,
@@ -102,7 +108,14 @@ impl TryFrom<CircuitDagSerializable> for CircuitDag {
             last_operation_involving_qubit: value.last_operation_involving_qubit,
             first_operation_involving_classical: value.first_operation_involving_classical,
             last_operation_involving_classical: value.last_operation_involving_classical,
-        })
+            commuting_first_operations_involving_qubit: HashMap::new(),
+            commuting_last_operations_involving_qubit: HashMap::new(),
+        };
+        dag.commuting_first_operations_involving_qubit =
+            dag.commuting_operations_involving_qubit(Incoming);
+        dag.commuting_last_operations_involving_qubit =
+            dag.commuting_operations_involving_qubit(Outgoing);
+        Ok(dag)
     }
 }
 
@@ -163,10 +176,174 @@ impl CircuitDag {
             first_operation_involving_classical: HashMap::<(String, usize), NodeIndex<usize>>::new(
             ),
             last_operation_involving_classical: HashMap::<(String, usize), NodeIndex<usize>>::new(),
+            commuting_first_operations_involving_qubit: HashMap::new(),
+            commuting_last_operations_involving_qubit: HashMap::new(),
             _roqoqo_version: RoqoqoVersion,
         }
     }
 
+    /// Creates a new CircuitDag from a given Circuit using gate-level commutation rules.
+    ///
+    /// In contrast to `CircuitDag::from(circuit)`, operations acting on the same qubits are only connected
+    /// by an edge when they do not commute, so that the CircuitDag exposes the partial order of the circuit.
+    /// Two operations commute when they are gates and
+    ///
+    /// * both gates are diagonal in the computational basis,
+    /// * both gates are Pauli gates or rotations about commuting Pauli strings,
+    /// * a single-qubit gate diagonal in the computational basis acts on a control qubit of a controlled gate,
+    ///   or a single-qubit gate about the axis of the target acts on the target qubit of a controlled gate,
+    /// * or the unitary matrices of both gates commute (only for gates with numeric parameters).
+    ///
+    /// All other operations, such as measurements and PRAGMAs, are ordered like in `CircuitDag::from(circuit)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit the CircuitDag is created from.
+    ///
+    /// # Returns
+    ///
+    /// * `CircuitDag` - The CircuitDag containing only the dependencies between non-commuting operations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roqoqo::operations::{CNOT, RotateZ};
+    /// use roqoqo::{Circuit, CircuitDag};
+    ///
+    /// let mut circuit = Circuit::new();
+    /// circuit += CNOT::new(0, 1);
+    /// circuit += RotateZ::new(0, "theta".into());
+    /// let dag = CircuitDag::from_circuit_with_commutation(circuit);
+    /// assert_eq!(dag.first_parallel_block().len(), 2);
+    /// ```
+    pub fn from_circuit_with_commutation(circuit: Circuit) -> Self {
+        let mut dag = CircuitDag::with_capacity(circuit.len(), circuit.operations().len());
+        // The nodes acting on each qubit since the last operation involving all qubits
+        let mut qubit_nodes: HashMap<usize, Vec<NodeIndex<usize>>> = HashMap::new();
+        // The (node, qubit) pairs where all previous nodes on the qubit are predecessors of the node
+        let mut barriers: HashSet<(NodeIndex<usize>, usize)> = HashSet::new();
+        // The nodes involving qubits without successors
+        let mut sinks: HashSet<NodeIndex<usize>> = HashSet::new();
+
+        for operation in circuit.iter() {
+            let node = dag.graph.add_node(operation.clone()).index();
+            match operation.involved_qubits() {
+                InvolvedQubits::None => {
+                    let is_definition = matches!(
+                        operation,
+                        Operation::DefinitionBit(_)
+                            | Operation::DefinitionFloat(_)
+                            | Operation::DefinitionUsize(_)
+                            | Operation::DefinitionComplex(_)
+                    );
+                    if is_definition
+                        || matches!(operation.involved_classical(), InvolvedClassical::None)
+                    {
+                        dag.commuting_operations.push(node);
+                    }
+                }
+                InvolvedQubits::Set(qubits) => {
+                    let mut qubits: Vec<usize> = qubits.into_iter().collect();
+                    qubits.sort_unstable();
+                    let mut predecessors: HashSet<NodeIndex<usize>> = HashSet::new();
+                    for qubit in qubits.iter() {
+                        let mut is_barrier = true;
+                        for previous in qubit_nodes.get(qubit).into_iter().flatten().rev() {
+                            let previous_operation =
+                                dag.graph.node_weight((*previous).into()).unwrap();
+                            if operations_commute(previous_operation, operation) {
+                                is_barrier = false;
+                                continue;
+                            }
+                            predecessors.insert(*previous);
+                            if barriers.contains(&(*previous, *qubit)) {
+                                break;
+                            }
+                        }
+                        if is_barrier {
+                            barriers.insert((node, *qubit));
+                        }
+                        qubit_nodes.entry(*qubit).or_default().push(node);
+                    }
+                    if predecessors.is_empty() {
+                        if let Some(last_all) = dag.last_all {
+                            predecessors.insert(last_all);
+                        }
+                    }
+                    for previous in predecessors.iter() {
+                        dag.graph.update_edge((*previous).into(), node.into(), ());
+                        sinks.remove(previous);
+                    }
+                    sinks.insert(node);
+                    if predecessors.is_empty() {
+                        dag.first_parallel_block.insert(node);
+                    }
+                    for qubit in qubits {
+                        if dag
+                            .last_operation_involving_qubit
+                            .insert(qubit, node)
+                            .is_none()
+                        {
+                            dag.first_operation_involving_qubit
+                                .insert(qubit, dag.last_all.unwrap_or(node));
+                        }
+                    }
+                }
+                InvolvedQubits::All => {
+                    for previous in sinks.drain() {
+                        dag.graph.update_edge(previous.into(), node.into(), ());
+                    }
+                    sinks.insert(node);
+                    qubit_nodes.clear();
+                    barriers.clear();
+                    dag.update_from_all_operation_back(node);
+                }
+            }
+            if !dag.is_definition_classical_populate(node, operation.clone()) {
+                dag.update_classical_back(node, operation.clone());
+            }
+        }
+        dag.last_parallel_block = sinks;
+        dag.commuting_first_operations_involving_qubit =
+            dag.commuting_operations_involving_qubit(Incoming);
+        dag.commuting_last_operations_involving_qubit =
+            dag.commuting_operations_involving_qubit(Outgoing);
+        dag
+    }
+
+    /// Returns the nodes acting on a qubit without a predecessor (`Incoming`) or successor (`Outgoing`)
+    /// acting on the qubit, for all qubits with more than one such node.
+    ///
+    /// # Arguments
+    ///
+    /// * 'direction' - Whether the first (`Incoming`) or last (`Outgoing`) operations are returned.
+    fn commuting_operations_involving_qubit(
+        &self,
+        direction: petgraph::Direction,
+    ) -> HashMap<usize, HashSet<NodeIndex<usize>>> {
+        let mut operations: HashMap<usize, HashSet<NodeIndex<usize>>> = HashMap::new();
+        for node in self.graph.node_indices() {
+            let InvolvedQubits::Set(qubits) = self.graph[node].involved_qubits() else {
+                continue;
+            };
+            for qubit in qubits {
+                let bounded = self
+                    .graph
+                    .neighbors_directed(node, direction)
+                    .any(|neighbour| match self.graph[neighbour].involved_qubits() {
+                        InvolvedQubits::All => true,
+                        InvolvedQubits::Set(qubits) => qubits.contains(&qubit),
+                        InvolvedQubits::None => false,
+                    });
+                if !bounded {
+                    operations.entry(qubit).or_default().insert(node.index());
+                }
+            }
+        }
+        operations.retain(|_, nodes| nodes.len() > 1);
+        operations
+    }
+
     /// Adds an operation to the back of the CircuitDag, if necessary.
     ///
     /// # Arguments
@@ -249,6 +426,15 @@ impl CircuitDag {
         if let Some(&i) = self.last_operation_involving_qubit.get(&qubit) {
             self.graph.update_edge(i.into(), node.into(), ());
             self.last_parallel_block.remove(&i);
+            for commuting in self
+                .commuting_last_operations_involving_qubit
+                .remove(&qubit)
+                .into_iter()
+                .flatten()
+            {
+                self.graph.update_edge(commuting.into(), node.into(), ());
+                self.last_parallel_block.remove(&commuting);
+            }
         } else if let Some(la) = self.last_all {
             self.graph
                 .update_edge(self.last_all.unwrap().into(), node.into(), ());
@@ -304,6 +490,13 @@ impl CircuitDag {
                 self.graph.update_edge(old_node.into(), node.into(), ());
                 temp_map.insert(qubit, node);
             }
+            for commuting in self
+                .commuting_last_operations_involving_qubit
+                .drain()
+                .flat_map(|(_, nodes)| nodes)
+            {
+                self.graph.update_edge(commuting.into(), node.into(), ());
+            }
             self.last_operation_involving_qubit = temp_map;
         }
     }
@@ -390,6 +583,15 @@ impl CircuitDag {
         if let Some(&i) = self.first_operation_involving_qubit.get(&qubit) {
             self.graph.update_edge(node.into(), i.into(), ());
             self.first_parallel_block.remove(&i);
+            for commuting in self
+                .commuting_first_operations_involving_qubit
+                .remove(&qubit)
+                .into_iter()
+                .flatten()
+            {
+                self.graph.update_edge(node.into(), commuting.into(), ());
+                self.first_parallel_block.remove(&commuting);
+            }
         } else if let Some(fa) = self.first_all {
             self.graph
                 .update_edge(node.into(), self.first_all.unwrap().into(), ());
@@ -445,6 +647,13 @@ impl CircuitDag {
                 self.graph.update_edge(node.into(), old_node.into(), ());
                 temp_map.insert(qubit, node);
             }
+            for commuting in self
+                .commuting_first_operations_involving_qubit
+                .drain()
+                .flat_map(|(_, nodes)| nodes)
+            {
+                self.graph.update_edge(node.into(), commuting.into(), ());
+            }
             self.first_operation_involving_qubit = temp_map;
         }
     }
@@ -816,6 +1025,8 @@ impl From<Circuit> for CircuitDag {
             first_operation_involving_classical: HashMap::<(String, usize), NodeIndex<usize>>::new(
             ),
             last_operation_involving_classical: HashMap::<(String, usize), NodeIndex<usize>>::new(),
+            commuting_first_operations_involving_qubit: HashMap::new(),
+            commuting_last_operations_involving_qubit: HashMap::new(),
             _roqoqo_version: RoqoqoVersion,
         };
 
@@ -892,6 +1103,180 @@ impl Iterator for ParallelBlocks<'_> {
     }
 }
 
+/// Maximum number of qubits for which the commutation of two gates is checked with their unitary matrices.
+const MAX_NUMERIC_COMMUTATION_QUBITS: usize = 6;
+
+/// Returns true when two operations acting on overlapping qubits commute.
+///
+/// Only gates are considered to commute, all other operations are never reordered.
+fn operations_commute(first: &Operation, second: &Operation) -> bool {
+    let (first_gate, second_gate) = match (
+        GateOperation::try_from(first.clone()),
+        GateOperation::try_from(second.clone()),
+    ) {
+        (Ok(first_gate), Ok(second_gate)) => (first_gate, second_gate),
+        _ => return false,
+    };
+    if is_diagonal(first) && is_diagonal(second) {
+        return true;
+    }
+    if let (Some(first_string), Some(second_string)) = (pauli_string(first), pauli_string(second)) {
+        let anticommuting = first_string
+            .iter()
+            .filter(|(qubit, pauli)| {
+                second_string
+                    .get(qubit)
+                    .is_some_and(|other| other != *pauli)
+            })
+            .count();
+        return anticommuting % 2 == 0;
+    }
+    if commutes_through_controls(first, second) || commutes_through_controls(second, first) {
+        return true;
+    }
+    unitaries_commute(first_gate, second_gate)
+}
+
+/// Returns true when the gate is diagonal in the computational basis.
+fn is_diagonal(operation: &Operation) -> bool {
+    matches!(
+        operation,
+        Operation::Identity(_)
+            | Operation::PauliZ(_)
+            | Operation::RotateZ(_)
+            | Operation::SGate(_)
+            | Operation::InvSGate(_)
+            | Operation::TGate(_)
+            | Operation::InvTGate(_)
+            | Operation::PhaseShiftState0(_)
+            | Operation::PhaseShiftState1(_)
+            | Operation::ControlledPauliZ(_)
+            | Operation::ControlledPhaseShift(_)
+            | Operation::PhaseShiftedControlledZ(_)
+            | Operation::PhaseShiftedControlledPhase(_)
+            | Operation::ControlledControlledPauliZ(_)
+            | Operation::ControlledControlledPhaseShift(_)
+            | Operation::PhaseShiftedControlledControlledZ(_)
+            | Operation::PhaseShiftedControlledControlledPhase(_)
+            | Operation::TripleControlledPauliZ(_)
+            | Operation::TripleControlledPhaseShift(_)
+            | Operation::MultiQubitZZ(_)
+    )
+}
+
+/// Returns the Pauli string P of a gate that is a Pauli gate or a rotation exp(-i θ P) up to a global phase.
+fn pauli_string(operation: &Operation) -> Option<HashMap<usize, char>> {
+    let single = |qubit: usize, pauli: char| Some(HashMap::from([(qubit, pauli)]));
+    match operation {
+        Operation::Identity(_) => Some(HashMap::new()),
+        Operation::PauliX(op) => single(*op.qubit(), 'X'),
+        Operation::RotateX(op) => single(*op.qubit(), 'X'),
+        Operation::SqrtPauliX(op) => single(*op.qubit(), 'X'),
+        Operation::InvSqrtPauliX(op) => single(*op.qubit(), 'X'),
+        Operation::SXGate(op) => single(*op.qubit(), 'X'),
+        Operation::InvSXGate(op) => single(*op.qubit(), 'X'),
+        Operation::PauliY(op) => single(*op.qubit(), 'Y'),
+        Operation::RotateY(op) => single(*op.qubit(), 'Y'),
+        Operation::SqrtPauliY(op) => single(*op.qubit(), 'Y'),
+        Operation::InvSqrtPauliY(op) => single(*op.qubit(), 'Y'),
+        Operation::PauliZ(op) => single(*op.qubit(), 'Z'),
+        Operation::RotateZ(op) => single(*op.qubit(), 'Z'),
+        Operation::SGate(op) => single(*op.qubit(), 'Z'),
+        Operation::InvSGate(op) => single(*op.qubit(), 'Z'),
+        Operation::TGate(op) => single(*op.qubit(), 'Z'),
+        Operation::InvTGate(op) => single(*op.qubit(), 'Z'),
+        Operation::PhaseShiftState0(op) => single(*op.qubit(), 'Z'),
+        Operation::PhaseShiftState1(op) => single(*op.qubit(), 'Z'),
+        Operation::MolmerSorensenXX(op) => {
+            Some(HashMap::from([(*op.control(), 'X'), (*op.target(), 'X')]))
+        }
+        Operation::VariableMSXX(op) => {
+            Some(HashMap::from([(*op.control(), 'X'), (*op.target(), 'X')]))
+        }
+        Operation::MultiQubitMS(op) => {
+            Some(op.qubits().iter().map(|qubit| (*qubit, 'X')).collect())
+        }
+        Operation::MultiQubitZZ(op) => {
+            Some(op.qubits().iter().map(|qubit| (*qubit, 'Z')).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Returns true when `single` is a single-qubit gate that commutes with the controlled gate `controlled`.
+///
+/// Single-qubit gates diagonal in the computational basis commute through control qubits,
+/// single-qubit gates about the axis of the target operation commute through the target qubit.
+fn commutes_through_controls(single: &Operation, controlled: &Operation) -> bool {
+    let (controls, target, target_axis): (Vec<usize>, usize, Option<char>) = match controlled {
+        Operation::CNOT(op) => (vec![*op.control()], *op.target(), Some('X')),
+        Operation::ControlledPauliY(op) => (vec![*op.control()], *op.target(), Some('Y')),
+        Operation::ControlledRotateX(op) => (vec![*op.control()], *op.target(), Some('X')),
+        Operation::ControlledRotateXY(op) => (vec![*op.control()], *op.target(), None),
+        Operation::Toffoli(op) => (
+            vec![*op.control_0(), *op.control_1()],
+            *op.target(),
+            Some('X'),
+        ),
+        Operation::TripleControlledPauliX(op) => (
+            vec![*op.control_0(), *op.control_1(), *op.control_2()],
+            *op.target(),
+            Some('X'),
+        ),
+        Operation::MultiQubitCNOT(op) => match op.qubits().split_last() {
+            Some((target, controls)) => (controls.to_vec(), *target, Some('X')),
+            None => return false,
+        },
+        _ => return false,
+    };
+    let Some(single_string) = pauli_string(single) else {
+        return false;
+    };
+    if single_string.len() != 1 {
+        return single_string.is_empty();
+    }
+    let (qubit, axis) = single_string.into_iter().next().unwrap();
+    (controls.contains(&qubit) && axis == 'Z') || (qubit == target && Some(axis) == target_axis)
+}
+
+/// Returns true when the unitary matrices of two gates with numeric parameters commute.
+fn unitaries_commute(first: GateOperation, second: GateOperation) -> bool {
+    let mut qubits: Vec<usize> = Vec::new();
+    for gate in [&first, &second] {
+        if let InvolvedQubits::Set(gate_qubits) = gate.involved_qubits() {
+            qubits.extend(gate_qubits);
+        }
+    }
+    qubits.sort_unstable();
+    qubits.dedup();
+    if qubits.len() > MAX_NUMERIC_COMMUTATION_QUBITS {
+        return false;
+    }
+    // Remap the qubits to the lowest indices to keep the unitary matrices small,
+    // the mapping is completed to a permutation as required by remap_qubits
+    let mut mapping: HashMap<usize, usize> = qubits
+        .iter()
+        .enumerate()
+        .map(|(index, qubit)| (*qubit, index))
+        .collect();
+    let free_indices = (0..qubits.len()).filter(|index| !mapping.contains_key(index));
+    let moved_qubits = qubits.iter().filter(|qubit| **qubit >= qubits.len());
+    mapping.extend(free_indices.zip(moved_qubits.copied()).collect::<Vec<_>>());
+    let (Ok(first), Ok(second)) = (first.remap_qubits(&mapping), second.remap_qubits(&mapping))
+    else {
+        return false;
+    };
+    let mut first_second = Circuit::new();
+    first_second += Operation::from(first.clone());
+    first_second += Operation::from(second.clone());
+    let mut second_first = Circuit::new();
+    second_first += Operation::from(second);
+    second_first += Operation::from(first);
+    first_second
+        .is_equivalent(&second_first, 1e-10, false)
+        .unwrap_or(false)
+}
+
 impl crate::operations::SupportedVersion for CircuitDag {
     fn minimum_supported_roqoqo_version(&self) -> (u32, u32, u32) {
         let mut current_minimum_version = (1, 0, 0);
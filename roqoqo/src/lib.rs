@@ -228,6 +228,12 @@ pub enum RoqoqoError {
         /// Error message.
         msg: String,
     },
+    /// Error occured in classical shadow measurement.
+    #[error("Error occured in classical shadow measurement. {msg}")]
+    ClassicalShadowMeasurementError {
+        /// Error message.
+        msg: String,
+    },
     /// Error serializing an internal roqoqo object
     #[error("An error occured serializing a roqoqo object: {msg} ")]
     SerializationError {
@@ -240,6 +246,30 @@ pub enum RoqoqoError {
         /// Generic error message
         msg: String,
     },
+    /// Error parsing an OpenQASM program.
+    #[error("Error parsing OpenQASM at line {line}, column {column}: {msg}")]
+    QasmParsingError {
+        /// Line of the error in the OpenQASM program (starting at 1).
+        line: usize,
+        /// Column of the error in the OpenQASM program (starting at 1).
+        column: usize,
+        /// Error message.
+        msg: String,
+    },
+    /// Error when named parameters are not input parameters of a QuantumProgram.
+    #[error("Unknown parameters {names:?}, the input parameters of the QuantumProgram are {input_parameter_names:?}")]
+    UnknownInputParameters {
+        /// Names of the parameters that are not input parameters.
+        names: Vec<String>,
+        /// Names of the input parameters of the QuantumProgram.
+        input_parameter_names: Vec<String>,
+    },
+    /// Error when input parameters of a QuantumProgram have no value.
+    #[error("Missing values for the input parameters {names:?}")]
+    MissingInputParameters {
+        /// Names of the input parameters without a value.
+        names: Vec<String>,
+    },
     /// Error when trying to deserialize roqoqo data created with an incompatible version of roqoqo
     #[error("Version conflict. Data created with roqoqo version: {data_major_version}.{data_minor_version} could not be deserialized to Library version: {library_major_version}.{library_minor_version}. Please update your libraries to compatible versions or use the data conversion tool.")]
     VersionMissmatch {
@@ -421,9 +451,13 @@ pub mod devices;
 pub mod measurements;
 pub mod operations;
 pub mod prelude;
+pub mod qasm;
 #[doc(hidden)]
 mod quantum_program;
 pub mod registers;
-pub use quantum_program::QuantumProgram;
+#[cfg(feature = "simulators")]
+pub mod simulators;
+pub mod transpiler;
+pub use quantum_program::{ParameterShiftPrograms, QuantumProgram};
 
 pub mod noise_models;
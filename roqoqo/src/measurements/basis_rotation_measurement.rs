@@ -11,9 +11,17 @@
 // limitations under the License.
 
 use super::*;
+use crate::operations::{
+    DefinitionBit, Hadamard, Operation, PragmaRepeatedMeasurement, PragmaSetNumberOfMeasurements,
+    RotateX,
+};
+use crate::CalculatorFloat;
 use ndarray::{Array1, Array2};
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliOperator, SinglePauliOperator};
 
 /// Collected information for executing a measurement of PauliZ product.
 #[derive(Debug, PartialEq, Clone)]
@@ -28,6 +36,361 @@ pub struct PauliZProduct {
     pub input: PauliZProductInput,
 }
 
+impl PauliZProduct {
+    /// Creates a PauliZProduct measurement of the expectation value of a Hamiltonian.
+    ///
+    /// The terms of the Hamiltonian are grouped greedily into sets of qubit-wise commuting Pauli products.
+    /// For each set a Circuit is generated that rotates every measured qubit into the Z-basis
+    /// (Hadamard for X, RotateX(pi/2) for Y) and reads out all qubits with a [crate::operations::PragmaRepeatedMeasurement]
+    /// into the bit register `ro_{index of set}`.
+    /// The input contains a linear expectation value `name` reconstructing the Hamiltonian.
+    ///
+    /// # Arguments
+    ///
+    /// * `hamiltonian` - The Hamiltonian whose expectation value is measured.
+    /// * `name` - The name of the measured expectation value.
+    /// * `number_qubits` - The number of qubits in the measured circuits.
+    /// * `number_measurements` - The number of projective measurements for each basis rotation circuit.
+    /// * `constant_circuit` - The constant Circuit that is executed before each basis rotation circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)` - The measurement of the Hamiltonian expectation value.
+    /// * `Err([RoqoqoError::PauliProductExceedsQubits])` - A term of the Hamiltonian acts on a qubit exceeding number_qubits.
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - A coefficient of the Hamiltonian is symbolic.
+    pub fn from_pauli_hamiltonian(
+        hamiltonian: &PauliHamiltonian,
+        name: String,
+        number_qubits: usize,
+        number_measurements: usize,
+        constant_circuit: Option<Circuit>,
+    ) -> Result<Self, RoqoqoError> {
+        // Greedy grouping: every term is added to the first group it commutes qubit-wise with
+        let mut groups: Vec<HashMap<usize, SinglePauliOperator>> = Vec::new();
+        let mut group_indices: Vec<usize> = Vec::new();
+        for (product, _) in hamiltonian.iter() {
+            let position = groups.iter().position(|group| {
+                product
+                    .iter()
+                    .all(|(qubit, pauli)| group.get(qubit).map_or(true, |p| p == pauli))
+            });
+            let index = match position {
+                Some(index) => index,
+                None => {
+                    groups.push(HashMap::new());
+                    groups.len() - 1
+                }
+            };
+            groups[index].extend(product.iter().copied());
+            group_indices.push(index);
+        }
+        if groups.is_empty() {
+            groups.push(HashMap::new());
+        }
+
+        let mut input = PauliZProductInput::new(number_qubits, false);
+        let mut linear: HashMap<usize, f64> = HashMap::new();
+        for ((product, coefficient), group_index) in hamiltonian.iter().zip(group_indices) {
+            let coefficient =
+                coefficient
+                    .float()
+                    .map_err(|_| RoqoqoError::PauliZProductMeasurementError {
+                        msg: format!(
+                            "Coefficient {coefficient} of Pauli product {product} is not a float"
+                        ),
+                    })?;
+            let mask: PauliProductMask = product.iter().map(|(qubit, _)| *qubit).collect();
+            let index = input.add_pauliz_product(format!("ro_{group_index}"), mask)?;
+            *linear.entry(index).or_insert(0.0) += coefficient;
+        }
+        input.add_linear_exp_val(name, linear)?;
+
+        let mut circuits: Vec<Circuit> = Vec::with_capacity(groups.len());
+        for (group_index, group) in groups.iter().enumerate() {
+            let readout = format!("ro_{group_index}");
+            let mut circuit = Circuit::new();
+            circuit += DefinitionBit::new(readout.clone(), number_qubits, true);
+            let mut qubits: Vec<&usize> = group.keys().collect();
+            qubits.sort();
+            for qubit in qubits {
+                match group[qubit] {
+                    SinglePauliOperator::X => circuit += Hadamard::new(*qubit),
+                    SinglePauliOperator::Y => {
+                        circuit += RotateX::new(*qubit, CalculatorFloat::FRAC_PI_2)
+                    }
+                    SinglePauliOperator::Z | SinglePauliOperator::Identity => (),
+                }
+            }
+            circuit += PragmaRepeatedMeasurement::new(readout, number_measurements, None);
+            circuits.push(circuit);
+        }
+
+        Ok(Self {
+            constant_circuit,
+            circuits,
+            input,
+        })
+    }
+
+    /// Creates a PauliZProduct measurement of the expectation value of a hermitian PauliOperator.
+    ///
+    /// See [PauliZProduct::from_pauli_hamiltonian] for the construction of the measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `operator` - The hermitian operator whose expectation value is measured.
+    /// * `name` - The name of the measured expectation value.
+    /// * `number_qubits` - The number of qubits in the measured circuits.
+    /// * `number_measurements` - The number of projective measurements for each basis rotation circuit.
+    /// * `constant_circuit` - The constant Circuit that is executed before each basis rotation circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)` - The measurement of the operator expectation value.
+    /// * `Err([RoqoqoError::PauliProductExceedsQubits])` - A term of the operator acts on a qubit exceeding number_qubits.
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - The operator is not hermitian or has symbolic coefficients.
+    pub fn from_pauli_operator(
+        operator: &PauliOperator,
+        name: String,
+        number_qubits: usize,
+        number_measurements: usize,
+        constant_circuit: Option<Circuit>,
+    ) -> Result<Self, RoqoqoError> {
+        let hamiltonian = PauliHamiltonian::try_from(operator.clone()).map_err(|err| {
+            RoqoqoError::PauliZProductMeasurementError {
+                msg: format!("PauliOperator can not be converted to PauliHamiltonian: {err}"),
+            }
+        })?;
+        Self::from_pauli_hamiltonian(
+            &hamiltonian,
+            name,
+            number_qubits,
+            number_measurements,
+            constant_circuit,
+        )
+    }
+
+    /// Returns a copy of the measurement with a total shot budget distributed over the circuits.
+    ///
+    /// The number of measurements of every circuit is chosen to minimise the variance of the linear
+    /// expectation value `expectation_value`.
+    /// The standard deviation of the contribution of a readout register is bounded by the sum of the
+    /// absolute coefficients of its (non-identity) Pauli products, so the variance is minimised by
+    /// measurement numbers proportional to these sums.
+    /// Every measured circuit keeps at least one measurement and the rounded numbers add up exactly
+    /// to `total_number_measurements`.
+    ///
+    /// The numbers of measurements are rewritten in all [crate::operations::PragmaRepeatedMeasurement]
+    /// and [crate::operations::PragmaSetNumberOfMeasurements] operations reading out into a register of the input.
+    /// Circuits without such operations are not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `expectation_value` - The name of the linear expectation value whose variance is minimised.
+    /// * `total_number_measurements` - The total number of measurements distributed over all circuits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)` - The measurement with the new numbers of measurements.
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - The expectation value is not linear, does not exist or the budget is smaller than the number of measured circuits.
+    pub fn allocate_shots(
+        &self,
+        expectation_value: &str,
+        total_number_measurements: usize,
+    ) -> Result<Self, RoqoqoError> {
+        let linear = match self.input.measured_exp_vals.get(expectation_value) {
+            Some(PauliProductsToExpVal::Linear(linear)) => linear,
+            Some(PauliProductsToExpVal::Symbolic(_)) => {
+                return Err(RoqoqoError::PauliZProductMeasurementError {
+                    msg: format!(
+                        "Shots can only be allocated for linear expectation values, {expectation_value} is symbolic"
+                    ),
+                })
+            }
+            None => {
+                return Err(RoqoqoError::PauliZProductMeasurementError {
+                    msg: format!("Expectation value {expectation_value} not found"),
+                })
+            }
+        };
+        let register_weights: HashMap<&str, f64> = self
+            .input
+            .pauli_product_qubit_masks
+            .iter()
+            .map(|(register_name, masks)| {
+                let weight: f64 = masks
+                    .iter()
+                    .filter(|(_, mask)| !mask.is_empty())
+                    .filter_map(|(index, _)| linear.get(index))
+                    .map(|coefficient| coefficient.abs())
+                    .sum();
+                (register_name.as_str(), weight)
+            })
+            .collect();
+        let register_weight = |readout: &str| -> Option<f64> {
+            register_weights.get(readout).copied().or_else(|| {
+                readout
+                    .strip_suffix("_flipped")
+                    .filter(|_| self.input.use_flipped_measurement)
+                    .and_then(|register| register_weights.get(register).copied())
+            })
+        };
+
+        // Weights of the circuits reading out into at least one register of the input
+        let circuit_weights: Vec<Option<f64>> = self
+            .circuits
+            .iter()
+            .map(|circuit| {
+                let readouts: HashSet<&String> = circuit
+                    .iter()
+                    .filter_map(|operation| match operation {
+                        Operation::PragmaRepeatedMeasurement(op) => Some(op.readout()),
+                        Operation::PragmaSetNumberOfMeasurements(op) => Some(op.readout()),
+                        _ => None,
+                    })
+                    .collect();
+                readouts
+                    .into_iter()
+                    .filter_map(|readout| register_weight(readout))
+                    .reduce(|sum, weight| sum + weight)
+            })
+            .collect();
+        let number_measured_circuits = circuit_weights.iter().flatten().count();
+        if total_number_measurements < number_measured_circuits {
+            return Err(RoqoqoError::PauliZProductMeasurementError {
+                msg: format!(
+                    "Total number of measurements {total_number_measurements} is smaller than the number of measured circuits {number_measured_circuits}"
+                ),
+            });
+        }
+        let total_weight: f64 = circuit_weights.iter().flatten().sum();
+        let remaining = (total_number_measurements - number_measured_circuits) as f64;
+
+        // Proportional allocation on top of one measurement per circuit, remainder assigned by largest fractional part
+        let mut allocations: Vec<Option<(usize, f64)>> = circuit_weights
+            .iter()
+            .map(|weight| {
+                weight.map(|weight| {
+                    let share = if total_weight > 0.0 {
+                        remaining * weight / total_weight
+                    } else {
+                        remaining / number_measured_circuits as f64
+                    };
+                    (1 + share.floor() as usize, share - share.floor())
+                })
+            })
+            .collect();
+        let allocated: usize = allocations.iter().flatten().map(|(number, _)| number).sum();
+        let mut by_fraction: Vec<usize> = (0..allocations.len())
+            .filter(|index| allocations[*index].is_some())
+            .collect();
+        by_fraction.sort_by(|a, b| {
+            let fraction = |index: &usize| allocations[*index].map_or(0.0, |(_, f)| f);
+            fraction(b).total_cmp(&fraction(a))
+        });
+        for index in by_fraction
+            .into_iter()
+            .take(total_number_measurements.saturating_sub(allocated))
+        {
+            if let Some((number, _)) = allocations[index].as_mut() {
+                *number += 1;
+            }
+        }
+
+        let circuits = self
+            .circuits
+            .iter()
+            .zip(allocations)
+            .map(|(circuit, allocation)| match allocation {
+                None => circuit.clone(),
+                Some((number_measurements, _)) => {
+                    let mut new_circuit = Circuit::new();
+                    for operation in circuit.iter() {
+                        new_circuit += match operation {
+                            Operation::PragmaRepeatedMeasurement(op)
+                                if register_weight(op.readout()).is_some() =>
+                            {
+                                PragmaRepeatedMeasurement::new(
+                                    op.readout().clone(),
+                                    number_measurements,
+                                    op.qubit_mapping().clone(),
+                                )
+                                .into()
+                            }
+                            Operation::PragmaSetNumberOfMeasurements(op)
+                                if register_weight(op.readout()).is_some() =>
+                            {
+                                PragmaSetNumberOfMeasurements::new(
+                                    number_measurements,
+                                    op.readout().clone(),
+                                )
+                                .into()
+                            }
+                            _ => operation.clone(),
+                        };
+                    }
+                    new_circuit
+                }
+            })
+            .collect();
+        Ok(Self {
+            constant_circuit: self.constant_circuit.clone(),
+            circuits,
+            input: self.input.clone(),
+        })
+    }
+
+    /// Executes the PauliZ product measurement and mitigates readout errors.
+    ///
+    /// Equivalent to [MeasureExpectationValues::evaluate] with the measured Pauli products
+    /// corrected for the readout errors given by `readout_error_mitigation`
+    /// instead of the mitigation stored in the measurement input.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `readout_error_mitigation` - The readout errors of the measured qubits and how they are mitigated.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(HashMap<String, f64>))` - The measurement has been evaluated successfully. The HashMap contains the measured expectation values.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - An error occured in PauliZ product measurement
+    ///   or the measurement fidelity of a qubit is not in the range (0, 1].
+    pub fn evaluate_with_readout_error_mitigation(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        readout_error_mitigation: &ReadoutErrorMitigation,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoError> {
+        self.expectation_values(&bit_registers, Some(readout_error_mitigation))
+    }
+
+    /// Executes the PauliZ product measurement, mitigates readout errors and estimates the statistical errors.
+    ///
+    /// Equivalent to [MeasureExpectationValues::evaluate_with_errors] with the measured Pauli products
+    /// corrected for the readout errors given by `readout_error_mitigation`
+    /// instead of the mitigation stored in the measurement input.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `readout_error_mitigation` - The readout errors of the measured qubits and how they are mitigated.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExpectationValuesWithErrors))` - The measurement has been evaluated successfully.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - An error occured in PauliZ product measurement
+    ///   or the measurement fidelity of a qubit is not in the range (0, 1].
+    pub fn evaluate_with_errors_and_readout_error_mitigation(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        readout_error_mitigation: &ReadoutErrorMitigation,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        self.expectation_values_with_errors(&bit_registers, Some(readout_error_mitigation))
+    }
+}
+
 impl Measure for PauliZProduct {
     /// Returns the constant Circuit that is executed before each Circuit in circuits.
     ///
@@ -87,10 +450,11 @@ impl Measure for PauliZProduct {
 }
 
 impl MeasureExpectationValues for PauliZProduct {
-    // TODO add optional device later for use with flipped measurement
     #[allow(unused_variables)]
     /// Executes the PauliZ product measurement.
     ///
+    /// Readout errors are mitigated with the `readout_error_mitigation` of the measurement input.
+    ///
     /// # Arguments
     ///
     /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
@@ -109,31 +473,110 @@ impl MeasureExpectationValues for PauliZProduct {
         float_registers: HashMap<String, FloatOutputRegister>,
         complex_registers: HashMap<String, ComplexOutputRegister>,
     ) -> Result<Option<HashMap<String, f64>>, RoqoqoError> {
-        // todo replace with actual input
-        let measurement_fidelities = vec![1.0; self.input.number_qubits];
+        self.expectation_values(&bit_registers, self.input.readout_error_mitigation.as_ref())
+    }
 
-        // Setting up measurement correction factors for flipped measurement
-        let mut measurement_correction_factors: HashMap<String, Vec<f64>> = HashMap::new();
-        let flipped_and_extension: Vec<(bool, &'static str)>;
-        if self.input.use_flipped_measurement {
-            // helper vector to iterate over when evaluating the pauli products
-            flipped_and_extension = vec![(false, ""), (true, "_flipped")];
-            for (name, pauli_product_mask) in self.input.pauli_product_qubit_masks.iter() {
-                let mut measurement_correction_factor: Vec<f64> =
-                    (0..self.input.number_pauli_products).map(|_| 1.0).collect();
-                for (pp_index, indices) in pauli_product_mask.iter() {
-                    if !indices.is_empty() {
-                        for i in indices {
-                            measurement_correction_factor[*pp_index] *= measurement_fidelities[*i]
-                        }
-                    }
-                }
-                measurement_correction_factors.insert(name.clone(), measurement_correction_factor);
+    #[allow(unused_variables)]
+    /// Executes the PauliZ product measurement and estimates the statistical errors from the single shots.
+    ///
+    /// The covariance of the Pauli product mean values is estimated from the sample covariance of the
+    /// single shot values of every readout register and propagated to the expectation values
+    /// (linearly for symbolic expectation values).
+    /// Registers with a single shot have undefined (NaN) errors.
+    /// Readout errors are mitigated with the `readout_error_mitigation` of the measurement input.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `float_registers` - The classical float registers as a HashMap with the register name as key.
+    /// * `complex_registers` - The classical complex registers as a HashMap with the register name as key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExpectationValuesWithErrors))` - The measurement has been evaluated successfully.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - An error occured in PauliZ product measurement.
+    fn evaluate_with_errors(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        self.expectation_values_with_errors(
+            &bit_registers,
+            self.input.readout_error_mitigation.as_ref(),
+        )
+    }
+}
+
+impl PauliZProduct {
+    /// Evaluates the expectation values, optionally mitigating readout errors.
+    fn expectation_values(
+        &self,
+        bit_registers: &HashMap<String, BitOutputRegister>,
+        mitigation: Option<&ReadoutErrorMitigation>,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoError> {
+        let (pauli_products, _) = self.pauli_product_statistics(bit_registers, mitigation)?;
+        // Evaluating expectation values
+        let mut results: HashMap<String, f64> = HashMap::new();
+        for (name, evaluation) in self.input.measured_exp_vals.iter() {
+            results.insert(name.clone(), evaluation.evaluate(&pauli_products)?);
+        }
+        Ok(Some(results))
+    }
+
+    /// Evaluates the expectation values and their statistical errors, optionally mitigating readout errors.
+    fn expectation_values_with_errors(
+        &self,
+        bit_registers: &HashMap<String, BitOutputRegister>,
+        mitigation: Option<&ReadoutErrorMitigation>,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        let (pauli_products, covariance) =
+            self.pauli_product_statistics(bit_registers, mitigation)?;
+        let mut values: HashMap<String, f64> = HashMap::new();
+        let mut gradients: HashMap<&String, Array1<f64>> = HashMap::new();
+        for (name, evaluation) in self.input.measured_exp_vals.iter() {
+            values.insert(name.clone(), evaluation.evaluate(&pauli_products)?);
+            gradients.insert(name, evaluation.gradient(&pauli_products)?);
+        }
+        let mut covariances: HashMap<(String, String), f64> = HashMap::new();
+        for (first_name, first_gradient) in gradients.iter() {
+            let propagated = covariance.dot(first_gradient);
+            for (second_name, second_gradient) in gradients.iter() {
+                covariances.insert(
+                    ((*first_name).clone(), (*second_name).clone()),
+                    second_gradient.dot(&propagated),
+                );
             }
-        } else {
-            flipped_and_extension = vec![(false, "")];
         }
-        let mut pauli_product_dict: HashMap<String, Array1<f64>> = HashMap::new();
+        Ok(Some(ExpectationValuesWithErrors::new(values, covariances)))
+    }
+
+    /// Returns the mean values of all Pauli products and the covariance matrix of the mean values.
+    fn pauli_product_statistics(
+        &self,
+        bit_registers: &HashMap<String, BitOutputRegister>,
+        mitigation: Option<&ReadoutErrorMitigation>,
+    ) -> Result<(Array1<f64>, Array2<f64>), RoqoqoError> {
+        // helper vector to iterate over when evaluating the pauli products
+        let flipped_and_extension: Vec<(bool, &'static str)> = if self.input.use_flipped_measurement
+        {
+            vec![(false, ""), (true, "_flipped")]
+        } else {
+            vec![(false, "")]
+        };
+        let readout_errors: Vec<(f64, f64)> = match mitigation {
+            Some(mitigation) => {
+                mitigation.validate(self.input.number_qubits)?;
+                (0..self.input.number_qubits)
+                    .map(|qubit| mitigation.readout_error(qubit))
+                    .collect()
+            }
+            None => vec![(1.0, 0.0); self.input.number_qubits],
+        };
+        let confusion_matrix_inversion =
+            mitigation.is_some_and(|mitigation| mitigation.confusion_matrix_inversion());
+        let mut pauli_product_dict: HashMap<String, (Array1<f64>, Array2<f64>)> = HashMap::new();
         for (register_name, mask) in self.input.pauli_product_qubit_masks.iter() {
             for (flip_measurement, extension) in flipped_and_extension.iter() {
                 let register = bit_registers
@@ -150,6 +593,26 @@ impl MeasureExpectationValues for PauliZProduct {
                 for (index, mask_val) in mask.iter() {
                     if mask_val.is_empty() {
                         single_shot_pauli_products.column_mut(*index).fill(1.0);
+                    } else if confusion_matrix_inversion {
+                        let mut column = single_shot_pauli_products.column_mut(*index);
+                        for (row_index, values) in register.iter().enumerate() {
+                            // Inverting the confusion matrix of every qubit gives an unbiased estimate of the
+                            // single shot PauliZ value: (z - shift) / fidelity.
+                            // The flipped readout measures -z so the sign of the shift is reversed.
+                            column[row_index] = mask_val
+                                .iter()
+                                .map(|i| {
+                                    let (fidelity, shift) = readout_errors[*i];
+                                    let value = if values[*i] ^ flip_measurement {
+                                        -1.0
+                                    } else {
+                                        1.0
+                                    };
+                                    let shift = if *flip_measurement { -shift } else { shift };
+                                    (value - shift) / fidelity
+                                })
+                                .product();
+                        }
                     } else {
                         // Accessing column of single_shot_pauli_products that corresponds to pauli product designated by index
                         let mut column = single_shot_pauli_products.column_mut(*index);
@@ -172,6 +635,15 @@ impl MeasureExpectationValues for PauliZProduct {
                         }
                     }
                 }
+                if mitigation.is_some() && !confusion_matrix_inversion {
+                    // Rescaling the single shot values with the measurement fidelities
+                    for (index, mask_val) in mask.iter() {
+                        let fidelity: f64 = mask_val.iter().map(|i| readout_errors[*i].0).product();
+                        single_shot_pauli_products
+                            .column_mut(*index)
+                            .mapv_inplace(|value| value / fidelity);
+                    }
+                }
                 let mut pauli_products_tmp: Array1<f64> =
                     Array1::zeros(self.input.number_pauli_products);
                 for i in 0..self.input.number_pauli_products {
@@ -181,70 +653,49 @@ impl MeasureExpectationValues for PauliZProduct {
                         },
                     )?;
                 }
+                // Covariance of the mean values estimated with the unbiased sample covariance of the single shots
+                let number_shots = register.len() as f64;
+                let centered = &single_shot_pauli_products - &pauli_products_tmp;
+                let covariance_tmp: Array2<f64> =
+                    centered.t().dot(&centered) / (number_shots * (number_shots - 1.0));
                 pauli_product_dict.insert(
                     format!("{}{}", register_name.as_str(), extension),
-                    pauli_products_tmp,
+                    (pauli_products_tmp, covariance_tmp),
                 );
             }
         }
 
         let mut pauli_products: Array1<f64> = Array1::zeros(self.input.number_pauli_products);
+        let mut covariance: Array2<f64> = Array2::zeros((
+            self.input.number_pauli_products,
+            self.input.number_pauli_products,
+        ));
         for register_name in self.input.pauli_product_qubit_masks.keys() {
             if !register_name.ends_with("flipped") {
-                // Create temporary averaged vector of pauli_products
+                let (register_pauli_products, register_covariance) = pauli_product_dict
+                    .get(register_name.as_str())
+                    .ok_or(RoqoqoError::PauliZProductMeasurementError {
+                        msg: format!("Register name {register_name} not fount"),
+                    })?;
                 // Averaging between normal and flipped readout when flipped measurement is used
                 if self.input.use_flipped_measurement {
-                    let tmp_pauli_products = (&pauli_product_dict
-                        .get(register_name.as_str())
+                    let (flipped_pauli_products, flipped_covariance) = pauli_product_dict
+                        .get(format!("{register_name}_flipped").as_str())
                         .ok_or(RoqoqoError::PauliZProductMeasurementError {
-                            msg: format!("Register name {register_name} not fount"),
-                        })?
-                        .view()
-                        + &pauli_product_dict
-                            .get(format!("{register_name}_flipped").as_str())
-                            .ok_or(RoqoqoError::PauliZProductMeasurementError {
-                                msg: format!("Register name {register_name}_flipped not fount"),
-                            })?
-                            .view())
-                        / 2.0;
-                    // reinserting in dict of pauli products
-                    pauli_products += &tmp_pauli_products.view();
+                            msg: format!("Register name {register_name}_flipped not fount"),
+                        })?;
+                    pauli_products +=
+                        &((&register_pauli_products.view() + &flipped_pauli_products.view()) / 2.0);
+                    // Normal and flipped readout are independent
+                    covariance +=
+                        &((&register_covariance.view() + &flipped_covariance.view()) / 4.0);
                 } else {
-                    pauli_products += &pauli_product_dict
-                        .get(register_name.as_str())
-                        .ok_or(RoqoqoError::PauliZProductMeasurementError {
-                            msg: format!("Register name {register_name} not fount"),
-                        })?
-                        .view()
+                    pauli_products += &register_pauli_products.view();
+                    covariance += &register_covariance.view();
                 }
             }
         }
-        // Evaluating expectation values
-        let mut results: HashMap<String, f64> = HashMap::new();
-
-        for (name, evaluation) in self.input.measured_exp_vals.iter() {
-            results.insert(
-                name.clone(),
-                match evaluation {
-                    PauliProductsToExpVal::Linear(hm) => {
-                        let mut value: f64 = 0.0;
-                        for (index, coefficient) in hm {
-                            value += pauli_products[*index] * coefficient;
-                        }
-                        value
-                    }
-                    PauliProductsToExpVal::Symbolic(x) => {
-                        let mut calculator = qoqo_calculator::Calculator::new();
-                        for (ind, p) in pauli_products.iter().enumerate() {
-                            calculator.set_variable(format!("pauli_product_{ind}").as_str(), *p);
-                        }
-                        calculator.parse_get(x.clone())?
-                    }
-                },
-            );
-        }
-
-        Ok(Some(results))
+        Ok((pauli_products, covariance))
     }
 }
 
@@ -10,7 +10,9 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::measurements::{CheatedInput, Measure, MeasureExpectationValues};
+use crate::measurements::{
+    CheatedInput, ExpectationValuesWithErrors, Measure, MeasureExpectationValues,
+};
 use crate::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
 use crate::Circuit;
 use crate::RoqoqoError;
@@ -175,6 +177,30 @@ impl MeasureExpectationValues for Cheated {
         }
         Ok(Some(results))
     }
+
+    /// Evaluates the exact expectation values of the cheated measurement with vanishing errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key
+    /// * `float_registers` - The classical float registers as a HashMap with the register name as key
+    /// * `complex_registers` - The classical complex registers as a HashMap with the register name as key
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExpectationValuesWithErrors))` - The measurement has been evaluated successfully
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err(RoqoqoError)` - The measurement evaluation failed, see `evaluate`.
+    fn evaluate_with_errors(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        Ok(self
+            .evaluate(bit_registers, float_registers, complex_registers)?
+            .map(ExpectationValuesWithErrors::exact))
+    }
 }
 
 #[inline]
@@ -0,0 +1,440 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::operations::{DefinitionBit, Hadamard, PragmaRepeatedMeasurement, RotateX};
+use crate::CalculatorFloat;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use struqture::prelude::*;
+use struqture::spins::{PauliProduct, SinglePauliOperator};
+
+/// Collected information for executing a classical shadow measurement.
+///
+/// Every circuit measures all qubits in a random basis drawn uniformly from the X-, Y- and Z-basis,
+/// which is equivalent to measuring after a random single-qubit Clifford gate.
+/// Every single shot is a snapshot of the measured state.
+/// The expectation values of Pauli observables and the fidelities with target states are estimated
+/// from the snapshots with the median-of-means estimator.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ClassicalShadow {
+    /// Constant Circuit that is executed before each Circuit in circuits.
+    pub constant_circuit: Option<Circuit>,
+    /// Collection of quantum circuits for the separate random basis rotations.
+    pub circuits: Vec<Circuit>,
+    /// Additional input information required for measurement.
+    pub input: ClassicalShadowInput,
+}
+
+impl ClassicalShadow {
+    /// Creates a ClassicalShadow measurement with randomly drawn measurement bases.
+    ///
+    /// Circuit `i` rotates every qubit into its random basis (Hadamard for X, RotateX(pi/2) for Y)
+    /// and reads out all qubits with a [crate::operations::PragmaRepeatedMeasurement] into the bit register `ro_i`.
+    /// The drawn bases replace the measurement bases of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `constant_circuit` - The constant Circuit that is executed before each basis rotation circuit.
+    /// * `input` - The input defining the estimated observables and fidelities.
+    /// * `number_circuits` - The number of random basis rotation circuits.
+    /// * `number_measurements` - The number of projective measurements for each basis rotation circuit.
+    /// * `seed` - The seed of the random number generator drawing the bases, random when None.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new ClassicalShadow measurement.
+    pub fn new_random(
+        constant_circuit: Option<Circuit>,
+        input: ClassicalShadowInput,
+        number_circuits: usize,
+        number_measurements: usize,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        };
+        let mut input = input;
+        input.measurement_bases = (0..number_circuits)
+            .map(|_| {
+                let mut basis = PauliProduct::new();
+                for qubit in 0..input.number_qubits {
+                    basis = match rng.random_range(0..3) {
+                        0 => basis.x(qubit),
+                        1 => basis.y(qubit),
+                        _ => basis.z(qubit),
+                    };
+                }
+                basis
+            })
+            .collect();
+        let circuits = input
+            .measurement_bases
+            .iter()
+            .enumerate()
+            .map(|(index, basis)| {
+                let readout = format!("ro_{index}");
+                let mut circuit = Circuit::new();
+                circuit += DefinitionBit::new(readout.clone(), input.number_qubits, true);
+                for (qubit, pauli) in basis.iter() {
+                    match pauli {
+                        SinglePauliOperator::X => circuit += Hadamard::new(*qubit),
+                        SinglePauliOperator::Y => {
+                            circuit += RotateX::new(*qubit, CalculatorFloat::FRAC_PI_2)
+                        }
+                        SinglePauliOperator::Z | SinglePauliOperator::Identity => (),
+                    }
+                }
+                circuit += PragmaRepeatedMeasurement::new(readout, number_measurements, None);
+                circuit
+            })
+            .collect();
+        Self {
+            constant_circuit,
+            circuits,
+            input,
+        }
+    }
+
+    /// Returns the single snapshot estimates of all expectation values.
+    ///
+    /// The snapshots are ordered by circuit and by shot within each circuit.
+    fn snapshot_estimates(
+        &self,
+        bit_registers: &HashMap<String, BitOutputRegister>,
+    ) -> Result<HashMap<String, Vec<f64>>, RoqoqoError> {
+        if self.input.number_groups == 0 {
+            return Err(RoqoqoError::ClassicalShadowMeasurementError {
+                msg: "Number of groups of the median-of-means estimation is zero".to_string(),
+            });
+        }
+        self.input.validate()?;
+        let number_qubits = self.input.number_qubits;
+        let mut circuit_bases: Vec<Vec<SinglePauliOperator>> = Vec::new();
+        let mut snapshots: Vec<(usize, &Vec<bool>)> = Vec::new();
+        for (index, basis) in self.input.measurement_bases.iter().enumerate() {
+            let readout = format!("ro_{index}");
+            let register = bit_registers
+                .get(&readout)
+                .ok_or(RoqoqoError::MissingRegister { name: readout })?;
+            let mut bases = vec![SinglePauliOperator::Z; number_qubits];
+            for (qubit, pauli) in basis.iter() {
+                if *qubit < number_qubits && *pauli != SinglePauliOperator::Identity {
+                    bases[*qubit] = *pauli;
+                }
+            }
+            for shot in register.iter() {
+                if shot.len() < number_qubits {
+                    return Err(RoqoqoError::ClassicalShadowMeasurementError {
+                        msg: format!(
+                            "Shot of register ro_{index} contains {} instead of {number_qubits} qubits",
+                            shot.len()
+                        ),
+                    });
+                }
+                snapshots.push((index, shot));
+            }
+            circuit_bases.push(bases);
+        }
+
+        let mut estimates: HashMap<String, Vec<f64>> = HashMap::new();
+        for (name, observable) in self.input.observables.iter() {
+            let coefficients: Vec<(&PauliProduct, f64)> = observable
+                .iter()
+                .map(|(product, coefficient)| {
+                    coefficient.float().map(|c| (product, *c)).map_err(|_| {
+                        RoqoqoError::ClassicalShadowMeasurementError {
+                            msg: format!(
+                                "Coefficient {coefficient} of observable {name} is not a float"
+                            ),
+                        }
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            let values = snapshots
+                .iter()
+                .map(|(circuit, shot)| {
+                    let bases = &circuit_bases[*circuit];
+                    coefficients
+                        .iter()
+                        .map(|(product, coefficient)| {
+                            // The snapshot estimate of a Pauli is 3 * (+-1) in its own basis and zero otherwise
+                            coefficient
+                                * product
+                                    .iter()
+                                    .map(|(qubit, pauli)| {
+                                        if bases[*qubit] != *pauli {
+                                            0.0
+                                        } else if shot[*qubit] {
+                                            -3.0
+                                        } else {
+                                            3.0
+                                        }
+                                    })
+                                    .product::<f64>()
+                        })
+                        .sum()
+                })
+                .collect();
+            estimates.insert(name.clone(), values);
+        }
+        for (name, target_state) in self.input.target_states.iter() {
+            // Identical snapshots are evaluated only once
+            let mut cache: HashMap<(usize, &Vec<bool>), f64> = HashMap::new();
+            let values = snapshots
+                .iter()
+                .map(|(circuit, shot)| {
+                    *cache.entry((*circuit, shot)).or_insert_with(|| {
+                        snapshot_fidelity(target_state, &circuit_bases[*circuit], shot)
+                    })
+                })
+                .collect();
+            estimates.insert(name.clone(), values);
+        }
+        if estimates.values().any(|values| values.is_empty()) {
+            return Err(RoqoqoError::ClassicalShadowMeasurementError {
+                msg: "No snapshots have been measured".to_string(),
+            });
+        }
+        Ok(estimates)
+    }
+
+    /// Returns the median-of-means estimates of all expectation values from their snapshot estimates.
+    fn median_of_means_estimates(
+        &self,
+        estimates: &HashMap<String, Vec<f64>>,
+    ) -> HashMap<String, f64> {
+        estimates
+            .iter()
+            .map(|(name, values)| {
+                (
+                    name.clone(),
+                    median_of_means(values, self.input.number_groups),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Returns the overlap of a normalised target state with the snapshot of a single shot.
+///
+/// The snapshot is the tensor product of `(3 |b><b| - 1)` over all qubits,
+/// where `|b>` is the eigenstate of the measured Pauli corresponding to the measured bit.
+fn snapshot_fidelity(
+    target_state: &[Complex64],
+    bases: &[SinglePauliOperator],
+    shot: &[bool],
+) -> f64 {
+    let mut state = target_state.to_vec();
+    for (qubit, (pauli, bit)) in bases.iter().zip(shot.iter()).enumerate() {
+        // 3 |b><b| - 1 = 1/2 + 3/2 * (+-1) * pauli
+        let sign = if *bit { -1.5 } else { 1.5 };
+        let i = Complex64::i();
+        let matrix: [[Complex64; 2]; 2] = match pauli {
+            SinglePauliOperator::X => [[0.5.into(), sign.into()], [sign.into(), 0.5.into()]],
+            SinglePauliOperator::Y => [[0.5.into(), -i * sign], [i * sign, 0.5.into()]],
+            _ => [
+                [(0.5 + sign).into(), 0.0.into()],
+                [0.0.into(), (0.5 - sign).into()],
+            ],
+        };
+        let mask = 1 << qubit;
+        for index in (0..state.len()).filter(|index| index & mask == 0) {
+            let zero = state[index];
+            let one = state[index | mask];
+            state[index] = matrix[0][0] * zero + matrix[0][1] * one;
+            state[index | mask] = matrix[1][0] * zero + matrix[1][1] * one;
+        }
+    }
+    target_state
+        .iter()
+        .zip(state.iter())
+        .map(|(target, snapshot)| target.conj() * snapshot)
+        .sum::<Complex64>()
+        .re
+}
+
+/// Returns the median of the means of the estimates distributed round-robin over the groups.
+fn median_of_means(estimates: &[f64], number_groups: usize) -> f64 {
+    let number_groups = number_groups.min(estimates.len());
+    let mut means: Vec<f64> = (0..number_groups)
+        .map(|group| {
+            let group_estimates: Vec<f64> = estimates
+                .iter()
+                .skip(group)
+                .step_by(number_groups)
+                .copied()
+                .collect();
+            group_estimates.iter().sum::<f64>() / group_estimates.len() as f64
+        })
+        .collect();
+    means.sort_by(|a, b| a.total_cmp(b));
+    let middle = means.len() / 2;
+    if means.len() % 2 == 0 {
+        (means[middle - 1] + means[middle]) / 2.0
+    } else {
+        means[middle]
+    }
+}
+
+impl Measure for ClassicalShadow {
+    /// Returns the constant Circuit that is executed before each Circuit in circuits.
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<Circuit` - The constant Circuit (None if not defined).
+    fn constant_circuit(&self) -> &Option<Circuit> {
+        &self.constant_circuit
+    }
+
+    /// Returns iterator over circuits for measurement.
+    ///
+    /// # Returns
+    ///
+    /// * `Box<dyn Iterator<Item = &'a Circuit> + 'a>` - The quantum circuits.
+    fn circuits<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Circuit> + 'a> {
+        Box::new(self.circuits.iter())
+    }
+
+    /// Returns clone of Measurement with symbolic parameters replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `substituted_parameters` - The HashMap containing the substitutions to use in the Circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)` -  The Circuits with the parameters substituted.
+    /// * `Err(RoqoqoError)` - The subsitution failed.
+    ///
+    fn substitute_parameters(
+        &self,
+        substituted_parameters: HashMap<String, f64>,
+    ) -> Result<Self, RoqoqoError> {
+        let mut calculator = qoqo_calculator::Calculator::new();
+        for (name, val) in substituted_parameters.iter() {
+            calculator.set_variable(name, *val)
+        }
+        let new_constant_circuit = match &self.constant_circuit {
+            None => None,
+            Some(c) => Some(c.substitute_parameters(&calculator)?),
+        };
+        let mut new_circuits = Vec::new();
+        for circ in self.circuits.iter() {
+            new_circuits.push(circ.substitute_parameters(&calculator)?)
+        }
+        Ok(Self {
+            constant_circuit: new_constant_circuit,
+            circuits: new_circuits,
+            input: self.input.clone(),
+        })
+    }
+}
+
+impl MeasureExpectationValues for ClassicalShadow {
+    #[allow(unused_variables)]
+    /// Executes the classical shadow measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `float_registers` - The classical float registers as a HashMap with the register name as key.
+    /// * `complex_registers` - The classical complex registers as a HashMap with the register name as key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(HashMap<String, f64>))` - The measurement has been evaluated successfully. The HashMap contains the estimated expectation values and fidelities.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err([RoqoqoError::MissingRegister])` - The register of a circuit is missing.
+    /// * `Err([RoqoqoError::ClassicalShadowMeasurementError])` - An error occured in classical shadow measurement.
+    fn evaluate(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoError> {
+        let estimates = self.snapshot_estimates(&bit_registers)?;
+        Ok(Some(self.median_of_means_estimates(&estimates)))
+    }
+
+    #[allow(unused_variables)]
+    /// Executes the classical shadow measurement and estimates the statistical errors from the snapshots.
+    ///
+    /// The values are the median-of-means estimates returned by `evaluate`.
+    /// The covariances are the covariances of the means of all snapshot estimates.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `float_registers` - The classical float registers as a HashMap with the register name as key.
+    /// * `complex_registers` - The classical complex registers as a HashMap with the register name as key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExpectationValuesWithErrors))` - The measurement has been evaluated successfully.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed
+    /// * `Err([RoqoqoError::MissingRegister])` - The register of a circuit is missing.
+    /// * `Err([RoqoqoError::ClassicalShadowMeasurementError])` - An error occured in classical shadow measurement.
+    fn evaluate_with_errors(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        let estimates = self.snapshot_estimates(&bit_registers)?;
+        let values = self.median_of_means_estimates(&estimates);
+        let centered: HashMap<&String, Vec<f64>> = estimates
+            .iter()
+            .map(|(name, values)| {
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                (name, values.iter().map(|value| value - mean).collect())
+            })
+            .collect();
+        let mut covariances: HashMap<(String, String), f64> = HashMap::new();
+        for (first_name, first) in centered.iter() {
+            for (second_name, second) in centered.iter() {
+                let number_snapshots = first.len() as f64;
+                let covariance = first
+                    .iter()
+                    .zip(second.iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<f64>()
+                    / (number_snapshots * (number_snapshots - 1.0));
+                covariances.insert(((*first_name).clone(), (*second_name).clone()), covariance);
+            }
+        }
+        Ok(Some(ExpectationValuesWithErrors::new(values, covariances)))
+    }
+}
+
+impl crate::operations::SupportedVersion for ClassicalShadow {
+    fn minimum_supported_roqoqo_version(&self) -> (u32, u32, u32) {
+        let mut current_minimum_version = (1, 0, 0);
+        let comparison_version = self.input.minimum_supported_roqoqo_version();
+        crate::update_roqoqo_version(&mut current_minimum_version, comparison_version);
+        if let Some(circuit) = self.constant_circuit() {
+            let comparison_version = circuit.minimum_supported_roqoqo_version();
+            crate::update_roqoqo_version(&mut current_minimum_version, comparison_version);
+        }
+        for circuit in self.circuits.iter() {
+            let comparison_version = circuit.minimum_supported_roqoqo_version();
+            crate::update_roqoqo_version(&mut current_minimum_version, comparison_version);
+        }
+        current_minimum_version
+    }
+}
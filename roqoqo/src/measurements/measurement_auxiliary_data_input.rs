@@ -10,12 +10,16 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::noise_models::ImperfectReadoutModel;
 use crate::CalculatorFloat;
 #[cfg(feature = "json_schema")]
 use crate::Complex64Def;
 use crate::RoqoqoError;
+use ndarray::Array1;
 use num_complex::Complex64;
 use std::collections::HashMap;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliProduct};
 
 /// Provides PauliProductMasks for all Pauli Products measured from one readout register.
 pub type SingleReadoutPauliProductMasks = HashMap<usize, PauliProductMask>;
@@ -42,6 +46,152 @@ pub enum PauliProductsToExpVal {
     Symbolic(CalculatorFloat),
 }
 
+impl PauliProductsToExpVal {
+    /// Evaluates the expectation value from the expectation values of the Pauli products.
+    pub(crate) fn evaluate(&self, pauli_products: &Array1<f64>) -> Result<f64, RoqoqoError> {
+        match self {
+            PauliProductsToExpVal::Linear(hm) => {
+                let mut value: f64 = 0.0;
+                for (index, coefficient) in hm {
+                    value += pauli_products[*index] * coefficient;
+                }
+                Ok(value)
+            }
+            PauliProductsToExpVal::Symbolic(x) => {
+                let mut calculator = qoqo_calculator::Calculator::new();
+                for (ind, p) in pauli_products.iter().enumerate() {
+                    calculator.set_variable(format!("pauli_product_{ind}").as_str(), *p);
+                }
+                Ok(calculator.parse_get(x.clone())?)
+            }
+        }
+    }
+
+    /// Returns the derivatives of the expectation value with respect to the Pauli product expectation values.
+    ///
+    /// Symbolic expressions are differentiated with central finite differences.
+    pub(crate) fn gradient(
+        &self,
+        pauli_products: &Array1<f64>,
+    ) -> Result<Array1<f64>, RoqoqoError> {
+        let mut gradient: Array1<f64> = Array1::zeros(pauli_products.len());
+        match self {
+            PauliProductsToExpVal::Linear(hm) => {
+                for (index, coefficient) in hm {
+                    gradient[*index] += coefficient;
+                }
+            }
+            PauliProductsToExpVal::Symbolic(_) => {
+                for index in 0..pauli_products.len() {
+                    let mut shifted = pauli_products.clone();
+                    shifted[index] += FINITE_DIFFERENCE_STEP;
+                    let upper = self.evaluate(&shifted)?;
+                    shifted[index] -= 2.0 * FINITE_DIFFERENCE_STEP;
+                    let lower = self.evaluate(&shifted)?;
+                    gradient[index] = (upper - lower) / (2.0 * FINITE_DIFFERENCE_STEP);
+                }
+            }
+        }
+        Ok(gradient)
+    }
+}
+
+/// Step size for differentiating symbolic expectation values.
+const FINITE_DIFFERENCE_STEP: f64 = 1e-6;
+
+/// Defines how readout errors are mitigated when evaluating a [crate::measurements::PauliZProduct] measurement.
+///
+/// The mitigation is stored in [PauliZProductInput] with [PauliZProductInput::set_readout_error_mitigation]
+/// and applied when evaluating the measurement.
+///
+/// The readout of qubit `i` contracts the measured PauliZ expectation value by the measurement fidelity
+/// `f_i = 1 - p_i(0->1) - p_i(1->0)` and shifts it by `p_i(1->0) - p_i(0->1)`.
+/// The shift cancels when flipped measurements are used.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReadoutErrorMitigation {
+    /// Pauli product expectation values are divided by the product of the measurement fidelities of the involved qubits.
+    ///
+    /// Qubits without a fidelity are measured perfectly.
+    /// Assumes symmetric readout errors or flipped measurements.
+    Fidelities(HashMap<usize, f64>),
+    /// Readout errors are given by the error probabilities of an [ImperfectReadoutModel].
+    ImperfectReadoutModel {
+        /// The readout error probabilities of the qubits.
+        model: ImperfectReadoutModel,
+        /// Whether the confusion matrix of each qubit is inverted for every single shot.
+        ///
+        /// The inversion also removes the shift of asymmetric readout errors
+        /// but increases the statistical fluctuations.
+        /// When false, the expectation values are only divided by the measurement fidelities.
+        confusion_matrix_inversion: bool,
+    },
+}
+
+impl ReadoutErrorMitigation {
+    /// Returns the measurement fidelity and the shift of the measured PauliZ expectation value of a qubit.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The measured qubit.
+    ///
+    /// # Returns
+    ///
+    /// * `(f64, f64)` - The measurement fidelity and the shift, the shift is zero for fidelities.
+    pub fn readout_error(&self, qubit: usize) -> (f64, f64) {
+        match self {
+            ReadoutErrorMitigation::Fidelities(fidelities) => {
+                (*fidelities.get(&qubit).unwrap_or(&1.0), 0.0)
+            }
+            ReadoutErrorMitigation::ImperfectReadoutModel { model, .. } => {
+                let prob_detect_0_as_1 = model.prob_detect_0_as_1(&qubit);
+                let prob_detect_1_as_0 = model.prob_detect_1_as_0(&qubit);
+                (
+                    1.0 - prob_detect_0_as_1 - prob_detect_1_as_0,
+                    prob_detect_1_as_0 - prob_detect_0_as_1,
+                )
+            }
+        }
+    }
+
+    /// Returns true when the confusion matrices are inverted for every single shot.
+    pub fn confusion_matrix_inversion(&self) -> bool {
+        matches!(
+            self,
+            ReadoutErrorMitigation::ImperfectReadoutModel {
+                confusion_matrix_inversion: true,
+                ..
+            }
+        )
+    }
+
+    /// Checks that the readout errors of the qubits can be mitigated.
+    ///
+    /// # Arguments
+    ///
+    /// * `number_qubits` - The number of measured qubits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The measurement fidelities of all qubits are in the range (0, 1].
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - A measurement fidelity is not in the range (0, 1].
+    pub fn validate(&self, number_qubits: usize) -> Result<(), RoqoqoError> {
+        for qubit in 0..number_qubits {
+            let (fidelity, _) = self.readout_error(qubit);
+            if !(fidelity > 0.0 && fidelity <= 1.0) {
+                return Err(RoqoqoError::PauliZProductMeasurementError {
+                    msg: format!(
+                        "Measurement fidelity {fidelity} of qubit {qubit} is not in the range (0, 1], \
+                         readout errors can not be mitigated"
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Provides Necessary Information to run a [crate::measurements::PauliZProduct] measurement.
 ///
 /// PauliZProductInput is the input struct for a PauliZProduct measurement, dictating which expectation
@@ -65,6 +215,9 @@ pub struct PauliZProductInput {
     ///
     /// Measurement errors are symmetrized by repeating measurement with final flip of all qubits.
     pub use_flipped_measurement: bool,
+    /// Optional mitigation of readout errors applied to the measured Pauli products.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub readout_error_mitigation: Option<ReadoutErrorMitigation>,
 }
 
 impl PauliZProductInput {
@@ -87,7 +240,29 @@ impl PauliZProductInput {
             number_pauli_products: 0,
             measured_exp_vals: HashMap::new(),
             use_flipped_measurement,
+            readout_error_mitigation: None,
+        }
+    }
+
+    /// Sets the mitigation of readout errors used when evaluating the measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `readout_error_mitigation` - The readout error mitigation, None disables the mitigation.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok()` - The mitigation was successfully set.
+    /// * `Err([RoqoqoError::PauliZProductMeasurementError])` - The measurement fidelity of a qubit is not in the range (0, 1].
+    pub fn set_readout_error_mitigation(
+        &mut self,
+        readout_error_mitigation: Option<ReadoutErrorMitigation>,
+    ) -> Result<(), RoqoqoError> {
+        if let Some(mitigation) = &readout_error_mitigation {
+            mitigation.validate(self.number_qubits)?;
         }
+        self.readout_error_mitigation = readout_error_mitigation;
+        Ok(())
     }
 
     /// Adds measured Pauli product to PauliZProductInput and returns index of Pauli product.
@@ -419,8 +594,236 @@ impl CheatedInput {
     }
 }
 
+/// Provides necessary information to run a [crate::measurements::ClassicalShadow] measurement.
+///
+/// Is used by the full measurement struct [crate::measurements::ClassicalShadow].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(try_from = "ClassicalShadowInputSerialize")
+)]
+pub struct ClassicalShadowInput {
+    /// Number of qubits that are measured.
+    pub number_qubits: usize,
+    /// Number of groups of snapshots averaged separately in the median-of-means estimation.
+    pub number_groups: usize,
+    /// Measurement basis of every circuit.
+    ///
+    /// Qubits that are not contained in the PauliProduct are measured in the Z-basis.
+    pub measurement_bases: Vec<PauliProduct>,
+    /// Collection of names and Pauli observables whose expectation values are estimated.
+    pub observables: HashMap<String, PauliHamiltonian>,
+    /// Collection of names and target statevectors whose fidelities with the measured state are estimated.
+    pub target_states: HashMap<String, Vec<Complex64>>,
+}
+
+#[cfg(feature = "json_schema")]
+impl schemars::JsonSchema for ClassicalShadowInput {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ClassicalShadowInput".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        <SchemaHelperClassicalShadowInput>::json_schema(generator)
+    }
+}
+
+#[cfg(feature = "json_schema")]
+#[derive(schemars::JsonSchema)]
+#[allow(dead_code)]
+struct SchemaHelperClassicalShadowInput {
+    /// Number of qubits that are measured.
+    number_qubits: usize,
+    /// Number of groups of snapshots averaged separately in the median-of-means estimation.
+    number_groups: usize,
+    /// Measurement basis of every circuit.
+    measurement_bases: Vec<PauliProduct>,
+    /// Collection of names and Pauli observables whose expectation values are estimated.
+    observables: HashMap<String, PauliHamiltonian>,
+    /// Collection of names and target statevectors whose fidelities with the measured state are estimated.
+    ///
+    /// The amplitudes are serialized as pairs of real and imaginary part.
+    target_states: HashMap<String, Vec<(f64, f64)>>,
+}
+
+#[cfg(feature = "serialize")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "ClassicalShadowInput")]
+struct ClassicalShadowInputSerialize {
+    number_qubits: usize,
+    number_groups: usize,
+    measurement_bases: Vec<PauliProduct>,
+    observables: HashMap<String, PauliHamiltonian>,
+    target_states: HashMap<String, Vec<Complex64>>,
+}
+
+#[cfg(feature = "serialize")]
+impl TryFrom<ClassicalShadowInputSerialize> for ClassicalShadowInput {
+    type Error = RoqoqoError;
+    fn try_from(value: ClassicalShadowInputSerialize) -> Result<Self, Self::Error> {
+        let input = ClassicalShadowInput {
+            number_qubits: value.number_qubits,
+            number_groups: value.number_groups,
+            measurement_bases: value.measurement_bases,
+            observables: value.observables,
+            target_states: value.target_states,
+        };
+        input.validate()?;
+        Ok(input)
+    }
+}
+
+impl ClassicalShadowInput {
+    /// Creates new ClassicalShadowInput.
+    ///
+    /// # Arguments
+    ///
+    /// * `number_qubits` - The number of qubits in the ClassicalShadow measurement.
+    /// * `number_groups` - The number of groups of snapshots in the median-of-means estimation.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new instance of ClassicalShadowInput without measurement bases, observables and target states.
+    pub fn new(number_qubits: usize, number_groups: usize) -> Self {
+        Self {
+            number_qubits,
+            number_groups,
+            measurement_bases: Vec::new(),
+            observables: HashMap::new(),
+            target_states: HashMap::new(),
+        }
+    }
+
+    /// Adds a Pauli observable whose expectation value is estimated to measurement input.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the expectation value.
+    /// * `observable` - The observable given as a sum of Pauli products with real coefficients.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok()` - The observable was successfully added.
+    /// * `Err([RoqoqoError::PauliProductExceedsQubits])` - The observable involves a qubit exceeding the number of qubits.
+    /// * `Err([RoqoqoError::ExpValUsedTwice])` - The name of expectation value is already taken.
+    pub fn add_observable(
+        &mut self,
+        name: String,
+        observable: PauliHamiltonian,
+    ) -> Result<(), RoqoqoError> {
+        self.check_observable(&observable)?;
+        if self.observables.contains_key(&name) || self.target_states.contains_key(&name) {
+            return Err(RoqoqoError::ExpValUsedTwice { name });
+        }
+        self.observables.insert(name, observable);
+        Ok(())
+    }
+
+    /// Adds a target state whose fidelity with the measured state is estimated to measurement input.
+    ///
+    /// The target statevector is normalised before it is stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the estimated fidelity.
+    /// * `target_state` - The statevector of the target state.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok()` - The target state was successfully added.
+    /// * `Err([RoqoqoError::MismatchedRegisterDimension])` - The statevector does not match the dimension of the Hilbert space.
+    /// * `Err([RoqoqoError::ExpValUsedTwice])` - The name of expectation value is already taken.
+    /// * `Err([RoqoqoError::ClassicalShadowMeasurementError])` - The statevector has zero norm.
+    pub fn add_fidelity(
+        &mut self,
+        name: String,
+        target_state: Vec<Complex64>,
+    ) -> Result<(), RoqoqoError> {
+        self.check_target_state(&target_state)?;
+        let norm: f64 = target_state
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .sum::<f64>()
+            .sqrt();
+        if norm == 0.0 {
+            return Err(RoqoqoError::ClassicalShadowMeasurementError {
+                msg: format!("Target state {name} has zero norm"),
+            });
+        }
+        if self.observables.contains_key(&name) || self.target_states.contains_key(&name) {
+            return Err(RoqoqoError::ExpValUsedTwice { name });
+        }
+        self.target_states.insert(
+            name,
+            target_state
+                .into_iter()
+                .map(|amplitude| amplitude / norm)
+                .collect(),
+        );
+        Ok(())
+    }
+
+    /// Checks that all observables and target states fit the number of qubits.
+    ///
+    /// The fields of the input are public, so the checks of `add_observable` and `add_fidelity`
+    /// are repeated before the input is used.
+    pub(crate) fn validate(&self) -> Result<(), RoqoqoError> {
+        for observable in self.observables.values() {
+            self.check_observable(observable)?;
+        }
+        for target_state in self.target_states.values() {
+            self.check_target_state(target_state)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that an observable only involves measured qubits.
+    fn check_observable(&self, observable: &PauliHamiltonian) -> Result<(), RoqoqoError> {
+        match observable
+            .keys()
+            .flat_map(|product| product.iter().map(|(qubit, _)| *qubit))
+            .find(|qubit| qubit >= &self.number_qubits)
+        {
+            Some(qubit) => Err(RoqoqoError::PauliProductExceedsQubits {
+                pp_qubit: qubit,
+                number_qubits: self.number_qubits,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that a statevector has the dimension of the Hilbert space of the measured qubits.
+    fn check_target_state(&self, target_state: &[Complex64]) -> Result<(), RoqoqoError> {
+        let dimension = u32::try_from(self.number_qubits)
+            .ok()
+            .and_then(|number_qubits| 1usize.checked_shl(number_qubits));
+        if dimension != Some(target_state.len()) {
+            return Err(RoqoqoError::MismatchedRegisterDimension {
+                dim: target_state.len(),
+                number_qubits: self.number_qubits,
+            });
+        }
+        Ok(())
+    }
+}
+
 impl crate::operations::SupportedVersion for CheatedInput {}
 
 impl crate::operations::SupportedVersion for CheatedPauliZProductInput {}
 
-impl crate::operations::SupportedVersion for PauliZProductInput {}
+impl crate::operations::SupportedVersion for PauliZProductInput {
+    fn minimum_supported_roqoqo_version(&self) -> (u32, u32, u32) {
+        if self.readout_error_mitigation.is_some() {
+            (1, 23, 0)
+        } else {
+            (1, 0, 0)
+        }
+    }
+}
+
+impl crate::operations::SupportedVersion for ClassicalShadowInput {
+    fn minimum_supported_roqoqo_version(&self) -> (u32, u32, u32) {
+        (1, 23, 0)
+    }
+}
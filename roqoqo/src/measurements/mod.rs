@@ -38,6 +38,9 @@ pub use cheated_measurement::*;
 #[doc(hidden)]
 mod classical_register_measurement;
 pub use classical_register_measurement::*;
+#[doc(hidden)]
+mod classical_shadow_measurement;
+pub use classical_shadow_measurement::*;
 
 use crate::registers::BitOutputRegister;
 use crate::{
@@ -239,4 +242,114 @@ pub trait MeasureExpectationValues: PartialEq + Clone + Measure {
             .evaluate(bit_registers, float_registers, complex_registers)?
             .unwrap())
     }
+
+    /// Evaluates measurement results together with their statistical errors.
+    ///
+    /// Measurements post-processing finite numbers of shots return estimates of the shot noise,
+    /// measurements evaluating exact expectation values (e.g. from statevectors or density matrices)
+    /// return vanishing errors. The default implementation returns an error for measurements
+    /// that can not estimate their statistical errors.
+    ///
+    /// Arguments:
+    ///
+    /// * `bit_registers` - The classical bit registers as a HashMap with the register name as key.
+    /// * `float_registers` - The classical float registers as a HashMap with the register name as key.
+    /// * `complex_registers` - The classical complex registers as a HashMap with the register name as key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExpectationValuesWithErrors))` - The measurement has been evaluated successfully.
+    /// * `Ok(None)` - The measurement did not fail but is incomplete. A new round of measurements is needed.
+    /// * `Err(RoqoqoError)` - The measurement evaluation failed.
+    /// * `Err(RoqoqoError::GenericError)` - The measurement does not support the evaluation of statistical errors.
+    #[allow(unused_variables)]
+    fn evaluate_with_errors(
+        &self,
+        bit_registers: HashMap<String, BitOutputRegister>,
+        float_registers: HashMap<String, FloatOutputRegister>,
+        complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<ExpectationValuesWithErrors>, RoqoqoError> {
+        Err(RoqoqoError::GenericError {
+            msg: "Evaluating statistical errors is not supported by this measurement".to_string(),
+        })
+    }
+}
+
+/// Expectation values of a measurement together with their statistical errors.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpectationValuesWithErrors {
+    /// The measured expectation values.
+    pub values: HashMap<String, f64>,
+    /// The standard errors of the measured expectation values.
+    pub standard_errors: HashMap<String, f64>,
+    /// The covariances between all pairs of measured expectation values.
+    pub covariances: HashMap<(String, String), f64>,
+}
+
+impl ExpectationValuesWithErrors {
+    /// Creates new ExpectationValuesWithErrors, deriving the standard errors from the covariances.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The measured expectation values.
+    /// * `covariances` - The covariances between all pairs of measured expectation values.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new ExpectationValuesWithErrors.
+    pub fn new(values: HashMap<String, f64>, covariances: HashMap<(String, String), f64>) -> Self {
+        let standard_errors = values
+            .keys()
+            .map(|name| {
+                let variance = covariances
+                    .get(&(name.clone(), name.clone()))
+                    .copied()
+                    .unwrap_or(f64::NAN);
+                (name.clone(), variance.sqrt())
+            })
+            .collect();
+        Self {
+            values,
+            standard_errors,
+            covariances,
+        }
+    }
+
+    /// Creates new ExpectationValuesWithErrors for exact expectation values with vanishing errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The exact expectation values.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new ExpectationValuesWithErrors.
+    pub fn exact(values: HashMap<String, f64>) -> Self {
+        let covariances = values
+            .keys()
+            .flat_map(|first| {
+                values
+                    .keys()
+                    .map(move |second| ((first.clone(), second.clone()), 0.0))
+            })
+            .collect();
+        Self::new(values, covariances)
+    }
+
+    /// Returns the covariance between two measured expectation values.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The name of the first expectation value.
+    /// * `second` - The name of the second expectation value.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The covariance of the two expectation values.
+    /// * `None` - One of the expectation values has not been measured.
+    pub fn covariance(&self, first: &str, second: &str) -> Option<f64> {
+        self.covariances
+            .get(&(first.to_string(), second.to_string()))
+            .copied()
+    }
 }
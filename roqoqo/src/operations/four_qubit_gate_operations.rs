@@ -22,6 +22,7 @@ use num_complex::Complex64;
     Debug,
     Clone,
     PartialEq,
+    Eq,
     roqoqo_derive::Operate,
     roqoqo_derive::OperateFourQubit,
     roqoqo_derive::InvolveQubits,
@@ -56,6 +57,14 @@ const TAGS_TripleControlledPauliX: &[&str; 4] = &[
     "TripleControlledPauliX",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for TripleControlledPauliX {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 impl OperateGate for TripleControlledPauliX {
     fn unitary_matrix(&self) -> Result<Array2<Complex64>, RoqoqoError> {
         let dim = 16;
@@ -96,6 +105,7 @@ impl OperateFourQubitGate for TripleControlledPauliX {
     Debug,
     Clone,
     PartialEq,
+    Eq,
     roqoqo_derive::Operate,
     roqoqo_derive::OperateFourQubit,
     roqoqo_derive::InvolveQubits,
@@ -130,6 +140,14 @@ const TAGS_TripleControlledPauliZ: &[&str; 4] = &[
     "TripleControlledPauliZ",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for TripleControlledPauliZ {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 impl OperateGate for TripleControlledPauliZ {
     fn unitary_matrix(&self) -> Result<Array2<Complex64>, RoqoqoError> {
         let dim = 16;
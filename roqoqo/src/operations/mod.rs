@@ -553,14 +553,17 @@ pub trait Define:
 ///
 /// # Example
 /// ```
-/// use roqoqo::operations::{OperateConstantGate, PauliX};
+/// use roqoqo::operations::{GateOperation, InvSGate, OperateConstantGate, PauliX, SGate};
 /// let paulix = PauliX::new(0);
+/// assert_eq!(paulix.inverse(), GateOperation::from(PauliX::new(0)));
+/// let sgate = SGate::new(0);
+/// assert_eq!(sgate.inverse(), GateOperation::from(InvSGate::new(0)));
 /// ```
 ///
 pub trait OperateConstantGate:
     OperateGate + Operate + InvolveQubits + Substitute + Clone + PartialEq + SupportedVersion
 {
-    /// Returns true when unitary operation U is self inverse U*U = I.
+    /// Returns the inverse U^† of the unitary operation U (up to a global phase).
     fn inverse(&self) -> GateOperation;
 }
 
@@ -1001,6 +1004,60 @@ pub(crate) fn check_valid_mapping(mapping: &HashMap<usize, usize>) -> Result<(),
     Ok(())
 }
 
+/// Returns the qubits a gate Operation acts on in descending order of significance in its unitary matrix.
+///
+/// Returns `None` when the Operation is not a gate operation acting on a fixed set of qubits.
+pub(crate) fn gate_qubits(operation: &Operation) -> Option<Vec<usize>> {
+    if let Ok(gate) = SingleQubitGateOperation::try_from(operation) {
+        return Some(vec![*gate.qubit()]);
+    }
+    if let Ok(gate) = TwoQubitGateOperation::try_from(operation) {
+        return Some(vec![*gate.control(), *gate.target()]);
+    }
+    if let Ok(gate) = ThreeQubitGateOperation::try_from(operation) {
+        return Some(vec![*gate.control_0(), *gate.control_1(), *gate.target()]);
+    }
+    if let Ok(gate) = FourQubitGateOperation::try_from(operation) {
+        return Some(vec![
+            *gate.control_0(),
+            *gate.control_1(),
+            *gate.control_2(),
+            *gate.target(),
+        ]);
+    }
+    if let Ok(gate) = MultiQubitGateOperation::try_from(operation) {
+        return Some(gate.qubits().clone());
+    }
+    None
+}
+
+/// Returns the indices of the basis states spanning the subspace of a gate.
+///
+/// The returned indices are ordered like the rows of the unitary matrix of a gate acting on `qubits`,
+/// where the first qubit in `qubits` is the most significant one.
+///
+/// # Arguments
+///
+/// * `base` - Index of the basis state with all gate qubits in the zero state.
+/// * `qubits` - The qubits the gate acts on in descending order of significance.
+pub(crate) fn subspace_indices(base: usize, qubits: &[usize]) -> Vec<usize> {
+    let number_gate_qubits = qubits.len();
+    (0..1_usize << number_gate_qubits)
+        .map(|local_index| {
+            qubits
+                .iter()
+                .enumerate()
+                .fold(base, |index, (position, qubit)| {
+                    if (local_index >> (number_gate_qubits - 1 - position)) & 1 == 1 {
+                        index | (1 << qubit)
+                    } else {
+                        index
+                    }
+                })
+        })
+        .collect()
+}
+
 /// Represents bosonic modes involved in a roqoqo bosonic Operation.
 #[derive(Debug, PartialEq, Clone, Eq)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
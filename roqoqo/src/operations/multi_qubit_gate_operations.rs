@@ -77,12 +77,12 @@ impl OperateMultiQubitGate for MultiQubitMS {
         for q in self.qubits.iter() {
             circuit += operations::Hadamard::new(*q);
         }
-        for q in self.qubits[1..].iter() {
-            circuit += operations::CNOT::new(*q - 1, *q);
+        for pair in self.qubits.windows(2) {
+            circuit += operations::CNOT::new(pair[0], pair[1]);
         }
-        circuit += operations::RotateZ::new(dim - 1, self.theta.clone());
-        for q in self.qubits[1..].iter() {
-            circuit += operations::CNOT::new(dim - *q - 1, dim - *q);
+        circuit += operations::RotateZ::new(self.qubits[dim - 1], self.theta.clone());
+        for pair in self.qubits.windows(2).rev() {
+            circuit += operations::CNOT::new(pair[0], pair[1]);
         }
         for q in self.qubits.iter() {
             circuit += operations::Hadamard::new(*q);
@@ -231,12 +231,12 @@ impl OperateMultiQubitGate for MultiQubitZZ {
     fn circuit(&self) -> Circuit {
         let dim = self.qubits.len();
         let mut circuit = Circuit::new();
-        for q in self.qubits[1..].iter() {
-            circuit += operations::CNOT::new(*q - 1, *q);
+        for pair in self.qubits.windows(2) {
+            circuit += operations::CNOT::new(pair[0], pair[1]);
         }
-        circuit += operations::RotateZ::new(dim - 1, self.theta.clone());
-        for q in self.qubits[1..].iter() {
-            circuit += operations::CNOT::new(dim - *q - 1, dim - *q);
+        circuit += operations::RotateZ::new(self.qubits[dim - 1], self.theta.clone());
+        for pair in self.qubits.windows(2).rev() {
+            circuit += operations::CNOT::new(pair[0], pair[1]);
         }
         circuit
     }
@@ -17,8 +17,8 @@ use std::convert::TryFrom;
 use std::f64::consts::PI;
 
 use crate::operations::{
-    InvolveQubits, InvolvedQubits, Operate, OperateGate, OperateSingleQubit,
-    OperateSingleQubitGate, Rotate, Substitute, SupportedVersion,
+    GateOperation, InvolveQubits, InvolvedQubits, Operate, OperateConstantGate, OperateGate,
+    OperateSingleQubit, OperateSingleQubitGate, Rotate, Substitute, SupportedVersion,
 };
 use crate::RoqoqoError;
 #[cfg(feature = "overrotate")]
@@ -464,6 +464,14 @@ const TAGS_PauliX: &[&str; 4] = &[
     "PauliX",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for PauliX {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for PauliX {
     /// Returns unitary matrix of the gate.
@@ -552,6 +560,14 @@ const TAGS_PauliY: &[&str; 4] = &[
     "PauliY",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for PauliY {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for PauliY {
     /// Returns unitary matrix of the gate.
@@ -640,6 +656,14 @@ const TAGS_PauliZ: &[&str; 4] = &[
     "PauliZ",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for PauliZ {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for PauliZ {
     /// Returns unitary matrix of the gate.
@@ -728,6 +752,14 @@ const TAGS_SqrtPauliX: &[&str; 4] = &[
     "SqrtPauliX",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SqrtPauliX {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvSqrtPauliX::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SqrtPauliX {
     /// Returns unitary matrix of the gate.
@@ -819,6 +851,14 @@ const TAGS_InvSqrtPauliX: &[&str; 4] = &[
     "InvSqrtPauliX",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvSqrtPauliX {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        SqrtPauliX::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvSqrtPauliX {
     /// Returns unitary matrix of the gate.
@@ -910,6 +950,14 @@ const TAGS_Hadamard: &[&str; 4] = &[
     "Hadamard",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for Hadamard {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for Hadamard {
     /// Returns unitary matrix of the gate.
@@ -999,6 +1047,14 @@ const TAGS_SGate: &[&str; 4] = &[
     "SGate",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvSGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SGate {
     /// Returns unitary matrix of the gate.
@@ -1087,6 +1143,14 @@ const TAGS_TGate: &[&str; 4] = &[
     "TGate",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for TGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvTGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for TGate {
     /// Returns unitary matrix of the gate.
@@ -1789,6 +1853,14 @@ const TAGS_Identity: &[&str; 4] = &[
     "Identity",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for Identity {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for Identity {
     /// Returns unitary matrix of the gate.
@@ -1884,6 +1956,14 @@ impl SupportedVersion for SqrtPauliY {
 
 impl super::ImplementedIn1point15 for SqrtPauliY {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SqrtPauliY {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvSqrtPauliY::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SqrtPauliY {
     /// Returns unitary matrix of the gate.
@@ -1982,6 +2062,14 @@ impl SupportedVersion for InvSqrtPauliY {
 
 impl super::ImplementedIn1point15 for InvSqrtPauliY {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvSqrtPauliY {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        SqrtPauliY::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvSqrtPauliY {
     /// Returns unitary matrix of the gate.
@@ -2080,6 +2168,14 @@ impl SupportedVersion for InvSGate {
 
 impl super::ImplementedIn1point16 for InvSGate {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvSGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        SGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvSGate {
     /// Returns unitary matrix of the gate.
@@ -2175,6 +2271,14 @@ impl SupportedVersion for InvTGate {
 
 impl super::ImplementedIn1point16 for InvTGate {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvTGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        TGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvTGate {
     /// Returns unitary matrix of the gate.
@@ -2273,6 +2377,14 @@ impl SupportedVersion for SXGate {
 
 impl super::ImplementedIn1point16 for SXGate {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SXGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvSXGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SXGate {
     /// Returns unitary matrix of the gate.
@@ -2372,6 +2484,14 @@ impl SupportedVersion for InvSXGate {
 
 impl super::ImplementedIn1point16 for InvSXGate {}
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvSXGate {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        SXGate::new(self.qubit).into()
+    }
+}
+
 /// Trait for all operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvSXGate {
     /// Returns unitary matrix of the gate.
@@ -12,6 +12,7 @@
 
 use super::{ControlledPhaseShift, Hadamard, PhaseShiftedControlledPhase, CNOT};
 use super::{RotateZ, TGate};
+use crate::operations::GateOperation;
 use crate::prelude::*;
 use crate::Circuit;
 use ndarray::{array, Array2};
@@ -63,6 +64,14 @@ const TAGS_ControlledControlledPauliZ: &[&str; 4] = &[
     "ControlledControlledPauliZ",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for ControlledControlledPauliZ {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for ControlledControlledPauliZ {
     /// Returns unitary matrix of the gate.
@@ -336,6 +345,7 @@ impl OperateThreeQubitGate for ControlledControlledPhaseShift {
     Debug,
     Clone,
     PartialEq,
+    Eq,
     roqoqo_derive::InvolveQubits,
     roqoqo_derive::Operate,
     roqoqo_derive::Substitute,
@@ -368,6 +378,14 @@ const TAGS_Toffoli: &[&str; 4] = &[
     "Toffoli",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for Toffoli {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for Toffoli {
     /// Returns unitary matrix of the gate.
@@ -519,6 +537,14 @@ const TAGS_ControlledSWAP: &[&str; 4] = &[
     "ControlledSWAP",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for ControlledSWAP {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for ControlledSWAP {
     /// Returns unitary matrix of the gate.
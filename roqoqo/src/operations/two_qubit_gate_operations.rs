@@ -11,6 +11,7 @@
 // limitations under the License.
 
 use crate::operations::single_qubit_gate_operations::*;
+use crate::operations::GateOperation;
 use crate::prelude::*;
 use crate::Circuit;
 use crate::RoqoqoError;
@@ -78,6 +79,14 @@ const TAGS_CNOT: &[&str; 4] = &[
     "CNOT",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for CNOT {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for CNOT {
     /// Returns unitary matrix of the gate.
@@ -177,6 +186,14 @@ const TAGS_SWAP: &[&str; 4] = &[
     "SWAP",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SWAP {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SWAP {
     /// Returns unitary matrix of the gate.
@@ -269,6 +286,14 @@ const TAGS_ISwap: &[&str; 4] = &[
     "ISwap",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for ISwap {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        XY::new(self.control, self.target, CalculatorFloat::PI * (-1.0)).into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for ISwap {
     /// Returns unitary matrix of the gate.
@@ -362,6 +387,14 @@ const TAGS_FSwap: &[&str; 4] = &[
     "FSwap",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for FSwap {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for FSwap {
     /// Returns unitary matrix of the gate.
@@ -458,6 +491,14 @@ const TAGS_SqrtISwap: &[&str; 4] = &[
     "SqrtISwap",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for SqrtISwap {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        InvSqrtISwap::new(self.control, self.target).into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for SqrtISwap {
     /// Returns unitary matrix of the gate.
@@ -550,6 +591,14 @@ const TAGS_InvSqrtISwap: &[&str; 4] = &[
     "InvSqrtISwap",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for InvSqrtISwap {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        SqrtISwap::new(self.control, self.target).into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for InvSqrtISwap {
     /// Returns unitary matrix of the gate.
@@ -843,6 +892,14 @@ const TAGS_ControlledPauliY: &[&str; 4] = &[
     "ControlledPauliY",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for ControlledPauliY {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for ControlledPauliY {
     /// Returns unitary matrix of the gate.
@@ -942,6 +999,14 @@ const TAGS_ControlledPauliZ: &[&str; 4] = &[
     "ControlledPauliZ",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for ControlledPauliZ {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for ControlledPauliZ {
     /// Returns unitary matrix of the gate.
@@ -1037,6 +1102,19 @@ const TAGS_MolmerSorensenXX: &[&str; 4] = &[
     "MolmerSorensenXX",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for MolmerSorensenXX {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        VariableMSXX::new(
+            self.control,
+            self.target,
+            CalculatorFloat::FRAC_PI_2 * (-1.0),
+        )
+        .into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for MolmerSorensenXX {
     /// Returns unitary matrix of the gate.
@@ -2516,6 +2594,7 @@ impl OperateTwoQubitGate for ControlledRotateXY {
     Debug,
     Clone,
     PartialEq,
+    Eq,
     roqoqo_derive::InvolveQubits,
     roqoqo_derive::Operate,
     roqoqo_derive::Substitute,
@@ -2545,6 +2624,14 @@ const TAGS_EchoCrossResonance: &[&str; 4] = &[
     "EchoCrossResonance",
 ];
 
+/// Trait for unitary operations without any free parameters.
+impl OperateConstantGate for EchoCrossResonance {
+    /// Returns the inverse of the gate.
+    fn inverse(&self) -> GateOperation {
+        self.clone().into()
+    }
+}
+
 /// Trait for all Operations acting with a unitary gate on a set of qubits.
 impl OperateGate for EchoCrossResonance {
     /// Returns unitary matrix of the gate.
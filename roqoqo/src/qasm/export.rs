@@ -0,0 +1,629 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of roqoqo circuits to OpenQASM.
+
+use std::collections::{HashMap, HashSet};
+
+use super::gates::{format_float, format_parameter, gate_to_qasm, CustomGate, GateCall};
+use super::QasmVersion;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use qoqo_calculator::CalculatorFloat;
+
+/// The name of the quantum register holding all qubits of the circuit.
+const QUANTUM_REGISTER: &str = "q";
+
+/// Converts a [crate::Circuit] to an OpenQASM program.
+///
+/// Supported operations are gate operations, [MeasureQubit], [PragmaRepeatedMeasurement],
+/// [DefinitionBit], [PragmaConditional], [PragmaLoop], [PragmaActiveReset], [PragmaStopParallelBlock]
+/// (exported as a barrier) and [PragmaGlobalPhase].
+/// OpenQASM 3.0 additionally supports the remaining classical definitions, [InputBit], [InputSymbolic]
+/// and [PragmaSleep] (exported as a delay).
+/// Operations that only carry compilation hints ([PragmaSetNumberOfMeasurements] and the decomposition blocks)
+/// are skipped; [GateDefinition] and [CallDefinedGate] are exported as gate definition and call.
+///
+/// Symbolic parameters are written as they are in OpenQASM 3.0 and inside gate definitions.
+/// In OpenQASM 2.0 the circuit has to be fully substituted.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is exported.
+/// * `version` - The OpenQASM version of the output.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The OpenQASM program.
+/// * `Err(RoqoqoError)` - The circuit contains operations that have no equivalent in the OpenQASM version.
+pub fn circuit_to_qasm(circuit: &Circuit, version: QasmVersion) -> Result<String, RoqoqoError> {
+    let mut exporter = Exporter {
+        version,
+        number_qubits: circuit.number_of_qubits(),
+        definitions: Vec::new(),
+        builtin_gates: HashSet::new(),
+        defined_gates: HashSet::new(),
+        bit_registers: HashMap::new(),
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let scope = Scope {
+        arguments: None,
+        condition: None,
+        indent: 0,
+    };
+    for operation in circuit.iter() {
+        exporter.add_operation(operation, &scope, &mut lines)?;
+    }
+
+    let mut program = match version {
+        QasmVersion::V2_0 => "OPENQASM 2.0;\ninclude \"qelib1.inc\";\n\n".to_string(),
+        QasmVersion::V3_0 => "OPENQASM 3.0;\ninclude \"stdgates.inc\";\n\n".to_string(),
+    };
+    for definition in exporter.definitions.iter() {
+        program.push_str(definition);
+    }
+    if exporter.number_qubits > 0 {
+        match version {
+            QasmVersion::V2_0 => program.push_str(&format!(
+                "qreg {QUANTUM_REGISTER}[{}];\n",
+                exporter.number_qubits
+            )),
+            QasmVersion::V3_0 => program.push_str(&format!(
+                "qubit[{}] {QUANTUM_REGISTER};\n",
+                exporter.number_qubits
+            )),
+        }
+    }
+    for line in lines {
+        program.push_str(&line);
+        program.push('\n');
+    }
+    Ok(program)
+}
+
+/// The context in which operations are exported.
+struct Scope<'a> {
+    /// The names of the qubit arguments when exporting the body of a gate definition.
+    arguments: Option<&'a HashMap<usize, String>>,
+    /// The classical condition prefixed to every statement (OpenQASM 2.0 only).
+    condition: Option<String>,
+    /// The indentation level of the statements.
+    indent: usize,
+}
+
+impl Scope<'_> {
+    /// Returns the name of a qubit in the scope.
+    fn qubit(&self, qubit: usize) -> Result<String, RoqoqoError> {
+        match self.arguments {
+            None => Ok(format!("{QUANTUM_REGISTER}[{qubit}]")),
+            Some(arguments) => {
+                arguments
+                    .get(&qubit)
+                    .cloned()
+                    .ok_or_else(|| RoqoqoError::GenericError {
+                        msg: format!(
+                        "Qubit {qubit} is used in a gate definition but is not one of its qubits"
+                    ),
+                    })
+            }
+        }
+    }
+
+    /// Returns the comma separated names of several qubits.
+    fn qubits(&self, qubits: &[usize]) -> Result<String, RoqoqoError> {
+        Ok(qubits
+            .iter()
+            .map(|qubit| self.qubit(*qubit))
+            .collect::<Result<Vec<String>, RoqoqoError>>()?
+            .join(", "))
+    }
+}
+
+/// Collects the gate definitions and classical registers while exporting a circuit.
+struct Exporter {
+    version: QasmVersion,
+    number_qubits: usize,
+    /// Rendered gate definitions in the order they have to appear in the program.
+    definitions: Vec<String>,
+    /// Names of the gates defined for roqoqo gates without an equivalent in the include file.
+    builtin_gates: HashSet<String>,
+    /// Names of the gates defined with a GateDefinition.
+    defined_gates: HashSet<String>,
+    /// Lengths of the bit registers.
+    bit_registers: HashMap<String, usize>,
+}
+
+impl Exporter {
+    /// Returns the error for an operation without an equivalent in the OpenQASM version.
+    fn unsupported(&self, operation: &Operation) -> RoqoqoError {
+        RoqoqoError::GenericError {
+            msg: format!(
+                "Operation {} has no equivalent in OpenQASM {}",
+                operation.hqslang(),
+                self.version
+            ),
+        }
+    }
+
+    /// Returns whether symbolic parameters can be exported in the scope.
+    fn symbolic(&self, scope: &Scope) -> bool {
+        scope.arguments.is_some() || self.version == QasmVersion::V3_0
+    }
+
+    /// Adds a statement to the exported lines.
+    fn push(&self, lines: &mut Vec<String>, scope: &Scope, statement: String) {
+        let indent = "    ".repeat(scope.indent);
+        match &scope.condition {
+            Some(condition) => lines.push(format!("{indent}{condition} {statement}")),
+            None => lines.push(format!("{indent}{statement}")),
+        }
+    }
+
+    /// Adds an operation to the exported lines.
+    fn add_operation(
+        &mut self,
+        operation: &Operation,
+        scope: &Scope,
+        lines: &mut Vec<String>,
+    ) -> Result<(), RoqoqoError> {
+        if let Some(gate) = gate_to_qasm(operation, self.version, self.symbolic(scope))? {
+            if let Some(definition) = gate.definition {
+                self.add_builtin_definition(definition)?;
+            }
+            let statement = self.gate_call(&gate.call, scope)?;
+            self.push(lines, scope, statement);
+            return Ok(());
+        }
+        let symbolic = self.symbolic(scope);
+        if scope.arguments.is_some() {
+            return self.add_definition_body_operation(operation, scope, lines);
+        }
+        let version = self.version;
+        match operation {
+            Operation::PragmaAnnotatedOp(op) => self.add_operation(&op.operation, scope, lines)?,
+            Operation::DefinitionBit(op) => {
+                self.check_register_name(operation, op.name(), scope)?;
+                self.bit_registers.insert(op.name().clone(), *op.length());
+                let statement = match version {
+                    QasmVersion::V2_0 => format!("creg {}[{}];", op.name(), op.length()),
+                    QasmVersion::V3_0 => format!("bit[{}] {};", op.length(), op.name()),
+                };
+                self.push(lines, scope, statement);
+            }
+            Operation::DefinitionFloat(op) if version == QasmVersion::V3_0 => {
+                self.check_register_name(operation, op.name(), scope)?;
+                let statement = format!("array[float[64], {}] {};", op.length(), op.name());
+                self.push(lines, scope, statement);
+            }
+            Operation::DefinitionComplex(op) if version == QasmVersion::V3_0 => {
+                self.check_register_name(operation, op.name(), scope)?;
+                let statement =
+                    format!("array[complex[float[64]], {}] {};", op.length(), op.name());
+                self.push(lines, scope, statement);
+            }
+            Operation::DefinitionUsize(op) if version == QasmVersion::V3_0 => {
+                self.check_register_name(operation, op.name(), scope)?;
+                let statement = format!("array[uint[64], {}] {};", op.length(), op.name());
+                self.push(lines, scope, statement);
+            }
+            Operation::InputSymbolic(op) if version == QasmVersion::V3_0 => {
+                self.check_register_name(operation, op.name(), scope)?;
+                let statement =
+                    format!("float[64] {} = {};", op.name(), format_float(*op.input())?);
+                self.push(lines, scope, statement);
+            }
+            Operation::InputBit(op) if version == QasmVersion::V3_0 => {
+                let statement =
+                    format!("{}[{}] = {};", op.name(), op.index(), u8::from(*op.value()));
+                self.push(lines, scope, statement);
+            }
+            Operation::MeasureQubit(op) => {
+                let statement =
+                    self.measurement(*op.qubit(), op.readout(), *op.readout_index(), scope)?;
+                self.push(lines, scope, statement);
+            }
+            Operation::PragmaRepeatedMeasurement(op) => {
+                let mut mapping: Vec<(usize, usize)> = match op.qubit_mapping() {
+                    Some(mapping) => mapping
+                        .iter()
+                        .map(|(qubit, index)| (*qubit, *index))
+                        .collect(),
+                    None => {
+                        let length = self
+                            .bit_registers
+                            .get(op.readout())
+                            .copied()
+                            .unwrap_or(self.number_qubits);
+                        (0..self.number_qubits.min(length))
+                            .map(|qubit| (qubit, qubit))
+                            .collect()
+                    }
+                };
+                mapping.sort_unstable();
+                for (qubit, index) in mapping {
+                    let statement = self.measurement(qubit, op.readout(), index, scope)?;
+                    self.push(lines, scope, statement);
+                }
+            }
+            Operation::PragmaActiveReset(op) => {
+                let statement = format!("reset {};", scope.qubit(*op.qubit())?);
+                self.push(lines, scope, statement);
+            }
+            Operation::PragmaStopParallelBlock(op) => {
+                if scope.condition.is_some() {
+                    return Err(self.unsupported_in_condition(operation));
+                }
+                if !op.qubits().is_empty() {
+                    let statement = format!("barrier {};", scope.qubits(op.qubits())?);
+                    self.push(lines, scope, statement);
+                }
+            }
+            Operation::PragmaGlobalPhase(op) => {
+                if version == QasmVersion::V3_0 {
+                    let phase = format_parameter(op.phase(), operation.hqslang(), symbolic)?;
+                    self.push(lines, scope, format!("gphase({phase});"));
+                }
+            }
+            Operation::PragmaSleep(op) if version == QasmVersion::V3_0 => {
+                let time = format_parameter(op.sleep_time(), operation.hqslang(), false)?;
+                let statement = format!("delay[{time}s] {};", scope.qubits(op.qubits())?);
+                self.push(lines, scope, statement);
+            }
+            Operation::PragmaSetNumberOfMeasurements(_)
+            | Operation::PragmaStartDecompositionBlock(_)
+            | Operation::PragmaStopDecompositionBlock(_) => (),
+            Operation::PragmaLoop(op) => self.add_loop(op, scope, lines)?,
+            Operation::PragmaConditional(op) => self.add_conditional(op, scope, lines)?,
+            #[cfg(feature = "unstable_operation_definition")]
+            Operation::GateDefinition(op) => self.add_gate_definition(op)?,
+            #[cfg(feature = "unstable_operation_definition")]
+            Operation::CallDefinedGate(op) => {
+                let statement = self.defined_gate_call(op, scope)?;
+                self.push(lines, scope, statement);
+            }
+            _ => return Err(self.unsupported(operation)),
+        }
+        Ok(())
+    }
+
+    /// Adds an operation in the body of a gate definition.
+    ///
+    /// Only gates can be used in gate definitions, gate operations are handled before.
+    fn add_definition_body_operation(
+        &mut self,
+        operation: &Operation,
+        scope: &Scope,
+        lines: &mut Vec<String>,
+    ) -> Result<(), RoqoqoError> {
+        match operation {
+            Operation::PragmaAnnotatedOp(op) => self.add_operation(&op.operation, scope, lines),
+            Operation::PragmaGlobalPhase(op) => {
+                if self.version == QasmVersion::V3_0 {
+                    let phase = format_parameter(op.phase(), operation.hqslang(), true)?;
+                    self.push(lines, scope, format!("gphase({phase});"));
+                }
+                Ok(())
+            }
+            #[cfg(feature = "unstable_operation_definition")]
+            Operation::CallDefinedGate(op) => {
+                let statement = self.defined_gate_call(op, scope)?;
+                self.push(lines, scope, statement);
+                Ok(())
+            }
+            _ => Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Operation {} can not be used in an OpenQASM gate definition",
+                    operation.hqslang()
+                ),
+            }),
+        }
+    }
+
+    /// Returns the error for an operation that can not be conditioned in OpenQASM 2.0.
+    fn unsupported_in_condition(&self, operation: &Operation) -> RoqoqoError {
+        RoqoqoError::GenericError {
+            msg: format!(
+                "Operation {} can not be used in a PragmaConditional in OpenQASM {}",
+                operation.hqslang(),
+                self.version
+            ),
+        }
+    }
+
+    /// Checks that a classical register does not shadow the quantum register.
+    fn check_register_name(
+        &self,
+        operation: &Operation,
+        name: &str,
+        scope: &Scope,
+    ) -> Result<(), RoqoqoError> {
+        if scope.condition.is_some() {
+            return Err(self.unsupported_in_condition(operation));
+        }
+        if name == QUANTUM_REGISTER {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Classical register {name} of {} clashes with the name of the OpenQASM quantum register",
+                    operation.hqslang()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the statement measuring a qubit into a bit register.
+    fn measurement(
+        &self,
+        qubit: usize,
+        readout: &str,
+        index: usize,
+        scope: &Scope,
+    ) -> Result<String, RoqoqoError> {
+        let qubit = scope.qubit(qubit)?;
+        Ok(match self.version {
+            QasmVersion::V2_0 => format!("measure {qubit} -> {readout}[{index}];"),
+            QasmVersion::V3_0 => format!("{readout}[{index}] = measure {qubit};"),
+        })
+    }
+
+    /// Returns the statement calling a gate.
+    fn gate_call(&self, call: &GateCall, scope: &Scope) -> Result<String, RoqoqoError> {
+        let qubits = scope.qubits(&call.qubits)?;
+        if call.parameters.is_empty() {
+            Ok(format!("{} {qubits};", call.name))
+        } else {
+            Ok(format!(
+                "{}({}) {qubits};",
+                call.name,
+                call.parameters.join(", ")
+            ))
+        }
+    }
+
+    /// Adds the definition of a roqoqo gate without an equivalent in the include file.
+    fn add_builtin_definition(&mut self, definition: CustomGate) -> Result<(), RoqoqoError> {
+        if self.defined_gates.contains(&definition.name) {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "The GateDefinition {} clashes with the OpenQASM definition of a roqoqo gate",
+                    definition.name
+                ),
+            });
+        }
+        if !self.builtin_gates.insert(definition.name.clone()) {
+            return Ok(());
+        }
+        self.definitions.push(builtin_definition(&definition));
+        Ok(())
+    }
+
+    /// Adds a PragmaLoop, unrolled in OpenQASM 2.0 and as a for loop in OpenQASM 3.0.
+    fn add_loop(
+        &mut self,
+        operation: &PragmaLoop,
+        scope: &Scope,
+        lines: &mut Vec<String>,
+    ) -> Result<(), RoqoqoError> {
+        let repetitions = match operation.repetitions() {
+            CalculatorFloat::Float(x) => x.floor().max(0.0) as usize,
+            CalculatorFloat::Str(expression) => {
+                return Err(RoqoqoError::GenericError {
+                    msg: format!(
+                        "PragmaLoop with symbolic number of repetitions {expression} can not be exported to OpenQASM"
+                    ),
+                })
+            }
+        };
+        match self.version {
+            QasmVersion::V2_0 => {
+                for _ in 0..repetitions {
+                    for inner in operation.circuit().iter() {
+                        self.add_operation(inner, scope, lines)?;
+                    }
+                }
+            }
+            QasmVersion::V3_0 => {
+                if repetitions == 0 {
+                    return Ok(());
+                }
+                self.push(
+                    lines,
+                    scope,
+                    format!("for uint i{} in [0:{}] {{", scope.indent, repetitions - 1),
+                );
+                let inner_scope = Scope {
+                    arguments: None,
+                    condition: None,
+                    indent: scope.indent + 1,
+                };
+                for inner in operation.circuit().iter() {
+                    self.add_operation(inner, &inner_scope, lines)?;
+                }
+                self.push(lines, scope, "}".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a PragmaConditional.
+    ///
+    /// OpenQASM 2.0 can only compare complete registers to an integer,
+    /// so the condition register has to be a single bit.
+    fn add_conditional(
+        &mut self,
+        operation: &PragmaConditional,
+        scope: &Scope,
+        lines: &mut Vec<String>,
+    ) -> Result<(), RoqoqoError> {
+        let register = operation.condition_register();
+        match self.version {
+            QasmVersion::V2_0 => {
+                if scope.condition.is_some() {
+                    return Err(self.unsupported_in_condition(&operation.clone().into()));
+                }
+                if self.bit_registers.get(register) != Some(&1) || *operation.condition_index() != 0
+                {
+                    return Err(RoqoqoError::GenericError {
+                        msg: format!(
+                            "PragmaConditional on bit {} of register {register} can not be exported to OpenQASM 2.0, \
+                             which only supports conditions on registers of length 1",
+                            operation.condition_index()
+                        ),
+                    });
+                }
+                let inner_scope = Scope {
+                    arguments: None,
+                    condition: Some(format!("if({register}==1)")),
+                    indent: scope.indent,
+                };
+                for inner in operation.circuit().iter() {
+                    self.add_operation(inner, &inner_scope, lines)?;
+                }
+            }
+            QasmVersion::V3_0 => {
+                self.push(
+                    lines,
+                    scope,
+                    format!("if ({register}[{}] == 1) {{", operation.condition_index()),
+                );
+                let inner_scope = Scope {
+                    arguments: None,
+                    condition: None,
+                    indent: scope.indent + 1,
+                };
+                for inner in operation.circuit().iter() {
+                    self.add_operation(inner, &inner_scope, lines)?;
+                }
+                self.push(lines, scope, "}".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a GateDefinition as an OpenQASM gate definition.
+    #[cfg(feature = "unstable_operation_definition")]
+    fn add_gate_definition(&mut self, operation: &GateDefinition) -> Result<(), RoqoqoError> {
+        let name = operation.name();
+        if self.builtin_gates.contains(name) || !self.defined_gates.insert(name.clone()) {
+            return Err(RoqoqoError::GenericError {
+                msg: format!("Gate {name} is defined more than once"),
+            });
+        }
+        let arguments: HashMap<usize, String> = operation
+            .qubits()
+            .iter()
+            .enumerate()
+            .map(|(position, qubit)| (*qubit, format!("q{position}")))
+            .collect();
+        let scope = Scope {
+            arguments: Some(&arguments),
+            condition: None,
+            indent: 1,
+        };
+        let mut body: Vec<String> = Vec::new();
+        for operation in operation.circuit().iter() {
+            self.add_operation(operation, &scope, &mut body)?;
+        }
+        self.definitions.push(render_definition(
+            name,
+            operation.free_parameters(),
+            operation.qubits().len(),
+            body,
+        ));
+        Ok(())
+    }
+
+    /// Returns the statement calling a gate defined with a GateDefinition.
+    #[cfg(feature = "unstable_operation_definition")]
+    fn defined_gate_call(
+        &self,
+        operation: &CallDefinedGate,
+        scope: &Scope,
+    ) -> Result<String, RoqoqoError> {
+        if !self.defined_gates.contains(operation.gate_name()) {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "CallDefinedGate calls gate {} before its GateDefinition",
+                    operation.gate_name()
+                ),
+            });
+        }
+        let parameters = operation
+            .free_parameters()
+            .iter()
+            .map(|parameter| format_parameter(parameter, "CallDefinedGate", self.symbolic(scope)))
+            .collect::<Result<Vec<String>, RoqoqoError>>()?;
+        self.gate_call(
+            &GateCall {
+                name: operation.gate_name().clone(),
+                parameters,
+                qubits: operation.qubits().clone(),
+            },
+            scope,
+        )
+    }
+}
+
+/// Renders the definition of a roqoqo gate without an equivalent in the include file.
+pub(super) fn builtin_definition(definition: &CustomGate) -> String {
+    let body = definition
+        .body
+        .iter()
+        .map(|call| {
+            let qubits = call
+                .qubits
+                .iter()
+                .map(|qubit| format!("q{qubit}"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            if call.parameters.is_empty() {
+                format!("    {} {qubits};", call.name)
+            } else {
+                format!(
+                    "    {}({}) {qubits};",
+                    call.name,
+                    call.parameters.join(", ")
+                )
+            }
+        })
+        .collect();
+    render_definition(
+        &definition.name,
+        &definition.parameters,
+        definition.number_qubits,
+        body,
+    )
+}
+
+/// Renders a gate definition with the qubit arguments q0, q1, ...
+fn render_definition(
+    name: &str,
+    parameters: &[String],
+    number_qubits: usize,
+    body: Vec<String>,
+) -> String {
+    let arguments = (0..number_qubits)
+        .map(|position| format!("q{position}"))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut definition = if parameters.is_empty() {
+        format!("gate {name} {arguments} {{\n")
+    } else {
+        format!("gate {name}({}) {arguments} {{\n", parameters.join(", "))
+    };
+    for line in body {
+        definition.push_str(&line);
+        definition.push('\n');
+    }
+    definition.push_str("}\n");
+    definition
+}
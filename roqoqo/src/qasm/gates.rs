@@ -0,0 +1,1201 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of roqoqo gate operations to OpenQASM gate calls and gate definitions.
+
+use super::QasmVersion;
+use crate::operations::*;
+use crate::RoqoqoError;
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+
+/// A call of a gate in an OpenQASM program.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GateCall {
+    /// The name of the called gate.
+    pub name: String,
+    /// The parameters of the call as OpenQASM expressions.
+    pub parameters: Vec<String>,
+    /// The qubits the gate acts on.
+    pub qubits: Vec<usize>,
+}
+
+/// The `gate` definition of a gate that is not part of the standard include file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CustomGate {
+    /// The name of the defined gate.
+    pub name: String,
+    /// The names of the formal parameters of the gate.
+    pub parameters: Vec<String>,
+    /// The number of qubits the gate acts on.
+    pub number_qubits: usize,
+    /// The body of the definition, the qubits of the calls are indices into the qubit arguments.
+    pub body: Vec<GateCall>,
+}
+
+/// A gate operation translated to OpenQASM.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct QasmGate {
+    /// The call of the gate.
+    pub call: GateCall,
+    /// The definition that has to precede the call if the gate is not in the include file.
+    pub definition: Option<CustomGate>,
+}
+
+/// Collects the calls in the body of a custom gate definition.
+#[derive(Debug, Default)]
+struct Body(Vec<GateCall>);
+
+impl Body {
+    /// Adds a call of a gate without parameters.
+    fn gate(&mut self, name: &str, qubits: &[usize]) {
+        self.0.push(GateCall {
+            name: name.to_string(),
+            parameters: Vec::new(),
+            qubits: qubits.to_vec(),
+        });
+    }
+
+    /// Adds a call of a gate with a single angle parameter.
+    fn rotation(&mut self, name: &str, angle: impl AsRef<str>, qubits: &[usize]) {
+        self.0.push(GateCall {
+            name: name.to_string(),
+            parameters: vec![angle.as_ref().to_string()],
+            qubits: qubits.to_vec(),
+        });
+    }
+
+    /// Adds exp(-i * angle/2 * X_a X_b).
+    fn rxx(&mut self, angle: impl AsRef<str>, a: usize, b: usize) {
+        self.gate("h", &[a]);
+        self.gate("h", &[b]);
+        self.rzz(angle, a, b);
+        self.gate("h", &[a]);
+        self.gate("h", &[b]);
+    }
+
+    /// Adds exp(-i * angle/2 * Y_a Y_b).
+    fn ryy(&mut self, angle: impl AsRef<str>, a: usize, b: usize) {
+        self.rotation("rx", "pi/2", &[a]);
+        self.rotation("rx", "pi/2", &[b]);
+        self.rzz(angle, a, b);
+        self.rotation("rx", "-pi/2", &[a]);
+        self.rotation("rx", "-pi/2", &[b]);
+    }
+
+    /// Adds exp(-i * angle/2 * Z_a Z_b).
+    fn rzz(&mut self, angle: impl AsRef<str>, a: usize, b: usize) {
+        self.gate("cx", &[a, b]);
+        self.rotation("rz", angle, &[b]);
+        self.gate("cx", &[a, b]);
+    }
+
+    /// Adds a phase shift on the target that is applied when all controls are in the state |1>.
+    fn multi_controlled_phase(&mut self, angle: &str, controls: &[usize], target: usize) {
+        match controls.split_last() {
+            None => self.rotation("p", angle, &[target]),
+            Some((last, [])) => self.rotation("cp", angle, &[*last, target]),
+            Some((last, rest)) => {
+                let half_angle = halve(angle);
+                self.rotation("cp", &half_angle, &[*last, target]);
+                self.multi_controlled_x(rest, *last);
+                self.rotation("cp", negate(&half_angle), &[*last, target]);
+                self.multi_controlled_x(rest, *last);
+                self.multi_controlled_phase(&half_angle, rest, target);
+            }
+        }
+    }
+
+    /// Adds a PauliX on the target that is applied when all controls are in the state |1>.
+    fn multi_controlled_x(&mut self, controls: &[usize], target: usize) {
+        match controls {
+            [] => self.gate("x", &[target]),
+            [control] => self.gate("cx", &[*control, target]),
+            [control_0, control_1] => self.gate("ccx", &[*control_0, *control_1, target]),
+            _ => {
+                self.gate("h", &[target]);
+                self.multi_controlled_phase("pi", controls, target);
+                self.gate("h", &[target]);
+            }
+        }
+    }
+}
+
+/// Wraps an expression in parentheses unless it is a single (possibly negated) product.
+fn wrap(expression: &str) -> String {
+    if expression
+        .chars()
+        .skip(1)
+        .any(|character| character == '+' || character == '-')
+    {
+        format!("({expression})")
+    } else {
+        expression.to_string()
+    }
+}
+
+/// Returns the negated expression.
+fn negate(expression: &str) -> String {
+    match expression.strip_prefix('-') {
+        Some(rest) if !rest.contains(['+', '-']) => rest.to_string(),
+        _ => format!("-{}", wrap(expression)),
+    }
+}
+
+/// Returns half of the expression.
+fn halve(expression: &str) -> String {
+    format!("{}/2", wrap(expression))
+}
+
+/// Formats a floating point number as an OpenQASM literal.
+pub(crate) fn format_float(value: f64) -> Result<String, RoqoqoError> {
+    if value.is_finite() {
+        Ok(format!("{value}"))
+    } else {
+        Err(RoqoqoError::GenericError {
+            msg: format!("Value {value} can not be represented in OpenQASM"),
+        })
+    }
+}
+
+/// Formats a parameter of an operation as an OpenQASM expression.
+///
+/// Symbolic parameters are written as they are when `symbolic` is true.
+pub(crate) fn format_parameter(
+    value: &CalculatorFloat,
+    hqslang: &str,
+    symbolic: bool,
+) -> Result<String, RoqoqoError> {
+    match value {
+        CalculatorFloat::Float(x) => format_float(*x),
+        CalculatorFloat::Str(expression) if symbolic => Ok(expression.clone()),
+        CalculatorFloat::Str(expression) => Err(RoqoqoError::GenericError {
+            msg: format!(
+                "Symbolic parameter {expression} of {hqslang} can not be exported to OpenQASM, \
+                 substitute the parameters of the circuit first"
+            ),
+        }),
+    }
+}
+
+/// Returns the numerical value of a parameter required to compute derived parameters.
+fn numeric_parameter(value: &CalculatorFloat, hqslang: &str) -> Result<f64, RoqoqoError> {
+    match value {
+        CalculatorFloat::Float(x) => Ok(*x),
+        CalculatorFloat::Str(expression) => Err(RoqoqoError::GenericError {
+            msg: format!(
+                "Symbolic parameter {expression} of {hqslang} can not be exported to OpenQASM, \
+                 the gate requires numerical parameters"
+            ),
+        }),
+    }
+}
+
+/// Returns the call of a gate of the include file.
+fn standard(name: &str, parameters: Vec<String>, qubits: Vec<usize>) -> QasmGate {
+    QasmGate {
+        call: GateCall {
+            name: name.to_string(),
+            parameters,
+            qubits,
+        },
+        definition: None,
+    }
+}
+
+/// Returns the call of a gate together with its custom definition.
+fn custom(
+    name: String,
+    parameter_names: &[&str],
+    parameters: Vec<String>,
+    qubits: Vec<usize>,
+    build_body: impl FnOnce(&mut Body),
+) -> QasmGate {
+    let mut body = Body::default();
+    build_body(&mut body);
+    QasmGate {
+        call: GateCall {
+            name: name.clone(),
+            parameters,
+            qubits: qubits.clone(),
+        },
+        definition: Some(CustomGate {
+            name,
+            parameters: parameter_names
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            number_qubits: qubits.len(),
+            body: body.0,
+        }),
+    }
+}
+
+/// Translates a gate operation to an OpenQASM gate call.
+///
+/// Formal parameters of custom definitions use the field names of the roqoqo gate, except where
+/// a field name coincides with the name of a standard gate (x, y, z, t, u), which are prefixed with `param_`.
+///
+/// # Arguments
+///
+/// * `operation` - The operation to translate.
+/// * `version` - The OpenQASM version of the output.
+/// * `symbolic` - Whether symbolic parameters are written as expressions instead of returning an error.
+///
+/// # Returns
+///
+/// * `Ok(Some(QasmGate))` - The translated gate.
+/// * `Ok(None)` - The operation is not a gate operation.
+/// * `Err(RoqoqoError)` - The gate can not be translated with the given parameters.
+pub(crate) fn gate_to_qasm(
+    operation: &Operation,
+    version: QasmVersion,
+    symbolic: bool,
+) -> Result<Option<QasmGate>, RoqoqoError> {
+    let hqslang = operation.hqslang();
+    let parameter = |value: &CalculatorFloat| format_parameter(value, hqslang, symbolic);
+    let name = hqslang.to_lowercase();
+    let gate = match operation {
+        // Single-qubit gates
+        Operation::PauliX(op) => standard("x", vec![], vec![*op.qubit()]),
+        Operation::PauliY(op) => standard("y", vec![], vec![*op.qubit()]),
+        Operation::PauliZ(op) => standard("z", vec![], vec![*op.qubit()]),
+        Operation::Hadamard(op) => standard("h", vec![], vec![*op.qubit()]),
+        Operation::SGate(op) => standard("s", vec![], vec![*op.qubit()]),
+        Operation::InvSGate(op) => standard("sdg", vec![], vec![*op.qubit()]),
+        Operation::TGate(op) => standard("t", vec![], vec![*op.qubit()]),
+        Operation::InvTGate(op) => standard("tdg", vec![], vec![*op.qubit()]),
+        Operation::Identity(op) => standard("id", vec![], vec![*op.qubit()]),
+        Operation::SXGate(op) => standard("sx", vec![], vec![*op.qubit()]),
+        Operation::InvSXGate(op) => match version {
+            QasmVersion::V2_0 => standard("sxdg", vec![], vec![*op.qubit()]),
+            QasmVersion::V3_0 => custom(name, &[], vec![], vec![*op.qubit()], |body| {
+                body.rotation("rx", "-pi/2", &[0])
+            }),
+        },
+        Operation::RotateX(op) => standard("rx", vec![parameter(op.theta())?], vec![*op.qubit()]),
+        Operation::RotateY(op) => standard("ry", vec![parameter(op.theta())?], vec![*op.qubit()]),
+        Operation::RotateZ(op) => standard("rz", vec![parameter(op.theta())?], vec![*op.qubit()]),
+        Operation::PhaseShiftState1(op) => {
+            standard("p", vec![parameter(op.theta())?], vec![*op.qubit()])
+        }
+        Operation::SingleQubitGate(op) => {
+            let alpha = Complex64::new(
+                numeric_parameter(&op.alpha_r(), hqslang)?,
+                numeric_parameter(&op.alpha_i(), hqslang)?,
+            );
+            let beta = Complex64::new(
+                numeric_parameter(&op.beta_r(), hqslang)?,
+                numeric_parameter(&op.beta_i(), hqslang)?,
+            );
+            // [[alpha, -beta*], [beta, alpha*]] equals u3(theta, phi, lambda) up to a global phase
+            let theta = 2.0 * beta.norm().atan2(alpha.norm());
+            let phi = beta.arg() - alpha.arg();
+            let lambda = -beta.arg() - alpha.arg();
+            standard(
+                "u3",
+                vec![
+                    format_float(theta)?,
+                    format_float(phi)?,
+                    format_float(lambda)?,
+                ],
+                vec![*op.qubit()],
+            )
+        }
+        Operation::PhaseShiftState0(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.qubit()],
+            |body| body.rotation("p", "-theta", &[0]),
+        ),
+        Operation::SqrtPauliX(op) => custom(name, &[], vec![], vec![*op.qubit()], |body| {
+            body.rotation("rx", "pi/2", &[0])
+        }),
+        Operation::InvSqrtPauliX(op) => custom(name, &[], vec![], vec![*op.qubit()], |body| {
+            body.rotation("rx", "-pi/2", &[0])
+        }),
+        Operation::SqrtPauliY(op) => custom(name, &[], vec![], vec![*op.qubit()], |body| {
+            body.rotation("ry", "pi/2", &[0])
+        }),
+        Operation::InvSqrtPauliY(op) => custom(name, &[], vec![], vec![*op.qubit()], |body| {
+            body.rotation("ry", "-pi/2", &[0])
+        }),
+        Operation::RotateAroundSphericalAxis(op) => custom(
+            name,
+            &["theta", "spherical_theta", "spherical_phi"],
+            vec![
+                parameter(op.theta())?,
+                parameter(op.spherical_theta())?,
+                parameter(op.spherical_phi())?,
+            ],
+            vec![*op.qubit()],
+            |body| {
+                body.rotation("rz", "-spherical_phi", &[0]);
+                body.rotation("ry", "-spherical_theta", &[0]);
+                body.rotation("rz", "theta", &[0]);
+                body.rotation("ry", "spherical_theta", &[0]);
+                body.rotation("rz", "spherical_phi", &[0]);
+            },
+        ),
+        Operation::RotateXY(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.qubit()],
+            |body| {
+                body.rotation("rz", "-phi", &[0]);
+                body.rotation("rx", "theta", &[0]);
+                body.rotation("rz", "phi", &[0]);
+            },
+        ),
+        Operation::GPi(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.qubit()],
+            |body| {
+                body.rotation("rz", "-theta", &[0]);
+                body.gate("x", &[0]);
+                body.rotation("rz", "theta", &[0]);
+            },
+        ),
+        Operation::GPi2(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.qubit()],
+            |body| {
+                body.rotation("rz", "-theta", &[0]);
+                body.rotation("rx", "pi/2", &[0]);
+                body.rotation("rz", "theta", &[0]);
+            },
+        ),
+        // Two-qubit gates
+        Operation::CNOT(op) => standard("cx", vec![], vec![*op.control(), *op.target()]),
+        Operation::ControlledPauliY(op) => {
+            standard("cy", vec![], vec![*op.control(), *op.target()])
+        }
+        Operation::ControlledPauliZ(op) => {
+            standard("cz", vec![], vec![*op.control(), *op.target()])
+        }
+        Operation::SWAP(op) => standard("swap", vec![], vec![*op.control(), *op.target()]),
+        Operation::ControlledPhaseShift(op) => standard(
+            "cp",
+            vec![parameter(op.theta())?],
+            vec![*op.control(), *op.target()],
+        ),
+        Operation::ControlledRotateX(op) => standard(
+            "crx",
+            vec![parameter(op.theta())?],
+            vec![*op.control(), *op.target()],
+        ),
+        Operation::ISwap(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("-pi/2", 0, 1);
+                body.ryy("-pi/2", 0, 1);
+            },
+        ),
+        Operation::SqrtISwap(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("-pi/4", 0, 1);
+                body.ryy("-pi/4", 0, 1);
+            },
+        ),
+        Operation::InvSqrtISwap(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("pi/4", 0, 1);
+                body.ryy("pi/4", 0, 1);
+            },
+        ),
+        Operation::XY(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("-theta/2", 0, 1);
+                body.ryy("-theta/2", 0, 1);
+            },
+        ),
+        Operation::FSwap(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.gate("swap", &[0, 1]);
+                body.gate("cz", &[0, 1]);
+            },
+        ),
+        Operation::MolmerSorensenXX(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| body.rxx("pi/2", 0, 1),
+        ),
+        Operation::VariableMSXX(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.control(), *op.target()],
+            |body| body.rxx("theta", 0, 1),
+        ),
+        Operation::GivensRotation(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rotation("rz", "phi+pi/2", &[1]);
+                body.rxx("-theta", 0, 1);
+                body.ryy("-theta", 0, 1);
+                body.rotation("rz", "-pi/2", &[1]);
+            },
+        ),
+        Operation::GivensRotationLittleEndian(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rotation("rz", "-pi/2", &[0]);
+                body.rxx("-theta", 0, 1);
+                body.ryy("-theta", 0, 1);
+                body.rotation("rz", "phi+pi/2", &[0]);
+            },
+        ),
+        Operation::Qsim(op) => custom(
+            name,
+            &["param_x", "param_y", "param_z"],
+            vec![parameter(op.x())?, parameter(op.y())?, parameter(op.z())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("2*param_x-pi/2", 0, 1);
+                body.ryy("2*param_y-pi/2", 0, 1);
+                body.rzz("2*param_z-pi/2", 0, 1);
+            },
+        ),
+        Operation::SpinInteraction(op) => custom(
+            name,
+            &["param_x", "param_y", "param_z"],
+            vec![parameter(op.x())?, parameter(op.y())?, parameter(op.z())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("2*param_x", 0, 1);
+                body.ryy("2*param_y", 0, 1);
+                body.rzz("2*param_z", 0, 1);
+            },
+        ),
+        Operation::Fsim(op) => custom(
+            name,
+            &["param_t", "param_u", "delta"],
+            vec![
+                parameter(op.t())?,
+                parameter(op.u())?,
+                parameter(op.delta())?,
+            ],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("param_t-delta-pi/2", 0, 1);
+                body.ryy("param_t+delta-pi/2", 0, 1);
+                body.rzz("param_u/2", 0, 1);
+                body.rotation("rz", "-param_u/2-pi/2", &[0]);
+                body.rotation("rz", "-param_u/2-pi/2", &[1]);
+            },
+        ),
+        Operation::PMInteraction(op) => custom(
+            name,
+            &["param_t"],
+            vec![parameter(op.t())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rxx("param_t", 0, 1);
+                body.ryy("param_t", 0, 1);
+            },
+        ),
+        Operation::Bogoliubov(op) => {
+            let delta = Complex64::new(
+                numeric_parameter(op.delta_real(), hqslang)?,
+                numeric_parameter(op.delta_imag(), hqslang)?,
+            );
+            custom(
+                name,
+                &["delta_abs", "delta_arg"],
+                vec![format_float(delta.norm())?, format_float(delta.arg())?],
+                vec![*op.control(), *op.target()],
+                |body| {
+                    body.rotation("rz", "delta_arg", &[1]);
+                    body.rxx("-delta_abs", 0, 1);
+                    body.ryy("delta_abs", 0, 1);
+                    body.rotation("rz", "-delta_arg", &[1]);
+                },
+            )
+        }
+        Operation::ComplexPMInteraction(op) => {
+            let t = Complex64::new(
+                numeric_parameter(op.t_real(), hqslang)?,
+                numeric_parameter(op.t_imag(), hqslang)?,
+            );
+            custom(
+                name,
+                &["t_abs", "t_arg"],
+                vec![format_float(t.norm())?, format_float(t.arg())?],
+                vec![*op.control(), *op.target()],
+                |body| {
+                    body.rotation("rz", "t_arg", &[1]);
+                    body.rxx("t_abs", 0, 1);
+                    body.ryy("t_abs", 0, 1);
+                    body.rotation("rz", "-t_arg", &[1]);
+                },
+            )
+        }
+        Operation::PhaseShiftedControlledZ(op) => custom(
+            name,
+            &["phi"],
+            vec![parameter(op.phi())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.gate("cz", &[0, 1]);
+                body.rotation("p", "phi", &[0]);
+                body.rotation("p", "phi", &[1]);
+            },
+        ),
+        Operation::PhaseShiftedControlledPhase(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rotation("cp", "theta", &[0, 1]);
+                body.rotation("p", "phi", &[0]);
+                body.rotation("p", "phi", &[1]);
+            },
+        ),
+        Operation::ControlledRotateXY(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.rotation("rz", "-phi", &[1]);
+                body.rotation("crx", "theta", &[0, 1]);
+                body.rotation("rz", "phi", &[1]);
+            },
+        ),
+        Operation::EchoCrossResonance(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control(), *op.target()],
+            |body| {
+                body.gate("s", &[0]);
+                body.rotation("rz", "pi/2", &[0]);
+                body.rotation("ry", "pi/2", &[0]);
+                body.rotation("rx", "pi", &[1]);
+                body.rxx("-pi/2", 0, 1);
+                body.rotation("ry", "-pi/2", &[0]);
+                body.gate("x", &[0]);
+            },
+        ),
+        // Three-qubit gates
+        Operation::Toffoli(op) => standard(
+            "ccx",
+            vec![],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+        ),
+        Operation::ControlledSWAP(op) => standard(
+            "cswap",
+            vec![],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+        ),
+        Operation::ControlledControlledPauliZ(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+            |body| body.multi_controlled_phase("pi", &[0, 1], 2),
+        ),
+        Operation::ControlledControlledPhaseShift(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+            |body| body.multi_controlled_phase("theta", &[0, 1], 2),
+        ),
+        Operation::PhaseShiftedControlledControlledZ(op) => custom(
+            name,
+            &["phi"],
+            vec![parameter(op.phi())?],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+            |body| {
+                body.multi_controlled_phase("pi", &[0, 1], 2);
+                for qubit in 0..3 {
+                    body.rotation("p", "phi", &[qubit]);
+                }
+            },
+        ),
+        Operation::PhaseShiftedControlledControlledPhase(op) => custom(
+            name,
+            &["theta", "phi"],
+            vec![parameter(op.theta())?, parameter(op.phi())?],
+            vec![*op.control_0(), *op.control_1(), *op.target()],
+            |body| {
+                body.multi_controlled_phase("theta", &[0, 1], 2);
+                for qubit in 0..3 {
+                    body.rotation("p", "phi", &[qubit]);
+                }
+            },
+        ),
+        // Four-qubit gates
+        Operation::TripleControlledPauliX(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![
+                *op.control_0(),
+                *op.control_1(),
+                *op.control_2(),
+                *op.target(),
+            ],
+            |body| body.multi_controlled_x(&[0, 1, 2], 3),
+        ),
+        Operation::TripleControlledPauliZ(op) => custom(
+            name,
+            &[],
+            vec![],
+            vec![
+                *op.control_0(),
+                *op.control_1(),
+                *op.control_2(),
+                *op.target(),
+            ],
+            |body| body.multi_controlled_phase("pi", &[0, 1, 2], 3),
+        ),
+        Operation::TripleControlledPhaseShift(op) => custom(
+            name,
+            &["theta"],
+            vec![parameter(op.theta())?],
+            vec![
+                *op.control_0(),
+                *op.control_1(),
+                *op.control_2(),
+                *op.target(),
+            ],
+            |body| body.multi_controlled_phase("theta", &[0, 1, 2], 3),
+        ),
+        // Multi-qubit gates
+        Operation::MultiQubitCNOT(op) => {
+            let qubits = op.qubits().clone();
+            match qubits.len() {
+                2 => standard("cx", vec![], qubits),
+                3 => standard("ccx", vec![], qubits),
+                n => custom(format!("{name}_{n}"), &[], vec![], qubits, |body| {
+                    let controls: Vec<usize> = (0..n.saturating_sub(1)).collect();
+                    body.multi_controlled_x(&controls, n.saturating_sub(1))
+                }),
+            }
+        }
+        Operation::MultiQubitMS(op) => {
+            let n = op.qubits().len();
+            custom(
+                format!("{name}_{n}"),
+                &["theta"],
+                vec![parameter(op.theta())?],
+                op.qubits().clone(),
+                |body| {
+                    for qubit in 0..n {
+                        body.gate("h", &[qubit]);
+                    }
+                    multi_qubit_zz(body, n);
+                    for qubit in 0..n {
+                        body.gate("h", &[qubit]);
+                    }
+                },
+            )
+        }
+        Operation::MultiQubitZZ(op) => {
+            let n = op.qubits().len();
+            custom(
+                format!("{name}_{n}"),
+                &["theta"],
+                vec![parameter(op.theta())?],
+                op.qubits().clone(),
+                |body| multi_qubit_zz(body, n),
+            )
+        }
+        Operation::QFT(op) => {
+            let n = op.qubits().len();
+            let inverse = if *op.inverse() { "_inverse" } else { "" };
+            let swaps = if *op.swaps() { "" } else { "_noswaps" };
+            custom(
+                format!("{name}{inverse}{swaps}_{n}"),
+                &[],
+                vec![],
+                op.qubits().clone(),
+                |body| quantum_fourier_transform(body, n, *op.swaps(), *op.inverse()),
+            )
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(gate))
+}
+
+/// Adds exp(-i * theta/2 * Z_0 Z_1 ... Z_n-1) with a ladder of CNOTs.
+fn multi_qubit_zz(body: &mut Body, number_qubits: usize) {
+    for qubit in 1..number_qubits {
+        body.gate("cx", &[qubit - 1, qubit]);
+    }
+    body.rotation("rz", "theta", &[number_qubits.saturating_sub(1)]);
+    for qubit in (1..number_qubits).rev() {
+        body.gate("cx", &[qubit - 1, qubit]);
+    }
+}
+
+/// Adds the (inverse) quantum Fourier transform of [crate::operations::QFT].
+fn quantum_fourier_transform(body: &mut Body, number_qubits: usize, swaps: bool, inverse: bool) {
+    let mut forward = Body::default();
+    for i in 0..number_qubits {
+        forward.gate("h", &[i]);
+        for j in i + 1..number_qubits {
+            forward.rotation("cp", format!("pi/{}", 1_u64 << (j - i)), &[j, i]);
+        }
+    }
+    if swaps {
+        for i in 0..number_qubits / 2 {
+            forward.gate("swap", &[i, number_qubits - i - 1]);
+        }
+    }
+    if inverse {
+        // All gates are self-inverse up to the sign of the phase
+        for call in forward.0.into_iter().rev() {
+            body.0.push(GateCall {
+                parameters: call.parameters.iter().map(|p| negate(p)).collect(),
+                ..call
+            });
+        }
+    } else {
+        body.0.extend(forward.0);
+    }
+}
+
+/// Names of the gates of the include files (and built-in gates) that are mapped directly to roqoqo operations.
+pub(crate) const INCLUDE_GATES: &[&str] = &[
+    "U", "u", "u3", "u2", "u1", "u0", "p", "phase", "id", "x", "y", "z", "h", "s", "sdg", "t",
+    "tdg", "sx", "sxdg", "rx", "ry", "rz", "CX", "cx", "cy", "cz", "swap", "cp", "cphase", "cu1",
+    "crx", "rxx", "rzz", "ccx", "cswap", "c3x", "c4x", "gphase",
+];
+
+/// Returns the roqoqo operation of a gate of the include files.
+///
+/// # Returns
+///
+/// * `Some(Operation)` - The gate is known and is applied to the right number of parameters and qubits.
+/// * `None` - Otherwise.
+pub(crate) fn include_gate(
+    name: &str,
+    parameters: &[CalculatorFloat],
+    qubits: &[usize],
+) -> Option<Operation> {
+    let operation: Operation = match (name, parameters, qubits) {
+        ("U" | "u" | "u3", [theta, phi, lambda], [q]) => u3(*q, theta, phi, lambda),
+        ("u2", [phi, lambda], [q]) => u3(*q, &CalculatorFloat::FRAC_PI_2, phi, lambda),
+        ("u1" | "p" | "phase", [theta], [q]) => PhaseShiftState1::new(*q, theta.clone()).into(),
+        ("u0", [_], [q]) | ("id", [], [q]) => Identity::new(*q).into(),
+        ("x", [], [q]) => PauliX::new(*q).into(),
+        ("y", [], [q]) => PauliY::new(*q).into(),
+        ("z", [], [q]) => PauliZ::new(*q).into(),
+        ("h", [], [q]) => Hadamard::new(*q).into(),
+        ("s", [], [q]) => SGate::new(*q).into(),
+        ("sdg", [], [q]) => InvSGate::new(*q).into(),
+        ("t", [], [q]) => TGate::new(*q).into(),
+        ("tdg", [], [q]) => InvTGate::new(*q).into(),
+        ("sx", [], [q]) => SXGate::new(*q).into(),
+        ("sxdg", [], [q]) => InvSXGate::new(*q).into(),
+        ("rx", [theta], [q]) => RotateX::new(*q, theta.clone()).into(),
+        ("ry", [theta], [q]) => RotateY::new(*q, theta.clone()).into(),
+        ("rz", [theta], [q]) => RotateZ::new(*q, theta.clone()).into(),
+        ("CX" | "cx", [], [c, t]) => CNOT::new(*c, *t).into(),
+        ("cy", [], [c, t]) => ControlledPauliY::new(*c, *t).into(),
+        ("cz", [], [c, t]) => ControlledPauliZ::new(*c, *t).into(),
+        ("swap", [], [c, t]) => SWAP::new(*c, *t).into(),
+        ("cp" | "cphase" | "cu1", [theta], [c, t]) => {
+            ControlledPhaseShift::new(*c, *t, theta.clone()).into()
+        }
+        ("crx", [theta], [c, t]) => ControlledRotateX::new(*c, *t, theta.clone()).into(),
+        ("rxx", [theta], [c, t]) => VariableMSXX::new(*c, *t, theta.clone()).into(),
+        ("rzz", [theta], [c, t]) => MultiQubitZZ::new(vec![*c, *t], theta.clone()).into(),
+        ("ccx", [], [c0, c1, t]) => Toffoli::new(*c0, *c1, *t).into(),
+        ("cswap", [], [c, t0, t1]) => ControlledSWAP::new(*c, *t0, *t1).into(),
+        ("c3x", [], [c0, c1, c2, t]) => TripleControlledPauliX::new(*c0, *c1, *c2, *t).into(),
+        ("c4x", [], [_, _, _, _, _]) => MultiQubitCNOT::new(qubits.to_vec()).into(),
+        ("gphase", [phase], []) => PragmaGlobalPhase::new(phase.clone()).into(),
+        _ => return None,
+    };
+    Some(operation)
+}
+
+/// Returns the SingleQubitGate equal to u3(theta, phi, lambda) including the global phase.
+fn u3(
+    qubit: usize,
+    theta: &CalculatorFloat,
+    phi: &CalculatorFloat,
+    lambda: &CalculatorFloat,
+) -> Operation {
+    let half_theta = theta.clone() / 2.0;
+    let half_sum = (phi.clone() + lambda) / 2.0;
+    let half_difference = (phi.clone() - lambda) / 2.0;
+    SingleQubitGate::new(
+        qubit,
+        half_theta.cos() * half_sum.cos(),
+        -(half_theta.cos() * half_sum.sin()),
+        half_theta.sin() * half_difference.cos(),
+        half_theta.sin() * half_difference.sin(),
+        half_sum,
+    )
+    .into()
+}
+
+/// Returns the roqoqo gate of a custom definition created by [gate_to_qasm].
+///
+/// # Returns
+///
+/// * `Some(Operation)` - The name belongs to a roqoqo gate applied to the right number of parameters and qubits.
+/// * `None` - Otherwise.
+pub(crate) fn roqoqo_gate(
+    name: &str,
+    parameters: &[CalculatorFloat],
+    qubits: &[usize],
+) -> Option<Operation> {
+    let p = |index: usize| parameters[index].clone();
+    let operation: Operation = match (name, parameters.len(), qubits) {
+        ("phaseshiftstate0", 1, [q]) => PhaseShiftState0::new(*q, p(0)).into(),
+        ("sqrtpaulix", 0, [q]) => SqrtPauliX::new(*q).into(),
+        ("invsqrtpaulix", 0, [q]) => InvSqrtPauliX::new(*q).into(),
+        ("sqrtpauliy", 0, [q]) => SqrtPauliY::new(*q).into(),
+        ("invsqrtpauliy", 0, [q]) => InvSqrtPauliY::new(*q).into(),
+        ("invsxgate", 0, [q]) => InvSXGate::new(*q).into(),
+        ("rotatearoundsphericalaxis", 3, [q]) => {
+            RotateAroundSphericalAxis::new(*q, p(0), p(1), p(2)).into()
+        }
+        ("rotatexy", 2, [q]) => RotateXY::new(*q, p(0), p(1)).into(),
+        ("gpi", 1, [q]) => GPi::new(*q, p(0)).into(),
+        ("gpi2", 1, [q]) => GPi2::new(*q, p(0)).into(),
+        ("iswap", 0, [c, t]) => ISwap::new(*c, *t).into(),
+        ("sqrtiswap", 0, [c, t]) => SqrtISwap::new(*c, *t).into(),
+        ("invsqrtiswap", 0, [c, t]) => InvSqrtISwap::new(*c, *t).into(),
+        ("xy", 1, [c, t]) => XY::new(*c, *t, p(0)).into(),
+        ("fswap", 0, [c, t]) => FSwap::new(*c, *t).into(),
+        ("molmersorensenxx", 0, [c, t]) => MolmerSorensenXX::new(*c, *t).into(),
+        ("variablemsxx", 1, [c, t]) => VariableMSXX::new(*c, *t, p(0)).into(),
+        ("givensrotation", 2, [c, t]) => GivensRotation::new(*c, *t, p(0), p(1)).into(),
+        ("givensrotationlittleendian", 2, [c, t]) => {
+            GivensRotationLittleEndian::new(*c, *t, p(0), p(1)).into()
+        }
+        ("qsim", 3, [c, t]) => Qsim::new(*c, *t, p(0), p(1), p(2)).into(),
+        ("spininteraction", 3, [c, t]) => SpinInteraction::new(*c, *t, p(0), p(1), p(2)).into(),
+        ("fsim", 3, [c, t]) => Fsim::new(*c, *t, p(0), p(1), p(2)).into(),
+        ("pminteraction", 1, [c, t]) => PMInteraction::new(*c, *t, p(0)).into(),
+        ("bogoliubov", 2, [c, t]) => {
+            Bogoliubov::new(*c, *t, p(0) * p(1).cos(), p(0) * p(1).sin()).into()
+        }
+        ("complexpminteraction", 2, [c, t]) => {
+            ComplexPMInteraction::new(*c, *t, p(0) * p(1).cos(), p(0) * p(1).sin()).into()
+        }
+        ("phaseshiftedcontrolledz", 1, [c, t]) => PhaseShiftedControlledZ::new(*c, *t, p(0)).into(),
+        ("phaseshiftedcontrolledphase", 2, [c, t]) => {
+            PhaseShiftedControlledPhase::new(*c, *t, p(0), p(1)).into()
+        }
+        ("controlledrotatexy", 2, [c, t]) => ControlledRotateXY::new(*c, *t, p(0), p(1)).into(),
+        ("echocrossresonance", 0, [c, t]) => EchoCrossResonance::new(*c, *t).into(),
+        ("controlledcontrolledpauliz", 0, [c0, c1, t]) => {
+            ControlledControlledPauliZ::new(*c0, *c1, *t).into()
+        }
+        ("controlledcontrolledphaseshift", 1, [c0, c1, t]) => {
+            ControlledControlledPhaseShift::new(*c0, *c1, *t, p(0)).into()
+        }
+        ("phaseshiftedcontrolledcontrolledz", 1, [c0, c1, t]) => {
+            PhaseShiftedControlledControlledZ::new(*c0, *c1, *t, p(0)).into()
+        }
+        ("phaseshiftedcontrolledcontrolledphase", 2, [c0, c1, t]) => {
+            PhaseShiftedControlledControlledPhase::new(*c0, *c1, *t, p(0), p(1)).into()
+        }
+        ("triplecontrolledpaulix", 0, [c0, c1, c2, t]) => {
+            TripleControlledPauliX::new(*c0, *c1, *c2, *t).into()
+        }
+        ("triplecontrolledpauliz", 0, [c0, c1, c2, t]) => {
+            TripleControlledPauliZ::new(*c0, *c1, *c2, *t).into()
+        }
+        ("triplecontrolledphaseshift", 1, [c0, c1, c2, t]) => {
+            TripleControlledPhaseShift::new(*c0, *c1, *c2, *t, p(0)).into()
+        }
+        _ => {
+            // Multi-qubit gates carry the number of qubits as suffix
+            let (base, number_qubits) = name.rsplit_once('_')?;
+            if number_qubits.parse::<usize>().ok()? != qubits.len() {
+                return None;
+            }
+            let qubits = qubits.to_vec();
+            match (base, parameters.len()) {
+                ("multiqubitcnot", 0) => MultiQubitCNOT::new(qubits).into(),
+                ("multiqubitms", 1) => MultiQubitMS::new(qubits, p(0)).into(),
+                ("multiqubitzz", 1) => MultiQubitZZ::new(qubits, p(0)).into(),
+                ("qft", 0) => QFT::new(qubits, true, false).into(),
+                ("qft_inverse", 0) => QFT::new(qubits, true, true).into(),
+                ("qft_noswaps", 0) => QFT::new(qubits, false, false).into(),
+                ("qft_inverse_noswaps", 0) => QFT::new(qubits, false, true).into(),
+                _ => return None,
+            }
+        }
+    };
+    Some(operation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Circuit;
+    use qoqo_calculator::Calculator;
+    use std::f64::consts::PI;
+    use test_case::test_case;
+
+    /// Returns the roqoqo operation of a gate from the include file.
+    fn include_gate(name: &str, parameters: &[f64], qubits: &[usize]) -> Operation {
+        match (name, parameters, qubits) {
+            ("x", [], [q]) => PauliX::new(*q).into(),
+            ("y", [], [q]) => PauliY::new(*q).into(),
+            ("z", [], [q]) => PauliZ::new(*q).into(),
+            ("h", [], [q]) => Hadamard::new(*q).into(),
+            ("s", [], [q]) => SGate::new(*q).into(),
+            ("sdg", [], [q]) => InvSGate::new(*q).into(),
+            ("t", [], [q]) => TGate::new(*q).into(),
+            ("tdg", [], [q]) => InvTGate::new(*q).into(),
+            ("id", [], [q]) => Identity::new(*q).into(),
+            ("sx", [], [q]) => SXGate::new(*q).into(),
+            ("sxdg", [], [q]) => InvSXGate::new(*q).into(),
+            ("rx", [theta], [q]) => RotateX::new(*q, (*theta).into()).into(),
+            ("ry", [theta], [q]) => RotateY::new(*q, (*theta).into()).into(),
+            ("rz", [theta], [q]) => RotateZ::new(*q, (*theta).into()).into(),
+            ("p", [theta], [q]) => PhaseShiftState1::new(*q, (*theta).into()).into(),
+            ("u3", [theta, phi, lambda], [q]) => {
+                let alpha = Complex64::from_polar((theta / 2.0).cos(), -(phi + lambda) / 2.0);
+                let beta = Complex64::from_polar((theta / 2.0).sin(), (phi - lambda) / 2.0);
+                SingleQubitGate::new(
+                    *q,
+                    alpha.re.into(),
+                    alpha.im.into(),
+                    beta.re.into(),
+                    beta.im.into(),
+                    ((phi + lambda) / 2.0).into(),
+                )
+                .into()
+            }
+            ("cx", [], [c, t]) => CNOT::new(*c, *t).into(),
+            ("cy", [], [c, t]) => ControlledPauliY::new(*c, *t).into(),
+            ("cz", [], [c, t]) => ControlledPauliZ::new(*c, *t).into(),
+            ("swap", [], [c, t]) => SWAP::new(*c, *t).into(),
+            ("cp", [theta], [c, t]) => ControlledPhaseShift::new(*c, *t, (*theta).into()).into(),
+            ("crx", [theta], [c, t]) => ControlledRotateX::new(*c, *t, (*theta).into()).into(),
+            ("ccx", [], [c0, c1, t]) => Toffoli::new(*c0, *c1, *t).into(),
+            ("cswap", [], [c, t0, t1]) => ControlledSWAP::new(*c, *t0, *t1).into(),
+            _ => panic!("Gate {name} is not part of the include files"),
+        }
+    }
+
+    /// Returns the circuit of a gate call, expanding the custom definition.
+    fn call_circuit(gate: &QasmGate) -> Circuit {
+        let mut calculator = Calculator::new();
+        calculator.set_variable("pi", PI);
+        let evaluate = |calculator: &Calculator, parameters: &[String]| -> Vec<f64> {
+            parameters
+                .iter()
+                .map(|p| calculator.parse_get(CalculatorFloat::from(p)).unwrap())
+                .collect()
+        };
+        let mut circuit = Circuit::new();
+        match &gate.definition {
+            None => {
+                let parameters = evaluate(&calculator, &gate.call.parameters);
+                circuit += include_gate(&gate.call.name, &parameters, &gate.call.qubits);
+            }
+            Some(definition) => {
+                assert_eq!(definition.name, gate.call.name);
+                assert_eq!(definition.number_qubits, gate.call.qubits.len());
+                let values = evaluate(&calculator, &gate.call.parameters);
+                for (name, value) in definition.parameters.iter().zip(values) {
+                    calculator.set_variable(name, value);
+                }
+                for call in definition.body.iter() {
+                    let qubits: Vec<usize> =
+                        call.qubits.iter().map(|q| gate.call.qubits[*q]).collect();
+                    let parameters = evaluate(&calculator, &call.parameters);
+                    circuit += include_gate(&call.name, &parameters, &qubits);
+                }
+            }
+        }
+        circuit
+    }
+
+    #[test_case(PauliX::new(1).into(); "PauliX")]
+    #[test_case(PauliY::new(1).into(); "PauliY")]
+    #[test_case(PauliZ::new(1).into(); "PauliZ")]
+    #[test_case(Hadamard::new(1).into(); "Hadamard")]
+    #[test_case(SGate::new(1).into(); "SGate")]
+    #[test_case(InvSGate::new(1).into(); "InvSGate")]
+    #[test_case(TGate::new(1).into(); "TGate")]
+    #[test_case(InvTGate::new(1).into(); "InvTGate")]
+    #[test_case(Identity::new(1).into(); "Identity")]
+    #[test_case(SXGate::new(1).into(); "SXGate")]
+    #[test_case(InvSXGate::new(1).into(); "InvSXGate")]
+    #[test_case(SqrtPauliX::new(1).into(); "SqrtPauliX")]
+    #[test_case(InvSqrtPauliX::new(1).into(); "InvSqrtPauliX")]
+    #[test_case(SqrtPauliY::new(1).into(); "SqrtPauliY")]
+    #[test_case(InvSqrtPauliY::new(1).into(); "InvSqrtPauliY")]
+    #[test_case(RotateX::new(1, 0.37.into()).into(); "RotateX")]
+    #[test_case(RotateY::new(1, 0.37.into()).into(); "RotateY")]
+    #[test_case(RotateZ::new(1, 0.37.into()).into(); "RotateZ")]
+    #[test_case(PhaseShiftState1::new(1, 0.37.into()).into(); "PhaseShiftState1")]
+    #[test_case(PhaseShiftState0::new(1, 0.37.into()).into(); "PhaseShiftState0")]
+    #[test_case(RotateAroundSphericalAxis::new(1, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "RotateAroundSphericalAxis")]
+    #[test_case(RotateXY::new(1, 0.37.into(), 1.1.into()).into(); "RotateXY")]
+    #[test_case(GPi::new(1, 0.37.into()).into(); "GPi")]
+    #[test_case(GPi2::new(1, 0.37.into()).into(); "GPi2")]
+    #[test_case(SingleQubitGate::new(1, 0.5.into(), 0.5.into(), (-0.5).into(), 0.5.into(), 0.2.into()).into(); "SingleQubitGate")]
+    #[test_case(CNOT::new(2, 0).into(); "CNOT")]
+    #[test_case(ControlledPauliY::new(2, 0).into(); "ControlledPauliY")]
+    #[test_case(ControlledPauliZ::new(2, 0).into(); "ControlledPauliZ")]
+    #[test_case(SWAP::new(2, 0).into(); "SWAP")]
+    #[test_case(ControlledPhaseShift::new(2, 0, 0.37.into()).into(); "ControlledPhaseShift")]
+    #[test_case(ControlledRotateX::new(2, 0, 0.37.into()).into(); "ControlledRotateX")]
+    #[test_case(ISwap::new(2, 0).into(); "ISwap")]
+    #[test_case(SqrtISwap::new(2, 0).into(); "SqrtISwap")]
+    #[test_case(InvSqrtISwap::new(2, 0).into(); "InvSqrtISwap")]
+    #[test_case(XY::new(2, 0, 0.37.into()).into(); "XY")]
+    #[test_case(FSwap::new(2, 0).into(); "FSwap")]
+    #[test_case(MolmerSorensenXX::new(2, 0).into(); "MolmerSorensenXX")]
+    #[test_case(VariableMSXX::new(2, 0, 0.37.into()).into(); "VariableMSXX")]
+    #[test_case(GivensRotation::new(2, 0, 0.37.into(), 1.1.into()).into(); "GivensRotation")]
+    #[test_case(GivensRotationLittleEndian::new(2, 0, 0.37.into(), 1.1.into()).into(); "GivensRotationLittleEndian")]
+    #[test_case(Qsim::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "Qsim")]
+    #[test_case(SpinInteraction::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "SpinInteraction")]
+    #[test_case(Fsim::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "Fsim")]
+    #[test_case(PMInteraction::new(2, 0, 0.37.into()).into(); "PMInteraction")]
+    #[test_case(Bogoliubov::new(2, 0, 0.37.into(), (-0.6).into()).into(); "Bogoliubov")]
+    #[test_case(ComplexPMInteraction::new(2, 0, 0.37.into(), (-0.6).into()).into(); "ComplexPMInteraction")]
+    #[test_case(PhaseShiftedControlledZ::new(2, 0, 0.37.into()).into(); "PhaseShiftedControlledZ")]
+    #[test_case(PhaseShiftedControlledPhase::new(2, 0, 0.37.into(), 1.1.into()).into(); "PhaseShiftedControlledPhase")]
+    #[test_case(ControlledRotateXY::new(2, 0, 0.37.into(), 1.1.into()).into(); "ControlledRotateXY")]
+    #[test_case(EchoCrossResonance::new(2, 0).into(); "EchoCrossResonance")]
+    #[test_case(Toffoli::new(2, 0, 1).into(); "Toffoli")]
+    #[test_case(ControlledSWAP::new(2, 0, 1).into(); "ControlledSWAP")]
+    #[test_case(ControlledControlledPauliZ::new(2, 0, 1).into(); "ControlledControlledPauliZ")]
+    #[test_case(ControlledControlledPhaseShift::new(2, 0, 1, 0.37.into()).into(); "ControlledControlledPhaseShift")]
+    #[test_case(PhaseShiftedControlledControlledZ::new(2, 0, 1, 0.37.into()).into(); "PhaseShiftedControlledControlledZ")]
+    #[test_case(PhaseShiftedControlledControlledPhase::new(2, 0, 1, 0.37.into(), 1.1.into()).into(); "PhaseShiftedControlledControlledPhase")]
+    #[test_case(TripleControlledPauliX::new(2, 0, 3, 1).into(); "TripleControlledPauliX")]
+    #[test_case(TripleControlledPauliZ::new(2, 0, 3, 1).into(); "TripleControlledPauliZ")]
+    #[test_case(TripleControlledPhaseShift::new(2, 0, 3, 1, 0.37.into()).into(); "TripleControlledPhaseShift")]
+    #[test_case(MultiQubitCNOT::new(vec![2, 0]).into(); "MultiQubitCNOT_2")]
+    #[test_case(MultiQubitCNOT::new(vec![2, 0, 1]).into(); "MultiQubitCNOT_3")]
+    #[test_case(MultiQubitCNOT::new(vec![2, 0, 3, 1]).into(); "MultiQubitCNOT_4")]
+    #[test_case(MultiQubitCNOT::new(vec![2, 4, 0, 3, 1]).into(); "MultiQubitCNOT_5")]
+    #[test_case(MultiQubitMS::new(vec![2, 0, 1], 0.37.into()).into(); "MultiQubitMS")]
+    #[test_case(MultiQubitZZ::new(vec![2, 0, 1], 0.37.into()).into(); "MultiQubitZZ")]
+    #[test_case(QFT::new(vec![2, 0, 1], true, false).into(); "QFT")]
+    #[test_case(QFT::new(vec![2, 0, 1], true, true).into(); "QFT_inverse")]
+    fn test_gate_equivalence(operation: Operation) {
+        for version in [QasmVersion::V2_0, QasmVersion::V3_0] {
+            let gate = gate_to_qasm(&operation, version, false).unwrap().unwrap();
+            let mut expected = Circuit::new();
+            expected += operation.clone();
+            assert!(
+                call_circuit(&gate)
+                    .is_equivalent(&expected, 1e-9, true)
+                    .unwrap(),
+                "{gate:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_qft_without_swaps() {
+        let qubits = vec![2, 0, 1];
+        let forward = QFT::new(qubits.clone(), false, false);
+        let gate = gate_to_qasm(&forward.clone().into(), QasmVersion::V3_0, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(gate.call.name, "qft_noswaps_3");
+        let forward_circuit = call_circuit(&gate);
+        assert!(forward_circuit
+            .is_equivalent(&forward.circuit(), 1e-9, true)
+            .unwrap());
+
+        let inverse = gate_to_qasm(
+            &QFT::new(qubits, false, true).into(),
+            QasmVersion::V3_0,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(inverse.call.name, "qft_inverse_noswaps_3");
+        let round_trip = forward_circuit + call_circuit(&inverse);
+        assert!(round_trip
+            .is_equivalent(&Circuit::new(), 1e-9, true)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_symbolic_parameters() {
+        let operation: Operation = RotateXY::new(0, "theta".into(), 0.5.into()).into();
+        let gate = gate_to_qasm(&operation, QasmVersion::V3_0, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            gate.call.parameters,
+            vec!["theta".to_string(), "0.5".to_string()]
+        );
+        assert!(gate_to_qasm(&operation, QasmVersion::V3_0, false).is_err());
+
+        let operation: Operation = Bogoliubov::new(0, 1, "delta".into(), 0.5.into()).into();
+        assert!(gate_to_qasm(&operation, QasmVersion::V3_0, true).is_err());
+    }
+
+    #[test_case("theta", "-theta"; "symbol")]
+    #[test_case("-pi/2", "pi/2"; "negative product")]
+    #[test_case("phi+pi/2", "-(phi+pi/2)"; "sum")]
+    #[test_case("-phi+pi/2", "-(-phi+pi/2)"; "negative sum")]
+    fn test_negate(expression: &str, negated: &str) {
+        assert_eq!(negate(expression), negated);
+    }
+
+    #[test]
+    fn test_non_gate_operation() {
+        let operation: Operation = MeasureQubit::new(0, "ro".into(), 0).into();
+        assert_eq!(
+            gate_to_qasm(&operation, QasmVersion::V2_0, false).unwrap(),
+            None
+        );
+    }
+}
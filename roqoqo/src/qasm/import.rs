@@ -0,0 +1,1466 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::{E, TAU};
+
+use super::export::builtin_definition;
+use super::gates::{gate_to_qasm, include_gate, roqoqo_gate, INCLUDE_GATES};
+use super::QasmVersion;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use qoqo_calculator::CalculatorFloat;
+
+/// The maximal number of qubits of a program and the maximal length of a bit register.
+const MAXIMUM_REGISTER_LENGTH: usize = 1 << 20;
+
+/// The maximal number of iterations of a loop that is unrolled.
+const MAXIMUM_LOOP_ITERATIONS: usize = 1 << 20;
+
+/// Gates of the include files that are not mapped to a single roqoqo gate.
+///
+/// The definitions are parsed before the program and are inlined when the gates are called.
+const PRELUDE: &str = "
+gate ch a, b { s b; h b; t b; cx a, b; tdg b; h b; sdg b; }
+gate cry(theta) a, b { ry(theta/2) b; cx a, b; ry(-theta/2) b; cx a, b; }
+gate crz(theta) a, b { rz(theta/2) b; cx a, b; rz(-theta/2) b; cx a, b; }
+gate cu3(theta, phi, lambda) a, b {
+    u1((lambda+phi)/2) a; u1((lambda-phi)/2) b; cx a, b;
+    u3(-theta/2, 0, -(phi+lambda)/2) b; cx a, b; u3(theta/2, phi, 0) b;
+}
+gate cu(theta, phi, lambda, gamma) a, b { p(gamma) a; cu3(theta, phi, lambda) a, b; }
+gate csx a, b { h b; cu1(pi/2) a, b; h b; }
+";
+
+/// Converts an OpenQASM 2.0 or 3.0 program to a roqoqo [crate::Circuit].
+///
+/// Gates of the include files are mapped to the equivalent roqoqo gates, custom `gate` definitions
+/// are inlined. Definitions created by [crate::qasm::circuit_to_qasm] for roqoqo gates without an
+/// equivalent in the include file are mapped back to the roqoqo gates.
+/// Parameters that are not defined in the program are kept as symbolic parameters.
+/// Conditions comparing several bits with an integer are evaluated into additional one-bit registers
+/// that are not output registers.
+///
+/// # Arguments
+///
+/// * `input` - The OpenQASM program.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit of the program.
+/// * `Err(RoqoqoError::QasmParsingError)` - The program can not be parsed, the error contains the position of the problem.
+pub fn circuit_from_qasm(input: &str) -> Result<Circuit, RoqoqoError> {
+    let prelude = tokenize(PRELUDE)?;
+    let prelude_end = prelude.len() - 1;
+    let mut tokens = prelude;
+    tokens.pop();
+    tokens.extend(tokenize(input)?);
+    let mut importer = Importer {
+        tokens,
+        position: 0,
+        version: QasmVersion::V3_0,
+        depth: 0,
+        number_qubits: 0,
+        qubit_registers: HashMap::new(),
+        bit_registers: HashMap::new(),
+        constants: HashMap::new(),
+        variables: HashMap::new(),
+        gates: HashMap::new(),
+        gate_qubits: None,
+        expanding: Vec::new(),
+        condition_registers: Vec::new(),
+    };
+    let mut circuit = Circuit::new();
+    while importer.position < prelude_end {
+        importer.statement(&mut circuit)?;
+    }
+    importer.header()?;
+    while importer.peek().kind != TokenKind::End {
+        importer.statement(&mut circuit)?;
+    }
+    for name in importer.condition_registers {
+        circuit.add_operation(DefinitionBit::new(name, 1, false));
+    }
+    Ok(circuit)
+}
+
+/// The kinds of tokens of an OpenQASM program.
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// An identifier or keyword.
+    Identifier(String),
+    /// An integer literal.
+    Integer(usize),
+    /// A floating point literal.
+    Real(f64),
+    /// A string literal.
+    Str(String),
+    /// A duration literal in seconds.
+    Duration(f64),
+    /// An operator or punctuation.
+    Symbol(&'static str),
+    /// The end of the program.
+    End,
+}
+
+/// A token with its position in the program.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    /// The kind of the token.
+    kind: TokenKind,
+    /// The line of the first character, starting at 1.
+    line: usize,
+    /// The column of the first character, starting at 1.
+    column: usize,
+}
+
+impl Token {
+    /// Returns an error at the position of the token.
+    fn error(&self, msg: impl Into<String>) -> RoqoqoError {
+        RoqoqoError::QasmParsingError {
+            line: self.line,
+            column: self.column,
+            msg: msg.into(),
+        }
+    }
+
+    /// Returns a description of the token for error messages.
+    fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Identifier(name) => format!("'{name}'"),
+            TokenKind::Integer(value) => format!("'{value}'"),
+            TokenKind::Real(value) => format!("'{value}'"),
+            TokenKind::Str(value) => format!("\"{value}\""),
+            TokenKind::Duration(value) => format!("'{value}s'"),
+            TokenKind::Symbol(symbol) => format!("'{symbol}'"),
+            TokenKind::End => "end of input".to_string(),
+        }
+    }
+}
+
+/// Statements that can not be used in the body of a gate definition.
+const NON_GATE_STATEMENTS: &[&str] = &[
+    "include", "qreg", "qubit", "creg", "bit", "output", "input", "const", "float", "angle", "int",
+    "uint", "array", "gate", "measure", "reset", "if", "for", "delay", "opaque",
+];
+
+/// Operators and punctuation, longer symbols first.
+const SYMBOLS: &[&str] = &[
+    "->", "==", "!=", "<=", ">=", "**", ";", ",", "(", ")", "[", "]", "{", "}", "=", "+", "-", "*",
+    "/", "^", ":", "@", "<", ">", "!",
+];
+
+/// Splits an OpenQASM program into tokens, the last token is always [TokenKind::End].
+fn tokenize(input: &str) -> Result<Vec<Token>, RoqoqoError> {
+    let characters: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+    while index < characters.len() {
+        let character = characters[index];
+        let column = index - line_start + 1;
+        let token = move |kind| Token { kind, line, column };
+        if character == '\n' {
+            index += 1;
+            line += 1;
+            line_start = index;
+        } else if character.is_whitespace() {
+            index += 1;
+        } else if character == '/' && characters.get(index + 1) == Some(&'/') {
+            while index < characters.len() && characters[index] != '\n' {
+                index += 1;
+            }
+        } else if character == '/' && characters.get(index + 1) == Some(&'*') {
+            index += 2;
+            loop {
+                match characters.get(index) {
+                    None => return Err(token(TokenKind::End).error("Unterminated comment")),
+                    Some('*') if characters.get(index + 1) == Some(&'/') => {
+                        index += 2;
+                        break;
+                    }
+                    Some('\n') => {
+                        index += 1;
+                        line += 1;
+                        line_start = index;
+                    }
+                    Some(_) => index += 1,
+                }
+            }
+        } else if character == '"' {
+            let start = index + 1;
+            index = start;
+            while index < characters.len() && characters[index] != '"' {
+                if characters[index] == '\n' {
+                    return Err(token(TokenKind::End).error("Unterminated string"));
+                }
+                index += 1;
+            }
+            if index == characters.len() {
+                return Err(token(TokenKind::End).error("Unterminated string"));
+            }
+            tokens.push(token(TokenKind::Str(
+                characters[start..index].iter().collect(),
+            )));
+            index += 1;
+        } else if character.is_ascii_digit()
+            || (character == '.'
+                && characters
+                    .get(index + 1)
+                    .is_some_and(|next| next.is_ascii_digit()))
+        {
+            let start = index;
+            let mut is_real = false;
+            while index < characters.len() && characters[index].is_ascii_digit() {
+                index += 1;
+            }
+            if characters.get(index) == Some(&'.') {
+                is_real = true;
+                index += 1;
+                while index < characters.len() && characters[index].is_ascii_digit() {
+                    index += 1;
+                }
+            }
+            if matches!(characters.get(index), Some('e') | Some('E')) {
+                let mut exponent = index + 1;
+                if matches!(characters.get(exponent), Some('+') | Some('-')) {
+                    exponent += 1;
+                }
+                if characters
+                    .get(exponent)
+                    .is_some_and(|next| next.is_ascii_digit())
+                {
+                    is_real = true;
+                    index = exponent;
+                    while index < characters.len() && characters[index].is_ascii_digit() {
+                        index += 1;
+                    }
+                }
+            }
+            let text: String = characters[start..index].iter().collect();
+            let value: f64 = text
+                .parse()
+                .map_err(|_| token(TokenKind::End).error(format!("Invalid number {text}")))?;
+            let unit_start = index;
+            while index < characters.len() && is_identifier_character(characters[index]) {
+                index += 1;
+            }
+            let unit: String = characters[unit_start..index].iter().collect();
+            let kind = match unit.as_str() {
+                "" if is_real => TokenKind::Real(value),
+                "" => TokenKind::Integer(text.parse().map_err(|_| {
+                    token(TokenKind::End).error(format!("Integer {text} is too large"))
+                })?),
+                "ns" => TokenKind::Duration(value * 1e-9),
+                "us" | "µs" => TokenKind::Duration(value * 1e-6),
+                "ms" => TokenKind::Duration(value * 1e-3),
+                "s" => TokenKind::Duration(value),
+                _ => {
+                    return Err(token(TokenKind::End)
+                        .error(format!("Unsupported unit {unit} of number {text}")))
+                }
+            };
+            tokens.push(token(kind));
+        } else if character.is_alphabetic() || character == '_' {
+            let start = index;
+            while index < characters.len() && is_identifier_character(characters[index]) {
+                index += 1;
+            }
+            tokens.push(token(TokenKind::Identifier(
+                characters[start..index].iter().collect(),
+            )));
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| {
+                symbol
+                    .chars()
+                    .enumerate()
+                    .all(|(offset, c)| characters.get(index + offset) == Some(&c))
+            });
+            match symbol {
+                Some(symbol) => {
+                    index += symbol.len();
+                    tokens.push(token(TokenKind::Symbol(symbol)));
+                }
+                None => {
+                    return Err(
+                        token(TokenKind::End).error(format!("Unexpected character '{character}'"))
+                    )
+                }
+            }
+        }
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        line,
+        column: characters.len() - line_start + 1,
+    });
+    Ok(tokens)
+}
+
+/// Returns true if the character can be part of an identifier.
+fn is_identifier_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+/// A gate defined with a `gate` statement.
+#[derive(Debug, Clone)]
+struct GateDefinition {
+    /// The names of the formal parameters.
+    parameters: Vec<String>,
+    /// The names of the qubit arguments.
+    qubits: Vec<String>,
+    /// The position of the opening brace of the body.
+    body: usize,
+    /// Whether the definition was created by the export of a roqoqo gate.
+    builtin: bool,
+}
+
+/// Parses the tokens of an OpenQASM program and builds the circuit.
+struct Importer {
+    /// The tokens of the prelude and the program.
+    tokens: Vec<Token>,
+    /// The position of the next token.
+    position: usize,
+    /// The version from the header of the program.
+    version: QasmVersion,
+    /// The nesting depth of loops, conditions and gate bodies.
+    depth: usize,
+    /// The number of declared qubits.
+    number_qubits: usize,
+    /// The offset and length of the quantum registers.
+    qubit_registers: HashMap<String, (usize, usize)>,
+    /// The length of the classical bit registers.
+    bit_registers: HashMap<String, usize>,
+    /// The values of constants.
+    constants: HashMap<String, CalculatorFloat>,
+    /// The values of loop variables and gate parameters.
+    variables: HashMap<String, CalculatorFloat>,
+    /// The defined gates.
+    gates: HashMap<String, GateDefinition>,
+    /// The qubit arguments when expanding a gate body.
+    gate_qubits: Option<HashMap<String, usize>>,
+    /// The gates that are currently expanded.
+    expanding: Vec<String>,
+    /// The scratch registers holding the result of conditions on several bits.
+    condition_registers: Vec<String>,
+}
+
+/// The values of the loop variable of a `for` loop.
+#[derive(Debug, Clone)]
+enum LoopValues {
+    /// The values of a set `{a, b}`.
+    Set(Vec<f64>),
+    /// The values of a range `[start:step:stop]`, the number of values can exceed the addressable range.
+    Range { start: f64, step: f64, count: f64 },
+}
+
+impl LoopValues {
+    /// Returns the number of values.
+    fn count(&self) -> f64 {
+        match self {
+            LoopValues::Set(values) => values.len() as f64,
+            LoopValues::Range { count, .. } => *count,
+        }
+    }
+
+    /// Returns the value with the given index.
+    fn value(&self, index: usize) -> f64 {
+        match self {
+            LoopValues::Set(values) => values[index],
+            LoopValues::Range { start, step, .. } => start + step * index as f64,
+        }
+    }
+}
+
+impl Importer {
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    /// Returns the token after the next one without consuming it.
+    fn peek_second(&self) -> &Token {
+        &self.tokens[(self.position + 1).min(self.tokens.len() - 1)]
+    }
+
+    /// Consumes and returns the next token.
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// Returns true if the next token is the symbol.
+    fn check_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek().kind, TokenKind::Symbol(s) if s == symbol)
+    }
+
+    /// Returns true if the next token is the keyword.
+    fn check_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Identifier(name) if name == keyword)
+    }
+
+    /// Consumes the next token if it is the symbol.
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.check_symbol(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    /// Consumes the next token, which has to be the symbol.
+    fn expect_symbol(&mut self, symbol: &str) -> Result<Token, RoqoqoError> {
+        if self.check_symbol(symbol) {
+            Ok(self.advance())
+        } else {
+            let token = self.peek();
+            Err(token.error(format!("Expected '{symbol}', found {}", token.describe())))
+        }
+    }
+
+    /// Consumes the next token, which has to be an identifier.
+    fn expect_identifier(&mut self) -> Result<(String, Token), RoqoqoError> {
+        let token = self.advance();
+        match &token.kind {
+            TokenKind::Identifier(name) => Ok((name.clone(), token)),
+            _ => Err(token.error(format!(
+                "Expected an identifier, found {}",
+                token.describe()
+            ))),
+        }
+    }
+
+    /// Parses the optional `OPENQASM` header, programs without header are treated as OpenQASM 3.0.
+    fn header(&mut self) -> Result<(), RoqoqoError> {
+        if !self.check_keyword("OPENQASM") {
+            return Ok(());
+        }
+        self.advance();
+        let token = self.advance();
+        let version = match token.kind {
+            TokenKind::Integer(version) => Some(version as f64),
+            TokenKind::Real(version) => Some(version),
+            _ => None,
+        };
+        self.version = if version == Some(2.0) {
+            QasmVersion::V2_0
+        } else if version == Some(3.0) {
+            QasmVersion::V3_0
+        } else {
+            return Err(token.error(format!(
+                "Unsupported OpenQASM version {}, supported versions are 2.0 and 3.0",
+                token.describe()
+            )));
+        };
+        self.expect_symbol(";")?;
+        Ok(())
+    }
+
+    /// Parses a statement and adds its operations to the circuit.
+    fn statement(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let token = self.peek().clone();
+        let keyword = match &token.kind {
+            TokenKind::Identifier(name) => name.clone(),
+            _ => return Err(token.error(format!("Unexpected {}", token.describe()))),
+        };
+        if self.gate_qubits.is_some() && NON_GATE_STATEMENTS.contains(&keyword.as_str()) {
+            return Err(token.error(format!(
+                "Statement {} is not allowed in a gate definition",
+                token.describe()
+            )));
+        }
+        match keyword.as_str() {
+            "OPENQASM" => Err(token.error("The OPENQASM header has to be the first statement")),
+            "include" => {
+                self.advance();
+                let file = self.advance();
+                match &file.kind {
+                    TokenKind::Str(name) if name == "qelib1.inc" || name == "stdgates.inc" => (),
+                    _ => {
+                        return Err(file.error(format!(
+                        "Unsupported include {}, only qelib1.inc and stdgates.inc are supported",
+                        file.describe()
+                    )))
+                    }
+                }
+                self.expect_symbol(";")?;
+                Ok(())
+            }
+            "qreg" | "qubit" => self.qubit_declaration(),
+            "creg" | "bit" => self.bit_declaration(circuit),
+            "output" => {
+                self.advance();
+                if self.check_keyword("bit") {
+                    self.bit_declaration(circuit)
+                } else {
+                    self.classical_declaration(circuit)
+                }
+            }
+            "input" => {
+                self.advance();
+                self.classical_type()?;
+                self.expect_identifier()?;
+                self.expect_symbol(";")?;
+                Ok(())
+            }
+            "const" => {
+                self.advance();
+                self.classical_type()?;
+                let (name, _) = self.expect_identifier()?;
+                self.expect_symbol("=")?;
+                let value = self.expression()?;
+                self.expect_symbol(";")?;
+                self.constants.insert(name, value);
+                Ok(())
+            }
+            "float" | "angle" | "int" | "uint" => self.classical_declaration(circuit),
+            "array" => self.array_declaration(circuit),
+            "gate" => self.gate_definition(),
+            "measure" => {
+                self.advance();
+                let qubits = self.qubit_argument()?;
+                self.expect_symbol("->")?;
+                let (register, indices) = self.bit_argument()?;
+                self.expect_symbol(";")?;
+                measure(circuit, &token, &qubits, &register, &indices)
+            }
+            "reset" => {
+                self.advance();
+                let qubits = self.qubit_argument()?;
+                self.expect_symbol(";")?;
+                for qubit in qubits {
+                    circuit.add_operation(PragmaActiveReset::new(qubit));
+                }
+                Ok(())
+            }
+            "barrier" => {
+                self.advance();
+                let qubits = if self.check_symbol(";") {
+                    (0..self.number_qubits).collect()
+                } else {
+                    self.qubit_arguments()?.concat()
+                };
+                self.expect_symbol(";")?;
+                circuit.add_operation(PragmaStopParallelBlock::new(qubits, 0.0.into()));
+                Ok(())
+            }
+            "if" => self.conditional(circuit),
+            "for" => self.for_loop(circuit),
+            "delay" => {
+                self.advance();
+                self.expect_symbol("[")?;
+                let duration = self.advance();
+                let time = match duration.kind {
+                    TokenKind::Duration(time) => time,
+                    _ => {
+                        return Err(duration.error(format!(
+                            "Expected a duration, found {}",
+                            duration.describe()
+                        )))
+                    }
+                };
+                self.expect_symbol("]")?;
+                let qubits = if self.check_symbol(";") {
+                    (0..self.number_qubits).collect()
+                } else {
+                    self.qubit_arguments()?.concat()
+                };
+                self.expect_symbol(";")?;
+                circuit.add_operation(PragmaSleep::new(qubits, time.into()));
+                Ok(())
+            }
+            "opaque" => Err(token.error("Opaque gates are not supported")),
+            "ctrl" | "negctrl" | "inv" | "pow" => {
+                Err(token.error(format!("Gate modifier {keyword} is not supported")))
+            }
+            "else" | "while" | "def" | "defcal" | "cal" | "box" | "let" | "extern" | "return"
+            | "break" | "continue" | "end" | "switch" | "duration" | "stretch" | "bool"
+            | "complex" => Err(token.error(format!("Statement {keyword} is not supported"))),
+            _ if self.bit_registers.contains_key(&keyword)
+                && matches!(
+                    self.peek_second().kind,
+                    TokenKind::Symbol("[") | TokenKind::Symbol("=")
+                ) =>
+            {
+                self.assignment(circuit)
+            }
+            _ => self.gate_call(circuit),
+        }
+    }
+
+    /// Returns an error if declarations are used inside of loops, conditions or gate bodies.
+    fn check_top_level(&self, token: &Token) -> Result<(), RoqoqoError> {
+        if self.depth > 0 {
+            return Err(
+                token.error("Declarations are only supported at the top level of the program")
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the name is already used by a register.
+    fn check_new_register(&self, name: &str, token: &Token) -> Result<(), RoqoqoError> {
+        if self.qubit_registers.contains_key(name) || self.bit_registers.contains_key(name) {
+            return Err(token.error(format!("Register {name} is already declared")));
+        }
+        Ok(())
+    }
+
+    /// Parses an optional designator `[n]` and returns its value.
+    fn designator(&mut self) -> Result<Option<usize>, RoqoqoError> {
+        if !self.eat_symbol("[") {
+            return Ok(None);
+        }
+        let length = self.integer_expression()?;
+        self.expect_symbol("]")?;
+        Ok(Some(length))
+    }
+
+    /// Parses `qreg name[n];`, `qubit name;` or `qubit[n] name;`.
+    fn qubit_declaration(&mut self) -> Result<(), RoqoqoError> {
+        let (keyword, token) = self.expect_identifier()?;
+        self.check_top_level(&token)?;
+        let (name, length) = if keyword == "qreg" {
+            let (name, _) = self.expect_identifier()?;
+            (name, self.designator()?.unwrap_or(1))
+        } else {
+            let length = self.designator()?.unwrap_or(1);
+            (self.expect_identifier()?.0, length)
+        };
+        self.expect_symbol(";")?;
+        self.check_new_register(&name, &token)?;
+        if self.number_qubits + length > MAXIMUM_REGISTER_LENGTH {
+            return Err(token.error(format!(
+                "Register {name} exceeds the maximal number of {MAXIMUM_REGISTER_LENGTH} qubits"
+            )));
+        }
+        self.qubit_registers
+            .insert(name, (self.number_qubits, length));
+        self.number_qubits += length;
+        Ok(())
+    }
+
+    /// Parses `creg name[n];`, `bit name;` or `bit[n] name;`.
+    fn bit_declaration(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let (keyword, token) = self.expect_identifier()?;
+        self.check_top_level(&token)?;
+        let (name, length) = if keyword == "creg" {
+            let (name, _) = self.expect_identifier()?;
+            (name, self.designator()?.unwrap_or(1))
+        } else {
+            let length = self.designator()?.unwrap_or(1);
+            (self.expect_identifier()?.0, length)
+        };
+        if self.check_symbol("=") {
+            return Err(self
+                .peek()
+                .error("Initialization of bit registers is not supported"));
+        }
+        self.expect_symbol(";")?;
+        self.check_new_register(&name, &token)?;
+        check_bit_register_length(&name, length, &token)?;
+        self.bit_registers.insert(name.clone(), length);
+        circuit.add_operation(DefinitionBit::new(name, length, true));
+        Ok(())
+    }
+
+    /// Parses a classical type like `float[64]` or `complex[float[64]]` and returns its name.
+    fn classical_type(&mut self) -> Result<String, RoqoqoError> {
+        let (name, token) = self.expect_identifier()?;
+        if !matches!(
+            name.as_str(),
+            "float" | "angle" | "int" | "uint" | "bit" | "bool" | "complex"
+        ) {
+            return Err(token.error(format!("Unsupported type {name}")));
+        }
+        if self.eat_symbol("[") {
+            if name == "complex" {
+                self.classical_type()?;
+            } else {
+                self.integer_expression()?;
+            }
+            self.expect_symbol("]")?;
+        }
+        Ok(name)
+    }
+
+    /// Parses the declaration of a classical variable.
+    ///
+    /// Variables initialized with a number are added as [InputSymbolic],
+    /// uninitialized variables are free symbolic parameters.
+    fn classical_declaration(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let token = self.peek().clone();
+        self.check_top_level(&token)?;
+        self.classical_type()?;
+        let (name, _) = self.expect_identifier()?;
+        if self.eat_symbol("=") {
+            let value_token = self.peek().clone();
+            match self.expression()? {
+                CalculatorFloat::Float(value) => {
+                    circuit.add_operation(InputSymbolic::new(name, value));
+                }
+                CalculatorFloat::Str(expression) => {
+                    return Err(value_token.error(format!(
+                        "Variable {name} has to be initialized with a number, found {expression}"
+                    )))
+                }
+            }
+        }
+        self.expect_symbol(";")?;
+        Ok(())
+    }
+
+    /// Parses `array[type, n] name;`.
+    fn array_declaration(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let (_, token) = self.expect_identifier()?;
+        self.check_top_level(&token)?;
+        self.expect_symbol("[")?;
+        let element_type = self.classical_type()?;
+        self.expect_symbol(",")?;
+        let length = self.integer_expression()?;
+        self.expect_symbol("]")?;
+        let (name, _) = self.expect_identifier()?;
+        self.expect_symbol(";")?;
+        self.check_new_register(&name, &token)?;
+        match element_type.as_str() {
+            "float" | "angle" => circuit.add_operation(DefinitionFloat::new(name, length, true)),
+            "complex" => circuit.add_operation(DefinitionComplex::new(name, length, true)),
+            "uint" | "int" => circuit.add_operation(DefinitionUsize::new(name, length, true)),
+            "bit" | "bool" => {
+                check_bit_register_length(&name, length, &token)?;
+                self.bit_registers.insert(name.clone(), length);
+                circuit.add_operation(DefinitionBit::new(name, length, true))
+            }
+            _ => return Err(token.error(format!("Unsupported array type {element_type}"))),
+        }
+        Ok(())
+    }
+
+    /// Parses a `gate` definition, the body is parsed when the gate is called.
+    fn gate_definition(&mut self) -> Result<(), RoqoqoError> {
+        let start = self.position;
+        let (_, token) = self.expect_identifier()?;
+        self.check_top_level(&token)?;
+        let (name, name_token) = self.expect_identifier()?;
+        let mut parameters = Vec::new();
+        if self.eat_symbol("(") {
+            if !self.check_symbol(")") {
+                loop {
+                    parameters.push(self.expect_identifier()?.0);
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        let mut qubits = Vec::new();
+        loop {
+            qubits.push(self.expect_identifier()?.0);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        let body = self.position;
+        self.skip_block()?;
+        if INCLUDE_GATES.contains(&name.as_str()) || self.gates.contains_key(&name) {
+            return Err(name_token.error(format!("Gate {name} is already defined")));
+        }
+        let builtin = self.is_builtin_definition(&name, parameters.len(), qubits.len(), start);
+        self.gates.insert(
+            name,
+            GateDefinition {
+                parameters,
+                qubits,
+                body,
+                builtin,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns true if the tokens from `start` to the current position are the definition
+    /// of a roqoqo gate written by the export.
+    fn is_builtin_definition(
+        &self,
+        name: &str,
+        number_parameters: usize,
+        number_qubits: usize,
+        start: usize,
+    ) -> bool {
+        let qubits: Vec<usize> = (0..number_qubits).collect();
+        let parameters = vec![CalculatorFloat::from(0.0); number_parameters];
+        let definition = roqoqo_gate(name, &parameters, &qubits)
+            .and_then(|operation| gate_to_qasm(&operation, self.version, false).ok().flatten())
+            .and_then(|gate| gate.definition);
+        let expected = match definition.map(|definition| tokenize(&builtin_definition(&definition)))
+        {
+            Some(Ok(tokens)) => tokens,
+            _ => return false,
+        };
+        let found = &self.tokens[start..self.position];
+        expected.len() == found.len() + 1
+            && expected
+                .iter()
+                .zip(found.iter())
+                .all(|(expected, found)| expected.kind == found.kind)
+    }
+
+    /// Skips a block in braces.
+    fn skip_block(&mut self) -> Result<(), RoqoqoError> {
+        let open = self.expect_symbol("{")?;
+        let mut level = 1;
+        while level > 0 {
+            let token = self.advance();
+            match token.kind {
+                TokenKind::Symbol("{") => level += 1,
+                TokenKind::Symbol("}") => level -= 1,
+                TokenKind::End => return Err(open.error("Unclosed '{'")),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a block in braces and adds its operations to the circuit.
+    fn block(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        self.expect_symbol("{")?;
+        while !self.check_symbol("}") {
+            if self.peek().kind == TokenKind::End {
+                return Err(self.peek().error("Expected '}', found end of input"));
+            }
+            self.statement(circuit)?;
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /// Parses a block or a single statement.
+    fn body(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        self.depth += 1;
+        let result = if self.check_symbol("{") {
+            self.block(circuit)
+        } else {
+            self.statement(circuit)
+        };
+        self.depth -= 1;
+        result
+    }
+
+    /// Parses a gate call and adds the gates to the circuit.
+    fn gate_call(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let (name, token) = self.expect_identifier()?;
+        if self.check_symbol("@") {
+            return Err(token.error(format!("Gate modifier {name} is not supported")));
+        }
+        let mut parameters = Vec::new();
+        if self.eat_symbol("(") {
+            if !self.check_symbol(")") {
+                loop {
+                    parameters.push(self.expression()?);
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        if name == "gphase" {
+            self.expect_symbol(";")?;
+            return match parameters.as_slice() {
+                [phase] => {
+                    circuit.add_operation(PragmaGlobalPhase::new(phase.clone()));
+                    Ok(())
+                }
+                _ => Err(token.error("gphase expects one parameter")),
+            };
+        }
+        let arguments = self.qubit_arguments()?;
+        self.expect_symbol(";")?;
+        let length = arguments
+            .iter()
+            .map(|argument| argument.len())
+            .filter(|length| *length != 1)
+            .try_fold(1, |length, other| {
+                if length == 1 || length == other {
+                    Ok(other)
+                } else {
+                    Err(token.error(format!(
+                        "Registers of different length are used in the call of gate {name}"
+                    )))
+                }
+            })?;
+        for index in 0..length {
+            let qubits: Vec<usize> = arguments
+                .iter()
+                .map(|argument| {
+                    if argument.len() == 1 {
+                        argument[0]
+                    } else {
+                        argument[index]
+                    }
+                })
+                .collect();
+            if qubits.iter().collect::<HashSet<&usize>>().len() != qubits.len() {
+                return Err(token.error(format!("Gate {name} is applied to the same qubit twice")));
+            }
+            self.apply_gate(circuit, &name, &parameters, &qubits, &token)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a gate applied to single qubits to the circuit.
+    fn apply_gate(
+        &mut self,
+        circuit: &mut Circuit,
+        name: &str,
+        parameters: &[CalculatorFloat],
+        qubits: &[usize],
+        token: &Token,
+    ) -> Result<(), RoqoqoError> {
+        let definition = match self.gates.get(name) {
+            Some(definition) => definition.clone(),
+            None => {
+                if let Some(operation) = include_gate(name, parameters, qubits) {
+                    circuit.add_operation(operation);
+                    return Ok(());
+                }
+                return Err(if INCLUDE_GATES.contains(&name) {
+                    token.error(format!(
+                        "Gate {name} is called with the wrong number of parameters or qubits"
+                    ))
+                } else {
+                    token.error(format!("Unknown gate {name}"))
+                });
+            }
+        };
+        if definition.parameters.len() != parameters.len()
+            || definition.qubits.len() != qubits.len()
+        {
+            return Err(token.error(format!(
+                "Gate {name} expects {} parameters and {} qubits, found {} parameters and {} qubits",
+                definition.parameters.len(),
+                definition.qubits.len(),
+                parameters.len(),
+                qubits.len()
+            )));
+        }
+        if definition.builtin {
+            if let Some(operation) = roqoqo_gate(name, parameters, qubits) {
+                circuit.add_operation(operation);
+                return Ok(());
+            }
+        }
+        if self.expanding.iter().any(|expanded| expanded == name) {
+            return Err(token.error(format!("Gate {name} is defined recursively")));
+        }
+        let variables = definition
+            .parameters
+            .iter()
+            .cloned()
+            .zip(parameters.iter().cloned())
+            .collect();
+        let gate_qubits = definition
+            .qubits
+            .iter()
+            .cloned()
+            .zip(qubits.iter().copied())
+            .collect();
+        let position = self.position;
+        let variables = std::mem::replace(&mut self.variables, variables);
+        let gate_qubits = self.gate_qubits.replace(gate_qubits);
+        self.position = definition.body;
+        self.expanding.push(name.to_string());
+        let result = self.body(circuit);
+        self.expanding.pop();
+        self.position = position;
+        self.variables = variables;
+        self.gate_qubits = gate_qubits;
+        result.map_err(|error| match error {
+            RoqoqoError::QasmParsingError { msg, .. } if self.expanding.is_empty() => {
+                token.error(format!("In definition of gate {name}: {msg}"))
+            }
+            error => error,
+        })
+    }
+
+    /// Parses a comma separated list of qubit arguments.
+    fn qubit_arguments(&mut self) -> Result<Vec<Vec<usize>>, RoqoqoError> {
+        let mut arguments = vec![self.qubit_argument()?];
+        while self.eat_symbol(",") {
+            arguments.push(self.qubit_argument()?);
+        }
+        Ok(arguments)
+    }
+
+    /// Parses a qubit argument, a complete register is returned as all of its qubits.
+    fn qubit_argument(&mut self) -> Result<Vec<usize>, RoqoqoError> {
+        let (name, token) = self.expect_identifier()?;
+        if let Some(gate_qubits) = &self.gate_qubits {
+            return match gate_qubits.get(&name) {
+                Some(qubit) if !self.check_symbol("[") => Ok(vec![*qubit]),
+                Some(_) => Err(self
+                    .peek()
+                    .error("Qubit arguments of gates can not be indexed")),
+                None => Err(token.error(format!("Unknown qubit argument {name}"))),
+            };
+        }
+        let (offset, length) = *self
+            .qubit_registers
+            .get(&name)
+            .ok_or_else(|| token.error(format!("Unknown quantum register {name}")))?;
+        match self.index(&name, length)? {
+            Some(index) => Ok(vec![offset + index]),
+            None => Ok((offset..offset + length).collect()),
+        }
+    }
+
+    /// Parses a classical bit argument, a complete register is returned as all of its indices.
+    fn bit_argument(&mut self) -> Result<(String, Vec<usize>), RoqoqoError> {
+        let (name, token) = self.expect_identifier()?;
+        let length = *self
+            .bit_registers
+            .get(&name)
+            .ok_or_else(|| token.error(format!("Unknown classical register {name}")))?;
+        match self.index(&name, length)? {
+            Some(index) => Ok((name, vec![index])),
+            None => Ok((name, (0..length).collect())),
+        }
+    }
+
+    /// Parses an optional index into a register.
+    fn index(&mut self, name: &str, length: usize) -> Result<Option<usize>, RoqoqoError> {
+        if !self.check_symbol("[") {
+            return Ok(None);
+        }
+        self.advance();
+        let token = self.peek().clone();
+        let index = self.integer_expression()?;
+        self.expect_symbol("]")?;
+        if index >= length {
+            return Err(token.error(format!(
+                "Index {index} is out of range for register {name} of length {length}"
+            )));
+        }
+        Ok(Some(index))
+    }
+
+    /// Parses an assignment to a bit register, `c[i] = measure q[j];` or `c[i] = 1;`.
+    fn assignment(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let token = self.peek().clone();
+        let (register, indices) = self.bit_argument()?;
+        self.expect_symbol("=")?;
+        if self.check_keyword("measure") {
+            self.advance();
+            let qubits = self.qubit_argument()?;
+            self.expect_symbol(";")?;
+            return measure(circuit, &token, &qubits, &register, &indices);
+        }
+        let value_token = self.advance();
+        let value = match value_token.kind {
+            TokenKind::Integer(0) => false,
+            TokenKind::Integer(1) => true,
+            TokenKind::Identifier(ref name) if name == "false" => false,
+            TokenKind::Identifier(ref name) if name == "true" => true,
+            _ => {
+                return Err(value_token.error(format!(
+                    "Expected measure or a bit value, found {}",
+                    value_token.describe()
+                )))
+            }
+        };
+        self.expect_symbol(";")?;
+        for index in indices {
+            circuit.add_operation(InputBit::new(register.clone(), index, value));
+        }
+        Ok(())
+    }
+
+    /// Parses an `if` statement.
+    ///
+    /// Conditions on a single bit being set are added as a [PragmaConditional]. Comparisons of
+    /// several bits with an integer (`if (c == 5)`, the first bit is the least significant one)
+    /// set a scratch bit with nested [PragmaConditional] operations, the body is conditioned on the scratch bit.
+    fn conditional(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        self.advance();
+        self.expect_symbol("(")?;
+        let token = self.peek().clone();
+        let (register, indices) = self.bit_argument()?;
+        let value = if self.eat_symbol("==") {
+            let value_token = self.advance();
+            match value_token.kind {
+                TokenKind::Integer(value) => value,
+                TokenKind::Identifier(ref name) if name == "true" => 1,
+                TokenKind::Identifier(ref name) if name == "false" => 0,
+                _ => {
+                    return Err(value_token.error(format!(
+                        "Conditions are only supported on comparisons with integers, found {}",
+                        value_token.describe()
+                    )))
+                }
+            }
+        } else if indices.len() == 1 {
+            1
+        } else {
+            return Err(token.error(format!(
+                "Conditions on register {register} with {} bits need a comparison with an integer",
+                indices.len()
+            )));
+        };
+        if indices.len() < usize::BITS as usize && value >> indices.len() != 0 {
+            return Err(token.error(format!(
+                "Value {value} can not be represented by the {} bits of register {register}",
+                indices.len()
+            )));
+        }
+        self.expect_symbol(")")?;
+        let mut body = Circuit::new();
+        self.body(&mut body)?;
+        if self.check_keyword("else") {
+            return Err(self.peek().error("Statement else is not supported"));
+        }
+        if let ([index], 1) = (indices.as_slice(), value) {
+            circuit.add_operation(PragmaConditional::new(register, *index, body));
+            return Ok(());
+        }
+
+        let condition_register = self.condition_register(&register);
+        // The scratch bit is set if all bits that have to be 1 are set and reset by any bit that has to be 0
+        let mut set_condition = Circuit::new();
+        set_condition += InputBit::new(condition_register.clone(), 0, true);
+        for (position, index) in indices.iter().enumerate().rev() {
+            if position < usize::BITS as usize && (value >> position) & 1 == 1 {
+                let mut nested = Circuit::new();
+                nested += PragmaConditional::new(register.clone(), *index, set_condition);
+                set_condition = nested;
+            }
+        }
+        circuit.add_operation(InputBit::new(condition_register.clone(), 0, false));
+        for operation in set_condition.iter() {
+            circuit.add_operation(operation.clone());
+        }
+        for (position, index) in indices.iter().enumerate() {
+            if position >= usize::BITS as usize || (value >> position) & 1 == 0 {
+                let mut reset_condition = Circuit::new();
+                reset_condition += InputBit::new(condition_register.clone(), 0, false);
+                circuit.add_operation(PragmaConditional::new(
+                    register.clone(),
+                    *index,
+                    reset_condition,
+                ));
+            }
+        }
+        circuit.add_operation(PragmaConditional::new(condition_register, 0, body));
+        Ok(())
+    }
+
+    /// Returns the name of a new scratch register for a condition on a register.
+    ///
+    /// The scratch registers are defined at the end of the import.
+    fn condition_register(&mut self, register: &str) -> String {
+        let mut index = self.condition_registers.len();
+        let mut name = format!("{register}_condition_{index}");
+        while self.bit_registers.contains_key(&name) {
+            index += 1;
+            name = format!("{register}_condition_{index}");
+        }
+        self.bit_registers.insert(name.clone(), 1);
+        self.condition_registers.push(name.clone());
+        name
+    }
+
+    /// Parses a `for` loop.
+    ///
+    /// Loops whose body does not use the loop variable are added as a [PragmaLoop],
+    /// otherwise the loop is unrolled.
+    fn for_loop(&mut self, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        self.advance();
+        let (mut variable, _) = self.expect_identifier()?;
+        if self.check_symbol("[") {
+            self.designator()?;
+            variable = self.expect_identifier()?.0;
+        } else if !self.check_keyword("in") {
+            variable = self.expect_identifier()?.0;
+        }
+        let (_, in_token) = self.expect_identifier()?;
+        if in_token.kind != TokenKind::Identifier("in".to_string()) {
+            return Err(in_token.error(format!("Expected 'in', found {}", in_token.describe())));
+        }
+        let values_token = self.peek().clone();
+        let values = self.loop_values()?;
+        let count = values.count();
+
+        let start = self.position;
+        let previous = self.variables.remove(&variable);
+        let first_value = if count > 0.0 { values.value(0) } else { 0.0 };
+        self.variables
+            .insert(variable.clone(), CalculatorFloat::from(first_value));
+        let mut body = Circuit::new();
+        let mut result = self.body(&mut body);
+        let end = self.position;
+        let uses_variable = self.tokens[start..end]
+            .iter()
+            .any(|token| token.kind == TokenKind::Identifier(variable.clone()));
+        if result.is_ok() && count > 0.0 {
+            if !uses_variable {
+                circuit.add_operation(PragmaLoop::new(CalculatorFloat::from(count), body));
+            } else if count > MAXIMUM_LOOP_ITERATIONS as f64 {
+                result = Err(values_token.error(format!(
+                    "Loops using the loop variable are unrolled and limited to {MAXIMUM_LOOP_ITERATIONS} iterations, found {count}"
+                )));
+            } else {
+                for index in 0..count as usize {
+                    self.position = start;
+                    self.variables
+                        .insert(variable.clone(), CalculatorFloat::from(values.value(index)));
+                    result = self.body(circuit);
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        self.position = end;
+        match previous {
+            Some(value) => self.variables.insert(variable, value),
+            None => self.variables.remove(&variable),
+        };
+        result
+    }
+
+    /// Parses the range `[start:stop]`, `[start:step:stop]` or the set `{a, b}` of a loop.
+    fn loop_values(&mut self) -> Result<LoopValues, RoqoqoError> {
+        if self.eat_symbol("{") {
+            let mut values = Vec::new();
+            loop {
+                values.push(self.integer_value()?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol("}")?;
+            return Ok(LoopValues::Set(values));
+        }
+        let open = self.expect_symbol("[")?;
+        let start = self.integer_value()?;
+        self.expect_symbol(":")?;
+        let mut step = 1.0;
+        let mut stop = self.integer_value()?;
+        if self.eat_symbol(":") {
+            step = stop;
+            stop = self.integer_value()?;
+        }
+        self.expect_symbol("]")?;
+        if step == 0.0 {
+            return Err(open.error("The step of a loop range can not be zero"));
+        }
+        let count = ((stop - start) / step).floor() + 1.0;
+        Ok(LoopValues::Range {
+            start,
+            step,
+            count: count.max(0.0),
+        })
+    }
+
+    /// Parses an expression that has to evaluate to an integer.
+    fn integer_value(&mut self) -> Result<f64, RoqoqoError> {
+        let token = self.peek().clone();
+        match self.expression()? {
+            CalculatorFloat::Float(value) if value.fract() == 0.0 => Ok(value),
+            value => Err(token.error(format!("Expected an integer, found {value}"))),
+        }
+    }
+
+    /// Parses an expression that has to evaluate to a non-negative integer.
+    fn integer_expression(&mut self) -> Result<usize, RoqoqoError> {
+        let token = self.peek().clone();
+        match self.integer_value()? {
+            value if value >= 0.0 => Ok(value as usize),
+            value => Err(token.error(format!("Expected a non-negative integer, found {value}"))),
+        }
+    }
+
+    /// Parses an arithmetic expression.
+    fn expression(&mut self) -> Result<CalculatorFloat, RoqoqoError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat_symbol("+") {
+                value += self.term()?;
+            } else if self.eat_symbol("-") {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parses a product or quotient.
+    fn term(&mut self) -> Result<CalculatorFloat, RoqoqoError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat_symbol("*") {
+                value *= self.unary()?;
+            } else if self.check_symbol("/") {
+                let token = self.advance();
+                let divisor = self.unary()?;
+                if divisor == CalculatorFloat::from(0.0) {
+                    return Err(token.error("Division by zero"));
+                }
+                value /= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parses a unary sign.
+    fn unary(&mut self) -> Result<CalculatorFloat, RoqoqoError> {
+        if self.eat_symbol("-") {
+            Ok(-self.unary()?)
+        } else if self.eat_symbol("+") {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// Parses a power, `^` and `**` are right associative.
+    fn power(&mut self) -> Result<CalculatorFloat, RoqoqoError> {
+        let base = self.primary()?;
+        if self.eat_symbol("**") || self.eat_symbol("^") {
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    /// Parses a number, identifier, function call or parenthesized expression.
+    fn primary(&mut self) -> Result<CalculatorFloat, RoqoqoError> {
+        let token = self.advance();
+        match &token.kind {
+            TokenKind::Integer(value) => Ok(CalculatorFloat::from(*value as f64)),
+            TokenKind::Real(value) => Ok(CalculatorFloat::from(*value)),
+            TokenKind::Symbol("(") => {
+                let value = self.expression()?;
+                self.expect_symbol(")")?;
+                Ok(value)
+            }
+            TokenKind::Identifier(name) if self.check_symbol("(") => {
+                self.advance();
+                let mut arguments = vec![self.expression()?];
+                while self.eat_symbol(",") {
+                    arguments.push(self.expression()?);
+                }
+                self.expect_symbol(")")?;
+                function(name, arguments).ok_or_else(|| {
+                    token.error(format!(
+                        "Unknown function {name} or wrong number of arguments"
+                    ))
+                })
+            }
+            TokenKind::Identifier(name) => self.identifier_value(name, &token),
+            _ => Err(token.error(format!(
+                "Expected an expression, found {}",
+                token.describe()
+            ))),
+        }
+    }
+
+    /// Returns the value of an identifier in an expression.
+    ///
+    /// Unknown identifiers outside of gate bodies are symbolic parameters.
+    fn identifier_value(&self, name: &str, token: &Token) -> Result<CalculatorFloat, RoqoqoError> {
+        if let Some(value) = self
+            .variables
+            .get(name)
+            .or_else(|| self.constants.get(name))
+        {
+            return Ok(value.clone());
+        }
+        match name {
+            "pi" | "π" => Ok(CalculatorFloat::PI),
+            "tau" | "τ" => Ok(CalculatorFloat::from(TAU)),
+            "euler" | "ℇ" => Ok(CalculatorFloat::from(E)),
+            _ if self.gate_qubits.is_some() => {
+                Err(token.error(format!("Unknown parameter {name}")))
+            }
+            _ if self.qubit_registers.contains_key(name)
+                || self.bit_registers.contains_key(name) =>
+            {
+                Err(token.error(format!("Register {name} can not be used in an expression")))
+            }
+            _ => Ok(CalculatorFloat::Str(name.to_string())),
+        }
+    }
+}
+
+/// Returns an error if a bit register exceeds the maximal register length.
+fn check_bit_register_length(name: &str, length: usize, token: &Token) -> Result<(), RoqoqoError> {
+    if length > MAXIMUM_REGISTER_LENGTH {
+        return Err(token.error(format!(
+            "Register {name} exceeds the maximal length of {MAXIMUM_REGISTER_LENGTH} bits"
+        )));
+    }
+    Ok(())
+}
+
+/// Adds the measurement of qubits into bits of a classical register.
+fn measure(
+    circuit: &mut Circuit,
+    token: &Token,
+    qubits: &[usize],
+    register: &str,
+    indices: &[usize],
+) -> Result<(), RoqoqoError> {
+    if qubits.len() != indices.len() {
+        return Err(token.error(format!(
+            "Measurement of {} qubits into {} bits",
+            qubits.len(),
+            indices.len()
+        )));
+    }
+    for (qubit, index) in qubits.iter().zip(indices.iter()) {
+        circuit.add_operation(MeasureQubit::new(*qubit, register.to_string(), *index));
+    }
+    Ok(())
+}
+
+/// Applies a built-in function of OpenQASM to its arguments.
+///
+/// Symbolic arguments are translated to the equivalent expression of [qoqo_calculator].
+fn function(name: &str, arguments: Vec<CalculatorFloat>) -> Option<CalculatorFloat> {
+    let (calculator_name, function): (&str, fn(f64) -> f64) = match (name, arguments.as_slice()) {
+        ("sqrt", [x]) => return Some(x.sqrt()),
+        ("exp", [x]) => return Some(x.exp()),
+        ("sin", [x]) => return Some(x.sin()),
+        ("cos", [x]) => return Some(x.cos()),
+        ("arccos" | "acos", [x]) => return Some(x.acos()),
+        ("abs", [x]) => return Some(x.abs()),
+        ("atan2", [y, x]) => return Some(y.atan2(x)),
+        ("pow", [x, y]) => return Some(x.powf(y)),
+        ("tan", [_]) => ("tan", f64::tan),
+        ("arcsin" | "asin", [_]) => ("asin", f64::asin),
+        ("arctan" | "atan", [_]) => ("atan", f64::atan),
+        ("ln" | "log", [_]) => ("log", f64::ln),
+        ("ceiling" | "ceil", [_]) => ("ceil", f64::ceil),
+        ("floor", [_]) => ("floor", f64::floor),
+        _ => return None,
+    };
+    match &arguments[0] {
+        CalculatorFloat::Float(x) => Some(CalculatorFloat::from(function(*x))),
+        CalculatorFloat::Str(x) => Some(CalculatorFloat::Str(format!("{calculator_name}({x})"))),
+    }
+}
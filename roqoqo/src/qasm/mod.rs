@@ -0,0 +1,75 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of roqoqo [crate::Circuit]s to and from OpenQASM programs.
+//!
+//! Two versions of OpenQASM are supported:
+//! * OpenQASM 2.0, using the gates of the `qelib1.inc` include file distributed with Qiskit.
+//! * OpenQASM 3.0, using the gates of the `stdgates.inc` include file.
+//!
+//! The qubits of a circuit are mapped to a single quantum register `q`, the classical bit
+//! registers keep the names of their [crate::operations::DefinitionBit].
+//! Gates without an equivalent in the include file are exported with a `gate` definition
+//! at the top of the program. The name of the definition is the lowercase hqslang name of the gate
+//! (for multi-qubit gates followed by the number of qubits) and its body reproduces the
+//! gate up to a global phase.
+//!
+//! The import supports OpenQASM 2.0 and a practical subset of OpenQASM 3.0: register declarations,
+//! gate calls with register broadcasting, custom `gate` definitions, `measure`, `reset`, `barrier`,
+//! conditions on single bits, `for` loops over integer ranges and parameter expressions,
+//! which are kept symbolic when they contain undefined variables.
+//! Definitions written by the export are mapped back to the roqoqo gates, so exported circuits
+//! can be imported again.
+
+mod export;
+mod gates;
+mod import;
+
+pub use export::circuit_to_qasm;
+pub use import::circuit_from_qasm;
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::RoqoqoError;
+
+/// The version of the OpenQASM language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QasmVersion {
+    /// OpenQASM 2.0.
+    V2_0,
+    /// OpenQASM 3.0.
+    V3_0,
+}
+
+impl fmt::Display for QasmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QasmVersion::V2_0 => write!(f, "2.0"),
+            QasmVersion::V3_0 => write!(f, "3.0"),
+        }
+    }
+}
+
+impl FromStr for QasmVersion {
+    type Err = RoqoqoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "2" | "2.0" => Ok(QasmVersion::V2_0),
+            "3" | "3.0" => Ok(QasmVersion::V3_0),
+            _ => Err(RoqoqoError::GenericError {
+                msg: format!("Unknown OpenQASM version {s}, supported versions are 2.0 and 3.0"),
+            }),
+        }
+    }
+}
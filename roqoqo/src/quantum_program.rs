@@ -18,7 +18,10 @@ use crate::backends::AsyncEvaluatingBackend;
 use crate::backends::{EvaluatingBackend, RegisterResult};
 use crate::measurements;
 use crate::measurements::Measure;
-use crate::RoqoqoBackendError;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoBackendError, RoqoqoError};
+use qoqo_calculator::{Calculator, CalculatorFloat};
+use std::f64::consts::FRAC_PI_2;
 use std::fmt::{Display, Formatter};
 
 /// Represents a quantum program evaluating measurements based on a one or more free float parameters.
@@ -67,6 +70,13 @@ pub enum QuantumProgram {
         /// List of free input parameters that can be set when the QuantumProgram is executed
         input_parameter_names: Vec<String>,
     },
+    /// Variant for classical shadow measurement based quantum programs
+    ClassicalShadow {
+        /// The measurement that is performed
+        measurement: measurements::ClassicalShadow,
+        /// List of free input parameters that can be set when the QuantumProgram is executed
+        input_parameter_names: Vec<String>,
+    },
 }
 
 impl QuantumProgram {
@@ -112,10 +122,78 @@ impl QuantumProgram {
                 )?;
                 backend.run_measurement(&substituted_measurement)
             }
+            QuantumProgram::ClassicalShadow{measurement, input_parameter_names } => {
+                if parameters.len() != input_parameter_names.len() { return Err(RoqoqoBackendError::GenericError{msg: format!("Wrong number of parameters {} parameters expected {} parameters given", input_parameter_names.len(), parameters.len())})};
+                let substituted_parameters: HashMap<String, f64> = input_parameter_names.iter().zip(parameters.iter()).map(|(key, value)| (key.clone(), *value)).collect();
+                let substituted_measurement = measurement.substitute_parameters(
+                    substituted_parameters
+                )?;
+                backend.run_measurement(&substituted_measurement)
+            }
             _ => Err(RoqoqoBackendError::GenericError{msg: "A quantum programm returning classical registeres cannot be executed by `run` use `run_registers` instead".to_string()})
         }
     }
 
+    /// Runs the QuantumProgram for a batch of parameter points and returns expectation values.
+    ///
+    /// The parameters are substituted once for every point and the measurements of all points are run
+    /// with [EvaluatingBackend::run_measurement_batch], by default submitting the circuits of all points
+    /// together so that the backend can execute them as one batch.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The parameter points, each a list of float ([f64]) parameters in order of `input_parameter_names`
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(Vec<Option<HashMap<String, f64>>>)` - The expectation values for every parameter point.
+    /// * `Err(RoqoqoBackendError)` - A parameter point has the wrong length or running the circuits failed.
+    pub fn run_batch<T>(
+        &self,
+        backend: T,
+        parameters: &[Vec<f64>],
+    ) -> Result<Vec<Option<HashMap<String, f64>>>, RoqoqoBackendError>
+    where
+        T: EvaluatingBackend,
+    {
+        match self {
+            QuantumProgram::PauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => backend.run_measurement_batch(&substitute_batch(
+                measurement,
+                input_parameter_names,
+                parameters,
+            )?),
+            QuantumProgram::CheatedPauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => backend.run_measurement_batch(&substitute_batch(
+                measurement,
+                input_parameter_names,
+                parameters,
+            )?),
+            QuantumProgram::Cheated {
+                measurement,
+                input_parameter_names,
+            } => backend.run_measurement_batch(&substitute_batch(
+                measurement,
+                input_parameter_names,
+                parameters,
+            )?),
+            QuantumProgram::ClassicalShadow {
+                measurement,
+                input_parameter_names,
+            } => backend.run_measurement_batch(&substitute_batch(
+                measurement,
+                input_parameter_names,
+                parameters,
+            )?),
+            _ => Err(RoqoqoBackendError::GenericError{msg: "A quantum programm returning classical registeres cannot be executed by `run_batch` use `run_registers` instead".to_string()})
+        }
+    }
+
     /// Runs the QuantumProgram and returns the classical registers of the quantum program.
     ///
     /// Runs the quantum programm for a given set of parameters passed in the same order as the parameters
@@ -189,10 +267,91 @@ impl QuantumProgram {
                 )?;
                 backend.async_run_measurement(&substituted_measurement).await
             }
+            QuantumProgram::ClassicalShadow{measurement, input_parameter_names } => {
+                if parameters.len() != input_parameter_names.len() { return Err(RoqoqoBackendError::GenericError{msg: format!("Wrong number of parameters {} parameters expected {} parameters given", input_parameter_names.len(), parameters.len())})};
+                let substituted_parameters: HashMap<String, f64> = input_parameter_names.iter().zip(parameters.iter()).map(|(key, value)| (key.clone(), *value)).collect();
+                let substituted_measurement = measurement.substitute_parameters(
+                    substituted_parameters
+                )?;
+                backend.async_run_measurement(&substituted_measurement).await
+            }
             _ => Err(RoqoqoBackendError::GenericError{msg: "A quantum programm returning classical registeres cannot be executed by `run` use `run_registers` instead".to_string()})
         }
     }
 
+    /// Runs the QuantumProgram for a batch of parameter points and returns expectation values.
+    ///
+    /// The parameters are substituted once for every point and the measurements of all points
+    /// are run concurrently on the backend.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The parameter points, each a list of float ([f64]) parameters in order of `input_parameter_names`
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(Vec<Option<HashMap<String, f64>>>)` - The expectation values for every parameter point.
+    /// * `Err(RoqoqoBackendError)` - A parameter point has the wrong length or running the circuits failed.
+    #[cfg(feature = "async")]
+    pub async fn async_run_batch<T>(
+        &self,
+        backend: T,
+        parameters: &[Vec<f64>],
+    ) -> Result<Vec<Option<HashMap<String, f64>>>, RoqoqoBackendError>
+    where
+        T: AsyncEvaluatingBackend,
+        T: Sync,
+    {
+        match self {
+            QuantumProgram::PauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => {
+                let measurements =
+                    substitute_batch(measurement, input_parameter_names, parameters)?;
+                futures::future::try_join_all(
+                    measurements.iter().map(|m| backend.async_run_measurement(m)),
+                )
+                .await
+            }
+            QuantumProgram::CheatedPauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => {
+                let measurements =
+                    substitute_batch(measurement, input_parameter_names, parameters)?;
+                futures::future::try_join_all(
+                    measurements.iter().map(|m| backend.async_run_measurement(m)),
+                )
+                .await
+            }
+            QuantumProgram::Cheated {
+                measurement,
+                input_parameter_names,
+            } => {
+                let measurements =
+                    substitute_batch(measurement, input_parameter_names, parameters)?;
+                futures::future::try_join_all(
+                    measurements.iter().map(|m| backend.async_run_measurement(m)),
+                )
+                .await
+            }
+            QuantumProgram::ClassicalShadow {
+                measurement,
+                input_parameter_names,
+            } => {
+                let measurements =
+                    substitute_batch(measurement, input_parameter_names, parameters)?;
+                futures::future::try_join_all(
+                    measurements.iter().map(|m| backend.async_run_measurement(m)),
+                )
+                .await
+            }
+            _ => Err(RoqoqoBackendError::GenericError{msg: "A quantum programm returning classical registeres cannot be executed by `run_batch` use `run_registers` instead".to_string()})
+        }
+    }
+
     /// Runs the QuantumProgram and returns the classical registers of the quantum program.
     ///
     /// Runs the quantum programm for a given set of parameters passed in the same order as the parameters
@@ -223,6 +382,793 @@ impl QuantumProgram {
             _ => Err(RoqoqoBackendError::GenericError{msg: "A quantum programm returning expectation values cannot be executed by `run_registers` use `run` instead".to_string()})
         }
     }
+
+    /// Runs the QuantumProgram with named parameters and returns expectation values.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The float ([f64]) value of every input parameter by name.
+    pub fn run_named<T>(
+        &self,
+        backend: T,
+        parameters: &HashMap<String, f64>,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoBackendError>
+    where
+        T: EvaluatingBackend,
+    {
+        self.run(backend, &self.ordered_parameters(parameters)?)
+    }
+
+    /// Runs the QuantumProgram with named parameters and returns the classical registers of the quantum program.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The float ([f64]) value of every input parameter by name.
+    pub fn run_registers_named<T>(
+        &self,
+        backend: T,
+        parameters: &HashMap<String, f64>,
+    ) -> RegisterResult
+    where
+        T: EvaluatingBackend,
+    {
+        self.run_registers(backend, &self.ordered_parameters(parameters)?)
+    }
+
+    /// Runs the QuantumProgram with named parameters and returns expectation values.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The float ([f64]) value of every input parameter by name.
+    #[cfg(feature = "async")]
+    pub async fn async_run_named<T>(
+        &self,
+        backend: T,
+        parameters: &HashMap<String, f64>,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoBackendError>
+    where
+        T: AsyncEvaluatingBackend,
+        T: Sync,
+    {
+        let parameters = self.ordered_parameters(parameters)?;
+        self.async_run(backend, &parameters).await
+    }
+
+    /// Runs the QuantumProgram with named parameters and returns the classical registers of the quantum program.
+    ///
+    /// Every name in `input_parameter_names` must be given a value in `parameters`
+    /// and `parameters` must not contain any other names.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - The float ([f64]) value of every input parameter by name.
+    #[cfg(feature = "async")]
+    pub async fn async_run_registers_named<T>(
+        &self,
+        backend: T,
+        parameters: &HashMap<String, f64>,
+    ) -> RegisterResult
+    where
+        T: AsyncEvaluatingBackend,
+        T: Sync,
+    {
+        let parameters = self.ordered_parameters(parameters)?;
+        self.async_run_registers(backend, &parameters).await
+    }
+
+    /// Binds a subset of the input parameters to fixed values.
+    ///
+    /// The bound parameters are added as [InputSymbolic] definitions to every circuit of the measurement
+    /// and removed from `input_parameter_names`. The remaining input parameters keep their order.
+    ///
+    /// Arguments:
+    ///
+    /// * `parameters` - The float ([f64]) values of the input parameters that are bound by name.
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(QuantumProgram)` - The QuantumProgram with the reduced list of input parameters.
+    /// * `Err(RoqoqoError::UnknownInputParameters)` - A name in `parameters` is not an input parameter of the QuantumProgram.
+    pub fn bind_parameters(
+        &self,
+        parameters: &HashMap<String, f64>,
+    ) -> Result<QuantumProgram, RoqoqoError> {
+        self.check_unknown_parameters(parameters)?;
+        let input_parameter_names = self.input_parameter_names();
+        // Bound in the order of the input parameters so that the circuits are deterministic
+        let bound: Vec<(&String, f64)> = input_parameter_names
+            .iter()
+            .filter_map(|name| parameters.get(name).map(|value| (name, *value)))
+            .collect();
+        let bind_circuit = |circuit: &Circuit| -> Circuit {
+            let mut circuit = circuit.clone();
+            for (name, value) in bound.iter() {
+                circuit.add_operation(InputSymbolic::new((*name).clone(), *value));
+            }
+            circuit
+        };
+        let remaining_parameter_names: Vec<String> = input_parameter_names
+            .iter()
+            .filter(|name| !parameters.contains_key(*name))
+            .cloned()
+            .collect();
+        Ok(match self {
+            QuantumProgram::PauliZProduct { measurement, .. } => QuantumProgram::PauliZProduct {
+                measurement: measurements::PauliZProduct {
+                    constant_circuit: measurement.constant_circuit.as_ref().map(bind_circuit),
+                    circuits: measurement.circuits.iter().map(bind_circuit).collect(),
+                    input: measurement.input.clone(),
+                },
+                input_parameter_names: remaining_parameter_names,
+            },
+            QuantumProgram::CheatedPauliZProduct { measurement, .. } => {
+                QuantumProgram::CheatedPauliZProduct {
+                    measurement: measurements::CheatedPauliZProduct {
+                        constant_circuit: measurement.constant_circuit.as_ref().map(bind_circuit),
+                        circuits: measurement.circuits.iter().map(bind_circuit).collect(),
+                        input: measurement.input.clone(),
+                    },
+                    input_parameter_names: remaining_parameter_names,
+                }
+            }
+            QuantumProgram::Cheated { measurement, .. } => QuantumProgram::Cheated {
+                measurement: measurements::Cheated {
+                    constant_circuit: measurement.constant_circuit.as_ref().map(bind_circuit),
+                    circuits: measurement.circuits.iter().map(bind_circuit).collect(),
+                    input: measurement.input.clone(),
+                },
+                input_parameter_names: remaining_parameter_names,
+            },
+            QuantumProgram::ClassicalRegister { measurement, .. } => {
+                QuantumProgram::ClassicalRegister {
+                    measurement: measurements::ClassicalRegister {
+                        constant_circuit: measurement.constant_circuit.as_ref().map(bind_circuit),
+                        circuits: measurement.circuits.iter().map(bind_circuit).collect(),
+                    },
+                    input_parameter_names: remaining_parameter_names,
+                }
+            }
+            QuantumProgram::ClassicalShadow { measurement, .. } => {
+                QuantumProgram::ClassicalShadow {
+                    measurement: measurements::ClassicalShadow {
+                        constant_circuit: measurement.constant_circuit.as_ref().map(bind_circuit),
+                        circuits: measurement.circuits.iter().map(bind_circuit).collect(),
+                        input: measurement.input.clone(),
+                    },
+                    input_parameter_names: remaining_parameter_names,
+                }
+            }
+        })
+    }
+
+    /// Orders named parameters in the order of `input_parameter_names`.
+    ///
+    /// Arguments:
+    ///
+    /// * `parameters` - The float ([f64]) value of every input parameter by name.
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(Vec<f64>)` - The parameters in order of `input_parameter_names`.
+    /// * `Err(RoqoqoError::UnknownInputParameters)` - A name in `parameters` is not an input parameter of the QuantumProgram.
+    /// * `Err(RoqoqoError::MissingInputParameters)` - An input parameter has no value in `parameters`.
+    pub fn ordered_parameters(
+        &self,
+        parameters: &HashMap<String, f64>,
+    ) -> Result<Vec<f64>, RoqoqoError> {
+        self.check_unknown_parameters(parameters)?;
+        let input_parameter_names = self.input_parameter_names();
+        let missing: Vec<String> = input_parameter_names
+            .iter()
+            .filter(|name| !parameters.contains_key(*name))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(RoqoqoError::MissingInputParameters { names: missing });
+        }
+        Ok(input_parameter_names
+            .iter()
+            .map(|name| parameters[name])
+            .collect())
+    }
+
+    /// Runs the QuantumProgram and returns the Jacobian of the expectation values.
+    ///
+    /// The derivatives are evaluated with the parameter-shift rule, see [QuantumProgram::parameter_shift_programs].
+    ///
+    /// Arguments:
+    ///
+    /// * `backend` - The backend the program is executed on.
+    /// * `parameters` - List of float ([f64]) parameters of the function call in order of `input_parameter_names`
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(Some(HashMap<String, Vec<f64>>))` - The derivatives of every expectation value in order of `input_parameter_names`.
+    /// * `Err(RoqoqoBackendError)` - The gradient cannot be evaluated for the QuantumProgram or the backend failed.
+    pub fn run_gradient<T>(
+        &self,
+        backend: T,
+        parameters: &[f64],
+    ) -> Result<Option<HashMap<String, Vec<f64>>>, RoqoqoBackendError>
+    where
+        T: EvaluatingBackend,
+    {
+        let shifted = self.parameter_shift_programs(parameters)?;
+        let mut results: Vec<HashMap<String, f64>> = Vec::with_capacity(shifted.programs.len());
+        for program in shifted.programs.iter() {
+            match program.run_substituted(&backend)? {
+                Some(result) => results.push(result),
+                None => return Ok(None),
+            }
+        }
+        shifted.jacobian(&results).map(Some)
+    }
+
+    /// Returns the shifted quantum programs evaluating the Jacobian with the parameter-shift rule.
+    ///
+    /// For every rotation angle that depends on the input parameters, two programs are created
+    /// with all input parameters substituted and the angle shifted by +pi/2 and -pi/2.
+    /// The derivative of an expectation value with respect to the angle is half the difference
+    /// of the two shifted expectation values.
+    /// The derivatives with respect to the input parameters follow with the chain rule.
+    ///
+    /// The parameter-shift rule is exact for the gates RotateX, RotateY, RotateZ, RotateXY,
+    /// RotateAroundSphericalAxis, PhaseShiftState0, PhaseShiftState1, ControlledPhaseShift,
+    /// ControlledControlledPhaseShift, PhaseShiftedControlledPhase, VariableMSXX, MultiQubitMS and MultiQubitZZ.
+    /// The input parameters may only enter the rotation angle theta of these gates,
+    /// and the angle must be an affine function of every input parameter.
+    ///
+    /// Arguments:
+    ///
+    /// * `parameters` - List of float ([f64]) parameters of the function call in order of `input_parameter_names`
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(ParameterShiftPrograms)` - The shifted programs and their weights in the Jacobian.
+    /// * `Err(RoqoqoBackendError)` - An input parameter enters an unsupported operation or a non-affine expression.
+    pub fn parameter_shift_programs(
+        &self,
+        parameters: &[f64],
+    ) -> Result<ParameterShiftPrograms, RoqoqoBackendError> {
+        match self {
+            QuantumProgram::PauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => ShiftedCircuits::new(
+                measurement.constant_circuit.as_ref(),
+                &measurement.circuits,
+                input_parameter_names,
+                parameters,
+            )?
+            .programs(|constant_circuit, circuits| QuantumProgram::PauliZProduct {
+                measurement: measurements::PauliZProduct {
+                    constant_circuit,
+                    circuits,
+                    input: measurement.input.clone(),
+                },
+                input_parameter_names: Vec::new(),
+            }),
+            QuantumProgram::CheatedPauliZProduct {
+                measurement,
+                input_parameter_names,
+            } => ShiftedCircuits::new(
+                measurement.constant_circuit.as_ref(),
+                &measurement.circuits,
+                input_parameter_names,
+                parameters,
+            )?
+            .programs(
+                |constant_circuit, circuits| QuantumProgram::CheatedPauliZProduct {
+                    measurement: measurements::CheatedPauliZProduct {
+                        constant_circuit,
+                        circuits,
+                        input: measurement.input.clone(),
+                    },
+                    input_parameter_names: Vec::new(),
+                },
+            ),
+            QuantumProgram::Cheated {
+                measurement,
+                input_parameter_names,
+            } => ShiftedCircuits::new(
+                measurement.constant_circuit.as_ref(),
+                &measurement.circuits,
+                input_parameter_names,
+                parameters,
+            )?
+            .programs(|constant_circuit, circuits| QuantumProgram::Cheated {
+                measurement: measurements::Cheated {
+                    constant_circuit,
+                    circuits,
+                    input: measurement.input.clone(),
+                },
+                input_parameter_names: Vec::new(),
+            }),
+            QuantumProgram::ClassicalShadow {
+                measurement,
+                input_parameter_names,
+            } => ShiftedCircuits::new(
+                measurement.constant_circuit.as_ref(),
+                &measurement.circuits,
+                input_parameter_names,
+                parameters,
+            )?
+            .programs(|constant_circuit, circuits| QuantumProgram::ClassicalShadow {
+                measurement: measurements::ClassicalShadow {
+                    constant_circuit,
+                    circuits,
+                    input: measurement.input.clone(),
+                },
+                input_parameter_names: Vec::new(),
+            }),
+            _ => Err(RoqoqoBackendError::GenericError {
+                msg: "The gradient of a quantum programm returning classical registeres cannot be evaluated".to_string(),
+            }),
+        }
+    }
+
+    /// Runs the measurement of a QuantumProgram without free input parameters.
+    fn run_substituted<T>(
+        &self,
+        backend: &T,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoBackendError>
+    where
+        T: EvaluatingBackend,
+    {
+        match self {
+            QuantumProgram::PauliZProduct { measurement, .. } => {
+                backend.run_measurement(measurement)
+            }
+            QuantumProgram::CheatedPauliZProduct { measurement, .. } => {
+                backend.run_measurement(measurement)
+            }
+            QuantumProgram::Cheated { measurement, .. } => backend.run_measurement(measurement),
+            QuantumProgram::ClassicalShadow { measurement, .. } => {
+                backend.run_measurement(measurement)
+            }
+            _ => Err(RoqoqoBackendError::GenericError {
+                msg: "A quantum programm returning classical registeres cannot be executed by `run` use `run_registers` instead".to_string(),
+            }),
+        }
+    }
+
+    /// Returns the list of free input parameters of the QuantumProgram.
+    fn input_parameter_names(&self) -> &[String] {
+        match self {
+            QuantumProgram::PauliZProduct {
+                input_parameter_names,
+                ..
+            } => input_parameter_names,
+            QuantumProgram::CheatedPauliZProduct {
+                input_parameter_names,
+                ..
+            } => input_parameter_names,
+            QuantumProgram::Cheated {
+                input_parameter_names,
+                ..
+            } => input_parameter_names,
+            QuantumProgram::ClassicalRegister {
+                input_parameter_names,
+                ..
+            } => input_parameter_names,
+            QuantumProgram::ClassicalShadow {
+                input_parameter_names,
+                ..
+            } => input_parameter_names,
+        }
+    }
+
+    /// Returns an error listing the names in `parameters` that are not input parameters.
+    fn check_unknown_parameters(
+        &self,
+        parameters: &HashMap<String, f64>,
+    ) -> Result<(), RoqoqoError> {
+        let input_parameter_names = self.input_parameter_names();
+        let mut unknown: Vec<String> = parameters
+            .keys()
+            .filter(|name| !input_parameter_names.contains(name))
+            .cloned()
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        Err(RoqoqoError::UnknownInputParameters {
+            names: unknown,
+            input_parameter_names: input_parameter_names.to_vec(),
+        })
+    }
+}
+
+/// Shifted quantum programs evaluating the Jacobian of a [QuantumProgram] with the parameter-shift rule.
+///
+/// Created by [QuantumProgram::parameter_shift_programs]. The programs have no free input parameters
+/// and can be run with an empty parameter list.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParameterShiftPrograms {
+    /// The quantum programs with substituted input parameters and one shifted rotation angle.
+    pub programs: Vec<QuantumProgram>,
+    /// The weights of the expectation values of every program in the derivatives with respect to every input parameter.
+    pub weights: Vec<Vec<f64>>,
+}
+
+impl ParameterShiftPrograms {
+    /// Combines the expectation values of the shifted programs to the Jacobian.
+    ///
+    /// Arguments:
+    ///
+    /// * `results` - The expectation values returned by every program in `programs`.
+    ///
+    /// Returns:
+    ///
+    /// * `Ok(HashMap<String, Vec<f64>>)` - The derivatives of every expectation value in order of the input parameters.
+    /// * `Err(RoqoqoBackendError)` - The number of results does not match the number of programs.
+    pub fn jacobian(
+        &self,
+        results: &[HashMap<String, f64>],
+    ) -> Result<HashMap<String, Vec<f64>>, RoqoqoBackendError> {
+        if results.len() != self.programs.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Wrong number of results {} results expected {} results given",
+                    self.programs.len(),
+                    results.len()
+                ),
+            });
+        }
+        let mut jacobian: HashMap<String, Vec<f64>> = HashMap::new();
+        for (result, weights) in results.iter().zip(self.weights.iter()) {
+            for (name, value) in result.iter() {
+                let derivatives = jacobian
+                    .entry(name.clone())
+                    .or_insert_with(|| vec![0.0; weights.len()]);
+                for (derivative, weight) in derivatives.iter_mut().zip(weights.iter()) {
+                    *derivative += weight * value;
+                }
+            }
+        }
+        Ok(jacobian)
+    }
+}
+
+/// Substitutes the input parameters of a measurement for every parameter point of a batch.
+fn substitute_batch<T>(
+    measurement: &T,
+    input_parameter_names: &[String],
+    parameters: &[Vec<f64>],
+) -> Result<Vec<T>, RoqoqoBackendError>
+where
+    T: Measure,
+{
+    parameters
+        .iter()
+        .map(|point| {
+            if point.len() != input_parameter_names.len() {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "Wrong number of parameters {} parameters expected {} parameters given",
+                        input_parameter_names.len(),
+                        point.len()
+                    ),
+                });
+            }
+            let substituted_parameters: HashMap<String, f64> = input_parameter_names
+                .iter()
+                .zip(point.iter())
+                .map(|(key, value)| (key.clone(), *value))
+                .collect();
+            Ok(measurement.substitute_parameters(substituted_parameters)?)
+        })
+        .collect()
+}
+
+/// A rotation angle depending on the input parameters.
+#[derive(Debug, Clone)]
+struct ShiftedAngle {
+    /// Index of the circuit in the measurement, None for the constant circuit.
+    circuit: Option<usize>,
+    /// Index of the operation in the circuit.
+    index: usize,
+    /// The operation with the angle shifted by +pi/2.
+    plus: Operation,
+    /// The operation with the angle shifted by -pi/2.
+    minus: Operation,
+    /// The derivatives of the angle with respect to the input parameters.
+    derivatives: Vec<f64>,
+}
+
+/// The substituted circuits of a measurement and the rotation angles depending on the input parameters.
+#[derive(Debug, Clone)]
+struct ShiftedCircuits {
+    constant_circuit: Option<Circuit>,
+    circuits: Vec<Circuit>,
+    angles: Vec<ShiftedAngle>,
+    number_parameters: usize,
+}
+
+impl ShiftedCircuits {
+    fn new(
+        constant_circuit: Option<&Circuit>,
+        circuits: &[Circuit],
+        input_parameter_names: &[String],
+        parameters: &[f64],
+    ) -> Result<Self, RoqoqoBackendError> {
+        if parameters.len() != input_parameter_names.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Wrong number of parameters {} parameters expected {} parameters given",
+                    input_parameter_names.len(),
+                    parameters.len()
+                ),
+            });
+        }
+        let mut angles: Vec<ShiftedAngle> = Vec::new();
+        let constant_circuit = constant_circuit
+            .map(|circuit| {
+                substitute_with_angles(
+                    circuit,
+                    None,
+                    input_parameter_names,
+                    parameters,
+                    &mut angles,
+                )
+            })
+            .transpose()?;
+        let circuits = circuits
+            .iter()
+            .enumerate()
+            .map(|(index, circuit)| {
+                substitute_with_angles(
+                    circuit,
+                    Some(index),
+                    input_parameter_names,
+                    parameters,
+                    &mut angles,
+                )
+            })
+            .collect::<Result<Vec<Circuit>, RoqoqoBackendError>>()?;
+        Ok(Self {
+            constant_circuit,
+            circuits,
+            angles,
+            number_parameters: parameters.len(),
+        })
+    }
+
+    fn programs<F>(&self, create_program: F) -> Result<ParameterShiftPrograms, RoqoqoBackendError>
+    where
+        F: Fn(Option<Circuit>, Vec<Circuit>) -> QuantumProgram,
+    {
+        if self.angles.is_empty() {
+            // Nothing depends on the input parameters, the program is run once to obtain the names of the expectation values
+            return Ok(ParameterShiftPrograms {
+                programs: vec![create_program(
+                    self.constant_circuit.clone(),
+                    self.circuits.clone(),
+                )],
+                weights: vec![vec![0.0; self.number_parameters]],
+            });
+        }
+        let mut programs: Vec<QuantumProgram> = Vec::with_capacity(2 * self.angles.len());
+        let mut weights: Vec<Vec<f64>> = Vec::with_capacity(2 * self.angles.len());
+        for angle in self.angles.iter() {
+            for (operation, sign) in [(&angle.plus, 0.5), (&angle.minus, -0.5)] {
+                let mut constant_circuit = self.constant_circuit.clone();
+                let mut circuits = self.circuits.clone();
+                let circuit = match angle.circuit {
+                    None => constant_circuit.as_mut(),
+                    Some(index) => circuits.get_mut(index),
+                }
+                .ok_or_else(|| RoqoqoBackendError::GenericError {
+                    msg: "Shifted rotation angle refers to a missing circuit of the measurement"
+                        .to_string(),
+                })?;
+                *circuit.get_mut(angle.index).ok_or_else(|| {
+                    RoqoqoBackendError::GenericError {
+                        msg: format!(
+                        "Shifted rotation angle refers to the missing operation {} of the circuit",
+                        angle.index
+                    ),
+                    }
+                })? = operation.clone();
+                programs.push(create_program(constant_circuit, circuits));
+                weights.push(angle.derivatives.iter().map(|d| sign * d).collect());
+            }
+        }
+        Ok(ParameterShiftPrograms { programs, weights })
+    }
+}
+
+/// Substitutes the input parameters in a circuit and collects the rotation angles depending on them.
+fn substitute_with_angles(
+    circuit: &Circuit,
+    circuit_index: Option<usize>,
+    input_parameter_names: &[String],
+    parameters: &[f64],
+    angles: &mut Vec<ShiftedAngle>,
+) -> Result<Circuit, RoqoqoBackendError> {
+    let mut calculator = Calculator::new();
+    for (name, value) in input_parameter_names.iter().zip(parameters.iter()) {
+        calculator.set_variable(name, *value);
+    }
+    let mut substituted = Circuit::new();
+    for (index, operation) in circuit.iter().enumerate() {
+        let substituted_operation = operation.substitute_parameters(&calculator)?;
+        if let Operation::InputSymbolic(x) = &substituted_operation {
+            calculator.set_variable(x.name(), *x.input())
+        }
+        if operation.is_parametrized() {
+            match (
+                shift_rule_angle(operation),
+                with_shift_rule_angle(operation, CalculatorFloat::from(0.0)),
+            ) {
+                (Some(theta), Some(without_angle)) => {
+                    if depends_on_parameters(&without_angle, &calculator, input_parameter_names)? {
+                        return Err(RoqoqoBackendError::GenericError {
+                            msg: format!("Input parameters enter a parameter of {} other than the rotation angle, the parameter-shift rule cannot be applied", operation.hqslang()),
+                        });
+                    }
+                    let derivatives =
+                        affine_derivatives(theta, &calculator, input_parameter_names)?;
+                    if derivatives.iter().any(|d| *d != 0.0) {
+                        let angle = calculator.parse_get(theta.clone())?;
+                        let shifted = |shift: f64| -> Result<Operation, RoqoqoBackendError> {
+                            with_shift_rule_angle(
+                                &substituted_operation,
+                                CalculatorFloat::from(angle + shift),
+                            )
+                            .ok_or_else(|| RoqoqoBackendError::GenericError {
+                                msg: format!("The rotation angle of the substituted operation {} cannot be shifted", substituted_operation.hqslang()),
+                            })
+                        };
+                        angles.push(ShiftedAngle {
+                            circuit: circuit_index,
+                            index,
+                            plus: shifted(FRAC_PI_2)?,
+                            minus: shifted(-FRAC_PI_2)?,
+                            derivatives,
+                        });
+                    }
+                }
+                _ => {
+                    if depends_on_parameters(operation, &calculator, input_parameter_names)? {
+                        return Err(RoqoqoBackendError::GenericError {
+                            msg: format!("Input parameters enter the operation {}, which does not support the parameter-shift rule", operation.hqslang()),
+                        });
+                    }
+                }
+            }
+        }
+        substituted += substituted_operation;
+    }
+    Ok(substituted)
+}
+
+/// Returns true if the substituted operation changes when one of the input parameters changes.
+fn depends_on_parameters(
+    operation: &Operation,
+    calculator: &Calculator,
+    input_parameter_names: &[String],
+) -> Result<bool, RoqoqoBackendError> {
+    let substituted = operation.substitute_parameters(calculator)?;
+    for name in input_parameter_names.iter() {
+        let mut shifted_calculator = calculator.clone();
+        let value = calculator.get_variable(name)?;
+        shifted_calculator.set_variable(name, value + 1.0);
+        if operation.substitute_parameters(&shifted_calculator)? != substituted {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Returns the derivatives of an angle that is an affine function of every input parameter.
+fn affine_derivatives(
+    theta: &CalculatorFloat,
+    calculator: &Calculator,
+    input_parameter_names: &[String],
+) -> Result<Vec<f64>, RoqoqoBackendError> {
+    let value = calculator.parse_get(theta.clone())?;
+    let mut derivatives: Vec<f64> = Vec::with_capacity(input_parameter_names.len());
+    for name in input_parameter_names.iter() {
+        let parameter = calculator.get_variable(name)?;
+        let mut shifted_calculator = calculator.clone();
+        let mut value_at = |shift: f64| -> Result<f64, RoqoqoBackendError> {
+            shifted_calculator.set_variable(name, parameter + shift);
+            Ok(shifted_calculator.parse_get(theta.clone())?)
+        };
+        let derivative = value_at(1.0)? - value;
+        let tolerance = 1e-9 * (1.0 + value.abs() + derivative.abs());
+        if (value - value_at(-1.0)? - derivative).abs() > tolerance
+            || (value_at(0.5)? - value - 0.5 * derivative).abs() > tolerance
+        {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Rotation angle {theta} is not an affine function of the input parameter {name}"
+                ),
+            });
+        }
+        derivatives.push(derivative);
+    }
+    Ok(derivatives)
+}
+
+/// Returns the rotation angle of gates for which the two-term parameter-shift rule is exact.
+///
+/// The rule holds when the generator of the rotation has the two eigenvalues ±1/2 (or 0 and 1).
+fn shift_rule_angle(operation: &Operation) -> Option<&CalculatorFloat> {
+    match operation {
+        Operation::RotateX(op) => Some(op.theta()),
+        Operation::RotateY(op) => Some(op.theta()),
+        Operation::RotateZ(op) => Some(op.theta()),
+        Operation::RotateXY(op) => Some(op.theta()),
+        Operation::RotateAroundSphericalAxis(op) => Some(op.theta()),
+        Operation::PhaseShiftState0(op) => Some(op.theta()),
+        Operation::PhaseShiftState1(op) => Some(op.theta()),
+        Operation::ControlledPhaseShift(op) => Some(op.theta()),
+        Operation::ControlledControlledPhaseShift(op) => Some(op.theta()),
+        Operation::PhaseShiftedControlledPhase(op) => Some(op.theta()),
+        Operation::VariableMSXX(op) => Some(op.theta()),
+        Operation::MultiQubitMS(op) => Some(op.theta()),
+        Operation::MultiQubitZZ(op) => Some(op.theta()),
+        _ => None,
+    }
+}
+
+/// Returns a copy of a gate supported by [shift_rule_angle] with the rotation angle replaced.
+fn with_shift_rule_angle(operation: &Operation, theta: CalculatorFloat) -> Option<Operation> {
+    match operation {
+        Operation::RotateX(op) => Some(RotateX::new(*op.qubit(), theta).into()),
+        Operation::RotateY(op) => Some(RotateY::new(*op.qubit(), theta).into()),
+        Operation::RotateZ(op) => Some(RotateZ::new(*op.qubit(), theta).into()),
+        Operation::RotateXY(op) => Some(RotateXY::new(*op.qubit(), theta, op.phi().clone()).into()),
+        Operation::RotateAroundSphericalAxis(op) => Some(
+            RotateAroundSphericalAxis::new(
+                *op.qubit(),
+                theta,
+                op.spherical_theta().clone(),
+                op.spherical_phi().clone(),
+            )
+            .into(),
+        ),
+        Operation::PhaseShiftState0(op) => Some(PhaseShiftState0::new(*op.qubit(), theta).into()),
+        Operation::PhaseShiftState1(op) => Some(PhaseShiftState1::new(*op.qubit(), theta).into()),
+        Operation::ControlledPhaseShift(op) => {
+            Some(ControlledPhaseShift::new(*op.control(), *op.target(), theta).into())
+        }
+        Operation::ControlledControlledPhaseShift(op) => Some(
+            ControlledControlledPhaseShift::new(
+                *op.control_0(),
+                *op.control_1(),
+                *op.target(),
+                theta,
+            )
+            .into(),
+        ),
+        Operation::PhaseShiftedControlledPhase(op) => Some(
+            PhaseShiftedControlledPhase::new(*op.control(), *op.target(), theta, op.phi().clone())
+                .into(),
+        ),
+        Operation::VariableMSXX(op) => {
+            Some(VariableMSXX::new(*op.control(), *op.target(), theta).into())
+        }
+        Operation::MultiQubitMS(op) => Some(MultiQubitMS::new(op.qubits().clone(), theta).into()),
+        Operation::MultiQubitZZ(op) => Some(MultiQubitZZ::new(op.qubits().clone(), theta).into()),
+        _ => None,
+    }
 }
 
 /// Implements the Display trait for QuantumProgram.
@@ -243,6 +1189,9 @@ impl Display for QuantumProgram {
             QuantumProgram::ClassicalRegister { .. } => {
                 s.push_str("QuantumProgram::ClassicalRegister");
             }
+            QuantumProgram::ClassicalShadow { .. } => {
+                s.push_str("QuantumProgram::ClassicalShadow");
+            }
         }
 
         write!(f, "{s}")
@@ -264,6 +1213,9 @@ impl crate::operations::SupportedVersion for QuantumProgram {
             QuantumProgram::ClassicalRegister { measurement, .. } => {
                 measurement.minimum_supported_roqoqo_version()
             }
+            QuantumProgram::ClassicalShadow { measurement, .. } => {
+                measurement.minimum_supported_roqoqo_version()
+            }
         }
     }
 }
@@ -0,0 +1,382 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::statevector::{apply_matrix, pauli_product_action, sample_basis_states};
+use super::{
+    dense_dimension, gate_qubits, number_qubits, run_operations, ClassicalRegisters, RunCounter,
+    SimulatorState,
+};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{
+    GateOperation, InvolveQubits, InvolvedQubits, Operate, OperateGate, OperatePragmaNoise,
+    Operation, PragmaNoiseOperation,
+};
+use crate::registers::{BitRegister, ComplexRegister, FloatRegister};
+use crate::RoqoqoBackendError;
+
+/// Density-matrix simulator supporting noise PRAGMAs.
+///
+/// Gate operations are applied as ρ → U ρ U† using their [crate::operations::OperateGate::unitary_matrix].
+/// `PragmaDamping`, `PragmaDephasing`, `PragmaDepolarising` and `PragmaGeneralNoise` are applied exactly
+/// using their [crate::operations::OperatePragmaNoise::superoperator].
+/// `PragmaRandomNoise` is applied as its averaged channel, a depolarising channel with the depolarising rate
+/// followed by a dephasing channel with the dephasing rate.
+///
+/// Besides gate operations and noise PRAGMAs the simulator supports classical register definitions,
+/// `MeasureQubit`, `PragmaRepeatedMeasurement`, `PragmaSetNumberOfMeasurements`,
+/// `PragmaGetDensityMatrix`, `PragmaGetOccupationProbability`, `PragmaGetPauliProduct`,
+/// `PragmaActiveReset`, `PragmaSetStateVector`, `PragmaSetDensityMatrix`, `PragmaGlobalPhase`,
+/// `PragmaConditional` and `PragmaLoop`.
+/// Since the simulated state is in general mixed, `PragmaGetStateVector` returns
+/// [RoqoqoBackendError::OperationNotInBackend].
+///
+/// `MeasureQubit` samples an outcome from the mixed state and projects the state onto it,
+/// `PragmaActiveReset` is applied deterministically as the amplitude-damping channel mapping |1> to |0>.
+///
+/// The number of qubits is determined from the highest qubit index used in the circuit.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{DefinitionFloat, PauliX, PragmaDamping, PragmaGetOccupationProbability};
+/// use roqoqo::simulators::DensityMatrixSimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionFloat::new("occupation".to_string(), 1, true);
+/// circuit += PauliX::new(0);
+/// circuit += PragmaDamping::new(0, 1.0.into(), 0.5.into());
+/// circuit += PragmaGetOccupationProbability::new("occupation".to_string(), None);
+///
+/// let backend = DensityMatrixSimulator::new(None);
+/// let (_, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+/// assert!((float_registers["occupation"][0][0] - (-0.5_f64).exp()).abs() < 1e-10);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DensityMatrixSimulator {
+    /// Seed of the random number generator used for measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl DensityMatrixSimulator {
+    /// Creates a new DensityMatrixSimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed of the random number generator used for measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new DensityMatrixSimulator.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for DensityMatrixSimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        let operations: Vec<&Operation> = circuit.collect();
+        let state = DensityMatrixState::new(number_qubits(&operations))?;
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &operations, &mut rng)
+    }
+}
+
+/// Mixed quantum state of a qubit register stored as a dense density matrix.
+///
+/// The density matrix ρ is stored flattened in row-major order.
+/// Element ρ\[i, j\] is stored at index `i * 2^N + j`, so the flattened density matrix can be treated
+/// as a vector of 2N qubits where qubit `q` of the row index is qubit `q + N` of the vector
+/// and qubit `q` of the column index is qubit `q` of the vector.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DensityMatrixState {
+    number_qubits: usize,
+    elements: Vec<Complex64>,
+}
+
+impl DensityMatrixState {
+    /// Creates a new state with all qubits in the zero state.
+    ///
+    /// Returns an error when the density matrix of `number_qubits` qubits can not be allocated.
+    pub(crate) fn new(number_qubits: usize) -> Result<Self, RoqoqoBackendError> {
+        let dimension = dense_dimension(number_qubits, 1)?;
+        let mut elements =
+            vec![Complex64::new(0.0, 0.0); dense_dimension(number_qubits, dimension)?];
+        elements[0] = Complex64::new(1.0, 0.0);
+        Ok(Self {
+            number_qubits,
+            elements,
+        })
+    }
+
+    /// Returns the dimension of the Hilbert space.
+    fn dimension(&self) -> usize {
+        1 << self.number_qubits
+    }
+
+    /// Applies a unitary matrix acting on `qubits` (in descending order of significance) as ρ → U ρ U†.
+    fn apply_unitary(&mut self, matrix: &Array2<Complex64>, qubits: &[usize]) {
+        let row_qubits: Vec<usize> = qubits
+            .iter()
+            .map(|qubit| qubit + self.number_qubits)
+            .collect();
+        apply_matrix(&mut self.elements, matrix, &row_qubits);
+        apply_matrix(
+            &mut self.elements,
+            &matrix.mapv(|value| value.conj()),
+            qubits,
+        );
+    }
+
+    /// Applies a single-qubit superoperator acting on the row-major flattened density matrix of a qubit.
+    fn apply_superoperator(&mut self, superoperator: &Array2<f64>, qubit: usize) {
+        let matrix = superoperator.mapv(|value| Complex64::new(value, 0.0));
+        apply_matrix(
+            &mut self.elements,
+            &matrix,
+            &[qubit + self.number_qubits, qubit],
+        );
+    }
+
+    /// Applies a noise PRAGMA to the state.
+    fn apply_noise(&mut self, noise: &PragmaNoiseOperation) -> Result<(), RoqoqoBackendError> {
+        // All noise PRAGMAs act on a single qubit
+        let qubit = match noise.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.into_iter().next().unwrap_or_default(),
+            _ => 0,
+        };
+        self.check_qubit(qubit)?;
+        match noise {
+            PragmaNoiseOperation::PragmaRandomNoise(op) => {
+                let depolarising = crate::operations::PragmaDepolarising::new(
+                    qubit,
+                    op.gate_time().clone(),
+                    op.depolarising_rate().clone(),
+                );
+                let dephasing = crate::operations::PragmaDephasing::new(
+                    qubit,
+                    op.gate_time().clone(),
+                    op.dephasing_rate().clone(),
+                );
+                self.apply_superoperator(&depolarising.superoperator()?, qubit);
+                self.apply_superoperator(&dephasing.superoperator()?, qubit);
+            }
+            _ => self.apply_superoperator(&noise.superoperator()?, qubit),
+        }
+        Ok(())
+    }
+
+    /// Returns the diagonal of the density matrix.
+    fn diagonal(&self) -> Vec<f64> {
+        let dimension = self.dimension();
+        (0..dimension)
+            .map(|index| self.elements[index * dimension + index].re)
+            .collect()
+    }
+
+    /// Returns the probability to find a qubit in the state |1>.
+    fn excited_probability(&self, qubit: usize) -> f64 {
+        self.diagonal()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| (index >> qubit) & 1 == 1)
+            .map(|(_, probability)| probability)
+            .sum()
+    }
+
+    /// Projects a qubit onto a measurement outcome and renormalises the state.
+    fn project(&mut self, qubit: usize, outcome: bool) {
+        let dimension = self.dimension();
+        let mut trace: f64 = 0.0;
+        for (index, element) in self.elements.iter_mut().enumerate() {
+            let row = index / dimension;
+            let column = index % dimension;
+            if ((row >> qubit) & 1 == 1) != outcome || ((column >> qubit) & 1 == 1) != outcome {
+                *element = Complex64::new(0.0, 0.0);
+            } else if row == column {
+                trace += element.re;
+            }
+        }
+        if trace > 0.0 {
+            for element in self.elements.iter_mut() {
+                *element /= trace;
+            }
+        }
+    }
+
+    /// Checks that a qubit is part of the simulated register.
+    fn check_qubit(&self, qubit: usize) -> Result<(), RoqoqoBackendError> {
+        if qubit >= self.number_qubits {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit {qubit} is not part of the simulated register of {} qubits",
+                    self.number_qubits
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SimulatorState for DensityMatrixState {
+    fn backend_name(&self) -> &'static str {
+        "DensityMatrixSimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        _registers: &mut ClassicalRegisters,
+        _rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        if let Some(qubits) = gate_qubits(operation) {
+            let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+            self.apply_unitary(&matrix, &qubits);
+            return Ok(());
+        }
+        if let Ok(noise) = PragmaNoiseOperation::try_from(operation) {
+            return self.apply_noise(&noise);
+        }
+        match operation {
+            // A global phase has no effect on the density matrix
+            Operation::PragmaGlobalPhase(_) => Ok(()),
+            Operation::PragmaSetStateVector(op) => {
+                if op.statevector().len() != self.dimension() {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!(
+                            "Statevector of length {} does not match the simulated register of {} qubits",
+                            op.statevector().len(),
+                            self.number_qubits
+                        ),
+                    });
+                }
+                self.elements = op
+                    .statevector()
+                    .iter()
+                    .flat_map(|row| {
+                        op.statevector()
+                            .iter()
+                            .map(move |column| row * column.conj())
+                    })
+                    .collect();
+                Ok(())
+            }
+            Operation::PragmaSetDensityMatrix(op) => {
+                let dimension = self.dimension();
+                if op.density_matrix().dim() != (dimension, dimension) {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!(
+                            "Density matrix of shape {:?} does not match the simulated register of {} qubits",
+                            op.density_matrix().dim(),
+                            self.number_qubits
+                        ),
+                    });
+                }
+                self.elements = op.density_matrix().iter().copied().collect();
+                Ok(())
+            }
+            _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: self.backend_name(),
+                hqslang: operation.hqslang(),
+            }),
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        self.check_qubit(qubit)?;
+        let outcome = rng.random::<f64>() < self.excited_probability(qubit);
+        self.project(qubit, outcome);
+        Ok(outcome)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, _rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        self.check_qubit(qubit)?;
+        // Kraus operators |0><0| and |0><1|
+        let dimension = self.dimension();
+        let mask = 1 << qubit;
+        let mut elements = vec![Complex64::new(0.0, 0.0); self.elements.len()];
+        for (index, element) in self.elements.iter().enumerate() {
+            let row = index / dimension;
+            let column = index % dimension;
+            if (row & mask == 0) == (column & mask == 0) {
+                elements[(row & !mask) * dimension + (column & !mask)] += element;
+            }
+        }
+        self.elements = elements;
+        Ok(())
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        Ok(sample_basis_states(
+            &self.diagonal(),
+            self.number_qubits,
+            number_measurements,
+            rng,
+        ))
+    }
+
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Ok(self.elements.clone())
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        Ok((0..self.number_qubits)
+            .map(|qubit| self.excited_probability(qubit))
+            .collect())
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        let (flip_mask, phase_function) = pauli_product_action(qubit_paulis)?;
+        for qubit in qubit_paulis.keys() {
+            self.check_qubit(*qubit)?;
+        }
+        let dimension = self.dimension();
+        // Tr(P ρ) = Σ_i phase(i) ρ[i, i ^ flip_mask]
+        let value: Complex64 = (0..dimension)
+            .map(|index| {
+                phase_function(index) * self.elements[index * dimension + (index ^ flip_mask)]
+            })
+            .sum();
+        Ok(value.re)
+    }
+}
@@ -0,0 +1,646 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::statevector::{apply_matrix, pauli_product_action, sample_basis_states};
+use super::{
+    dense_dimension, gate_qubits, number_qubits, run_operations, ClassicalRegisters, RunCounter,
+    SimulatorState,
+};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{
+    GateOperation, InvolveModes, InvolvedModes, Operate, OperateGate, OperateSingleMode,
+    OperateSingleQubit, OperateTwoMode, Operation, SingleModeOperation, TwoModeOperation,
+};
+use crate::registers::{BitRegister, ComplexRegister, FloatRegister};
+use crate::RoqoqoBackendError;
+
+/// Simulator for circuits of qubits and bosonic modes in a truncated Fock space.
+///
+/// Every bosonic mode is simulated in the space spanned by the Fock states |0>, ..., |cutoff - 1>.
+/// The cutoff can be set for each mode individually, modes without an explicit cutoff use the default cutoff.
+/// Bosonic and spin-boson gates are applied as the matrix exponential of their generator
+/// truncated to this space, so results are only accurate when the occupation of the highest
+/// Fock states stays negligible.
+///
+/// The gates are defined as follows (b is the annihilation operator of a mode, σ^- = |0><1|):
+/// * `Squeezing(r, φ)` - exp(r / 2 * (exp(-iφ) b² - exp(iφ) b^†²))
+/// * `PhaseDisplacement(|α|, φ)` - exp(α b^† - α^* b) with α = |α| exp(iφ)
+/// * `PhaseShift(θ)` - exp(iθ b^† b)
+/// * `BeamSplitter(θ, φ)` - exp(θ (exp(iφ) b_0 b_1^† - exp(-iφ) b_0^† b_1))
+/// * `QuantumRabi(θ)` - exp(-iθ X (b^† + b))
+/// * `LongitudinalCoupling(θ)` - exp(-iθ Z (b^† + b))
+/// * `JaynesCummings(θ)` - exp(-iθ (σ^- b^† + σ^+ b))
+/// * `SingleExcitationStore` and `SingleExcitationLoad` - exchange |1>_Q |0>_B and |0>_Q |1>_B
+/// * `CZQubitResonator` - phase of -1 on |1>_Q |1>_B
+///
+/// `PhotonDetection` samples the photon number of a mode, projects the state onto the result
+/// and writes it to the entry of a float register.
+/// Besides bosonic operations the simulator supports the same qubit operations as the
+/// [crate::simulators::StatevectorSimulator].
+/// The statevector and density matrix returned by the readout PRAGMAs cover qubits and modes,
+/// where the qubits are the least significant part of the index followed by the modes in ascending order.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{DefinitionFloat, PauliX, PhotonDetection, SingleExcitationStore};
+/// use roqoqo::simulators::FockSimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionFloat::new("photons".to_string(), 1, true);
+/// circuit += PauliX::new(0);
+/// circuit += SingleExcitationStore::new(0, 0);
+/// circuit += PhotonDetection::new(0, "photons".to_string(), 0);
+///
+/// let backend = FockSimulator::new(4, None);
+/// let (_, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+/// assert_eq!(float_registers["photons"][0][0], 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FockSimulator {
+    /// Number of Fock states simulated for modes without an explicit cutoff.
+    default_cutoff: usize,
+    /// Number of Fock states simulated for individual modes.
+    cutoffs: HashMap<usize, usize>,
+    /// Seed of the random number generator used for measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl FockSimulator {
+    /// Creates a new FockSimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_cutoff` - The number of Fock states simulated for every mode without an explicit cutoff.
+    /// * `seed` - Seed of the random number generator used for measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new FockSimulator.
+    pub fn new(default_cutoff: usize, seed: Option<u64>) -> Self {
+        Self {
+            default_cutoff,
+            cutoffs: HashMap::new(),
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Sets the number of Fock states simulated for a mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The bosonic mode.
+    /// * `cutoff` - The number of Fock states |0>, ..., |cutoff - 1> simulated for the mode.
+    pub fn set_cutoff(&mut self, mode: usize, cutoff: usize) {
+        self.cutoffs.insert(mode, cutoff);
+    }
+
+    /// Returns the number of Fock states simulated for a mode.
+    pub fn cutoff(&self, mode: usize) -> usize {
+        self.cutoffs
+            .get(&mode)
+            .copied()
+            .unwrap_or(self.default_cutoff)
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for FockSimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        let operations: Vec<&Operation> = circuit.collect();
+        let cutoffs: Vec<usize> = (0..number_modes(&operations))
+            .map(|mode| self.cutoff(mode))
+            .collect();
+        if let Some(mode) = cutoffs.iter().position(|cutoff| *cutoff == 0) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!("The cutoff of mode {mode} must be at least 1"),
+            });
+        }
+        let state = FockState::new(number_qubits(&operations), cutoffs)?;
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &operations, &mut rng)
+    }
+}
+
+/// Returns the number of bosonic modes needed to simulate a sequence of operations.
+fn number_modes(operations: &[&Operation]) -> usize {
+    operations
+        .iter()
+        .map(|operation| operation_number_modes(operation))
+        .max()
+        .unwrap_or_default()
+}
+
+/// Returns the number of bosonic modes needed to simulate a single operation.
+fn operation_number_modes(operation: &Operation) -> usize {
+    let circuit_number_modes = |circuit: &crate::Circuit| -> usize {
+        let operations: Vec<&Operation> = circuit.iter().collect();
+        number_modes(&operations)
+    };
+    let involved_modes = if let Ok(op) = SingleModeOperation::try_from(operation) {
+        op.involved_modes()
+    } else if let Ok(op) = TwoModeOperation::try_from(operation) {
+        op.involved_modes()
+    } else {
+        InvolvedModes::None
+    };
+    match operation {
+        Operation::PragmaGetStateVector(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_modes)
+            .unwrap_or_default(),
+        Operation::PragmaGetDensityMatrix(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_modes)
+            .unwrap_or_default(),
+        Operation::PragmaGetOccupationProbability(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_modes)
+            .unwrap_or_default(),
+        Operation::PragmaGetPauliProduct(op) => circuit_number_modes(op.circuit()),
+        Operation::PragmaConditional(op) => circuit_number_modes(op.circuit()),
+        Operation::PragmaLoop(op) => circuit_number_modes(op.circuit()),
+        Operation::PragmaAnnotatedOp(op) => operation_number_modes(&op.operation),
+        _ => match involved_modes {
+            InvolvedModes::Set(modes) => modes.into_iter().max().map_or(0, |mode| mode + 1),
+            _ => 0,
+        },
+    }
+}
+
+/// Returns the annihilation operator of a mode truncated to `cutoff` Fock states.
+fn annihilation(cutoff: usize) -> DMatrix<Complex64> {
+    DMatrix::from_fn(cutoff, cutoff, |row, column| {
+        if column == row + 1 {
+            Complex64::new((column as f64).sqrt(), 0.0)
+        } else {
+            Complex64::new(0.0, 0.0)
+        }
+    })
+}
+
+/// Returns the Kronecker product of the matrices of a qubit and a mode.
+fn qubit_mode_kron(
+    qubit_matrix: [[f64; 2]; 2],
+    mode_matrix: &DMatrix<Complex64>,
+) -> DMatrix<Complex64> {
+    let qubit_matrix = DMatrix::from_fn(2, 2, |row, column| {
+        Complex64::new(qubit_matrix[row][column], 0.0)
+    });
+    qubit_matrix.kronecker(mode_matrix)
+}
+
+/// Converts a nalgebra matrix into an ndarray matrix.
+fn to_array(matrix: &DMatrix<Complex64>) -> Array2<Complex64> {
+    Array2::from_shape_fn((matrix.nrows(), matrix.ncols()), |(row, column)| {
+        matrix[(row, column)]
+    })
+}
+
+/// A subsystem of the simulated register given by the stride of its index and its dimension.
+type Subsystem = (usize, usize);
+
+/// A local operator together with the subsystems it acts on.
+type LocalGate = (Array2<Complex64>, Vec<Subsystem>);
+
+/// Pure quantum state of qubits and truncated bosonic modes stored as a dense statevector.
+///
+/// Qubit `q` corresponds to bit `q` of the index of a basis state,
+/// mode `m` to the digit with stride 2^N ∏_{m' < m} cutoff_m' for N qubits.
+#[derive(Debug, Clone)]
+struct FockState {
+    number_qubits: usize,
+    cutoffs: Vec<usize>,
+    amplitudes: Vec<Complex64>,
+}
+
+impl FockState {
+    /// Creates a new state with all qubits and modes in the zero state.
+    ///
+    /// Returns an error when the state of the qubits and modes can not be allocated.
+    fn new(number_qubits: usize, cutoffs: Vec<usize>) -> Result<Self, RoqoqoBackendError> {
+        let mode_dimension = cutoffs
+            .iter()
+            .try_fold(1_usize, |product, cutoff| product.checked_mul(*cutoff))
+            .ok_or_else(|| RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The Fock space of {} modes is too large to be simulated",
+                    cutoffs.len()
+                ),
+            })?;
+        let dimension = dense_dimension(number_qubits, mode_dimension)?;
+        let mut amplitudes = vec![Complex64::new(0.0, 0.0); dimension];
+        amplitudes[0] = Complex64::new(1.0, 0.0);
+        Ok(Self {
+            number_qubits,
+            cutoffs,
+            amplitudes,
+        })
+    }
+
+    /// Returns the subsystem of a qubit.
+    fn qubit_subsystem(&self, qubit: usize) -> Result<Subsystem, RoqoqoBackendError> {
+        if qubit >= self.number_qubits {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit {qubit} is not part of the simulated register of {} qubits",
+                    self.number_qubits
+                ),
+            });
+        }
+        Ok((1 << qubit, 2))
+    }
+
+    /// Returns the subsystem of a mode.
+    fn mode_subsystem(&self, mode: usize) -> Result<Subsystem, RoqoqoBackendError> {
+        if mode >= self.cutoffs.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Mode {mode} is not part of the simulated register of {} modes",
+                    self.cutoffs.len()
+                ),
+            });
+        }
+        let stride = (1 << self.number_qubits) * self.cutoffs[..mode].iter().product::<usize>();
+        Ok((stride, self.cutoffs[mode]))
+    }
+
+    /// Applies a matrix acting on a list of subsystems (in descending order of significance) to the state.
+    fn apply_local_matrix(&mut self, matrix: &Array2<Complex64>, subsystems: &[Subsystem]) {
+        let digit = |index: usize, (stride, dimension): Subsystem| (index / stride) % dimension;
+        let local_dimension: usize = subsystems.iter().map(|(_, dimension)| dimension).product();
+        let mut offsets = vec![0; local_dimension];
+        for (local_index, offset) in offsets.iter_mut().enumerate() {
+            let mut remainder = local_index;
+            for (stride, dimension) in subsystems.iter().rev() {
+                *offset += (remainder % dimension) * stride;
+                remainder /= dimension;
+            }
+        }
+        let mut local = vec![Complex64::new(0.0, 0.0); local_dimension];
+        for base in 0..self.amplitudes.len() {
+            if subsystems
+                .iter()
+                .any(|subsystem| digit(base, *subsystem) != 0)
+            {
+                continue;
+            }
+            for (value, offset) in local.iter_mut().zip(offsets.iter()) {
+                *value = self.amplitudes[base + offset];
+            }
+            for (row, offset) in offsets.iter().enumerate() {
+                self.amplitudes[base + offset] = matrix
+                    .row(row)
+                    .iter()
+                    .zip(local.iter())
+                    .map(|(element, value)| element * value)
+                    .sum();
+            }
+        }
+    }
+
+    /// Returns the probability of every photon number of a mode.
+    fn photon_number_probabilities(&self, (stride, dimension): Subsystem) -> Vec<f64> {
+        let mut probabilities = vec![0.0; dimension];
+        for (index, amplitude) in self.amplitudes.iter().enumerate() {
+            probabilities[(index / stride) % dimension] += amplitude.norm_sqr();
+        }
+        probabilities
+    }
+
+    /// Projects a subsystem onto a basis state and renormalises the state.
+    fn project(&mut self, (stride, dimension): Subsystem, outcome: usize) {
+        let mut norm: f64 = 0.0;
+        for (index, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if (index / stride) % dimension != outcome {
+                *amplitude = Complex64::new(0.0, 0.0);
+            } else {
+                norm += amplitude.norm_sqr();
+            }
+        }
+        if norm > 0.0 {
+            let factor = 1.0 / norm.sqrt();
+            for amplitude in self.amplitudes.iter_mut() {
+                *amplitude *= factor;
+            }
+        }
+    }
+
+    /// Applies a bosonic or spin-boson operation.
+    ///
+    /// Returns `Ok(false)` when the operation is not a bosonic operation.
+    fn apply_mode_operation(
+        &mut self,
+        operation: &Operation,
+        registers: &mut ClassicalRegisters,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        if let Operation::PhotonDetection(op) = operation {
+            let subsystem = self.mode_subsystem(*op.mode())?;
+            let probabilities = self.photon_number_probabilities(subsystem);
+            let mut random_value = rng.random::<f64>() * probabilities.iter().sum::<f64>();
+            let mut outcome = probabilities.len() - 1;
+            for (number, probability) in probabilities.iter().enumerate() {
+                if random_value < *probability {
+                    outcome = number;
+                    break;
+                }
+                random_value -= probability;
+            }
+            self.project(subsystem, outcome);
+            registers.set_float(op.readout(), *op.readout_index(), outcome as f64)?;
+            return Ok(true);
+        }
+        match self.mode_gate(operation)? {
+            Some((matrix, subsystems)) => {
+                self.apply_local_matrix(&matrix, &subsystems);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the matrix of a bosonic or spin-boson gate and the subsystems it acts on.
+    ///
+    /// Returns `Ok(None)` when the operation is not a bosonic gate.
+    fn mode_gate(&self, operation: &Operation) -> Result<Option<LocalGate>, RoqoqoBackendError> {
+        let i = Complex64::new(0.0, 1.0);
+        // Single-mode operations acting on a qubit and a mode are ordered as [qubit, mode]
+        let qubit_mode = |state: &Self,
+                          qubit: usize,
+                          mode: usize|
+         -> Result<(Vec<Subsystem>, usize), RoqoqoBackendError> {
+            let mode_subsystem = state.mode_subsystem(mode)?;
+            Ok((
+                vec![state.qubit_subsystem(qubit)?, mode_subsystem],
+                mode_subsystem.1,
+            ))
+        };
+        let gate = match operation {
+            Operation::Squeezing(op) => {
+                let subsystem = self.mode_subsystem(*op.mode())?;
+                let squeezing = f64::try_from(op.squeezing().clone())?;
+                let phase = f64::try_from(op.phase().clone())?;
+                let b = annihilation(subsystem.1);
+                let b_squared = &b * &b;
+                let generator = (b_squared.clone() * Complex64::from_polar(1.0, -phase)
+                    - b_squared.adjoint() * Complex64::from_polar(1.0, phase))
+                    * Complex64::new(squeezing / 2.0, 0.0);
+                (to_array(&generator.exp()), vec![subsystem])
+            }
+            Operation::PhaseDisplacement(op) => {
+                let subsystem = self.mode_subsystem(*op.mode())?;
+                let alpha = Complex64::from_polar(
+                    f64::try_from(op.displacement().clone())?,
+                    f64::try_from(op.phase().clone())?,
+                );
+                let b = annihilation(subsystem.1);
+                let generator = b.adjoint() * alpha - b * alpha.conj();
+                (to_array(&generator.exp()), vec![subsystem])
+            }
+            Operation::PhaseShift(op) => {
+                let subsystem = self.mode_subsystem(*op.mode())?;
+                let phase = f64::try_from(op.phase().clone())?;
+                let matrix = Array2::from_shape_fn((subsystem.1, subsystem.1), |(row, column)| {
+                    if row == column {
+                        Complex64::from_polar(1.0, phase * row as f64)
+                    } else {
+                        Complex64::new(0.0, 0.0)
+                    }
+                });
+                (matrix, vec![subsystem])
+            }
+            Operation::BeamSplitter(op) => {
+                let subsystem_0 = self.mode_subsystem(*op.mode_0())?;
+                let subsystem_1 = self.mode_subsystem(*op.mode_1())?;
+                if op.mode_0() == op.mode_1() {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: "BeamSplitter must act on two different modes".to_string(),
+                    });
+                }
+                let theta = f64::try_from(op.theta().clone())?;
+                let phi = f64::try_from(op.phi().clone())?;
+                let b_0 = annihilation(subsystem_0.1)
+                    .kronecker(&DMatrix::identity(subsystem_1.1, subsystem_1.1));
+                let b_1 = DMatrix::<Complex64>::identity(subsystem_0.1, subsystem_0.1)
+                    .kronecker(&annihilation(subsystem_1.1));
+                let generator = ((&b_0 * b_1.adjoint()) * Complex64::from_polar(1.0, phi)
+                    - (b_0.adjoint() * &b_1) * Complex64::from_polar(1.0, -phi))
+                    * Complex64::new(theta, 0.0);
+                (to_array(&generator.exp()), vec![subsystem_0, subsystem_1])
+            }
+            Operation::QuantumRabi(op) => {
+                let (subsystems, cutoff) = qubit_mode(self, *op.qubit(), *op.mode())?;
+                let theta = f64::try_from(op.theta().clone())?;
+                let b = annihilation(cutoff);
+                let generator =
+                    qubit_mode_kron([[0.0, 1.0], [1.0, 0.0]], &(b.adjoint() + b)) * (-i * theta);
+                (to_array(&generator.exp()), subsystems)
+            }
+            Operation::LongitudinalCoupling(op) => {
+                let (subsystems, cutoff) = qubit_mode(self, *op.qubit(), *op.mode())?;
+                let theta = f64::try_from(op.theta().clone())?;
+                let b = annihilation(cutoff);
+                let generator =
+                    qubit_mode_kron([[1.0, 0.0], [0.0, -1.0]], &(b.adjoint() + b)) * (-i * theta);
+                (to_array(&generator.exp()), subsystems)
+            }
+            Operation::JaynesCummings(op) => {
+                let (subsystems, cutoff) = qubit_mode(self, *op.qubit(), *op.mode())?;
+                let theta = f64::try_from(op.theta().clone())?;
+                let b = annihilation(cutoff);
+                let generator = (qubit_mode_kron([[0.0, 1.0], [0.0, 0.0]], &b.adjoint())
+                    + qubit_mode_kron([[0.0, 0.0], [1.0, 0.0]], &b))
+                    * (-i * theta);
+                (to_array(&generator.exp()), subsystems)
+            }
+            Operation::SingleExcitationStore(_) | Operation::SingleExcitationLoad(_) => {
+                let (qubit, mode) = match operation {
+                    Operation::SingleExcitationStore(op) => (*op.qubit(), *op.mode()),
+                    Operation::SingleExcitationLoad(op) => (*op.qubit(), *op.mode()),
+                    _ => unreachable!(),
+                };
+                let (subsystems, cutoff) = qubit_mode(self, qubit, mode)?;
+                if cutoff < 2 {
+                    return Err(cutoff_error(operation, mode));
+                }
+                // Exchanges |1>_Q |0>_B (local index cutoff) and |0>_Q |1>_B (local index 1)
+                let mut matrix = Array2::<Complex64>::eye(2 * cutoff);
+                matrix[(1, 1)] = Complex64::new(0.0, 0.0);
+                matrix[(cutoff, cutoff)] = Complex64::new(0.0, 0.0);
+                matrix[(1, cutoff)] = Complex64::new(1.0, 0.0);
+                matrix[(cutoff, 1)] = Complex64::new(1.0, 0.0);
+                (matrix, subsystems)
+            }
+            Operation::CZQubitResonator(op) => {
+                let (subsystems, cutoff) = qubit_mode(self, *op.qubit(), *op.mode())?;
+                let mut matrix = Array2::<Complex64>::eye(2 * cutoff);
+                if cutoff >= 2 {
+                    matrix[(cutoff + 1, cutoff + 1)] = Complex64::new(-1.0, 0.0);
+                }
+                (matrix, subsystems)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(gate))
+    }
+}
+
+/// Creates the error returned when the cutoff of a mode is too small for an operation.
+fn cutoff_error(operation: &Operation, mode: usize) -> RoqoqoBackendError {
+    RoqoqoBackendError::GenericError {
+        msg: format!(
+            "{} requires a cutoff of at least 2 for mode {mode}",
+            operation.hqslang()
+        ),
+    }
+}
+
+impl SimulatorState for FockState {
+    fn backend_name(&self) -> &'static str {
+        "FockSimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        registers: &mut ClassicalRegisters,
+        rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        if self.apply_mode_operation(operation, registers, rng)? {
+            return Ok(());
+        }
+        if let Some(qubits) = gate_qubits(operation) {
+            for qubit in qubits.iter() {
+                self.qubit_subsystem(*qubit)?;
+            }
+            let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+            // Qubits are the least significant digits of the index
+            apply_matrix(&mut self.amplitudes, &matrix, &qubits);
+            return Ok(());
+        }
+        match operation {
+            Operation::PragmaGlobalPhase(op) => {
+                let phase = Complex64::from_polar(1.0, f64::try_from(op.phase().clone())?);
+                for amplitude in self.amplitudes.iter_mut() {
+                    *amplitude *= phase;
+                }
+                Ok(())
+            }
+            _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: self.backend_name(),
+                hqslang: operation.hqslang(),
+            }),
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        let subsystem = self.qubit_subsystem(qubit)?;
+        let outcome = rng.random::<f64>() < self.photon_number_probabilities(subsystem)[1];
+        self.project(subsystem, usize::from(outcome));
+        Ok(outcome)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        if self.measure_qubit(qubit, rng)? {
+            for index in (0..self.amplitudes.len()).filter(|index| (index >> qubit) & 1 == 0) {
+                self.amplitudes.swap(index, index | (1 << qubit));
+            }
+        }
+        Ok(())
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        let probabilities: Vec<f64> = self
+            .amplitudes
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect();
+        Ok(sample_basis_states(
+            &probabilities,
+            self.number_qubits,
+            number_measurements,
+            rng,
+        ))
+    }
+
+    fn statevector(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Ok(self.amplitudes.clone())
+    }
+
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Ok(self
+            .amplitudes
+            .iter()
+            .flat_map(|row| {
+                self.amplitudes
+                    .iter()
+                    .map(move |column| row * column.conj())
+            })
+            .collect())
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        Ok((0..self.number_qubits)
+            .map(|qubit| self.photon_number_probabilities((1 << qubit, 2))[1])
+            .collect())
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        for qubit in qubit_paulis.keys() {
+            self.qubit_subsystem(*qubit)?;
+        }
+        let (flip_mask, phase_function) = pauli_product_action(qubit_paulis)?;
+        let value: Complex64 = self
+            .amplitudes
+            .iter()
+            .enumerate()
+            .map(|(index, amplitude)| {
+                self.amplitudes[index ^ flip_mask].conj() * phase_function(index) * amplitude
+            })
+            .sum();
+        Ok(value.re)
+    }
+}
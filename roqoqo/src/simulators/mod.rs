@@ -0,0 +1,621 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reference simulators implementing the [crate::backends::EvaluatingBackend] trait.
+//!
+//! The simulators in this module are pure-Rust reference implementations intended for testing
+//! measurement pipelines and small-scale simulations without an external simulator backend.
+//! They are not optimised for performance.
+//!
+//! All simulators use the same qubit ordering convention as the rest of roqoqo:
+//! qubit `i` corresponds to bit `i` of the index of a basis state (qubit 0 is the least significant bit).
+//!
+//! Every run of a simulator uses its own random number generator. For a seeded simulator the generator
+//! is derived from the seed and the number of previous runs, so a sequence of runs is reproducible.
+//!
+//! Available simulators:
+//! * [StatevectorSimulator] - Noise-free statevector simulation of qubit circuits.
+//! * [DensityMatrixSimulator] - Exact density-matrix simulation of qubit circuits including noise PRAGMAs.
+//! * [FockSimulator] - Simulation of qubits and bosonic modes in a truncated Fock space.
+//! * [MatrixProductStateSimulator] - Matrix product state simulation of qubit chains with bond-dimension control.
+//! * [QuantumTrajectorySimulator] - Stochastic unravelling of noise PRAGMAs on a statevector.
+//! * [StabilizerSimulator] - Stabilizer tableau simulation of large Clifford circuits.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub(crate) use crate::operations::{gate_qubits, subspace_indices};
+use crate::operations::{Define, InvolveQubits, InvolvedQubits, OperateSingleQubit, Operation};
+use crate::registers::{
+    BitOutputRegister, BitRegister, ComplexOutputRegister, ComplexRegister, FloatOutputRegister,
+    FloatRegister, Registers,
+};
+use crate::{RoqoqoBackendError, RoqoqoError};
+
+mod density_matrix;
+pub use density_matrix::DensityMatrixSimulator;
+mod fock;
+pub use fock::FockSimulator;
+mod mps;
+pub use mps::MatrixProductStateSimulator;
+mod stabilizer;
+pub use stabilizer::StabilizerSimulator;
+mod statevector;
+pub use statevector::StatevectorSimulator;
+mod trajectory;
+pub use trajectory::QuantumTrajectorySimulator;
+
+/// Internal trait for the quantum state evolved by a simulator.
+///
+/// The classical control flow (definitions, conditionals, loops, repeated measurements and
+/// readout PRAGMAs) is handled generically by [run_operations].
+/// Implementors only need to provide the quantum mechanical part of the simulation.
+pub(crate) trait SimulatorState: Clone {
+    /// Returns the name of the backend used in error messages.
+    fn backend_name(&self) -> &'static str;
+
+    /// Applies an Operation that is not handled by the generic simulation loop to the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The Operation that is applied.
+    /// * `registers` - The classical registers of the current run.
+    /// * `rng` - The random number generator of the current run.
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        registers: &mut ClassicalRegisters,
+        rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError>;
+
+    /// Projectively measures a single qubit in the Z-basis and returns the outcome.
+    fn measure_qubit(&mut self, qubit: usize, rng: &mut StdRng)
+        -> Result<bool, RoqoqoBackendError>;
+
+    /// Resets a single qubit to the zero state.
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError>;
+
+    /// Samples `number_measurements` Z-basis measurements of all qubits without changing the state.
+    ///
+    /// Each returned row contains the outcome for every qubit of the state, ordered by qubit index.
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError>;
+
+    /// Returns the statevector of the state.
+    fn statevector(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: self.backend_name(),
+            hqslang: "PragmaGetStateVector",
+        })
+    }
+
+    /// Returns the flattened (row-major) density matrix of the state.
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: self.backend_name(),
+            hqslang: "PragmaGetDensityMatrix",
+        })
+    }
+
+    /// Returns the probability to find each qubit in the excited state |1>.
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: self.backend_name(),
+            hqslang: "PragmaGetOccupationProbability",
+        })
+    }
+
+    /// Returns the expectation value of a Pauli product.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit_paulis` - The Pauli operator acting on each qubit (`0` = identity, `1` = PauliX, `2` = PauliY, `3` = PauliZ).
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        let _ = qubit_paulis;
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: self.backend_name(),
+            hqslang: "PragmaGetPauliProduct",
+        })
+    }
+
+    /// Writes simulator specific information about a finished run to the classical registers.
+    ///
+    /// Called once at the end of every run, the default implementation does not write anything.
+    fn record_run_information(
+        &self,
+        registers: &mut ClassicalRegisters,
+    ) -> Result<(), RoqoqoBackendError> {
+        let _ = registers;
+        Ok(())
+    }
+}
+
+/// Classical registers of a single simulated run of a circuit.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClassicalRegisters {
+    bit_registers: HashMap<String, BitRegister>,
+    float_registers: HashMap<String, FloatRegister>,
+    complex_registers: HashMap<String, ComplexRegister>,
+    repeated_bit_registers: HashMap<String, BitOutputRegister>,
+    bit_outputs: HashSet<String>,
+    float_outputs: HashSet<String>,
+    complex_outputs: HashSet<String>,
+}
+
+impl ClassicalRegisters {
+    /// Defines a new bit register initialised with `false`.
+    pub(crate) fn define_bit(&mut self, name: &str, length: usize, is_output: bool) {
+        self.bit_registers
+            .insert(name.to_string(), vec![false; length]);
+        if is_output {
+            self.bit_outputs.insert(name.to_string());
+        }
+    }
+
+    /// Defines a new float register initialised with `0.0`.
+    pub(crate) fn define_float(&mut self, name: &str, length: usize, is_output: bool) {
+        self.float_registers
+            .insert(name.to_string(), vec![0.0; length]);
+        if is_output {
+            self.float_outputs.insert(name.to_string());
+        }
+    }
+
+    /// Defines a new complex register initialised with `0.0`.
+    pub(crate) fn define_complex(&mut self, name: &str, length: usize, is_output: bool) {
+        self.complex_registers
+            .insert(name.to_string(), vec![Complex64::new(0.0, 0.0); length]);
+        if is_output {
+            self.complex_outputs.insert(name.to_string());
+        }
+    }
+
+    /// Returns the value of an entry in a bit register.
+    pub(crate) fn bit(&self, name: &str, index: usize) -> Result<bool, RoqoqoBackendError> {
+        let register =
+            self.bit_registers
+                .get(name)
+                .ok_or_else(|| RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                })?;
+        register
+            .get(index)
+            .copied()
+            .ok_or_else(|| index_error(name, index, register.len()))
+    }
+
+    /// Returns the length of a bit register.
+    pub(crate) fn bit_length(&self, name: &str) -> Result<usize, RoqoqoBackendError> {
+        self.bit_registers
+            .get(name)
+            .map(|register| register.len())
+            .ok_or_else(|| {
+                RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// Sets an entry in a bit register.
+    pub(crate) fn set_bit(
+        &mut self,
+        name: &str,
+        index: usize,
+        value: bool,
+    ) -> Result<(), RoqoqoBackendError> {
+        let register =
+            self.bit_registers
+                .get_mut(name)
+                .ok_or_else(|| RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                })?;
+        let length = register.len();
+        let entry = register
+            .get_mut(index)
+            .ok_or_else(|| index_error(name, index, length))?;
+        *entry = value;
+        Ok(())
+    }
+
+    /// Sets an entry in a float register.
+    pub(crate) fn set_float(
+        &mut self,
+        name: &str,
+        index: usize,
+        value: f64,
+    ) -> Result<(), RoqoqoBackendError> {
+        let register =
+            self.float_registers
+                .get_mut(name)
+                .ok_or_else(|| RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                })?;
+        let length = register.len();
+        let entry = register
+            .get_mut(index)
+            .ok_or_else(|| index_error(name, index, length))?;
+        *entry = value;
+        Ok(())
+    }
+
+    /// Replaces the full content of a previously defined float register.
+    pub(crate) fn replace_float(
+        &mut self,
+        name: &str,
+        values: FloatRegister,
+    ) -> Result<(), RoqoqoBackendError> {
+        let register =
+            self.float_registers
+                .get_mut(name)
+                .ok_or_else(|| RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                })?;
+        *register = values;
+        Ok(())
+    }
+
+    /// Replaces the full content of a previously defined complex register.
+    pub(crate) fn replace_complex(
+        &mut self,
+        name: &str,
+        values: ComplexRegister,
+    ) -> Result<(), RoqoqoBackendError> {
+        let register =
+            self.complex_registers
+                .get_mut(name)
+                .ok_or_else(|| RoqoqoError::MissingRegister {
+                    name: name.to_string(),
+                })?;
+        *register = values;
+        Ok(())
+    }
+
+    /// Adds the records of a repeated measurement to a previously defined bit register.
+    pub(crate) fn add_repeated_bits(
+        &mut self,
+        name: &str,
+        mut records: BitOutputRegister,
+    ) -> Result<(), RoqoqoBackendError> {
+        if !self.bit_registers.contains_key(name) {
+            return Err(RoqoqoError::MissingRegister {
+                name: name.to_string(),
+            }
+            .into());
+        }
+        self.repeated_bit_registers
+            .entry(name.to_string())
+            .or_default()
+            .append(&mut records);
+        Ok(())
+    }
+
+    /// Appends the output registers of this run to the combined output registers of all runs.
+    ///
+    /// Bit registers written by a repeated measurement contribute all recorded measurements,
+    /// all other output registers contribute their final value.
+    pub(crate) fn append_to_output(mut self, output: &mut Registers) {
+        for name in self.bit_outputs.iter() {
+            let records = match self.repeated_bit_registers.remove(name) {
+                Some(records) => records,
+                None => vec![self.bit_registers.remove(name).unwrap_or_default()],
+            };
+            output.0.entry(name.clone()).or_default().extend(records);
+        }
+        for name in self.float_outputs.iter() {
+            let register = self.float_registers.remove(name).unwrap_or_default();
+            output.1.entry(name.clone()).or_default().push(register);
+        }
+        for name in self.complex_outputs.iter() {
+            let register = self.complex_registers.remove(name).unwrap_or_default();
+            output.2.entry(name.clone()).or_default().push(register);
+        }
+    }
+}
+
+/// Creates the error returned when accessing an entry outside of a classical register.
+fn index_error(name: &str, index: usize, length: usize) -> RoqoqoBackendError {
+    RoqoqoBackendError::GenericError {
+        msg: format!("Index {index} is out of range for register {name} of length {length}"),
+    }
+}
+
+/// Creates the random number generator for a simulation run.
+///
+/// When a seed is given the generator is seeded deterministically, otherwise it is seeded from system entropy.
+pub(crate) fn create_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => rand::make_rng(),
+    }
+}
+
+/// Counts the runs of a simulator so that every run uses its own random number generator.
+///
+/// Clones continue counting from the run of the original.
+/// The counter is not part of the state of a simulator and is ignored when comparing simulators.
+#[derive(Debug, Default)]
+pub(crate) struct RunCounter(AtomicU64);
+
+impl RunCounter {
+    /// Creates the random number generator for the next run of a simulator.
+    ///
+    /// With a seed, the generator of the n-th run is seeded with the seed mixed with n,
+    /// so that a sequence of runs is reproducible without repeating the same random numbers.
+    pub(crate) fn next_rng(&self, seed: Option<u64>) -> StdRng {
+        let run = self.0.fetch_add(1, Ordering::Relaxed);
+        create_rng(seed.map(|seed| seed ^ run.wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+    }
+}
+
+impl Clone for RunCounter {
+    fn clone(&self) -> Self {
+        Self(AtomicU64::new(self.0.load(Ordering::Relaxed)))
+    }
+}
+
+impl PartialEq for RunCounter {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for RunCounter {}
+
+/// Returns the number of amplitudes of a dense state of `number_qubits` qubits and `factor` further basis states.
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The number of amplitudes.
+/// * `Err(RoqoqoBackendError::GenericError)` - The state can not be allocated.
+pub(crate) fn dense_dimension(
+    number_qubits: usize,
+    factor: usize,
+) -> Result<usize, RoqoqoBackendError> {
+    u32::try_from(number_qubits)
+        .ok()
+        .and_then(|number_qubits| 2_usize.checked_pow(number_qubits))
+        .and_then(|dimension| dimension.checked_mul(factor))
+        .filter(|dimension| {
+            dimension
+                .checked_mul(std::mem::size_of::<Complex64>())
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or_else(|| RoqoqoBackendError::GenericError {
+            msg: format!("A dense state of {number_qubits} qubits is too large to be simulated"),
+        })
+}
+
+/// Returns the number of qubits needed to simulate a sequence of operations.
+pub(crate) fn number_qubits(operations: &[&Operation]) -> usize {
+    operations
+        .iter()
+        .map(|operation| operation_number_qubits(operation))
+        .max()
+        .unwrap_or_default()
+}
+
+/// Returns the number of qubits needed to simulate a single operation.
+fn operation_number_qubits(operation: &Operation) -> usize {
+    let circuit_number_qubits = |circuit: &crate::Circuit| -> usize {
+        let operations: Vec<&Operation> = circuit.iter().collect();
+        number_qubits(&operations)
+    };
+    match operation {
+        Operation::PragmaSetStateVector(op) => op.statevector().len().max(1).ilog2() as usize,
+        Operation::PragmaSetDensityMatrix(op) => {
+            op.density_matrix().nrows().max(1).ilog2() as usize
+        }
+        Operation::PragmaGetStateVector(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_qubits)
+            .unwrap_or_default(),
+        Operation::PragmaGetDensityMatrix(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_qubits)
+            .unwrap_or_default(),
+        Operation::PragmaGetOccupationProbability(op) => op
+            .circuit()
+            .as_ref()
+            .map(circuit_number_qubits)
+            .unwrap_or_default(),
+        Operation::PragmaGetPauliProduct(op) => op
+            .qubit_paulis()
+            .keys()
+            .map(|qubit| qubit + 1)
+            .chain(std::iter::once(circuit_number_qubits(op.circuit())))
+            .max()
+            .unwrap_or_default(),
+        Operation::PragmaRepeatedMeasurement(op) => op
+            .qubit_mapping()
+            .as_ref()
+            .and_then(|mapping| mapping.keys().max().map(|qubit| qubit + 1))
+            .unwrap_or_default(),
+        Operation::PragmaConditional(op) => circuit_number_qubits(op.circuit()),
+        Operation::PragmaLoop(op) => circuit_number_qubits(op.circuit()),
+        Operation::PragmaAnnotatedOp(op) => operation_number_qubits(&op.operation),
+        _ => match operation.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.into_iter().max().map_or(0, |qubit| qubit + 1),
+            _ => 0,
+        },
+    }
+}
+
+/// Runs a sequence of operations on copies of an initial state and collects the output registers.
+///
+/// When the operations contain a [crate::operations::PragmaSetNumberOfMeasurements],
+/// the full sequence is executed the corresponding number of times and the output registers of
+/// all runs are concatenated.
+///
+/// # Arguments
+///
+/// * `initial_state` - The state every run starts from.
+/// * `operations` - The operations that are executed.
+/// * `rng` - The random number generator used for all stochastic parts of the simulation.
+pub(crate) fn run_operations<S: SimulatorState>(
+    initial_state: &S,
+    operations: &[&Operation],
+    rng: &mut StdRng,
+) -> Result<Registers, RoqoqoBackendError> {
+    let repetitions = operations
+        .iter()
+        .filter_map(|operation| match operation {
+            Operation::PragmaSetNumberOfMeasurements(op) => Some(*op.number_measurements()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(1);
+    let mut output: Registers = (
+        HashMap::<String, BitOutputRegister>::new(),
+        HashMap::<String, FloatOutputRegister>::new(),
+        HashMap::<String, ComplexOutputRegister>::new(),
+    );
+    for _ in 0..repetitions {
+        let mut state = initial_state.clone();
+        let mut registers = ClassicalRegisters::default();
+        for operation in operations.iter() {
+            execute_operation(&mut state, operation, &mut registers, rng)?;
+        }
+        state.record_run_information(&mut registers)?;
+        registers.append_to_output(&mut output);
+    }
+    Ok(output)
+}
+
+/// Executes a single operation on a state, handling the classical control flow of the circuit.
+pub(crate) fn execute_operation<S: SimulatorState>(
+    state: &mut S,
+    operation: &Operation,
+    registers: &mut ClassicalRegisters,
+    rng: &mut StdRng,
+) -> Result<(), RoqoqoBackendError> {
+    match operation {
+        Operation::DefinitionBit(op) => {
+            registers.define_bit(op.name(), *op.length(), *op.is_output());
+        }
+        Operation::DefinitionFloat(op) => {
+            registers.define_float(op.name(), *op.length(), *op.is_output());
+        }
+        Operation::DefinitionComplex(op) => {
+            registers.define_complex(op.name(), *op.length(), *op.is_output());
+        }
+        Operation::InputBit(op) => {
+            registers.set_bit(op.name(), *op.index(), *op.value())?;
+        }
+        Operation::MeasureQubit(op) => {
+            let result = state.measure_qubit(*op.qubit(), rng)?;
+            registers.set_bit(op.readout(), *op.readout_index(), result)?;
+        }
+        Operation::PragmaRepeatedMeasurement(op) => {
+            let length = registers.bit_length(op.readout())?;
+            let samples = state.sample(*op.number_measurements(), rng)?;
+            let records: BitOutputRegister = samples
+                .into_iter()
+                .map(|sample| {
+                    let mut record = vec![false; length];
+                    match op.qubit_mapping() {
+                        Some(mapping) => {
+                            for (qubit, index) in mapping.iter() {
+                                if *index < length {
+                                    record[*index] = sample.get(*qubit).copied().unwrap_or(false);
+                                }
+                            }
+                        }
+                        None => {
+                            for (index, value) in sample.into_iter().take(length).enumerate() {
+                                record[index] = value;
+                            }
+                        }
+                    }
+                    record
+                })
+                .collect();
+            registers.add_repeated_bits(op.readout(), records)?;
+        }
+        Operation::PragmaActiveReset(op) => {
+            state.reset_qubit(*op.qubit(), rng)?;
+        }
+        Operation::PragmaGetStateVector(op) => {
+            let prepared = prepare_state(state, op.circuit().as_ref(), registers, rng)?;
+            registers.replace_complex(op.readout(), prepared.statevector()?)?;
+        }
+        Operation::PragmaGetDensityMatrix(op) => {
+            let prepared = prepare_state(state, op.circuit().as_ref(), registers, rng)?;
+            registers.replace_complex(op.readout(), prepared.density_matrix()?)?;
+        }
+        Operation::PragmaGetOccupationProbability(op) => {
+            let prepared = prepare_state(state, op.circuit().as_ref(), registers, rng)?;
+            registers.replace_float(op.readout(), prepared.occupation_probabilities()?)?;
+        }
+        Operation::PragmaGetPauliProduct(op) => {
+            let prepared = prepare_state(state, Some(op.circuit()), registers, rng)?;
+            let value = prepared.pauli_product(op.qubit_paulis())?;
+            registers.replace_float(op.readout(), vec![value])?;
+        }
+        Operation::PragmaConditional(op) => {
+            if registers.bit(op.condition_register(), *op.condition_index())? {
+                for inner in op.circuit().iter() {
+                    execute_operation(state, inner, registers, rng)?;
+                }
+            }
+        }
+        Operation::PragmaLoop(op) => {
+            let repetitions = f64::try_from(op.repetitions().clone())?.floor().max(0.0) as usize;
+            for _ in 0..repetitions {
+                for inner in op.circuit().iter() {
+                    execute_operation(state, inner, registers, rng)?;
+                }
+            }
+        }
+        Operation::PragmaAnnotatedOp(op) => {
+            execute_operation(state, &op.operation, registers, rng)?;
+        }
+        // Operations that only carry information for compilation or hardware execution
+        Operation::PragmaSetNumberOfMeasurements(_)
+        | Operation::PragmaSleep(_)
+        | Operation::PragmaStopParallelBlock(_)
+        | Operation::PragmaStartDecompositionBlock(_)
+        | Operation::PragmaStopDecompositionBlock(_)
+        | Operation::InputSymbolic(_) => (),
+        _ => state.apply_operation(operation, registers, rng)?,
+    }
+    Ok(())
+}
+
+/// Returns a copy of the state with an optional preparation circuit applied.
+fn prepare_state<S: SimulatorState>(
+    state: &S,
+    circuit: Option<&crate::Circuit>,
+    registers: &ClassicalRegisters,
+    rng: &mut StdRng,
+) -> Result<S, RoqoqoBackendError> {
+    let mut prepared = state.clone();
+    if let Some(circuit) = circuit {
+        let mut scratch_registers = registers.clone();
+        for operation in circuit.iter() {
+            execute_operation(&mut prepared, operation, &mut scratch_registers, rng)?;
+        }
+    }
+    Ok(prepared)
+}
@@ -0,0 +1,704 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use nalgebra::{DMatrix, DVector};
+use ndarray::{Array2, Array3};
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::{
+    gate_qubits, number_qubits, run_operations, ClassicalRegisters, RunCounter, SimulatorState,
+};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{GateOperation, Operate, OperateGate, Operation};
+use crate::registers::{BitRegister, ComplexRegister, FloatRegister};
+use crate::RoqoqoBackendError;
+
+/// Relative weight of singular values that is always discarded as numerical noise.
+const NEGLIGIBLE_WEIGHT: f64 = 1e-28;
+
+/// Largest number of qubits for which the statevector or density matrix of the state is returned.
+const MAXIMUM_DENSE_QUBITS: usize = 24;
+
+/// Matrix product state (MPS) simulator for qubit chains.
+///
+/// The state is stored as a chain of tensors, one for every qubit in ascending order of the qubit index,
+/// connected by bonds whose dimension grows with the entanglement between the two halves of the chain.
+/// Single-qubit gates are applied locally, two-qubit gates are applied to neighbouring qubits with a
+/// singular value decomposition of the updated pair of tensors.
+/// For gates on qubits that are not neighbours in the chain, SWAP gates are inserted to move the
+/// qubits next to each other before the gate and back to their original position afterwards.
+/// Gates acting on more than two qubits return [RoqoqoBackendError::OperationNotInBackend].
+///
+/// After every decomposition the smallest singular values are discarded as long as their combined
+/// relative weight stays below the truncation threshold, and at most `max_bond_dimension` singular
+/// values are kept.
+/// The truncation error of a run is the sum of the discarded relative weights of all decompositions,
+/// an estimate of the infidelity of the final state.
+/// At the end of every run the truncation error and the largest bond dimension that occurred are
+/// written to the float output register [MatrixProductStateSimulator::TRUNCATION_REGISTER].
+///
+/// Besides gate operations the simulator supports classical register definitions,
+/// `MeasureQubit`, `PragmaRepeatedMeasurement`, `PragmaSetNumberOfMeasurements`,
+/// `PragmaGetStateVector`, `PragmaGetDensityMatrix`, `PragmaGetOccupationProbability`,
+/// `PragmaGetPauliProduct`, `PragmaActiveReset`, `PragmaSetStateVector`, `PragmaGlobalPhase`,
+/// `PragmaConditional` and `PragmaLoop`.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{CNOT, DefinitionBit, Hadamard, PragmaRepeatedMeasurement};
+/// use roqoqo::simulators::MatrixProductStateSimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionBit::new("ro".to_string(), 100, true);
+/// circuit += Hadamard::new(0);
+/// for qubit in 0..99 {
+///     circuit += CNOT::new(qubit, qubit + 1);
+/// }
+/// circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+///
+/// let backend = MatrixProductStateSimulator::new(Some(16), 1e-10, Some(42));
+/// let (bit_registers, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+/// for record in bit_registers["ro"].iter() {
+///     assert!(record.iter().all(|value| *value == record[0]));
+/// }
+/// let truncation = &float_registers[MatrixProductStateSimulator::TRUNCATION_REGISTER][0];
+/// assert!(truncation[0] < 1e-10);
+/// assert_eq!(truncation[1], 2.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MatrixProductStateSimulator {
+    /// Largest bond dimension kept after a decomposition (None for no limit).
+    max_bond_dimension: Option<usize>,
+    /// Largest relative weight of singular values discarded in a single decomposition.
+    truncation_threshold: f64,
+    /// Seed of the random number generator used for measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl MatrixProductStateSimulator {
+    /// Name of the float output register containing the truncation error and the largest bond dimension of each run.
+    pub const TRUNCATION_REGISTER: &'static str = "mps_truncation";
+
+    /// Creates a new MatrixProductStateSimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bond_dimension` - The largest bond dimension kept after a decomposition. When `None` the bond dimension is not limited.
+    /// * `truncation_threshold` - The largest relative weight of singular values discarded in a single decomposition.
+    /// * `seed` - Seed of the random number generator used for measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new MatrixProductStateSimulator.
+    pub fn new(
+        max_bond_dimension: Option<usize>,
+        truncation_threshold: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            max_bond_dimension,
+            truncation_threshold,
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Returns the largest bond dimension kept after a decomposition.
+    pub fn max_bond_dimension(&self) -> Option<usize> {
+        self.max_bond_dimension
+    }
+
+    /// Returns the largest relative weight of singular values discarded in a single decomposition.
+    pub fn truncation_threshold(&self) -> f64 {
+        self.truncation_threshold
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for MatrixProductStateSimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        if self.max_bond_dimension == Some(0) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: "The maximum bond dimension of the MatrixProductStateSimulator must be at least 1".to_string(),
+            });
+        }
+        if !(0.0..1.0).contains(&self.truncation_threshold) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The truncation threshold {} of the MatrixProductStateSimulator is not in [0, 1)",
+                    self.truncation_threshold
+                ),
+            });
+        }
+        let operations: Vec<&Operation> = circuit.collect();
+        let state = MatrixProductState::new(
+            number_qubits(&operations),
+            self.max_bond_dimension,
+            self.truncation_threshold,
+        );
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &operations, &mut rng)
+    }
+}
+
+/// Pure quantum state of a qubit chain stored as a matrix product state.
+///
+/// Every tensor has the shape (left bond, 2, right bond).
+/// All tensors left of the orthogonality center are left-orthonormal and all tensors right of it
+/// are right-orthonormal, so the norm of the state is the norm of the center tensor.
+#[derive(Debug, Clone)]
+struct MatrixProductState {
+    tensors: Vec<Array3<Complex64>>,
+    center: usize,
+    max_bond_dimension: Option<usize>,
+    truncation_threshold: f64,
+    truncation_error: f64,
+    largest_bond_dimension: usize,
+}
+
+impl MatrixProductState {
+    /// Creates a new state with all qubits in the zero state.
+    fn new(
+        number_qubits: usize,
+        max_bond_dimension: Option<usize>,
+        truncation_threshold: f64,
+    ) -> Self {
+        let mut zero = Array3::<Complex64>::zeros((1, 2, 1));
+        zero[(0, 0, 0)] = Complex64::new(1.0, 0.0);
+        Self {
+            tensors: vec![zero; number_qubits.max(1)],
+            center: 0,
+            max_bond_dimension,
+            truncation_threshold,
+            truncation_error: 0.0,
+            largest_bond_dimension: 1,
+        }
+    }
+
+    /// Checks that a qubit is part of the simulated chain.
+    fn check_qubit(&self, qubit: usize) -> Result<(), RoqoqoBackendError> {
+        if qubit >= self.tensors.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit {qubit} is not part of the simulated register of {} qubits",
+                    self.tensors.len()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves the orthogonality center to a site using QR decompositions.
+    fn move_center(&mut self, site: usize) {
+        while self.center < site {
+            let index = self.center;
+            let left_bond = self.tensors[index].shape()[0];
+            let qr = left_matrix(&self.tensors[index]).qr();
+            self.tensors[index] = from_left_matrix(&qr.q(), left_bond);
+            let right_bond = self.tensors[index + 1].shape()[2];
+            let next = qr.r() * right_matrix(&self.tensors[index + 1]);
+            self.tensors[index + 1] = from_right_matrix(&next, right_bond);
+            self.center += 1;
+        }
+        while self.center > site {
+            let index = self.center;
+            let right_bond = self.tensors[index].shape()[2];
+            // LQ decomposition from the QR decomposition of the adjoint matrix
+            let qr = right_matrix(&self.tensors[index]).adjoint().qr();
+            self.tensors[index] = from_right_matrix(&qr.q().adjoint(), right_bond);
+            let left_bond = self.tensors[index - 1].shape()[0];
+            let previous = left_matrix(&self.tensors[index - 1]) * qr.r().adjoint();
+            self.tensors[index - 1] = from_left_matrix(&previous, left_bond);
+            self.center -= 1;
+        }
+    }
+
+    /// Applies a single-qubit matrix to the tensor of a qubit.
+    fn apply_single_qubit_matrix(&mut self, matrix: &Array2<Complex64>, qubit: usize) {
+        let tensor = &self.tensors[qubit];
+        self.tensors[qubit] = Array3::from_shape_fn(tensor.raw_dim(), |(left, physical, right)| {
+            matrix[(physical, 0)] * tensor[(left, 0, right)]
+                + matrix[(physical, 1)] * tensor[(left, 1, right)]
+        });
+    }
+
+    /// Applies a two-qubit matrix to two qubits (in descending order of significance), inserting SWAP gates when they are not neighbours.
+    fn apply_two_qubit_matrix(
+        &mut self,
+        matrix: &Array2<Complex64>,
+        qubit_0: usize,
+        qubit_1: usize,
+    ) -> Result<(), RoqoqoBackendError> {
+        let (first, second, matrix) = if qubit_0 < qubit_1 {
+            (qubit_0, qubit_1, matrix.clone())
+        } else {
+            (qubit_1, qubit_0, swap_qubit_order(matrix))
+        };
+        let swap = swap_matrix();
+        for site in first..second - 1 {
+            self.apply_neighbour_matrix(&swap, site)?;
+        }
+        self.apply_neighbour_matrix(&matrix, second - 1)?;
+        for site in (first..second - 1).rev() {
+            self.apply_neighbour_matrix(&swap, site)?;
+        }
+        Ok(())
+    }
+
+    /// Applies a two-qubit matrix to the neighbouring sites `site` and `site + 1`, where `site` is the most significant qubit.
+    fn apply_neighbour_matrix(
+        &mut self,
+        matrix: &Array2<Complex64>,
+        site: usize,
+    ) -> Result<(), RoqoqoBackendError> {
+        self.move_center(site);
+        let left_bond = self.tensors[site].shape()[0];
+        let right_bond = self.tensors[site + 1].shape()[2];
+        // Rows are indexed by (left bond, qubit site), columns by (qubit site + 1, right bond)
+        let pair = left_matrix(&self.tensors[site]) * right_matrix(&self.tensors[site + 1]);
+        let updated = DMatrix::from_fn(2 * left_bond, 2 * right_bond, |row, column| {
+            let (left, physical_0) = (row / 2, row % 2);
+            let (physical_1, right) = (column / right_bond, column % right_bond);
+            (0..4)
+                .map(|local| {
+                    matrix[(2 * physical_0 + physical_1, local)]
+                        * pair[(2 * left + local / 2, (local % 2) * right_bond + right)]
+                })
+                .sum()
+        });
+        let (left, right) = self.truncated_decomposition(updated)?;
+        self.tensors[site] = from_left_matrix(&left, left_bond);
+        self.tensors[site + 1] = from_right_matrix(&right, right_bond);
+        self.center = site + 1;
+        Ok(())
+    }
+
+    /// Splits a matrix into a left-orthonormal factor and a remainder using a truncated singular value decomposition.
+    ///
+    /// The discarded weight is added to the truncation error and the remainder is rescaled to
+    /// conserve the norm of the matrix.
+    fn truncated_decomposition(
+        &mut self,
+        matrix: DMatrix<Complex64>,
+    ) -> Result<(DMatrix<Complex64>, DMatrix<Complex64>), RoqoqoBackendError> {
+        let svd = matrix.try_svd(true, true, f64::EPSILON, 0).ok_or_else(|| {
+            RoqoqoBackendError::GenericError {
+                msg: "Singular value decomposition did not converge".to_string(),
+            }
+        })?;
+        let (Some(u), Some(v_t)) = (svd.u, svd.v_t) else {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: "Singular value decomposition did not return singular vectors".to_string(),
+            });
+        };
+        let singular_values = svd.singular_values;
+        let weights: Vec<f64> = singular_values.iter().map(|value| value * value).collect();
+        let total_weight: f64 = weights.iter().sum();
+        let threshold = self.truncation_threshold.max(NEGLIGIBLE_WEIGHT) * total_weight;
+        // Singular values are sorted in descending order, discard from the end
+        let mut kept = weights.len();
+        let mut discarded_weight = 0.0;
+        while kept > 1 && discarded_weight + weights[kept - 1] <= threshold {
+            discarded_weight += weights[kept - 1];
+            kept -= 1;
+        }
+        if let Some(max_bond_dimension) = self.max_bond_dimension {
+            while kept > max_bond_dimension {
+                discarded_weight += weights[kept - 1];
+                kept -= 1;
+            }
+        }
+        if total_weight > 0.0 {
+            self.truncation_error += discarded_weight / total_weight;
+        }
+        self.largest_bond_dimension = self.largest_bond_dimension.max(kept);
+        let kept_weight = total_weight - discarded_weight;
+        let scale = if kept_weight > 0.0 {
+            (total_weight / kept_weight).sqrt()
+        } else {
+            1.0
+        };
+        let scaled_values: DVector<Complex64> = singular_values
+            .rows(0, kept)
+            .map(|value| Complex64::new(value * scale, 0.0));
+        let left = u.columns(0, kept).into_owned();
+        let right = DMatrix::from_diagonal(&scaled_values) * v_t.rows(0, kept);
+        Ok((left, right))
+    }
+
+    /// Returns the probability to find the qubit at the orthogonality center in the state |1>.
+    fn center_excited_probability(&self) -> f64 {
+        self.tensors[self.center]
+            .index_axis(ndarray::Axis(1), 1)
+            .iter()
+            .map(|value| value.norm_sqr())
+            .sum()
+    }
+
+    /// Projects a qubit onto a measurement outcome and renormalises the state.
+    fn project(&mut self, qubit: usize, outcome: bool) {
+        self.move_center(qubit);
+        let probability = if outcome {
+            self.center_excited_probability()
+        } else {
+            1.0 - self.center_excited_probability()
+        };
+        let factor = if probability > 0.0 {
+            1.0 / probability.sqrt()
+        } else {
+            1.0
+        };
+        let physical = usize::from(outcome);
+        for ((_, index, _), value) in self.tensors[qubit].indexed_iter_mut() {
+            if index == physical {
+                *value *= factor;
+            } else {
+                *value = Complex64::new(0.0, 0.0);
+            }
+        }
+    }
+
+    /// Replaces the state with the matrix product state of a statevector.
+    fn set_statevector(&mut self, statevector: &[Complex64]) -> Result<(), RoqoqoBackendError> {
+        let number_qubits = self.tensors.len();
+        let dimension = u32::try_from(number_qubits)
+            .ok()
+            .and_then(|number_qubits| 1_usize.checked_shl(number_qubits));
+        if dimension != Some(statevector.len()) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Statevector of length {} does not match the simulated register of {} qubits",
+                    statevector.len(),
+                    number_qubits
+                ),
+            });
+        }
+        // Columns of the remainder are indexed by the state of the remaining qubits, lowest qubit first
+        let mut remainder = DMatrix::from_row_slice(1, statevector.len(), statevector);
+        for site in 0..number_qubits - 1 {
+            let left_bond = remainder.nrows();
+            let remaining = remainder.ncols() / 2;
+            let reshaped = DMatrix::from_fn(2 * left_bond, remaining, |row, column| {
+                remainder[(row / 2, row % 2 + 2 * column)]
+            });
+            let (left, right) = self.truncated_decomposition(reshaped)?;
+            self.tensors[site] = from_left_matrix(&left, left_bond);
+            remainder = right;
+        }
+        self.tensors[number_qubits - 1] = from_right_matrix(&remainder, 1);
+        self.center = number_qubits - 1;
+        Ok(())
+    }
+
+    /// Returns the dense statevector of the state.
+    fn dense_statevector(&self) -> Result<Vec<Complex64>, RoqoqoBackendError> {
+        if self.tensors.len() > MAXIMUM_DENSE_QUBITS {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The dense state of {} qubits is too large to be returned by the MatrixProductStateSimulator",
+                    self.tensors.len()
+                ),
+            });
+        }
+        // Rows are indexed by the basis states of the contracted qubits, columns by the open bond
+        let mut contracted = DMatrix::from_element(1, 1, Complex64::new(1.0, 0.0));
+        for tensor in self.tensors.iter() {
+            let number_states = contracted.nrows();
+            let mut next = DMatrix::zeros(2 * number_states, tensor.shape()[2]);
+            for physical in 0..2 {
+                let slice = slice_matrix(tensor, physical);
+                let product = &contracted * slice;
+                next.rows_mut(physical * number_states, number_states)
+                    .copy_from(&product);
+            }
+            contracted = next;
+        }
+        Ok(contracted.column(0).iter().copied().collect())
+    }
+
+    /// Returns the expectation value of a product of single-qubit operators.
+    fn expectation_value(&self, operators: &HashMap<usize, Array2<Complex64>>) -> Complex64 {
+        let mut environment = DMatrix::from_element(1, 1, Complex64::new(1.0, 0.0));
+        for (site, tensor) in self.tensors.iter().enumerate() {
+            let slices = [slice_matrix(tensor, 0), slice_matrix(tensor, 1)];
+            let right_bond = tensor.shape()[2];
+            let mut next = DMatrix::zeros(right_bond, right_bond);
+            for (bra, bra_slice) in slices.iter().enumerate() {
+                for (ket, ket_slice) in slices.iter().enumerate() {
+                    let element = match operators.get(&site) {
+                        Some(operator) => operator[(bra, ket)],
+                        None if bra == ket => Complex64::new(1.0, 0.0),
+                        None => continue,
+                    };
+                    if element != Complex64::new(0.0, 0.0) {
+                        next += bra_slice.adjoint() * &environment * ket_slice * element;
+                    }
+                }
+            }
+            environment = next;
+        }
+        environment[(0, 0)]
+    }
+}
+
+impl SimulatorState for MatrixProductState {
+    fn backend_name(&self) -> &'static str {
+        "MatrixProductStateSimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        _registers: &mut ClassicalRegisters,
+        _rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        if let Some(qubits) = gate_qubits(operation) {
+            for qubit in qubits.iter() {
+                self.check_qubit(*qubit)?;
+            }
+            return match qubits.as_slice() {
+                [qubit] => {
+                    let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+                    self.apply_single_qubit_matrix(&matrix, *qubit);
+                    Ok(())
+                }
+                [qubit_0, qubit_1] => {
+                    let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+                    self.apply_two_qubit_matrix(&matrix, *qubit_0, *qubit_1)
+                }
+                _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                    backend: self.backend_name(),
+                    hqslang: operation.hqslang(),
+                }),
+            };
+        }
+        match operation {
+            Operation::PragmaGlobalPhase(op) => {
+                let phase = Complex64::from_polar(1.0, f64::try_from(op.phase().clone())?);
+                self.tensors[self.center].mapv_inplace(|value| value * phase);
+                Ok(())
+            }
+            Operation::PragmaSetStateVector(op) => {
+                let statevector: Vec<Complex64> = op.statevector().to_vec();
+                self.set_statevector(&statevector)
+            }
+            _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: self.backend_name(),
+                hqslang: operation.hqslang(),
+            }),
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        self.check_qubit(qubit)?;
+        self.move_center(qubit);
+        let outcome = rng.random::<f64>() < self.center_excited_probability();
+        self.project(qubit, outcome);
+        Ok(outcome)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        if self.measure_qubit(qubit, rng)? {
+            let tensor = &mut self.tensors[qubit];
+            let (mut zero, mut one) =
+                tensor.multi_slice_mut((ndarray::s![.., 0..1, ..], ndarray::s![.., 1..2, ..]));
+            ndarray::Zip::from(&mut zero)
+                .and(&mut one)
+                .for_each(std::mem::swap);
+        }
+        Ok(())
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        // With the center at the first site the marginal distribution of every qubit given the
+        // outcomes of the previous qubits only depends on the tensors up to that qubit
+        let mut canonical = self.clone();
+        canonical.move_center(0);
+        let slices: Vec<[DMatrix<Complex64>; 2]> = canonical
+            .tensors
+            .iter()
+            .map(|tensor| [slice_matrix(tensor, 0), slice_matrix(tensor, 1)])
+            .collect();
+        Ok((0..number_measurements)
+            .map(|_| {
+                let mut environment = DMatrix::from_element(1, 1, Complex64::new(1.0, 0.0));
+                slices
+                    .iter()
+                    .map(|[zero, one]| {
+                        let zero_branch = &environment * zero;
+                        let one_branch = &environment * one;
+                        let zero_weight = zero_branch.norm_squared();
+                        let one_weight = one_branch.norm_squared();
+                        let outcome = rng.random::<f64>() * (zero_weight + one_weight) < one_weight;
+                        let (branch, weight) = if outcome {
+                            (one_branch, one_weight)
+                        } else {
+                            (zero_branch, zero_weight)
+                        };
+                        environment = if weight > 0.0 {
+                            branch / Complex64::new(weight.sqrt(), 0.0)
+                        } else {
+                            branch
+                        };
+                        outcome
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn statevector(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        self.dense_statevector()
+    }
+
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        let amplitudes = self.dense_statevector()?;
+        Ok(amplitudes
+            .iter()
+            .flat_map(|row| amplitudes.iter().map(move |column| row * column.conj()))
+            .collect())
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        let mut canonical = self.clone();
+        canonical.move_center(0);
+        Ok((0..canonical.tensors.len())
+            .map(|qubit| {
+                canonical.move_center(qubit);
+                canonical.center_excited_probability()
+            })
+            .collect())
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        let mut operators: HashMap<usize, Array2<Complex64>> = HashMap::new();
+        for (qubit, pauli) in qubit_paulis.iter() {
+            self.check_qubit(*qubit)?;
+            let (zero, one, i) = (
+                Complex64::new(0.0, 0.0),
+                Complex64::new(1.0, 0.0),
+                Complex64::new(0.0, 1.0),
+            );
+            let operator = match pauli {
+                0 => continue,
+                1 => [[zero, one], [one, zero]],
+                2 => [[zero, -i], [i, zero]],
+                3 => [[one, zero], [zero, -one]],
+                _ => {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!("Pauli operator {pauli} on qubit {qubit} is not in 0..=3"),
+                    })
+                }
+            };
+            operators.insert(*qubit, Array2::from(operator.to_vec()));
+        }
+        Ok(self.expectation_value(&operators).re)
+    }
+
+    fn record_run_information(
+        &self,
+        registers: &mut ClassicalRegisters,
+    ) -> Result<(), RoqoqoBackendError> {
+        let name = MatrixProductStateSimulator::TRUNCATION_REGISTER;
+        registers.define_float(name, 2, true);
+        registers.replace_float(
+            name,
+            vec![self.truncation_error, self.largest_bond_dimension as f64],
+        )
+    }
+}
+
+/// Returns the matrix of a tensor with rows indexed by (left bond, qubit) and columns by the right bond.
+fn left_matrix(tensor: &Array3<Complex64>) -> DMatrix<Complex64> {
+    let shape = tensor.shape();
+    DMatrix::from_fn(2 * shape[0], shape[2], |row, column| {
+        tensor[(row / 2, row % 2, column)]
+    })
+}
+
+/// Returns the matrix of a tensor with rows indexed by the left bond and columns by (qubit, right bond).
+fn right_matrix(tensor: &Array3<Complex64>) -> DMatrix<Complex64> {
+    let shape = tensor.shape();
+    let right_bond = shape[2];
+    DMatrix::from_fn(shape[0], 2 * right_bond, |row, column| {
+        tensor[(row, column / right_bond, column % right_bond)]
+    })
+}
+
+/// Creates a tensor from a matrix with rows indexed by (left bond, qubit).
+fn from_left_matrix(matrix: &DMatrix<Complex64>, left_bond: usize) -> Array3<Complex64> {
+    Array3::from_shape_fn((left_bond, 2, matrix.ncols()), |(left, physical, right)| {
+        matrix[(2 * left + physical, right)]
+    })
+}
+
+/// Creates a tensor from a matrix with columns indexed by (qubit, right bond).
+fn from_right_matrix(matrix: &DMatrix<Complex64>, right_bond: usize) -> Array3<Complex64> {
+    Array3::from_shape_fn(
+        (matrix.nrows(), 2, right_bond),
+        |(left, physical, right)| matrix[(left, physical * right_bond + right)],
+    )
+}
+
+/// Returns the matrix of a tensor for a fixed state of its qubit.
+fn slice_matrix(tensor: &Array3<Complex64>, physical: usize) -> DMatrix<Complex64> {
+    let shape = tensor.shape();
+    DMatrix::from_fn(shape[0], shape[2], |left, right| {
+        tensor[(left, physical, right)]
+    })
+}
+
+/// Returns the matrix of a two-qubit gate with the order of significance of the two qubits exchanged.
+fn swap_qubit_order(matrix: &Array2<Complex64>) -> Array2<Complex64> {
+    let exchange = |index: usize| 2 * (index % 2) + index / 2;
+    Array2::from_shape_fn((4, 4), |(row, column)| {
+        matrix[(exchange(row), exchange(column))]
+    })
+}
+
+/// Returns the matrix of a SWAP gate.
+fn swap_matrix() -> Array2<Complex64> {
+    let exchange = |index: usize| 2 * (index % 2) + index / 2;
+    Array2::from_shape_fn((4, 4), |(row, column)| {
+        if exchange(row) == column {
+            Complex64::new(1.0, 0.0)
+        } else {
+            Complex64::new(0.0, 0.0)
+        }
+    })
+}
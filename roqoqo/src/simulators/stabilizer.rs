@@ -0,0 +1,563 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::{
+    gate_qubits, number_qubits, run_operations, ClassicalRegisters, RunCounter, SimulatorState,
+};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{GateOperation, Operate, OperateGate, Operation};
+use crate::registers::{BitRegister, FloatRegister};
+use crate::{RoqoqoBackendError, RoqoqoError};
+
+/// Largest number of qubits of a gate that is checked for being a Clifford gate.
+const MAXIMUM_CLIFFORD_GATE_QUBITS: usize = 3;
+
+/// Numerical tolerance used when identifying the Pauli conjugation table of a gate.
+const CLIFFORD_TOLERANCE: f64 = 1e-9;
+
+/// Stabilizer simulator for Clifford circuits based on a stabilizer tableau.
+///
+/// The simulator stores the stabilizer and destabilizer generators of the state
+/// (Aaronson and Gottesman, Phys. Rev. A 70, 052328 (2004)), so the memory scales
+/// quadratically and gates scale linearly with the number of qubits.
+/// This allows the simulation of Clifford circuits with thousands of qubits.
+///
+/// A gate operation acting on up to three qubits is accepted when it is a Clifford gate,
+/// that is when its unitary matrix maps every Pauli operator to a Pauli operator.
+/// This includes gates like `Hadamard`, `SGate`, `CNOT`, `ISwap` or `SqrtPauliX` as well as
+/// rotation gates with angles that are multiples of π/2.
+/// All other gates, including gates with symbolic parameters, return [RoqoqoBackendError::OperationNotInBackend].
+///
+/// Besides Clifford gates the simulator supports classical register definitions,
+/// `MeasureQubit`, `PragmaRepeatedMeasurement`, `PragmaSetNumberOfMeasurements`,
+/// `PragmaGetOccupationProbability`, `PragmaGetPauliProduct`, `PragmaActiveReset`,
+/// `PragmaGlobalPhase`, `PragmaConditional` and `PragmaLoop`.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{CNOT, DefinitionBit, Hadamard, PragmaRepeatedMeasurement};
+/// use roqoqo::simulators::StabilizerSimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionBit::new("ro".to_string(), 1000, true);
+/// circuit += Hadamard::new(0);
+/// for qubit in 0..999 {
+///     circuit += CNOT::new(qubit, qubit + 1);
+/// }
+/// circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+///
+/// let backend = StabilizerSimulator::new(Some(42));
+/// let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+/// for record in bit_registers["ro"].iter() {
+///     assert!(record.iter().all(|value| *value == record[0]));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StabilizerSimulator {
+    /// Seed of the random number generator used for measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl StabilizerSimulator {
+    /// Creates a new StabilizerSimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed of the random number generator used for measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new StabilizerSimulator.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for StabilizerSimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        let operations: Vec<&Operation> = circuit.collect();
+        let state = StabilizerState::new(number_qubits(&operations));
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &operations, &mut rng)
+    }
+}
+
+/// A Pauli operator on a few qubits in the form i^phase ∏ X^x Z^z.
+///
+/// Bit `i` of `x` and `z` corresponds to position `i` in the list of qubits of a gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LocalPauli {
+    x: usize,
+    z: usize,
+    phase: u8,
+}
+
+impl LocalPauli {
+    /// Returns the product self * other.
+    fn multiply(&self, other: &LocalPauli) -> LocalPauli {
+        // Z^z1 X^x2 = (-1)^(z1 · x2) X^x2 Z^z1
+        let sign = 2 * ((self.z & other.x).count_ones() % 2) as u8;
+        LocalPauli {
+            x: self.x ^ other.x,
+            z: self.z ^ other.z,
+            phase: (self.phase + other.phase + sign) % 4,
+        }
+    }
+
+    /// Returns the matrix of the Pauli operator where position 0 is the most significant qubit.
+    fn matrix(&self, number_positions: usize) -> Array2<Complex64> {
+        let dimension = 1 << number_positions;
+        let mut matrix = Array2::<Complex64>::zeros((dimension, dimension));
+        let phase = Complex64::new(0.0, 1.0).powu(self.phase as u32);
+        // Bit i of the matrix index belongs to position number_positions - 1 - i
+        let position_mask = |mask: usize| -> usize {
+            (0..number_positions)
+                .filter(|position| (mask >> position) & 1 == 1)
+                .map(|position| 1 << (number_positions - 1 - position))
+                .sum()
+        };
+        let x_mask = position_mask(self.x);
+        let z_mask = position_mask(self.z);
+        for column in 0..dimension {
+            // X^x Z^z |column> = (-1)^(z · column) |column ^ x>
+            let sign = if (column & z_mask).count_ones() % 2 == 1 {
+                -1.0
+            } else {
+                1.0
+            };
+            matrix[(column ^ x_mask, column)] = phase * sign;
+        }
+        matrix
+    }
+}
+
+/// Returns the images U X_i U† and U Z_i U† of the Pauli generators of every gate qubit.
+///
+/// Returns `None` when the unitary is not a Clifford gate.
+/// The images are ordered X_0, Z_0, X_1, Z_1, ... by position of the qubit in the gate.
+fn clifford_images(
+    unitary: &Array2<Complex64>,
+    number_positions: usize,
+) -> Option<Vec<LocalPauli>> {
+    let dimension = 1 << number_positions;
+    let adjoint = unitary.t().mapv(|value| value.conj());
+    let mut images = Vec::with_capacity(2 * number_positions);
+    for position in 0..number_positions {
+        for generator in [
+            LocalPauli {
+                x: 1 << position,
+                z: 0,
+                phase: 0,
+            },
+            LocalPauli {
+                x: 0,
+                z: 1 << position,
+                phase: 0,
+            },
+        ] {
+            let conjugated = unitary
+                .dot(&generator.matrix(number_positions))
+                .dot(&adjoint);
+            let image = (0..dimension * dimension).find_map(|mask| {
+                let candidate = LocalPauli {
+                    x: mask % dimension,
+                    z: mask / dimension,
+                    phase: 0,
+                };
+                // The Pauli matrices are orthogonal with respect to Tr(P† M) / 2^N
+                let overlap: Complex64 = candidate
+                    .matrix(number_positions)
+                    .iter()
+                    .zip(conjugated.iter())
+                    .map(|(pauli, value)| pauli.conj() * value)
+                    .sum::<Complex64>()
+                    / dimension as f64;
+                if (overlap.norm() - 1.0).abs() > CLIFFORD_TOLERANCE {
+                    return None;
+                }
+                let phase = (overlap.arg() / std::f64::consts::FRAC_PI_2).round();
+                let expected = Complex64::new(0.0, 1.0).powf(phase);
+                if (overlap - expected).norm() > CLIFFORD_TOLERANCE {
+                    return None;
+                }
+                Some(LocalPauli {
+                    phase: phase.rem_euclid(4.0) as u8,
+                    ..candidate
+                })
+            })?;
+            images.push(image);
+        }
+    }
+    Some(images)
+}
+
+/// A Pauli operator on the full register in the form i^phase ∏ X^x Z^z stored as bit vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PauliRow {
+    x: Vec<u64>,
+    z: Vec<u64>,
+    phase: u8,
+}
+
+impl PauliRow {
+    /// Creates the identity on `number_qubits` qubits.
+    fn identity(number_qubits: usize) -> Self {
+        let number_words = number_qubits.div_ceil(64);
+        Self {
+            x: vec![0; number_words],
+            z: vec![0; number_words],
+            phase: 0,
+        }
+    }
+
+    /// Returns the X bit of a qubit.
+    fn x(&self, qubit: usize) -> bool {
+        (self.x[qubit / 64] >> (qubit % 64)) & 1 == 1
+    }
+
+    /// Returns the Z bit of a qubit.
+    fn z(&self, qubit: usize) -> bool {
+        (self.z[qubit / 64] >> (qubit % 64)) & 1 == 1
+    }
+
+    /// Sets the X and Z bits of a qubit.
+    fn set(&mut self, qubit: usize, x: bool, z: bool) {
+        let bit = 1 << (qubit % 64);
+        if x {
+            self.x[qubit / 64] |= bit;
+        } else {
+            self.x[qubit / 64] &= !bit;
+        }
+        if z {
+            self.z[qubit / 64] |= bit;
+        } else {
+            self.z[qubit / 64] &= !bit;
+        }
+    }
+
+    /// Multiplies the row from the right with another row.
+    fn multiply_right(&mut self, other: &PauliRow) {
+        // Z^z1 X^x2 = (-1)^(z1 · x2) X^x2 Z^z1
+        let overlap: u32 = self
+            .z
+            .iter()
+            .zip(other.x.iter())
+            .map(|(z, x)| (z & x).count_ones())
+            .sum();
+        self.phase = (self.phase + other.phase + 2 * (overlap % 2) as u8) % 4;
+        for (x, other_x) in self.x.iter_mut().zip(other.x.iter()) {
+            *x ^= other_x;
+        }
+        for (z, other_z) in self.z.iter_mut().zip(other.z.iter()) {
+            *z ^= other_z;
+        }
+    }
+
+    /// Returns true when the row anticommutes with another row.
+    fn anticommutes(&self, other: &PauliRow) -> bool {
+        let count: u32 = self
+            .x
+            .iter()
+            .zip(other.z.iter())
+            .chain(self.z.iter().zip(other.x.iter()))
+            .map(|(a, b)| (a & b).count_ones())
+            .sum();
+        count % 2 == 1
+    }
+
+    /// Applies a Clifford gate acting on `qubits` given by the images of its Pauli generators.
+    fn apply_clifford(&mut self, qubits: &[usize], images: &[LocalPauli]) {
+        if qubits
+            .iter()
+            .all(|qubit| !self.x(*qubit) && !self.z(*qubit))
+        {
+            return;
+        }
+        let mut image = LocalPauli {
+            x: 0,
+            z: 0,
+            phase: 0,
+        };
+        // Operators on different qubits commute, so the local part can be conjugated separately
+        for (position, qubit) in qubits.iter().enumerate() {
+            if self.x(*qubit) {
+                image = image.multiply(&images[2 * position]);
+            }
+            if self.z(*qubit) {
+                image = image.multiply(&images[2 * position + 1]);
+            }
+        }
+        for (position, qubit) in qubits.iter().enumerate() {
+            self.set(
+                *qubit,
+                (image.x >> position) & 1 == 1,
+                (image.z >> position) & 1 == 1,
+            );
+        }
+        self.phase = (self.phase + image.phase) % 4;
+    }
+}
+
+/// Stabilizer state stored as a tableau of destabilizer and stabilizer generators.
+#[derive(Debug, Clone, PartialEq)]
+struct StabilizerState {
+    number_qubits: usize,
+    /// The destabilizers (rows 0..N) followed by the stabilizers (rows N..2N).
+    rows: Vec<PauliRow>,
+}
+
+impl StabilizerState {
+    /// Creates a new state with all qubits in the zero state.
+    fn new(number_qubits: usize) -> Self {
+        let mut rows = vec![PauliRow::identity(number_qubits); 2 * number_qubits];
+        for qubit in 0..number_qubits {
+            rows[qubit].set(qubit, true, false);
+            rows[qubit + number_qubits].set(qubit, false, true);
+        }
+        Self {
+            number_qubits,
+            rows,
+        }
+    }
+
+    /// Returns the product of the stabilizers that reproduces a Pauli operator commuting with all stabilizers.
+    ///
+    /// The Pauli operator is in the stabilizer group up to its sign when the returned product
+    /// has the same X and Z bits.
+    fn stabilizer_product(&self, pauli: &PauliRow) -> PauliRow {
+        let mut product = PauliRow::identity(self.number_qubits);
+        for index in 0..self.number_qubits {
+            if self.rows[index].anticommutes(pauli) {
+                product.multiply_right(&self.rows[index + self.number_qubits]);
+            }
+        }
+        product
+    }
+
+    /// Returns the sign of the Pauli Z operator of a qubit that commutes with all stabilizers.
+    ///
+    /// The destabilizers anticommuting with Z are the ones with an X on the qubit.
+    fn pauli_z_sign(&self, qubit: usize) -> bool {
+        let mut product = PauliRow::identity(self.number_qubits);
+        for index in (0..self.number_qubits).filter(|index| self.rows[*index].x(qubit)) {
+            product.multiply_right(&self.rows[index + self.number_qubits]);
+        }
+        product.phase == 2
+    }
+
+    /// Returns the outcome of measuring a qubit when it is deterministic.
+    fn deterministic_outcome(&self, qubit: usize) -> Option<bool> {
+        if (self.number_qubits..2 * self.number_qubits).any(|index| self.rows[index].x(qubit)) {
+            return None;
+        }
+        Some(self.pauli_z_sign(qubit))
+    }
+
+    /// Checks that a qubit is part of the simulated register.
+    fn check_qubit(&self, qubit: usize) -> Result<(), RoqoqoBackendError> {
+        if qubit >= self.number_qubits {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit {qubit} is not part of the simulated register of {} qubits",
+                    self.number_qubits
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SimulatorState for StabilizerState {
+    fn backend_name(&self) -> &'static str {
+        "StabilizerSimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        _registers: &mut ClassicalRegisters,
+        _rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        let not_in_backend = RoqoqoBackendError::OperationNotInBackend {
+            backend: self.backend_name(),
+            hqslang: operation.hqslang(),
+        };
+        if let Some(qubits) = gate_qubits(operation) {
+            if qubits.len() > MAXIMUM_CLIFFORD_GATE_QUBITS {
+                return Err(not_in_backend);
+            }
+            for qubit in qubits.iter() {
+                self.check_qubit(*qubit)?;
+            }
+            // Gates with symbolic parameters can not be identified as Clifford gates
+            let matrix = match GateOperation::try_from(operation)?.unitary_matrix() {
+                Err(RoqoqoError::CalculatorError(_)) => return Err(not_in_backend),
+                matrix => matrix?,
+            };
+            let images = clifford_images(&matrix, qubits.len()).ok_or(not_in_backend)?;
+            for row in self.rows.iter_mut() {
+                row.apply_clifford(&qubits, &images);
+            }
+            return Ok(());
+        }
+        match operation {
+            // A global phase has no effect on the stabilizers
+            Operation::PragmaGlobalPhase(_) => Ok(()),
+            _ => Err(not_in_backend),
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        self.check_qubit(qubit)?;
+        let number_qubits = self.number_qubits;
+        let anticommuting =
+            (number_qubits..2 * number_qubits).find(|index| self.rows[*index].x(qubit));
+        let Some(pivot) = anticommuting else {
+            return Ok(self.pauli_z_sign(qubit));
+        };
+        let outcome = rng.random::<bool>();
+        let pivot_row = self.rows[pivot].clone();
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            if index != pivot && row.x(qubit) {
+                row.multiply_right(&pivot_row);
+            }
+        }
+        self.rows[pivot - number_qubits] = pivot_row;
+        let mut measured = PauliRow::identity(number_qubits);
+        measured.set(qubit, false, true);
+        measured.phase = if outcome { 2 } else { 0 };
+        self.rows[pivot] = measured;
+        Ok(outcome)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        if self.measure_qubit(qubit, rng)? {
+            // Conjugation with PauliX flips the sign of every row with a Z on the qubit
+            for row in self.rows.iter_mut() {
+                if row.z(qubit) {
+                    row.phase = (row.phase + 2) % 4;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        // The outcomes are distributed uniformly over a reference outcome combined with
+        // the span of the X parts of the stabilizers
+        let mut reference_state = self.clone();
+        let mut reference = vec![0_u64; self.number_qubits.div_ceil(64)];
+        for qubit in 0..self.number_qubits {
+            if reference_state.measure_qubit(qubit, rng)? {
+                reference[qubit / 64] |= 1 << (qubit % 64);
+            }
+        }
+        let flips: Vec<&Vec<u64>> = self.rows[self.number_qubits..]
+            .iter()
+            .map(|row| &row.x)
+            .filter(|x| x.iter().any(|word| *word != 0))
+            .collect();
+        Ok((0..number_measurements)
+            .map(|_| {
+                let mut outcome = reference.clone();
+                for flip in flips.iter() {
+                    if rng.random::<bool>() {
+                        for (word, flip_word) in outcome.iter_mut().zip(flip.iter()) {
+                            *word ^= flip_word;
+                        }
+                    }
+                }
+                (0..self.number_qubits)
+                    .map(|qubit| (outcome[qubit / 64] >> (qubit % 64)) & 1 == 1)
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        Ok((0..self.number_qubits)
+            .map(|qubit| match self.deterministic_outcome(qubit) {
+                Some(true) => 1.0,
+                Some(false) => 0.0,
+                None => 0.5,
+            })
+            .collect())
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        let mut pauli = PauliRow::identity(self.number_qubits);
+        for (qubit, operator) in qubit_paulis.iter() {
+            self.check_qubit(*qubit)?;
+            match operator {
+                0 => (),
+                1 => pauli.set(*qubit, true, false),
+                // Y = i X Z
+                2 => {
+                    pauli.set(*qubit, true, true);
+                    pauli.phase = (pauli.phase + 1) % 4;
+                }
+                3 => pauli.set(*qubit, false, true),
+                _ => {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!("Pauli operator {operator} on qubit {qubit} is not in 0..=3"),
+                    })
+                }
+            }
+        }
+        if self.rows[self.number_qubits..]
+            .iter()
+            .any(|stabilizer| stabilizer.anticommutes(&pauli))
+        {
+            return Ok(0.0);
+        }
+        let product = self.stabilizer_product(&pauli);
+        // The product equals ±pauli, the relative phase is 0 or 2
+        Ok(if (product.phase + 4 - pauli.phase) % 4 == 0 {
+            1.0
+        } else {
+            -1.0
+        })
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::{
+    dense_dimension, gate_qubits, number_qubits, run_operations, subspace_indices,
+    ClassicalRegisters, RunCounter, SimulatorState,
+};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{GateOperation, Operate, OperateGate, Operation};
+use crate::registers::{BitRegister, ComplexRegister, FloatRegister};
+use crate::RoqoqoBackendError;
+
+/// Noise-free statevector simulator.
+///
+/// Every gate operation is applied to the full statevector using its
+/// [crate::operations::OperateGate::unitary_matrix].
+/// Besides gate operations the simulator supports classical register definitions,
+/// `MeasureQubit`, `PragmaRepeatedMeasurement`, `PragmaSetNumberOfMeasurements`,
+/// `PragmaGetStateVector`, `PragmaGetDensityMatrix`, `PragmaGetOccupationProbability`,
+/// `PragmaGetPauliProduct`, `PragmaActiveReset`, `PragmaSetStateVector`, `PragmaGlobalPhase`,
+/// `PragmaConditional` and `PragmaLoop`.
+/// Noise PRAGMAs are not supported and return [RoqoqoBackendError::OperationNotInBackend].
+///
+/// The number of qubits is determined from the highest qubit index used in the circuit.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{CNOT, DefinitionBit, Hadamard, PragmaRepeatedMeasurement};
+/// use roqoqo::simulators::StatevectorSimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionBit::new("ro".to_string(), 2, true);
+/// circuit += Hadamard::new(0);
+/// circuit += CNOT::new(0, 1);
+/// circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 100, None);
+///
+/// let backend = StatevectorSimulator::new(Some(42));
+/// let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+/// for record in bit_registers["ro"].iter() {
+///     assert_eq!(record[0], record[1]);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StatevectorSimulator {
+    /// Seed of the random number generator used for measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl StatevectorSimulator {
+    /// Creates a new StatevectorSimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed of the random number generator used for measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new StatevectorSimulator.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for StatevectorSimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        let operations: Vec<&Operation> = circuit.collect();
+        let state = StatevectorState::new(number_qubits(&operations))?;
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &operations, &mut rng)
+    }
+}
+
+/// Pure quantum state of a qubit register stored as a dense statevector.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StatevectorState {
+    number_qubits: usize,
+    amplitudes: Vec<Complex64>,
+}
+
+impl StatevectorState {
+    /// Creates a new state with all qubits in the zero state.
+    ///
+    /// Returns an error when the statevector of `number_qubits` qubits can not be allocated.
+    pub(crate) fn new(number_qubits: usize) -> Result<Self, RoqoqoBackendError> {
+        let mut amplitudes = vec![Complex64::new(0.0, 0.0); dense_dimension(number_qubits, 1)?];
+        amplitudes[0] = Complex64::new(1.0, 0.0);
+        Ok(Self {
+            number_qubits,
+            amplitudes,
+        })
+    }
+
+    /// Applies a matrix acting on `qubits` (in descending order of significance) to the state.
+    ///
+    /// The matrix does not need to be unitary, the state is not renormalised.
+    pub(crate) fn apply_matrix(&mut self, matrix: &Array2<Complex64>, qubits: &[usize]) {
+        apply_matrix(&mut self.amplitudes, matrix, qubits);
+    }
+
+    /// Returns the probability to find a qubit in the state |1>.
+    pub(crate) fn excited_probability(&self, qubit: usize) -> f64 {
+        self.amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| (index >> qubit) & 1 == 1)
+            .map(|(_, amplitude)| amplitude.norm_sqr())
+            .sum()
+    }
+
+    /// Returns the reduced density matrix of a single qubit.
+    pub(crate) fn reduced_density_matrix(&self, qubit: usize) -> Array2<Complex64> {
+        let mut reduced = Array2::<Complex64>::zeros((2, 2));
+        for index in (0..self.amplitudes.len()).filter(|index| (index >> qubit) & 1 == 0) {
+            let zero = self.amplitudes[index];
+            let one = self.amplitudes[index | (1 << qubit)];
+            reduced[(0, 0)] += zero * zero.conj();
+            reduced[(0, 1)] += zero * one.conj();
+            reduced[(1, 0)] += one * zero.conj();
+            reduced[(1, 1)] += one * one.conj();
+        }
+        reduced
+    }
+
+    /// Rescales the state to unit norm.
+    pub(crate) fn normalize(&mut self) {
+        let norm: f64 = self
+            .amplitudes
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .sum();
+        if norm > 0.0 {
+            let factor = 1.0 / norm.sqrt();
+            for amplitude in self.amplitudes.iter_mut() {
+                *amplitude *= factor;
+            }
+        }
+    }
+
+    /// Projects a qubit onto a measurement outcome and renormalises the state.
+    pub(crate) fn project(&mut self, qubit: usize, outcome: bool) {
+        for (index, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if ((index >> qubit) & 1 == 1) != outcome {
+                *amplitude = Complex64::new(0.0, 0.0);
+            }
+        }
+        self.normalize();
+    }
+
+    /// Checks that a qubit is part of the simulated register.
+    pub(crate) fn check_qubit(&self, qubit: usize) -> Result<(), RoqoqoBackendError> {
+        if qubit >= self.number_qubits {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit {qubit} is not part of the simulated register of {} qubits",
+                    self.number_qubits
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SimulatorState for StatevectorState {
+    fn backend_name(&self) -> &'static str {
+        "StatevectorSimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        _registers: &mut ClassicalRegisters,
+        _rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        if let Some(qubits) = gate_qubits(operation) {
+            let matrix = GateOperation::try_from(operation)?.unitary_matrix()?;
+            self.apply_matrix(&matrix, &qubits);
+            return Ok(());
+        }
+        match operation {
+            Operation::PragmaGlobalPhase(op) => {
+                let phase = Complex64::from_polar(1.0, f64::try_from(op.phase().clone())?);
+                for amplitude in self.amplitudes.iter_mut() {
+                    *amplitude *= phase;
+                }
+                Ok(())
+            }
+            Operation::PragmaSetStateVector(op) => {
+                if op.statevector().len() != self.amplitudes.len() {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!(
+                            "Statevector of length {} does not match the simulated register of {} qubits",
+                            op.statevector().len(),
+                            self.number_qubits
+                        ),
+                    });
+                }
+                self.amplitudes = op.statevector().to_vec();
+                Ok(())
+            }
+            _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: self.backend_name(),
+                hqslang: operation.hqslang(),
+            }),
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        self.check_qubit(qubit)?;
+        let outcome = rng.random::<f64>() < self.excited_probability(qubit);
+        self.project(qubit, outcome);
+        Ok(outcome)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        if self.measure_qubit(qubit, rng)? {
+            for index in (0..self.amplitudes.len()).filter(|index| (index >> qubit) & 1 == 0) {
+                self.amplitudes.swap(index, index | (1 << qubit));
+            }
+        }
+        Ok(())
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        let probabilities: Vec<f64> = self
+            .amplitudes
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect();
+        Ok(sample_basis_states(
+            &probabilities,
+            self.number_qubits,
+            number_measurements,
+            rng,
+        ))
+    }
+
+    fn statevector(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Ok(self.amplitudes.clone())
+    }
+
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        Ok(self
+            .amplitudes
+            .iter()
+            .flat_map(|row| {
+                self.amplitudes
+                    .iter()
+                    .map(move |column| row * column.conj())
+            })
+            .collect())
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        Ok((0..self.number_qubits)
+            .map(|qubit| self.excited_probability(qubit))
+            .collect())
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        let (flip_mask, phase_function) = pauli_product_action(qubit_paulis)?;
+        for qubit in qubit_paulis.keys() {
+            self.check_qubit(*qubit)?;
+        }
+        let value: Complex64 = self
+            .amplitudes
+            .iter()
+            .enumerate()
+            .map(|(index, amplitude)| {
+                self.amplitudes[index ^ flip_mask].conj() * phase_function(index) * amplitude
+            })
+            .sum();
+        Ok(value.re)
+    }
+}
+
+/// Applies a matrix acting on a subset of qubits to a vector in the computational basis.
+///
+/// # Arguments
+///
+/// * `vector` - The vector the matrix is applied to, of length 2^N for N qubits.
+/// * `matrix` - The matrix acting on the subspace of `qubits`.
+/// * `qubits` - The qubits the matrix acts on in descending order of significance.
+pub(crate) fn apply_matrix(vector: &mut [Complex64], matrix: &Array2<Complex64>, qubits: &[usize]) {
+    let mask: usize = qubits.iter().map(|qubit| 1 << qubit).sum();
+    let mut local = vec![Complex64::new(0.0, 0.0); 1 << qubits.len()];
+    for base in (0..vector.len()).filter(|index| index & mask == 0) {
+        let indices = subspace_indices(base, qubits);
+        for (value, index) in local.iter_mut().zip(indices.iter()) {
+            *value = vector[*index];
+        }
+        for (row, index) in indices.iter().enumerate() {
+            vector[*index] = matrix
+                .row(row)
+                .iter()
+                .zip(local.iter())
+                .map(|(element, value)| element * value)
+                .sum();
+        }
+    }
+}
+
+/// Returns the action of a Pauli product on the computational basis.
+///
+/// A Pauli product P maps a basis state |i> to `phase(i) |i ^ flip_mask>`.
+/// The function returns the flip mask and the phase function.
+///
+/// # Arguments
+///
+/// * `qubit_paulis` - The Pauli operator acting on each qubit (`0` = identity, `1` = PauliX, `2` = PauliY, `3` = PauliZ).
+pub(crate) fn pauli_product_action(
+    qubit_paulis: &HashMap<usize, usize>,
+) -> Result<(usize, impl Fn(usize) -> Complex64), RoqoqoBackendError> {
+    let mut flip_mask: usize = 0;
+    let mut y_mask: usize = 0;
+    let mut z_mask: usize = 0;
+    for (qubit, pauli) in qubit_paulis.iter() {
+        match pauli {
+            0 => (),
+            1 => flip_mask |= 1 << qubit,
+            2 => {
+                flip_mask |= 1 << qubit;
+                y_mask |= 1 << qubit;
+            }
+            3 => z_mask |= 1 << qubit,
+            _ => {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!("Pauli operator {pauli} on qubit {qubit} is not in 0..=3"),
+                })
+            }
+        }
+    }
+    let phase_function = move |index: usize| -> Complex64 {
+        // Y|0> = i|1> and Y|1> = -i|0>
+        let y_ones = (index & y_mask).count_ones();
+        let y_zeros = y_mask.count_ones() - y_ones;
+        let mut phase =
+            Complex64::new(0.0, 1.0).powu(y_zeros) * Complex64::new(0.0, -1.0).powu(y_ones);
+        if (index & z_mask).count_ones() % 2 == 1 {
+            phase = -phase;
+        }
+        phase
+    };
+    Ok((flip_mask, phase_function))
+}
+
+/// Samples basis states from a probability distribution over the computational basis.
+///
+/// # Arguments
+///
+/// * `probabilities` - The probability of each basis state.
+/// * `number_qubits` - The number of qubits of the register.
+/// * `number_measurements` - The number of samples.
+/// * `rng` - The random number generator.
+///
+/// # Returns
+///
+/// * `Vec<BitRegister>` - One record per sample containing the value of each qubit.
+pub(crate) fn sample_basis_states(
+    probabilities: &[f64],
+    number_qubits: usize,
+    number_measurements: usize,
+    rng: &mut StdRng,
+) -> Vec<BitRegister> {
+    let mut cumulative: Vec<f64> = Vec::with_capacity(probabilities.len());
+    let mut total = 0.0;
+    for probability in probabilities.iter() {
+        total += probability.max(0.0);
+        cumulative.push(total);
+    }
+    (0..number_measurements)
+        .map(|_| {
+            let random_value = rng.random::<f64>() * total;
+            let index = cumulative
+                .partition_point(|value| *value <= random_value)
+                .min(probabilities.len() - 1);
+            (0..number_qubits)
+                .map(|qubit| (index >> qubit) & 1 == 1)
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,311 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use nalgebra::{Matrix4, SymmetricEigen};
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::RngExt;
+
+use super::statevector::StatevectorState;
+use super::{number_qubits, run_operations, ClassicalRegisters, RunCounter, SimulatorState};
+use crate::backends::{EvaluatingBackend, RegisterResult};
+use crate::operations::{
+    InvolveQubits, InvolvedQubits, OperatePragmaNoise, Operation, PragmaDephasing,
+    PragmaDepolarising, PragmaNoiseOperation, PragmaRepeatedMeasurement,
+    PragmaSetNumberOfMeasurements,
+};
+use crate::registers::{BitRegister, ComplexRegister, FloatRegister};
+use crate::RoqoqoBackendError;
+
+/// Quantum trajectory (Monte-Carlo wavefunction) simulator.
+///
+/// The simulator evolves a pure statevector and unravels every noise PRAGMA stochastically:
+/// the superoperator of the PRAGMA is decomposed into Kraus operators and for each application
+/// one Kraus operator K is chosen with probability ‖Kψ‖² and applied to the renormalised state.
+/// Averaged over many trajectories this reproduces the results of the
+/// [crate::simulators::DensityMatrixSimulator] while only requiring memory for a statevector.
+/// `PragmaRandomNoise` is unravelled as a depolarising channel with the depolarising rate
+/// followed by a dephasing channel with the dephasing rate.
+///
+/// Every shot is simulated as an independent trajectory.
+/// The number of trajectories is the largest number of measurements set by a
+/// `PragmaSetNumberOfMeasurements` or `PragmaRepeatedMeasurement` in the circuit,
+/// each `PragmaRepeatedMeasurement` records a single measurement per trajectory.
+/// Readout PRAGMAs like `PragmaGetOccupationProbability` return one entry per trajectory.
+///
+/// Besides noise PRAGMAs the simulator supports the same operations as the
+/// [crate::simulators::StatevectorSimulator].
+/// The random number generator can be seeded to make the simulation reproducible.
+///
+/// # Example
+/// ```
+/// use roqoqo::backends::EvaluatingBackend;
+/// use roqoqo::operations::{DefinitionBit, PauliX, PragmaDamping, PragmaRepeatedMeasurement};
+/// use roqoqo::simulators::QuantumTrajectorySimulator;
+/// use roqoqo::Circuit;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += DefinitionBit::new("ro".to_string(), 1, true);
+/// circuit += PauliX::new(0);
+/// circuit += PragmaDamping::new(0, 1.0.into(), 0.5.into());
+/// circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 1000, None);
+///
+/// let backend = QuantumTrajectorySimulator::new(Some(42));
+/// let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+/// assert_eq!(bit_registers["ro"].len(), 1000);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct QuantumTrajectorySimulator {
+    /// Seed of the random number generator used for noise and measurements (None for a random seed).
+    seed: Option<u64>,
+    /// Counter of the runs, used to give every run its own random number generator.
+    runs: RunCounter,
+}
+
+impl QuantumTrajectorySimulator {
+    /// Creates a new QuantumTrajectorySimulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed of the random number generator used for noise and measurements. When `None` the generator is seeded randomly.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The new QuantumTrajectorySimulator.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            runs: RunCounter::default(),
+        }
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl EvaluatingBackend for QuantumTrajectorySimulator {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> RegisterResult {
+        let operations: Vec<&Operation> = circuit.collect();
+        // Every repeated measurement is replaced by a single measurement per trajectory
+        let mut number_trajectories: Option<(usize, String)> = None;
+        let single_shot_operations: Vec<Operation> = operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::PragmaRepeatedMeasurement(op) => {
+                    if number_trajectories
+                        .as_ref()
+                        .map_or(true, |(number, _)| op.number_measurements() > number)
+                    {
+                        number_trajectories =
+                            Some((*op.number_measurements(), op.readout().clone()));
+                    }
+                    PragmaRepeatedMeasurement::new(
+                        op.readout().clone(),
+                        1,
+                        op.qubit_mapping().clone(),
+                    )
+                    .into()
+                }
+                _ => (*operation).clone(),
+            })
+            .collect();
+        let mut trajectory_operations: Vec<&Operation> = single_shot_operations.iter().collect();
+        let number_trajectories_operation = number_trajectories
+            .map(|(number, readout)| PragmaSetNumberOfMeasurements::new(number, readout).into());
+        if let Some(operation) = number_trajectories_operation.as_ref() {
+            trajectory_operations.insert(0, operation);
+        }
+
+        let state = TrajectoryState {
+            state: StatevectorState::new(number_qubits(&operations))?,
+        };
+        let mut rng = self.runs.next_rng(self.seed);
+        run_operations(&state, &trajectory_operations, &mut rng)
+    }
+}
+
+/// A single trajectory of the quantum trajectory simulation.
+#[derive(Debug, Clone, PartialEq)]
+struct TrajectoryState {
+    state: StatevectorState,
+}
+
+impl TrajectoryState {
+    /// Applies a randomly chosen Kraus operator of a single-qubit superoperator to the state.
+    fn apply_superoperator(
+        &mut self,
+        superoperator: &Array2<f64>,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        let kraus_operators = kraus_operators(superoperator);
+        let reduced = self.state.reduced_density_matrix(qubit);
+        // The probability of a Kraus operator K is Tr(K ρ K†) of the reduced density matrix ρ
+        let probabilities: Vec<f64> = kraus_operators
+            .iter()
+            .map(|kraus| {
+                kraus
+                    .dot(&reduced)
+                    .dot(&kraus.t().mapv(|value| value.conj()))
+            })
+            .map(|product| (product[(0, 0)] + product[(1, 1)]).re.max(0.0))
+            .collect();
+        let total: f64 = probabilities.iter().sum();
+        let mut random_value = rng.random::<f64>() * total;
+        let mut chosen = kraus_operators.len() - 1;
+        for (index, probability) in probabilities.iter().enumerate() {
+            if random_value < *probability {
+                chosen = index;
+                break;
+            }
+            random_value -= probability;
+        }
+        self.state.apply_matrix(&kraus_operators[chosen], &[qubit]);
+        self.state.normalize();
+        Ok(())
+    }
+
+    /// Unravels a noise PRAGMA.
+    fn apply_noise(
+        &mut self,
+        noise: &PragmaNoiseOperation,
+        rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        // All noise PRAGMAs act on a single qubit
+        let qubit = match noise.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.into_iter().next().unwrap_or_default(),
+            _ => 0,
+        };
+        self.state.check_qubit(qubit)?;
+        match noise {
+            PragmaNoiseOperation::PragmaRandomNoise(op) => {
+                let depolarising = PragmaDepolarising::new(
+                    qubit,
+                    op.gate_time().clone(),
+                    op.depolarising_rate().clone(),
+                );
+                let dephasing = PragmaDephasing::new(
+                    qubit,
+                    op.gate_time().clone(),
+                    op.dephasing_rate().clone(),
+                );
+                self.apply_superoperator(&depolarising.superoperator()?, qubit, rng)?;
+                self.apply_superoperator(&dephasing.superoperator()?, qubit, rng)
+            }
+            _ => self.apply_superoperator(&noise.superoperator()?, qubit, rng),
+        }
+    }
+}
+
+impl SimulatorState for TrajectoryState {
+    fn backend_name(&self) -> &'static str {
+        "QuantumTrajectorySimulator"
+    }
+
+    fn apply_operation(
+        &mut self,
+        operation: &Operation,
+        registers: &mut ClassicalRegisters,
+        rng: &mut StdRng,
+    ) -> Result<(), RoqoqoBackendError> {
+        if let Ok(noise) = PragmaNoiseOperation::try_from(operation) {
+            return self.apply_noise(&noise, rng);
+        }
+        match self.state.apply_operation(operation, registers, rng) {
+            Err(RoqoqoBackendError::OperationNotInBackend { hqslang, .. }) => {
+                Err(RoqoqoBackendError::OperationNotInBackend {
+                    backend: self.backend_name(),
+                    hqslang,
+                })
+            }
+            result => result,
+        }
+    }
+
+    fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut StdRng,
+    ) -> Result<bool, RoqoqoBackendError> {
+        self.state.measure_qubit(qubit, rng)
+    }
+
+    fn reset_qubit(&mut self, qubit: usize, rng: &mut StdRng) -> Result<(), RoqoqoBackendError> {
+        self.state.reset_qubit(qubit, rng)
+    }
+
+    fn sample(
+        &self,
+        number_measurements: usize,
+        rng: &mut StdRng,
+    ) -> Result<Vec<BitRegister>, RoqoqoBackendError> {
+        self.state.sample(number_measurements, rng)
+    }
+
+    fn statevector(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        self.state.statevector()
+    }
+
+    fn density_matrix(&self) -> Result<ComplexRegister, RoqoqoBackendError> {
+        self.state.density_matrix()
+    }
+
+    fn occupation_probabilities(&self) -> Result<FloatRegister, RoqoqoBackendError> {
+        self.state.occupation_probabilities()
+    }
+
+    fn pauli_product(
+        &self,
+        qubit_paulis: &HashMap<usize, usize>,
+    ) -> Result<f64, RoqoqoBackendError> {
+        self.state.pauli_product(qubit_paulis)
+    }
+}
+
+/// Returns a set of Kraus operators of a single-qubit superoperator.
+///
+/// The superoperator S acts on the row-major flattened density matrix.
+/// The Kraus operators are obtained from the eigendecomposition of the Choi matrix
+/// C\[(a, c), (b, d)\] = S\[(a, b), (c, d)\], where every eigenvector v with eigenvalue λ > 0
+/// corresponds to the Kraus operator K\[a, c\] = √λ v\[(a, c)\].
+fn kraus_operators(superoperator: &Array2<f64>) -> Vec<Array2<Complex64>> {
+    let choi = Matrix4::from_fn(|row, column| {
+        let (a, c) = (row / 2, row % 2);
+        let (b, d) = (column / 2, column % 2);
+        superoperator[(2 * a + b, 2 * c + d)]
+    });
+    let eigen = SymmetricEigen::new(choi);
+    let kraus_operators: Vec<Array2<Complex64>> = eigen
+        .eigenvalues
+        .iter()
+        .zip(eigen.eigenvectors.column_iter())
+        .filter(|(eigenvalue, _)| **eigenvalue > 1e-12)
+        .map(|(eigenvalue, eigenvector)| {
+            Array2::from_shape_fn((2, 2), |(a, c)| {
+                Complex64::new(eigenvalue.sqrt() * eigenvector[2 * a + c], 0.0)
+            })
+        })
+        .collect();
+    if kraus_operators.is_empty() {
+        vec![Array2::eye(2)]
+    } else {
+        kraus_operators
+    }
+}
@@ -0,0 +1,752 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decomposition of circuits into the gates available on a device.
+
+use crate::devices::Device;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use ndarray::{array, Array2};
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use std::convert::TryFrom;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Rotation angles and vector components below this value are treated as zero.
+const TOLERANCE: f64 = 1e-12;
+
+/// Decomposes all gates of a circuit into the gates available on a device.
+///
+/// Gates whose name is in the gate names of the device are kept unchanged. The other gates are rewritten:
+///
+/// * Single-qubit gates are rewritten into the rotations of the device using the alpha and beta parameters
+///   of [OperateSingleQubitGate]. The device needs two rotations about perpendicular axes out of
+///   `RotateX`, `RotateY`, `RotateZ` (or `PhaseShiftState0`/`PhaseShiftState1`), or `RotateXY`.
+///   When the device offers `SingleQubitGate`, single-qubit gates are rewritten into it instead.
+/// * Two-qubit gates are rewritten via their [KakDecomposition] into single-qubit gates and the entangling gate
+///   of the device, one of `CNOT`, `ControlledPauliZ`, `ControlledPhaseShift`, `VariableMSXX`,
+///   `MolmerSorensenXX`, `ControlledPauliY` or `ISwap`.
+/// * Three-qubit, four-qubit and multi-qubit gates are rewritten via their `circuit()` decompositions.
+///
+/// All other operations, as well as the operations in the circuits of `PragmaLoop` and `PragmaConditional`,
+/// are decomposed recursively or kept unchanged.
+/// `PragmaControlledCircuit` is only kept when its circuit contains gates of the device,
+/// since the global phase of a decomposition would become a relative phase under the control.
+/// The decomposed circuit implements the same unitary up to a global phase.
+/// Symbolic rotation angles are kept symbolic where the gate is a rotation about a fixed axis,
+/// other gates with symbolic parameters can only be kept if they are available on the device.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is decomposed.
+/// * `device` - The device providing the gate names.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit containing only gates available on the device.
+/// * `Err(RoqoqoError)` - A gate cannot be decomposed into the gates of the device.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::AllToAllDevice;
+/// use roqoqo::operations::{Hadamard, Operate, SWAP};
+/// use roqoqo::transpiler::decompose_to_device;
+///
+/// let device = AllToAllDevice::new(
+///     2,
+///     &["RotateZ".to_string(), "RotateX".to_string()],
+///     &["CNOT".to_string()],
+///     1.0,
+/// );
+/// let mut circuit = Circuit::new();
+/// circuit += Hadamard::new(0);
+/// circuit += SWAP::new(0, 1);
+/// let decomposed = decompose_to_device(&circuit, &device).unwrap();
+/// assert!(decomposed
+///     .iter()
+///     .all(|operation| ["RotateZ", "RotateX", "CNOT"].contains(&operation.hqslang())));
+/// assert!(decomposed.is_equivalent(&circuit, 1e-10, true).unwrap());
+/// ```
+pub fn decompose_to_device(circuit: &Circuit, device: &dyn Device) -> Result<Circuit, RoqoqoError> {
+    let gate_set = NativeGateSet::new(device);
+    let mut decomposed = Circuit::new();
+    for operation in circuit.iter() {
+        gate_set.decompose(operation, &mut decomposed)?;
+    }
+    Ok(decomposed)
+}
+
+/// The gate available on a device for rotations about the z-axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ZRotation {
+    RotateZ,
+    PhaseShiftState1,
+    PhaseShiftState0,
+}
+
+/// The entangling gate of a device used to decompose two-qubit gates.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entangler {
+    CNOT,
+    ControlledPauliZ,
+    ControlledPhaseShift,
+    VariableMSXX,
+    MolmerSorensenXX,
+    ControlledPauliY,
+    ISwap,
+}
+
+impl Entangler {
+    /// The entangling gates in order of preference.
+    const PREFERENCE: [(&'static str, Entangler); 7] = [
+        ("CNOT", Entangler::CNOT),
+        ("ControlledPauliZ", Entangler::ControlledPauliZ),
+        ("ControlledPhaseShift", Entangler::ControlledPhaseShift),
+        ("VariableMSXX", Entangler::VariableMSXX),
+        ("MolmerSorensenXX", Entangler::MolmerSorensenXX),
+        ("ControlledPauliY", Entangler::ControlledPauliY),
+        ("ISwap", Entangler::ISwap),
+    ];
+}
+
+/// The gates available on a device.
+#[derive(Debug, Clone)]
+struct NativeGateSet {
+    single_qubit_gates: Vec<String>,
+    two_qubit_gates: Vec<String>,
+    multi_qubit_gates: Vec<String>,
+    rotate_x: bool,
+    rotate_y: bool,
+    rotate_xy: bool,
+    rotate_z: Option<ZRotation>,
+    entangler: Option<Entangler>,
+}
+
+impl NativeGateSet {
+    /// Collects the gates available on a device.
+    fn new(device: &dyn Device) -> Self {
+        let single_qubit_gates = device.single_qubit_gate_names();
+        let two_qubit_gates = device.two_qubit_gate_names();
+        let multi_qubit_gates = device.multi_qubit_gate_names();
+        let available = |name: &str| single_qubit_gates.iter().any(|gate| gate == name);
+        let rotate_z = if available("RotateZ") {
+            Some(ZRotation::RotateZ)
+        } else if available("PhaseShiftState1") {
+            Some(ZRotation::PhaseShiftState1)
+        } else if available("PhaseShiftState0") {
+            Some(ZRotation::PhaseShiftState0)
+        } else {
+            None
+        };
+        let entangler = Entangler::PREFERENCE
+            .iter()
+            .find(|(name, _)| two_qubit_gates.iter().any(|gate| gate == name))
+            .map(|(_, entangler)| *entangler);
+        NativeGateSet {
+            rotate_x: available("RotateX"),
+            rotate_y: available("RotateY"),
+            rotate_xy: available("RotateXY"),
+            rotate_z,
+            entangler,
+            single_qubit_gates,
+            two_qubit_gates,
+            multi_qubit_gates,
+        }
+    }
+
+    /// Decomposes an operation and adds the result to a circuit.
+    fn decompose(&self, operation: &Operation, circuit: &mut Circuit) -> Result<(), RoqoqoError> {
+        let hqslang = operation.hqslang();
+        if let Ok(gate) = SingleQubitGateOperation::try_from(operation) {
+            if self.single_qubit_gates.iter().any(|name| name == hqslang) {
+                circuit.add_operation(operation.clone());
+                return Ok(());
+            }
+            return self.single_qubit_gate(operation, &gate, circuit);
+        }
+        if let Ok(gate) = TwoQubitGateOperation::try_from(operation) {
+            if self.two_qubit_gates.iter().any(|name| name == hqslang) {
+                circuit.add_operation(operation.clone());
+                return Ok(());
+            }
+            return self.two_qubit_gate(operation, &gate, circuit);
+        }
+        let gate_circuit = if let Ok(gate) = ThreeQubitGateOperation::try_from(operation) {
+            Some(gate.circuit())
+        } else if let Ok(gate) = FourQubitGateOperation::try_from(operation) {
+            Some(gate.circuit())
+        } else if let Ok(gate) = MultiQubitGateOperation::try_from(operation) {
+            Some(gate.circuit())
+        } else {
+            None
+        };
+        if let Some(gate_circuit) = gate_circuit {
+            if self.multi_qubit_gates.iter().any(|name| name == hqslang) {
+                circuit.add_operation(operation.clone());
+                return Ok(());
+            }
+            for gate_operation in gate_circuit.iter() {
+                self.decompose(gate_operation, circuit)?;
+            }
+            return Ok(());
+        }
+        match operation {
+            Operation::PragmaLoop(op) => {
+                circuit.add_operation(PragmaLoop::new(
+                    op.repetitions().clone(),
+                    self.decompose_circuit(op.circuit())?,
+                ));
+            }
+            Operation::PragmaConditional(op) => {
+                circuit.add_operation(PragmaConditional::new(
+                    op.condition_register().clone(),
+                    *op.condition_index(),
+                    self.decompose_circuit(op.circuit())?,
+                ));
+            }
+            Operation::PragmaControlledCircuit(op) => {
+                // Decompositions are only exact up to a global phase, which the control turns into
+                // a relative phase, so controlled circuits can only be kept when they are native.
+                if &self.decompose_circuit(op.circuit())? != op.circuit() {
+                    return Err(not_decomposable(
+                        operation,
+                        "the controlled circuit contains gates that are not available on the device",
+                    ));
+                }
+                circuit.add_operation(operation.clone());
+            }
+            _ => circuit.add_operation(operation.clone()),
+        }
+        Ok(())
+    }
+
+    /// Decomposes all operations of a circuit.
+    fn decompose_circuit(&self, circuit: &Circuit) -> Result<Circuit, RoqoqoError> {
+        let mut decomposed = Circuit::new();
+        for operation in circuit.iter() {
+            self.decompose(operation, &mut decomposed)?;
+        }
+        Ok(decomposed)
+    }
+
+    /// Decomposes a single-qubit gate that is not available on the device.
+    fn single_qubit_gate(
+        &self,
+        operation: &Operation,
+        gate: &SingleQubitGateOperation,
+        circuit: &mut Circuit,
+    ) -> Result<(), RoqoqoError> {
+        let qubit = *gate.qubit();
+        let axis_rotation = rotation_axis(operation);
+        if let Some((axis, theta)) = axis_rotation.as_ref() {
+            if self.native_rotation(qubit, axis, theta, circuit) {
+                return Ok(());
+            }
+        }
+        if self
+            .single_qubit_gates
+            .iter()
+            .any(|name| name == "SingleQubitGate")
+        {
+            circuit.add_operation(SingleQubitGate::new(
+                qubit,
+                gate.alpha_r(),
+                gate.alpha_i(),
+                gate.beta_r(),
+                gate.beta_i(),
+                gate.global_phase(),
+            ));
+            return Ok(());
+        }
+        if let Ok(unitary) = gate.unitary_matrix() {
+            return self
+                .single_qubit_unitary(qubit, &unitary, circuit)
+                .map_err(|msg| not_decomposable(operation, &msg));
+        }
+        match axis_rotation {
+            Some((axis, theta)) => self
+                .rotation(qubit, &axis, &theta, circuit)
+                .map_err(|msg| not_decomposable(operation, &msg)),
+            None => Err(not_decomposable(
+                operation,
+                "the symbolic parameters do not describe a rotation about a fixed axis",
+            )),
+        }
+    }
+
+    /// Adds a rotation about an arbitrary axis, conjugating a rotation of the device if necessary.
+    fn rotation(
+        &self,
+        qubit: usize,
+        axis: &[f64; 3],
+        theta: &CalculatorFloat,
+        circuit: &mut Circuit,
+    ) -> Result<(), String> {
+        if self.native_rotation(qubit, axis, theta, circuit) {
+            return Ok(());
+        }
+        let native_axis = if self.rotate_z.is_some() {
+            [0.0, 0.0, 1.0]
+        } else if self.rotate_xy {
+            let norm = axis[0].hypot(axis[1]);
+            if norm > TOLERANCE {
+                [axis[0] / norm, axis[1] / norm, 0.0]
+            } else {
+                [1.0, 0.0, 0.0]
+            }
+        } else if self.rotate_x {
+            [1.0, 0.0, 0.0]
+        } else if self.rotate_y {
+            [0.0, 1.0, 0.0]
+        } else {
+            return Err("the device has no single-qubit rotation gates".to_string());
+        };
+        // R_axis(theta) = W R_native(theta) W^†, where W rotates the native axis onto the axis
+        let basis_change = axis_rotation_unitary(&native_axis, axis);
+        self.single_qubit_unitary(qubit, &adjoint(&basis_change), circuit)?;
+        if !self.native_rotation(qubit, &native_axis, theta, circuit) {
+            return Err("the device has no single-qubit rotation gates".to_string());
+        }
+        self.single_qubit_unitary(qubit, &basis_change, circuit)
+    }
+
+    /// Adds a rotation if a rotation gate of the device has the same axis, returns whether it was added.
+    fn native_rotation(
+        &self,
+        qubit: usize,
+        axis: &[f64; 3],
+        theta: &CalculatorFloat,
+        circuit: &mut Circuit,
+    ) -> bool {
+        for sign in [1.0, -1.0] {
+            let angle = theta.clone() * sign;
+            let parallel = |x: f64, y: f64, z: f64| {
+                (axis[0] - sign * x).abs() < TOLERANCE
+                    && (axis[1] - sign * y).abs() < TOLERANCE
+                    && (axis[2] - sign * z).abs() < TOLERANCE
+            };
+            if parallel(0.0, 0.0, 1.0) {
+                if let Some(rotate_z) = self.rotate_z {
+                    add_rotation(circuit, z_rotation(rotate_z, qubit, angle));
+                    return true;
+                }
+            }
+            if parallel(1.0, 0.0, 0.0) && self.rotate_x {
+                add_rotation(circuit, RotateX::new(qubit, angle).into());
+                return true;
+            }
+            if parallel(0.0, 1.0, 0.0) && self.rotate_y {
+                add_rotation(circuit, RotateY::new(qubit, angle).into());
+                return true;
+            }
+        }
+        if axis[2].abs() < TOLERANCE && self.rotate_xy {
+            add_rotation(
+                circuit,
+                RotateXY::new(qubit, theta.clone(), axis[1].atan2(axis[0]).into()).into(),
+            );
+            return true;
+        }
+        false
+    }
+
+    /// Adds the rotations of the device implementing a numeric single-qubit unitary up to a global phase.
+    fn single_qubit_unitary(
+        &self,
+        qubit: usize,
+        unitary: &Array2<Complex64>,
+        circuit: &mut Circuit,
+    ) -> Result<(), String> {
+        let rotations: Vec<([f64; 3], f64)> =
+            match self.rotate_z {
+                Some(_) if self.rotate_y || self.rotate_xy => {
+                    let (alpha, beta, gamma) = zyz_angles(unitary);
+                    vec![
+                        ([0.0, 0.0, 1.0], gamma),
+                        ([0.0, 1.0, 0.0], beta),
+                        ([0.0, 0.0, 1.0], alpha),
+                    ]
+                }
+                Some(_) if self.rotate_x => {
+                    // RotateY(beta) = RotateZ(pi/2) RotateX(beta) RotateZ(-pi/2)
+                    let (alpha, beta, gamma) = zyz_angles(unitary);
+                    vec![
+                        ([0.0, 0.0, 1.0], gamma - FRAC_PI_2),
+                        ([1.0, 0.0, 0.0], beta),
+                        ([0.0, 0.0, 1.0], alpha + FRAC_PI_2),
+                    ]
+                }
+                None if self.rotate_x && self.rotate_y => {
+                    // The Hadamard basis change maps the z-y-z decomposition to a x-y-x decomposition
+                    let hadamard = array![
+                        [Complex64::new(1.0, 0.0), Complex64::new(1.0, 0.0)],
+                        [Complex64::new(1.0, 0.0), Complex64::new(-1.0, 0.0)]
+                    ] / Complex64::new(2.0_f64.sqrt(), 0.0);
+                    let (alpha, beta, gamma) = zyz_angles(&hadamard.dot(unitary).dot(&hadamard));
+                    vec![
+                        ([1.0, 0.0, 0.0], gamma),
+                        ([0.0, 1.0, 0.0], -beta),
+                        ([1.0, 0.0, 0.0], alpha),
+                    ]
+                }
+                None if self.rotate_xy => {
+                    // RotateZ(alpha) RotateY(beta) RotateZ(gamma) = RotateZ(alpha + gamma) R_n(beta)
+                    // with n in the x-y plane, and RotateZ(2 phi) is a product of two pi rotations in the x-y plane
+                    let (alpha, beta, gamma) = zyz_angles(unitary);
+                    let phi = (alpha + gamma) / 2.0;
+                    let mut rotations = vec![([gamma.sin(), gamma.cos(), 0.0], beta)];
+                    if normalized_angle(alpha + gamma).abs() > TOLERANCE {
+                        rotations.push(([1.0, 0.0, 0.0], PI));
+                        rotations.push(([phi.cos(), phi.sin(), 0.0], PI));
+                    }
+                    rotations
+                }
+                _ => return Err(
+                    "the device has no two single-qubit rotation gates about perpendicular axes"
+                        .to_string(),
+                ),
+            };
+        for (axis, angle) in rotations {
+            let angle = normalized_angle(angle);
+            if angle.abs() > TOLERANCE
+                && !self.native_rotation(qubit, &axis, &angle.into(), circuit)
+            {
+                return Err("the device has no single-qubit rotation gates".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decomposes a two-qubit gate that is not available on the device.
+    fn two_qubit_gate(
+        &self,
+        operation: &Operation,
+        gate: &TwoQubitGateOperation,
+        circuit: &mut Circuit,
+    ) -> Result<(), RoqoqoError> {
+        let entangler = self.entangler.ok_or_else(|| {
+            not_decomposable(
+                operation,
+                "the device has no supported two-qubit entangling gate",
+            )
+        })?;
+        let control = *gate.control();
+        let target = *gate.target();
+        match operation {
+            Operation::CNOT(_) => return self.cnot(entangler, control, target, circuit),
+            Operation::ControlledPauliZ(_) => {
+                self.decompose(&Hadamard::new(target).into(), circuit)?;
+                self.cnot(entangler, control, target, circuit)?;
+                return self.decompose(&Hadamard::new(target).into(), circuit);
+            }
+            Operation::SWAP(_) => {
+                self.cnot(entangler, control, target, circuit)?;
+                self.cnot(entangler, target, control, circuit)?;
+                return self.cnot(entangler, control, target, circuit);
+            }
+            _ => (),
+        }
+        let kak = gate.kak_decomposition();
+        if let Some(circuit_before) = kak.circuit_before.as_ref() {
+            for local_operation in circuit_before.iter() {
+                self.decompose(local_operation, circuit)?;
+            }
+        }
+        let [kx, ky, kz] = kak.k_vector;
+        // exp(i k PP) = (B ⊗ B) exp(i k ZZ) (B^† ⊗ B^†) with B Z B^† = P
+        let hadamard: Operation = Hadamard::new(0).into();
+        let inverse_sqrt_x: Operation = RotateX::new(0, CalculatorFloat::FRAC_PI_2 * (-1.0)).into();
+        let interactions = [
+            (kx, hadamard.clone(), hadamard),
+            (
+                ky,
+                RotateX::new(0, CalculatorFloat::FRAC_PI_2).into(),
+                inverse_sqrt_x,
+            ),
+            (kz, Identity::new(0).into(), Identity::new(0).into()),
+        ];
+        for (k, basis_change_before, basis_change_after) in interactions {
+            if let CalculatorFloat::Float(value) = k {
+                if value.abs() < TOLERANCE {
+                    continue;
+                }
+            }
+            for qubit in [control, target] {
+                self.local_gate(&basis_change_before, qubit, circuit)?;
+            }
+            self.zz_interaction(entangler, control, target, k, circuit)?;
+            for qubit in [control, target] {
+                self.local_gate(&basis_change_after, qubit, circuit)?;
+            }
+        }
+        if let Some(circuit_after) = kak.circuit_after.as_ref() {
+            for local_operation in circuit_after.iter() {
+                self.decompose(local_operation, circuit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decomposes a single-qubit gate given on qubit 0 on another qubit, skipping the identity.
+    fn local_gate(
+        &self,
+        gate: &Operation,
+        qubit: usize,
+        circuit: &mut Circuit,
+    ) -> Result<(), RoqoqoError> {
+        if let Operation::Identity(_) = gate {
+            return Ok(());
+        }
+        let mapping = std::collections::HashMap::from([(0, qubit), (qubit, 0)]);
+        self.decompose(&gate.remap_qubits(&mapping)?, circuit)
+    }
+
+    /// Adds the gates implementing exp(i k Z_control Z_target) up to a global phase.
+    fn zz_interaction(
+        &self,
+        entangler: Entangler,
+        control: usize,
+        target: usize,
+        k: CalculatorFloat,
+        circuit: &mut Circuit,
+    ) -> Result<(), RoqoqoError> {
+        match entangler {
+            Entangler::VariableMSXX => {
+                // VariableMSXX(theta) = exp(-i theta/2 XX)
+                for qubit in [control, target] {
+                    self.decompose(&Hadamard::new(qubit).into(), circuit)?;
+                }
+                circuit.add_operation(VariableMSXX::new(control, target, k * (-2.0)));
+                for qubit in [control, target] {
+                    self.decompose(&Hadamard::new(qubit).into(), circuit)?;
+                }
+            }
+            Entangler::ControlledPhaseShift => {
+                // ControlledPhaseShift(4k) = exp(i k) RotateZ(2k) ⊗ RotateZ(2k) exp(i k ZZ)
+                circuit.add_operation(ControlledPhaseShift::new(control, target, k.clone() * 4.0));
+                for qubit in [control, target] {
+                    self.decompose(&RotateZ::new(qubit, k.clone() * (-2.0)).into(), circuit)?;
+                }
+            }
+            _ => {
+                // exp(-i theta/2 ZZ) = CNOT (1 ⊗ RotateZ(theta)) CNOT
+                self.cnot(entangler, control, target, circuit)?;
+                self.decompose(&RotateZ::new(target, k * (-2.0)).into(), circuit)?;
+                self.cnot(entangler, control, target, circuit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the gates implementing a CNOT up to a global phase with the entangling gate of the device.
+    fn cnot(
+        &self,
+        entangler: Entangler,
+        control: usize,
+        target: usize,
+        circuit: &mut Circuit,
+    ) -> Result<(), RoqoqoError> {
+        let mut gates: Vec<Operation> = Vec::new();
+        match entangler {
+            Entangler::CNOT => gates.push(CNOT::new(control, target).into()),
+            Entangler::ControlledPauliZ => {
+                gates.push(Hadamard::new(target).into());
+                gates.push(ControlledPauliZ::new(control, target).into());
+                gates.push(Hadamard::new(target).into());
+            }
+            Entangler::ControlledPhaseShift => {
+                gates.push(Hadamard::new(target).into());
+                gates.push(ControlledPhaseShift::new(control, target, CalculatorFloat::PI).into());
+                gates.push(Hadamard::new(target).into());
+            }
+            Entangler::ControlledPauliY => {
+                // ControlledPauliY = (1 ⊗ S) CNOT (1 ⊗ S^†)
+                gates.push(SGate::new(target).into());
+                gates.push(ControlledPauliY::new(control, target).into());
+                gates.push(InvSGate::new(target).into());
+            }
+            Entangler::MolmerSorensenXX | Entangler::VariableMSXX => {
+                // ControlledPauliZ = (RotateZ(pi/2) H ⊗ RotateZ(pi/2) H) MolmerSorensenXX (H Z ⊗ H Z),
+                // the Hadamard gates on the target turning it into a CNOT merge into X and RotateX
+                gates.push(PauliZ::new(control).into());
+                gates.push(Hadamard::new(control).into());
+                gates.push(PauliX::new(target).into());
+                if entangler == Entangler::MolmerSorensenXX {
+                    gates.push(MolmerSorensenXX::new(control, target).into());
+                } else {
+                    gates.push(
+                        VariableMSXX::new(control, target, CalculatorFloat::FRAC_PI_2).into(),
+                    );
+                }
+                gates.push(Hadamard::new(control).into());
+                gates.push(RotateZ::new(control, CalculatorFloat::FRAC_PI_2).into());
+                gates.push(RotateX::new(target, CalculatorFloat::FRAC_PI_2).into());
+            }
+            Entangler::ISwap => {
+                // ISwap (RotateX(pi/2) ⊗ 1) ISwap = (S ⊗ H S) CNOT (1 ⊗ S^†)
+                gates.push(SGate::new(target).into());
+                gates.push(ISwap::new(control, target).into());
+                gates.push(RotateX::new(control, CalculatorFloat::FRAC_PI_2).into());
+                gates.push(ISwap::new(control, target).into());
+                gates.push(InvSGate::new(control).into());
+                gates.push(Hadamard::new(target).into());
+                gates.push(InvSGate::new(target).into());
+            }
+        }
+        for gate in gates {
+            if TwoQubitGateOperation::try_from(&gate).is_ok() {
+                circuit.add_operation(gate);
+            } else {
+                self.decompose(&gate, circuit)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the rotation axis and angle of a single-qubit gate rotating about a fixed axis.
+///
+/// The gate is equal to exp(-i theta/2 n·σ) up to a global phase.
+fn rotation_axis(operation: &Operation) -> Option<([f64; 3], CalculatorFloat)> {
+    match operation {
+        Operation::RotateX(op) => Some(([1.0, 0.0, 0.0], op.theta().clone())),
+        Operation::RotateY(op) => Some(([0.0, 1.0, 0.0], op.theta().clone())),
+        Operation::RotateZ(op) => Some(([0.0, 0.0, 1.0], op.theta().clone())),
+        Operation::PhaseShiftState1(op) => Some(([0.0, 0.0, 1.0], op.theta().clone())),
+        Operation::PhaseShiftState0(op) => Some(([0.0, 0.0, 1.0], op.theta().clone() * (-1.0))),
+        Operation::RotateXY(op) => {
+            let phi = *op.phi().float().ok()?;
+            Some(([phi.cos(), phi.sin(), 0.0], op.theta().clone()))
+        }
+        Operation::RotateAroundSphericalAxis(op) => {
+            let spherical_theta = *op.spherical_theta().float().ok()?;
+            let spherical_phi = *op.spherical_phi().float().ok()?;
+            Some((
+                [
+                    spherical_theta.sin() * spherical_phi.cos(),
+                    spherical_theta.sin() * spherical_phi.sin(),
+                    spherical_theta.cos(),
+                ],
+                op.theta().clone(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the gate of the device rotating about the z-axis by an angle up to a global phase.
+fn z_rotation(rotate_z: ZRotation, qubit: usize, theta: CalculatorFloat) -> Operation {
+    match rotate_z {
+        ZRotation::RotateZ => RotateZ::new(qubit, theta).into(),
+        ZRotation::PhaseShiftState1 => PhaseShiftState1::new(qubit, theta).into(),
+        ZRotation::PhaseShiftState0 => PhaseShiftState0::new(qubit, theta * (-1.0)).into(),
+    }
+}
+
+/// Adds a rotation to a circuit, skipping numeric rotations by multiples of 2π.
+fn add_rotation(circuit: &mut Circuit, rotation: Operation) {
+    let skip = Rotation::try_from(&rotation)
+        .ok()
+        .and_then(|rotation| rotation.theta().float().ok().copied())
+        .is_some_and(|theta| normalized_angle(theta).abs() < TOLERANCE);
+    if !skip {
+        circuit.add_operation(rotation);
+    }
+}
+
+/// Returns the angles (alpha, beta, gamma) with U = exp(i phi) RotateZ(alpha) RotateY(beta) RotateZ(gamma).
+fn zyz_angles(unitary: &Array2<Complex64>) -> (f64, f64, f64) {
+    // Removing the global phase gives U(0, 0) = exp(-i (alpha + gamma)/2) cos(beta/2)
+    // and U(1, 0) = exp(i (alpha - gamma)/2) sin(beta/2)
+    let determinant = unitary[[0, 0]] * unitary[[1, 1]] - unitary[[0, 1]] * unitary[[1, 0]];
+    let phase = Complex64::from_polar(1.0, -determinant.arg() / 2.0);
+    let beta = 2.0 * unitary[[1, 0]].norm().atan2(unitary[[0, 0]].norm());
+    let half_sum = if unitary[[0, 0]].norm() > TOLERANCE {
+        -(unitary[[0, 0]] * phase).arg()
+    } else {
+        0.0
+    };
+    if unitary[[1, 0]].norm() > TOLERANCE {
+        let half_difference = (unitary[[1, 0]] * phase).arg();
+        (half_sum + half_difference, beta, half_sum - half_difference)
+    } else {
+        // A diagonal unitary is a single rotation about the z-axis
+        (2.0 * half_sum, beta, 0.0)
+    }
+}
+
+/// Returns the unitary rotating the unit vector `from` onto the unit vector `to`.
+fn axis_rotation_unitary(from: &[f64; 3], to: &[f64; 3]) -> Array2<Complex64> {
+    let cross = [
+        from[1] * to[2] - from[2] * to[1],
+        from[2] * to[0] - from[0] * to[2],
+        from[0] * to[1] - from[1] * to[0],
+    ];
+    let dot = from[0] * to[0] + from[1] * to[1] + from[2] * to[2];
+    let norm = (cross[0].powi(2) + cross[1].powi(2) + cross[2].powi(2)).sqrt();
+    let axis = if norm > TOLERANCE {
+        [cross[0] / norm, cross[1] / norm, cross[2] / norm]
+    } else if dot > 0.0 {
+        return Array2::eye(2);
+    } else if from[0].abs() < 0.9 {
+        // Antiparallel vectors are rotated by pi about any perpendicular axis
+        let perpendicular = [0.0, from[2], -from[1]];
+        let norm = (perpendicular[1].powi(2) + perpendicular[2].powi(2)).sqrt();
+        [0.0, perpendicular[1] / norm, perpendicular[2] / norm]
+    } else {
+        let perpendicular = [from[1], -from[0], 0.0];
+        let norm = (perpendicular[0].powi(2) + perpendicular[1].powi(2)).sqrt();
+        [perpendicular[0] / norm, perpendicular[1] / norm, 0.0]
+    };
+    let angle = norm.atan2(dot);
+    let c = (angle / 2.0).cos();
+    let s = (angle / 2.0).sin();
+    array![
+        [
+            Complex64::new(c, -s * axis[2]),
+            Complex64::new(-s * axis[1], -s * axis[0])
+        ],
+        [
+            Complex64::new(s * axis[1], -s * axis[0]),
+            Complex64::new(c, s * axis[2])
+        ]
+    ]
+}
+
+/// Returns the conjugate transpose of a matrix.
+fn adjoint(matrix: &Array2<Complex64>) -> Array2<Complex64> {
+    matrix.t().mapv(|value| value.conj())
+}
+
+/// Maps an angle to the interval (-π, π].
+fn normalized_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Creates the error for an operation that cannot be decomposed into the gates of the device.
+fn not_decomposable(operation: &Operation, reason: &str) -> RoqoqoError {
+    RoqoqoError::GenericError {
+        msg: format!(
+            "Operation {} cannot be decomposed into the gates of the device: {}",
+            operation.hqslang(),
+            reason
+        ),
+    }
+}
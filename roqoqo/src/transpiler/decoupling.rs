@@ -0,0 +1,250 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Insertion of dynamical decoupling sequences into the idle windows of qubits.
+
+use super::{schedule_circuit, SchedulingPolicy};
+use crate::devices::Device;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use qoqo_calculator::CalculatorFloat;
+use std::collections::HashMap;
+
+/// The dynamical decoupling sequence inserted into idle windows.
+///
+/// The free time of an idle window is the length of the window minus the gate times of the pulses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecouplingSequence {
+    /// Two X pulses splitting the free time into three equal delays.
+    XX,
+    /// Two X pulses with delays of a quarter, a half and a quarter of the free time (Carr-Purcell-Meiboom-Gill).
+    CPMG,
+    /// The pulses X, Y, X, Y with delays of an eighth of the free time before and after the sequence
+    /// and of a quarter of the free time between the pulses.
+    XY4,
+}
+
+/// The gates implementing the pulses of a [DecouplingSequence].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecouplingPulse {
+    /// X and Y pulses are `PauliX` and `PauliY` gates.
+    Pauli,
+    /// X and Y pulses are `RotateX` and `RotateY` gates with the angle π.
+    Rotation,
+}
+
+/// Inserts dynamical decoupling sequences into the idle windows of the qubits of a circuit.
+///
+/// The circuit is scheduled with the gate times of the device (see [crate::transpiler::schedule_circuit]).
+/// Every window of at least `minimum_window` seconds in which no operation acts on a qubit is filled
+/// with the pulses of the sequence, separated by `PragmaSleep` operations so that the schedule of
+/// all other operations and the duration of the circuit are unchanged.
+/// A `PragmaSleep` acting only on the qubit is part of the window and is replaced by the sequence.
+/// All other operations acting on the qubit end a window, including a `PragmaSleep` on several qubits,
+/// which synchronises these qubits. Only the gaps before and after such a sleep are filled, so the
+/// filled windows can be shorter than the idle windows of [crate::transpiler::Schedule].
+/// Windows shorter than the gate times of the pulses are left empty.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit the sequences are inserted into.
+/// * `device` - The device providing the gate times.
+/// * `sequence` - The dynamical decoupling sequence.
+/// * `pulse` - The gates implementing the pulses of the sequence.
+/// * `minimum_window` - The minimal length (in seconds) of the idle windows filled with the sequence.
+/// * `policy` - Whether the circuit is scheduled as soon as possible (ASAP) or as late as possible (ALAP).
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit with the dynamical decoupling sequences.
+/// * `Err(RoqoqoError)` - The circuit cannot be scheduled on the device or a pulse is not available
+///   on a qubit with an idle window.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::AllToAllDevice;
+/// use roqoqo::operations::{PauliX, PragmaSleep, RotateX, CNOT};
+/// use roqoqo::transpiler::{
+///     insert_dynamical_decoupling, DecouplingPulse, DecouplingSequence, SchedulingPolicy,
+/// };
+///
+/// let device = AllToAllDevice::new(
+///     2,
+///     &["RotateX".to_string(), "PauliX".to_string()],
+///     &["CNOT".to_string()],
+///     1.0,
+/// )
+/// .set_all_single_qubit_gate_times("RotateX", 4.0)
+/// .set_all_single_qubit_gate_times("PauliX", 0.5);
+/// let mut circuit = Circuit::new();
+/// circuit += RotateX::new(0, 0.1.into());
+/// circuit += CNOT::new(0, 1);
+///
+/// let decoupled_circuit = insert_dynamical_decoupling(
+///     &circuit,
+///     &device,
+///     DecouplingSequence::CPMG,
+///     DecouplingPulse::Pauli,
+///     1.0,
+///     SchedulingPolicy::AsSoonAsPossible,
+/// )
+/// .unwrap();
+/// let mut expected = Circuit::new();
+/// expected += RotateX::new(0, 0.1.into());
+/// expected += PragmaSleep::new(vec![1], 0.75.into());
+/// expected += PauliX::new(1);
+/// expected += PragmaSleep::new(vec![1], 1.5.into());
+/// expected += PauliX::new(1);
+/// expected += PragmaSleep::new(vec![1], 0.75.into());
+/// expected += CNOT::new(0, 1);
+/// assert_eq!(decoupled_circuit, expected);
+/// ```
+pub fn insert_dynamical_decoupling(
+    circuit: &Circuit,
+    device: &dyn Device,
+    sequence: DecouplingSequence,
+    pulse: DecouplingPulse,
+    minimum_window: f64,
+    policy: SchedulingPolicy,
+) -> Result<Circuit, RoqoqoError> {
+    let schedule = schedule_circuit(circuit, device, policy)?;
+    let mut qubits: Vec<usize> = schedule.idle_windows.keys().copied().collect();
+    qubits.sort_unstable();
+
+    // The sequences inserted before each operation; the last entry is inserted at the end of the circuit
+    let number_operations = schedule.operations.len();
+    let mut decoupling: Vec<Vec<Operation>> = vec![Vec::new(); number_operations + 1];
+    // The end time of the last operation acting on each qubit
+    let mut free_since: HashMap<usize, f64> = HashMap::new();
+    // The single-qubit sleeps in the current window of each qubit
+    let mut window_sleeps: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut replaced_sleeps: Vec<bool> = vec![false; number_operations];
+    for (index, scheduled) in schedule.operations.iter().enumerate() {
+        if let Operation::PragmaSleep(sleep) = &scheduled.operation {
+            if let [qubit] = sleep.qubits().as_slice() {
+                window_sleeps.entry(*qubit).or_default().push(index);
+                continue;
+            }
+        }
+        let involved_qubits: Vec<usize> = match scheduled.operation.involved_qubits() {
+            InvolvedQubits::None => continue,
+            InvolvedQubits::All => qubits.clone(),
+            InvolvedQubits::Set(involved_qubits) => {
+                let mut involved_qubits: Vec<usize> = involved_qubits.into_iter().collect();
+                involved_qubits.sort_unstable();
+                involved_qubits
+            }
+        };
+        for qubit in involved_qubits {
+            let start = free_since.insert(qubit, scheduled.end_time).unwrap_or(0.0);
+            let window = scheduled.start_time - start;
+            let sleeps = window_sleeps.remove(&qubit).unwrap_or_default();
+            if window > 0.0 && window >= minimum_window {
+                let operations = decoupling_operations(device, qubit, window, sequence, pulse)?;
+                if !operations.is_empty() {
+                    sleeps
+                        .into_iter()
+                        .for_each(|sleep| replaced_sleeps[sleep] = true);
+                }
+                decoupling[index].extend(operations);
+            }
+        }
+    }
+    for qubit in qubits.iter() {
+        let window = schedule.duration - free_since.get(qubit).copied().unwrap_or(0.0);
+        let sleeps = window_sleeps.remove(qubit).unwrap_or_default();
+        if window > 0.0 && window >= minimum_window {
+            let operations = decoupling_operations(device, *qubit, window, sequence, pulse)?;
+            if !operations.is_empty() {
+                sleeps
+                    .into_iter()
+                    .for_each(|sleep| replaced_sleeps[sleep] = true);
+            }
+            decoupling[number_operations].extend(operations);
+        }
+    }
+
+    let mut decoupled_circuit = Circuit::new();
+    for ((scheduled, decoupling_operations), replaced) in schedule
+        .operations
+        .iter()
+        .zip(decoupling.iter())
+        .zip(replaced_sleeps)
+    {
+        for operation in decoupling_operations {
+            decoupled_circuit.add_operation(operation.clone());
+        }
+        if !replaced {
+            decoupled_circuit.add_operation(scheduled.operation.clone());
+        }
+    }
+    for operation in decoupling[number_operations].iter() {
+        decoupled_circuit.add_operation(operation.clone());
+    }
+    Ok(decoupled_circuit)
+}
+
+/// Returns the pulses and sleeps of a dynamical decoupling sequence filling an idle window of a qubit.
+fn decoupling_operations(
+    device: &dyn Device,
+    qubit: usize,
+    window: f64,
+    sequence: DecouplingSequence,
+    pulse: DecouplingPulse,
+) -> Result<Vec<Operation>, RoqoqoError> {
+    let (x_pulse, y_pulse): (Operation, Operation) = match pulse {
+        DecouplingPulse::Pauli => (PauliX::new(qubit).into(), PauliY::new(qubit).into()),
+        DecouplingPulse::Rotation => (
+            RotateX::new(qubit, CalculatorFloat::PI).into(),
+            RotateY::new(qubit, CalculatorFloat::PI).into(),
+        ),
+    };
+    let (pulses, delays) = match sequence {
+        DecouplingSequence::XX => (vec![x_pulse.clone(), x_pulse], vec![1.0 / 3.0; 3]),
+        DecouplingSequence::CPMG => (vec![x_pulse.clone(), x_pulse], vec![0.25, 0.5, 0.25]),
+        DecouplingSequence::XY4 => (
+            vec![x_pulse.clone(), y_pulse.clone(), x_pulse, y_pulse],
+            vec![0.125, 0.25, 0.25, 0.25, 0.125],
+        ),
+    };
+    let mut pulse_time = 0.0;
+    for pulse in pulses.iter() {
+        pulse_time += device
+            .single_qubit_gate_time(pulse.hqslang(), &qubit)
+            .ok_or_else(|| RoqoqoError::GenericError {
+                msg: format!(
+                    "Gate {} on qubit {} is not available on the device",
+                    pulse.hqslang(),
+                    qubit
+                ),
+            })?;
+    }
+    let free_time = window - pulse_time;
+    if free_time < 0.0 {
+        return Ok(Vec::new());
+    }
+
+    let mut operations: Vec<Operation> = Vec::new();
+    for (index, delay) in delays.iter().enumerate() {
+        if free_time * delay > 0.0 {
+            operations.push(PragmaSleep::new(vec![qubit], (free_time * delay).into()).into());
+        }
+        if let Some(pulse) = pulses.get(index) {
+            operations.push(pulse.clone());
+        }
+    }
+    Ok(operations)
+}
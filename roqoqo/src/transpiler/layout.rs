@@ -0,0 +1,390 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::distances;
+use crate::devices::Device;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use std::collections::{BTreeSet, HashMap};
+
+/// The number of two-qubit gates a SWAP is decomposed into.
+const SWAP_GATES: f64 = 3.0;
+
+/// Scores the placement of the qubits of a circuit on the qubits of a device.
+///
+/// Implement this trait to use a custom fidelity model in [select_layout].
+pub trait LayoutCost {
+    /// Returns the cost of executing a circuit with a layout on a device.
+    ///
+    /// A lower cost is better, layouts that cannot be executed should have an infinite cost.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit that is executed.
+    /// * `device` - The device the circuit is executed on.
+    /// * `layout` - The device qubit of every qubit of the circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `f64` - The cost of the layout.
+    fn layout_cost(
+        &self,
+        circuit: &Circuit,
+        device: &dyn Device,
+        layout: &HashMap<usize, usize>,
+    ) -> f64;
+}
+
+/// Scores layouts with the decoherence of the used device qubits during the circuit.
+///
+/// The cost of a layout is the duration of the circuit multiplied by the sum of the traces of the
+/// [Device::qubit_decoherence_rates] of the device qubits the circuit is placed on.
+/// The duration is the largest total gate time of a single device qubit, using the gate times of the device.
+/// A two-qubit gate between qubits that are not connected adds the time of the SWAP gates
+/// (each three two-qubit gates with the average two-qubit gate time of the device) that are needed to connect them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoherenceLayoutCost;
+
+impl LayoutCost for DecoherenceLayoutCost {
+    fn layout_cost(
+        &self,
+        circuit: &Circuit,
+        device: &dyn Device,
+        layout: &HashMap<usize, usize>,
+    ) -> f64 {
+        let number_qubits = device.number_qubits();
+        if layout.values().any(|physical| *physical >= number_qubits) {
+            return f64::INFINITY;
+        }
+        let edges = device.two_qubit_edges();
+        let distances = distances(number_qubits, &edges);
+        let two_qubit_gate_times: Vec<f64> = edges
+            .iter()
+            .filter_map(|(first, second)| two_qubit_gate_time(device, None, *first, *second))
+            .collect();
+        let average_two_qubit_gate_time = if two_qubit_gate_times.is_empty() {
+            0.0
+        } else {
+            two_qubit_gate_times.iter().sum::<f64>() / two_qubit_gate_times.len() as f64
+        };
+
+        let mut busy_time = vec![0.0; number_qubits];
+        for operation in circuit.iter() {
+            let qubits = match gate_qubits(operation) {
+                Some(qubits) => qubits
+                    .iter()
+                    .map(|qubit| layout.get(qubit).copied())
+                    .collect::<Option<Vec<usize>>>(),
+                None => continue,
+            };
+            let Some(qubits) = qubits else {
+                return f64::INFINITY;
+            };
+            let hqslang = operation.hqslang();
+            let time = match qubits.as_slice() {
+                [qubit] => device
+                    .single_qubit_gate_time(hqslang, qubit)
+                    .or_else(|| {
+                        device
+                            .single_qubit_gate_names()
+                            .iter()
+                            .filter_map(|name| device.single_qubit_gate_time(name, qubit))
+                            .reduce(f64::min)
+                    })
+                    .unwrap_or(0.0),
+                [first, second] => match distances[*first][*second] {
+                    usize::MAX => return f64::INFINITY,
+                    1 => two_qubit_gate_time(device, Some(hqslang), *first, *second)
+                        .unwrap_or(average_two_qubit_gate_time),
+                    distance => {
+                        (SWAP_GATES * (distance - 1) as f64 + 1.0) * average_two_qubit_gate_time
+                    }
+                },
+                [control_0, control_1, target] => device
+                    .three_qubit_gate_time(hqslang, control_0, control_1, target)
+                    .unwrap_or(0.0),
+                _ => device
+                    .multi_qubit_gate_time(hqslang, &qubits)
+                    .unwrap_or(0.0),
+            };
+            for qubit in qubits {
+                busy_time[qubit] += time;
+            }
+        }
+        let duration = busy_time.iter().copied().fold(0.0, f64::max);
+        let decoherence: f64 = layout
+            .values()
+            .filter_map(|physical| device.qubit_decoherence_rates(physical))
+            .map(|rates| rates.diag().sum())
+            .sum();
+        duration * decoherence
+    }
+}
+
+/// Selects the qubits of a device a circuit is executed on.
+///
+/// Candidate layouts are constructed by placing the qubits of the circuit one after the other,
+/// starting from every qubit of the device, each qubit next to the qubits it interacts with most.
+/// The candidate with the lowest cost is then improved by moving qubits to neighbouring device qubits
+/// (exchanging them with the qubits placed there) as long as the cost decreases.
+/// Layouts with the same cost are compared by the number of SWAP gates needed to connect the interacting qubits.
+///
+/// The returned mapping is a permutation that can be used with [crate::Circuit::remap_qubits]:
+/// every qubit of the circuit is mapped to its device qubit and the remaining qubits fill the free places.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is placed on the device.
+/// * `device` - The device the circuit is executed on.
+/// * `cost` - The cost model scoring the layouts, for example [DecoherenceLayoutCost].
+///
+/// # Returns
+///
+/// * `Ok(HashMap<usize, usize>)` - The mapping from the qubits of the circuit to the qubits of the device.
+/// * `Err(RoqoqoError::GenericError)` - The circuit has more qubits than the device.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::SquareLatticeDevice;
+/// use roqoqo::operations::CNOT;
+/// use roqoqo::transpiler::{select_layout, DecoherenceLayoutCost};
+///
+/// let device = SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0);
+/// let mut circuit = Circuit::new();
+/// circuit += CNOT::new(0, 2);
+/// let layout = select_layout(&circuit, &device, &DecoherenceLayoutCost).unwrap();
+/// let distance = layout[&0].abs_diff(layout[&2]);
+/// assert_eq!(distance, 1);
+/// ```
+pub fn select_layout(
+    circuit: &Circuit,
+    device: &dyn Device,
+    cost: &dyn LayoutCost,
+) -> Result<HashMap<usize, usize>, RoqoqoError> {
+    let number_qubits = device.number_qubits();
+    let mut qubits: BTreeSet<usize> = BTreeSet::new();
+    let mut weights: HashMap<(usize, usize), usize> = HashMap::new();
+    for operation in circuit.iter() {
+        if let InvolvedQubits::Set(involved) = operation.involved_qubits() {
+            qubits.extend(involved);
+        }
+        if let Some(gate) = gate_qubits(operation) {
+            for (index, first) in gate.iter().enumerate() {
+                for second in gate[index + 1..].iter() {
+                    *weights
+                        .entry((*first.min(second), *first.max(second)))
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    let qubits: Vec<usize> = qubits.into_iter().collect();
+    if qubits.len() > number_qubits {
+        return Err(RoqoqoError::GenericError {
+            msg: format!(
+                "The circuit acts on {} qubits, but the device only has {number_qubits} qubits",
+                qubits.len()
+            ),
+        });
+    }
+    let distances = distances(number_qubits, &device.two_qubit_edges());
+    let decoherence: Vec<f64> = (0..number_qubits)
+        .map(|physical| {
+            device
+                .qubit_decoherence_rates(&physical)
+                .map_or(0.0, |rates| rates.diag().sum())
+        })
+        .collect();
+    let weight = |first: usize, second: usize| {
+        weights
+            .get(&(first.min(second), first.max(second)))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    // Layouts with the same cost are compared by the number of SWAPs needed to connect the qubits
+    let score = |layout: &HashMap<usize, usize>| -> (f64, usize) {
+        let swaps = weights
+            .iter()
+            .map(|((first, second), count)| {
+                distances[layout[first]][layout[second]]
+                    .saturating_sub(1)
+                    .saturating_mul(*count)
+            })
+            .fold(0usize, usize::saturating_add);
+        (cost.layout_cost(circuit, device, layout), swaps)
+    };
+    let better = |score: (f64, usize), other: (f64, usize)| {
+        score.0 < other.0 || (score.0 == other.0 && score.1 < other.1)
+    };
+
+    let mut best: Option<(HashMap<usize, usize>, (f64, usize))> = None;
+    let mut consider = |layout: HashMap<usize, usize>| {
+        let layout_score = score(&layout);
+        if best
+            .as_ref()
+            .map_or(true, |(_, best_score)| better(layout_score, *best_score))
+        {
+            best = Some((layout, layout_score));
+        }
+    };
+    for start in 0..number_qubits {
+        consider(greedy_layout(
+            &qubits,
+            start,
+            &weight,
+            &distances,
+            &decoherence,
+        ));
+    }
+    if qubits.iter().all(|qubit| *qubit < number_qubits) {
+        consider(qubits.iter().map(|qubit| (*qubit, *qubit)).collect());
+    }
+    let Some((mut layout, mut layout_score)) = best else {
+        return Ok(HashMap::new());
+    };
+
+    // Local search moving single qubits of the circuit to neighbouring device qubits
+    for _ in 0..qubits.len() {
+        let mut improvement: Option<(HashMap<usize, usize>, (f64, usize))> = None;
+        for qubit in qubits.iter() {
+            let neighbours = (0..number_qubits)
+                .filter(|physical| distances[layout[qubit]][*physical] == 1)
+                .collect::<Vec<usize>>();
+            for physical in neighbours {
+                let mut candidate = layout.clone();
+                if let Some(other) = qubits.iter().find(|other| layout[*other] == physical) {
+                    candidate.insert(*other, layout[qubit]);
+                }
+                candidate.insert(*qubit, physical);
+                let candidate_score = score(&candidate);
+                let best_score = improvement
+                    .as_ref()
+                    .map_or(layout_score, |(_, improved_score)| *improved_score);
+                if better(candidate_score, best_score) {
+                    improvement = Some((candidate, candidate_score));
+                }
+            }
+        }
+        match improvement {
+            Some((improved, improved_score)) => {
+                layout = improved;
+                layout_score = improved_score;
+            }
+            None => break,
+        }
+    }
+
+    Ok(complete_permutation(&layout))
+}
+
+/// Places the qubits of a circuit one after the other next to the qubits they interact with most.
+fn greedy_layout(
+    qubits: &[usize],
+    start: usize,
+    weight: &dyn Fn(usize, usize) -> usize,
+    distances: &[Vec<usize>],
+    decoherence: &[f64],
+) -> HashMap<usize, usize> {
+    let total_weight = |qubit: usize, others: &[usize]| -> usize {
+        others.iter().map(|other| weight(qubit, *other)).sum()
+    };
+    let mut layout: HashMap<usize, usize> = HashMap::new();
+    let mut placed: Vec<usize> = Vec::new();
+    let mut free: Vec<bool> = vec![true; distances.len()];
+    while placed.len() < qubits.len() {
+        let qubit = qubits
+            .iter()
+            .copied()
+            .filter(|qubit| !layout.contains_key(qubit))
+            .max_by_key(|qubit| {
+                (
+                    total_weight(*qubit, &placed),
+                    total_weight(*qubit, qubits),
+                    std::cmp::Reverse(*qubit),
+                )
+            })
+            .expect("Unplaced qubit missing");
+        let physical = if placed.is_empty() {
+            start
+        } else {
+            let spread = |physical: usize| -> f64 {
+                placed
+                    .iter()
+                    .map(|other| {
+                        let distance = distances[physical][layout[other]];
+                        let distance = if distance == usize::MAX {
+                            distances.len() as f64
+                        } else {
+                            distance as f64
+                        };
+                        weight(qubit, *other) as f64 * distance
+                    })
+                    .sum()
+            };
+            (0..distances.len())
+                .filter(|physical| free[*physical])
+                .min_by(|first, second| {
+                    (spread(*first), decoherence[*first])
+                        .partial_cmp(&(spread(*second), decoherence[*second]))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .expect("Free device qubit missing")
+        };
+        free[physical] = false;
+        layout.insert(qubit, physical);
+        placed.push(qubit);
+    }
+    layout
+}
+
+/// Extends a layout to a permutation mapping the device qubits that are not used to the free qubits.
+fn complete_permutation(layout: &HashMap<usize, usize>) -> HashMap<usize, usize> {
+    let mut permutation = layout.clone();
+    let mut unused_keys: Vec<usize> = layout
+        .values()
+        .copied()
+        .filter(|physical| !layout.contains_key(physical))
+        .collect();
+    let mut unused_values: Vec<usize> = layout
+        .keys()
+        .copied()
+        .filter(|qubit| !layout.values().any(|physical| physical == qubit))
+        .collect();
+    unused_keys.sort_unstable();
+    unused_values.sort_unstable();
+    permutation.extend(unused_keys.into_iter().zip(unused_values));
+    permutation
+}
+
+/// Returns the gate time of a two-qubit gate, or the shortest time of the two-qubit gates of the device.
+fn two_qubit_gate_time(
+    device: &dyn Device,
+    hqslang: Option<&str>,
+    first: usize,
+    second: usize,
+) -> Option<f64> {
+    let time = |name: &str| {
+        device
+            .two_qubit_gate_time(name, &first, &second)
+            .or_else(|| device.two_qubit_gate_time(name, &second, &first))
+    };
+    hqslang.and_then(time).or_else(|| {
+        device
+            .two_qubit_gate_names()
+            .iter()
+            .filter_map(|name| time(name))
+            .reduce(f64::min)
+    })
+}
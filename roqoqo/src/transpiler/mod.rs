@@ -0,0 +1,81 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transformations of roqoqo [crate::Circuit]s for the execution on a [crate::devices::Device].
+//!
+//! Circuits are usually written with the full set of roqoqo gates and for all-to-all connectivity.
+//! The passes in this module rewrite a circuit into a form a device can execute.
+//!
+//! * [decompose_to_device] rewrites all gates into the gates available on a device.
+//! * [select_layout] chooses the qubits of a device the qubits of a circuit are placed on.
+//! * [route_to_device] inserts SWAP gates so that all two-qubit gates act on connected qubits of a device.
+//! * [schedule_circuit] computes the start and end times of the operations and the idle windows of the qubits
+//!   from the gate times of a device.
+//! * [insert_dynamical_decoupling] fills the idle windows of the qubits with dynamical decoupling sequences.
+//! * [apply_noise_models] inserts the noise of [crate::noise_models::NoiseModel]s as explicit noise operations.
+//!
+//! The optimisation passes remove redundant gates and return an [OptimizationReport] of the combined operations.
+//!
+//! * [cancel_inverse_gates] removes adjacent pairs of gates that are the inverse of each other.
+//! * [merge_rotations] merges adjacent rotations about the same axis.
+//! * [fuse_single_qubit_gates] fuses runs of single-qubit gates into one [crate::operations::SingleQubitGate].
+
+use std::collections::VecDeque;
+
+mod decomposition;
+mod decoupling;
+mod layout;
+mod noise;
+mod optimization;
+#[cfg(feature = "circuitdag")]
+mod routing;
+mod scheduling;
+
+pub use decomposition::decompose_to_device;
+pub use decoupling::{insert_dynamical_decoupling, DecouplingPulse, DecouplingSequence};
+pub use layout::{select_layout, DecoherenceLayoutCost, LayoutCost};
+pub use noise::apply_noise_models;
+pub use optimization::{
+    cancel_inverse_gates, fuse_single_qubit_gates, merge_rotations, OptimizationReport,
+};
+#[cfg(feature = "circuitdag")]
+pub use routing::route_to_device;
+pub use scheduling::{schedule_circuit, Schedule, ScheduledOperation, SchedulingPolicy};
+
+/// Returns the length of the shortest path between all pairs of qubits of a device.
+///
+/// Qubits that are not connected have the distance `usize::MAX`.
+fn distances(number_qubits: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); number_qubits];
+    for (first, second) in edges.iter().copied() {
+        if first < number_qubits && second < number_qubits && first != second {
+            neighbours[first].push(second);
+            neighbours[second].push(first);
+        }
+    }
+    (0..number_qubits)
+        .map(|start| {
+            let mut distance = vec![usize::MAX; number_qubits];
+            distance[start] = 0;
+            let mut queue = VecDeque::from([start]);
+            while let Some(qubit) = queue.pop_front() {
+                for neighbour in neighbours[qubit].iter().copied() {
+                    if distance[neighbour] == usize::MAX {
+                        distance[neighbour] = distance[qubit] + 1;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            distance
+        })
+        .collect()
+}
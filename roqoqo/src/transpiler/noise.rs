@@ -0,0 +1,428 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Application of noise models to circuits as explicit noise operations.
+
+use super::{schedule_circuit, SchedulingPolicy};
+use crate::devices::Device;
+use crate::noise_models::{
+    DecoherenceOnGateModel, ImperfectReadoutModel, NoiseModel, SingleQubitOverrotationDescription,
+    SingleQubitOverrotationOnGate,
+};
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use ndarray::{array, Array2};
+use qoqo_calculator::CalculatorFloat;
+use std::collections::HashMap;
+use struqture::prelude::*;
+use struqture::spins::{PlusMinusLindbladNoiseOperator, PlusMinusProduct, SinglePlusMinusOperator};
+
+/// Rates below this fraction of the largest rate of a qubit are treated as zero.
+const RELATIVE_TOLERANCE: f64 = 1e-12;
+
+/// The 3x3 Lindblad rate matrices of the qubits, with L0 = σ+, L1 = σ- and L2 = σz.
+type QubitRates = HashMap<usize, Array2<f64>>;
+
+/// Returns a circuit with the noise of noise models inserted as explicit noise operations.
+///
+/// The circuit is scheduled as soon as possible with the gate times of the device
+/// (see [crate::transpiler::schedule_circuit]). The noise models are applied as follows:
+///
+/// * [crate::noise_models::ContinuousDecoherenceModel] - noise for the gate time on the qubits of every gate
+///   and for the idle windows of every qubit.
+/// * [crate::noise_models::DecoherenceOnGateModel] - noise for the gate time on the qubits of the gates with an error.
+/// * [crate::noise_models::DecoherenceOnIdleModel] - noise for the idle windows of every qubit.
+/// * [crate::noise_models::SingleQubitOverrotationOnGate] - a `PragmaOverrotation` and the overrotation gate
+///   with the mean angle after the gates with an overrotation.
+/// * [crate::noise_models::ImperfectReadoutModel] - a noise operation flipping the populations of the qubit
+///   with the readout error probabilities before `MeasureQubit` and before `PragmaRepeatedMeasurement`
+///   on the qubits in its qubit mapping, or without a mapping on the qubits `0..n` of its readout register of length `n`.
+///   The flip is a physical bit flip of the qubit before the measurement, not an error of the recorded bit,
+///   so it also changes the state after the measurement and the bits used by later `PragmaConditional` operations.
+///
+/// Gate noise is inserted after the gate. Idle noise is inserted before the operation ending the idle window.
+/// The Lindblad rates of a qubit are inserted as `PragmaDamping`, `PragmaDephasing` and `PragmaDepolarising`
+/// when they can be expressed by these operations and as `PragmaGeneralNoise` otherwise.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit the noise is applied to.
+/// * `device` - The device providing the gate times.
+/// * `noise_models` - The noise models that are applied.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit with the explicit noise operations.
+/// * `Err(RoqoqoError)` - The circuit cannot be scheduled on the device, a noise model contains multi-qubit
+///   or complex Lindblad terms, an overrotation gate is not a single-qubit rotation or readout error probabilities
+///   cannot be represented as a noise operation.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::AllToAllDevice;
+/// use roqoqo::noise_models::{ContinuousDecoherenceModel, NoiseModel};
+/// use roqoqo::operations::{PragmaDamping, RotateX};
+/// use roqoqo::transpiler::apply_noise_models;
+///
+/// let device = AllToAllDevice::new(1, &["RotateX".to_string()], &[], 1.0)
+///     .set_all_single_qubit_gate_times("RotateX", 2.0);
+/// let noise_model = ContinuousDecoherenceModel::new().add_damping_rate(&[0], 0.1);
+/// let mut circuit = Circuit::new();
+/// circuit += RotateX::new(0, 0.1.into());
+///
+/// let noisy_circuit =
+///     apply_noise_models(&circuit, &device, &[NoiseModel::from(noise_model)]).unwrap();
+/// let mut expected = Circuit::new();
+/// expected += RotateX::new(0, 0.1.into());
+/// expected += PragmaDamping::new(0, 2.0.into(), 0.1.into());
+/// assert_eq!(noisy_circuit, expected);
+/// ```
+pub fn apply_noise_models(
+    circuit: &Circuit,
+    device: &dyn Device,
+    noise_models: &[NoiseModel],
+) -> Result<Circuit, RoqoqoError> {
+    let schedule = schedule_circuit(circuit, device, SchedulingPolicy::AsSoonAsPossible)?;
+
+    let mut continuous_rates = QubitRates::new();
+    let mut idle_rates = QubitRates::new();
+    let mut gate_models: Vec<&DecoherenceOnGateModel> = Vec::new();
+    let mut overrotation_models: Vec<&SingleQubitOverrotationOnGate> = Vec::new();
+    let mut readout_models: Vec<&ImperfectReadoutModel> = Vec::new();
+    for noise_model in noise_models {
+        match noise_model {
+            NoiseModel::ContinuousDecoherenceModel(model) => {
+                let rates = qubit_rates(&model.lindblad_noise)?;
+                add_rates(&mut continuous_rates, &rates);
+                add_rates(&mut idle_rates, &rates);
+            }
+            NoiseModel::DecoherenceOnIdleModel(model) => {
+                add_rates(&mut idle_rates, &qubit_rates(&model.lindblad_noise)?);
+            }
+            NoiseModel::DecoherenceOnGateModel(model) => gate_models.push(model),
+            NoiseModel::SingleQubitOverrotationOnGate(model) => overrotation_models.push(model),
+            NoiseModel::ImperfectReadoutModel(model) => readout_models.push(model),
+        }
+    }
+
+    let mut qubits: Vec<usize> = schedule.idle_windows.keys().copied().collect();
+    qubits.sort_unstable();
+
+    // The idle noise inserted before each operation; the last entry is inserted at the end of the circuit
+    let number_operations = schedule.operations.len();
+    let mut idle_noise: Vec<Vec<Operation>> = vec![Vec::new(); number_operations + 1];
+    for qubit in qubits.iter().copied() {
+        let Some(rates) = idle_rates.get(&qubit) else {
+            continue;
+        };
+        for (start, end) in schedule.idle_windows[&qubit].iter().copied() {
+            let index = schedule
+                .operations
+                .iter()
+                .position(|scheduled| {
+                    scheduled.start_time >= end && involves_qubit(&scheduled.operation, qubit)
+                })
+                .unwrap_or(number_operations);
+            idle_noise[index].extend(noise_operations(qubit, end - start, rates));
+        }
+    }
+
+    let register_lengths: HashMap<&str, usize> = circuit
+        .definitions()
+        .iter()
+        .filter_map(|definition| match definition {
+            Operation::DefinitionBit(register) => {
+                Some((register.name().as_str(), *register.length()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut noisy_circuit = Circuit::new();
+    for (scheduled, idle_operations) in schedule.operations.iter().zip(idle_noise.iter()) {
+        for operation in idle_operations {
+            noisy_circuit.add_operation(operation.clone());
+        }
+        let operation = &scheduled.operation;
+        let measured_qubits = match operation {
+            Operation::MeasureQubit(measurement) => vec![*measurement.qubit()],
+            Operation::PragmaRepeatedMeasurement(measurement) if !readout_models.is_empty() => {
+                repeated_measurement_qubits(measurement, &register_lengths)?
+            }
+            _ => Vec::new(),
+        };
+        for model in readout_models.iter() {
+            for qubit in measured_qubits.iter() {
+                for readout_operation in readout_noise(model, *qubit)? {
+                    noisy_circuit.add_operation(readout_operation);
+                }
+            }
+        }
+        noisy_circuit.add_operation(operation.clone());
+
+        let Some(gate_qubits) = gate_qubits(operation) else {
+            continue;
+        };
+        let hqslang = operation.hqslang();
+        for model in overrotation_models.iter() {
+            for gate_operation in overrotation(model, hqslang, &gate_qubits)? {
+                noisy_circuit.add_operation(gate_operation);
+            }
+        }
+        let mut gate_rates = QubitRates::new();
+        for qubit in gate_qubits.iter() {
+            if let Some(rates) = continuous_rates.get(qubit) {
+                gate_rates.insert(*qubit, rates.clone());
+            }
+        }
+        for model in gate_models.iter() {
+            let gate_error = match gate_qubits.as_slice() {
+                [qubit] => model.get_single_qubit_gate_error(hqslang, *qubit),
+                [control, target] => model.get_two_qubit_gate_error(hqslang, *control, *target),
+                [control_0, control_1, target] => {
+                    model.get_three_qubit_gate_error(hqslang, *control_0, *control_1, *target)
+                }
+                _ => model.get_multi_qubit_gate_error(hqslang, gate_qubits.clone()),
+            };
+            if let Some(gate_error) = gate_error {
+                add_rates(&mut gate_rates, &qubit_rates(gate_error)?);
+            }
+        }
+        let gate_time = scheduled.end_time - scheduled.start_time;
+        let mut noisy_qubits: Vec<usize> = gate_rates.keys().copied().collect();
+        noisy_qubits.sort_unstable();
+        for qubit in noisy_qubits {
+            for noise_operation in noise_operations(qubit, gate_time, &gate_rates[&qubit]) {
+                noisy_circuit.add_operation(noise_operation);
+            }
+        }
+    }
+    for operation in idle_noise[number_operations].iter() {
+        noisy_circuit.add_operation(operation.clone());
+    }
+    Ok(noisy_circuit)
+}
+
+/// Returns true when an operation acts on a qubit or on all qubits.
+fn involves_qubit(operation: &Operation, qubit: usize) -> bool {
+    match operation.involved_qubits() {
+        InvolvedQubits::None => false,
+        InvolvedQubits::All => true,
+        InvolvedQubits::Set(qubits) => qubits.contains(&qubit),
+    }
+}
+
+/// Adds the rate matrices of qubits to the rate matrices of other qubits.
+fn add_rates(rates: &mut QubitRates, other: &QubitRates) {
+    for (qubit, other_rates) in other.iter() {
+        *rates.entry(*qubit).or_insert_with(|| Array2::zeros((3, 3))) += other_rates;
+    }
+}
+
+/// Returns the rate matrices of the qubits of a Lindblad noise operator with single-qubit terms.
+fn qubit_rates(noise: &PlusMinusLindbladNoiseOperator) -> Result<QubitRates, RoqoqoError> {
+    let mut rates = QubitRates::new();
+    for ((left, right), value) in noise.iter() {
+        let (left_qubit, row) = lindblad_index(left)?;
+        let (right_qubit, column) = lindblad_index(right)?;
+        if left_qubit != right_qubit {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Lindblad term ({}, {}) acts on several qubits and cannot be applied as single-qubit noise",
+                    left, right
+                ),
+            });
+        }
+        if f64::try_from(value.im.clone())? != 0.0 {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Lindblad term ({}, {}) has a complex rate and cannot be applied as noise operation",
+                    left, right
+                ),
+            });
+        }
+        rates
+            .entry(left_qubit)
+            .or_insert_with(|| Array2::zeros((3, 3)))[(row, column)] +=
+            f64::try_from(value.re.clone())?;
+    }
+    Ok(rates)
+}
+
+/// Returns the qubit and the Lindblad operator index (σ+: 0, σ-: 1, σz: 2) of a single-qubit product.
+fn lindblad_index(product: &PlusMinusProduct) -> Result<(usize, usize), RoqoqoError> {
+    let mut operators = product.iter();
+    match (operators.next(), operators.next()) {
+        (Some((qubit, SinglePlusMinusOperator::Plus)), None) => Ok((*qubit, 0)),
+        (Some((qubit, SinglePlusMinusOperator::Minus)), None) => Ok((*qubit, 1)),
+        (Some((qubit, SinglePlusMinusOperator::Z)), None) => Ok((*qubit, 2)),
+        _ => Err(RoqoqoError::GenericError {
+            msg: format!(
+                "Lindblad operator {} is not a single-qubit operator and cannot be applied as single-qubit noise",
+                product
+            ),
+        }),
+    }
+}
+
+/// Returns the noise operations applying Lindblad rates to a qubit for a time.
+///
+/// Damping and dephasing, and depolarising and additional dephasing, are applied by the dedicated
+/// noise operations, all other rates by a `PragmaGeneralNoise`.
+fn noise_operations(qubit: usize, time: f64, rates: &Array2<f64>) -> Vec<Operation> {
+    let largest_rate = rates
+        .iter()
+        .fold(0.0, |largest, rate| f64::max(largest, rate.abs()));
+    if time <= 0.0 || largest_rate == 0.0 {
+        return Vec::new();
+    }
+    let tolerance = largest_rate * RELATIVE_TOLERANCE;
+    let (damping, excitation, dephasing) = (rates[(0, 0)], rates[(1, 1)], rates[(2, 2)]);
+    let diagonal = (0..3)
+        .all(|row| (0..3).all(|column| row == column || rates[(row, column)].abs() <= tolerance));
+    let mut operations: Vec<Operation> = Vec::new();
+    if diagonal && damping > -tolerance && dephasing > -tolerance && excitation.abs() <= tolerance {
+        if damping > tolerance {
+            operations.push(PragmaDamping::new(qubit, time.into(), damping.into()).into());
+        }
+        if dephasing > tolerance {
+            operations.push(PragmaDephasing::new(qubit, time.into(), dephasing.into()).into());
+        }
+    } else if diagonal
+        && (damping - excitation).abs() <= tolerance
+        && dephasing - damping / 2.0 > -tolerance
+        && damping > 0.0
+    {
+        operations.push(PragmaDepolarising::new(qubit, time.into(), (2.0 * damping).into()).into());
+        if dephasing - damping / 2.0 > tolerance {
+            operations.push(
+                PragmaDephasing::new(qubit, time.into(), (dephasing - damping / 2.0).into()).into(),
+            );
+        }
+    } else {
+        operations.push(PragmaGeneralNoise::new(qubit, time.into(), rates.clone()).into());
+    }
+    operations
+}
+
+/// Returns the qubits read out by a `PragmaRepeatedMeasurement`.
+///
+/// Without a qubit mapping, qubit `i` is read out into bit `i` of the readout register.
+fn repeated_measurement_qubits(
+    measurement: &PragmaRepeatedMeasurement,
+    register_lengths: &HashMap<&str, usize>,
+) -> Result<Vec<usize>, RoqoqoError> {
+    if let Some(mapping) = measurement.qubit_mapping() {
+        let mut qubits: Vec<usize> = mapping.keys().copied().collect();
+        qubits.sort_unstable();
+        return Ok(qubits);
+    }
+    register_lengths
+        .get(measurement.readout().as_str())
+        .map(|length| (0..*length).collect())
+        .ok_or_else(|| RoqoqoError::GenericError {
+            msg: format!(
+                "Readout register {} of PragmaRepeatedMeasurement is not defined",
+                measurement.readout()
+            ),
+        })
+}
+
+/// Returns the noise operations flipping the populations of a qubit with the readout error probabilities.
+///
+/// Damping with rate γ·p(1→0)/p and excitation with rate γ·p(0→1)/p for the time 1,
+/// where p = p(0→1) + p(1→0) and exp(-γ) = 1 - p, flip |1> to |0> with probability p(1→0)
+/// and |0> to |1> with probability p(0→1).
+fn readout_noise(
+    model: &ImperfectReadoutModel,
+    qubit: usize,
+) -> Result<Vec<Operation>, RoqoqoError> {
+    let detect_0_as_1 = model.prob_detect_0_as_1(&qubit);
+    let detect_1_as_0 = model.prob_detect_1_as_0(&qubit);
+    let total = detect_0_as_1 + detect_1_as_0;
+    if total == 0.0 {
+        return Ok(Vec::new());
+    }
+    if total >= 1.0 {
+        return Err(RoqoqoError::GenericError {
+            msg: format!(
+                "Readout error probabilities of qubit {} sum to {} and cannot be applied as noise operation",
+                qubit, total
+            ),
+        });
+    }
+    let rate = -(1.0 - total).ln();
+    let rates = array![
+        [rate * detect_1_as_0 / total, 0.0, 0.0],
+        [0.0, rate * detect_0_as_1 / total, 0.0],
+        [0.0, 0.0, 0.0],
+    ];
+    Ok(noise_operations(qubit, 1.0, &rates))
+}
+
+/// Returns the overrotation operations of an overrotation model applied after a gate.
+fn overrotation(
+    model: &SingleQubitOverrotationOnGate,
+    hqslang: &str,
+    qubits: &[usize],
+) -> Result<Vec<Operation>, RoqoqoError> {
+    let descriptions: Vec<(usize, &SingleQubitOverrotationDescription)> = match qubits {
+        [qubit] => model
+            .get_single_qubit_overrotation(hqslang, *qubit)
+            .map(|description| vec![(*qubit, description)])
+            .unwrap_or_default(),
+        [control, target] => model
+            .get_two_qubit_overrotation(hqslang, *control, *target)
+            .map(|(control_description, target_description)| {
+                vec![
+                    (*control, control_description),
+                    (*target, target_description),
+                ]
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let mut operations: Vec<Operation> = Vec::new();
+    for (qubit, description) in descriptions {
+        if description.theta_mean() == 0.0 && description.theta_std() == 0.0 {
+            continue;
+        }
+        let mean: CalculatorFloat = description.theta_mean().into();
+        let rotation: Operation = match description.gate().as_str() {
+            "RotateX" => RotateX::new(qubit, mean).into(),
+            "RotateY" => RotateY::new(qubit, mean).into(),
+            "RotateZ" => RotateZ::new(qubit, mean).into(),
+            "PhaseShiftState0" => PhaseShiftState0::new(qubit, mean).into(),
+            "PhaseShiftState1" => PhaseShiftState1::new(qubit, mean).into(),
+            gate => {
+                return Err(RoqoqoError::GenericError {
+                    msg: format!("Overrotation gate {} is not a single-qubit rotation", gate),
+                })
+            }
+        };
+        if description.theta_std() != 0.0 {
+            operations.push(
+                PragmaOverrotation::new(
+                    description.gate().clone(),
+                    vec![qubit],
+                    1.0,
+                    description.theta_std(),
+                )
+                .into(),
+            );
+        }
+        operations.push(rotation);
+    }
+    Ok(operations)
+}
@@ -0,0 +1,389 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Peephole optimisations removing redundant gates from circuits.
+
+use crate::operations::*;
+use crate::Circuit;
+use qoqo_calculator::CalculatorFloat;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Merged rotation angles below this value are treated as zero.
+const TOLERANCE: f64 = 1e-12;
+
+/// The changes made to a circuit by an optimisation pass.
+///
+/// All indices are the positions of the operations in the iterator of the original circuit.
+/// Each pass only fills the field of the combinations it performs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OptimizationReport {
+    /// The number of operations removed from the circuit.
+    pub removed_operations: usize,
+    /// The pairs of gates removed by [cancel_inverse_gates], ordered by the index of their first gate.
+    pub cancelled_pairs: Vec<(usize, usize)>,
+    /// The runs of rotations merged into one rotation by [merge_rotations], ordered by the index of their first rotation.
+    /// Runs whose merged angle is zero have been removed from the circuit.
+    pub merged_rotations: Vec<Vec<usize>>,
+    /// The runs of gates fused into one [SingleQubitGate] by [fuse_single_qubit_gates],
+    /// ordered by the index of their first gate.
+    pub fused_runs: Vec<Vec<usize>>,
+}
+
+/// Removes pairs of adjacent gates that are the inverse of each other.
+///
+/// Two gates are adjacent when they act on the same set of qubits and no other operation acts on
+/// any of these qubits between them.
+/// A pair is cancelled when one of the gates implements [OperateConstantGate] and the other gate
+/// is equal to its [OperateConstantGate::inverse].
+/// Cancellations are repeated with the gates that become adjacent by removing a pair,
+/// so that for example `H S S^† H` is removed completely.
+/// Operations acting on all qubits are never moved past.
+/// The optimised circuit implements the same unitary up to a global phase.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is optimised.
+///
+/// # Returns
+///
+/// * `(Circuit, OptimizationReport)` - The optimised circuit and the report of the cancelled pairs.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::operations::{CNOT, Hadamard, InvSGate, SGate};
+/// use roqoqo::transpiler::cancel_inverse_gates;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += Hadamard::new(0);
+/// circuit += SGate::new(0);
+/// circuit += InvSGate::new(0);
+/// circuit += Hadamard::new(0);
+/// circuit += CNOT::new(0, 1);
+/// let (optimised, report) = cancel_inverse_gates(&circuit);
+/// assert_eq!(report.removed_operations, 4);
+/// assert_eq!(report.cancelled_pairs, vec![(0, 3), (1, 2)]);
+/// assert_eq!(optimised.len(), 1);
+/// ```
+pub fn cancel_inverse_gates(circuit: &Circuit) -> (Circuit, OptimizationReport) {
+    let (optimised, removed_operations, combinations) =
+        combine_adjacent(circuit, |previous, operation| {
+            let previous_gate = GateOperation::try_from(previous.clone()).ok()?;
+            let gate = GateOperation::try_from(operation.clone()).ok()?;
+            if is_inverse(previous, &gate) || is_inverse(operation, &previous_gate) {
+                Some(None)
+            } else {
+                None
+            }
+        });
+    let report = OptimizationReport {
+        removed_operations,
+        cancelled_pairs: combinations
+            .into_iter()
+            .map(|pair| (pair[0], pair[1]))
+            .collect(),
+        ..OptimizationReport::default()
+    };
+    (optimised, report)
+}
+
+/// Merges adjacent rotations about the same axis into a single rotation.
+///
+/// The rotation angles are added as [CalculatorFloat]s, so symbolic angles are kept symbolic.
+/// Merged rotations with an angle of zero are removed.
+/// The merged rotations are `RotateX`, `RotateY`, `RotateZ`, `PhaseShiftState0`, `PhaseShiftState1`,
+/// `RotateXY` with the same `phi`, and `ControlledPhaseShift`, `ControlledRotateX`, `XY`, `VariableMSXX`
+/// and `PMInteraction` acting on the same control and target qubits.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is optimised.
+///
+/// # Returns
+///
+/// * `(Circuit, OptimizationReport)` - The optimised circuit and the report of the merged rotations.
+///
+/// # Example
+///
+/// ```
+/// use qoqo_calculator::CalculatorFloat;
+/// use roqoqo::Circuit;
+/// use roqoqo::operations::{Operation, RotateZ};
+/// use roqoqo::transpiler::merge_rotations;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += RotateZ::new(0, CalculatorFloat::from("theta"));
+/// circuit += RotateZ::new(0, CalculatorFloat::from(0.5));
+/// let (optimised, report) = merge_rotations(&circuit);
+/// assert_eq!(report.removed_operations, 1);
+/// assert_eq!(report.merged_rotations, vec![vec![0, 1]]);
+/// assert_eq!(
+///     optimised[0],
+///     Operation::from(RotateZ::new(0, CalculatorFloat::from("theta") + 0.5))
+/// );
+/// ```
+pub fn merge_rotations(circuit: &Circuit) -> (Circuit, OptimizationReport) {
+    let (optimised, removed_operations, merged_rotations) =
+        combine_adjacent(circuit, |previous, operation| {
+            let merged = merged_rotation(previous, operation)?;
+            match rotation_angle(&merged).float() {
+                Ok(angle) if angle.abs() < TOLERANCE => Some(None),
+                _ => Some(Some(merged)),
+            }
+        });
+    let report = OptimizationReport {
+        removed_operations,
+        merged_rotations,
+        ..OptimizationReport::default()
+    };
+    (optimised, report)
+}
+
+/// Fuses runs of adjacent single-qubit gates into one [SingleQubitGate].
+///
+/// The gates are multiplied using the alpha and beta parameters of [OperateSingleQubitGate].
+/// Gates with symbolic parameters are not fused, so that their parameters are kept symbolic.
+/// The fused gate is placed at the position of the first gate of the run.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is optimised.
+///
+/// # Returns
+///
+/// * `(Circuit, OptimizationReport)` - The optimised circuit and the report of the fused runs.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::operations::{Hadamard, PauliZ, Operate};
+/// use roqoqo::transpiler::fuse_single_qubit_gates;
+///
+/// let mut circuit = Circuit::new();
+/// circuit += Hadamard::new(0);
+/// circuit += PauliZ::new(0);
+/// circuit += Hadamard::new(0);
+/// let (optimised, report) = fuse_single_qubit_gates(&circuit);
+/// assert_eq!(report.removed_operations, 2);
+/// assert_eq!(report.fused_runs, vec![vec![0, 1, 2]]);
+/// assert_eq!(optimised[0].hqslang(), "SingleQubitGate");
+/// ```
+pub fn fuse_single_qubit_gates(circuit: &Circuit) -> (Circuit, OptimizationReport) {
+    let (optimised, removed_operations, fused_runs) =
+        combine_adjacent(circuit, |previous, operation| {
+            let previous_gate = numeric_single_qubit_gate(previous)?;
+            let gate = numeric_single_qubit_gate(operation)?;
+            gate.mul(&previous_gate)
+                .ok()
+                .map(|fused| Some(Operation::from(fused)))
+        });
+    let report = OptimizationReport {
+        removed_operations,
+        fused_runs,
+        ..OptimizationReport::default()
+    };
+    (optimised, report)
+}
+
+/// Combines adjacent operations of a circuit.
+///
+/// The function `combine` is called with an operation and the previous operation acting on the same set of qubits.
+/// It returns `None` when the operations are kept, `Some(None)` when both operations are removed
+/// and `Some(Some(operation))` when both operations are replaced by one operation at the position of the previous operation.
+///
+/// Returns the combined circuit, the number of removed operations and the indices of the original operations
+/// of every group of combined operations, ordered by their first index.
+fn combine_adjacent<F>(circuit: &Circuit, combine: F) -> (Circuit, usize, Vec<Vec<usize>>)
+where
+    F: Fn(&Operation, &Operation) -> Option<Option<Operation>>,
+{
+    let mut operations: Vec<Option<Operation>> = Vec::new();
+    // The indices of the original operations each operation was combined from
+    let mut origins: Vec<Vec<usize>> = Vec::new();
+    let mut removed_groups: Vec<Vec<usize>> = Vec::new();
+    // The positions of the operations acting on each qubit, the last one is the current adjacent operation
+    let mut positions: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut removed: usize = 0;
+    for (index, operation) in circuit.iter().enumerate() {
+        let qubits = match operation.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits,
+            InvolvedQubits::All => {
+                positions.clear();
+                operations.push(Some(operation.clone()));
+                origins.push(vec![index]);
+                continue;
+            }
+            InvolvedQubits::None => {
+                operations.push(Some(operation.clone()));
+                origins.push(vec![index]);
+                continue;
+            }
+        };
+        let previous = qubits
+            .iter()
+            .map(|qubit| positions.get(qubit).and_then(|stack| stack.last()).copied())
+            .reduce(|first, second| if first == second { first } else { None })
+            .flatten()
+            .filter(|position| {
+                operations[*position].as_ref().is_some_and(|previous| {
+                    previous.involved_qubits() == InvolvedQubits::Set(qubits.clone())
+                })
+            });
+        if let Some(position) = previous {
+            let previous_operation = operations[position]
+                .as_ref()
+                .expect("Adjacent operations are never removed");
+            match combine(previous_operation, operation) {
+                Some(Some(combined)) => {
+                    operations[position] = Some(combined);
+                    origins[position].push(index);
+                    removed += 1;
+                    continue;
+                }
+                Some(None) => {
+                    operations[position] = None;
+                    let mut group = std::mem::take(&mut origins[position]);
+                    group.push(index);
+                    removed += 2;
+                    removed_groups.push(group);
+                    for qubit in qubits.iter() {
+                        if let Some(stack) = positions.get_mut(qubit) {
+                            stack.pop();
+                        }
+                    }
+                    continue;
+                }
+                None => (),
+            }
+        }
+        for qubit in qubits {
+            positions.entry(qubit).or_default().push(operations.len());
+        }
+        operations.push(Some(operation.clone()));
+        origins.push(vec![index]);
+    }
+    let mut groups: Vec<Vec<usize>> = origins
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .chain(removed_groups)
+        .collect();
+    groups.sort_by_key(|group| group[0]);
+    let mut combined = Circuit::new();
+    for operation in operations.into_iter().flatten() {
+        combined.add_operation(operation);
+    }
+    (combined, removed, groups)
+}
+
+/// Returns true when `operation` is a constant gate whose inverse is `gate`.
+fn is_inverse(operation: &Operation, gate: &GateOperation) -> bool {
+    ConstantGateOperation::try_from(operation.clone())
+        .map(|constant| &constant.inverse() == gate)
+        .unwrap_or(false)
+}
+
+/// Returns the rotation combining two rotations about the same axis.
+fn merged_rotation(previous: &Operation, operation: &Operation) -> Option<Operation> {
+    let merged: Operation = match (previous, operation) {
+        (Operation::RotateX(a), Operation::RotateX(b)) if a.qubit() == b.qubit() => {
+            RotateX::new(*a.qubit(), a.theta() + b.theta()).into()
+        }
+        (Operation::RotateY(a), Operation::RotateY(b)) if a.qubit() == b.qubit() => {
+            RotateY::new(*a.qubit(), a.theta() + b.theta()).into()
+        }
+        (Operation::RotateZ(a), Operation::RotateZ(b)) if a.qubit() == b.qubit() => {
+            RotateZ::new(*a.qubit(), a.theta() + b.theta()).into()
+        }
+        (Operation::PhaseShiftState0(a), Operation::PhaseShiftState0(b))
+            if a.qubit() == b.qubit() =>
+        {
+            PhaseShiftState0::new(*a.qubit(), a.theta() + b.theta()).into()
+        }
+        (Operation::PhaseShiftState1(a), Operation::PhaseShiftState1(b))
+            if a.qubit() == b.qubit() =>
+        {
+            PhaseShiftState1::new(*a.qubit(), a.theta() + b.theta()).into()
+        }
+        (Operation::RotateXY(a), Operation::RotateXY(b))
+            if a.qubit() == b.qubit() && a.phi() == b.phi() =>
+        {
+            RotateXY::new(*a.qubit(), a.theta() + b.theta(), a.phi().clone()).into()
+        }
+        (Operation::ControlledPhaseShift(a), Operation::ControlledPhaseShift(b))
+            if a.control() == b.control() && a.target() == b.target() =>
+        {
+            ControlledPhaseShift::new(*a.control(), *a.target(), a.theta() + b.theta()).into()
+        }
+        (Operation::ControlledRotateX(a), Operation::ControlledRotateX(b))
+            if a.control() == b.control() && a.target() == b.target() =>
+        {
+            ControlledRotateX::new(*a.control(), *a.target(), a.theta() + b.theta()).into()
+        }
+        (Operation::XY(a), Operation::XY(b))
+            if a.control() == b.control() && a.target() == b.target() =>
+        {
+            XY::new(*a.control(), *a.target(), a.theta() + b.theta()).into()
+        }
+        (Operation::VariableMSXX(a), Operation::VariableMSXX(b))
+            if a.control() == b.control() && a.target() == b.target() =>
+        {
+            VariableMSXX::new(*a.control(), *a.target(), a.theta() + b.theta()).into()
+        }
+        (Operation::PMInteraction(a), Operation::PMInteraction(b))
+            if a.control() == b.control() && a.target() == b.target() =>
+        {
+            PMInteraction::new(*a.control(), *a.target(), a.t() + b.t()).into()
+        }
+        _ => return None,
+    };
+    Some(merged)
+}
+
+/// Returns the rotation angle of a rotation created by [merged_rotation].
+fn rotation_angle(rotation: &Operation) -> CalculatorFloat {
+    match rotation {
+        Operation::RotateX(op) => op.theta().clone(),
+        Operation::RotateY(op) => op.theta().clone(),
+        Operation::RotateZ(op) => op.theta().clone(),
+        Operation::PhaseShiftState0(op) => op.theta().clone(),
+        Operation::PhaseShiftState1(op) => op.theta().clone(),
+        Operation::RotateXY(op) => op.theta().clone(),
+        Operation::ControlledPhaseShift(op) => op.theta().clone(),
+        Operation::ControlledRotateX(op) => op.theta().clone(),
+        Operation::XY(op) => op.theta().clone(),
+        Operation::VariableMSXX(op) => op.theta().clone(),
+        Operation::PMInteraction(op) => op.t().clone(),
+        _ => CalculatorFloat::from(f64::NAN),
+    }
+}
+
+/// Returns the operation as a single-qubit gate when all its parameters are numeric.
+fn numeric_single_qubit_gate(operation: &Operation) -> Option<SingleQubitGate> {
+    let gate = SingleQubitGateOperation::try_from(operation.clone()).ok()?;
+    let numeric = [
+        gate.alpha_r(),
+        gate.alpha_i(),
+        gate.beta_r(),
+        gate.beta_i(),
+        gate.global_phase(),
+    ]
+    .iter()
+    .all(|parameter| parameter.is_float());
+    if numeric {
+        Some(gate.to_single_qubit_gate())
+    } else {
+        None
+    }
+}
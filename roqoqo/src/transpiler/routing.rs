@@ -0,0 +1,523 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::distances;
+use crate::devices::Device;
+use crate::operations::*;
+use crate::{Circuit, CircuitDag, RoqoqoError};
+use petgraph::Direction::Incoming;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The maximal number of upcoming two-qubit interactions considered when scoring a SWAP.
+const EXTENDED_SET_SIZE: usize = 20;
+/// The weight of the upcoming two-qubit interactions relative to the front layer.
+const EXTENDED_SET_WEIGHT: f64 = 0.5;
+/// The increase of the decay of a qubit every time it is part of a SWAP.
+const DECAY_INCREMENT: f64 = 0.001;
+/// The number of SWAPs after which the decay of all qubits is reset.
+const DECAY_RESET_INTERVAL: usize = 5;
+
+/// Routes a circuit on the connectivity of a device by inserting SWAP operations.
+///
+/// The routing uses the SABRE heuristic: the operations of the circuit are executed in the order
+/// of the front layer of the [crate::CircuitDag] of the circuit.
+/// Whenever no operation of the front layer can be executed, because its two qubits are not
+/// connected by one of the [Device::two_qubit_edges] of the device,
+/// the [SWAP] minimising the distance between the qubits of the blocked two-qubit gates
+/// (with a smaller weight for the upcoming two-qubit gates) is inserted.
+///
+/// Qubit `i` of the input circuit starts on qubit `i` of the device.
+/// The qubits of the operations in the routed circuit are the qubits of the device.
+/// The qubit mapping of a [PragmaRepeatedMeasurement] is set so that every readout bit is read from the
+/// device qubit holding the state of the circuit qubit measured into that bit in the input circuit.
+/// The inserted SWAP gates are not decomposed and can be rewritten into the gates
+/// of the device with [crate::transpiler::decompose_to_device].
+///
+/// The operations between a [PragmaStartDecompositionBlock] and the corresponding [PragmaStopDecompositionBlock]
+/// are kept together: all SWAPs needed by the two-qubit gates of the block are inserted before the start of the block
+/// and no SWAP acts on the qubits of the block until the end of the block.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is routed.
+/// * `device` - The device providing the connectivity.
+///
+/// # Returns
+///
+/// * `Ok((Circuit, HashMap<usize, usize>))` - The routed circuit and the final qubit permutation,
+///   mapping every qubit of the input circuit to the qubit of the device holding its state at the end of the routed circuit.
+/// * `Err(RoqoqoError::GenericError)` - The circuit acts on qubits that are not in the device,
+///   contains gates acting on more than two qubits or two-qubit gates between qubits that are not connected.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::SquareLatticeDevice;
+/// use roqoqo::operations::CNOT;
+/// use roqoqo::transpiler::route_to_device;
+///
+/// let device = SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0);
+/// let mut circuit = Circuit::new();
+/// circuit += CNOT::new(0, 2);
+/// let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+/// assert_eq!(routed.len(), 2);
+/// assert_eq!(permutation[&0], 1);
+/// ```
+pub fn route_to_device(
+    circuit: &Circuit,
+    device: &dyn Device,
+) -> Result<(Circuit, HashMap<usize, usize>), RoqoqoError> {
+    let mut router = Router::new(circuit, device)?;
+    router.route()?;
+    let permutation = router
+        .layout
+        .iter()
+        .enumerate()
+        .map(|(qubit, physical)| (qubit, *physical))
+        .collect();
+    Ok((router.routed, permutation))
+}
+
+/// The state of the SABRE routing of a circuit.
+struct Router {
+    /// The dependency graph of the circuit that is routed.
+    dag: CircuitDag,
+    /// The length of the shortest path between two qubits of the device.
+    distances: Vec<Vec<usize>>,
+    /// The connected qubit pairs of the device.
+    edges: Vec<(usize, usize)>,
+    /// The two-qubit interactions of the decomposition blocks, indexed by the node of the block start.
+    block_interactions: HashMap<usize, Vec<(usize, usize)>>,
+    /// The device qubit holding each qubit of the circuit.
+    layout: Vec<usize>,
+    /// The number of qubits of the circuit.
+    number_circuit_qubits: usize,
+    /// The length of the bit registers defined in the circuit.
+    bit_register_lengths: HashMap<String, usize>,
+    /// The number of open decomposition blocks on each device qubit.
+    locks: Vec<usize>,
+    /// The decay of each device qubit penalising repeated SWAPs on the same qubits.
+    decay: Vec<f64>,
+    /// The routed circuit.
+    routed: Circuit,
+}
+
+impl Router {
+    /// Creates a new router after checking that the circuit can be routed on the device.
+    fn new(circuit: &Circuit, device: &dyn Device) -> Result<Self, RoqoqoError> {
+        let number_qubits = device.number_qubits();
+        let edges = device.two_qubit_edges();
+        let distances = distances(number_qubits, &edges);
+
+        let operations: Vec<&Operation> = circuit.iter().collect();
+        let mut block_interactions: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        for (index, operation) in operations.iter().enumerate() {
+            if let InvolvedQubits::Set(qubits) = operation.involved_qubits() {
+                if let Some(qubit) = qubits.iter().find(|qubit| **qubit >= number_qubits) {
+                    return Err(RoqoqoError::GenericError {
+                        msg: format!(
+                            "Operation {} acts on qubit {qubit}, but the device only has {number_qubits} qubits",
+                            operation.hqslang()
+                        ),
+                    });
+                }
+            }
+            if let Some((first, second)) = interaction(operation)? {
+                if distances[first][second] == usize::MAX {
+                    return Err(RoqoqoError::GenericError {
+                        msg: format!(
+                            "Operation {} acts on qubits {first} and {second} that are not connected in the device",
+                            operation.hqslang()
+                        ),
+                    });
+                }
+            }
+            if let Operation::PragmaStartDecompositionBlock(start) = operation {
+                block_interactions.insert(index, block(start, &operations[index + 1..])?);
+            }
+        }
+
+        Ok(Self {
+            dag: CircuitDag::from(circuit.clone()),
+            distances,
+            edges,
+            block_interactions,
+            layout: (0..number_qubits).collect(),
+            number_circuit_qubits: circuit.number_of_qubits(),
+            bit_register_lengths: circuit
+                .definitions()
+                .iter()
+                .filter_map(|definition| match definition {
+                    Operation::DefinitionBit(bit) => Some((bit.name().clone(), *bit.length())),
+                    _ => None,
+                })
+                .collect(),
+            locks: vec![0; number_qubits],
+            decay: vec![1.0; number_qubits],
+            routed: Circuit::new(),
+        })
+    }
+
+    /// Routes all operations of the circuit.
+    fn route(&mut self) -> Result<(), RoqoqoError> {
+        let mut front_layer: Vec<usize> = self
+            .dag
+            .graph
+            .externals(Incoming)
+            .map(|node| node.index())
+            .collect();
+        front_layer.sort_unstable();
+        let mut executed: Vec<usize> = Vec::with_capacity(self.dag.graph.node_count());
+        // SABRE can get stuck alternating between SWAPs, bringing the qubits together
+        // along a shortest path guarantees progress in that case
+        let stall_limit = 10 * self.layout.len();
+        let mut swaps_without_progress = 0;
+
+        while !front_layer.is_empty() {
+            let executable: Vec<usize> = front_layer
+                .iter()
+                .copied()
+                .filter(|node| {
+                    self.interactions(*node)
+                        .iter()
+                        .all(|(first, second)| self.connected(*first, *second))
+                })
+                .collect();
+            if !executable.is_empty() {
+                for node in executable {
+                    self.execute(node)?;
+                    front_layer = self.dag.new_front_layer(&executed, &front_layer, &node)?;
+                    front_layer.retain(|other| *other != node);
+                    executed.push(node);
+                }
+                front_layer.sort_unstable();
+                self.decay.iter_mut().for_each(|decay| *decay = 1.0);
+                swaps_without_progress = 0;
+                continue;
+            }
+
+            let blocked: Vec<(usize, usize)> = front_layer
+                .iter()
+                .flat_map(|node| self.interactions(*node))
+                .filter(|(first, second)| !self.connected(*first, *second))
+                .collect();
+            if swaps_without_progress < stall_limit {
+                let upcoming = self.upcoming_interactions(&front_layer, &executed);
+                match self.best_swap(&blocked, &upcoming) {
+                    Some((first, second)) => self.swap(first, second),
+                    None => self.swap_along_shortest_path(blocked[0])?,
+                }
+                swaps_without_progress += 1;
+                if swaps_without_progress % DECAY_RESET_INTERVAL == 0 {
+                    self.decay.iter_mut().for_each(|decay| *decay = 1.0);
+                }
+            } else if swaps_without_progress < 2 * stall_limit {
+                self.swap_along_shortest_path(blocked[0])?;
+                swaps_without_progress += 1;
+            } else {
+                return Err(RoqoqoError::GenericError {
+                    msg: "The two-qubit gates of a decomposition block cannot be placed on connected qubits of the device at the same time".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the operation of a node to the routed circuit with the qubits of the device.
+    fn execute(&mut self, node: usize) -> Result<(), RoqoqoError> {
+        let operation = self
+            .dag
+            .get(node)
+            .expect("Node of the front layer not in the CircuitDag");
+        let routed_operation = match operation {
+            // The qubit mapping of a repeated measurement maps device qubits to readout bits:
+            // the bit of a circuit qubit is read from the device qubit holding its state.
+            Operation::PragmaRepeatedMeasurement(measurement) => {
+                let qubit_mapping = match measurement.qubit_mapping() {
+                    Some(qubit_mapping) => qubit_mapping
+                        .iter()
+                        .map(|(qubit, bit)| {
+                            self.layout
+                                .get(*qubit)
+                                .map(|physical| (*physical, *bit))
+                                .ok_or_else(|| RoqoqoError::GenericError {
+                                    msg: format!("Qubit {} is not in the device", qubit),
+                                })
+                        })
+                        .collect::<Result<HashMap<usize, usize>, RoqoqoError>>()?,
+                    // Without a mapping the circuit qubits are read out to the bits with the same index
+                    None => {
+                        let number_bits = self
+                            .bit_register_lengths
+                            .get(measurement.readout())
+                            .map_or(self.number_circuit_qubits, |length| {
+                                (*length).min(self.number_circuit_qubits)
+                            });
+                        self.layout
+                            .iter()
+                            .take(number_bits)
+                            .enumerate()
+                            .map(|(qubit, physical)| (*physical, qubit))
+                            .collect()
+                    }
+                };
+                PragmaRepeatedMeasurement::new(
+                    measurement.readout().clone(),
+                    *measurement.number_measurements(),
+                    Some(qubit_mapping),
+                )
+                .into()
+            }
+            _ => {
+                let mapping: HashMap<usize, usize> =
+                    self.layout.iter().copied().enumerate().collect();
+                operation.remap_qubits(&mapping)?
+            }
+        };
+        match operation {
+            Operation::PragmaStartDecompositionBlock(start) => {
+                for qubit in start.qubits() {
+                    self.locks[self.layout[*qubit]] += 1;
+                }
+            }
+            Operation::PragmaStopDecompositionBlock(stop) => {
+                for qubit in stop.qubits() {
+                    let physical = self.layout[*qubit];
+                    self.locks[physical] = self.locks[physical].saturating_sub(1);
+                }
+            }
+            _ => (),
+        }
+        self.routed.add_operation(routed_operation);
+        Ok(())
+    }
+
+    /// Returns the two-qubit interactions that need connected qubits to execute a node.
+    fn interactions(&self, node: usize) -> Vec<(usize, usize)> {
+        if let Some(interactions) = self.block_interactions.get(&node) {
+            return interactions.clone();
+        }
+        self.dag
+            .get(node)
+            .and_then(|operation| interaction(operation).ok().flatten())
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the two-qubit interactions of the operations following the front layer.
+    fn upcoming_interactions(
+        &self,
+        front_layer: &[usize],
+        executed: &[usize],
+    ) -> Vec<(usize, usize)> {
+        let executed: HashSet<usize> = executed.iter().copied().collect();
+        let mut visited: HashSet<usize> = front_layer.iter().copied().collect();
+        let mut queue: VecDeque<usize> = front_layer.iter().copied().collect();
+        let mut upcoming = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            for successor in self.dag.successors(node) {
+                let successor = successor.index();
+                if executed.contains(&successor) || !visited.insert(successor) {
+                    continue;
+                }
+                upcoming.extend(self.interactions(successor));
+                if upcoming.len() >= EXTENDED_SET_SIZE {
+                    upcoming.truncate(EXTENDED_SET_SIZE);
+                    return upcoming;
+                }
+                queue.push_back(successor);
+            }
+        }
+        upcoming
+    }
+
+    /// Returns the SWAP between two device qubits with the lowest SABRE cost.
+    fn best_swap(
+        &self,
+        blocked: &[(usize, usize)],
+        upcoming: &[(usize, usize)],
+    ) -> Option<(usize, usize)> {
+        let involved: HashSet<usize> = blocked
+            .iter()
+            .flat_map(|(first, second)| [self.layout[*first], self.layout[*second]])
+            .collect();
+        let mut best: Option<((usize, usize), f64)> = None;
+        for (first, second) in self.edges.iter().copied() {
+            if self.locks[first] > 0
+                || self.locks[second] > 0
+                || !(involved.contains(&first) || involved.contains(&second))
+            {
+                continue;
+            }
+            let swapped = |physical: usize| {
+                if physical == first {
+                    second
+                } else if physical == second {
+                    first
+                } else {
+                    physical
+                }
+            };
+            let distance = |interactions: &[(usize, usize)]| -> f64 {
+                interactions
+                    .iter()
+                    .map(|(a, b)| {
+                        self.distances[swapped(self.layout[*a])][swapped(self.layout[*b])] as f64
+                    })
+                    .sum::<f64>()
+                    / interactions.len().max(1) as f64
+            };
+            let cost = self.decay[first].max(self.decay[second])
+                * (distance(blocked) + EXTENDED_SET_WEIGHT * distance(upcoming));
+            if best.map_or(true, |(_, best_cost)| cost < best_cost) {
+                best = Some(((first, second), cost));
+            }
+        }
+        best.map(|(swap, _)| swap)
+    }
+
+    /// Moves the first qubit of a blocked interaction next to the second qubit along a shortest path.
+    fn swap_along_shortest_path(&mut self, blocked: (usize, usize)) -> Result<(), RoqoqoError> {
+        let (mut start, mut end) = (self.layout[blocked.0], self.layout[blocked.1]);
+        if self.locks[start] > 0 {
+            std::mem::swap(&mut start, &mut end);
+        }
+        let path = self.unlocked_path(start, end).ok_or_else(|| RoqoqoError::GenericError {
+            msg: format!(
+                "Qubits {} and {} cannot be connected without acting on the qubits of an open decomposition block",
+                blocked.0, blocked.1
+            ),
+        })?;
+        for pair in path.windows(2).take(path.len().saturating_sub(2)) {
+            self.swap(pair[0], pair[1]);
+        }
+        Ok(())
+    }
+
+    /// Returns a shortest path between two device qubits that only moves unlocked qubits.
+    fn unlocked_path(&self, start: usize, end: usize) -> Option<Vec<usize>> {
+        if self.locks[start] > 0 {
+            return None;
+        }
+        let mut previous: Vec<Option<usize>> = vec![None; self.layout.len()];
+        let mut queue = VecDeque::from([start]);
+        while let Some(qubit) = queue.pop_front() {
+            if qubit == end {
+                let mut path = vec![end];
+                let mut current = end;
+                while let Some(before) = previous[current] {
+                    path.push(before);
+                    current = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (first, second) in self.edges.iter().copied() {
+                let neighbour = if first == qubit {
+                    second
+                } else if second == qubit {
+                    first
+                } else {
+                    continue;
+                };
+                if neighbour != start
+                    && previous[neighbour].is_none()
+                    && (neighbour == end || self.locks[neighbour] == 0)
+                {
+                    previous[neighbour] = Some(qubit);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    /// Adds a SWAP between two device qubits and updates the layout.
+    fn swap(&mut self, first: usize, second: usize) {
+        self.routed.add_operation(SWAP::new(first, second));
+        for physical in self.layout.iter_mut() {
+            if *physical == first {
+                *physical = second;
+            } else if *physical == second {
+                *physical = first;
+            }
+        }
+        self.decay[first] += DECAY_INCREMENT;
+        self.decay[second] += DECAY_INCREMENT;
+    }
+
+    /// Returns true when two qubits of the circuit are on connected device qubits.
+    fn connected(&self, first: usize, second: usize) -> bool {
+        self.distances[self.layout[first]][self.layout[second]] == 1
+    }
+}
+
+/// Returns the pair of qubits that need to be connected to execute an operation.
+fn interaction(operation: &Operation) -> Result<Option<(usize, usize)>, RoqoqoError> {
+    if let Some(qubits) = gate_qubits(operation) {
+        return match qubits.as_slice() {
+            [first, second] => Ok(Some((*first, *second))),
+            [_, _, _, ..] => Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Operation {} acts on more than two qubits and needs to be decomposed before routing",
+                    operation.hqslang()
+                ),
+            }),
+            _ => Ok(None),
+        };
+    }
+    let inner_circuit = match operation {
+        Operation::PragmaAnnotatedOp(op) => return interaction(&op.operation),
+        Operation::PragmaLoop(op) => op.circuit(),
+        Operation::PragmaConditional(op) => op.circuit(),
+        Operation::PragmaControlledCircuit(_) => {
+            return Err(RoqoqoError::GenericError {
+                msg: "Operation PragmaControlledCircuit cannot be routed".to_string(),
+            })
+        }
+        _ => return Ok(None),
+    };
+    for inner_operation in inner_circuit.iter() {
+        if interaction(inner_operation)?.is_some() {
+            return Err(RoqoqoError::GenericError {
+                msg: format!(
+                    "Operation {} contains two-qubit gates and cannot be routed",
+                    operation.hqslang()
+                ),
+            });
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the two-qubit interactions of a decomposition block.
+fn block(
+    start: &PragmaStartDecompositionBlock,
+    following: &[&Operation],
+) -> Result<Vec<(usize, usize)>, RoqoqoError> {
+    let qubits: HashSet<usize> = start.qubits().iter().copied().collect();
+    let mut interactions = Vec::new();
+    for operation in following {
+        if let Operation::PragmaStopDecompositionBlock(stop) = operation {
+            if stop.qubits().iter().copied().collect::<HashSet<usize>>() == qubits {
+                break;
+            }
+        }
+        if let Some((first, second)) = interaction(operation)? {
+            if qubits.contains(&first) && qubits.contains(&second) {
+                interactions.push((first, second));
+            }
+        }
+    }
+    Ok(interactions)
+}
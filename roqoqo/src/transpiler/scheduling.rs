@@ -0,0 +1,276 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scheduling of circuits in time using the gate times of a device.
+
+use crate::devices::Device;
+use crate::operations::*;
+use crate::{Circuit, RoqoqoError};
+use std::collections::HashMap;
+
+/// Idle windows shorter than this fraction of the circuit duration are ignored.
+const RELATIVE_TOLERANCE: f64 = 1e-12;
+
+/// The policy placing the operations of a circuit in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SchedulingPolicy {
+    /// Every operation starts as soon as possible (ASAP).
+    AsSoonAsPossible,
+    /// Every operation ends as late as possible (ALAP).
+    AsLateAsPossible,
+}
+
+/// An operation of a circuit with its start and end time in a [Schedule].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScheduledOperation {
+    /// The scheduled operation.
+    pub operation: Operation,
+    /// The time the operation starts at in seconds.
+    pub start_time: f64,
+    /// The time the operation ends at in seconds.
+    pub end_time: f64,
+}
+
+/// The schedule of a circuit on a device.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schedule {
+    /// The operations of the circuit in the order of `Circuit::iter` with their start and end times.
+    pub operations: Vec<ScheduledOperation>,
+    /// The total duration of the circuit in seconds.
+    pub duration: f64,
+    /// The time windows (start, end) in which no gate acts on a qubit, for every qubit of the circuit, sorted by time.
+    pub idle_windows: HashMap<usize, Vec<(f64, f64)>>,
+}
+
+/// The qubits an operation synchronises in a schedule.
+enum Timing {
+    /// The operation does not act on qubits.
+    None,
+    /// The operation acts on a set of qubits.
+    Qubits(Vec<usize>),
+    /// The operation acts on all qubits.
+    All,
+}
+
+/// An operation prepared for scheduling.
+struct Item {
+    timing: Timing,
+    duration: f64,
+    /// True when the qubits of the operation are busy (not idle) during the operation.
+    busy: bool,
+}
+
+/// Schedules the operations of a circuit in time using the gate times of a device.
+///
+/// Operations acting on the same qubits are executed in the order of the circuit.
+/// The durations of gates are the gate times of the device, `PragmaSleep` and `PragmaStopParallelBlock`
+/// last for their sleep and execution time, and all other operations take no time.
+/// `PragmaStopParallelBlock` acts as a barrier: the operations after it on its qubits start after all
+/// operations before it on its qubits have ended and the execution time has passed.
+/// Operations acting on all qubits, such as `PragmaRepeatedMeasurement`, are barriers for all qubits.
+///
+/// The idle windows of a qubit are the periods between the start and the end of the circuit
+/// in which no gate acts on the qubit. Periods of `PragmaSleep` and `PragmaStopParallelBlock` are idle.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit that is scheduled.
+/// * `device` - The device providing the gate times.
+/// * `policy` - Whether operations are scheduled as soon as possible (ASAP) or as late as possible (ALAP).
+///
+/// # Returns
+///
+/// * `Ok(Schedule)` - The start and end times of the operations, the duration and the idle windows of the circuit.
+/// * `Err(RoqoqoError)` - A gate is not available on the device, a time is symbolic or the circuit contains
+///   `PragmaLoop`, `PragmaConditional` or `PragmaControlledCircuit`.
+///
+/// # Example
+///
+/// ```
+/// use roqoqo::Circuit;
+/// use roqoqo::devices::AllToAllDevice;
+/// use roqoqo::operations::{CNOT, RotateX};
+/// use roqoqo::transpiler::{schedule_circuit, SchedulingPolicy};
+///
+/// let device = AllToAllDevice::new(2, &["RotateX".to_string()], &["CNOT".to_string()], 2.0)
+///     .set_all_single_qubit_gate_times("RotateX", 1.0);
+/// let mut circuit = Circuit::new();
+/// circuit += RotateX::new(0, 0.1.into());
+/// circuit += RotateX::new(0, 0.2.into());
+/// circuit += RotateX::new(1, 0.3.into());
+/// circuit += CNOT::new(0, 1);
+/// let schedule = schedule_circuit(&circuit, &device, SchedulingPolicy::AsLateAsPossible).unwrap();
+/// assert_eq!(schedule.duration, 4.0);
+/// assert_eq!(schedule.operations[2].start_time, 1.0);
+/// assert_eq!(schedule.idle_windows[&1], vec![(0.0, 1.0)]);
+/// ```
+pub fn schedule_circuit(
+    circuit: &Circuit,
+    device: &dyn Device,
+    policy: SchedulingPolicy,
+) -> Result<Schedule, RoqoqoError> {
+    let items = circuit
+        .iter()
+        .map(|operation| schedule_item(operation, device))
+        .collect::<Result<Vec<Item>, RoqoqoError>>()?;
+    let times = match policy {
+        SchedulingPolicy::AsSoonAsPossible => as_soon_as_possible(items.iter()),
+        SchedulingPolicy::AsLateAsPossible => {
+            let mut reversed_times = as_soon_as_possible(items.iter().rev());
+            reversed_times.reverse();
+            let duration = total_duration(&reversed_times);
+            reversed_times
+                .into_iter()
+                .map(|(start, end)| (duration - end, duration - start))
+                .collect()
+        }
+    };
+    let duration = total_duration(&times);
+
+    let mut busy_intervals: HashMap<usize, Vec<(f64, f64)>> = HashMap::new();
+    for (item, (start, end)) in items.iter().zip(times.iter()) {
+        if let Timing::Qubits(qubits) = &item.timing {
+            for qubit in qubits.iter() {
+                let intervals = busy_intervals.entry(*qubit).or_default();
+                if item.busy {
+                    intervals.push((*start, *end));
+                }
+            }
+        }
+    }
+    let idle_windows = busy_intervals
+        .into_iter()
+        .map(|(qubit, intervals)| (qubit, idle_windows(intervals, duration)))
+        .collect();
+
+    Ok(Schedule {
+        operations: circuit
+            .iter()
+            .zip(times)
+            .map(|(operation, (start_time, end_time))| ScheduledOperation {
+                operation: operation.clone(),
+                start_time,
+                end_time,
+            })
+            .collect(),
+        duration,
+        idle_windows,
+    })
+}
+
+/// Returns the qubits and the duration of an operation on a device.
+fn schedule_item(operation: &Operation, device: &dyn Device) -> Result<Item, RoqoqoError> {
+    if let Some(qubits) = gate_qubits(operation) {
+        let hqslang = operation.hqslang();
+        let duration = match qubits.as_slice() {
+            [qubit] => device.single_qubit_gate_time(hqslang, qubit),
+            [control, target] => device.two_qubit_gate_time(hqslang, control, target),
+            [control_0, control_1, target] => {
+                device.three_qubit_gate_time(hqslang, control_0, control_1, target)
+            }
+            _ => device.multi_qubit_gate_time(hqslang, &qubits),
+        }
+        .ok_or_else(|| RoqoqoError::GenericError {
+            msg: format!(
+                "Gate {} on qubits {:?} is not available on the device",
+                hqslang, qubits
+            ),
+        })?;
+        return Ok(Item {
+            timing: Timing::Qubits(qubits),
+            duration,
+            busy: true,
+        });
+    }
+    let duration = match operation {
+        Operation::PragmaSleep(op) => f64::try_from(op.sleep_time().clone())?,
+        Operation::PragmaStopParallelBlock(op) => f64::try_from(op.execution_time().clone())?,
+        Operation::PragmaLoop(_)
+        | Operation::PragmaConditional(_)
+        | Operation::PragmaControlledCircuit(_) => {
+            return Err(RoqoqoError::GenericError {
+                msg: format!("Operation {} cannot be scheduled", operation.hqslang()),
+            })
+        }
+        _ => 0.0,
+    };
+    let timing = match operation.involved_qubits() {
+        InvolvedQubits::None => Timing::None,
+        InvolvedQubits::All => Timing::All,
+        InvolvedQubits::Set(qubits) => {
+            let mut qubits: Vec<usize> = qubits.into_iter().collect();
+            qubits.sort_unstable();
+            Timing::Qubits(qubits)
+        }
+    };
+    Ok(Item {
+        timing,
+        duration,
+        busy: false,
+    })
+}
+
+/// Returns the (start, end) times of the operations when every operation starts as soon as possible.
+fn as_soon_as_possible<'a>(items: impl Iterator<Item = &'a Item>) -> Vec<(f64, f64)> {
+    let mut ready: HashMap<usize, f64> = HashMap::new();
+    // The end time of the last operation acting on all qubits
+    let mut barrier = 0.0;
+    items
+        .map(|item| match &item.timing {
+            Timing::None => (barrier, barrier),
+            Timing::Qubits(qubits) => {
+                let start = qubits
+                    .iter()
+                    .filter_map(|qubit| ready.get(qubit))
+                    .copied()
+                    .fold(barrier, f64::max);
+                let end = start + item.duration;
+                for qubit in qubits.iter() {
+                    ready.insert(*qubit, end);
+                }
+                (start, end)
+            }
+            Timing::All => {
+                let start = ready.values().copied().fold(barrier, f64::max);
+                barrier = start + item.duration;
+                ready.clear();
+                (start, barrier)
+            }
+        })
+        .collect()
+}
+
+/// Returns the total duration of scheduled operations.
+fn total_duration(times: &[(f64, f64)]) -> f64 {
+    times.iter().map(|(_, end)| *end).fold(0.0, f64::max)
+}
+
+/// Returns the windows between zero and the duration not covered by the busy intervals of a qubit.
+fn idle_windows(mut intervals: Vec<(f64, f64)>, duration: f64) -> Vec<(f64, f64)> {
+    let tolerance = duration * RELATIVE_TOLERANCE;
+    intervals.sort_by(|first, second| first.0.total_cmp(&second.0));
+    let mut windows = Vec::new();
+    let mut time = 0.0;
+    for (start, end) in intervals {
+        if start - time > tolerance {
+            windows.push((time, start));
+        }
+        time = f64::max(time, end);
+    }
+    if duration - time > tolerance {
+        windows.push((time, duration));
+    }
+    windows
+}
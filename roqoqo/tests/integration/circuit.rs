@@ -11,9 +11,10 @@
 // limitations under the License.
 #[cfg(feature = "json_schema")]
 use jsonschema::{Draft, Validator};
+use num_complex::Complex64;
 use qoqo_calculator::{Calculator, CalculatorFloat};
 use roqoqo::operations::*;
-use roqoqo::{AsVec, Circuit};
+use roqoqo::{AsVec, Circuit, RoqoqoError};
 #[cfg(feature = "json_schema")]
 use schemars::schema_for;
 use std::collections::{HashMap, HashSet};
@@ -449,6 +450,127 @@ fn test_number_of_qubits() {
     assert_eq!(circuit.number_of_qubits(), 7);
 }
 
+/// Test the unitary matrix of a Circuit in the little-endian qubit order
+#[test]
+fn test_unitary_matrix() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += PauliX::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+    circuit += PragmaGlobalPhase::new(std::f64::consts::FRAC_PI_2.into());
+    let unitary = circuit.unitary_matrix().unwrap();
+    assert_eq!(unitary.dim(), (4, 4));
+    // |00> -> i|11>, |01> -> i|00>, |10> -> i|01>, |11> -> i|10> with qubit 0 as least significant bit
+    for (column, row) in [(0, 3), (1, 0), (2, 1), (3, 2)] {
+        for index in 0..4 {
+            let expected = if index == row {
+                Complex64::new(0.0, 1.0)
+            } else {
+                Complex64::new(0.0, 0.0)
+            };
+            assert!((unitary[(index, column)] - expected).norm() < 1e-12);
+        }
+    }
+
+    assert_eq!(Circuit::new().unitary_matrix().unwrap().dim(), (1, 1));
+}
+
+/// Test that non-unitary and symbolic operations return an error
+#[test_case(Operation::from(MeasureQubit::new(0, "ro".to_string(), 0)); "MeasureQubit")]
+#[test_case(Operation::from(PragmaDamping::new(0, 1.0.into(), 0.1.into())); "PragmaDamping")]
+#[test_case(Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 10, None)); "PragmaRepeatedMeasurement")]
+#[test_case(Operation::from(PragmaActiveReset::new(0)); "PragmaActiveReset")]
+fn test_unitary_matrix_non_unitary(operation: Operation) {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit.add_operation(operation);
+    assert!(matches!(
+        circuit.unitary_matrix(),
+        Err(RoqoqoError::GenericError { .. })
+    ));
+}
+
+#[test]
+fn test_unitary_matrix_symbolic() {
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, "theta".into());
+    assert!(matches!(
+        circuit.unitary_matrix(),
+        Err(RoqoqoError::CalculatorError(_))
+    ));
+    assert!(circuit.is_equivalent(&Circuit::new(), 1e-10, true).is_err());
+}
+
+#[test_case(40; "matrix_too_large")]
+#[test_case(64; "dimension_overflow")]
+fn test_unitary_matrix_too_many_qubits(qubit: usize) {
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(qubit);
+    assert!(matches!(
+        circuit.unitary_matrix(),
+        Err(RoqoqoError::GenericError { .. })
+    ));
+}
+
+/// Test equivalence checks with and without global phase
+#[test]
+fn test_is_equivalent() {
+    let mut cnot = Circuit::new();
+    cnot += CNOT::new(0, 1);
+    let mut decomposed = Circuit::new();
+    decomposed += Hadamard::new(1);
+    decomposed += ControlledPauliZ::new(0, 1);
+    decomposed += Hadamard::new(1);
+    assert!(cnot.is_equivalent(&decomposed, 1e-10, false).unwrap());
+    assert!(decomposed.is_equivalent(&cnot, 1e-10, true).unwrap());
+
+    let mut reversed = Circuit::new();
+    reversed += CNOT::new(1, 0);
+    assert!(!cnot.is_equivalent(&reversed, 1e-10, true).unwrap());
+
+    // RotateZ(2π) = -I only differs from the identity by a global phase
+    let mut rotation = Circuit::new();
+    rotation += RotateZ::new(0, (2.0 * std::f64::consts::PI).into());
+    assert!(rotation
+        .is_equivalent(&Circuit::new(), 1e-10, true)
+        .unwrap());
+    assert!(!rotation
+        .is_equivalent(&Circuit::new(), 1e-10, false)
+        .unwrap());
+
+    // Circuits on different numbers of qubits are compared on the larger register
+    let mut identity = Circuit::new();
+    identity += Identity::new(3);
+    assert!(identity
+        .is_equivalent(&Circuit::new(), 1e-10, false)
+        .unwrap());
+
+    let mut small_rotation = Circuit::new();
+    small_rotation += RotateX::new(0, 1e-6.into());
+    assert!(small_rotation
+        .is_equivalent(&Circuit::new(), 1e-5, false)
+        .unwrap());
+    assert!(!small_rotation
+        .is_equivalent(&Circuit::new(), 1e-8, false)
+        .unwrap());
+}
+
+/// Test that the decompositions of multi-qubit gates implement the same unitary as the gates
+#[test_case(ControlledControlledPauliZ::new(0, 2, 1).into(), ControlledControlledPauliZ::new(0, 2, 1).circuit(); "ControlledControlledPauliZ")]
+#[test_case(ControlledControlledPhaseShift::new(2, 0, 1, 0.3.into()).into(), ControlledControlledPhaseShift::new(2, 0, 1, 0.3.into()).circuit(); "ControlledControlledPhaseShift")]
+#[test_case(Toffoli::new(1, 2, 0).into(), Toffoli::new(1, 2, 0).circuit(); "Toffoli")]
+#[test_case(ControlledSWAP::new(2, 0, 1).into(), ControlledSWAP::new(2, 0, 1).circuit(); "ControlledSWAP")]
+#[test_case(MultiQubitMS::new(vec![0, 1, 2], 0.7.into()).into(), MultiQubitMS::new(vec![0, 1, 2], 0.7.into()).circuit(); "MultiQubitMS")]
+#[test_case(MultiQubitZZ::new(vec![0, 1, 2], 0.4.into()).into(), MultiQubitZZ::new(vec![0, 1, 2], 0.4.into()).circuit(); "MultiQubitZZ")]
+#[test_case(MultiQubitCNOT::new(vec![0, 2, 1]).into(), MultiQubitCNOT::new(vec![0, 2, 1]).circuit(); "MultiQubitCNOT")]
+#[test_case(QFT::new(vec![0, 1, 2], true, false).into(), QFT::new(vec![0, 1, 2], true, false).circuit(); "QFT")]
+fn test_decomposition_equivalence(gate: Operation, decomposition: Circuit) {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(gate);
+    assert!(circuit.is_equivalent(&decomposition, 1e-10, true).unwrap());
+}
+
 /// Test overrotate circuit
 #[test]
 #[cfg(feature = "overrotate")]
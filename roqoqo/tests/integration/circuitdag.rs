@@ -12,6 +12,7 @@
 
 use std::collections::HashSet;
 
+use qoqo_calculator::CalculatorFloat;
 use roqoqo::{
     operations::{self, *},
     RoqoqoError,
@@ -508,3 +509,273 @@ fn test_successors() {
     assert_eq!(iter.next().unwrap(), d.into());
     assert!(iter.next().is_none());
 }
+
+/// Returns the operations of a CircuitDag in a topological order that executes the latest
+/// operations as early as possible, reordering the circuit as much as the dependencies allow.
+fn reversed_topological_circuit(dag: &CircuitDag, number_nodes: usize) -> Circuit {
+    let mut in_degree = vec![0; number_nodes];
+    for node in 0..number_nodes {
+        for successor in dag.successors(node) {
+            in_degree[successor.index()] += 1;
+        }
+    }
+    let mut ready: Vec<usize> = (0..number_nodes)
+        .filter(|node| in_degree[*node] == 0)
+        .collect();
+    let mut circuit = Circuit::new();
+    while let Some(node) = ready.iter().copied().max() {
+        ready.retain(|other| *other != node);
+        circuit.add_operation(dag.get(node).unwrap().clone());
+        for successor in dag.successors(node) {
+            in_degree[successor.index()] -= 1;
+            if in_degree[successor.index()] == 0 {
+                ready.push(successor.index());
+            }
+        }
+    }
+    circuit
+}
+
+/// Test that reordering the operations within the commutation-aware CircuitDag keeps the circuit equivalent
+#[test_case(vec![
+    Operation::from(CNOT::new(0, 1)),
+    Operation::from(RotateZ::new(0, 0.3.into())),
+    Operation::from(RotateX::new(1, 0.2.into())),
+    Operation::from(PauliX::new(0)),
+    Operation::from(CNOT::new(0, 2)),
+    Operation::from(CNOT::new(1, 2)),
+    Operation::from(Hadamard::new(2)),
+]; "cnot")]
+#[test_case(vec![
+    Operation::from(ControlledPauliZ::new(0, 1)),
+    Operation::from(PauliZ::new(1)),
+    Operation::from(ControlledPhaseShift::new(1, 2, 0.4.into())),
+    Operation::from(RotateZ::new(2, 0.1.into())),
+    Operation::from(PauliX::new(0)),
+    Operation::from(ControlledPauliZ::new(0, 2)),
+]; "diagonal")]
+#[test_case(vec![
+    Operation::from(PauliX::new(0)),
+    Operation::from(PauliY::new(0)),
+    Operation::from(VariableMSXX::new(0, 1, 0.5.into())),
+    Operation::from(PauliX::new(1)),
+    Operation::from(RotateX::new(0, 0.2.into())),
+    Operation::from(MultiQubitZZ::new(vec![0, 1, 2], 0.3.into())),
+    Operation::from(PauliY::new(2)),
+    Operation::from(PauliZ::new(1)),
+]; "pauli")]
+#[test_case(vec![
+    Operation::from(Toffoli::new(0, 1, 2)),
+    Operation::from(TGate::new(0)),
+    Operation::from(SqrtPauliX::new(2)),
+    Operation::from(SWAP::new(1, 2)),
+    Operation::from(ISwap::new(0, 1)),
+    Operation::from(MultiQubitCNOT::new(vec![1, 0, 2])),
+    Operation::from(RotateY::new(1, 0.7.into())),
+    Operation::from(SGate::new(1)),
+]; "numeric")]
+fn test_commutation_dag_equivalence(operations: Vec<Operation>) {
+    let mut circuit = Circuit::new();
+    for operation in operations.iter() {
+        circuit.add_operation(operation.clone());
+    }
+    let dag = CircuitDag::from_circuit_with_commutation(circuit.clone());
+    let reordered = reversed_topological_circuit(&dag, operations.len());
+    assert_eq!(reordered.len(), circuit.len());
+    assert!(reordered.is_equivalent(&circuit, 1e-10, false).unwrap());
+    let sorted = Circuit::from(dag);
+    assert!(sorted.is_equivalent(&circuit, 1e-10, false).unwrap());
+}
+
+/// Test the dependencies of the commutation-aware CircuitDag
+#[test_case(Operation::from(CNOT::new(0, 1)), Operation::from(RotateZ::new(0, "theta".into())), false; "rotate_z_cnot_control")]
+#[test_case(Operation::from(CNOT::new(0, 1)), Operation::from(RotateZ::new(1, "theta".into())), true; "rotate_z_cnot_target")]
+#[test_case(Operation::from(CNOT::new(0, 1)), Operation::from(RotateX::new(1, "theta".into())), false; "rotate_x_cnot_target")]
+#[test_case(Operation::from(RotateZ::new(0, "theta".into())), Operation::from(ControlledPhaseShift::new(0, 1, "phi".into())), false; "diagonal")]
+#[test_case(Operation::from(PauliX::new(0)), Operation::from(PauliX::new(0)), false; "pauli_equal")]
+#[test_case(Operation::from(PauliX::new(0)), Operation::from(PauliY::new(0)), true; "pauli_anticommuting")]
+#[test_case(Operation::from(VariableMSXX::new(0, 1, "theta".into())), Operation::from(MultiQubitZZ::new(vec![0, 1], "phi".into())), false; "pauli_strings")]
+#[test_case(Operation::from(VariableMSXX::new(0, 1, "theta".into())), Operation::from(RotateZ::new(0, "phi".into())), true; "pauli_strings_anticommuting")]
+#[test_case(Operation::from(CNOT::new(0, 1)), Operation::from(CNOT::new(0, 2)), false; "numeric_commuting")]
+#[test_case(Operation::from(CNOT::new(0, 1)), Operation::from(CNOT::new(1, 2)), true; "numeric_not_commuting")]
+#[test_case(Operation::from(Hadamard::new(0)), Operation::from(RotateZ::new(0, "theta".into())), true; "symbolic")]
+#[test_case(Operation::from(MeasureQubit::new(0, "ro".to_string(), 0)), Operation::from(RotateZ::new(0, 0.1.into())), true; "measurement")]
+#[test_case(Operation::from(PragmaSleep::new(vec![0], 0.1.into())), Operation::from(PauliZ::new(0)), true; "pragma")]
+fn test_commutation_dag_edges(first: Operation, second: Operation, dependent: bool) {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(first);
+    circuit.add_operation(second);
+    let dag = CircuitDag::from_circuit_with_commutation(circuit);
+    assert_eq!(dag.successors(0).any(|node| node.index() == 1), dependent);
+    let expected: HashSet<usize> = if dependent { vec![0] } else { vec![0, 1] }
+        .into_iter()
+        .collect();
+    assert_eq!(dag.first_parallel_block(), &expected);
+    let expected: HashSet<usize> = if dependent { vec![1] } else { vec![0, 1] }
+        .into_iter()
+        .collect();
+    assert_eq!(dag.last_parallel_block(), &expected);
+}
+
+/// Test that the commutation-aware CircuitDag exposes larger parallel blocks
+#[test]
+fn test_commutation_dag_parallel_blocks() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += CNOT::new(0, 1);
+    circuit += CNOT::new(0, 2);
+    circuit += RotateZ::new(0, "theta".into());
+    circuit += CNOT::new(0, 3);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    circuit += RotateZ::new(1, 0.1.into());
+    circuit += PauliX::new(1);
+
+    let dag = CircuitDag::from(circuit.clone());
+    assert_eq!(dag.parallel_blocks().count(), 7);
+
+    let dag = CircuitDag::from_circuit_with_commutation(circuit);
+    let blocks: Vec<Vec<usize>> = dag
+        .parallel_blocks()
+        .map(|mut block| {
+            block.sort_unstable();
+            block
+        })
+        .collect();
+    assert_eq!(blocks, vec![vec![1, 2, 3, 4], vec![5], vec![6], vec![7]]);
+    assert_eq!(dag.commuting_operations(), &vec![0]);
+    assert_eq!(dag.first_operation_involving_qubit().get(&0), Some(&1));
+    assert_eq!(dag.last_operation_involving_qubit().get(&0), Some(&5));
+    assert_eq!(dag.last_operation_involving_qubit().get(&1), Some(&7));
+    assert_eq!(
+        dag.first_operation_involving_classical()
+            .get(&("ro".to_string(), 0)),
+        Some(&0)
+    );
+    assert_eq!(
+        dag.last_operation_involving_classical()
+            .get(&("ro".to_string(), 0)),
+        Some(&5)
+    );
+    assert_eq!(
+        dag.new_front_layer(&[1, 2, 3], &[1, 2, 3, 4], &4),
+        Ok(vec![1, 2, 3, 5])
+    );
+}
+
+/// Returns the commutation-aware CircuitDag of commuting operations on qubit 0,
+/// optionally after a serialization round trip.
+fn commuting_dag(round_trip: bool) -> CircuitDag {
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, 0.1.into());
+    circuit += PauliZ::new(0);
+    circuit += PauliX::new(1);
+    let dag = CircuitDag::from_circuit_with_commutation(circuit);
+    assert_eq!(dag.first_parallel_block(), &HashSet::from([0, 1, 2]));
+    if round_trip {
+        #[cfg(feature = "serialize")]
+        return serde_json::from_str(&serde_json::to_string(&dag).unwrap()).unwrap();
+    }
+    dag
+}
+
+/// Returns the sorted parallel blocks of a CircuitDag.
+fn sorted_parallel_blocks(dag: &CircuitDag) -> Vec<Vec<usize>> {
+    dag.parallel_blocks()
+        .map(|mut block| {
+            block.sort_unstable();
+            block
+        })
+        .collect()
+}
+
+/// Test adding operations to the back of a commutation-aware CircuitDag with several last operations on a qubit
+#[test_case(Operation::from(Hadamard::new(0)), false; "single_qubit")]
+#[test_case(Operation::from(CNOT::new(1, 0)), false; "two_qubit")]
+#[test_case(Operation::from(Hadamard::new(0)), true; "round_trip")]
+fn test_commutation_dag_add_to_back(operation: Operation, round_trip: bool) {
+    let mut dag = commuting_dag(round_trip);
+    let node = dag.add_to_back(operation).unwrap();
+    assert_eq!(node, 3);
+    assert!(dag.successors(0).any(|successor| successor.index() == 3));
+    assert!(dag.successors(1).any(|successor| successor.index() == 3));
+    assert!(dag.last_parallel_block().contains(&3));
+    assert!(!dag.last_parallel_block().contains(&0));
+    assert!(!dag.last_parallel_block().contains(&1));
+    assert_eq!(sorted_parallel_blocks(&dag), vec![vec![0, 1, 2], vec![3]]);
+
+    let node = dag.add_to_back(PauliX::new(0).into()).unwrap();
+    assert!(dag.last_parallel_block().contains(&node));
+    assert!(!dag.last_parallel_block().contains(&3));
+    assert_eq!(
+        sorted_parallel_blocks(&dag),
+        vec![vec![0, 1, 2], vec![3], vec![4]]
+    );
+}
+
+/// Test adding operations to the front of a commutation-aware CircuitDag with several first operations on a qubit
+#[test_case(Operation::from(Hadamard::new(0)), false; "single_qubit")]
+#[test_case(Operation::from(Hadamard::new(0)), true; "round_trip")]
+fn test_commutation_dag_add_to_front(operation: Operation, round_trip: bool) {
+    let mut dag = commuting_dag(round_trip);
+    let node = dag.add_to_front(operation).unwrap();
+    assert_eq!(node, 3);
+    assert!(dag.successors(3).any(|successor| successor.index() == 0));
+    assert!(dag.successors(3).any(|successor| successor.index() == 1));
+    assert_eq!(dag.first_parallel_block(), &HashSet::from([2, 3]));
+    assert_eq!(sorted_parallel_blocks(&dag), vec![vec![2, 3], vec![0, 1]]);
+}
+
+/// Test adding an operation involving all qubits to a commutation-aware CircuitDag
+#[test]
+fn test_commutation_dag_add_all_qubit_operation() {
+    let mut dag = commuting_dag(false);
+    let back = dag
+        .add_to_back(PragmaGlobalPhase::new(0.1.into()).into())
+        .unwrap();
+    assert_eq!(dag.commuting_operations(), &vec![back]);
+    let back = dag
+        .add_to_back(PragmaStopParallelBlock::new(vec![0, 1], 0.0.into()).into())
+        .unwrap();
+    assert_eq!(dag.last_parallel_block(), &HashSet::from([back]));
+    for node in [0, 1, 2] {
+        assert!(dag
+            .successors(node)
+            .any(|successor| successor.index() == back));
+    }
+}
+
+/// Test the commutation-aware CircuitDag on a longer circuit mixing all commutation rules
+#[test]
+fn test_commutation_dag_long_circuit() {
+    let mut circuit = Circuit::new();
+    let mut state: u64 = 7;
+    for _ in 0..120 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let value = (state >> 33) as usize;
+        let (first, second) = (value % 4, (value / 4) % 3);
+        let second = if second >= first { second + 1 } else { second };
+        let angle = CalculatorFloat::from((value % 17) as f64 * 0.1);
+        let operation: Operation = match (value / 16) % 12 {
+            0 => CNOT::new(first, second).into(),
+            1 => RotateZ::new(first, angle).into(),
+            2 => RotateX::new(first, angle).into(),
+            3 => PauliX::new(first).into(),
+            4 => PauliY::new(first).into(),
+            5 => Hadamard::new(first).into(),
+            6 => ControlledPauliZ::new(first, second).into(),
+            7 => VariableMSXX::new(first, second, angle).into(),
+            8 => SGate::new(first).into(),
+            9 => ControlledPhaseShift::new(first, second, angle).into(),
+            10 => SqrtPauliX::new(first).into(),
+            _ => ISwap::new(first, second).into(),
+        };
+        circuit.add_operation(operation);
+    }
+    let dag = CircuitDag::from_circuit_with_commutation(circuit.clone());
+    let reordered = reversed_topological_circuit(&dag, circuit.len());
+    assert_ne!(reordered, circuit);
+    assert!(reordered.is_equivalent(&circuit, 1e-10, false).unwrap());
+    assert!(dag.parallel_blocks().count() < CircuitDag::from(circuit).parallel_blocks().count());
+}
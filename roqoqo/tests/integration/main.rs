@@ -22,9 +22,19 @@ mod circuit;
 #[cfg(test)]
 mod devices;
 
+#[cfg(test)]
+mod qasm;
+
 #[cfg(test)]
 mod quantum_program;
 
 #[cfg(test)]
 #[cfg(feature = "circuitdag")]
 mod circuitdag;
+
+#[cfg(test)]
+#[cfg(feature = "simulators")]
+mod simulators;
+
+#[cfg(test)]
+mod transpiler;
@@ -14,17 +14,23 @@
 
 #[cfg(feature = "jsonschema")]
 use jsonschema::{Draft, Validator};
-use qoqo_calculator::CalculatorFloat;
+use qoqo_calculator::{CalculatorComplex, CalculatorFloat};
+use roqoqo::noise_models::ImperfectReadoutModel;
 use roqoqo::operations;
 use roqoqo::prelude::*;
-use roqoqo::Circuit;
+#[cfg(feature = "simulators")]
+use roqoqo::simulators::StatevectorSimulator;
 use roqoqo::{
-    measurements::{PauliZProduct, PauliZProductInput},
+    measurements::{PauliZProduct, PauliZProductInput, ReadoutErrorMitigation},
     registers::BitOutputRegister,
 };
+use roqoqo::{Circuit, RoqoqoError};
 #[cfg(feature = "jsonschema")]
 use schemars::schema_for;
 use std::collections::HashMap;
+use std::str::FromStr;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliOperator, PauliProduct};
 use test_case::test_case;
 
 #[test]
@@ -365,6 +371,150 @@ fn test_evaluate_symbolic(register: Vec<Vec<bool>>, constant: f64) {
     assert!((result.get("constant").unwrap() - constant).abs() < f64::EPSILON);
 }
 
+/// Returns a PauliZProduct measuring Z0, Z1 and Z0 Z1 of two qubits.
+fn readout_error_measurement(use_flipped_measurement: bool) -> PauliZProduct {
+    let mut bri = PauliZProductInput::new(2, use_flipped_measurement);
+    let mut expectation_values = Vec::new();
+    for mask in [vec![0], vec![1], vec![0, 1]] {
+        let index = bri.add_pauliz_product("ro".to_string(), mask).unwrap();
+        expectation_values.push(index);
+    }
+    for (name, index) in ["z0", "z1", "z0z1"].iter().zip(expectation_values) {
+        bri.add_linear_exp_val(name.to_string(), HashMap::from([(index, 1.0)]))
+            .unwrap();
+    }
+    PauliZProduct {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input: bri,
+    }
+}
+
+/// Returns 10 readouts of the state |00> with qubit 0 misread in the first `errors_0` shots
+/// and qubit 1 misread in the last `errors_1` shots.
+fn misread_register(errors_0: usize, errors_1: usize, flipped: bool) -> BitOutputRegister {
+    (0..10)
+        .map(|shot| {
+            vec![
+                (shot < errors_0) ^ flipped,
+                (shot >= 10 - errors_1) ^ flipped,
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn test_evaluate_readout_fidelities() {
+    let br = readout_error_measurement(false);
+    let mitigation = ReadoutErrorMitigation::Fidelities(HashMap::from([(0, 0.8), (1, 0.5)]));
+    let registers = HashMap::from([("ro".to_string(), misread_register(1, 0, false))]);
+    let unmitigated = br
+        .evaluate(registers.clone(), HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert!((unmitigated["z0"] - 0.8).abs() < 1e-12);
+    let result = br
+        .evaluate_with_readout_error_mitigation(registers, &mitigation)
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] - 0.8 / 0.8).abs() < 1e-12);
+    assert!((result["z1"] - 1.0 / 0.5).abs() < 1e-12);
+    assert!((result["z0z1"] - 0.8 / 0.4).abs() < 1e-12);
+}
+
+#[test]
+fn test_evaluate_stored_readout_error_mitigation() {
+    let mut br = readout_error_measurement(false);
+    let mitigation = ReadoutErrorMitigation::Fidelities(HashMap::from([(0, 0.8), (1, 0.5)]));
+    br.input
+        .set_readout_error_mitigation(Some(mitigation.clone()))
+        .unwrap();
+    let registers = HashMap::from([("ro".to_string(), misread_register(1, 0, false))]);
+    let result = br
+        .evaluate(registers.clone(), HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] - 0.8 / 0.8).abs() < 1e-12);
+    assert!((result["z1"] - 1.0 / 0.5).abs() < 1e-12);
+    assert!((result["z0z1"] - 0.8 / 0.4).abs() < 1e-12);
+    let with_errors = br
+        .evaluate_with_errors(registers.clone(), HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert!((with_errors.values["z1"] - 1.0 / 0.5).abs() < 1e-12);
+    // An explicitly given mitigation replaces the stored one
+    let perfect = ReadoutErrorMitigation::Fidelities(HashMap::new());
+    let result = br
+        .evaluate_with_readout_error_mitigation(registers, &perfect)
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] - 0.8).abs() < 1e-12);
+}
+
+#[test_case(false; "rescaling")]
+#[test_case(true; "confusion matrix inversion")]
+fn test_evaluate_imperfect_readout_model_flipped(confusion_matrix_inversion: bool) {
+    // Detecting 0 as 1 with probability 0.1 and 1 as 0 with probability 0.2
+    let model = ImperfectReadoutModel::new_with_uniform_error(2, 0.1, 0.2).unwrap();
+    let br = readout_error_measurement(true);
+    let mitigation = ReadoutErrorMitigation::ImperfectReadoutModel {
+        model,
+        confusion_matrix_inversion,
+    };
+    // The flipped measurement reads |11>, misreading 1 as 0 in two of ten shots
+    let registers = HashMap::from([
+        ("ro".to_string(), misread_register(1, 1, false)),
+        ("ro_flipped".to_string(), misread_register(2, 2, true)),
+    ]);
+    let result = br
+        .evaluate_with_readout_error_mitigation(registers, &mitigation)
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] - 1.0).abs() < 1e-12);
+    assert!((result["z1"] - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_evaluate_confusion_matrix_inversion() {
+    let model = ImperfectReadoutModel::new_with_uniform_error(2, 0.1, 0.2).unwrap();
+    let br = readout_error_measurement(false);
+    let mitigation = ReadoutErrorMitigation::ImperfectReadoutModel {
+        model: model.clone(),
+        confusion_matrix_inversion: true,
+    };
+    let registers = HashMap::from([("ro".to_string(), misread_register(1, 1, false))]);
+    let result = br
+        .evaluate_with_readout_error_mitigation(registers.clone(), &mitigation)
+        .unwrap()
+        .unwrap();
+    // Without flipped measurements only the inversion removes the shift of asymmetric errors
+    assert!((result["z0"] - 1.0).abs() < 1e-12);
+    assert!((result["z1"] - 1.0).abs() < 1e-12);
+    // Single shot values of Z0 Z1: 8 shots (0.9/0.7)^2, 2 shots (0.9/0.7)(-1.1/0.7)
+    let expected = (8.0 * 0.81 - 2.0 * 0.99) / (10.0 * 0.49);
+    assert!((result["z0z1"] - expected).abs() < 1e-12);
+
+    let mitigation = ReadoutErrorMitigation::ImperfectReadoutModel {
+        model,
+        confusion_matrix_inversion: false,
+    };
+    let result = br
+        .evaluate_with_readout_error_mitigation(registers, &mitigation)
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] - 0.8 / 0.7).abs() < 1e-12);
+}
+
+#[test]
+fn test_evaluate_invalid_readout_error_mitigation() {
+    let br = readout_error_measurement(false);
+    let mitigation = ReadoutErrorMitigation::Fidelities(HashMap::from([(1, 0.0)]));
+    let registers = HashMap::from([("ro".to_string(), misread_register(0, 0, false))]);
+    assert!(br
+        .evaluate_with_readout_error_mitigation(registers, &mitigation)
+        .is_err());
+}
+
 #[cfg(feature = "json_schema")]
 #[test]
 fn test_json_schema() {
@@ -402,3 +552,363 @@ fn test_json_schema() {
     let validation_result = compiled_schema.validate(&test_value);
     assert!(validation_result.is_ok());
 }
+
+#[test]
+fn test_from_pauli_hamiltonian_grouping() {
+    let mut hamiltonian = PauliHamiltonian::new();
+    for (product, coefficient) in [
+        ("0X", 1.0),
+        ("0X1X", 2.0),
+        ("2Z", 3.0),
+        ("1Y", 4.0),
+        ("", 5.0),
+    ] {
+        hamiltonian
+            .add_operator_product(PauliProduct::from_str(product).unwrap(), coefficient.into())
+            .unwrap();
+    }
+    let measurement =
+        PauliZProduct::from_pauli_hamiltonian(&hamiltonian, "energy".to_string(), 3, 100, None)
+            .unwrap();
+
+    let mut circuit_0 = Circuit::new();
+    circuit_0 += operations::DefinitionBit::new("ro_0".to_string(), 3, true);
+    circuit_0 += operations::Hadamard::new(0);
+    circuit_0 += operations::Hadamard::new(1);
+    circuit_0 += operations::PragmaRepeatedMeasurement::new("ro_0".to_string(), 100, None);
+    let mut circuit_1 = Circuit::new();
+    circuit_1 += operations::DefinitionBit::new("ro_1".to_string(), 3, true);
+    circuit_1 += operations::RotateX::new(1, CalculatorFloat::FRAC_PI_2);
+    circuit_1 += operations::PragmaRepeatedMeasurement::new("ro_1".to_string(), 100, None);
+    assert_eq!(measurement.circuits, vec![circuit_0, circuit_1]);
+    assert_eq!(measurement.constant_circuit, None);
+    assert_eq!(measurement.input.number_pauli_products, 5);
+    assert_eq!(measurement.input.pauli_product_qubit_masks["ro_0"].len(), 4);
+    assert_eq!(measurement.input.pauli_product_qubit_masks["ro_1"].len(), 1);
+
+    // All qubits measured in |0> give +1 for every Pauli product
+    let mut bit_registers: HashMap<String, BitOutputRegister> = HashMap::new();
+    bit_registers.insert("ro_0".to_string(), vec![vec![false; 3]; 4]);
+    bit_registers.insert("ro_1".to_string(), vec![vec![false; 3]; 4]);
+    let result = measurement
+        .evaluate(bit_registers, HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert!((result["energy"] - 15.0).abs() < 1e-10);
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_from_pauli_operator_bell_state() {
+    let mut operator = PauliOperator::new();
+    for (product, coefficient) in [("0X1X", 1.0), ("0Z1Z", 2.0), ("0Y1Y", 0.5), ("", 0.25)] {
+        operator
+            .add_operator_product(PauliProduct::from_str(product).unwrap(), coefficient.into())
+            .unwrap();
+    }
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::Hadamard::new(0);
+    constant_circuit += operations::CNOT::new(0, 1);
+    let measurement = PauliZProduct::from_pauli_operator(
+        &operator,
+        "energy".to_string(),
+        2,
+        50,
+        Some(constant_circuit),
+    )
+    .unwrap();
+    assert_eq!(measurement.circuits.len(), 3);
+
+    let backend = StatevectorSimulator::new(Some(7));
+    let result = backend.run_measurement(&measurement).unwrap().unwrap();
+    assert!((result["energy"] - 2.75).abs() < 1e-10);
+}
+
+#[test]
+fn test_from_pauli_hamiltonian_errors() {
+    let mut hamiltonian = PauliHamiltonian::new();
+    hamiltonian
+        .add_operator_product(PauliProduct::from_str("3Z").unwrap(), 1.0.into())
+        .unwrap();
+    let error =
+        PauliZProduct::from_pauli_hamiltonian(&hamiltonian, "energy".to_string(), 2, 10, None);
+    assert_eq!(
+        error,
+        Err(RoqoqoError::PauliProductExceedsQubits {
+            pp_qubit: 3,
+            number_qubits: 2
+        })
+    );
+
+    let mut hamiltonian = PauliHamiltonian::new();
+    hamiltonian
+        .add_operator_product(PauliProduct::from_str("0Z").unwrap(), "theta".into())
+        .unwrap();
+    let error =
+        PauliZProduct::from_pauli_hamiltonian(&hamiltonian, "energy".to_string(), 2, 10, None);
+    assert!(matches!(
+        error,
+        Err(RoqoqoError::PauliZProductMeasurementError { .. })
+    ));
+
+    let mut operator = PauliOperator::new();
+    operator
+        .add_operator_product(
+            PauliProduct::from_str("0Z").unwrap(),
+            CalculatorComplex::new(1.0, 1.0),
+        )
+        .unwrap();
+    let error = PauliZProduct::from_pauli_operator(&operator, "energy".to_string(), 2, 10, None);
+    assert!(matches!(
+        error,
+        Err(RoqoqoError::PauliZProductMeasurementError { .. })
+    ));
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_from_pauli_hamiltonian_y_basis() {
+    let mut hamiltonian = PauliHamiltonian::new();
+    hamiltonian
+        .add_operator_product(PauliProduct::from_str("0Y").unwrap(), 2.0.into())
+        .unwrap();
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::Hadamard::new(0);
+    constant_circuit += operations::SGate::new(0);
+    let measurement = PauliZProduct::from_pauli_hamiltonian(
+        &hamiltonian,
+        "y".to_string(),
+        1,
+        20,
+        Some(constant_circuit),
+    )
+    .unwrap();
+
+    let backend = StatevectorSimulator::new(Some(3));
+    let result = backend.run_measurement(&measurement).unwrap().unwrap();
+    assert!((result["y"] - 2.0).abs() < 1e-10);
+}
+
+fn statistics_measurement(use_flipped_measurement: bool) -> PauliZProduct {
+    let mut input = PauliZProductInput::new(2, use_flipped_measurement);
+    let z0 = input.add_pauliz_product("ro".to_string(), vec![0]).unwrap();
+    let z0z1 = input
+        .add_pauliz_product("ro".to_string(), vec![0, 1])
+        .unwrap();
+    let z0_other = input
+        .add_pauliz_product("ro_1".to_string(), vec![0])
+        .unwrap();
+    input
+        .add_linear_exp_val("a".to_string(), HashMap::from([(z0, 2.0)]))
+        .unwrap();
+    input
+        .add_linear_exp_val("b".to_string(), HashMap::from([(z0, 1.0), (z0z1, 1.0)]))
+        .unwrap();
+    input
+        .add_linear_exp_val("c".to_string(), HashMap::from([(z0, 1.0), (z0_other, 1.0)]))
+        .unwrap();
+    input
+        .add_symbolic_exp_val(
+            "d".to_string(),
+            "pauli_product_0 + 3 * pauli_product_1".into(),
+        )
+        .unwrap();
+    PauliZProduct {
+        constant_circuit: None,
+        circuits: vec![Circuit::new(), Circuit::new()],
+        input,
+    }
+}
+
+fn statistics_registers(use_flipped_measurement: bool) -> HashMap<String, BitOutputRegister> {
+    let ro: BitOutputRegister = vec![
+        vec![false, false],
+        vec![false, true],
+        vec![true, false],
+        vec![true, true],
+    ];
+    let ro_1: BitOutputRegister = vec![
+        vec![false, false],
+        vec![false, false],
+        vec![false, false],
+        vec![true, false],
+    ];
+    let mut registers: HashMap<String, BitOutputRegister> = HashMap::new();
+    if use_flipped_measurement {
+        let flip = |register: &BitOutputRegister| -> BitOutputRegister {
+            register
+                .iter()
+                .map(|shot| shot.iter().map(|bit| !bit).collect())
+                .collect()
+        };
+        registers.insert("ro_flipped".to_string(), flip(&ro));
+        registers.insert("ro_1_flipped".to_string(), flip(&ro_1));
+    }
+    registers.insert("ro".to_string(), ro);
+    registers.insert("ro_1".to_string(), ro_1);
+    registers
+}
+
+#[test_case(false, 1.0; "single readout")]
+#[test_case(true, 0.5; "flipped readout")]
+fn test_evaluate_with_errors(use_flipped_measurement: bool, variance_factor: f64) {
+    let measurement = statistics_measurement(use_flipped_measurement);
+    let registers = statistics_registers(use_flipped_measurement);
+    let result = measurement
+        .evaluate_with_errors(registers.clone(), HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    let values = measurement
+        .evaluate(registers, HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.values, values);
+
+    // Single shot variances of the register means: Z0 and Z0Z1 1/3 (uncorrelated), Z0 of ro_1 1/4
+    let expected = [
+        ("a", "a", 4.0 / 3.0),
+        ("b", "b", 2.0 / 3.0),
+        ("a", "b", 2.0 / 3.0),
+        ("c", "c", 1.0 / 3.0 + 1.0 / 4.0),
+        ("a", "c", 2.0 / 3.0),
+        ("d", "d", 10.0 / 3.0),
+        ("b", "d", 4.0 / 3.0),
+    ];
+    for (first, second, covariance) in expected {
+        let covariance = covariance * variance_factor;
+        assert!((result.covariance(first, second).unwrap() - covariance).abs() < 1e-6);
+        assert!((result.covariance(second, first).unwrap() - covariance).abs() < 1e-6);
+        if first == second {
+            assert!((result.standard_errors[first] - covariance.sqrt()).abs() < 1e-6);
+        }
+    }
+    assert_eq!(result.covariance("a", "missing"), None);
+}
+
+#[test]
+fn test_evaluate_with_errors_readout_fidelities() {
+    let measurement = statistics_measurement(false);
+    let mitigation = ReadoutErrorMitigation::Fidelities(HashMap::from([(0, 0.5), (1, 1.0)]));
+    let result = measurement
+        .evaluate_with_errors_and_readout_error_mitigation(statistics_registers(false), &mitigation)
+        .unwrap()
+        .unwrap();
+    // Rescaling by the fidelity of qubit 0 scales the variances by 1 / 0.5^2
+    assert!((result.standard_errors["a"] - (16.0_f64 / 3.0).sqrt()).abs() < 1e-10);
+    assert!((result.standard_errors["c"] - (4.0_f64 / 3.0 + 1.0).sqrt()).abs() < 1e-10);
+}
+
+#[test]
+fn test_evaluate_with_errors_single_shot() {
+    let mut input = PauliZProductInput::new(1, false);
+    let z0 = input.add_pauliz_product("ro".to_string(), vec![0]).unwrap();
+    input
+        .add_linear_exp_val("a".to_string(), HashMap::from([(z0, 1.0)]))
+        .unwrap();
+    let measurement = PauliZProduct {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input,
+    };
+    let registers = HashMap::from([("ro".to_string(), vec![vec![true]])]);
+    let result = measurement
+        .evaluate_with_errors(registers, HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.values["a"], -1.0);
+    assert!(result.standard_errors["a"].is_nan());
+}
+
+fn number_measurements(circuit: &Circuit) -> Vec<usize> {
+    circuit
+        .iter()
+        .filter_map(|operation| match operation {
+            operations::Operation::PragmaRepeatedMeasurement(op) => Some(*op.number_measurements()),
+            operations::Operation::PragmaSetNumberOfMeasurements(op) => {
+                Some(*op.number_measurements())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_allocate_shots() {
+    let mut hamiltonian = PauliHamiltonian::new();
+    for (product, coefficient) in [("0X", 2.0), ("0Z", 0.5), ("1Z", 1.0), ("", 3.0)] {
+        hamiltonian
+            .add_operator_product(PauliProduct::from_str(product).unwrap(), coefficient.into())
+            .unwrap();
+    }
+    let measurement =
+        PauliZProduct::from_pauli_hamiltonian(&hamiltonian, "energy".to_string(), 2, 10, None)
+            .unwrap();
+    let allocated = measurement.allocate_shots("energy", 1000).unwrap();
+    // Weights 3.0 (X0, Z1) and 0.5 (Z0) on top of one shot per circuit
+    assert_eq!(number_measurements(&allocated.circuits[0]), vec![856]);
+    assert_eq!(number_measurements(&allocated.circuits[1]), vec![144]);
+    assert_eq!(allocated.input, measurement.input);
+
+    let mut expected = measurement.clone();
+    expected.circuits[0] = Circuit::new();
+    expected.circuits[0] += operations::DefinitionBit::new("ro_0".to_string(), 2, true);
+    expected.circuits[0] += operations::Hadamard::new(0);
+    expected.circuits[0] +=
+        operations::PragmaRepeatedMeasurement::new("ro_0".to_string(), 856, None);
+    assert_eq!(allocated.circuits[0], expected.circuits[0]);
+}
+
+#[test]
+fn test_allocate_shots_flipped() {
+    let mut input = PauliZProductInput::new(2, true);
+    let z0 = input.add_pauliz_product("ro".to_string(), vec![0]).unwrap();
+    let identity = input.add_pauliz_product("ro".to_string(), vec![]).unwrap();
+    input
+        .add_linear_exp_val("a".to_string(), HashMap::from([(z0, 1.0), (identity, 5.0)]))
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaSetNumberOfMeasurements::new(10, "ro".to_string());
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    let mut flipped_circuit = Circuit::new();
+    flipped_circuit += operations::PauliX::new(0);
+    flipped_circuit +=
+        operations::PragmaRepeatedMeasurement::new("ro_flipped".to_string(), 10, None);
+    let mut unmeasured_circuit = Circuit::new();
+    unmeasured_circuit += operations::PauliX::new(1);
+    let measurement = PauliZProduct {
+        constant_circuit: None,
+        circuits: vec![circuit, flipped_circuit, unmeasured_circuit.clone()],
+        input,
+    };
+
+    let allocated = measurement.allocate_shots("a", 11).unwrap();
+    assert_eq!(number_measurements(&allocated.circuits[0]), vec![6]);
+    assert_eq!(number_measurements(&allocated.circuits[1]), vec![5]);
+    assert_eq!(allocated.circuits[2], unmeasured_circuit);
+}
+
+#[test]
+fn test_allocate_shots_errors() {
+    let mut input = PauliZProductInput::new(1, false);
+    let z0 = input.add_pauliz_product("ro".to_string(), vec![0]).unwrap();
+    input
+        .add_linear_exp_val("a".to_string(), HashMap::from([(z0, 1.0)]))
+        .unwrap();
+    input
+        .add_symbolic_exp_val("b".to_string(), "pauli_product_0".into())
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    let measurement = PauliZProduct {
+        constant_circuit: None,
+        circuits: vec![circuit.clone(), circuit],
+        input,
+    };
+    for (name, total) in [("b", 10), ("c", 10), ("a", 1)] {
+        assert!(matches!(
+            measurement.allocate_shots(name, total),
+            Err(RoqoqoError::PauliZProductMeasurementError { .. })
+        ));
+    }
+    assert!(measurement.allocate_shots("a", 2).is_ok());
+}
@@ -242,3 +242,32 @@ fn test_json_schema() {
     let validation_result = compiled_schema.validate(&test_value);
     assert!(validation_result.is_ok());
 }
+
+#[test]
+fn test_evaluate_with_errors() {
+    let mut bri = CheatedPauliZProductInput::new();
+    let _ = bri.add_pauliz_product("ro_pauli_product_0".to_string());
+    let mut linear_map: HashMap<usize, f64> = HashMap::new();
+    linear_map.insert(0, 3.0);
+    bri.add_linear_exp_val("single_pp_val".to_string(), linear_map)
+        .unwrap();
+    let br = CheatedPauliZProduct {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input: bri,
+    };
+
+    let mut measured_registers: HashMap<String, FloatOutputRegister> = HashMap::new();
+    let _ = measured_registers.insert("ro_pauli_product_0".to_string(), vec![vec![0.5]]);
+    let result = br
+        .evaluate_with_errors(HashMap::new(), measured_registers, HashMap::new())
+        .unwrap()
+        .unwrap();
+    // Cheated measurements are exact
+    assert_eq!(result.values.get("single_pp_val").unwrap(), &1.5);
+    assert_eq!(result.standard_errors.get("single_pp_val").unwrap(), &0.0);
+    assert_eq!(
+        result.covariance("single_pp_val", "single_pp_val"),
+        Some(0.0)
+    );
+}
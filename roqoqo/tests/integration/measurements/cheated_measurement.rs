@@ -181,6 +181,39 @@ fn test_evaluate(register: Vec<Vec<Complex64>>, value_diagonal: f64, value_off_d
     assert!((result.get("test_off_diagonal").unwrap() - value_off_diagonal).abs() < 1e-10);
 }
 
+#[test]
+fn test_evaluate_with_errors() {
+    let mut bri = CheatedInput::new(1);
+    let test_matrix = vec![
+        (0, 0, Complex64::new(1.0, 0.0)),
+        (1, 1, Complex64::new(-1.0, 0.0)),
+    ];
+    bri.add_operator_exp_val("test_diagonal".to_string(), test_matrix, "ro".to_string())
+        .unwrap();
+    let br = Cheated {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input: bri,
+    };
+
+    let mut measured_registers: HashMap<String, ComplexOutputRegister> = HashMap::new();
+    let _ = measured_registers.insert(
+        "ro".to_string(),
+        vec![vec![Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)]],
+    );
+    let result = br
+        .evaluate_with_errors(HashMap::new(), HashMap::new(), measured_registers)
+        .unwrap()
+        .unwrap();
+    // Cheated measurements are exact
+    assert!((result.values["test_diagonal"] - 1.0).abs() < 1e-10);
+    assert_eq!(result.standard_errors["test_diagonal"], 0.0);
+    assert_eq!(
+        result.covariance("test_diagonal", "test_diagonal"),
+        Some(0.0)
+    );
+}
+
 #[test]
 fn test_evaluate_error() {
     let register = vec![vec![
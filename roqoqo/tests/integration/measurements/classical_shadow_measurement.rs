@@ -0,0 +1,446 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration test for public API of classical shadow measurement
+
+#[cfg(feature = "json_schema")]
+use jsonschema::{Draft, Validator};
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::measurements::{ClassicalShadow, ClassicalShadowInput};
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::registers::BitOutputRegister;
+#[cfg(feature = "simulators")]
+use roqoqo::simulators::StatevectorSimulator;
+#[cfg(feature = "simulators")]
+use roqoqo::QuantumProgram;
+use roqoqo::{Circuit, RoqoqoError};
+#[cfg(feature = "json_schema")]
+use schemars::schema_for;
+use std::collections::HashMap;
+use std::str::FromStr;
+use struqture::prelude::*;
+use struqture::spins::{PauliHamiltonian, PauliProduct};
+use test_case::test_case;
+
+fn observable(terms: &[(&str, f64)]) -> PauliHamiltonian {
+    let mut hamiltonian = PauliHamiltonian::new();
+    for (product, coefficient) in terms {
+        hamiltonian
+            .add_operator_product(
+                PauliProduct::from_str(product).unwrap(),
+                CalculatorFloat::from(*coefficient),
+            )
+            .unwrap();
+    }
+    hamiltonian
+}
+
+#[test]
+fn test_new_random() {
+    let input = ClassicalShadowInput::new(3, 5);
+    let measurement =
+        ClassicalShadow::new_random(Some(Circuit::new()), input.clone(), 20, 7, Some(1));
+    assert_eq!(measurement.circuits.len(), 20);
+    assert_eq!(measurement.input.measurement_bases.len(), 20);
+    assert_eq!(measurement.constant_circuit, Some(Circuit::new()));
+    assert_eq!(
+        measurement,
+        ClassicalShadow::new_random(Some(Circuit::new()), input, 20, 7, Some(1))
+    );
+    for (index, (circuit, basis)) in measurement
+        .circuits
+        .iter()
+        .zip(measurement.input.measurement_bases.iter())
+        .enumerate()
+    {
+        let readout = format!("ro_{index}");
+        let mut expected = Circuit::new();
+        expected += operations::DefinitionBit::new(readout.clone(), 3, true);
+        for (qubit, pauli) in basis.iter() {
+            match pauli {
+                struqture::spins::SinglePauliOperator::X => {
+                    expected += operations::Hadamard::new(*qubit)
+                }
+                struqture::spins::SinglePauliOperator::Y => {
+                    expected += operations::RotateX::new(*qubit, CalculatorFloat::FRAC_PI_2)
+                }
+                _ => (),
+            }
+        }
+        expected += operations::PragmaRepeatedMeasurement::new(readout, 7, None);
+        assert_eq!(circuit, &expected);
+        assert_eq!(basis.len(), 3);
+    }
+    // All three bases are drawn
+    let bases: Vec<String> = measurement
+        .input
+        .measurement_bases
+        .iter()
+        .map(|basis| basis.to_string())
+        .collect();
+    for pauli in ["X", "Y", "Z"] {
+        assert!(bases.iter().any(|basis| basis.contains(pauli)));
+    }
+}
+
+#[test_case(1, 1.5; "one group")]
+#[test_case(2, 1.5; "two groups")]
+#[test_case(3, 1.5; "three groups")]
+#[test_case(4, 1.5; "four groups")]
+fn test_evaluate_median_of_means(number_groups: usize, expected: f64) {
+    let mut input = ClassicalShadowInput::new(2, number_groups);
+    input
+        .add_observable("x0".to_string(), observable(&[("0X", 1.0)]))
+        .unwrap();
+    input.measurement_bases = vec![
+        PauliProduct::from_str("0X1Z").unwrap(),
+        PauliProduct::from_str("0Y").unwrap(),
+    ];
+    let measurement = ClassicalShadow {
+        constant_circuit: None,
+        circuits: vec![Circuit::new(), Circuit::new()],
+        input,
+    };
+    let registers: HashMap<String, BitOutputRegister> = HashMap::from([
+        (
+            "ro_0".to_string(),
+            vec![vec![false, false], vec![false, true]],
+        ),
+        (
+            "ro_1".to_string(),
+            vec![vec![true, false], vec![false, false]],
+        ),
+    ]);
+    // Snapshot estimates are [3, 3, 0, 0]
+    let result = measurement
+        .evaluate(registers, HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert!((result["x0"] - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_evaluate_snapshots() {
+    let mut input = ClassicalShadowInput::new(2, 1);
+    input
+        .add_observable(
+            "observable".to_string(),
+            observable(&[("0Z1X", 2.0), ("1Z", 1.0), ("", 0.5)]),
+        )
+        .unwrap();
+    input
+        .add_fidelity(
+            "fidelity".to_string(),
+            vec![1.0.into(), 0.0.into(), 0.0.into(), 0.0.into()],
+        )
+        .unwrap();
+    input.measurement_bases = vec![PauliProduct::from_str("1X").unwrap()];
+    let measurement = ClassicalShadow {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input,
+    };
+    // Qubit 0 measured 1 in Z, qubit 1 measured 0 in X
+    let registers: HashMap<String, BitOutputRegister> =
+        HashMap::from([("ro_0".to_string(), vec![vec![true, false]])]);
+    let result = measurement
+        .evaluate(registers, HashMap::new(), HashMap::new())
+        .unwrap()
+        .unwrap();
+    // 2 * (-3) * 3 + 0 + 0.5
+    assert!((result["observable"] + 17.5).abs() < 1e-12);
+    // <00| (3|1><1| - 1) x (3|+><+| - 1) |00> = (-1) * 0.5
+    assert!((result["fidelity"] + 0.5).abs() < 1e-12);
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_evaluate_statevector() {
+    let mut input = ClassicalShadowInput::new(2, 5);
+    input
+        .add_observable("z0".to_string(), observable(&[("0Z", 1.0)]))
+        .unwrap();
+    input
+        .add_observable("x1".to_string(), observable(&[("1X", 1.0)]))
+        .unwrap();
+    input
+        .add_observable("z0x1".to_string(), observable(&[("0Z1X", 1.0)]))
+        .unwrap();
+    input
+        .add_observable("y1".to_string(), observable(&[("1Y", 1.0)]))
+        .unwrap();
+    let plus = Complex64::new(std::f64::consts::FRAC_1_SQRT_2, 0.0);
+    input
+        .add_fidelity(
+            "fidelity".to_string(),
+            vec![plus, 0.0.into(), plus, 0.0.into()],
+        )
+        .unwrap();
+    input
+        .add_fidelity(
+            "orthogonal".to_string(),
+            vec![plus, 0.0.into(), -plus, 0.0.into()],
+        )
+        .unwrap();
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::Hadamard::new(1);
+    let measurement = ClassicalShadow::new_random(Some(constant_circuit), input, 400, 10, Some(3));
+
+    let backend = StatevectorSimulator::new(Some(5));
+    let result = backend.run_measurement(&measurement).unwrap().unwrap();
+    for (name, expected) in [
+        ("z0", 1.0),
+        ("x1", 1.0),
+        ("z0x1", 1.0),
+        ("y1", 0.0),
+        ("fidelity", 1.0),
+        ("orthogonal", 0.0),
+    ] {
+        assert!(
+            (result[name] - expected).abs() < 0.2,
+            "{name}: {} != {expected}",
+            result[name]
+        );
+    }
+
+    let (bit_registers, float_registers, complex_registers) =
+        backend.run_measurement_registers(&measurement).unwrap();
+    let with_errors = measurement
+        .evaluate_with_errors(bit_registers, float_registers, complex_registers)
+        .unwrap()
+        .unwrap();
+    // Single snapshot variance of a one-qubit Pauli eigenstate is 3 - 1
+    assert!((with_errors.standard_errors["z0"] - (2.0_f64 / 4000.0).sqrt()).abs() < 0.005);
+    assert!(with_errors.covariance("z0", "x1").is_some());
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_quantum_program() {
+    let mut input = ClassicalShadowInput::new(1, 3);
+    input
+        .add_observable("z0".to_string(), observable(&[("0Z", 1.0)]))
+        .unwrap();
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::RotateX::new(0, "theta".into());
+    let measurement = ClassicalShadow::new_random(Some(constant_circuit), input, 200, 10, Some(2));
+    let program = QuantumProgram::ClassicalShadow {
+        measurement,
+        input_parameter_names: vec!["theta".to_string()],
+    };
+    assert_eq!(program.to_string(), "QuantumProgram::ClassicalShadow");
+    assert_eq!(program.minimum_supported_roqoqo_version(), (1, 23, 0));
+
+    let backend = StatevectorSimulator::new(Some(1));
+    let result = program.run(backend.clone(), &[0.0]).unwrap().unwrap();
+    assert!((result["z0"] - 1.0).abs() < 0.2);
+    let result = program
+        .run(backend.clone(), &[std::f64::consts::PI])
+        .unwrap()
+        .unwrap();
+    assert!((result["z0"] + 1.0).abs() < 0.2);
+    assert!(program.run(backend.clone(), &[]).is_err());
+    assert!(program.run_registers(backend, &[0.0]).is_err());
+}
+
+#[test]
+fn test_input_errors() {
+    let mut input = ClassicalShadowInput::new(2, 3);
+    assert_eq!(
+        input.add_observable("a".to_string(), observable(&[("2Z", 1.0)])),
+        Err(RoqoqoError::PauliProductExceedsQubits {
+            pp_qubit: 2,
+            number_qubits: 2
+        })
+    );
+    input
+        .add_observable("a".to_string(), observable(&[("1Z", 1.0)]))
+        .unwrap();
+    assert_eq!(
+        input.add_observable("a".to_string(), observable(&[("0Z", 1.0)])),
+        Err(RoqoqoError::ExpValUsedTwice {
+            name: "a".to_string()
+        })
+    );
+    assert_eq!(
+        input.add_fidelity("a".to_string(), vec![1.0.into(); 4]),
+        Err(RoqoqoError::ExpValUsedTwice {
+            name: "a".to_string()
+        })
+    );
+    assert_eq!(
+        input.add_fidelity("b".to_string(), vec![1.0.into(); 2]),
+        Err(RoqoqoError::MismatchedRegisterDimension {
+            dim: 2,
+            number_qubits: 2
+        })
+    );
+    assert!(matches!(
+        input.add_fidelity("b".to_string(), vec![0.0.into(); 4]),
+        Err(RoqoqoError::ClassicalShadowMeasurementError { .. })
+    ));
+    input
+        .add_fidelity("b".to_string(), vec![2.0.into(); 4])
+        .unwrap();
+    assert_eq!(input.target_states["b"], vec![Complex64::from(0.5); 4]);
+    // The dimension of the Hilbert space of too many qubits can not be represented
+    assert_eq!(
+        ClassicalShadowInput::new(200, 1).add_fidelity("b".to_string(), vec![1.0.into(); 2]),
+        Err(RoqoqoError::MismatchedRegisterDimension {
+            dim: 2,
+            number_qubits: 200
+        })
+    );
+}
+
+#[test]
+fn test_evaluate_errors() {
+    let mut input = ClassicalShadowInput::new(1, 0);
+    input
+        .add_observable("a".to_string(), observable(&[("0Z", 1.0)]))
+        .unwrap();
+    input.measurement_bases = vec![PauliProduct::new()];
+    let mut measurement = ClassicalShadow {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+        input,
+    };
+    let registers: HashMap<String, BitOutputRegister> =
+        HashMap::from([("ro_0".to_string(), vec![vec![false]])]);
+    assert!(matches!(
+        measurement.evaluate(registers.clone(), HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::ClassicalShadowMeasurementError { .. })
+    ));
+    measurement.input.number_groups = 1;
+    assert_eq!(
+        measurement.evaluate(HashMap::new(), HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::MissingRegister {
+            name: "ro_0".to_string()
+        })
+    );
+    let empty: HashMap<String, BitOutputRegister> = HashMap::from([("ro_0".to_string(), vec![])]);
+    assert!(matches!(
+        measurement.evaluate(empty, HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::ClassicalShadowMeasurementError { .. })
+    ));
+    let short: HashMap<String, BitOutputRegister> =
+        HashMap::from([("ro_0".to_string(), vec![vec![]])]);
+    assert!(matches!(
+        measurement.evaluate(short, HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::ClassicalShadowMeasurementError { .. })
+    ));
+    // Observables and target states set directly are checked before the evaluation
+    let mut invalid = measurement.clone();
+    invalid
+        .input
+        .observables
+        .insert("c".to_string(), observable(&[("3Z", 1.0)]));
+    assert_eq!(
+        invalid.evaluate_with_errors(registers.clone(), HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::PauliProductExceedsQubits {
+            pp_qubit: 3,
+            number_qubits: 1
+        })
+    );
+    let mut invalid = measurement.clone();
+    invalid
+        .input
+        .target_states
+        .insert("c".to_string(), vec![1.0.into()]);
+    assert_eq!(
+        invalid.evaluate(registers.clone(), HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::MismatchedRegisterDimension {
+            dim: 1,
+            number_qubits: 1
+        })
+    );
+    assert!(measurement
+        .evaluate(registers, HashMap::new(), HashMap::new())
+        .is_ok());
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_deserialize_invalid_input() {
+    let mut input = ClassicalShadowInput::new(2, 1);
+    input
+        .observables
+        .insert("a".to_string(), observable(&[("2Z", 1.0)]));
+    let serialized = serde_json::to_string(&input).unwrap();
+    assert!(serde_json::from_str::<ClassicalShadowInput>(&serialized).is_err());
+    let mut input = ClassicalShadowInput::new(2, 1);
+    input
+        .target_states
+        .insert("b".to_string(), vec![1.0.into(); 2]);
+    let serialized = serde_json::to_string(&input).unwrap();
+    assert!(serde_json::from_str::<ClassicalShadowInput>(&serialized).is_err());
+    input
+        .target_states
+        .insert("b".to_string(), vec![0.5.into(); 4]);
+    let serialized = serde_json::to_string(&input).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ClassicalShadowInput>(&serialized).unwrap(),
+        input
+    );
+}
+
+#[test]
+fn test_substitute_parameters() {
+    let mut circuit = Circuit::new();
+    circuit += operations::RotateX::new(0, "theta".into());
+    let measurement = ClassicalShadow {
+        constant_circuit: Some(circuit.clone()),
+        circuits: vec![circuit],
+        input: ClassicalShadowInput::new(1, 1),
+    };
+    let substituted = measurement
+        .substitute_parameters(HashMap::from([("theta".to_string(), 0.5)]))
+        .unwrap();
+    let mut expected = Circuit::new();
+    expected += operations::RotateX::new(0, 0.5.into());
+    assert_eq!(substituted.constant_circuit, Some(expected.clone()));
+    assert_eq!(substituted.circuits, vec![expected]);
+    assert!(measurement.substitute_parameters(HashMap::new()).is_err());
+}
+
+#[cfg(feature = "json_schema")]
+#[test]
+fn test_json_schema() {
+    let mut input = ClassicalShadowInput::new(1, 3);
+    input
+        .add_observable("a".to_string(), observable(&[("0Z", 1.0)]))
+        .unwrap();
+    input
+        .add_fidelity("b".to_string(), vec![1.0.into(), 0.0.into()])
+        .unwrap();
+    let measurement = ClassicalShadow::new_random(None, input, 3, 2, Some(0));
+
+    let test_json = serde_json::to_string(&measurement).unwrap();
+    let test_value: serde_json::Value = serde_json::from_str(&test_json).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ClassicalShadow>(&test_json).unwrap(),
+        measurement
+    );
+
+    let test_schema = schema_for!(ClassicalShadow);
+    let schema = serde_json::to_string(&test_schema).unwrap();
+    let schema_value: serde_json::Value = serde_json::from_str(&schema).unwrap();
+    let compiled_schema = Validator::options()
+        .with_draft(Draft::Draft7)
+        .build(&schema_value)
+        .unwrap();
+
+    let validation_result = compiled_schema.validate(&test_value);
+    assert!(validation_result.is_ok());
+}
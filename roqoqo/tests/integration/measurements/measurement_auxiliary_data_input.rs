@@ -16,7 +16,10 @@ use jsonschema::{Draft, Validator};
 use num_complex::Complex64;
 use roqoqo::measurements::{
     CheatedInput, CheatedPauliZProductInput, PauliProductsToExpVal, PauliZProductInput,
+    ReadoutErrorMitigation,
 };
+use roqoqo::noise_models::ImperfectReadoutModel;
+use roqoqo::operations::SupportedVersion;
 use roqoqo::RoqoqoError;
 #[cfg(feature = "json_schema")]
 use schemars::schema_for;
@@ -156,6 +159,86 @@ fn error_br() {
     );
 }
 
+#[test]
+fn readout_error_mitigation_br() {
+    let fidelities = ReadoutErrorMitigation::Fidelities(HashMap::from([(1, 0.8)]));
+    assert_eq!(fidelities.readout_error(0), (1.0, 0.0));
+    assert_eq!(fidelities.readout_error(1), (0.8, 0.0));
+    assert!(!fidelities.confusion_matrix_inversion());
+    assert!(fidelities.validate(2).is_ok());
+
+    let model = ImperfectReadoutModel::new_with_uniform_error(2, 0.1, 0.2).unwrap();
+    let mitigation = ReadoutErrorMitigation::ImperfectReadoutModel {
+        model,
+        confusion_matrix_inversion: true,
+    };
+    let (fidelity, shift) = mitigation.readout_error(1);
+    assert!((fidelity - 0.7).abs() < 1e-12);
+    assert!((shift - 0.1).abs() < 1e-12);
+    assert!(mitigation.confusion_matrix_inversion());
+
+    // Fully random readout can not be mitigated
+    let model = ImperfectReadoutModel::new_with_uniform_error(2, 0.5, 0.5).unwrap();
+    let error = ReadoutErrorMitigation::ImperfectReadoutModel {
+        model,
+        confusion_matrix_inversion: false,
+    }
+    .validate(2);
+    assert!(matches!(
+        error,
+        Err(RoqoqoError::PauliZProductMeasurementError { .. })
+    ));
+    let error = ReadoutErrorMitigation::Fidelities(HashMap::from([(0, 1.5)])).validate(2);
+    assert!(error.is_err());
+}
+
+/// Test that PauliZProductInput keeps the bincode layout of the five public fields
+#[cfg(feature = "serialize")]
+#[test]
+fn json_without_readout_error_mitigation_br() {
+    #[derive(serde::Serialize)]
+    struct PreviousPauliZProductInput {
+        pauli_product_qubit_masks: HashMap<String, HashMap<usize, Vec<usize>>>,
+        number_qubits: usize,
+        number_pauli_products: usize,
+        measured_exp_vals: HashMap<String, PauliProductsToExpVal>,
+        use_flipped_measurement: bool,
+    }
+
+    let mut bri = PauliZProductInput::new(2, true);
+    let index = bri
+        .add_pauliz_product("ro".to_string(), vec![0, 1])
+        .unwrap();
+    bri.add_linear_exp_val("z0z1".to_string(), HashMap::from([(index, 1.0)]))
+        .unwrap();
+    let previous = PreviousPauliZProductInput {
+        pauli_product_qubit_masks: bri.pauli_product_qubit_masks.clone(),
+        number_qubits: 2,
+        number_pauli_products: 1,
+        measured_exp_vals: bri.measured_exp_vals.clone(),
+        use_flipped_measurement: true,
+    };
+    let serialized = serde_json::to_string(&previous).unwrap();
+    let deserialized: PauliZProductInput = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, bri);
+    assert_eq!(deserialized.readout_error_mitigation, None);
+}
+
+#[test]
+fn set_readout_error_mitigation_br() {
+    let mut bri = PauliZProductInput::new(2, false);
+    assert_eq!(bri.minimum_supported_roqoqo_version(), (1, 0, 0));
+    let mitigation = ReadoutErrorMitigation::Fidelities(HashMap::from([(0, 0.9)]));
+    bri.set_readout_error_mitigation(Some(mitigation.clone()))
+        .unwrap();
+    assert_eq!(bri.readout_error_mitigation, Some(mitigation));
+    assert_eq!(bri.minimum_supported_roqoqo_version(), (1, 23, 0));
+    let invalid = ReadoutErrorMitigation::Fidelities(HashMap::from([(1, 0.0)]));
+    assert!(bri.set_readout_error_mitigation(Some(invalid)).is_err());
+    bri.set_readout_error_mitigation(None).unwrap();
+    assert_eq!(bri.readout_error_mitigation, None);
+}
+
 #[test]
 fn error_cbr() {
     let mut bri = CheatedPauliZProductInput::new();
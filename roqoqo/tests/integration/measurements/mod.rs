@@ -14,4 +14,58 @@ mod basis_rotation_measurement;
 mod cheated_basis_rotation_measurement;
 mod cheated_measurement;
 mod classical_register_measurement;
+mod classical_shadow_measurement;
 mod measurement_auxiliary_data_input;
+
+use roqoqo::measurements::{Measure, MeasureExpectationValues};
+use roqoqo::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
+use roqoqo::{Circuit, RoqoqoError};
+use std::collections::HashMap;
+
+/// Measurement relying on the default implementation of evaluate_with_errors.
+#[derive(Debug, Clone, PartialEq)]
+struct ConstantMeasurement {
+    constant_circuit: Option<Circuit>,
+    circuits: Vec<Circuit>,
+}
+
+impl Measure for ConstantMeasurement {
+    fn circuits<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Circuit> + 'a> {
+        Box::new(self.circuits.iter())
+    }
+
+    fn constant_circuit(&self) -> &Option<Circuit> {
+        &self.constant_circuit
+    }
+
+    fn substitute_parameters(
+        &self,
+        _substituted_parameters: HashMap<String, f64>,
+    ) -> Result<Self, RoqoqoError> {
+        Ok(self.clone())
+    }
+}
+
+impl MeasureExpectationValues for ConstantMeasurement {
+    fn evaluate(
+        &self,
+        _bit_registers: HashMap<String, BitOutputRegister>,
+        _float_registers: HashMap<String, FloatOutputRegister>,
+        _complex_registers: HashMap<String, ComplexOutputRegister>,
+    ) -> Result<Option<HashMap<String, f64>>, RoqoqoError> {
+        Ok(Some(HashMap::from([("constant".to_string(), 1.0)])))
+    }
+}
+
+/// Test that measurements without an estimate of their statistical errors return an error
+#[test]
+fn test_evaluate_with_errors_not_supported() {
+    let measurement = ConstantMeasurement {
+        constant_circuit: None,
+        circuits: vec![Circuit::new()],
+    };
+    assert!(matches!(
+        measurement.evaluate_with_errors(HashMap::new(), HashMap::new(), HashMap::new()),
+        Err(RoqoqoError::GenericError { .. })
+    ));
+}
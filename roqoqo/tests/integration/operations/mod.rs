@@ -37,7 +37,8 @@ mod spin_boson_operations;
 use nalgebra as na;
 use ndarray::Array2;
 use num_complex::Complex64;
-use roqoqo::operations::AVAILABLE_GATES_HQSLANG;
+use roqoqo::operations::*;
+use test_case::test_case;
 
 // Helper function to convert a two-dimensional ndarray to a NxM matrix (N, M depending on the vector)
 // The output can be used to be converted into a nalgebra matrix with `na::Matrix4::from()`
@@ -64,3 +65,48 @@ fn test_available_gates() {
     assert!(AVAILABLE_GATES_HQSLANG.contains(&"Hadamard"));
     assert!(!AVAILABLE_GATES_HQSLANG.contains(&"Error"));
 }
+
+// Test that the inverse of a constant gate multiplied with the gate is the identity up to a global phase
+#[test_case(ConstantGateOperation::from(Identity::new(0)); "Identity")]
+#[test_case(ConstantGateOperation::from(PauliX::new(0)); "PauliX")]
+#[test_case(ConstantGateOperation::from(PauliY::new(0)); "PauliY")]
+#[test_case(ConstantGateOperation::from(PauliZ::new(0)); "PauliZ")]
+#[test_case(ConstantGateOperation::from(Hadamard::new(0)); "Hadamard")]
+#[test_case(ConstantGateOperation::from(SqrtPauliX::new(0)); "SqrtPauliX")]
+#[test_case(ConstantGateOperation::from(InvSqrtPauliX::new(0)); "InvSqrtPauliX")]
+#[test_case(ConstantGateOperation::from(SqrtPauliY::new(0)); "SqrtPauliY")]
+#[test_case(ConstantGateOperation::from(InvSqrtPauliY::new(0)); "InvSqrtPauliY")]
+#[test_case(ConstantGateOperation::from(SGate::new(0)); "SGate")]
+#[test_case(ConstantGateOperation::from(InvSGate::new(0)); "InvSGate")]
+#[test_case(ConstantGateOperation::from(TGate::new(0)); "TGate")]
+#[test_case(ConstantGateOperation::from(InvTGate::new(0)); "InvTGate")]
+#[test_case(ConstantGateOperation::from(SXGate::new(0)); "SXGate")]
+#[test_case(ConstantGateOperation::from(InvSXGate::new(0)); "InvSXGate")]
+#[test_case(ConstantGateOperation::from(CNOT::new(0, 1)); "CNOT")]
+#[test_case(ConstantGateOperation::from(SWAP::new(0, 1)); "SWAP")]
+#[test_case(ConstantGateOperation::from(FSwap::new(0, 1)); "FSwap")]
+#[test_case(ConstantGateOperation::from(ISwap::new(0, 1)); "ISwap")]
+#[test_case(ConstantGateOperation::from(SqrtISwap::new(0, 1)); "SqrtISwap")]
+#[test_case(ConstantGateOperation::from(InvSqrtISwap::new(0, 1)); "InvSqrtISwap")]
+#[test_case(ConstantGateOperation::from(MolmerSorensenXX::new(0, 1)); "MolmerSorensenXX")]
+#[test_case(ConstantGateOperation::from(ControlledPauliY::new(0, 1)); "ControlledPauliY")]
+#[test_case(ConstantGateOperation::from(ControlledPauliZ::new(0, 1)); "ControlledPauliZ")]
+#[test_case(ConstantGateOperation::from(EchoCrossResonance::new(0, 1)); "EchoCrossResonance")]
+#[test_case(ConstantGateOperation::from(Toffoli::new(0, 1, 2)); "Toffoli")]
+#[test_case(ConstantGateOperation::from(ControlledControlledPauliZ::new(0, 1, 2)); "ControlledControlledPauliZ")]
+#[test_case(ConstantGateOperation::from(ControlledSWAP::new(0, 1, 2)); "ControlledSWAP")]
+#[test_case(ConstantGateOperation::from(TripleControlledPauliX::new(0, 1, 2, 3)); "TripleControlledPauliX")]
+#[test_case(ConstantGateOperation::from(TripleControlledPauliZ::new(0, 1, 2, 3)); "TripleControlledPauliZ")]
+fn test_constant_gate_inverse(gate: ConstantGateOperation) {
+    let inverse = gate.inverse();
+    assert_eq!(inverse.involved_qubits(), gate.involved_qubits());
+    let matrix = convert_matrix(gate.unitary_matrix().unwrap());
+    let inverse_matrix = convert_matrix(inverse.unitary_matrix().unwrap());
+    let product = matrix * inverse_matrix;
+    let phase = product[(0, 0)];
+    assert!((phase.norm() - 1.0).abs() < 1e-10);
+    let identity = na::DMatrix::<Complex64>::identity(product.nrows(), product.ncols()) * phase;
+    for (value, expected) in product.iter().zip(identity.iter()) {
+        assert!((value - expected).norm() < 1e-10);
+    }
+}
@@ -32,6 +32,7 @@ use test_case::test_case;
 /// Test circuit function of MultiQubitMolmerSorensen
 #[test_case(vec![0,1]; "two_qubit")]
 #[test_case(vec![0,1,2]; "three_qubit")]
+#[test_case(vec![2,0,1]; "unsorted")]
 fn test_circuit_multi_ms(qubits: Vec<usize>) {
     let gate = MultiQubitMS::new(qubits.clone(), CalculatorFloat::FRAC_PI_2);
     let c = gate.circuit();
@@ -59,6 +60,20 @@ fn test_circuit_multi_ms(qubits: Vec<usize>) {
         comparison_circuit += Hadamard::new(0);
         comparison_circuit += Hadamard::new(1);
         comparison_circuit += Hadamard::new(2);
+        if qubits == vec![2, 0, 1] {
+            comparison_circuit = Circuit::new();
+            comparison_circuit += Hadamard::new(2);
+            comparison_circuit += Hadamard::new(0);
+            comparison_circuit += Hadamard::new(1);
+            comparison_circuit += CNOT::new(2, 0);
+            comparison_circuit += CNOT::new(0, 1);
+            comparison_circuit += RotateZ::new(1, CalculatorFloat::FRAC_PI_2);
+            comparison_circuit += CNOT::new(0, 1);
+            comparison_circuit += CNOT::new(2, 0);
+            comparison_circuit += Hadamard::new(2);
+            comparison_circuit += Hadamard::new(0);
+            comparison_circuit += Hadamard::new(1);
+        }
         assert!(c == comparison_circuit);
     }
 }
@@ -408,6 +423,7 @@ fn test_involved_qubits_multi_cnot() {
 
 #[test_case(vec![0,1]; "two_qubit")]
 #[test_case(vec![0,1,2]; "three_qubit")]
+#[test_case(vec![2,0,1]; "unsorted")]
 fn test_circuit_multi_zz(qubits: Vec<usize>) {
     let gate = MultiQubitZZ::new(qubits.clone(), CalculatorFloat::FRAC_PI_2);
     let c = gate.circuit();
@@ -428,6 +444,14 @@ fn test_circuit_multi_zz(qubits: Vec<usize>) {
         comparison_circuit += RotateZ::new(2, CalculatorFloat::FRAC_PI_2);
         comparison_circuit += CNOT::new(1, 2);
         comparison_circuit += CNOT::new(0, 1);
+        if qubits == vec![2, 0, 1] {
+            comparison_circuit = Circuit::new();
+            comparison_circuit += CNOT::new(2, 0);
+            comparison_circuit += CNOT::new(0, 1);
+            comparison_circuit += RotateZ::new(1, CalculatorFloat::FRAC_PI_2);
+            comparison_circuit += CNOT::new(0, 1);
+            comparison_circuit += CNOT::new(2, 0);
+        }
 
         assert!(c == comparison_circuit);
     }
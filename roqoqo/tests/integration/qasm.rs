@@ -0,0 +1,697 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration tests for the OpenQASM export and import of circuits.
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::operations::*;
+use roqoqo::qasm::{circuit_from_qasm, circuit_to_qasm, QasmVersion};
+use roqoqo::Circuit;
+use roqoqo::RoqoqoError;
+use std::collections::HashMap;
+use test_case::test_case;
+
+/// Returns a circuit with a bell pair measured into a readout register.
+fn bell_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit
+}
+
+#[test]
+fn test_qasm_2_bell_circuit() {
+    let program = circuit_to_qasm(&bell_circuit(), QasmVersion::V2_0).unwrap();
+    assert_eq!(
+        program,
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\n\nqreg q[2];\ncreg ro[2];\nh q[0];\ncx q[0], q[1];\n\
+         measure q[0] -> ro[0];\nmeasure q[1] -> ro[1];\n"
+    );
+}
+
+#[test]
+fn test_qasm_3_bell_circuit() {
+    let program = bell_circuit().to_qasm(QasmVersion::V3_0).unwrap();
+    assert_eq!(
+        program,
+        "OPENQASM 3.0;\ninclude \"stdgates.inc\";\n\nqubit[2] q;\nbit[2] ro;\nh q[0];\ncx q[0], q[1];\n\
+         ro[0] = measure q[0];\nro[1] = measure q[1];\n"
+    );
+}
+
+#[test]
+fn test_parameters() {
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.5.into());
+    circuit += ControlledPhaseShift::new(1, 0, (-0.25).into());
+    let program = circuit.to_qasm(QasmVersion::V2_0).unwrap();
+    assert!(program.ends_with("rx(0.5) q[0];\ncp(-0.25) q[1], q[0];\n"));
+}
+
+#[test]
+fn test_custom_gate_definition() {
+    let mut circuit = Circuit::new();
+    circuit += ISwap::new(0, 1);
+    circuit += RotateXY::new(1, 0.5.into(), 0.25.into());
+    circuit += ISwap::new(1, 2);
+    let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    // Every gate is defined once, before the quantum register
+    assert_eq!(program.matches("gate iswap q0, q1 {").count(), 1);
+    assert_eq!(program.matches("gate rotatexy(theta, phi) q0 {").count(), 1);
+    assert!(program.contains(
+        "gate rotatexy(theta, phi) q0 {\n    rz(-phi) q0;\n    rx(theta) q0;\n    rz(phi) q0;\n}\n"
+    ));
+    let (definitions, body) = program.split_once("qubit[3] q;\n").unwrap();
+    assert!(definitions.contains("gate iswap"));
+    assert_eq!(
+        body,
+        "iswap q[0], q[1];\nrotatexy(0.5, 0.25) q[1];\niswap q[1], q[2];\n"
+    );
+}
+
+#[test]
+fn test_version_specific_gates() {
+    let mut circuit = Circuit::new();
+    circuit += InvSXGate::new(0);
+    let program = circuit.to_qasm(QasmVersion::V2_0).unwrap();
+    assert!(program.ends_with("qreg q[1];\nsxdg q[0];\n"));
+    let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    assert!(program.contains("gate invsxgate q0 {\n    rx(-pi/2) q0;\n}\n"));
+    assert!(program.ends_with("qubit[1] q;\ninvsxgate q[0];\n"));
+}
+
+#[test]
+fn test_repeated_measurement() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += PauliX::new(2);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 100, None);
+    let program = circuit.to_qasm(QasmVersion::V2_0).unwrap();
+    assert!(program.ends_with("x q[2];\nmeasure q[0] -> ro[0];\nmeasure q[1] -> ro[1];\n"));
+
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += PauliX::new(2);
+    circuit += PragmaRepeatedMeasurement::new(
+        "ro".to_string(),
+        100,
+        Some(HashMap::from([(2, 0), (0, 1)])),
+    );
+    let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    assert!(program.ends_with("x q[2];\nro[1] = measure q[0];\nro[0] = measure q[2];\n"));
+}
+
+#[test]
+fn test_pragmas() {
+    let mut circuit = Circuit::new();
+    circuit += PragmaSetNumberOfMeasurements::new(10, "ro".to_string());
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+    circuit += PragmaGlobalPhase::new(0.5.into());
+    circuit += PragmaSleep::new(vec![0], 0.001.into());
+    assert!(circuit.to_qasm(QasmVersion::V3_0).unwrap().ends_with(
+        "qubit[2] q;\nreset q[1];\nbarrier q[0], q[1];\ngphase(0.5);\ndelay[0.001s] q[0];\n"
+    ));
+    assert!(circuit.to_qasm(QasmVersion::V2_0).is_err());
+    let circuit = circuit
+        .iter()
+        .filter(|operation| operation.hqslang() != "PragmaSleep")
+        .cloned()
+        .collect::<Circuit>();
+    assert!(circuit
+        .to_qasm(QasmVersion::V2_0)
+        .unwrap()
+        .ends_with("qreg q[2];\nreset q[1];\nbarrier q[0], q[1];\n"));
+}
+
+#[test]
+fn test_loop() {
+    let mut inner = Circuit::new();
+    inner += Hadamard::new(0);
+    let mut circuit = Circuit::new();
+    circuit += PragmaLoop::new(2.0.into(), inner);
+    assert!(circuit
+        .to_qasm(QasmVersion::V2_0)
+        .unwrap()
+        .ends_with("qreg q[1];\nh q[0];\nh q[0];\n"));
+    assert!(circuit
+        .to_qasm(QasmVersion::V3_0)
+        .unwrap()
+        .ends_with("qubit[1] q;\nfor uint i0 in [0:1] {\n    h q[0];\n}\n"));
+
+    let mut circuit = Circuit::new();
+    circuit += PragmaLoop::new("n".into(), Circuit::new());
+    assert!(circuit.to_qasm(QasmVersion::V3_0).is_err());
+}
+
+#[test]
+fn test_conditional() {
+    let mut inner = Circuit::new();
+    inner += PauliX::new(1);
+    inner += MeasureQubit::new(1, "flag".to_string(), 0);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("flag".to_string(), 1, false);
+    circuit += MeasureQubit::new(0, "flag".to_string(), 0);
+    circuit += PragmaConditional::new("flag".to_string(), 0, inner);
+    let program = circuit.to_qasm(QasmVersion::V2_0).unwrap();
+    assert!(program.ends_with(
+        "measure q[0] -> flag[0];\nif(flag==1) x q[1];\nif(flag==1) measure q[1] -> flag[0];\n"
+    ));
+    let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    assert!(program.ends_with(
+        "flag[0] = measure q[0];\nif (flag[0] == 1) {\n    x q[1];\n    flag[0] = measure q[1];\n}\n"
+    ));
+}
+
+#[test]
+fn test_conditional_on_register_bit() {
+    let mut inner = Circuit::new();
+    inner += PauliX::new(0);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, false);
+    circuit += PragmaConditional::new("ro".to_string(), 1, inner);
+    // OpenQASM 2.0 can only condition on a complete register
+    assert!(circuit.to_qasm(QasmVersion::V2_0).is_err());
+    assert!(circuit
+        .to_qasm(QasmVersion::V3_0)
+        .unwrap()
+        .ends_with("if (ro[1] == 1) {\n    x q[0];\n}\n"));
+}
+
+#[test]
+fn test_symbolic_parameters() {
+    let mut circuit = Circuit::new();
+    circuit += InputSymbolic::new("theta".to_string(), 0.5);
+    circuit += RotateZ::new(0, "theta".into());
+    assert!(circuit.to_qasm(QasmVersion::V2_0).is_err());
+    assert!(circuit
+        .to_qasm(QasmVersion::V3_0)
+        .unwrap()
+        .ends_with("qubit[1] q;\nfloat[64] theta = 0.5;\nrz(theta) q[0];\n"));
+}
+
+#[test_case(PragmaDamping::new(0, 1.0.into(), 0.1.into()).into(); "PragmaDamping")]
+#[test_case(PragmaGetStateVector::new("ro".to_string(), None).into(); "PragmaGetStateVector")]
+#[test_case(PragmaControlledCircuit::new(0, Circuit::new()).into(); "PragmaControlledCircuit")]
+#[test_case(DefinitionFloat::new("ro".to_string(), 1, true).into(); "DefinitionFloat")]
+fn test_unsupported_operation(operation: Operation) {
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += operation.clone();
+    let error = circuit.to_qasm(QasmVersion::V2_0).unwrap_err();
+    assert!(format!("{error:?}").contains(operation.hqslang()));
+    if operation.hqslang() == "DefinitionFloat" {
+        assert!(circuit.to_qasm(QasmVersion::V3_0).is_ok());
+    } else {
+        assert!(circuit.to_qasm(QasmVersion::V3_0).is_err());
+    }
+}
+
+#[test]
+fn test_register_name_clash() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("q".to_string(), 1, true);
+    assert!(circuit.to_qasm(QasmVersion::V3_0).is_err());
+}
+
+#[test]
+fn test_symbolic_single_qubit_gate() {
+    let mut circuit = Circuit::new();
+    circuit += SingleQubitGate::new(
+        0,
+        CalculatorFloat::from("a"),
+        0.0.into(),
+        0.0.into(),
+        0.0.into(),
+        0.0.into(),
+    );
+    assert!(circuit.to_qasm(QasmVersion::V3_0).is_err());
+}
+
+#[test_case("2.0", QasmVersion::V2_0; "2.0")]
+#[test_case("2", QasmVersion::V2_0; "2")]
+#[test_case("3.0", QasmVersion::V3_0; "3.0")]
+fn test_version_from_str(input: &str, version: QasmVersion) {
+    assert_eq!(input.parse::<QasmVersion>().unwrap(), version);
+    assert!(version.to_string().starts_with(input));
+}
+
+#[test]
+fn test_unknown_version() {
+    assert!("1.0".parse::<QasmVersion>().is_err());
+}
+
+#[cfg(feature = "unstable_operation_definition")]
+#[test]
+fn test_gate_definition() {
+    let mut definition_circuit = Circuit::new();
+    definition_circuit += RotateX::new(5, "theta".into());
+    definition_circuit += ISwap::new(5, 3);
+    let mut circuit = Circuit::new();
+    circuit += GateDefinition::new(
+        definition_circuit,
+        "custom".to_string(),
+        vec![5, 3],
+        vec!["theta".to_string()],
+    );
+    circuit += CallDefinedGate::new("custom".to_string(), vec![1, 0], vec![0.5.into()]);
+    let program = circuit.to_qasm(QasmVersion::V2_0).unwrap();
+    let iswap = program.find("gate iswap q0, q1 {").unwrap();
+    let custom = program
+        .find("gate custom(theta) q0, q1 {\n    rx(theta) q0;\n    iswap q0, q1;\n}\n")
+        .unwrap();
+    // Definitions used in the body of a gate definition precede it
+    assert!(iswap < custom);
+    assert!(program.ends_with("qreg q[2];\ncustom(0.5) q[1], q[0];\n"));
+
+    let mut circuit = Circuit::new();
+    circuit += CallDefinedGate::new("custom".to_string(), vec![1, 0], vec![0.5.into()]);
+    assert!(circuit.to_qasm(QasmVersion::V3_0).is_err());
+}
+
+#[test]
+fn test_import_bell_circuit() {
+    let circuit = circuit_from_qasm(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\ncreg ro[2];\nh q[0];\ncx q[0],q[1];\n\
+         measure q -> ro;\n",
+    )
+    .unwrap();
+    assert_eq!(circuit, bell_circuit());
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 3;\ninclude \"stdgates.inc\";\nqubit[2] q;\nbit[2] ro;\nh q[0];\ncx q[0], q[1];\n\
+         ro[0] = measure q[0];\nro[1] = measure q[1];\n",
+    )
+    .unwrap();
+    assert_eq!(circuit, bell_circuit());
+}
+
+#[test_case(PauliX::new(1).into(); "PauliX")]
+#[test_case(PauliY::new(1).into(); "PauliY")]
+#[test_case(PauliZ::new(1).into(); "PauliZ")]
+#[test_case(Hadamard::new(1).into(); "Hadamard")]
+#[test_case(SGate::new(1).into(); "SGate")]
+#[test_case(InvSGate::new(1).into(); "InvSGate")]
+#[test_case(TGate::new(1).into(); "TGate")]
+#[test_case(InvTGate::new(1).into(); "InvTGate")]
+#[test_case(Identity::new(1).into(); "Identity")]
+#[test_case(SXGate::new(1).into(); "SXGate")]
+#[test_case(InvSXGate::new(1).into(); "InvSXGate")]
+#[test_case(SqrtPauliX::new(1).into(); "SqrtPauliX")]
+#[test_case(InvSqrtPauliX::new(1).into(); "InvSqrtPauliX")]
+#[test_case(SqrtPauliY::new(1).into(); "SqrtPauliY")]
+#[test_case(InvSqrtPauliY::new(1).into(); "InvSqrtPauliY")]
+#[test_case(RotateX::new(1, 0.37.into()).into(); "RotateX")]
+#[test_case(RotateY::new(1, 0.37.into()).into(); "RotateY")]
+#[test_case(RotateZ::new(1, 0.37.into()).into(); "RotateZ")]
+#[test_case(PhaseShiftState1::new(1, 0.37.into()).into(); "PhaseShiftState1")]
+#[test_case(PhaseShiftState0::new(1, 0.37.into()).into(); "PhaseShiftState0")]
+#[test_case(RotateAroundSphericalAxis::new(1, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "RotateAroundSphericalAxis")]
+#[test_case(RotateXY::new(1, 0.37.into(), 1.1.into()).into(); "RotateXY")]
+#[test_case(GPi::new(1, 0.37.into()).into(); "GPi")]
+#[test_case(GPi2::new(1, 0.37.into()).into(); "GPi2")]
+#[test_case(SingleQubitGate::new(1, 0.5.into(), 0.5.into(), (-0.5).into(), 0.5.into(), 0.2.into()).into(); "SingleQubitGate")]
+#[test_case(CNOT::new(2, 0).into(); "CNOT")]
+#[test_case(ControlledPauliY::new(2, 0).into(); "ControlledPauliY")]
+#[test_case(ControlledPauliZ::new(2, 0).into(); "ControlledPauliZ")]
+#[test_case(SWAP::new(2, 0).into(); "SWAP")]
+#[test_case(ControlledPhaseShift::new(2, 0, 0.37.into()).into(); "ControlledPhaseShift")]
+#[test_case(ControlledRotateX::new(2, 0, 0.37.into()).into(); "ControlledRotateX")]
+#[test_case(ISwap::new(2, 0).into(); "ISwap")]
+#[test_case(SqrtISwap::new(2, 0).into(); "SqrtISwap")]
+#[test_case(InvSqrtISwap::new(2, 0).into(); "InvSqrtISwap")]
+#[test_case(XY::new(2, 0, 0.37.into()).into(); "XY")]
+#[test_case(FSwap::new(2, 0).into(); "FSwap")]
+#[test_case(MolmerSorensenXX::new(2, 0).into(); "MolmerSorensenXX")]
+#[test_case(VariableMSXX::new(2, 0, 0.37.into()).into(); "VariableMSXX")]
+#[test_case(GivensRotation::new(2, 0, 0.37.into(), 1.1.into()).into(); "GivensRotation")]
+#[test_case(GivensRotationLittleEndian::new(2, 0, 0.37.into(), 1.1.into()).into(); "GivensRotationLittleEndian")]
+#[test_case(Qsim::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "Qsim")]
+#[test_case(SpinInteraction::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "SpinInteraction")]
+#[test_case(Fsim::new(2, 0, 0.37.into(), 1.1.into(), (-0.6).into()).into(); "Fsim")]
+#[test_case(PMInteraction::new(2, 0, 0.37.into()).into(); "PMInteraction")]
+#[test_case(Bogoliubov::new(2, 0, 0.37.into(), (-0.6).into()).into(); "Bogoliubov")]
+#[test_case(ComplexPMInteraction::new(2, 0, 0.37.into(), (-0.6).into()).into(); "ComplexPMInteraction")]
+#[test_case(PhaseShiftedControlledZ::new(2, 0, 0.37.into()).into(); "PhaseShiftedControlledZ")]
+#[test_case(PhaseShiftedControlledPhase::new(2, 0, 0.37.into(), 1.1.into()).into(); "PhaseShiftedControlledPhase")]
+#[test_case(ControlledRotateXY::new(2, 0, 0.37.into(), 1.1.into()).into(); "ControlledRotateXY")]
+#[test_case(EchoCrossResonance::new(2, 0).into(); "EchoCrossResonance")]
+#[test_case(Toffoli::new(2, 0, 1).into(); "Toffoli")]
+#[test_case(ControlledSWAP::new(2, 0, 1).into(); "ControlledSWAP")]
+#[test_case(ControlledControlledPauliZ::new(2, 0, 1).into(); "ControlledControlledPauliZ")]
+#[test_case(ControlledControlledPhaseShift::new(2, 0, 1, 0.37.into()).into(); "ControlledControlledPhaseShift")]
+#[test_case(PhaseShiftedControlledControlledZ::new(2, 0, 1, 0.37.into()).into(); "PhaseShiftedControlledControlledZ")]
+#[test_case(PhaseShiftedControlledControlledPhase::new(2, 0, 1, 0.37.into(), 1.1.into()).into(); "PhaseShiftedControlledControlledPhase")]
+#[test_case(TripleControlledPauliX::new(2, 0, 3, 1).into(); "TripleControlledPauliX")]
+#[test_case(TripleControlledPauliZ::new(2, 0, 3, 1).into(); "TripleControlledPauliZ")]
+#[test_case(TripleControlledPhaseShift::new(2, 0, 3, 1, 0.37.into()).into(); "TripleControlledPhaseShift")]
+#[test_case(MultiQubitCNOT::new(vec![2, 0]).into(); "MultiQubitCNOT_2")]
+#[test_case(MultiQubitCNOT::new(vec![2, 0, 1]).into(); "MultiQubitCNOT_3")]
+#[test_case(MultiQubitCNOT::new(vec![2, 0, 3, 1]).into(); "MultiQubitCNOT_4")]
+#[test_case(MultiQubitCNOT::new(vec![2, 4, 0, 3, 1]).into(); "MultiQubitCNOT_5")]
+#[test_case(MultiQubitMS::new(vec![2, 0, 1], 0.37.into()).into(); "MultiQubitMS")]
+#[test_case(MultiQubitZZ::new(vec![2, 0, 1], 0.37.into()).into(); "MultiQubitZZ")]
+#[test_case(QFT::new(vec![2, 0, 1], true, false).into(); "QFT")]
+#[test_case(QFT::new(vec![2, 0, 1], true, true).into(); "QFT_inverse")]
+fn test_import_round_trip(operation: Operation) {
+    let mut circuit = Circuit::new();
+    circuit += operation;
+    for version in [QasmVersion::V2_0, QasmVersion::V3_0] {
+        let program = circuit.to_qasm(version).unwrap();
+        let imported = Circuit::from_qasm(&program).unwrap();
+        // Every gate is imported as a single gate
+        assert_eq!(imported.len(), 1);
+        assert!(imported.is_equivalent(&circuit, 1e-10, true).unwrap());
+        // Renamed definitions are not mapped back, the import expands their bodies
+        let expanded = Circuit::from_qasm(&rename_definitions(&program)).unwrap();
+        assert!(expanded.is_equivalent(&circuit, 1e-10, true).unwrap());
+    }
+}
+
+/// Appends a suffix to the names of all gate definitions of a program and to their calls.
+fn rename_definitions(program: &str) -> String {
+    let definitions: Vec<&str> = program
+        .lines()
+        .filter_map(|line| line.strip_prefix("gate "))
+        .filter_map(|line| line.split([' ', '(']).next())
+        .collect();
+    let mut renamed = String::new();
+    let mut identifier = String::new();
+    for character in program.chars().chain(std::iter::once('\n')) {
+        if character.is_alphanumeric() || character == '_' {
+            identifier.push(character);
+            continue;
+        }
+        if definitions.contains(&identifier.as_str()) {
+            identifier.push_str("_expanded");
+        }
+        renamed.push_str(&identifier);
+        renamed.push(character);
+        identifier.clear();
+    }
+    renamed
+}
+
+#[test]
+fn test_import_round_trip_program() {
+    let mut inner = Circuit::new();
+    inner += RotateX::new(1, 0.5.into());
+    inner += ISwap::new(0, 1);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += PragmaLoop::new(3.0.into(), inner.clone());
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += PragmaConditional::new("ro".to_string(), 0, inner);
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaSleep::new(vec![0, 1], 0.001.into());
+    let program = circuit.to_qasm(QasmVersion::V3_0).unwrap();
+    assert_eq!(Circuit::from_qasm(&program).unwrap(), circuit);
+}
+
+/// Returns the unitary matrix of a gate with control qubit 0 and target qubit 1.
+fn controlled_unitary(target: &Array2<Complex64>) -> Array2<Complex64> {
+    // Little endian ordering, the control qubit is the least significant bit
+    Array2::from_shape_fn((4, 4), |(row, column)| {
+        let (row_control, row_target) = (row % 2, row / 2);
+        let (column_control, column_target) = (column % 2, column / 2);
+        match (row_control, column_control) {
+            (0, 0) if row_target == column_target => Complex64::new(1.0, 0.0),
+            (1, 1) => target[(row_target, column_target)],
+            _ => Complex64::new(0.0, 0.0),
+        }
+    })
+}
+
+#[test_case("ch q[0], q[1];", "h q[0];"; "ch")]
+#[test_case("cry(0.37) q[0], q[1];", "ry(0.37) q[0];"; "cry")]
+#[test_case("crz(0.37) q[0], q[1];", "rz(0.37) q[0];"; "crz")]
+#[test_case("crx(0.37) q[0], q[1];", "rx(0.37) q[0];"; "crx")]
+#[test_case("cu3(0.37, 1.1, -0.6) q[0], q[1];", "u3(0.37, 1.1, -0.6) q[0];"; "cu3")]
+#[test_case("cu(0.37, 1.1, -0.6, 0.2) q[0], q[1];", "u3(0.37, 1.1, -0.6) q[0]; gphase(0.2);"; "cu")]
+#[test_case("csx q[0], q[1];", "sx q[0];"; "csx")]
+#[test_case("cp(0.37) q[0], q[1];", "p(0.37) q[0];"; "cp")]
+#[test_case("cy q[0], q[1];", "y q[0];"; "cy")]
+fn test_import_controlled_gates(program: &str, target_program: &str) {
+    let circuit = Circuit::from_qasm(&format!("qubit[2] q;\n{program}")).unwrap();
+    let target = Circuit::from_qasm(&format!("qubit[1] q;\n{target_program}")).unwrap();
+    let expected = controlled_unitary(&target.unitary_matrix().unwrap());
+    let unitary = circuit.unitary_matrix().unwrap();
+    for (value, expected_value) in unitary.iter().zip(expected.iter()) {
+        assert!((value - expected_value).norm() < 1e-10);
+    }
+}
+
+#[test]
+fn test_import_u3() {
+    let (theta, phi, lambda) = (0.37_f64, 1.1_f64, -0.6_f64);
+    let circuit = Circuit::from_qasm("qubit q;\nU(0.37, 1.1, -0.6) q;").unwrap();
+    let unitary = circuit.unitary_matrix().unwrap();
+    let expected = [
+        Complex64::new((theta / 2.0).cos(), 0.0),
+        -Complex64::from_polar((theta / 2.0).sin(), lambda),
+        Complex64::from_polar((theta / 2.0).sin(), phi),
+        Complex64::from_polar((theta / 2.0).cos(), phi + lambda),
+    ];
+    for (value, expected_value) in unitary.iter().zip(expected.iter()) {
+        assert!((value - expected_value).norm() < 1e-10);
+    }
+}
+
+#[test]
+fn test_import_registers_and_broadcast() {
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg a[2];\nqreg b[2];\ncreg c[2];\n\
+         h a;\ncx a, b;\ncz a[1], b;\nbarrier a, b[0];\nbarrier;\nreset b;\nmeasure b -> c;",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("c".to_string(), 2, true);
+    expected += Hadamard::new(0);
+    expected += Hadamard::new(1);
+    expected += CNOT::new(0, 2);
+    expected += CNOT::new(1, 3);
+    expected += ControlledPauliZ::new(1, 2);
+    expected += ControlledPauliZ::new(1, 3);
+    expected += PragmaStopParallelBlock::new(vec![0, 1, 2], 0.0.into());
+    expected += PragmaStopParallelBlock::new(vec![0, 1, 2, 3], 0.0.into());
+    expected += PragmaActiveReset::new(2);
+    expected += PragmaActiveReset::new(3);
+    expected += MeasureQubit::new(2, "c".to_string(), 0);
+    expected += MeasureQubit::new(3, "c".to_string(), 1);
+    assert_eq!(circuit, expected);
+}
+
+#[test_case("pi/2", CalculatorFloat::FRAC_PI_2; "pi")]
+#[test_case("π/2", CalculatorFloat::FRAC_PI_2; "unicode pi")]
+#[test_case("-2**3^-1 + 1e-1", (-2.0_f64.powf(3.0_f64.powf(-1.0)) + 0.1).into(); "power")]
+#[test_case("sin(pi/2) * 2 - cos(0)", 1.0.into(); "functions")]
+#[test_case("ln(euler) + arctan(0) + sqrt(4)", 3.0.into(); "more functions")]
+#[test_case("c / 4", 0.25.into(); "constant")]
+#[test_case("theta", "theta".into(); "symbol")]
+#[test_case("2*theta + 1", "((2e0 * theta) + 1e0)".into(); "symbolic expression")]
+#[test_case("tan(theta)", "tan(theta)".into(); "symbolic function")]
+fn test_import_expressions(expression: &str, expected: CalculatorFloat) {
+    let circuit = Circuit::from_qasm(&format!(
+        "const float c = 1;\nqubit q;\nrx({expression}) q;"
+    ))
+    .unwrap();
+    match (circuit.get(0), expected) {
+        (Some(Operation::RotateX(op)), CalculatorFloat::Float(value)) => {
+            assert!((op.theta().float().unwrap() - value).abs() < 1e-12)
+        }
+        (Some(Operation::RotateX(op)), expected) => assert_eq!(op.theta(), &expected),
+        _ => panic!("Expected a RotateX operation"),
+    }
+}
+
+#[test]
+fn test_import_custom_gate() {
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\n\
+         gate inner(a) x { rz(a) x; }\n\
+         gate custom(theta, phi) x, y { inner(theta/2) x; cx x, y; ry(phi) y; }\n\
+         qreg q[2];\ncustom(0.5, beta) q[1], q[0];",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(1, 0.25.into());
+    expected += CNOT::new(1, 0);
+    expected += RotateY::new(0, "beta".into());
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_import_modified_builtin_definition() {
+    // A definition with the name of a roqoqo gate but a different body is inlined
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 3.0;\ngate iswap q0, q1 {\n    cx q0, q1;\n}\nqubit[2] q;\niswap q[0], q[1];",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += CNOT::new(0, 1);
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_import_loops() {
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 3.0;\nqubit[3] q;\nfor uint i in [0:2] {\n    x q[0];\n}\n\
+         for int i in [0:2:3] { rx(i * 0.5) q[i]; }\nfor i in {2, 1} h q[i];",
+    )
+    .unwrap();
+    let mut body = Circuit::new();
+    body += PauliX::new(0);
+    let mut expected = Circuit::new();
+    expected += PragmaLoop::new(3.0.into(), body);
+    expected += RotateX::new(0, 0.0.into());
+    expected += RotateX::new(2, 1.0.into());
+    expected += Hadamard::new(2);
+    expected += Hadamard::new(1);
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_import_conditionals() {
+    let mut body = Circuit::new();
+    body += PauliX::new(0);
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[1];\ncreg c[1];\nif(c==1) x q[0];",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("c".to_string(), 1, true);
+    expected += PragmaConditional::new("c".to_string(), 0, body.clone());
+    assert_eq!(circuit, expected);
+
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 3.0;\nqubit[1] q;\nbit[2] c;\nif (c[1] == 1) { x q[0]; }\nif (c[0]) x q[0];",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("c".to_string(), 2, true);
+    expected += PragmaConditional::new("c".to_string(), 1, body.clone());
+    expected += PragmaConditional::new("c".to_string(), 0, body);
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_import_conditional_on_register_value() {
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[1];\ncreg c[3];\nif(c==5) x q[0];",
+    )
+    .unwrap();
+    let mut body = Circuit::new();
+    body += PauliX::new(0);
+    let mut set_condition = Circuit::new();
+    set_condition += InputBit::new("c_condition_0".to_string(), 0, true);
+    let mut nested = Circuit::new();
+    nested += PragmaConditional::new("c".to_string(), 2, set_condition);
+    let mut reset_condition = Circuit::new();
+    reset_condition += InputBit::new("c_condition_0".to_string(), 0, false);
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("c".to_string(), 3, true);
+    expected += DefinitionBit::new("c_condition_0".to_string(), 1, false);
+    expected += InputBit::new("c_condition_0".to_string(), 0, false);
+    expected += PragmaConditional::new("c".to_string(), 0, nested);
+    expected += PragmaConditional::new("c".to_string(), 1, reset_condition);
+    expected += PragmaConditional::new("c_condition_0".to_string(), 0, body);
+    assert_eq!(circuit, expected);
+}
+
+#[cfg(feature = "simulators")]
+#[test_case(0; "value_0")]
+#[test_case(1; "value_1")]
+#[test_case(2; "value_2")]
+#[test_case(3; "value_3")]
+fn test_import_conditional_on_register_value_simulated(value: usize) {
+    use roqoqo::backends::EvaluatingBackend;
+    use roqoqo::simulators::StatevectorSimulator;
+
+    let program = format!(
+        "OPENQASM 3.0;\nqubit q;\nbit[2] c;\nbit r;\nc[0] = {};\nc[1] = {};\n\
+         if (c == 2) x q;\nr = measure q;",
+        value & 1,
+        value >> 1
+    );
+    let circuit = Circuit::from_qasm(&program).unwrap();
+    let (bits, _, _) = StatevectorSimulator::new(Some(1))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(bits["r"], vec![vec![value == 2]]);
+}
+
+#[test]
+fn test_import_large_loop() {
+    let circuit =
+        Circuit::from_qasm("OPENQASM 3.0;\nqubit q;\nfor uint i in [0:99999999999] { x q; }")
+            .unwrap();
+    let mut body = Circuit::new();
+    body += PauliX::new(0);
+    let mut expected = Circuit::new();
+    expected += PragmaLoop::new(1e11.into(), body);
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_import_classical_declarations() {
+    let circuit = Circuit::from_qasm(
+        "OPENQASM 3.0;\ninput float[64] alpha;\nfloat[64] beta = 0.5;\narray[float[64], 2] f;\n\
+         array[complex[float[64]], 3] z;\narray[uint[64], 4] u;\noutput bit[2] c;\nc[1] = 1;\n\
+         qubit[1] q;\ngphase(alpha);\ndelay[20ns] q;",
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += InputSymbolic::new("beta".to_string(), 0.5);
+    expected += DefinitionFloat::new("f".to_string(), 2, true);
+    expected += DefinitionComplex::new("z".to_string(), 3, true);
+    expected += DefinitionUsize::new("u".to_string(), 4, true);
+    expected += DefinitionBit::new("c".to_string(), 2, true);
+    expected += InputBit::new("c".to_string(), 1, true);
+    expected += PragmaGlobalPhase::new("alpha".into());
+    expected += PragmaSleep::new(vec![0], 2e-8.into());
+    assert_eq!(circuit, expected);
+}
+
+#[test_case("qubit[2] q;\nh q[2];", 2, 5, "out of range"; "index out of range")]
+#[test_case("qubit[2] q;\n  foo q[0];", 2, 3, "Unknown gate foo"; "unknown gate")]
+#[test_case("qubit[2] q;\nh r[0];", 2, 3, "Unknown quantum register r"; "unknown register")]
+#[test_case("qubit[2] q;\ncx q[0];", 2, 1, "wrong number"; "wrong arity")]
+#[test_case("qubit[2] q;\nh q[0]", 2, 7, "Expected ';'"; "missing semicolon")]
+#[test_case("OPENQASM 4.0;", 1, 10, "Unsupported OpenQASM version"; "version")]
+#[test_case("include \"other.inc\";", 1, 9, "Unsupported include"; "include")]
+#[test_case("qubit[2] q;\nctrl @ x q[0], q[1];", 2, 1, "Gate modifier ctrl"; "modifier")]
+#[test_case("qubit[2] q;\ncx q[0], q[0];", 2, 1, "same qubit"; "duplicate qubit")]
+#[test_case("qubit[2] q;\nh q[0]; # comment", 2, 9, "Unexpected character '#'"; "character")]
+#[test_case("gate g a { measure a; }\nqubit q;\ng q;", 3, 1, "In definition of gate g"; "gate body")]
+#[test_case("bit[2] c;\nqubit q;\nif (c) x q;", 3, 5, "comparison"; "condition register")]
+#[test_case("bit[2] c;\nqubit q;\nif (c == 4) x q;", 3, 5, "can not be represented"; "condition value")]
+#[test_case("qubit q;\nfor uint i in [0:99999999999] { rx(i) q; }", 2, 15, "limited"; "loop size")]
+#[test_case("qubit[99999999999] q;", 1, 1, "maximal number"; "qubit register size")]
+#[test_case("bit[99999999999] c;", 1, 1, "maximal length"; "bit register size")]
+#[test_case("qubit q;\nfor i in [0:1] { qubit r; }", 2, 18, "top level"; "declaration in loop")]
+fn test_import_errors(program: &str, line: usize, column: usize, message: &str) {
+    match Circuit::from_qasm(program) {
+        Err(RoqoqoError::QasmParsingError {
+            line: error_line,
+            column: error_column,
+            msg,
+        }) => {
+            assert!(msg.contains(message), "{msg}");
+            assert_eq!((error_line, error_column), (line, column), "{msg}");
+        }
+        result => panic!("Expected a parsing error, found {result:?}"),
+    }
+}
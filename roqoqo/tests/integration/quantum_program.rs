@@ -19,11 +19,17 @@ use roqoqo::measurements::{
 use roqoqo::operations;
 use roqoqo::prelude::*;
 use roqoqo::registers::{BitOutputRegister, ComplexOutputRegister, FloatOutputRegister};
+#[cfg(feature = "simulators")]
+use roqoqo::simulators::StatevectorSimulator;
 use roqoqo::Circuit;
 use roqoqo::QuantumProgram;
+#[cfg(feature = "simulators")]
+use roqoqo::{RoqoqoBackendError, RoqoqoError};
 #[cfg(feature = "json_schema")]
 use schemars::schema_for;
 use std::collections::HashMap;
+#[cfg(feature = "simulators")]
+use test_case::test_case;
 
 #[derive(Debug, Clone, Copy)]
 struct TestBackend;
@@ -372,3 +378,514 @@ fn test_registers_json_schema() {
     let validation_result = compiled_schema.validate(&test_value);
     assert!(validation_result.is_ok());
 }
+
+/// Creates a program measuring several Pauli products after a gate depending on the parameters a and b.
+fn gradient_program(gate: operations::Operation) -> QuantumProgram {
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::Hadamard::new(0);
+    constant_circuit += operations::RotateY::new(1, 0.4.into());
+    constant_circuit += operations::RotateX::new(2, 0.7.into());
+    constant_circuit += operations::CNOT::new(0, 2);
+    constant_circuit += gate;
+    constant_circuit += operations::RotateX::new(0, "a".into());
+    constant_circuit += operations::CNOT::new(0, 1);
+    constant_circuit += operations::CNOT::new(1, 2);
+
+    let mut input = CheatedPauliZProductInput::new();
+    let mut circuit = Circuit::new();
+    let products: [(&str, Vec<(usize, usize)>); 5] = [
+        ("z0", vec![(0, 3)]),
+        ("x1", vec![(1, 1)]),
+        ("y2", vec![(2, 2)]),
+        ("z0x1", vec![(0, 3), (1, 1)]),
+        ("x0y1z2", vec![(0, 1), (1, 2), (2, 3)]),
+    ];
+    for (name, qubit_paulis) in products {
+        circuit += operations::DefinitionFloat::new(name.to_string(), 1, true);
+        circuit += operations::PragmaGetPauliProduct::new(
+            qubit_paulis.into_iter().collect(),
+            name.to_string(),
+            Circuit::new(),
+        );
+        let index = input.add_pauliz_product(name.to_string());
+        input
+            .add_linear_exp_val(name.to_string(), HashMap::from([(index, 1.0)]))
+            .unwrap();
+    }
+    QuantumProgram::CheatedPauliZProduct {
+        measurement: CheatedPauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![circuit],
+            input,
+        },
+        input_parameter_names: vec!["a".to_string(), "b".to_string()],
+    }
+}
+
+#[cfg(feature = "simulators")]
+#[test_case(operations::RotateX::new(1, "2*a - b + 0.1".into()).into(); "RotateX")]
+#[test_case(operations::RotateY::new(1, "2*a - b + 0.1".into()).into(); "RotateY")]
+#[test_case(operations::RotateZ::new(1, "2*a - b + 0.1".into()).into(); "RotateZ")]
+#[test_case(operations::RotateXY::new(1, "2*a - b + 0.1".into(), 0.3.into()).into(); "RotateXY")]
+#[test_case(operations::RotateAroundSphericalAxis::new(1, "2*a - b + 0.1".into(), 0.3.into(), 1.1.into()).into(); "RotateAroundSphericalAxis")]
+#[test_case(operations::PhaseShiftState0::new(1, "2*a - b + 0.1".into()).into(); "PhaseShiftState0")]
+#[test_case(operations::PhaseShiftState1::new(1, "2*a - b + 0.1".into()).into(); "PhaseShiftState1")]
+#[test_case(operations::ControlledPhaseShift::new(0, 1, "2*a - b + 0.1".into()).into(); "ControlledPhaseShift")]
+#[test_case(operations::ControlledControlledPhaseShift::new(0, 2, 1, "2*a - b + 0.1".into()).into(); "ControlledControlledPhaseShift")]
+#[test_case(operations::PhaseShiftedControlledPhase::new(0, 1, "2*a - b + 0.1".into(), 0.3.into()).into(); "PhaseShiftedControlledPhase")]
+#[test_case(operations::VariableMSXX::new(1, 2, "2*a - b + 0.1".into()).into(); "VariableMSXX")]
+#[test_case(operations::MultiQubitMS::new(vec![0, 1, 2], "2*a - b + 0.1".into()).into(); "MultiQubitMS")]
+#[test_case(operations::MultiQubitZZ::new(vec![0, 2], "2*a - b + 0.1".into()).into(); "MultiQubitZZ")]
+fn test_run_gradient(gate: operations::Operation) {
+    let program = gradient_program(gate);
+    let backend = StatevectorSimulator::new(None);
+    let parameters = [0.3, -0.2];
+    let jacobian = program
+        .run_gradient(backend.clone(), &parameters)
+        .unwrap()
+        .unwrap();
+    assert_eq!(jacobian.len(), 5);
+
+    let step = 1e-5;
+    for (parameter_index, _) in parameters.iter().enumerate() {
+        let mut plus = parameters;
+        plus[parameter_index] += step;
+        let mut minus = parameters;
+        minus[parameter_index] -= step;
+        let result_plus = program.run(backend.clone(), &plus).unwrap().unwrap();
+        let result_minus = program.run(backend.clone(), &minus).unwrap().unwrap();
+        for (name, derivatives) in jacobian.iter() {
+            let finite_difference = (result_plus[name] - result_minus[name]) / (2.0 * step);
+            assert!(
+                (derivatives[parameter_index] - finite_difference).abs() < 1e-6,
+                "{name}: {} != {finite_difference}",
+                derivatives[parameter_index]
+            );
+        }
+    }
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_parameter_shift_programs() {
+    let program = gradient_program(operations::RotateX::new(1, "2*a - b + 0.1".into()).into());
+    let shifted = program.parameter_shift_programs(&[0.3, -0.2]).unwrap();
+    // One shifted pair for the gate and one for RotateX(0, a)
+    assert_eq!(shifted.programs.len(), 4);
+    let expected_weights = [[1.0, -0.5], [-1.0, 0.5], [0.5, 0.0], [-0.5, 0.0]];
+    for (weights, expected_weights) in shifted.weights.iter().zip(expected_weights.iter()) {
+        for (weight, expected_weight) in weights.iter().zip(expected_weights.iter()) {
+            assert!((weight - expected_weight).abs() < 1e-12);
+        }
+    }
+    let mut expected = Circuit::new();
+    expected += operations::RotateX::new(1, (0.9 + std::f64::consts::FRAC_PI_2).into());
+    match &shifted.programs[0] {
+        QuantumProgram::CheatedPauliZProduct {
+            measurement,
+            input_parameter_names,
+        } => {
+            assert!(input_parameter_names.is_empty());
+            let constant_circuit = measurement.constant_circuit.as_ref().unwrap();
+            assert_eq!(constant_circuit.get(4), expected.get(0));
+            assert!(!constant_circuit.is_parametrized());
+        }
+        _ => panic!("Unexpected type of shifted program"),
+    }
+    assert!(shifted.jacobian(&[HashMap::new()]).is_err());
+
+    // Rotation angles that do not depend on the parameters lead to vanishing derivatives
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("z0".to_string(), 1, true);
+    circuit += operations::RotateX::new(0, "0.5 * 2".into());
+    circuit += operations::PragmaGetPauliProduct::new(
+        HashMap::from([(0, 3)]),
+        "z0".to_string(),
+        Circuit::new(),
+    );
+    let mut input = CheatedPauliZProductInput::new();
+    let index = input.add_pauliz_product("z0".to_string());
+    input
+        .add_linear_exp_val("z0".to_string(), HashMap::from([(index, 1.0)]))
+        .unwrap();
+    let program = QuantumProgram::CheatedPauliZProduct {
+        measurement: CheatedPauliZProduct {
+            constant_circuit: None,
+            circuits: vec![circuit],
+            input,
+        },
+        input_parameter_names: vec!["a".to_string()],
+    };
+    let jacobian = program
+        .run_gradient(StatevectorSimulator::new(None), &[0.1])
+        .unwrap()
+        .unwrap();
+    assert_eq!(jacobian, HashMap::from([("z0".to_string(), vec![0.0])]));
+}
+
+#[cfg(feature = "simulators")]
+#[test_case(operations::RotateX::new(1, "sin(a)".into()).into(); "non-affine")]
+#[test_case(operations::RotateX::new(1, "a * a".into()).into(); "quadratic")]
+#[test_case(operations::RotateXY::new(1, 0.2.into(), "a".into()).into(); "phi")]
+#[test_case(operations::RotateAroundSphericalAxis::new(1, 0.2.into(), "b".into(), 0.3.into()).into(); "spherical_theta")]
+#[test_case(operations::ControlledRotateX::new(0, 1, "a".into()).into(); "unsupported gate")]
+#[test_case(operations::RotateX::new(1, "c".into()).into(); "unknown parameter")]
+fn test_run_gradient_error(gate: operations::Operation) {
+    let program = gradient_program(gate);
+    assert!(program.parameter_shift_programs(&[0.3, -0.2]).is_err());
+    assert!(program
+        .run_gradient(StatevectorSimulator::new(None), &[0.3, -0.2])
+        .is_err());
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_run_gradient_wrong_input() {
+    let program = gradient_program(operations::RotateX::new(1, "a".into()).into());
+    assert!(program
+        .run_gradient(StatevectorSimulator::new(None), &[0.3])
+        .is_err());
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![Circuit::new()],
+        },
+        input_parameter_names: vec![],
+    };
+    assert!(program
+        .run_gradient(StatevectorSimulator::new(None), &[])
+        .is_err());
+}
+
+/// Backend recording the sizes of the submitted circuit batches.
+#[cfg(feature = "simulators")]
+#[derive(Debug, Clone, Default)]
+struct BatchRecordingBackend {
+    batch_sizes: std::rc::Rc<std::cell::RefCell<Vec<usize>>>,
+}
+
+#[cfg(feature = "simulators")]
+impl EvaluatingBackend for BatchRecordingBackend {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a operations::Operation>,
+    ) -> roqoqo::backends::RegisterResult {
+        StatevectorSimulator::new(None).run_circuit_iterator(circuit)
+    }
+
+    fn run_circuit_batch(
+        &self,
+        circuits: &[Circuit],
+    ) -> Result<Vec<roqoqo::registers::Registers>, roqoqo::RoqoqoBackendError> {
+        self.batch_sizes.borrow_mut().push(circuits.len());
+        circuits
+            .iter()
+            .map(|circuit| self.run_circuit(circuit))
+            .collect()
+    }
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_run_batch() {
+    let program = gradient_program(operations::RotateY::new(1, "2*a - b + 0.1".into()).into());
+    let backend = BatchRecordingBackend::default();
+    let parameters = vec![vec![0.3, -0.2], vec![0.0, 0.0], vec![1.0, 2.0]];
+    let results = program.run_batch(backend.clone(), &parameters).unwrap();
+    assert_eq!(*backend.batch_sizes.borrow(), vec![3]);
+    assert_eq!(results.len(), 3);
+    for (point, result) in parameters.iter().zip(results.iter()) {
+        let expected = program
+            .run(StatevectorSimulator::new(None), point)
+            .unwrap()
+            .unwrap();
+        let result = result.as_ref().unwrap();
+        assert_eq!(result.len(), expected.len());
+        for (name, value) in expected.iter() {
+            assert!((result[name] - value).abs() < 1e-12);
+        }
+    }
+    assert!(program.run_batch(backend.clone(), &[]).unwrap().is_empty());
+    assert!(program
+        .run_batch(backend.clone(), &[vec![0.3, -0.2], vec![0.3]])
+        .is_err());
+
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![Circuit::new()],
+        },
+        input_parameter_names: vec![],
+    };
+    assert!(program.run_batch(backend.clone(), &[vec![]]).is_err());
+}
+
+/// Backend dropping the results of all circuits of a batch.
+#[derive(Debug, Clone, Copy)]
+struct IncompleteBatchBackend;
+
+impl EvaluatingBackend for IncompleteBatchBackend {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a operations::Operation>,
+    ) -> roqoqo::backends::RegisterResult {
+        TestBackend.run_circuit_iterator(circuit)
+    }
+
+    fn run_circuit_batch(
+        &self,
+        _circuits: &[Circuit],
+    ) -> Result<Vec<roqoqo::registers::Registers>, roqoqo::RoqoqoBackendError> {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn test_run_batch_incomplete_results() {
+    let program = gradient_program(operations::RotateY::new(1, "a".into()).into());
+    assert!(program
+        .run_batch(IncompleteBatchBackend, &[vec![0.3, -0.2]])
+        .is_err());
+}
+
+/// Backend evaluating every measurement of a batch to a fixed expectation value.
+#[derive(Debug, Clone, Copy)]
+struct MeasurementBatchBackend;
+
+impl EvaluatingBackend for MeasurementBatchBackend {
+    fn run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a operations::Operation>,
+    ) -> roqoqo::backends::RegisterResult {
+        TestBackend.run_circuit_iterator(circuit)
+    }
+
+    fn run_measurement_batch<T>(
+        &self,
+        measurements: &[T],
+    ) -> Result<Vec<Option<HashMap<String, f64>>>, roqoqo::RoqoqoBackendError>
+    where
+        T: roqoqo::measurements::MeasureExpectationValues,
+    {
+        Ok(measurements
+            .iter()
+            .map(|_| Some(HashMap::from([("fixed".to_string(), 1.0)])))
+            .collect())
+    }
+}
+
+#[test]
+fn test_run_batch_overridden_measurement_batch() {
+    let program = gradient_program(operations::RotateY::new(1, "a".into()).into());
+    let results = program
+        .run_batch(MeasurementBatchBackend, &[vec![0.3, -0.2], vec![0.1, 0.0]])
+        .unwrap();
+    assert_eq!(
+        results,
+        vec![Some(HashMap::from([("fixed".to_string(), 1.0)])); 2]
+    );
+}
+
+#[cfg(all(feature = "async", feature = "simulators"))]
+#[derive(Debug, Clone, Copy)]
+struct AsyncStatevectorBackend;
+
+#[cfg(all(feature = "async", feature = "simulators"))]
+#[async_trait::async_trait]
+impl roqoqo::backends::AsyncEvaluatingBackend for AsyncStatevectorBackend {
+    async fn async_run_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a operations::Operation> + std::marker::Send,
+    ) -> roqoqo::backends::RegisterResult {
+        StatevectorSimulator::new(None).run_circuit_iterator(circuit)
+    }
+}
+
+#[cfg(all(feature = "async", feature = "simulators"))]
+#[test]
+fn test_async_run_batch() {
+    let program = gradient_program(operations::RotateY::new(1, "2*a - b + 0.1".into()).into());
+    let parameters = vec![vec![0.3, -0.2], vec![1.0, 2.0]];
+    let results =
+        futures::executor::block_on(program.async_run_batch(AsyncStatevectorBackend, &parameters))
+            .unwrap();
+    let expected = program
+        .run_batch(StatevectorSimulator::new(None), &parameters)
+        .unwrap();
+    assert_eq!(results, expected);
+    assert!(futures::executor::block_on(
+        program.async_run_batch(AsyncStatevectorBackend, &[vec![0.3]])
+    )
+    .is_err());
+}
+
+#[cfg(feature = "simulators")]
+fn assert_close_results(result: &HashMap<String, f64>, expected: &HashMap<String, f64>) {
+    assert_eq!(result.len(), expected.len());
+    for (name, value) in expected.iter() {
+        assert!((result[name] - value).abs() < 1e-12);
+    }
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_run_named() {
+    let program = gradient_program(operations::RotateY::new(1, "2*a - b + 0.1".into()).into());
+    let expected = program
+        .run(StatevectorSimulator::new(None), &[0.3, -0.2])
+        .unwrap()
+        .unwrap();
+    let parameters = HashMap::from([("b".to_string(), -0.2), ("a".to_string(), 0.3)]);
+    let result = program
+        .run_named(StatevectorSimulator::new(None), &parameters)
+        .unwrap()
+        .unwrap();
+    assert_close_results(&result, &expected);
+
+    let missing = HashMap::from([("a".to_string(), 0.3)]);
+    let err = program
+        .run_named(StatevectorSimulator::new(None), &missing)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RoqoqoBackendError::RoqoqoError(RoqoqoError::MissingInputParameters {
+            names: vec!["b".to_string()]
+        })
+    );
+    let unknown = HashMap::from([
+        ("a".to_string(), 0.3),
+        ("b".to_string(), -0.2),
+        ("c".to_string(), 1.0),
+    ]);
+    let err = program
+        .run_named(StatevectorSimulator::new(None), &unknown)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RoqoqoBackendError::RoqoqoError(RoqoqoError::UnknownInputParameters {
+            names: vec!["c".to_string()],
+            input_parameter_names: vec!["a".to_string(), "b".to_string()]
+        })
+    );
+    assert!(program
+        .run_registers_named(StatevectorSimulator::new(None), &parameters)
+        .is_err());
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_run_registers_named() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += operations::RotateX::new(0, "theta".into());
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![circuit],
+        },
+        input_parameter_names: vec!["theta".to_string()],
+    };
+    let (bit_registers, _, _) = program
+        .run_registers_named(
+            StatevectorSimulator::new(None),
+            &HashMap::from([("theta".to_string(), std::f64::consts::PI)]),
+        )
+        .unwrap();
+    assert_eq!(bit_registers["ro"], vec![vec![true]]);
+    assert!(program
+        .run_registers_named(StatevectorSimulator::new(None), &HashMap::new())
+        .is_err());
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_bind_parameters() {
+    let program = gradient_program(operations::RotateY::new(1, "2*a - b + 0.1".into()).into());
+    let expected = program
+        .run(StatevectorSimulator::new(None), &[0.3, -0.2])
+        .unwrap()
+        .unwrap();
+
+    let bound = program
+        .bind_parameters(&HashMap::from([("b".to_string(), -0.2)]))
+        .unwrap();
+    match &bound {
+        QuantumProgram::CheatedPauliZProduct {
+            input_parameter_names,
+            ..
+        } => assert_eq!(input_parameter_names, &vec!["a".to_string()]),
+        _ => panic!("Wrong type of QuantumProgram"),
+    }
+    let result = bound
+        .run(StatevectorSimulator::new(None), &[0.3])
+        .unwrap()
+        .unwrap();
+    assert_close_results(&result, &expected);
+    assert!(bound
+        .run(StatevectorSimulator::new(None), &[0.3, -0.2])
+        .is_err());
+
+    // The gradient of the bound program is the gradient with respect to the remaining parameters
+    let gradient = program
+        .run_gradient(StatevectorSimulator::new(None), &[0.3, -0.2])
+        .unwrap()
+        .unwrap();
+    let bound_gradient = bound
+        .run_gradient(StatevectorSimulator::new(None), &[0.3])
+        .unwrap()
+        .unwrap();
+    for (name, derivatives) in gradient.iter() {
+        assert_eq!(bound_gradient[name].len(), 1);
+        assert!((bound_gradient[name][0] - derivatives[0]).abs() < 1e-12);
+    }
+
+    let fully_bound = bound
+        .bind_parameters(&HashMap::from([("a".to_string(), 0.3)]))
+        .unwrap();
+    let result = fully_bound
+        .run(StatevectorSimulator::new(None), &[])
+        .unwrap()
+        .unwrap();
+    assert_close_results(&result, &expected);
+    assert_eq!(program.bind_parameters(&HashMap::new()).unwrap(), program);
+
+    let err = bound
+        .bind_parameters(&HashMap::from([("b".to_string(), 1.0)]))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RoqoqoError::UnknownInputParameters {
+            names: vec!["b".to_string()],
+            input_parameter_names: vec!["a".to_string()]
+        }
+    );
+}
+
+#[cfg(feature = "simulators")]
+#[test]
+fn test_bind_parameters_classical_register() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::RotateX::new(0, "theta".into());
+    circuit += operations::RotateX::new(1, "phi".into());
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(1, "ro".to_string(), 1);
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: Some(Circuit::new()),
+            circuits: vec![circuit],
+        },
+        input_parameter_names: vec!["theta".to_string(), "phi".to_string()],
+    };
+    let bound = program
+        .bind_parameters(&HashMap::from([(
+            "theta".to_string(),
+            std::f64::consts::PI,
+        )]))
+        .unwrap();
+    let (bit_registers, _, _) = bound
+        .run_registers(StatevectorSimulator::new(None), &[0.0])
+        .unwrap();
+    assert_eq!(bit_registers["ro"], vec![vec![true, false]]);
+}
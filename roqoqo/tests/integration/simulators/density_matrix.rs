@@ -0,0 +1,261 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ndarray::array;
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::{DensityMatrixSimulator, StatevectorSimulator};
+use roqoqo::{Circuit, RoqoqoBackendError};
+use std::collections::HashMap;
+use test_case::test_case;
+
+/// Runs a circuit and returns the single row of a float register
+fn run_float(circuit: &Circuit, name: &str) -> Vec<f64> {
+    let backend = DensityMatrixSimulator::new(Some(1));
+    let (_, float_registers, _) = backend.run_circuit(circuit).unwrap();
+    float_registers[name][0].clone()
+}
+
+/// Runs a circuit and returns the single row of a complex register
+fn run_complex(circuit: &Circuit, name: &str) -> Vec<Complex64> {
+    let backend = DensityMatrixSimulator::new(Some(1));
+    let (_, _, complex_registers) = backend.run_circuit(circuit).unwrap();
+    complex_registers[name][0].clone()
+}
+
+/// Test that noise PRAGMAs change the occupation of an excited qubit as expected
+#[test_case(operations::PragmaDamping::new(0, 0.5.into(), 0.4.into()).into(), (-0.2_f64).exp(); "damping")]
+#[test_case(operations::PragmaDephasing::new(0, 0.5.into(), 0.4.into()).into(), 1.0; "dephasing")]
+#[test_case(operations::PragmaDepolarising::new(0, 0.5.into(), 0.4.into()).into(), 0.5 + 0.5 * (-0.2_f64).exp(); "depolarising")]
+#[test_case(operations::PragmaRandomNoise::new(0, 0.5.into(), 0.4.into(), 0.3.into()).into(), 0.5 + 0.5 * (-0.2_f64).exp(); "random noise")]
+fn test_noise_occupation(noise: operations::Operation, expected: f64) {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 2, true);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::Identity::new(1);
+    circuit.add_operation(noise);
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+
+    let occupation = run_float(&circuit, "occupation");
+    assert!((occupation[0] - expected).abs() < 1e-10);
+    assert!(occupation[1].abs() < 1e-10);
+}
+
+/// Test that noise PRAGMAs damp the coherence of a superposition as expected
+#[test_case(operations::PragmaDamping::new(0, 0.5.into(), 0.4.into()).into(), (-0.1_f64).exp(); "damping")]
+#[test_case(operations::PragmaDephasing::new(0, 0.5.into(), 0.4.into()).into(), (-0.4_f64).exp(); "dephasing")]
+#[test_case(operations::PragmaDepolarising::new(0, 0.5.into(), 0.4.into()).into(), (-0.2_f64).exp(); "depolarising")]
+#[test_case(operations::PragmaRandomNoise::new(0, 0.5.into(), 0.4.into(), 0.3.into()).into(), (-0.2_f64).exp() * (-0.3_f64).exp(); "random noise")]
+fn test_noise_coherence(noise: operations::Operation, expected: f64) {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    circuit += operations::DefinitionFloat::new("x".to_string(), 1, true);
+    circuit += operations::Hadamard::new(0);
+    circuit.add_operation(noise);
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+    circuit += operations::PragmaGetPauliProduct::new(
+        HashMap::from([(0, 1)]),
+        "x".to_string(),
+        Circuit::new(),
+    );
+
+    let density = run_complex(&circuit, "density");
+    assert!((density[1] - Complex64::new(0.5 * expected, 0.0)).norm() < 1e-10);
+    assert!((density[2] - Complex64::new(0.5 * expected, 0.0)).norm() < 1e-10);
+    let x = run_float(&circuit, "x");
+    assert!((x[0] - expected).abs() < 1e-10);
+}
+
+/// Test that PragmaGeneralNoise with a single decay rate is equivalent to PragmaDamping
+#[test]
+fn test_general_noise_matches_damping() {
+    let rates = array![[0.4, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]];
+    let mut general = Circuit::new();
+    general += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    general += operations::RotateX::new(0, 1.1.into());
+    general += operations::PragmaGeneralNoise::new(0, 0.5.into(), rates);
+    general += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+    let mut damping = Circuit::new();
+    damping += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    damping += operations::RotateX::new(0, 1.1.into());
+    damping += operations::PragmaDamping::new(0, 0.5.into(), 0.4.into());
+    damping += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+
+    let general_density = run_complex(&general, "density");
+    let damping_density = run_complex(&damping, "density");
+    for (general_value, damping_value) in general_density.iter().zip(damping_density.iter()) {
+        assert!((general_value - damping_value).norm() < 1e-10);
+    }
+}
+
+/// Test that noise-free circuits produce the same density matrix as the statevector simulator
+#[test]
+fn test_gates_match_statevector_simulator() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("density".to_string(), 64, true);
+    for qubit in 0..3 {
+        circuit += operations::RotateY::new(qubit, (0.3 + qubit as f64).into());
+        circuit += operations::RotateZ::new(qubit, (0.2 * qubit as f64).into());
+    }
+    circuit += operations::CNOT::new(2, 0);
+    circuit += operations::ControlledPhaseShift::new(0, 1, 0.7.into());
+    circuit += operations::Toffoli::new(1, 0, 2);
+    circuit += operations::PragmaGlobalPhase::new(0.3.into());
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+
+    let density = run_complex(&circuit, "density");
+    let (_, _, complex_registers) = StatevectorSimulator::new(None)
+        .run_circuit(&circuit)
+        .unwrap();
+    let expected = &complex_registers["density"][0];
+    for (value, expected_value) in density.iter().zip(expected.iter()) {
+        assert!((value - expected_value).norm() < 1e-10);
+    }
+}
+
+/// Test that MeasureQubit samples outcomes from a mixed state and collapses it
+#[test]
+fn test_measure_qubit_mixed_state() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::PragmaDamping::new(0, 1.0.into(), 2.0_f64.ln().into());
+    circuit += operations::CNOT::new(0, 1);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(4000, "ro".to_string());
+
+    let backend = DensityMatrixSimulator::new(Some(7));
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 4000);
+    for record in records.iter() {
+        assert_eq!(record[0], record[1]);
+    }
+    let excited = records.iter().filter(|record| record[0]).count() as f64 / 4000.0;
+    assert!((excited - 0.5).abs() < 0.05);
+
+    // A new backend with the same seed reproduces the records, a further run draws new ones
+    let (repeated_registers, _, _) = DensityMatrixSimulator::new(Some(7))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(bit_registers, repeated_registers);
+    let (next_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    assert_ne!(bit_registers, next_registers);
+}
+
+/// Test that PragmaRepeatedMeasurement samples from the diagonal of the density matrix
+#[test]
+fn test_repeated_measurement() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::PragmaDepolarising::new(0, 1.0.into(), 1.0.into());
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 10000, None);
+
+    let backend = DensityMatrixSimulator::new(Some(3));
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    let excited = bit_registers["ro"]
+        .iter()
+        .filter(|record| record[0])
+        .count() as f64
+        / 10000.0;
+    assert!((excited - (0.5 + 0.5 * (-1.0_f64).exp())).abs() < 0.03);
+}
+
+/// Test that PragmaActiveReset returns a qubit to the zero state deterministically
+#[test]
+fn test_active_reset() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::PragmaActiveReset::new(0);
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+
+    let density = run_complex(&circuit, "density");
+    let expected = [1.0, 0.0, 0.0, 0.0];
+    for (value, expected_value) in density.iter().zip(expected.iter()) {
+        assert!((value - Complex64::new(*expected_value, 0.0)).norm() < 1e-10);
+    }
+}
+
+/// Test setting the density matrix and the statevector of the simulated register
+#[test]
+fn test_set_state() {
+    let density_matrix = array![
+        [Complex64::new(0.25, 0.0), Complex64::new(0.0, 0.1)],
+        [Complex64::new(0.0, -0.1), Complex64::new(0.75, 0.0)]
+    ];
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 1, true);
+    circuit += operations::PragmaSetDensityMatrix::new(density_matrix);
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+    assert!((run_float(&circuit, "occupation")[0] - 0.75).abs() < 1e-10);
+
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    circuit += operations::PragmaSetStateVector::new(array![
+        Complex64::new(0.0, 0.0),
+        Complex64::new(0.0, 1.0)
+    ]);
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+    let density = run_complex(&circuit, "density");
+    assert!((density[3] - Complex64::new(1.0, 0.0)).norm() < 1e-10);
+
+    let mut circuit = Circuit::new();
+    circuit += operations::PauliX::new(1);
+    circuit += operations::PragmaSetDensityMatrix::new(array![[Complex64::new(1.0, 0.0)]]);
+    let result = DensityMatrixSimulator::new(None).run_circuit(&circuit);
+    assert!(matches!(
+        result,
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+}
+
+/// Test the errors for unsupported operations and symbolic parameters
+#[test]
+fn test_errors() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 2, true);
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+    let result = DensityMatrixSimulator::new(None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "DensityMatrixSimulator",
+            hqslang: "PragmaGetStateVector"
+        })
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaDamping::new(0, CalculatorFloat::from("t"), 0.1.into());
+    let result = DensityMatrixSimulator::new(None).run_circuit(&circuit);
+    assert!(matches!(result, Err(RoqoqoBackendError::RoqoqoError(_))));
+}
+
+#[test]
+fn test_too_many_qubits() {
+    let mut circuit = Circuit::new();
+    circuit += operations::PauliX::new(40);
+    assert!(matches!(
+        DensityMatrixSimulator::new(None).run_circuit(&circuit),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+    let mut circuit = Circuit::new();
+    circuit += operations::PauliX::new(64);
+    assert!(matches!(
+        StatevectorSimulator::new(None).run_circuit(&circuit),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+}
@@ -0,0 +1,204 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use num_complex::Complex64;
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::FockSimulator;
+use roqoqo::{Circuit, RoqoqoBackendError};
+use std::f64::consts::PI;
+
+/// Runs a circuit and returns the final statevector
+fn run_statevector(backend: &FockSimulator, circuit: &Circuit, length: usize) -> Vec<Complex64> {
+    let mut circuit = circuit.clone();
+    circuit += operations::DefinitionComplex::new("state".to_string(), length, true);
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+    let (_, _, complex_registers) = backend.run_circuit(&circuit).unwrap();
+    complex_registers["state"][0].clone()
+}
+
+/// Returns n!
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|k| k as f64).product()
+}
+
+/// Test that displacement and phase shift create the expected coherent state
+#[test]
+fn test_coherent_state() {
+    let backend = FockSimulator::new(20, None);
+    let mut circuit = Circuit::new();
+    circuit += operations::PhaseDisplacement::new(0, 0.5.into(), 0.3.into());
+    circuit += operations::PhaseShift::new(0, 0.4.into());
+    let state = run_statevector(&backend, &circuit, 20);
+
+    let alpha = Complex64::from_polar(0.5, 0.7);
+    for (n, amplitude) in state.iter().enumerate().take(10) {
+        let expected = (-0.125_f64).exp() * alpha.powu(n as u32) / factorial(n).sqrt();
+        assert!((amplitude - expected).norm() < 1e-8);
+    }
+}
+
+/// Test that squeezing the vacuum creates the expected squeezed vacuum state
+#[test]
+fn test_squeezed_vacuum() {
+    let backend = FockSimulator::new(40, None);
+    let mut circuit = Circuit::new();
+    circuit += operations::Squeezing::new(0, 0.3.into(), 0.5.into());
+    let state = run_statevector(&backend, &circuit, 40);
+
+    let prefactor = 1.0 / 0.3_f64.cosh().sqrt();
+    let ratio = -Complex64::from_polar(0.3_f64.tanh(), 0.5);
+    for n in 0..5 {
+        let expected = prefactor * ratio.powu(n as u32) * factorial(2 * n).sqrt()
+            / (2.0_f64.powi(n as i32) * factorial(n));
+        assert!((state[2 * n] - expected).norm() < 1e-8);
+        assert!(state[2 * n + 1].norm() < 1e-8);
+    }
+}
+
+/// Test that the beam splitter splits a single photon with the documented amplitudes
+#[test]
+fn test_beam_splitter() {
+    let backend = FockSimulator::new(3, None);
+    let mut circuit = Circuit::new();
+    circuit += operations::PhaseDisplacement::new(1, 0.0.into(), 0.0.into());
+    circuit += operations::PauliX::new(0);
+    circuit += operations::SingleExcitationStore::new(0, 0);
+    circuit += operations::BeamSplitter::new(0, 1, 0.4.into(), 0.9.into());
+    let state = run_statevector(&backend, &circuit, 18);
+
+    // Index = qubit + 2 * (n_0 + 3 * n_1)
+    assert!((state[2] - Complex64::new(0.4_f64.cos(), 0.0)).norm() < 1e-10);
+    assert!((state[6] - Complex64::from_polar(0.4_f64.sin(), 0.9)).norm() < 1e-10);
+}
+
+/// Test the spin-boson interaction gates
+#[test]
+fn test_spin_boson_gates() {
+    let backend = FockSimulator::new(25, None);
+
+    // Jaynes-Cummings exchanges a single excitation between the qubit and the mode
+    let mut circuit = Circuit::new();
+    circuit += operations::PauliX::new(0);
+    circuit += operations::JaynesCummings::new(0, 0, 0.3.into());
+    let state = run_statevector(&backend, &circuit, 50);
+    assert!((state[1] - Complex64::new(0.3_f64.cos(), 0.0)).norm() < 1e-10);
+    assert!((state[2] - Complex64::new(0.0, -0.3_f64.sin())).norm() < 1e-10);
+
+    // The longitudinal coupling displaces the mode by -iθ for the qubit in |0>
+    let mut circuit = Circuit::new();
+    circuit += operations::LongitudinalCoupling::new(0, 0, 0.6.into());
+    let state = run_statevector(&backend, &circuit, 50);
+    assert!((state[0].norm() - (-0.18_f64).exp()).abs() < 1e-8);
+    assert!((state[2] - (-0.18_f64).exp() * Complex64::new(0.0, -0.6)).norm() < 1e-8);
+
+    // The Rabi interaction entangles the qubit with opposite displacements of the mode
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 1, true);
+    circuit += operations::QuantumRabi::new(0, 0, 0.6.into());
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+    let (_, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+    let expected = (1.0 - (-2.0 * 0.36_f64).exp()) / 2.0;
+    assert!((float_registers["occupation"][0][0] - expected).abs() < 1e-8);
+}
+
+/// Test storing and loading a qubit state and the qubit-resonator CZ gate
+#[test]
+fn test_store_load_and_cz() {
+    let backend = FockSimulator::new(3, None);
+    let mut circuit = Circuit::new();
+    circuit += operations::RotateY::new(0, 0.7.into());
+    circuit += operations::SingleExcitationStore::new(0, 0);
+    let stored = run_statevector(&backend, &circuit, 6);
+    assert!((stored[0] - Complex64::new(0.35_f64.cos(), 0.0)).norm() < 1e-10);
+    assert!((stored[2] - Complex64::new(0.35_f64.sin(), 0.0)).norm() < 1e-10);
+
+    circuit += operations::SingleExcitationLoad::new(0, 0);
+    let loaded = run_statevector(&backend, &circuit, 6);
+    assert!((loaded[0] - Complex64::new(0.35_f64.cos(), 0.0)).norm() < 1e-10);
+    assert!((loaded[1] - Complex64::new(0.35_f64.sin(), 0.0)).norm() < 1e-10);
+
+    let mut circuit = Circuit::new();
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::SingleExcitationStore::new(0, 0);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::CZQubitResonator::new(0, 0);
+    let state = run_statevector(&backend, &circuit, 6);
+    assert!((state[1] - Complex64::new(1.0 / 2.0_f64.sqrt(), 0.0)).norm() < 1e-10);
+    assert!((state[3] - Complex64::new(-1.0 / 2.0_f64.sqrt(), 0.0)).norm() < 1e-10);
+}
+
+/// Test the photon number statistics of PhotonDetection and the collapse of the state
+#[test]
+fn test_photon_detection() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("photons".to_string(), 2, true);
+    circuit += operations::PhaseDisplacement::new(0, 1.0.into(), (PI / 4.0).into());
+    circuit += operations::PhotonDetection::new(0, "photons".to_string(), 0);
+    circuit += operations::PhotonDetection::new(0, "photons".to_string(), 1);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(4000, "photons".to_string());
+
+    let backend = FockSimulator::new(20, Some(9));
+    let (_, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+    let records = &float_registers["photons"];
+    assert_eq!(records.len(), 4000);
+    for record in records.iter() {
+        assert_eq!(record[0], record[1]);
+    }
+    let mean = records.iter().map(|record| record[0]).sum::<f64>() / 4000.0;
+    assert!((mean - 1.0).abs() < 0.06);
+}
+
+/// Test that the cutoff can be set for individual modes
+#[test]
+fn test_mode_cutoffs() {
+    let mut backend = FockSimulator::new(4, None);
+    backend.set_cutoff(1, 2);
+    assert_eq!(backend.cutoff(0), 4);
+    assert_eq!(backend.cutoff(1), 2);
+    let mut circuit = Circuit::new();
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::BeamSplitter::new(0, 1, 0.1.into(), 0.0.into());
+    let state = run_statevector(&backend, &circuit, 16);
+    assert_eq!(state.len(), 16);
+
+    backend.set_cutoff(1, 1);
+    let mut circuit = Circuit::new();
+    circuit += operations::SingleExcitationStore::new(0, 1);
+    assert!(matches!(
+        backend.run_circuit(&circuit),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+
+    backend.set_cutoff(1, 0);
+    let mut circuit = Circuit::new();
+    circuit += operations::PhaseShift::new(1, 0.1.into());
+    assert!(matches!(
+        backend.run_circuit(&circuit),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+}
+
+/// Test the error for unsupported operations
+#[test]
+fn test_unsupported_operation() {
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaDamping::new(0, 1.0.into(), 0.1.into());
+    let result = FockSimulator::new(2, None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "FockSimulator",
+            hqslang: "PragmaDamping"
+        })
+    );
+}
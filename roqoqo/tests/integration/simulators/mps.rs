@@ -0,0 +1,237 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use num_complex::Complex64;
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::{MatrixProductStateSimulator, StatevectorSimulator};
+use roqoqo::{Circuit, RoqoqoBackendError};
+use std::collections::HashMap;
+use test_case::test_case;
+
+/// Returns a circuit entangling five qubits with gates on neighbouring and distant qubits
+fn entangling_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    for qubit in 0..5 {
+        circuit += operations::RotateY::new(qubit, (0.3 + 0.4 * qubit as f64).into());
+        circuit += operations::RotateZ::new(qubit, (0.2 * qubit as f64).into());
+    }
+    circuit += operations::CNOT::new(0, 1);
+    circuit += operations::CNOT::new(4, 1);
+    circuit += operations::ControlledPhaseShift::new(0, 3, 0.7.into());
+    circuit += operations::ISwap::new(3, 0);
+    circuit += operations::SqrtISwap::new(2, 4);
+    circuit += operations::Hadamard::new(2);
+    circuit += operations::ControlledPauliY::new(3, 2);
+    circuit += operations::XY::new(1, 4, 0.5.into());
+    circuit += operations::PragmaGlobalPhase::new(0.3.into());
+    circuit
+}
+
+/// Returns the final statevector of a circuit
+fn final_statevector<T: EvaluatingBackend>(backend: &T, preparation: &Circuit) -> Vec<Complex64> {
+    let mut circuit = preparation.clone();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 32, true);
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+    backend.run_circuit(&circuit).unwrap().2["state"][0].clone()
+}
+
+/// Test that the statevector matches the statevector simulator including SWAP insertion
+#[test]
+fn test_statevector_matches_statevector_simulator() {
+    let circuit = entangling_circuit();
+    let mps = final_statevector(&MatrixProductStateSimulator::new(None, 0.0, None), &circuit);
+    let reference = final_statevector(&StatevectorSimulator::new(None), &circuit);
+    for (value, expected) in mps.iter().zip(reference.iter()) {
+        assert!((value - expected).norm() < 1e-10);
+    }
+}
+
+/// Test Pauli products and occupation probabilities against the statevector simulator
+#[test_case(vec![(0, 3)]; "Z0")]
+#[test_case(vec![(4, 1)]; "X4")]
+#[test_case(vec![(0, 1), (4, 2)]; "X0 Y4")]
+#[test_case(vec![(1, 3), (2, 1), (3, 2)]; "Z1 X2 Y3")]
+fn test_readout_matches_statevector_simulator(paulis: Vec<(usize, usize)>) {
+    let mut circuit = entangling_circuit();
+    circuit += operations::DefinitionFloat::new("pauli".to_string(), 1, true);
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 5, true);
+    let qubit_paulis: HashMap<usize, usize> = paulis.into_iter().collect();
+    circuit +=
+        operations::PragmaGetPauliProduct::new(qubit_paulis, "pauli".to_string(), Circuit::new());
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+
+    let (_, mps, _) = MatrixProductStateSimulator::new(None, 0.0, None)
+        .run_circuit(&circuit)
+        .unwrap();
+    let (_, reference, _) = StatevectorSimulator::new(None)
+        .run_circuit(&circuit)
+        .unwrap();
+    assert!((mps["pauli"][0][0] - reference["pauli"][0][0]).abs() < 1e-10);
+    for (value, expected) in mps["occupation"][0]
+        .iter()
+        .zip(reference["occupation"][0].iter())
+    {
+        assert!((value - expected).abs() < 1e-10);
+    }
+}
+
+/// Test a GHZ state on a long chain with the truncation register
+#[test]
+fn test_long_ghz_chain() {
+    let number_qubits = 200;
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), number_qubits, true);
+    circuit += operations::DefinitionFloat::new("parity".to_string(), 1, true);
+    circuit += operations::Hadamard::new(0);
+    for qubit in 0..number_qubits - 1 {
+        circuit += operations::CNOT::new(qubit, qubit + 1);
+    }
+    let qubit_paulis: HashMap<usize, usize> = (0..number_qubits).map(|qubit| (qubit, 1)).collect();
+    circuit +=
+        operations::PragmaGetPauliProduct::new(qubit_paulis, "parity".to_string(), Circuit::new());
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 20, None);
+
+    let backend = MatrixProductStateSimulator::new(Some(8), 1e-12, Some(5));
+    let (bit_registers, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 20);
+    for record in records.iter() {
+        assert!(record.iter().all(|value| *value == record[0]));
+    }
+    assert!(records.iter().any(|record| record[0]));
+    assert!(records.iter().any(|record| !record[0]));
+    assert!((float_registers["parity"][0][0] - 1.0).abs() < 1e-10);
+    let truncation = &float_registers[MatrixProductStateSimulator::TRUNCATION_REGISTER][0];
+    assert!(truncation[0] < 1e-12);
+    assert_eq!(truncation[1], 2.0);
+}
+
+/// Test that limiting the bond dimension truncates the state and reports the truncation error
+#[test]
+fn test_bond_dimension_truncation() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 6, true);
+    for qubit in 0..6 {
+        circuit += operations::Hadamard::new(qubit);
+    }
+    for layer in 0..3 {
+        for qubit in 0..5 {
+            circuit += operations::ControlledPhaseShift::new(
+                qubit,
+                qubit + 1,
+                (0.9 + 0.1 * layer as f64).into(),
+            );
+            circuit += operations::RotateX::new(qubit, (0.3 * (layer + qubit) as f64).into());
+        }
+    }
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+
+    let (_, exact, _) = MatrixProductStateSimulator::new(None, 0.0, None)
+        .run_circuit(&circuit)
+        .unwrap();
+    let exact_truncation = &exact[MatrixProductStateSimulator::TRUNCATION_REGISTER][0];
+    assert!(exact_truncation[0] < 1e-12);
+    assert!(exact_truncation[1] > 2.0);
+
+    let (_, truncated, _) = MatrixProductStateSimulator::new(Some(2), 0.0, None)
+        .run_circuit(&circuit)
+        .unwrap();
+    let truncation = &truncated[MatrixProductStateSimulator::TRUNCATION_REGISTER][0];
+    assert!(truncation[0] > 1e-6);
+    assert_eq!(truncation[1], 2.0);
+    // The occupation probabilities are still valid probabilities of a normalised state
+    for value in truncated["occupation"][0].iter() {
+        assert!((0.0..=1.0 + 1e-10).contains(value));
+    }
+}
+
+/// Test that measurements collapse the state and the truncation register is written for every run
+#[test]
+fn test_measurement_collapse_and_reset() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 4, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::CNOT::new(0, 3);
+    circuit += operations::MeasureQubit::new(3, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 1);
+    circuit += operations::PragmaActiveReset::new(3);
+    circuit += operations::MeasureQubit::new(3, "ro".to_string(), 2);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(30, "ro".to_string());
+
+    let backend = MatrixProductStateSimulator::new(None, 1e-10, Some(11));
+    let (bit_registers, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 30);
+    for record in records.iter() {
+        assert_eq!(record[0], record[1]);
+        assert!(!record[2]);
+    }
+    assert!(records.iter().any(|record| record[0]));
+    assert!(records.iter().any(|record| !record[0]));
+    assert_eq!(
+        float_registers[MatrixProductStateSimulator::TRUNCATION_REGISTER].len(),
+        30
+    );
+}
+
+/// Test that a statevector set with PragmaSetStateVector is reproduced
+#[test]
+fn test_set_statevector() {
+    let statevector: Vec<Complex64> = (0..8)
+        .map(|index| Complex64::new(1.0 + index as f64, 0.5 * index as f64))
+        .collect();
+    let norm: f64 = statevector.iter().map(|value| value.norm_sqr()).sum();
+    let statevector: Vec<Complex64> = statevector
+        .iter()
+        .map(|value| value / norm.sqrt())
+        .collect();
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 8, true);
+    circuit += operations::PragmaSetStateVector::new(statevector.clone().into());
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+
+    let backend = MatrixProductStateSimulator::new(None, 0.0, None);
+    let (_, _, complex_registers) = backend.run_circuit(&circuit).unwrap();
+    for (value, expected) in complex_registers["state"][0].iter().zip(statevector.iter()) {
+        assert!((value - expected).norm() < 1e-10);
+    }
+}
+
+/// Test that gates on more than two qubits are rejected
+#[test]
+fn test_three_qubit_gate() {
+    let mut circuit = Circuit::new();
+    circuit += operations::Toffoli::new(0, 1, 2);
+    let result = MatrixProductStateSimulator::new(None, 0.0, None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "MatrixProductStateSimulator",
+            hqslang: "Toffoli"
+        })
+    );
+}
+
+/// Test that invalid truncation settings return an error
+#[test_case(Some(0), 0.0; "zero bond dimension")]
+#[test_case(None, -0.1; "negative threshold")]
+#[test_case(None, 1.0; "threshold one")]
+fn test_invalid_configuration(max_bond_dimension: Option<usize>, truncation_threshold: f64) {
+    let mut circuit = Circuit::new();
+    circuit += operations::Hadamard::new(0);
+    let backend = MatrixProductStateSimulator::new(max_bond_dimension, truncation_threshold, None);
+    assert!(matches!(
+        backend.run_circuit(&circuit),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+}
@@ -0,0 +1,203 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::{StabilizerSimulator, StatevectorSimulator};
+use roqoqo::{Circuit, RoqoqoBackendError};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use test_case::test_case;
+
+/// Returns the Pauli product expectation values of all Pauli strings on three qubits
+fn all_pauli_products<T: EvaluatingBackend>(backend: &T, preparation: &Circuit) -> Vec<f64> {
+    let mut circuit = preparation.clone();
+    let mut names = Vec::new();
+    for index in 0..64 {
+        let qubit_paulis: HashMap<usize, usize> = (0..3)
+            .map(|qubit| (qubit, (index >> (2 * qubit)) & 3))
+            .collect();
+        let name = format!("pauli_{index}");
+        circuit += operations::DefinitionFloat::new(name.clone(), 1, true);
+        circuit +=
+            operations::PragmaGetPauliProduct::new(qubit_paulis, name.clone(), Circuit::new());
+        names.push(name);
+    }
+    let (_, float_registers, _) = backend.run_circuit(&circuit).unwrap();
+    names
+        .iter()
+        .map(|name| float_registers[name][0][0])
+        .collect()
+}
+
+/// Test that Clifford gates produce the same state as the statevector simulator
+#[test_case(operations::Hadamard::new(1).into(); "Hadamard")]
+#[test_case(operations::SGate::new(1).into(); "SGate")]
+#[test_case(operations::InvSGate::new(1).into(); "InvSGate")]
+#[test_case(operations::PauliX::new(1).into(); "PauliX")]
+#[test_case(operations::PauliY::new(1).into(); "PauliY")]
+#[test_case(operations::PauliZ::new(1).into(); "PauliZ")]
+#[test_case(operations::SqrtPauliX::new(1).into(); "SqrtPauliX")]
+#[test_case(operations::InvSqrtPauliX::new(1).into(); "InvSqrtPauliX")]
+#[test_case(operations::RotateX::new(1, (PI / 2.0).into()).into(); "RotateX pi/2")]
+#[test_case(operations::RotateY::new(1, PI.into()).into(); "RotateY pi")]
+#[test_case(operations::RotateZ::new(1, (-PI / 2.0).into()).into(); "RotateZ -pi/2")]
+#[test_case(operations::PhaseShiftState1::new(1, (3.0 * PI / 2.0).into()).into(); "PhaseShiftState1 3pi/2")]
+#[test_case(operations::CNOT::new(2, 0).into(); "CNOT")]
+#[test_case(operations::ControlledPauliZ::new(0, 2).into(); "ControlledPauliZ")]
+#[test_case(operations::ControlledPauliY::new(2, 1).into(); "ControlledPauliY")]
+#[test_case(operations::SWAP::new(0, 2).into(); "SWAP")]
+#[test_case(operations::ISwap::new(1, 2).into(); "ISwap")]
+#[test_case(operations::ControlledPhaseShift::new(1, 0, PI.into()).into(); "ControlledPhaseShift pi")]
+#[test_case(operations::MolmerSorensenXX::new(0, 1).into(); "MolmerSorensenXX")]
+fn test_clifford_gates_match_statevector(gate: operations::Operation) {
+    let mut circuit = Circuit::new();
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::SGate::new(0);
+    circuit += operations::CNOT::new(0, 1);
+    circuit += operations::Hadamard::new(2);
+    circuit += operations::PauliX::new(1);
+    circuit.add_operation(gate);
+    circuit += operations::Hadamard::new(1);
+    circuit += operations::CNOT::new(1, 2);
+
+    let stabilizer = all_pauli_products(&StabilizerSimulator::new(None), &circuit);
+    let statevector = all_pauli_products(&StatevectorSimulator::new(None), &circuit);
+    for (stabilizer_value, statevector_value) in stabilizer.iter().zip(statevector.iter()) {
+        assert!((stabilizer_value - statevector_value).abs() < 1e-10);
+    }
+}
+
+/// Test that non-Clifford gates are rejected
+#[test_case(operations::TGate::new(0).into(); "TGate")]
+#[test_case(operations::RotateX::new(0, 0.3.into()).into(); "RotateX")]
+#[test_case(operations::ControlledPhaseShift::new(0, 1, (PI / 2.0).into()).into(); "ControlledPhaseShift")]
+#[test_case(operations::InvSqrtISwap::new(0, 1).into(); "InvSqrtISwap")]
+#[test_case(operations::Toffoli::new(0, 1, 2).into(); "Toffoli")]
+#[test_case(operations::MultiQubitZZ::new(vec![0, 1, 2, 3], (PI / 2.0).into()).into(); "MultiQubitZZ")]
+#[test_case(operations::PragmaDamping::new(0, 1.0.into(), 0.1.into()).into(); "PragmaDamping")]
+fn test_non_clifford_operations(operation: operations::Operation) {
+    let mut circuit = Circuit::new();
+    circuit += operations::Hadamard::new(0);
+    let hqslang = operation.hqslang();
+    circuit.add_operation(operation);
+    let result = StabilizerSimulator::new(None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "StabilizerSimulator",
+            hqslang
+        })
+    );
+}
+
+/// Test that symbolic rotation angles are not in the backend
+#[test]
+fn test_symbolic_rotation() {
+    let mut circuit = Circuit::new();
+    circuit += operations::RotateZ::new(0, CalculatorFloat::from("theta"));
+    let result = StabilizerSimulator::new(None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "StabilizerSimulator",
+            hqslang: "RotateZ"
+        })
+    );
+}
+
+/// Test a GHZ state on thousands of qubits
+#[test]
+fn test_large_ghz_state() {
+    let number_qubits = 1000;
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), number_qubits, true);
+    circuit += operations::DefinitionBit::new("single".to_string(), number_qubits, true);
+    circuit += operations::Hadamard::new(0);
+    for qubit in 0..number_qubits - 1 {
+        circuit += operations::CNOT::new(qubit, qubit + 1);
+    }
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 100, None);
+    circuit += operations::MeasureQubit::new(number_qubits - 1, "single".to_string(), 0);
+    circuit += operations::MeasureQubit::new(0, "single".to_string(), 1);
+
+    let (bit_registers, _, _) = StabilizerSimulator::new(Some(8))
+        .run_circuit(&circuit)
+        .unwrap();
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 100);
+    for record in records.iter() {
+        assert!(record.iter().all(|value| *value == record[0]));
+    }
+    let excited = records.iter().filter(|record| record[0]).count();
+    assert!(excited > 20 && excited < 80);
+    let single = &bit_registers["single"][0];
+    assert_eq!(single[0], single[1]);
+}
+
+/// Test measurement statistics, collapse and reset
+#[test]
+fn test_measurement_and_reset() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 2, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::PauliX::new(1);
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 1);
+    circuit += operations::PragmaActiveReset::new(0);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 2);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(2000, "ro".to_string());
+
+    let (bit_registers, float_registers, _) = StabilizerSimulator::new(Some(4))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(float_registers["occupation"][0], vec![0.5, 1.0]);
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 2000);
+    for record in records.iter() {
+        assert_eq!(record[0], record[1]);
+        assert!(!record[2]);
+    }
+    let excited = records.iter().filter(|record| record[0]).count() as f64 / 2000.0;
+    assert!((excited - 0.5).abs() < 0.05);
+}
+
+/// Test the sign of Pauli products with PauliY
+#[test]
+fn test_pauli_y_expectation() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("y".to_string(), 1, true);
+    circuit += operations::DefinitionFloat::new("minus_y".to_string(), 1, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::SGate::new(0);
+    circuit += operations::PragmaGetPauliProduct::new(
+        HashMap::from([(0, 2)]),
+        "y".to_string(),
+        Circuit::new(),
+    );
+    let mut flip = Circuit::new();
+    flip += operations::PauliZ::new(0);
+    circuit += operations::PragmaGetPauliProduct::new(
+        HashMap::from([(0, 2)]),
+        "minus_y".to_string(),
+        flip,
+    );
+
+    let (_, float_registers, _) = StabilizerSimulator::new(None)
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(float_registers["y"][0], vec![1.0]);
+    assert_eq!(float_registers["minus_y"][0], vec![-1.0]);
+}
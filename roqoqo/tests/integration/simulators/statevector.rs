@@ -0,0 +1,407 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::measurements::{
+    Cheated, CheatedInput, CheatedPauliZProduct, CheatedPauliZProductInput, ClassicalRegister,
+    PauliZProduct, PauliZProductInput,
+};
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::StatevectorSimulator;
+use roqoqo::{Circuit, QuantumProgram};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use test_case::test_case;
+
+/// Test that gates act on the qubits in the order of the roqoqo unitary matrices
+#[test_case(operations::PauliX::new(0).into(), vec![0.0, 0.0, 0.0, 1.0]; "PauliX 0")]
+#[test_case(operations::PauliX::new(1).into(), vec![1.0, 0.0, 0.0, 0.0]; "PauliX 1")]
+#[test_case(operations::CNOT::new(1, 0).into(), vec![0.0, 0.0, 0.0, 1.0]; "CNOT 1 0")]
+#[test_case(operations::CNOT::new(0, 1).into(), vec![0.0, 0.0, 1.0, 0.0]; "CNOT 0 1")]
+#[test_case(operations::SWAP::new(0, 1).into(), vec![0.0, 1.0, 0.0, 0.0]; "SWAP")]
+fn test_gate_qubit_order(operation: operations::Operation, expected: Vec<f64>) {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 4, true);
+    circuit += operations::PauliX::new(1);
+    circuit += operations::Identity::new(0);
+    circuit.add_operation(operation);
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+
+    let backend = StatevectorSimulator::new(None);
+    let (_, _, complex_registers) = backend.run_circuit(&circuit).unwrap();
+    let state = &complex_registers["state"][0];
+    for (value, expected_value) in state.iter().zip(expected.iter()) {
+        assert!((value - Complex64::new(*expected_value, 0.0)).norm() < 1e-10);
+    }
+}
+
+/// Test three-qubit and multi-qubit gates against their decompositions
+#[test]
+fn test_multi_qubit_gates_match_decomposition() {
+    let gates: Vec<operations::Operation> = vec![
+        operations::Toffoli::new(0, 2, 1).into(),
+        operations::ControlledControlledPhaseShift::new(2, 0, 1, 0.3.into()).into(),
+        operations::ControlledSWAP::new(3, 0, 1).into(),
+        operations::MultiQubitMS::new(vec![0, 1, 2], 0.7.into()).into(),
+        operations::MultiQubitZZ::new(vec![0, 1, 2], 0.4.into()).into(),
+    ];
+    for gate in gates {
+        let mut preparation = Circuit::new();
+        preparation += operations::DefinitionComplex::new("state".to_string(), 16, true);
+        for qubit in 0..4 {
+            preparation += operations::RotateY::new(qubit, (0.3 + qubit as f64).into());
+            preparation += operations::RotateZ::new(qubit, (0.1 * qubit as f64).into());
+        }
+        let decomposition = match &gate {
+            operations::Operation::Toffoli(op) => op.circuit(),
+            operations::Operation::ControlledControlledPhaseShift(op) => op.circuit(),
+            operations::Operation::ControlledSWAP(op) => op.circuit(),
+            operations::Operation::MultiQubitMS(op) => op.circuit(),
+            operations::Operation::MultiQubitZZ(op) => op.circuit(),
+            _ => unreachable!(),
+        };
+        let mut direct = preparation.clone();
+        direct.add_operation(gate.clone());
+        direct += operations::PragmaGetStateVector::new("state".to_string(), None);
+        let mut decomposed = preparation.clone() + decomposition;
+        decomposed += operations::PragmaGetStateVector::new("state".to_string(), None);
+
+        let backend = StatevectorSimulator::new(None);
+        let direct_state = backend.run_circuit(&direct).unwrap().2["state"][0].clone();
+        let decomposed_state = backend.run_circuit(&decomposed).unwrap().2["state"][0].clone();
+        // Decompositions may differ by a global phase
+        let overlap: Complex64 = direct_state
+            .iter()
+            .zip(decomposed_state.iter())
+            .map(|(a, b)| a.conj() * b)
+            .sum();
+        assert!(
+            (overlap.norm() - 1.0).abs() < 1e-10,
+            "{} does not match its decomposition",
+            gate.hqslang()
+        );
+    }
+}
+
+#[test]
+fn test_repeated_measurement_bell_state() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::CNOT::new(0, 1);
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 200, None);
+
+    let backend = StatevectorSimulator::new(Some(1));
+    let (bit_registers, float_registers, complex_registers) =
+        backend.run_circuit(&circuit).unwrap();
+    assert!(float_registers.is_empty());
+    assert!(complex_registers.is_empty());
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 200);
+    let mut number_ones = 0;
+    for record in records.iter() {
+        assert_eq!(record.len(), 3);
+        assert_eq!(record[0], record[1]);
+        assert!(!record[2]);
+        if record[0] {
+            number_ones += 1;
+        }
+    }
+    assert!(number_ones > 50 && number_ones < 150);
+}
+
+#[test]
+fn test_repeated_measurement_qubit_mapping() {
+    let mut mapping: HashMap<usize, usize> = HashMap::new();
+    mapping.insert(0, 1);
+    mapping.insert(1, 0);
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::Identity::new(1);
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 10, Some(mapping));
+
+    let backend = StatevectorSimulator::new(None);
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    for record in bit_registers["ro"].iter() {
+        assert_eq!(record, &vec![false, true]);
+    }
+}
+
+#[test]
+fn test_measure_qubit_number_of_measurements() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::DefinitionBit::new("hidden".to_string(), 2, false);
+    circuit += operations::RotateX::new(0, CalculatorFloat::FRAC_PI_2);
+    circuit += operations::PauliX::new(1);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(50, "ro".to_string());
+
+    let backend = StatevectorSimulator::new(Some(7));
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    assert!(!bit_registers.contains_key("hidden"));
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 50);
+    assert!(records.iter().all(|record| record[1]));
+    assert!(records.iter().any(|record| record[0]));
+    assert!(records.iter().any(|record| !record[0]));
+
+    // A new backend with the same seed reproduces the records, a further run draws new ones
+    let (bit_registers_repeated, _, _) = StatevectorSimulator::new(Some(7))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(bit_registers, bit_registers_repeated);
+    let (bit_registers_next, _, _) = backend.run_circuit(&circuit).unwrap();
+    assert_ne!(bit_registers, bit_registers_next);
+}
+
+#[test]
+fn test_measurement_collapse_conditional_and_reset() {
+    let mut conditional_circuit = Circuit::new();
+    conditional_circuit += operations::PauliX::new(1);
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::PragmaConditional::new("ro".to_string(), 0, conditional_circuit);
+    circuit += operations::MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit += operations::PragmaActiveReset::new(0);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 2);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(40, "ro".to_string());
+
+    let backend = StatevectorSimulator::new(Some(3));
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    for record in bit_registers["ro"].iter() {
+        assert_eq!(record[0], record[1]);
+        assert!(!record[2]);
+    }
+}
+
+#[test]
+fn test_loop_and_readout_pragmas() {
+    let mut loop_circuit = Circuit::new();
+    loop_circuit += operations::RotateX::new(0, CalculatorFloat::FRAC_PI_4);
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("occupation".to_string(), 2, true);
+    circuit += operations::DefinitionFloat::new("pauli".to_string(), 1, true);
+    circuit += operations::DefinitionComplex::new("density".to_string(), 16, true);
+    circuit += operations::PragmaLoop::new(2.0.into(), loop_circuit);
+    circuit += operations::Identity::new(1);
+    circuit += operations::PragmaGetOccupationProbability::new("occupation".to_string(), None);
+    let mut paulis: HashMap<usize, usize> = HashMap::new();
+    paulis.insert(0, 2);
+    circuit += operations::PragmaGetPauliProduct::new(paulis, "pauli".to_string(), Circuit::new());
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+
+    let backend = StatevectorSimulator::new(None);
+    let (_, float_registers, complex_registers) = backend.run_circuit(&circuit).unwrap();
+    let occupation = &float_registers["occupation"][0];
+    assert!((occupation[0] - 0.5).abs() < 1e-10);
+    assert!(occupation[1].abs() < 1e-10);
+    // RotateX(pi/2)|0> has <Y> = -1
+    assert!((float_registers["pauli"][0][0] + 1.0).abs() < 1e-10);
+    let density = &complex_registers["density"][0];
+    assert_eq!(density.len(), 16);
+    assert!((density[0] - Complex64::new(0.5, 0.0)).norm() < 1e-10);
+    assert!((density[5] - Complex64::new(0.5, 0.0)).norm() < 1e-10);
+    assert!((density[1] - Complex64::new(0.0, 0.5)).norm() < 1e-10);
+}
+
+#[test]
+fn test_errors() {
+    let backend = StatevectorSimulator::new(None);
+
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaDamping::new(0, 1.0.into(), 0.1.into());
+    assert_eq!(
+        backend.run_circuit(&circuit),
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "StatevectorSimulator",
+            hqslang: "PragmaDamping"
+        })
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    assert_eq!(
+        backend.run_circuit(&circuit),
+        Err(RoqoqoBackendError::RoqoqoError(
+            RoqoqoError::MissingRegister {
+                name: "ro".to_string()
+            }
+        ))
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 1);
+    assert!(backend.run_circuit(&circuit).is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += operations::RotateX::new(0, "theta".into());
+    assert!(backend.run_circuit(&circuit).is_err());
+}
+
+#[test]
+fn test_pauli_z_product_end_to_end() {
+    let mut input = PauliZProductInput::new(2, false);
+    let z0 = input.add_pauliz_product("ro".to_string(), vec![0]).unwrap();
+    let z0z1 = input
+        .add_pauliz_product("ro".to_string(), vec![0, 1])
+        .unwrap();
+    let mut linear: HashMap<usize, f64> = HashMap::new();
+    linear.insert(z0, 1.0);
+    linear.insert(z0z1, 0.5);
+    input
+        .add_linear_exp_val("energy".to_string(), linear)
+        .unwrap();
+
+    let mut constant_circuit = Circuit::new();
+    constant_circuit += operations::RotateY::new(0, "theta".into());
+    constant_circuit += operations::CNOT::new(0, 1);
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 20000, None);
+    let measurement = PauliZProduct {
+        constant_circuit: Some(constant_circuit),
+        circuits: vec![circuit],
+        input,
+    };
+    let program = QuantumProgram::PauliZProduct {
+        measurement,
+        input_parameter_names: vec!["theta".to_string()],
+    };
+    let theta = PI / 3.0;
+    let result = program
+        .run(StatevectorSimulator::new(Some(11)), &[theta])
+        .unwrap()
+        .unwrap();
+    // <Z0> = cos(theta) and <Z0 Z1> = 1 for the prepared state
+    let expected = theta.cos() + 0.5;
+    assert!((result["energy"] - expected).abs() < 0.05);
+}
+
+#[test]
+fn test_cheated_pauli_z_product_end_to_end() {
+    let mut input = CheatedPauliZProductInput::new();
+    let x0 = input.add_pauliz_product("x0".to_string());
+    let z1 = input.add_pauliz_product("z1".to_string());
+    let mut linear: HashMap<usize, f64> = HashMap::new();
+    linear.insert(x0, 2.0);
+    linear.insert(z1, 1.0);
+    input
+        .add_linear_exp_val("value".to_string(), linear)
+        .unwrap();
+
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionFloat::new("x0".to_string(), 1, true);
+    circuit += operations::DefinitionFloat::new("z1".to_string(), 1, true);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::PauliX::new(1);
+    let mut x_paulis: HashMap<usize, usize> = HashMap::new();
+    x_paulis.insert(0, 1);
+    circuit += operations::PragmaGetPauliProduct::new(x_paulis, "x0".to_string(), Circuit::new());
+    let mut z_paulis: HashMap<usize, usize> = HashMap::new();
+    z_paulis.insert(1, 3);
+    circuit += operations::PragmaGetPauliProduct::new(z_paulis, "z1".to_string(), Circuit::new());
+
+    let measurement = CheatedPauliZProduct {
+        constant_circuit: None,
+        circuits: vec![circuit],
+        input,
+    };
+    let program = QuantumProgram::CheatedPauliZProduct {
+        measurement,
+        input_parameter_names: vec![],
+    };
+    let result = program
+        .run(StatevectorSimulator::new(None), &[])
+        .unwrap()
+        .unwrap();
+    assert!((result["value"] - 1.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_cheated_end_to_end() {
+    let mut input = CheatedInput::new(1);
+    input
+        .add_operator_exp_val(
+            "x".to_string(),
+            vec![
+                (0, 1, Complex64::new(1.0, 0.0)),
+                (1, 0, Complex64::new(1.0, 0.0)),
+            ],
+            "state".to_string(),
+        )
+        .unwrap();
+    input
+        .add_operator_exp_val(
+            "z".to_string(),
+            vec![
+                (0, 0, Complex64::new(1.0, 0.0)),
+                (1, 1, Complex64::new(-1.0, 0.0)),
+            ],
+            "density".to_string(),
+        )
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 2, true);
+    circuit += operations::DefinitionComplex::new("density".to_string(), 4, true);
+    circuit += operations::RotateY::new(0, "theta".into());
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+    let measurement = Cheated {
+        constant_circuit: None,
+        circuits: vec![circuit],
+        input,
+    };
+    let program = QuantumProgram::Cheated {
+        measurement,
+        input_parameter_names: vec!["theta".to_string()],
+    };
+    let theta = 0.4;
+    let result = program
+        .run(StatevectorSimulator::new(None), &[theta])
+        .unwrap()
+        .unwrap();
+    assert!((result["x"] - theta.sin()).abs() < 1e-10);
+    assert!((result["z"] - theta.cos()).abs() < 1e-10);
+}
+
+#[test]
+fn test_classical_register_end_to_end() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::PauliX::new(1);
+    circuit += operations::MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += operations::MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit += operations::PragmaSetNumberOfMeasurements::new(5, "ro".to_string());
+    let measurement = ClassicalRegister {
+        constant_circuit: None,
+        circuits: vec![circuit.clone(), circuit],
+    };
+    let program = QuantumProgram::ClassicalRegister {
+        measurement,
+        input_parameter_names: vec![],
+    };
+    let (bit_registers, _, _) = program
+        .run_registers(StatevectorSimulator::new(None), &[])
+        .unwrap();
+    assert_eq!(bit_registers["ro"].len(), 10);
+    for record in bit_registers["ro"].iter() {
+        assert_eq!(record, &vec![false, true]);
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ndarray::array;
+use num_complex::Complex64;
+use roqoqo::operations;
+use roqoqo::prelude::*;
+use roqoqo::simulators::{DensityMatrixSimulator, QuantumTrajectorySimulator};
+use roqoqo::{Circuit, RoqoqoBackendError};
+use std::collections::HashMap;
+use test_case::test_case;
+
+/// Test that the trajectory average of the density matrix converges to the exact density matrix
+#[test_case(operations::PragmaDamping::new(0, 0.5.into(), 0.8.into()).into(); "damping")]
+#[test_case(operations::PragmaDephasing::new(0, 0.5.into(), 0.8.into()).into(); "dephasing")]
+#[test_case(operations::PragmaDepolarising::new(0, 0.5.into(), 0.8.into()).into(); "depolarising")]
+#[test_case(operations::PragmaRandomNoise::new(0, 0.5.into(), 0.8.into(), 0.4.into()).into(); "random noise")]
+#[test_case(operations::PragmaGeneralNoise::new(0, 0.5.into(), array![[0.3, 0.0, 0.0], [0.0, 0.2, 0.0], [0.0, 0.0, 0.5]]).into(); "general noise")]
+fn test_average_matches_density_matrix(noise: operations::Operation) {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("density".to_string(), 16, true);
+    circuit += operations::RotateY::new(0, 1.1.into());
+    circuit += operations::RotateZ::new(0, 0.4.into());
+    circuit += operations::CNOT::new(0, 1);
+    circuit.add_operation(noise);
+    circuit += operations::Hadamard::new(0);
+    circuit += operations::PragmaGetDensityMatrix::new("density".to_string(), None);
+
+    let (_, _, exact_registers) = DensityMatrixSimulator::new(None)
+        .run_circuit(&circuit)
+        .unwrap();
+    let exact = &exact_registers["density"][0];
+
+    circuit += operations::PragmaSetNumberOfMeasurements::new(4000, "density".to_string());
+    let (_, _, trajectory_registers) = QuantumTrajectorySimulator::new(Some(11))
+        .run_circuit(&circuit)
+        .unwrap();
+    let trajectories = &trajectory_registers["density"];
+    assert_eq!(trajectories.len(), 4000);
+    for (index, exact_value) in exact.iter().enumerate() {
+        let average: Complex64 = trajectories
+            .iter()
+            .map(|trajectory| trajectory[index])
+            .sum::<Complex64>()
+            / 4000.0;
+        assert!((average - exact_value).norm() < 0.03);
+    }
+}
+
+/// Test that every repeated measurement is sampled from an independent trajectory
+#[test]
+fn test_repeated_measurement() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += operations::PauliX::new(0);
+    circuit += operations::PragmaDamping::new(0, 1.0.into(), 2.0_f64.ln().into());
+    circuit += operations::CNOT::new(0, 1);
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 4000, None);
+
+    let backend = QuantumTrajectorySimulator::new(Some(5));
+    let (bit_registers, _, _) = backend.run_circuit(&circuit).unwrap();
+    let records = &bit_registers["ro"];
+    assert_eq!(records.len(), 4000);
+    for record in records.iter() {
+        assert_eq!(record[0], record[1]);
+    }
+    let excited = records.iter().filter(|record| record[0]).count() as f64 / 4000.0;
+    assert!((excited - 0.5).abs() < 0.05);
+}
+
+/// Test that a seeded simulation is reproducible
+#[test]
+fn test_seed_reproducibility() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionBit::new("ro".to_string(), 3, true);
+    for qubit in 0..3 {
+        circuit += operations::Hadamard::new(qubit);
+        circuit += operations::PragmaDepolarising::new(qubit, 1.0.into(), 0.3.into());
+        circuit += operations::PragmaDamping::new(qubit, 1.0.into(), 0.3.into());
+    }
+    circuit += operations::PragmaRepeatedMeasurement::new("ro".to_string(), 200, None);
+
+    let (first, _, _) = QuantumTrajectorySimulator::new(Some(3))
+        .run_circuit(&circuit)
+        .unwrap();
+    let (second, _, _) = QuantumTrajectorySimulator::new(Some(3))
+        .run_circuit(&circuit)
+        .unwrap();
+    let (other, _, _) = QuantumTrajectorySimulator::new(Some(4))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+/// Test that every trajectory stays a normalised pure state
+#[test]
+fn test_trajectories_are_normalised() {
+    let mut circuit = Circuit::new();
+    circuit += operations::DefinitionComplex::new("state".to_string(), 1 << 10, true);
+    circuit += operations::DefinitionFloat::new("zz".to_string(), 1, true);
+    for qubit in 0..10 {
+        circuit += operations::Hadamard::new(qubit);
+        circuit += operations::PragmaRandomNoise::new(qubit, 1.0.into(), 0.2.into(), 0.1.into());
+    }
+    for qubit in 0..9 {
+        circuit += operations::CNOT::new(qubit, qubit + 1);
+        circuit += operations::PragmaDamping::new(qubit + 1, 1.0.into(), 0.2.into());
+    }
+    circuit += operations::PragmaGetStateVector::new("state".to_string(), None);
+    circuit += operations::PragmaGetPauliProduct::new(
+        HashMap::from([(0, 3), (9, 3)]),
+        "zz".to_string(),
+        Circuit::new(),
+    );
+    circuit += operations::PragmaSetNumberOfMeasurements::new(10, "state".to_string());
+
+    let (_, float_registers, complex_registers) = QuantumTrajectorySimulator::new(Some(2))
+        .run_circuit(&circuit)
+        .unwrap();
+    assert_eq!(complex_registers["state"].len(), 10);
+    for state in complex_registers["state"].iter() {
+        let norm: f64 = state.iter().map(|amplitude| amplitude.norm_sqr()).sum();
+        assert!((norm - 1.0).abs() < 1e-10);
+    }
+    assert_eq!(float_registers["zz"].len(), 10);
+}
+
+/// Test the error for unsupported operations
+#[test]
+fn test_unsupported_operation() {
+    let mut circuit = Circuit::new();
+    circuit += operations::PragmaOverrotation::new("RotateX".to_string(), vec![0], 0.1, 0.1);
+    let result = QuantumTrajectorySimulator::new(None).run_circuit(&circuit);
+    assert_eq!(
+        result,
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "QuantumTrajectorySimulator",
+            hqslang: "PragmaOverrotation"
+        })
+    );
+}
@@ -0,0 +1,1569 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration tests for the transpiler passes.
+
+use qoqo_calculator::{Calculator, CalculatorFloat};
+use roqoqo::devices::{AllToAllDevice, Device, GenericDevice, SquareLatticeDevice};
+use roqoqo::noise_models::{
+    ContinuousDecoherenceModel, DecoherenceOnGateModel, DecoherenceOnIdleModel,
+    ImperfectReadoutModel, NoiseModel, SingleQubitOverrotationDescription,
+    SingleQubitOverrotationOnGate,
+};
+use roqoqo::operations::*;
+use roqoqo::transpiler::{
+    apply_noise_models, cancel_inverse_gates, decompose_to_device, fuse_single_qubit_gates,
+    insert_dynamical_decoupling, merge_rotations, route_to_device, schedule_circuit, select_layout,
+    DecoherenceLayoutCost, DecouplingPulse, DecouplingSequence, LayoutCost, OptimizationReport,
+    SchedulingPolicy,
+};
+use roqoqo::Circuit;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use struqture::prelude::*;
+use struqture::spins::{PlusMinusLindbladNoiseOperator, PlusMinusProduct};
+use test_case::test_case;
+
+/// Returns an all-to-all device with the given gate names.
+fn device(single_qubit_gates: &[&str], two_qubit_gates: &[&str]) -> AllToAllDevice {
+    let single_qubit_gates: Vec<String> = single_qubit_gates
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let two_qubit_gates: Vec<String> = two_qubit_gates
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    AllToAllDevice::new(4, &single_qubit_gates, &two_qubit_gates, 1.0)
+}
+
+/// Asserts that a circuit only contains the given gates and is equivalent to another circuit.
+fn assert_decomposed(decomposed: &Circuit, circuit: &Circuit, gates: &[&str]) {
+    for operation in decomposed.iter() {
+        assert!(
+            gates.contains(&operation.hqslang()),
+            "{} is not a gate of the device",
+            operation.hqslang()
+        );
+    }
+    assert!(decomposed.is_equivalent(circuit, 1e-9, true).unwrap());
+}
+
+/// Returns a circuit with all single-qubit gates.
+fn single_qubit_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit += SingleQubitGate::new(
+        0,
+        0.5.into(),
+        0.5.into(),
+        0.5.into(),
+        (-0.5).into(),
+        0.3.into(),
+    );
+    circuit += RotateZ::new(0, 0.3.into());
+    circuit += RotateX::new(0, (-1.2).into());
+    circuit += RotateY::new(0, 2.5.into());
+    circuit += PauliX::new(0);
+    circuit += PauliY::new(0);
+    circuit += PauliZ::new(0);
+    circuit += SqrtPauliX::new(0);
+    circuit += InvSqrtPauliX::new(0);
+    circuit += Hadamard::new(0);
+    circuit += SGate::new(0);
+    circuit += TGate::new(0);
+    circuit += PhaseShiftState1::new(0, 0.7.into());
+    circuit += PhaseShiftState0::new(0, (-0.4).into());
+    circuit += RotateAroundSphericalAxis::new(0, 1.1.into(), 0.6.into(), 2.3.into());
+    circuit += RotateXY::new(0, 0.9.into(), (-2.1).into());
+    circuit += GPi::new(0, 0.8.into());
+    circuit += GPi2::new(0, 1.9.into());
+    circuit += Identity::new(0);
+    circuit += SqrtPauliY::new(0);
+    circuit += InvSqrtPauliY::new(0);
+    circuit += InvSGate::new(0);
+    circuit += InvTGate::new(0);
+    circuit += SXGate::new(0);
+    circuit += InvSXGate::new(0);
+    circuit
+}
+
+#[test_case(&["RotateZ", "RotateY"]; "zy")]
+#[test_case(&["RotateZ", "RotateX"]; "zx")]
+#[test_case(&["PhaseShiftState1", "RotateX"]; "phase_shift_1_x")]
+#[test_case(&["PhaseShiftState0", "RotateY"]; "phase_shift_0_y")]
+#[test_case(&["RotateX", "RotateY"]; "xy")]
+#[test_case(&["RotateZ", "RotateXY"]; "z_rotate_xy")]
+#[test_case(&["RotateXY"]; "rotate_xy")]
+#[test_case(&["SingleQubitGate"]; "single_qubit_gate")]
+fn test_decompose_single_qubit_gates(gates: &[&str]) {
+    let circuit = single_qubit_circuit();
+    let decomposed = decompose_to_device(&circuit, &device(gates, &["CNOT"])).unwrap();
+    assert_decomposed(&decomposed, &circuit, gates);
+    for operation in circuit.iter() {
+        let mut single_gate = Circuit::new();
+        single_gate.add_operation(operation.clone());
+        let decomposed = decompose_to_device(&single_gate, &device(gates, &["CNOT"])).unwrap();
+        assert_decomposed(&decomposed, &single_gate, gates);
+    }
+}
+
+/// Returns the two-qubit gates on a control and target qubit.
+fn two_qubit_gates(control: usize, target: usize) -> Vec<Operation> {
+    vec![
+        CNOT::new(control, target).into(),
+        SWAP::new(control, target).into(),
+        ISwap::new(control, target).into(),
+        FSwap::new(control, target).into(),
+        SqrtISwap::new(control, target).into(),
+        InvSqrtISwap::new(control, target).into(),
+        XY::new(control, target, 0.7.into()).into(),
+        ControlledPhaseShift::new(control, target, 1.3.into()).into(),
+        ControlledPauliY::new(control, target).into(),
+        ControlledPauliZ::new(control, target).into(),
+        MolmerSorensenXX::new(control, target).into(),
+        VariableMSXX::new(control, target, (-0.6).into()).into(),
+        GivensRotation::new(control, target, 0.4.into(), 1.2.into()).into(),
+        GivensRotationLittleEndian::new(control, target, 0.4.into(), 1.2.into()).into(),
+        Qsim::new(control, target, 0.3.into(), 0.2.into(), (-0.5).into()).into(),
+        Fsim::new(control, target, 0.3.into(), 0.2.into(), 0.6.into()).into(),
+        SpinInteraction::new(control, target, 0.3.into(), 0.2.into(), (-0.5).into()).into(),
+        Bogoliubov::new(control, target, 0.3.into(), 0.8.into()).into(),
+        PMInteraction::new(control, target, 0.9.into()).into(),
+        ComplexPMInteraction::new(control, target, 0.9.into(), (-0.2).into()).into(),
+        PhaseShiftedControlledZ::new(control, target, 0.5.into()).into(),
+        PhaseShiftedControlledPhase::new(control, target, 1.1.into(), 0.5.into()).into(),
+        ControlledRotateX::new(control, target, 0.8.into()).into(),
+        ControlledRotateXY::new(control, target, 0.8.into(), (-1.4).into()).into(),
+        EchoCrossResonance::new(control, target).into(),
+    ]
+}
+
+#[test_case(&["RotateZ", "RotateY"], "CNOT"; "cnot")]
+#[test_case(&["RotateZ", "RotateX"], "ControlledPauliZ"; "controlled_pauli_z")]
+#[test_case(&["RotateZ", "RotateX"], "ControlledPhaseShift"; "controlled_phase_shift")]
+#[test_case(&["RotateZ", "RotateXY"], "VariableMSXX"; "variable_msxx")]
+#[test_case(&["RotateXY"], "MolmerSorensenXX"; "molmer_sorensen_xx")]
+#[test_case(&["RotateX", "RotateY"], "ControlledPauliY"; "controlled_pauli_y")]
+#[test_case(&["PhaseShiftState1", "RotateY"], "ISwap"; "iswap")]
+fn test_decompose_two_qubit_gates(single_qubit_gates: &[&str], entangler: &str) {
+    let mut gates = single_qubit_gates.to_vec();
+    gates.push(entangler);
+    let device = device(single_qubit_gates, &[entangler]);
+    for (control, target) in [(0, 1), (2, 0)] {
+        for gate in two_qubit_gates(control, target) {
+            let mut circuit = Circuit::new();
+            circuit += Hadamard::new(0);
+            circuit.add_operation(gate.clone());
+            let decomposed = decompose_to_device(&circuit, &device).unwrap();
+            for operation in decomposed.iter() {
+                assert!(
+                    gates.contains(&operation.hqslang()),
+                    "{} is not a gate of the device decomposing {}",
+                    operation.hqslang(),
+                    gate.hqslang()
+                );
+            }
+            assert!(
+                decomposed.is_equivalent(&circuit, 1e-9, true).unwrap(),
+                "Decomposition of {} with {} is not equivalent",
+                gate.hqslang(),
+                entangler
+            );
+        }
+    }
+}
+
+#[test]
+fn test_decompose_multi_qubit_gates() {
+    let gates = ["RotateZ", "RotateY", "CNOT"];
+    let device = device(&["RotateZ", "RotateY"], &["CNOT"]);
+    let multi_qubit_gates: Vec<Operation> = vec![
+        ControlledControlledPauliZ::new(0, 1, 2).into(),
+        ControlledControlledPhaseShift::new(2, 0, 1, 0.7.into()).into(),
+        Toffoli::new(1, 2, 0).into(),
+        ControlledSWAP::new(0, 2, 1).into(),
+        MultiQubitMS::new(vec![0, 1, 2], 0.6.into()).into(),
+        MultiQubitCNOT::new(vec![0, 1, 2]).into(),
+        MultiQubitZZ::new(vec![0, 1, 2], (-0.9).into()).into(),
+        MultiQubitMS::new(vec![2, 0, 3], 0.6.into()).into(),
+        MultiQubitZZ::new(vec![2, 0, 1], (-0.9).into()).into(),
+        QFT::new(vec![0, 1, 2], true, false).into(),
+    ];
+    for gate in multi_qubit_gates {
+        let mut circuit = Circuit::new();
+        circuit += Hadamard::new(0);
+        circuit += Hadamard::new(3);
+        circuit.add_operation(gate.clone());
+        let decomposed = decompose_to_device(&circuit, &device).unwrap();
+        assert_decomposed(&decomposed, &circuit, &gates);
+    }
+}
+
+#[test]
+fn test_decompose_keeps_native_gates() {
+    let mut device = GenericDevice::new(3);
+    device
+        .set_single_qubit_gate_time("Hadamard", 0, 1.0)
+        .unwrap();
+    device
+        .set_two_qubit_gate_time("ControlledPauliZ", 0, 1, 1.0)
+        .unwrap();
+    device
+        .set_three_qubit_gate_time("Toffoli", 0, 1, 2, 1.0)
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += Hadamard::new(0);
+    circuit += ControlledPauliZ::new(0, 1);
+    circuit += Toffoli::new(0, 1, 2);
+    circuit += PragmaSleep::new(vec![0, 1], 1.0.into());
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 100, None);
+    assert_eq!(decompose_to_device(&circuit, &device).unwrap(), circuit);
+}
+
+#[test]
+fn test_decompose_nested_circuits() {
+    let device = device(&["RotateZ", "RotateX"], &["CNOT"]);
+    let mut inner = Circuit::new();
+    inner += Hadamard::new(0);
+    inner += ControlledPauliZ::new(0, 1);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += PragmaLoop::new(3.into(), inner.clone());
+    circuit += PragmaConditional::new("ro".to_string(), 0, inner.clone());
+    let decomposed = decompose_to_device(&circuit, &device).unwrap();
+    let decomposed_inner = decompose_to_device(&inner, &device).unwrap();
+    assert_decomposed(&decomposed_inner, &inner, &["RotateZ", "RotateX", "CNOT"]);
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("ro".to_string(), 1, true);
+    expected += PragmaLoop::new(3.into(), decomposed_inner.clone());
+    expected += PragmaConditional::new("ro".to_string(), 0, decomposed_inner);
+    assert_eq!(decomposed, expected);
+}
+
+#[test]
+fn test_decompose_controlled_circuit() {
+    let device = device(&["RotateZ", "RotateX"], &["CNOT"]);
+    let mut inner = Circuit::new();
+    inner += RotateZ::new(1, 0.4.into());
+    inner += CNOT::new(1, 2);
+    let mut circuit = Circuit::new();
+    circuit += PragmaControlledCircuit::new(0, inner.clone());
+    assert_eq!(decompose_to_device(&circuit, &device).unwrap(), circuit);
+
+    inner += Hadamard::new(1);
+    let mut circuit = Circuit::new();
+    circuit += PragmaControlledCircuit::new(0, inner);
+    assert!(decompose_to_device(&circuit, &device).is_err());
+}
+
+#[test_case(RotateX::new(0, "theta".into()).into(), &["RotateZ", "RotateY"]; "rotate_x_zy")]
+#[test_case(RotateZ::new(0, "theta".into()).into(), &["RotateX", "RotateY"]; "rotate_z_xy")]
+#[test_case(RotateY::new(0, "theta".into()).into(), &["RotateXY"]; "rotate_y_rotate_xy")]
+#[test_case(PhaseShiftState0::new(0, "theta".into()).into(), &["PhaseShiftState1", "RotateX"]; "phase_shift")]
+#[test_case(RotateAroundSphericalAxis::new(0, "theta".into(), 0.4.into(), 1.3.into()).into(), &["RotateZ", "RotateX"]; "spherical")]
+#[test_case(ControlledPhaseShift::new(0, 1, "theta".into()).into(), &["RotateZ", "RotateY", "CNOT"]; "controlled_phase_shift")]
+#[test_case(XY::new(0, 1, "theta".into()).into(), &["RotateZ", "RotateY", "VariableMSXX"]; "xy_variable_msxx")]
+#[test_case(VariableMSXX::new(0, 1, "theta".into()).into(), &["RotateZ", "RotateY", "ControlledPhaseShift"]; "variable_msxx_controlled_phase_shift")]
+fn test_decompose_symbolic(gate: Operation, gates: &[&str]) {
+    let single_qubit_gates: Vec<&str> = gates
+        .iter()
+        .filter(|name| name.starts_with("Rotate") || name.starts_with("Phase"))
+        .copied()
+        .collect();
+    let two_qubit_gates: Vec<&str> = gates
+        .iter()
+        .filter(|name| !single_qubit_gates.contains(name))
+        .copied()
+        .collect();
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit.add_operation(gate);
+    let decomposed =
+        decompose_to_device(&circuit, &device(&single_qubit_gates, &two_qubit_gates)).unwrap();
+    assert!(decomposed
+        .iter()
+        .any(|operation| operation.is_parametrized()));
+    let mut calculator = Calculator::new();
+    calculator.set_variable("theta", 0.77);
+    let substituted = circuit.substitute_parameters(&calculator).unwrap();
+    let decomposed = decomposed.substitute_parameters(&calculator).unwrap();
+    assert_decomposed(&decomposed, &substituted, gates);
+}
+
+#[test]
+fn test_decompose_skips_trivial_rotations() {
+    let device = device(&["RotateZ", "RotateY"], &["CNOT"]);
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, (2.0 * PI).into());
+    circuit += PauliZ::new(1);
+    let decomposed = decompose_to_device(&circuit, &device).unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(1, CalculatorFloat::PI);
+    assert_eq!(decomposed, expected);
+}
+
+#[test]
+fn test_decompose_errors() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    assert!(decompose_to_device(&circuit, &device(&["RotateZ"], &["CNOT"])).is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += SWAP::new(0, 1);
+    assert!(decompose_to_device(&circuit, &device(&["RotateZ", "RotateX"], &[])).is_err());
+    assert!(decompose_to_device(&circuit, &device(&["RotateZ", "RotateX"], &["SWAP"])).is_ok());
+
+    let mut circuit = Circuit::new();
+    circuit += GPi::new(0, "phi".into());
+    assert!(decompose_to_device(&circuit, &device(&["RotateZ", "RotateX"], &["CNOT"])).is_err());
+    assert!(decompose_to_device(&circuit, &device(&["SingleQubitGate"], &["CNOT"])).is_ok());
+}
+
+/// Asserts that all two-qubit gates of a routed circuit act on connected qubits and that
+/// the routed circuit followed by the inverse of the final permutation is equivalent to the circuit.
+fn assert_routed(
+    routed: &Circuit,
+    permutation: &HashMap<usize, usize>,
+    circuit: &Circuit,
+    device: &dyn Device,
+) {
+    let edges = device.two_qubit_edges();
+    for operation in routed.iter() {
+        if let Ok(gate) = TwoQubitGateOperation::try_from(operation) {
+            let (control, target) = (*gate.control(), *gate.target());
+            assert!(
+                edges.contains(&(control, target)) || edges.contains(&(target, control)),
+                "{} acts on qubits {control} and {target} that are not connected",
+                operation.hqslang()
+            );
+        }
+    }
+    let mut layout: Vec<usize> = (0..device.number_qubits())
+        .map(|qubit| permutation[&qubit])
+        .collect();
+    let mut restored = routed.clone();
+    for qubit in 0..layout.len() {
+        if layout[qubit] != qubit {
+            let other = layout
+                .iter()
+                .position(|physical| *physical == qubit)
+                .unwrap();
+            restored += SWAP::new(layout[qubit], qubit);
+            layout.swap(qubit, other);
+        }
+    }
+    assert!(restored.is_equivalent(circuit, 1e-9, false).unwrap());
+}
+
+#[test]
+fn test_route_keeps_connected_gates() {
+    let device = SquareLatticeDevice::new(2, 2, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += CNOT::new(2, 0);
+    circuit += ControlledPauliZ::new(3, 2);
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    assert_eq!(routed, circuit);
+    assert_eq!(permutation, (0..4).map(|qubit| (qubit, qubit)).collect());
+}
+
+#[test_case(SquareLatticeDevice::new(1, 5, &[], &["CNOT".to_string()], 1.0); "line")]
+#[test_case(SquareLatticeDevice::new(3, 3, &[], &["CNOT".to_string()], 1.0); "lattice")]
+fn test_route_inserts_swaps(device: SquareLatticeDevice) {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 4);
+    circuit += RotateX::new(4, 0.3.into());
+    circuit += ControlledPhaseShift::new(3, 1, 0.7.into());
+    circuit += CNOT::new(2, 0);
+    circuit += ISwap::new(4, 1);
+    circuit += RotateY::new(2, 1.1.into());
+    circuit += CNOT::new(0, 3);
+    circuit += XY::new(2, 4, 0.4.into());
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    assert!(routed.iter().any(|operation| operation.hqslang() == "SWAP"));
+    assert_routed(&routed, &permutation, &circuit, &device);
+}
+
+#[test]
+fn test_route_remaps_measurements() {
+    let device = SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += CNOT::new(0, 2);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("ro".to_string(), 3, true);
+    expected += SWAP::new(0, 1);
+    expected += CNOT::new(1, 2);
+    expected += MeasureQubit::new(1, "ro".to_string(), 0);
+    assert_eq!(routed, expected);
+    assert_eq!(permutation, HashMap::from([(0, 1), (1, 0), (2, 2)]));
+}
+
+#[cfg(feature = "simulators")]
+#[test_case(None; "no qubit mapping")]
+#[test_case(Some(HashMap::from([(0, 3), (2, 0), (3, 1)])); "qubit mapping")]
+fn test_route_repeated_measurement_readout(qubit_mapping: Option<HashMap<usize, usize>>) {
+    use roqoqo::backends::EvaluatingBackend;
+    use roqoqo::simulators::StatevectorSimulator;
+
+    let device = SquareLatticeDevice::new(1, 4, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 4, true);
+    circuit += PauliX::new(1);
+    circuit += PauliX::new(2);
+    circuit += CNOT::new(0, 3);
+    circuit += CNOT::new(3, 1);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 3, qubit_mapping);
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    // The final permutation is not its own inverse
+    assert!(permutation
+        .iter()
+        .any(|(qubit, physical)| permutation[physical] != *qubit));
+
+    let backend = StatevectorSimulator::new(Some(1));
+    let (bits, _, _) = backend.run_circuit(&circuit).unwrap();
+    let (routed_bits, _, _) = backend.run_circuit(&routed).unwrap();
+    assert_eq!(routed_bits["ro"], bits["ro"]);
+}
+
+#[test]
+fn test_route_repeated_measurement_larger_device() {
+    let device = SquareLatticeDevice::new(1, 6, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += PauliX::new(1);
+    circuit += CNOT::new(0, 2);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 3, None);
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    let measurement = routed
+        .iter()
+        .find_map(|operation| match operation {
+            Operation::PragmaRepeatedMeasurement(measurement) => Some(measurement.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let qubit_mapping = measurement.qubit_mapping().clone().unwrap();
+    assert_eq!(
+        qubit_mapping,
+        (0..3).map(|qubit| (permutation[&qubit], qubit)).collect()
+    );
+
+    #[cfg(feature = "simulators")]
+    {
+        use roqoqo::backends::EvaluatingBackend;
+        use roqoqo::simulators::StatevectorSimulator;
+
+        let backend = StatevectorSimulator::new(Some(1));
+        let (bits, _, _) = backend.run_circuit(&circuit).unwrap();
+        let (routed_bits, _, _) = backend.run_circuit(&routed).unwrap();
+        assert_eq!(routed_bits["ro"], bits["ro"]);
+    }
+}
+
+#[test]
+fn test_route_decomposition_block() {
+    let device = SquareLatticeDevice::new(1, 4, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(2, 3);
+    circuit += PragmaStartDecompositionBlock::new(vec![0, 1, 2], HashMap::new());
+    circuit += CNOT::new(0, 2);
+    circuit += CNOT::new(1, 2);
+    circuit += RotateZ::new(2, 0.5.into());
+    circuit += CNOT::new(0, 2);
+    circuit += PragmaStopDecompositionBlock::new(vec![0, 1, 2]);
+    circuit += CNOT::new(0, 3);
+    let (routed, permutation) = route_to_device(&circuit, &device).unwrap();
+    assert_routed(&routed, &permutation, &circuit, &device);
+
+    let operations: Vec<&str> = routed.iter().map(|operation| operation.hqslang()).collect();
+    let start = operations
+        .iter()
+        .position(|name| *name == "PragmaStartDecompositionBlock")
+        .unwrap();
+    let stop = operations
+        .iter()
+        .position(|name| *name == "PragmaStopDecompositionBlock")
+        .unwrap();
+    assert_eq!(stop - start, 5);
+    assert!(!operations[start..stop].contains(&"SWAP"));
+}
+
+#[test]
+fn test_route_errors() {
+    let device = SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0);
+
+    let mut circuit = Circuit::new();
+    circuit += Toffoli::new(0, 1, 2);
+    assert!(route_to_device(&circuit, &device).is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 3);
+    assert!(route_to_device(&circuit, &device).is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += PragmaStartDecompositionBlock::new(vec![0, 1, 2], HashMap::new());
+    circuit += CNOT::new(0, 1);
+    circuit += CNOT::new(1, 2);
+    circuit += CNOT::new(0, 2);
+    circuit += PragmaStopDecompositionBlock::new(vec![0, 1, 2]);
+    assert!(route_to_device(&circuit, &device).is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 1);
+    let mut device = GenericDevice::new(2);
+    device
+        .set_single_qubit_gate_time("RotateZ", 0, 1.0)
+        .unwrap();
+    assert!(route_to_device(&circuit, &device).is_err());
+}
+
+#[test]
+fn test_select_layout_connectivity() {
+    let device = SquareLatticeDevice::new(1, 5, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 4);
+    circuit += CNOT::new(1, 3);
+    circuit += CNOT::new(4, 0);
+    circuit += CNOT::new(3, 4);
+    let layout = select_layout(&circuit, &device, &DecoherenceLayoutCost).unwrap();
+    let remapped = circuit.remap_qubits(&layout).unwrap();
+    let (routed, _) = route_to_device(&remapped, &device).unwrap();
+    assert_eq!(routed, remapped);
+}
+
+#[test]
+fn test_select_layout_decoherence() {
+    let mut device = GenericDevice::new(3);
+    for qubit in 0..3 {
+        device
+            .set_single_qubit_gate_time("RotateX", qubit, 1.0)
+            .unwrap();
+        device
+            .add_damping(qubit, if qubit == 1 { 0.01 } else { 0.1 })
+            .unwrap();
+        for other in qubit + 1..3 {
+            device
+                .set_two_qubit_gate_time("CNOT", qubit, other, 2.0)
+                .unwrap();
+        }
+    }
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.5.into());
+    circuit += RotateX::new(0, 0.7.into());
+    let layout = select_layout(&circuit, &device, &DecoherenceLayoutCost).unwrap();
+    assert_eq!(layout[&0], 1);
+    assert!(
+        DecoherenceLayoutCost.layout_cost(&circuit, &device, &HashMap::from([(0, 1)]))
+            < DecoherenceLayoutCost.layout_cost(&circuit, &device, &HashMap::from([(0, 0)]))
+    );
+}
+
+/// A layout cost preferring device qubit 2 for qubit 0 of the circuit.
+struct PreferQubit;
+
+impl LayoutCost for PreferQubit {
+    fn layout_cost(
+        &self,
+        _circuit: &Circuit,
+        _device: &dyn Device,
+        layout: &HashMap<usize, usize>,
+    ) -> f64 {
+        (layout[&0] as f64 - 2.0).abs()
+    }
+}
+
+#[test]
+fn test_select_layout_custom_cost() {
+    let device = SquareLatticeDevice::new(1, 4, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    let layout = select_layout(&circuit, &device, &PreferQubit).unwrap();
+    assert_eq!(layout[&0], 2);
+}
+
+#[test]
+fn test_select_layout_permutation() {
+    let device = SquareLatticeDevice::new(1, 3, &[], &["CNOT".to_string()], 1.0);
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(5, 1);
+    circuit += PauliX::new(6);
+    let layout = select_layout(&circuit, &device, &DecoherenceLayoutCost).unwrap();
+    let remapped = circuit.remap_qubits(&layout).unwrap();
+    assert!(remapped
+        .iter()
+        .all(|operation| match operation.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.iter().all(|qubit| *qubit < 3),
+            _ => false,
+        }));
+
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 3);
+    circuit += PauliX::new(1);
+    circuit += PauliX::new(2);
+    assert!(select_layout(&circuit, &device, &DecoherenceLayoutCost).is_err());
+}
+
+#[test]
+fn test_cancel_inverse_gates() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += SGate::new(0);
+    circuit += InvSGate::new(0);
+    circuit += Hadamard::new(0);
+    circuit += ISwap::new(0, 1);
+    circuit += XY::new(0, 1, CalculatorFloat::PI * (-1.0));
+    circuit += CNOT::new(1, 2);
+    circuit += PauliX::new(2);
+    circuit += CNOT::new(1, 2);
+    circuit += TGate::new(1);
+    circuit += CNOT::new(2, 1);
+    circuit += CNOT::new(2, 1);
+    circuit += InvTGate::new(1);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+    let (optimised, report) = cancel_inverse_gates(&circuit);
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("ro".to_string(), 2, true);
+    expected += CNOT::new(1, 2);
+    expected += PauliX::new(2);
+    expected += CNOT::new(1, 2);
+    expected += MeasureQubit::new(1, "ro".to_string(), 1);
+    assert_eq!(optimised, expected);
+    assert_eq!(report.removed_operations, 10);
+    assert_eq!(
+        report.cancelled_pairs,
+        vec![(1, 4), (2, 3), (5, 6), (10, 13), (11, 12)]
+    );
+    assert!(report.merged_rotations.is_empty());
+    assert!(report.fused_runs.is_empty());
+}
+
+#[test]
+fn test_cancel_inverse_gates_barriers() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += PragmaStopParallelBlock::new(vec![0], 0.0.into());
+    circuit += Hadamard::new(0);
+    circuit += SqrtPauliX::new(1);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    circuit += InvSqrtPauliX::new(1);
+    circuit += CNOT::new(0, 1);
+    circuit += CNOT::new(1, 0);
+    let (optimised, report) = cancel_inverse_gates(&circuit);
+    assert_eq!(optimised, circuit);
+    assert_eq!(report, OptimizationReport::default());
+}
+
+#[test]
+fn test_merge_rotations() {
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.3.into());
+    circuit += RotateX::new(0, 0.4.into());
+    circuit += RotateZ::new(1, "theta".into());
+    circuit += CNOT::new(0, 2);
+    circuit += RotateZ::new(1, 0.5.into());
+    circuit += ControlledPhaseShift::new(0, 2, 0.2.into());
+    circuit += ControlledPhaseShift::new(0, 2, 0.1.into());
+    circuit += ControlledPhaseShift::new(2, 0, 0.1.into());
+    circuit += RotateXY::new(2, 0.2.into(), 0.5.into());
+    circuit += RotateXY::new(2, 0.3.into(), 0.5.into());
+    circuit += RotateXY::new(2, 0.3.into(), 0.6.into());
+    let (optimised, report) = merge_rotations(&circuit);
+    let mut expected = Circuit::new();
+    expected += RotateX::new(0, 0.7.into());
+    expected += RotateZ::new(1, CalculatorFloat::from("theta") + 0.5);
+    expected += CNOT::new(0, 2);
+    expected += ControlledPhaseShift::new(0, 2, 0.30000000000000004.into());
+    expected += ControlledPhaseShift::new(2, 0, 0.1.into());
+    expected += RotateXY::new(2, 0.5.into(), 0.5.into());
+    expected += RotateXY::new(2, 0.3.into(), 0.6.into());
+    assert_eq!(optimised, expected);
+    assert_eq!(report.removed_operations, 4);
+    assert_eq!(
+        report.merged_rotations,
+        vec![vec![0, 1], vec![2, 4], vec![5, 6], vec![8, 9]]
+    );
+    let mut substituted = Calculator::new();
+    substituted.set_variable("theta", 0.2);
+    assert!(optimised
+        .substitute_parameters(&substituted)
+        .unwrap()
+        .is_equivalent(
+            &circuit.substitute_parameters(&substituted).unwrap(),
+            1e-10,
+            false
+        )
+        .unwrap());
+}
+
+#[test_case(RotateX::new(0, 0.3.into()).into(), RotateX::new(0, 0.4.into()).into(); "rotate_x")]
+#[test_case(RotateY::new(0, 0.3.into()).into(), RotateY::new(0, 0.4.into()).into(); "rotate_y")]
+#[test_case(RotateZ::new(0, 0.3.into()).into(), RotateZ::new(0, 0.4.into()).into(); "rotate_z")]
+#[test_case(PhaseShiftState0::new(0, 0.3.into()).into(), PhaseShiftState0::new(0, 0.4.into()).into(); "phase_shift_0")]
+#[test_case(PhaseShiftState1::new(0, 0.3.into()).into(), PhaseShiftState1::new(0, 0.4.into()).into(); "phase_shift_1")]
+#[test_case(RotateXY::new(0, 0.3.into(), 0.2.into()).into(), RotateXY::new(0, 0.4.into(), 0.2.into()).into(); "rotate_xy")]
+#[test_case(ControlledPhaseShift::new(0, 1, 0.3.into()).into(), ControlledPhaseShift::new(0, 1, 0.4.into()).into(); "controlled_phase_shift")]
+#[test_case(ControlledRotateX::new(0, 1, 0.3.into()).into(), ControlledRotateX::new(0, 1, 0.4.into()).into(); "controlled_rotate_x")]
+#[test_case(XY::new(0, 1, 0.3.into()).into(), XY::new(0, 1, 0.4.into()).into(); "xy")]
+#[test_case(VariableMSXX::new(0, 1, 0.3.into()).into(), VariableMSXX::new(0, 1, 0.4.into()).into(); "variable_msxx")]
+#[test_case(PMInteraction::new(0, 1, 0.3.into()).into(), PMInteraction::new(0, 1, 0.4.into()).into(); "pm_interaction")]
+fn test_merge_rotations_equivalent(first: Operation, second: Operation) {
+    let mut circuit = Circuit::new();
+    circuit += first;
+    circuit += second;
+    let (optimised, report) = merge_rotations(&circuit);
+    assert_eq!(report.removed_operations, 1);
+    assert_eq!(optimised.len(), 1);
+    assert!(optimised.is_equivalent(&circuit, 1e-10, true).unwrap());
+}
+
+#[test]
+fn test_merge_rotations_removes_zero_rotations() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += RotateZ::new(0, 0.3.into());
+    circuit += RotateZ::new(0, (-0.3).into());
+    circuit += RotateZ::new(0, "theta".into());
+    circuit += RotateZ::new(0, CalculatorFloat::from("theta") * (-1.0));
+    let (optimised, report) = merge_rotations(&circuit);
+    let mut expected = Circuit::new();
+    expected += Hadamard::new(0);
+    expected += RotateZ::new(
+        0,
+        CalculatorFloat::from("theta") + CalculatorFloat::from("theta") * (-1.0),
+    );
+    assert_eq!(optimised, expected);
+    assert_eq!(report.removed_operations, 3);
+    assert_eq!(report.merged_rotations, vec![vec![1, 2], vec![3, 4]]);
+}
+
+#[test]
+fn test_fuse_single_qubit_gates() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += RotateX::new(0, 0.4.into());
+    circuit += SGate::new(0);
+    circuit += PauliY::new(1);
+    circuit += CNOT::new(0, 1);
+    circuit += TGate::new(0);
+    circuit += RotateZ::new(0, "theta".into());
+    circuit += Hadamard::new(0);
+    circuit += RotateY::new(1, 0.2.into());
+    let (optimised, report) = fuse_single_qubit_gates(&circuit);
+    assert_eq!(report.removed_operations, 2);
+    assert_eq!(report.fused_runs, vec![vec![0, 1, 2]]);
+    let names: Vec<&str> = optimised
+        .iter()
+        .map(|operation| operation.hqslang())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "SingleQubitGate",
+            "PauliY",
+            "CNOT",
+            "TGate",
+            "RotateZ",
+            "Hadamard",
+            "RotateY"
+        ]
+    );
+    let mut substituted = Calculator::new();
+    substituted.set_variable("theta", 0.7);
+    assert!(optimised
+        .substitute_parameters(&substituted)
+        .unwrap()
+        .is_equivalent(
+            &circuit.substitute_parameters(&substituted).unwrap(),
+            1e-10,
+            true
+        )
+        .unwrap());
+}
+
+#[test]
+fn test_optimisation_passes_combined() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += RotateZ::new(0, 0.2.into());
+    circuit += RotateZ::new(0, (-0.2).into());
+    circuit += Hadamard::new(0);
+    circuit += RotateX::new(1, 0.1.into());
+    circuit += SqrtPauliX::new(1);
+    circuit += CNOT::new(0, 1);
+    let (merged, merged_report) = merge_rotations(&circuit);
+    let (cancelled, cancelled_report) = cancel_inverse_gates(&merged);
+    let (fused, fused_report) = fuse_single_qubit_gates(&cancelled);
+    assert_eq!(
+        (
+            merged_report.removed_operations,
+            cancelled_report.removed_operations,
+            fused_report.removed_operations
+        ),
+        (2, 2, 1)
+    );
+    assert_eq!(merged_report.merged_rotations, vec![vec![1, 2]]);
+    assert_eq!(cancelled_report.cancelled_pairs, vec![(0, 1)]);
+    assert_eq!(fused_report.fused_runs, vec![vec![0, 1]]);
+    assert_eq!(fused.len(), 2);
+    assert!(fused.is_equivalent(&circuit, 1e-10, true).unwrap());
+}
+
+/// Returns an all-to-all device with single-qubit gate time 1 and two-qubit gate time 2.
+fn timed_device() -> AllToAllDevice {
+    AllToAllDevice::new(
+        3,
+        &["RotateX".to_string(), "RotateZ".to_string()],
+        &["CNOT".to_string()],
+        2.0,
+    )
+    .set_all_single_qubit_gate_times("RotateX", 1.0)
+    .set_all_single_qubit_gate_times("RotateZ", 1.0)
+}
+
+/// Returns the (start, end) times of all operations of a schedule.
+fn schedule_times(circuit: &Circuit, policy: SchedulingPolicy) -> Vec<(f64, f64)> {
+    schedule_circuit(circuit, &timed_device(), policy)
+        .unwrap()
+        .operations
+        .iter()
+        .map(|scheduled| (scheduled.start_time, scheduled.end_time))
+        .collect()
+}
+
+#[test]
+fn test_schedule_asap_alap() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += RotateZ::new(0, "theta".into());
+    circuit += RotateX::new(1, 0.2.into());
+    circuit += CNOT::new(0, 1);
+    circuit += RotateX::new(2, 0.3.into());
+    circuit += CNOT::new(1, 2);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    let schedule = schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    assert_eq!(schedule.duration, 6.0);
+    assert_eq!(schedule.operations.len(), circuit.len());
+    assert_eq!(
+        schedule.operations[4].operation,
+        Operation::from(CNOT::new(0, 1))
+    );
+    let times: Vec<(f64, f64)> = schedule
+        .operations
+        .iter()
+        .map(|scheduled| (scheduled.start_time, scheduled.end_time))
+        .collect();
+    assert_eq!(
+        times,
+        vec![
+            (0.0, 0.0),
+            (0.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 1.0),
+            (2.0, 4.0),
+            (0.0, 1.0),
+            (4.0, 6.0),
+            (4.0, 4.0)
+        ]
+    );
+    assert_eq!(schedule.idle_windows[&0], vec![(4.0, 6.0)]);
+    assert_eq!(schedule.idle_windows[&1], vec![(1.0, 2.0)]);
+    assert_eq!(schedule.idle_windows[&2], vec![(1.0, 4.0)]);
+
+    let schedule = schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsLateAsPossible,
+    )
+    .unwrap();
+    assert_eq!(schedule.duration, 6.0);
+    let times: Vec<(f64, f64)> = schedule
+        .operations
+        .iter()
+        .map(|scheduled| (scheduled.start_time, scheduled.end_time))
+        .collect();
+    assert_eq!(
+        times,
+        vec![
+            (6.0, 6.0),
+            (0.0, 1.0),
+            (1.0, 2.0),
+            (1.0, 2.0),
+            (2.0, 4.0),
+            (3.0, 4.0),
+            (4.0, 6.0),
+            (6.0, 6.0)
+        ]
+    );
+    assert_eq!(schedule.idle_windows[&0], vec![(4.0, 6.0)]);
+    assert_eq!(schedule.idle_windows[&1], vec![(0.0, 1.0)]);
+    assert_eq!(schedule.idle_windows[&2], vec![(0.0, 3.0)]);
+}
+
+#[test]
+fn test_schedule_barriers() {
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += CNOT::new(1, 2);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.5.into());
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += RotateX::new(2, 0.1.into());
+    circuit += PragmaSleep::new(vec![1], 1.5.into());
+    circuit += RotateZ::new(1, 0.1.into());
+    let times = schedule_times(&circuit, SchedulingPolicy::AsSoonAsPossible);
+    assert_eq!(
+        times,
+        vec![
+            (0.0, 1.0),
+            (0.0, 2.0),
+            (2.0, 2.5),
+            (2.5, 3.5),
+            (2.0, 3.0),
+            (2.5, 4.0),
+            (4.0, 5.0)
+        ]
+    );
+    let schedule = schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    assert_eq!(schedule.idle_windows[&0], vec![(1.0, 2.5), (3.5, 5.0)]);
+    assert_eq!(schedule.idle_windows[&1], vec![(2.0, 4.0)]);
+    assert_eq!(schedule.idle_windows[&2], vec![(3.0, 5.0)]);
+
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += CNOT::new(1, 2);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    circuit += RotateX::new(0, 0.1.into());
+    let times = schedule_times(&circuit, SchedulingPolicy::AsSoonAsPossible);
+    assert_eq!(times, vec![(0.0, 1.0), (0.0, 2.0), (2.0, 2.0), (2.0, 3.0)]);
+    let times = schedule_times(&circuit, SchedulingPolicy::AsLateAsPossible);
+    assert_eq!(times, vec![(1.0, 2.0), (0.0, 2.0), (2.0, 2.0), (2.0, 3.0)]);
+}
+
+#[test]
+fn test_schedule_multi_qubit_gates() {
+    let mut device = GenericDevice::new(4);
+    device.set_single_qubit_gate_time("PauliX", 0, 1.0).unwrap();
+    device
+        .set_three_qubit_gate_time("Toffoli", 0, 1, 2, 3.0)
+        .unwrap();
+    device
+        .set_multi_qubit_gate_time("MultiQubitZZ", vec![0, 1, 2, 3], 4.0)
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += Toffoli::new(0, 1, 2);
+    circuit += MultiQubitZZ::new(vec![0, 1, 2, 3], 0.1.into());
+    let schedule = schedule_circuit(&circuit, &device, SchedulingPolicy::AsSoonAsPossible).unwrap();
+    assert_eq!(schedule.duration, 8.0);
+    assert_eq!(schedule.operations[1].start_time, 1.0);
+    assert_eq!(schedule.operations[2].start_time, 4.0);
+    assert_eq!(schedule.idle_windows[&1], vec![(0.0, 1.0)]);
+    assert_eq!(schedule.idle_windows[&3], vec![(0.0, 4.0)]);
+}
+
+#[test]
+fn test_schedule_errors() {
+    let mut circuit = Circuit::new();
+    circuit += RotateY::new(0, 0.1.into());
+    assert!(schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsSoonAsPossible
+    )
+    .is_err());
+
+    let mut circuit = Circuit::new();
+    circuit += PragmaSleep::new(vec![0], "time".into());
+    assert!(schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsSoonAsPossible
+    )
+    .is_err());
+
+    let mut inner = Circuit::new();
+    inner += RotateX::new(0, 0.1.into());
+    let mut circuit = Circuit::new();
+    circuit += PragmaLoop::new(2.into(), inner);
+    assert!(schedule_circuit(
+        &circuit,
+        &timed_device(),
+        SchedulingPolicy::AsLateAsPossible
+    )
+    .is_err());
+}
+
+/// Returns the superoperator of the noise operations of a circuit applied one after another.
+fn noise_superoperator(circuit: &Circuit) -> ndarray::Array2<f64> {
+    circuit
+        .iter()
+        .filter_map(|operation| PragmaNoiseOperation::try_from(operation).ok())
+        .map(|noise| noise.superoperator().unwrap())
+        .fold(ndarray::Array2::eye(4), |product, superoperator| {
+            superoperator.dot(&product)
+        })
+}
+
+#[test]
+fn test_noise_continuous_decoherence() {
+    let noise_model = ContinuousDecoherenceModel::new().add_damping_rate(&[0, 1], 0.1);
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += RotateX::new(0, 0.2.into());
+    circuit += CNOT::new(0, 1);
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let mut expected = Circuit::new();
+    expected += RotateX::new(0, 0.1.into());
+    expected += PragmaDamping::new(0, 1.0.into(), 0.1.into());
+    expected += RotateX::new(0, 0.2.into());
+    expected += PragmaDamping::new(0, 1.0.into(), 0.1.into());
+    expected += PragmaDamping::new(1, 2.0.into(), 0.1.into());
+    expected += CNOT::new(0, 1);
+    expected += PragmaDamping::new(0, 2.0.into(), 0.1.into());
+    expected += PragmaDamping::new(1, 2.0.into(), 0.1.into());
+    assert_eq!(noisy_circuit, expected);
+}
+
+#[test_case(ContinuousDecoherenceModel::new().add_damping_rate(&[0], 0.1), &["PragmaDamping"]; "damping")]
+#[test_case(ContinuousDecoherenceModel::new().add_dephasing_rate(&[0], 0.1), &["PragmaDephasing"]; "dephasing")]
+#[test_case(ContinuousDecoherenceModel::new().add_depolarising_rate(&[0], 0.1), &["PragmaDepolarising"]; "depolarising")]
+#[test_case(ContinuousDecoherenceModel::new().add_excitation_rate(&[0], 0.1), &["PragmaGeneralNoise"]; "excitation")]
+#[test_case(
+    ContinuousDecoherenceModel::new().add_damping_rate(&[0], 0.1).add_dephasing_rate(&[0], 0.3),
+    &["PragmaDamping", "PragmaDephasing"];
+    "damping and dephasing"
+)]
+#[test_case(
+    ContinuousDecoherenceModel::new().add_depolarising_rate(&[0], 0.2).add_dephasing_rate(&[0], 0.3),
+    &["PragmaDepolarising", "PragmaDephasing"];
+    "depolarising and dephasing"
+)]
+#[test_case(
+    ContinuousDecoherenceModel::new().add_depolarising_rate(&[0], 0.2).add_damping_rate(&[0], 0.3),
+    &["PragmaGeneralNoise"];
+    "depolarising and damping"
+)]
+fn test_noise_operations(noise_model: ContinuousDecoherenceModel, expected: &[&str]) {
+    let mut rates = ndarray::Array2::<f64>::zeros((3, 3));
+    for ((left, _), value) in noise_model.lindblad_noise.iter() {
+        let index = match left.iter().next().unwrap().1 {
+            struqture::spins::SinglePlusMinusOperator::Plus => 0,
+            struqture::spins::SinglePlusMinusOperator::Minus => 1,
+            _ => 2,
+        };
+        rates[(index, index)] += f64::try_from(value.re.clone()).unwrap();
+    }
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.1.into());
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let names: Vec<&str> = noisy_circuit
+        .iter()
+        .skip(1)
+        .map(|operation| operation.hqslang())
+        .collect();
+    assert_eq!(names, expected);
+    let general_noise = PragmaGeneralNoise::new(0, 1.0.into(), rates)
+        .superoperator()
+        .unwrap();
+    for (value, expected_value) in noise_superoperator(&noisy_circuit)
+        .iter()
+        .zip(general_noise.iter())
+    {
+        assert!((value - expected_value).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_noise_on_gate() {
+    let mut lindblad_noise = PlusMinusLindbladNoiseOperator::new();
+    lindblad_noise
+        .add_operator_product(
+            (
+                PlusMinusProduct::new().minus(1),
+                PlusMinusProduct::new().minus(1),
+            ),
+            0.1.into(),
+        )
+        .unwrap();
+    let noise_model =
+        DecoherenceOnGateModel::new().set_two_qubit_gate_error("CNOT", 0, 1, lindblad_noise);
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 1);
+    circuit += CNOT::new(1, 0);
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let mut expected = Circuit::new();
+    expected += CNOT::new(0, 1);
+    expected += PragmaGeneralNoise::new(
+        1,
+        2.0.into(),
+        ndarray::array![[0.0, 0.0, 0.0], [0.0, 0.1, 0.0], [0.0, 0.0, 0.0]],
+    );
+    expected += CNOT::new(1, 0);
+    assert_eq!(noisy_circuit, expected);
+}
+
+#[test]
+fn test_noise_on_idle() {
+    let noise_model = DecoherenceOnIdleModel::new().add_dephasing_rate(&[0, 1], 0.2);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += RotateX::new(1, 0.2.into());
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+    circuit += CNOT::new(0, 2);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let mut expected = Circuit::new();
+    expected += DefinitionBit::new("ro".to_string(), 2, true);
+    expected += RotateX::new(0, 0.1.into());
+    expected += RotateX::new(1, 0.2.into());
+    expected += MeasureQubit::new(1, "ro".to_string(), 1);
+    expected += CNOT::new(0, 2);
+    expected += PragmaDephasing::new(1, 2.0.into(), 0.1.into());
+    expected += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    assert_eq!(noisy_circuit, expected);
+}
+
+#[test]
+fn test_noise_readout() {
+    let noise_model = ImperfectReadoutModel::new()
+        .set_error_probabilites(0, 0.1, 0.2)
+        .unwrap()
+        .set_error_probabilites(1, 0.0, 0.2)
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let operations: Vec<&Operation> = noisy_circuit.iter().collect();
+    assert_eq!(operations.len(), 5);
+    let flip = noise_superoperator(&vec![operations[1].clone()].into_iter().collect());
+    assert!((flip[(3, 0)] - 0.1).abs() < 1e-12);
+    assert!((flip[(0, 3)] - 0.2).abs() < 1e-12);
+    assert_eq!(
+        operations[2],
+        &Operation::from(MeasureQubit::new(0, "ro".to_string(), 0))
+    );
+    assert_eq!(
+        operations[3],
+        &Operation::from(PragmaDamping::new(1, 1.0.into(), (-(0.8_f64).ln()).into()))
+    );
+}
+
+#[test]
+fn test_noise_readout_repeated_measurement() {
+    let mut noise_model = ImperfectReadoutModel::new();
+    for qubit in 0..3 {
+        noise_model = noise_model.set_error_probabilites(qubit, 0.0, 0.2).unwrap();
+    }
+    let flip = |qubit: usize| -> Operation {
+        PragmaDamping::new(qubit, 1.0.into(), (-(0.8_f64).ln()).into()).into()
+    };
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    for qubit in 0..3 {
+        circuit += RotateX::new(qubit, 0.1.into());
+    }
+    let measurement =
+        PragmaRepeatedMeasurement::new("ro".to_string(), 10, Some(HashMap::from([(2, 0), (0, 1)])));
+    let noisy_circuit = apply_noise_models(
+        &(circuit.clone() + measurement.clone()),
+        &timed_device(),
+        &[noise_model.clone().into()],
+    )
+    .unwrap();
+    let operations: Vec<Operation> = noisy_circuit.iter().skip(4).cloned().collect();
+    assert_eq!(operations, vec![flip(0), flip(2), measurement.into()]);
+
+    // Without a qubit mapping the qubits are read out into the bits of the same index
+    let measurement = PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    let noisy_circuit = apply_noise_models(
+        &(circuit.clone() + measurement.clone()),
+        &timed_device(),
+        &[noise_model.clone().into()],
+    )
+    .unwrap();
+    let operations: Vec<Operation> = noisy_circuit.iter().skip(4).cloned().collect();
+    assert_eq!(operations, vec![flip(0), flip(1), measurement.into()]);
+
+    let measurement = PragmaRepeatedMeasurement::new("undefined".to_string(), 10, None);
+    let circuit = circuit + measurement;
+    assert!(apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).is_err());
+    assert!(apply_noise_models(&circuit, &timed_device(), &[]).is_ok());
+}
+
+#[test]
+fn test_noise_overrotation() {
+    let noise_model = SingleQubitOverrotationOnGate::new()
+        .set_single_qubit_overrotation(
+            "RotateX",
+            0,
+            SingleQubitOverrotationDescription::new("RotateZ", 0.1, 0.2),
+        )
+        .set_two_qubit_overrotation(
+            "CNOT",
+            0,
+            1,
+            (
+                SingleQubitOverrotationDescription::new("RotateX", 0.0, 0.0),
+                SingleQubitOverrotationDescription::new("RotateX", 0.3, 0.0),
+            ),
+        );
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.5.into());
+    circuit += CNOT::new(0, 1);
+    let noisy_circuit =
+        apply_noise_models(&circuit, &timed_device(), &[noise_model.into()]).unwrap();
+
+    let mut expected = Circuit::new();
+    expected += RotateX::new(0, 0.5.into());
+    expected += PragmaOverrotation::new("RotateZ".to_string(), vec![0], 1.0, 0.2);
+    expected += RotateZ::new(0, 0.1.into());
+    expected += CNOT::new(0, 1);
+    expected += RotateX::new(1, 0.3.into());
+    assert_eq!(noisy_circuit, expected);
+}
+
+#[test]
+fn test_noise_errors() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += CNOT::new(0, 1);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    let mut multi_qubit_noise = PlusMinusLindbladNoiseOperator::new();
+    multi_qubit_noise
+        .add_operator_product(
+            (
+                PlusMinusProduct::new().z(0).z(1),
+                PlusMinusProduct::new().z(0).z(1),
+            ),
+            0.1.into(),
+        )
+        .unwrap();
+    let mut complex_noise = PlusMinusLindbladNoiseOperator::new();
+    complex_noise
+        .add_operator_product(
+            (
+                PlusMinusProduct::new().z(0),
+                PlusMinusProduct::new().plus(0),
+            ),
+            qoqo_calculator::CalculatorComplex::new(0.0, 0.1),
+        )
+        .unwrap();
+    let noise_models: Vec<NoiseModel> = vec![
+        ContinuousDecoherenceModel::from(multi_qubit_noise.clone()).into(),
+        DecoherenceOnIdleModel::from(complex_noise).into(),
+        DecoherenceOnGateModel::new()
+            .set_two_qubit_gate_error("CNOT", 0, 1, multi_qubit_noise)
+            .into(),
+        SingleQubitOverrotationOnGate::new()
+            .set_two_qubit_overrotation(
+                "CNOT",
+                0,
+                1,
+                (
+                    SingleQubitOverrotationDescription::new("CNOT", 0.1, 0.0),
+                    SingleQubitOverrotationDescription::new("RotateX", 0.1, 0.0),
+                ),
+            )
+            .into(),
+        ImperfectReadoutModel::new()
+            .set_error_probabilites(0, 0.5, 0.5)
+            .unwrap()
+            .into(),
+    ];
+    for noise_model in noise_models {
+        assert!(apply_noise_models(&circuit, &timed_device(), &[noise_model]).is_err());
+    }
+
+    let mut circuit = Circuit::new();
+    circuit += RotateY::new(0, 0.1.into());
+    assert!(apply_noise_models(&circuit, &timed_device(), &[]).is_err());
+}
+
+/// Returns an all-to-all device with decoupling pulses of the given gate time.
+fn decoupling_device(pulse_time: f64) -> AllToAllDevice {
+    AllToAllDevice::new(
+        3,
+        &[
+            "RotateZ".to_string(),
+            "RotateX".to_string(),
+            "RotateY".to_string(),
+            "PauliX".to_string(),
+            "PauliY".to_string(),
+        ],
+        &["CNOT".to_string()],
+        1.0,
+    )
+    .set_all_single_qubit_gate_times("RotateX", pulse_time)
+    .set_all_single_qubit_gate_times("RotateY", pulse_time)
+    .set_all_single_qubit_gate_times("PauliX", pulse_time)
+    .set_all_single_qubit_gate_times("PauliY", pulse_time)
+}
+
+/// Returns a circuit with an idle window of length 2 on qubit 1 and of length 3 on qubit 2.
+fn decoupling_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += RotateZ::new(0, 0.1.into());
+    circuit += RotateZ::new(0, 0.2.into());
+    circuit += RotateZ::new(0, 0.3.into());
+    circuit += RotateZ::new(1, 0.4.into());
+    circuit += RotateZ::new(2, 0.5.into());
+    circuit += CNOT::new(0, 1);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+    circuit
+}
+
+#[test_case(DecouplingSequence::XX, DecouplingPulse::Pauli, SchedulingPolicy::AsSoonAsPossible, &["PragmaSleep", "PauliX", "PragmaSleep", "PauliX", "PragmaSleep"]; "xx asap")]
+#[test_case(DecouplingSequence::CPMG, DecouplingPulse::Rotation, SchedulingPolicy::AsSoonAsPossible, &["PragmaSleep", "RotateX", "PragmaSleep", "RotateX", "PragmaSleep"]; "cpmg asap")]
+#[test_case(DecouplingSequence::XY4, DecouplingPulse::Pauli, SchedulingPolicy::AsSoonAsPossible, &["PragmaSleep", "PauliX", "PragmaSleep", "PauliY", "PragmaSleep", "PauliX", "PragmaSleep", "PauliY", "PragmaSleep"]; "xy4 asap")]
+#[test_case(DecouplingSequence::XY4, DecouplingPulse::Rotation, SchedulingPolicy::AsLateAsPossible, &["PragmaSleep", "RotateX", "PragmaSleep", "RotateY", "PragmaSleep", "RotateX", "PragmaSleep", "RotateY", "PragmaSleep"]; "xy4 alap")]
+fn test_dynamical_decoupling_sequences(
+    sequence: DecouplingSequence,
+    pulse: DecouplingPulse,
+    policy: SchedulingPolicy,
+    expected: &[&str],
+) {
+    let device = decoupling_device(0.25);
+    let circuit = decoupling_circuit();
+    let decoupled_circuit =
+        insert_dynamical_decoupling(&circuit, &device, sequence, pulse, 1.0, policy).unwrap();
+
+    for qubit in [1, 2] {
+        let names: Vec<&str> = decoupled_circuit
+            .iter()
+            .filter(|operation| operation.hqslang() != "RotateZ")
+            .filter(|operation| operation.involved_qubits() == InvolvedQubits::Set([qubit].into()))
+            .map(|operation| operation.hqslang())
+            .collect();
+        assert_eq!(names, expected);
+    }
+
+    let schedule = schedule_circuit(&circuit, &device, policy).unwrap();
+    let decoupled_schedule = schedule_circuit(&decoupled_circuit, &device, policy).unwrap();
+    assert!((decoupled_schedule.duration - schedule.duration).abs() < 1e-12);
+    let decoupled_times: Vec<(f64, f64)> = decoupled_schedule
+        .operations
+        .iter()
+        .filter(|scheduled| {
+            circuit
+                .iter()
+                .any(|operation| operation == &scheduled.operation)
+        })
+        .map(|scheduled| (scheduled.start_time, scheduled.end_time))
+        .collect();
+    assert_eq!(decoupled_times.len(), schedule.operations.len());
+    for ((start, end), scheduled) in decoupled_times.iter().zip(schedule.operations.iter()) {
+        assert!((start - scheduled.start_time).abs() < 1e-12);
+        assert!((end - scheduled.end_time).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_dynamical_decoupling_windows() {
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, 0.1.into());
+    circuit += RotateZ::new(0, 0.2.into());
+    circuit += RotateZ::new(1, 0.3.into());
+    circuit += CNOT::new(0, 1);
+    let decoupled_circuit = insert_dynamical_decoupling(
+        &circuit,
+        &decoupling_device(0.25),
+        DecouplingSequence::XX,
+        DecouplingPulse::Pauli,
+        0.5,
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(0, 0.1.into());
+    expected += RotateZ::new(0, 0.2.into());
+    expected += RotateZ::new(1, 0.3.into());
+    expected += PragmaSleep::new(vec![1], (0.5 / 3.0).into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], (0.5 / 3.0).into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], (0.5 / 3.0).into());
+    expected += CNOT::new(0, 1);
+    assert_eq!(decoupled_circuit, expected);
+
+    // Windows shorter than the minimum window or the pulses are left empty
+    for (pulse_time, minimum_window) in [(0.25, 1.5), (0.75, 0.5)] {
+        let decoupled_circuit = insert_dynamical_decoupling(
+            &circuit,
+            &decoupling_device(pulse_time),
+            DecouplingSequence::XX,
+            DecouplingPulse::Pauli,
+            minimum_window,
+            SchedulingPolicy::AsSoonAsPossible,
+        )
+        .unwrap();
+        assert_eq!(decoupled_circuit, circuit);
+    }
+
+    // Windows as long as the pulses are filled without sleeps
+    let decoupled_circuit = insert_dynamical_decoupling(
+        &circuit,
+        &decoupling_device(0.5),
+        DecouplingSequence::CPMG,
+        DecouplingPulse::Pauli,
+        0.5,
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(0, 0.1.into());
+    expected += RotateZ::new(0, 0.2.into());
+    expected += RotateZ::new(1, 0.3.into());
+    expected += PauliX::new(1);
+    expected += PauliX::new(1);
+    expected += CNOT::new(0, 1);
+    assert_eq!(decoupled_circuit, expected);
+}
+
+#[test]
+fn test_dynamical_decoupling_sleeps() {
+    let device = decoupling_device(0.25);
+    // A sleep on the idle qubit is replaced by the sequence filling the whole window
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, 0.1.into());
+    circuit += PragmaSleep::new(vec![1], 0.5.into());
+    circuit += RotateZ::new(0, 0.2.into());
+    circuit += CNOT::new(0, 1);
+    let decoupled_circuit = insert_dynamical_decoupling(
+        &circuit,
+        &device,
+        DecouplingSequence::XX,
+        DecouplingPulse::Pauli,
+        1.5,
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(0, 0.1.into());
+    expected += RotateZ::new(0, 0.2.into());
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += CNOT::new(0, 1);
+    assert_eq!(decoupled_circuit, expected);
+    let schedule = schedule_circuit(
+        &decoupled_circuit,
+        &device,
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    assert_eq!(schedule.duration, 3.0);
+
+    // Sleeps on several qubits synchronise the qubits and are kept, only the gaps are filled
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, 0.1.into());
+    circuit += RotateZ::new(0, 0.2.into());
+    circuit += PragmaSleep::new(vec![0, 1], 2.0.into());
+    circuit += CNOT::new(0, 1);
+    let decoupled_circuit = insert_dynamical_decoupling(
+        &circuit,
+        &device,
+        DecouplingSequence::XX,
+        DecouplingPulse::Pauli,
+        1.5,
+        SchedulingPolicy::AsSoonAsPossible,
+    )
+    .unwrap();
+    let mut expected = Circuit::new();
+    expected += RotateZ::new(0, 0.1.into());
+    expected += RotateZ::new(0, 0.2.into());
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += PauliX::new(1);
+    expected += PragmaSleep::new(vec![1], 0.5.into());
+    expected += PragmaSleep::new(vec![0, 1], 2.0.into());
+    expected += CNOT::new(0, 1);
+    assert_eq!(decoupled_circuit, expected);
+}
+
+#[test]
+fn test_dynamical_decoupling_errors() {
+    let device = AllToAllDevice::new(
+        2,
+        &["RotateX".to_string(), "PauliX".to_string()],
+        &["CNOT".to_string()],
+        1.0,
+    );
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.1.into());
+    circuit += RotateX::new(0, 0.2.into());
+    circuit += CNOT::new(0, 1);
+    assert!(insert_dynamical_decoupling(
+        &circuit,
+        &device,
+        DecouplingSequence::XX,
+        DecouplingPulse::Pauli,
+        0.0,
+        SchedulingPolicy::AsSoonAsPossible
+    )
+    .is_ok());
+    assert!(insert_dynamical_decoupling(
+        &circuit,
+        &device,
+        DecouplingSequence::XY4,
+        DecouplingPulse::Pauli,
+        0.0,
+        SchedulingPolicy::AsSoonAsPossible
+    )
+    .is_err());
+
+    circuit += RotateY::new(0, 0.3.into());
+    assert!(insert_dynamical_decoupling(
+        &circuit,
+        &device,
+        DecouplingSequence::XX,
+        DecouplingPulse::Pauli,
+        0.0,
+        SchedulingPolicy::AsSoonAsPossible
+    )
+    .is_err());
+}